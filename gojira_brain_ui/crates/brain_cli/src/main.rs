@@ -1,15 +1,40 @@
-use brain_core::cleaner::{apply_replace_active_cleaner, sanitize_params};
-use brain_core::gemini::{generate_tone_auto, ToneRequest};
-use brain_core::protocol::{AppliedParam, ClientCommand, MergeMode, ServerMessage};
-use brain_core::modules::value_resolver::{resolve_ai_params, AiToneResponse};
-use brain_core::{param_map, protocol::ParamChange};
+use brain_core::cleaner::{apply_replace_active_cleaner_locked, sanitize_params};
+use brain_core::gemini::{
+    generate_tone_with, research_brief_with, truncate_chars, two_stage_enabled, ToneProgress,
+    ToneRequest,
+};
+use brain_core::param_meta::ParamMeta;
+use brain_core::tone_model::{ToneModel, ToneModelConfig, ToneProvider};
+use brain_core::protocol::{
+    AppliedParam, ClientCommand, Confidence, Encoding, Frame, GojiraInstance, HandshakeMeta,
+    LogEvent, MergeMode, ModuleId, ParamChange, ParamCheck, ParamCheckStatus, ParamSchemaEntry, PreviewRow, RuntimeMetrics,
+    ServerMessage, TimingStats, PAIRING_KEY_HEADER, PIPE_URL_PREFIX,
+};
+use brain_core::modules::value_resolver::{
+    rank_alternatives, resolve_ai_params, AiToneResponse, ParamAlternatives,
+};
+use brain_core::research_cache::{self, ResearchCache};
 use clap::Parser;
+use interprocess::local_socket::prelude::*;
+use interprocess::local_socket::{GenericNamespaced, RecvHalf, SendHalf};
 use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Read, Write};
 use std::net::TcpStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::handshake::client::Request;
+use tungstenite::handshake::client::Response;
+use tungstenite::handshake::HandshakeRole;
 use tungstenite::stream::MaybeTlsStream;
-use tungstenite::{connect, Message, WebSocket};
+#[cfg(feature = "tls")]
+use tungstenite::Connector;
+use tungstenite::{HandshakeError, Message, WebSocket};
+
+/// A few of the DLL's websocket ping intervals: no frame at all for this long and it is gone.
+const SERVER_SILENCE_TIMEOUT: Duration = Duration::from_secs(20);
 
 #[derive(Parser, Debug)]
 #[command(name = "brain_cli")]
@@ -26,17 +51,86 @@ struct Args {
     #[arg(long, value_name = "PATH")]
     ai_response_file: Option<PathBuf>,
 
+    /// Stage-1 research brief file. If it exists it is reused as-is (edit it freely); otherwise
+    /// the freshly generated brief is saved there for later runs.
+    #[arg(long, value_name = "PATH")]
+    research_file: Option<PathBuf>,
+
+    /// JSON cache of stage-1 research briefs keyed by band/era/style, so a follow-up prompt in
+    /// the same style (e.g. "... but for 8-string") reuses the brief. Ignored with --research-file.
+    #[arg(long, value_name = "PATH")]
+    research_cache: Option<PathBuf>,
+
+    /// Drop the cached research for this prompt's style and generate it again.
+    #[arg(long, default_value_t = false)]
+    refresh_research: bool,
+
     #[arg(long)]
     target_guid: Option<String>,
 
+    /// Pick the target by track or FX name (case-insensitive substring). With several matches,
+    /// or several instances and no target at all, you are asked to choose.
+    #[arg(long, value_name = "PATTERN", conflicts_with = "target_guid")]
+    target: Option<String>,
+
+    /// Param indices to leave untouched (comma-separated), e.g. a hand-dialed gate: --lock 2
+    #[arg(long, value_delimiter = ',')]
+    lock: Vec<i32>,
+
+    /// Glide continuous params to the new tone over this many milliseconds instead of jumping
+    /// (avoids zipper noise while audio plays; max 5000).
+    #[arg(long, value_name = "MS")]
+    ramp_ms: Option<u32>,
+
+    /// All or nothing: if any param can't be written, put the others back as they were.
+    #[arg(long, conflicts_with = "ramp_ms")]
+    atomic: bool,
+
+    /// Write only these parts of the tone (comma-separated), e.g. --module cab or --module amp,eq.
+    /// One of global, wow, oct, overdrive, distortion, phaser, chorus, amp, eq, cab, delay, reverb.
+    #[arg(long, value_delimiter = ',', value_parser = parse_module_id, conflicts_with = "dry_run")]
+    module: Vec<ModuleId>,
+
+    /// Write to the target even when it is in a project tab other than the current one.
+    #[arg(long, default_value_t = false)]
+    allow_background_projects: bool,
+
     #[arg(long, default_value = "auto")]
     backend: String,
 
-    #[arg(long, default_value = "gemini-2.5-pro")]
-    gemini_model: String,
+    /// Tone model provider: gemini, anthropic, openai or another hosted OpenAI-style service
+    /// (openrouter, groq, together, mistral, deepseek, ...), a local server (ollama, llamacpp,
+    /// vllm), or custom with --base-url. The key comes from --api-key-file, else the provider's
+    /// variable (GEMINI_API_KEY, ANTHROPIC_API_KEY, OPENAI_API_KEY, ..., TONE_API_KEY for local
+    /// servers).
+    #[arg(long, default_value = "gemini")]
+    provider: String,
+
+    /// OpenAI-compatible endpoint (up to `/v1`) for --provider custom, or to move another
+    /// OpenAI-style provider somewhere else.
+    #[arg(long, value_name = "URL")]
+    base_url: Option<String>,
+
+    /// Defaults to gemini-2.5-pro with --provider gemini; required for the other providers.
+    #[arg(long, visible_alias = "model")]
+    gemini_model: Option<String>,
+
+    /// Print the research brief and the model's reasoning as they are generated.
+    #[arg(long, default_value_t = false, conflicts_with = "ai_response_file")]
+    stream: bool,
+
+    /// DLL websocket URL. Defaults to GOJIRA_WS_ADDR, else the sidecar config file the DLL reads
+    /// (%APPDATA%/Gojira/sidecar.json) with the port the DLL wrote to sidecar-port.json next to
+    /// it, else ws://127.0.0.1:9001. `pipe:<name>` uses the local
+    /// pipe transport. The pairing key comes from GOJIRA_PAIRING_KEY, else the pairing.key file
+    /// next to that config.
+    #[arg(long)]
+    ws_url: Option<String>,
 
-    #[arg(long, default_value = "ws://127.0.0.1:9001")]
-    ws_url: String,
+    /// List the DLLs advertising themselves on the LAN over mDNS (with their --ws-url) and exit.
+    /// Needs the `mdns` feature, and `"mdns": true` in the sidecar config on the REAPER machine.
+    #[arg(long, default_value_t = false, conflicts_with = "ws_url")]
+    discover: bool,
 
     #[arg(long)]
     api_key_file: Option<String>,
@@ -50,21 +144,71 @@ struct Args {
     #[arg(long, default_value_t = false)]
     preview_only: bool,
 
+    /// Have the DLL resolve the tone against the live FX and print each param's current and new
+    /// value, formatted by the plugin, without writing anything.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["preview_only", "no_ws"])]
+    dry_run: bool,
+
     /// Skip REAPER websocket connection and only run AI + local QC (implies preview-only).
     #[arg(long, default_value_t = false)]
     no_ws: bool,
+
+    /// Print the DLL's runtime counters (commands, apply latency, tick rate, probes, queues) and exit.
+    #[arg(long, default_value_t = false, conflicts_with = "no_ws")]
+    metrics: bool,
+
+    /// Print the DLL's recent log events with its tick, scan and apply timings, then exit.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["no_ws", "metrics"])]
+    diagnostics: bool,
+
+    /// Check that REAPER's main thread is answering (round trip and DLL uptime), then exit.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["no_ws", "metrics"])]
+    ping: bool,
+
+    /// Make the DLL forget its cached param meta for the plugin and probe it again, then exit.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["no_ws", "metrics", "ping"])]
+    refresh_meta: bool,
+
+    /// With --refresh-meta, probe only these param indices (comma-separated), e.g. the IR
+    /// selectors after installing new IR files: --refresh-meta --meta-index 84,92
+    #[arg(long, value_delimiter = ',', requires = "refresh_meta")]
+    meta_index: Vec<i32>,
+
+    /// Revert the last tone applied to REAPER (one REAPER undo step) and exit.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["no_ws", "metrics"])]
+    undo: bool,
+
+    /// Put the target back the way it was before an earlier apply (the id printed after it) and
+    /// exit. Unlike --undo this ignores REAPER's undo history.
+    #[arg(long, value_name = "SNAPSHOT_ID", conflicts_with_all = ["no_ws", "metrics", "undo"])]
+    restore: Option<String>,
+
+    /// Save the target's current values as a named preset in the sidecar's presets folder and exit.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["no_ws", "metrics", "undo"])]
+    save_preset: Option<String>,
+
+    /// Load a saved preset (or the plugin's own preset of that name) onto the target and exit.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["no_ws", "metrics", "undo", "save_preset"])]
+    load_preset: Option<String>,
+
+    /// List the saved presets and the target's current plugin preset, then exit.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["no_ws", "metrics", "undo"])]
+    list_presets: bool,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     let args = Args::parse();
+    if args.discover {
+        return discover();
+    }
     let prompt_file_for_log = args
         .prompt_file
         .as_ref()
         .map(|p| p.display().to_string());
 
-    let mut prompt = if let Some(p) = args.prompt.clone() {
+    let prompt = if let Some(p) = args.prompt.clone() {
         p
     } else if let Some(path) = args.prompt_file.clone() {
         std::fs::read_to_string(&path)
@@ -72,7 +216,14 @@ async fn main() -> anyhow::Result<()> {
     } else {
         String::new()
     };
-    if args.ai_response_file.is_none() && prompt.trim().is_empty() {
+    let preset_only = args.save_preset.is_some() || args.load_preset.is_some() || args.list_presets;
+    let no_tone = args.metrics
+        || args.diagnostics
+        || args.ping
+        || args.undo
+        || args.restore.is_some()
+        || preset_only;
+    if !no_tone && args.ai_response_file.is_none() && prompt.trim().is_empty() {
         return Err(anyhow::anyhow!(
             "missing input: provide --prompt/--prompt-file, or use --ai-response-file"
         ));
@@ -90,50 +241,134 @@ async fn main() -> anyhow::Result<()> {
         std::env::set_var("VERTEX_LOCATION", loc.trim());
     }
 
+    let provider = ToneProvider::from_id(&args.provider, args.base_url.as_deref())
+        .map_err(|e| anyhow::anyhow!(e))?;
     let api_key = if let Some(path) = args.api_key_file.as_deref() {
         Some(std::fs::read_to_string(path)?.trim().to_string())
     } else {
-        std::env::var("GEMINI_API_KEY").ok()
+        std::env::var(provider.api_key_env()).ok()
     };
 
-    let (mut ws, session_token, target) = if args.no_ws {
-        (None, String::new(), None)
+    let (mut ws, session_token, target, meta) = if args.no_ws {
+        (None, String::new(), None, ParamMeta::default())
     } else {
-        let (mut ws, _resp) = connect(args.ws_url.as_str())?;
-        let (session_token, instances, validation_report, param_enums, param_formats, param_format_samples) =
-            wait_handshake(&mut ws)?;
+        let ws_url = match args.ws_url.clone() {
+            Some(url) => url,
+            None => brain_core::sidecar::ws_url().map_err(|e| anyhow::anyhow!(e))?,
+        };
+        let mut ws = open_ws(&ws_url)?;
+        // The DLL pings every few seconds, so this much silence means it is gone.
+        match ws.ws.get_mut() {
+            MaybeTlsStream::Plain(stream) => {
+                stream.set_read_timeout(Some(SERVER_SILENCE_TIMEOUT))?
+            }
+            #[cfg(feature = "tls")]
+            MaybeTlsStream::Rustls(stream) => {
+                stream.sock.set_read_timeout(Some(SERVER_SILENCE_TIMEOUT))?
+            }
+            _ => {}
+        }
+        let (session_token, instances, validation_report, meta) = wait_handshake(&mut ws)?;
 
         println!("handshake ok: {} instance(s)", instances.len());
         if !validation_report.is_empty() {
-            println!("validator:");
-            for (k, v) in validation_report.iter() {
-                println!("  {k}: {v}");
-            }
+            print_validation_report(&validation_report);
         }
 
-        // Match the UI behavior: append compact plugin meta so the model can pick enum labels,
-        // and so our resolver can convert human units using formatted triplets/samples.
-        prompt = append_plugin_param_meta_to_prompt(
-            &prompt,
-            &param_enums,
-            &param_formats,
-            &param_format_samples,
-        );
+        if args.metrics {
+            let cmd = ClientCommand::GetMetrics { session_token };
+            ws.send(&cmd)?;
+            print_metrics(&wait_metrics(&mut ws)?);
+            return Ok(());
+        }
+
+        if args.diagnostics {
+            let cmd = ClientCommand::GetDiagnostics {
+                session_token,
+                limit: None,
+            };
+            ws.send(&cmd)?;
+            let (events, timings) = wait_diagnostics(&mut ws)?;
+            print_diagnostics(&events, &timings);
+            return Ok(());
+        }
+
+        if args.ping {
+            let sent = Instant::now();
+            let cmd = ClientCommand::Ping {
+                session_token,
+                nonce: chrono_nanos() as u64,
+            };
+            ws.send(&cmd)?;
+            let uptime_ms = wait_pong(&mut ws)?;
+            println!(
+                "pong: {:.1}ms round trip, DLL up {:.0}s",
+                sent.elapsed().as_secs_f64() * 1000.0,
+                uptime_ms as f64 / 1000.0
+            );
+            return Ok(());
+        }
+
+        if args.refresh_meta {
+            let cmd = ClientCommand::RefreshParamMeta {
+                session_token,
+                indices: args.meta_index.clone(),
+            };
+            ws.send(&cmd)?;
+            let meta = if args.meta_index.is_empty() {
+                wait_handshake(&mut ws)?.3
+            } else {
+                let mut meta = ParamMeta::default();
+                wait_param_meta(&mut ws, &mut meta)?;
+                meta
+            };
+            println!(
+                "param meta re-probed: {} enum(s), {} format(s), {} kind(s)",
+                meta.enums.len(),
+                meta.formats.len(),
+                meta.kinds.len()
+            );
+            return Ok(());
+        }
+
+        if args.undo {
+            let cmd = ClientCommand::UndoLastTone {
+                session_token,
+                command_id: format!("cli-undo-{}", chrono_nanos()),
+            };
+            ws.send(&cmd)?;
+            wait_ack(&mut ws)?;
+            println!("reverted the last applied tone");
+            return Ok(());
+        }
+
+        if let Some(snapshot_id) = args.restore.clone() {
+            let cmd = ClientCommand::RestoreSnapshot {
+                session_token,
+                command_id: format!("cli-restore-{}", chrono_nanos()),
+                snapshot_id: snapshot_id.clone(),
+            };
+            ws.send(&cmd)?;
+            let written = wait_ack(&mut ws)?.len();
+            println!("restored snapshot {snapshot_id} ({written} param(s) written)");
+            return Ok(());
+        }
 
         let target = if let Some(g) = args.target_guid.clone() {
             g
         } else {
-            instances
-                .iter()
-                .find(|i| matches!(i.confidence, brain_core::protocol::Confidence::High))
-                .or_else(|| instances.first())
-                .ok_or_else(|| anyhow::anyhow!("no instances found (is the Gojira FX loaded?)"))?
-                .fx_guid
-                .clone()
+            pick_target(&instances, args.target.as_deref())?
         };
 
-        (Some(ws), session_token, Some(target))
+        if preset_only {
+            run_preset_command(&mut ws, &session_token, &target, &args)?;
+            return Ok(());
+        }
+
+        (Some(ws), session_token, Some(target), meta)
     };
+    // Labels and groups for the printouts below; empty without a DLL.
+    let schema = meta.schema.clone();
 
     let tone = if let Some(path) = args.ai_response_file.as_ref() {
         let raw = std::fs::read_to_string(path).map_err(|e| {
@@ -141,31 +376,95 @@ async fn main() -> anyhow::Result<()> {
         })?;
         let ai: AiToneResponse =
             serde_json::from_str(&raw).map_err(|e| anyhow::anyhow!("AI JSON parse failed: {e}"))?;
-        let params = resolve_ai_params(&prompt, ai.params)
+        let params = resolve_ai_params(&meta, ai.params)
             .map_err(|e| anyhow::anyhow!("resolve_ai_params failed: {e}"))?;
+        let alternatives = rank_alternatives(&meta, &params, &ai.alternatives);
         brain_core::gemini::ToneResponse {
             reasoning: ai.reasoning,
             params,
+            alternatives,
         }
     } else {
-        generate_tone_auto(
-            &args.gemini_model,
-            ToneRequest {
-                user_prompt: prompt,
+        let model = match (args.gemini_model.clone(), &provider) {
+            (Some(model), _) => model,
+            (None, ToneProvider::Gemini) => "gemini-2.5-pro".to_string(),
+            (None, _) => anyhow::bail!("--model is required for --provider {}", args.provider),
+        };
+        let tone_model = ToneModelConfig {
+            provider: provider.clone(),
+            model,
+            api_key: api_key.clone(),
+        }
+        .build();
+
+        let research_brief = match args.research_file.as_ref() {
+            Some(path) if path.exists() => {
+                let brief = std::fs::read_to_string(path).map_err(|e| {
+                    anyhow::anyhow!("failed to read --research-file {}: {e}", path.display())
+                })?;
+                println!("research_file={} (reused)", path.display());
+                Some(brief)
+            }
+            Some(path) => {
+                let brief = research_brief_with(tone_model.as_ref(), &prompt).await?;
+                std::fs::write(path, &brief).map_err(|e| {
+                    anyhow::anyhow!("failed to write --research-file {}: {e}", path.display())
+                })?;
+                println!("research_file={} (saved)", path.display());
+                Some(brief)
+            }
+            None => match args.research_cache.as_ref() {
+                Some(path) if two_stage_enabled() => {
+                    cached_research_brief(path, args.refresh_research, tone_model.as_ref(), &prompt)
+                        .await?
+                }
+                _ => None,
             },
-            api_key.as_deref(),
-        )
-        .await?
+        };
+
+        let req = ToneRequest {
+            user_prompt: prompt.clone(),
+            meta,
+            research_brief,
+        };
+        if args.stream {
+            let mut last_kind = "";
+            let mut print_progress = |event: ToneProgress| {
+                let (kind, text) = match &event {
+                    ToneProgress::Research { text } => ("research", text),
+                    ToneProgress::Reasoning { text } => ("reasoning", text),
+                    ToneProgress::Params { .. } => return,
+                };
+                if kind != last_kind {
+                    print!("\n{kind} (streaming):\n");
+                    last_kind = kind;
+                }
+                print!("{text}");
+                let _ = std::io::stdout().flush();
+            };
+            let tone =
+                generate_tone_with(tone_model.as_ref(), req, Some(&mut print_progress)).await?;
+            println!();
+            tone
+        } else {
+            generate_tone_with(tone_model.as_ref(), req, None).await?
+        }
     };
 
     println!("\nreasoning:\n{}\n", tone.reasoning);
+    print_alternatives(&tone.alternatives, &schema);
 
     let raw_params = tone.params.clone();
     let raw_sanitized = sanitize_params(raw_params.clone()).map_err(|e| anyhow::anyhow!(e))?;
-    let cleaned = apply_replace_active_cleaner(MergeMode::ReplaceActive, raw_sanitized.clone());
+    let locked: HashSet<i32> = args.lock.iter().copied().collect();
+    let cleaned = apply_replace_active_cleaner_locked(
+        MergeMode::ReplaceActive,
+        raw_sanitized.clone(),
+        &locked,
+    );
 
     println!("qc:");
-    print_qc(&raw_params, &raw_sanitized, &cleaned);
+    print_qc(&raw_params, &raw_sanitized, &cleaned, &schema);
 
     if args.preview_only || args.no_ws {
         println!("preview_only=true (not applying to REAPER)");
@@ -177,179 +476,763 @@ async fn main() -> anyhow::Result<()> {
     };
     let target = target.ok_or_else(|| anyhow::anyhow!("internal error: target missing"))?;
 
+    let command_id = format!("cli-{}", chrono_nanos());
+    if args.dry_run {
+        let cmd = ClientCommand::PreviewTone {
+            session_token,
+            command_id,
+            target_fx_guid: target,
+            mode: MergeMode::ReplaceActive,
+            params: cleaned,
+            locked_params: args.lock.clone(),
+        };
+        ws.send(&cmd)?;
+        print_preview(&wait_preview(ws)?);
+        println!("dry_run=true (nothing written)");
+        return Ok(());
+    }
     let cmd = ClientCommand::SetTone {
         session_token,
-        command_id: format!("cli-{}", chrono_nanos()),
-        target_fx_guid: target,
+        command_id: command_id.clone(),
+        target_fx_guid: target.clone(),
         mode: MergeMode::ReplaceActive,
         params: cleaned.clone(),
+        locked_params: args.lock.clone(),
+        ramp_ms: args.ramp_ms,
+        atomic: args.atomic,
+        apply_at: None,
+        modules: (!args.module.is_empty()).then(|| args.module.clone()),
+        allow_background_projects: args.allow_background_projects,
     };
 
-    ws.send(Message::Text(serde_json::to_string(&cmd)?))?;
+    ws.send(&cmd)?;
     let applied = wait_ack(ws)?;
-    print_applied_deltas(&cleaned, &applied);
+    let requested: Vec<ParamChange> = if args.module.is_empty() {
+        cleaned
+    } else {
+        // Params outside the chosen modules were never meant to be written.
+        cleaned
+            .into_iter()
+            .filter(|p| applied.iter().any(|a| a.index == p.index))
+            .collect()
+    };
+    print_applied_deltas(&requested, &applied);
+    println!("snapshot: {command_id} (revert with --restore {command_id})");
+
+    let summary = if prompt.trim().is_empty() {
+        args.ai_response_file
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default()
+    } else {
+        prompt.lines().next().unwrap_or_default().to_string()
+    };
+    record_last_applied(&target, &summary);
 
     Ok(())
 }
 
+/// Chooses the instance to apply to. A single candidate is used as-is; otherwise the list is
+/// shown (with the last tone the CLI applied to each) and, on a terminal, the user picks one.
+/// Without a terminal there is no one to ask, so several candidates are an error.
+fn pick_target(instances: &[GojiraInstance], pattern: Option<&str>) -> anyhow::Result<String> {
+    let candidates: Vec<&GojiraInstance> = match pattern {
+        Some(p) => {
+            let p = p.trim().to_ascii_lowercase();
+            instances
+                .iter()
+                .filter(|i| {
+                    i.track_name.to_ascii_lowercase().contains(&p)
+                        || i.fx_name.to_ascii_lowercase().contains(&p)
+                })
+                .collect()
+        }
+        None => instances.iter().collect(),
+    };
+    match (candidates.len(), pattern) {
+        (0, Some(p)) => return Err(anyhow::anyhow!("no instance matches --target {p:?}")),
+        (0, None) => {
+            return Err(anyhow::anyhow!(
+                "no instances found (is the Gojira FX loaded?)"
+            ))
+        }
+        (1, _) => return Ok(candidates[0].fx_guid.clone()),
+        _ => {}
+    }
+
+    let default = candidates
+        .iter()
+        .position(|i| matches!(i.confidence, Confidence::High))
+        .unwrap_or(0);
+    let history = load_last_applied();
+
+    println!("{} Gojira instances:", candidates.len());
+    for (n, inst) in candidates.iter().enumerate() {
+        let confidence = match inst.confidence {
+            Confidence::High => "high",
+            Confidence::Low => "low",
+        };
+        let profile = inst
+            .profile
+            .as_deref()
+            .map(|p| format!(", {p}"))
+            .unwrap_or_default();
+        let last = history
+            .get(&inst.fx_guid)
+            .map(|s| format!(" last: {}", truncate_chars(s, 48, "…")))
+            .unwrap_or_default();
+        let marker = if n == default { " *" } else { "" };
+        println!(
+            "  [{}] {} / {} (fx #{}, {confidence}{profile}){last}{marker}",
+            n + 1,
+            inst.track_name,
+            inst.fx_name,
+            inst.last_known_fx_index
+        );
+    }
+
+    // Guessing would apply a tone to whichever instance happened to be listed first.
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "{} instances match; pass --target-guid or --target to choose one",
+            candidates.len()
+        ));
+    }
+
+    loop {
+        print!("target [{}]: ", default + 1);
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line)? == 0 {
+            return Ok(candidates[default].fx_guid.clone());
+        }
+        let choice = line.trim();
+        if choice.is_empty() {
+            return Ok(candidates[default].fx_guid.clone());
+        }
+        match choice.parse::<usize>() {
+            Ok(n) if (1..=candidates.len()).contains(&n) => {
+                return Ok(candidates[n - 1].fx_guid.clone())
+            }
+            _ => println!("enter a number between 1 and {}", candidates.len()),
+        }
+    }
+}
+
+/// FX GUID -> first line of the last prompt applied from the CLI. `GOJIRA_CLI_HISTORY` overrides
+/// the default `~/.gojira_cli_history.json`.
+fn last_applied_path() -> Option<PathBuf> {
+    if let Ok(p) = std::env::var("GOJIRA_CLI_HISTORY") {
+        return Some(PathBuf::from(p));
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".gojira_cli_history.json"))
+}
+
+fn load_last_applied() -> HashMap<String, String> {
+    last_applied_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn record_last_applied(fx_guid: &str, summary: &str) {
+    let Some(path) = last_applied_path() else {
+        return;
+    };
+    let mut history = load_last_applied();
+    history.insert(fx_guid.to_string(), summary.trim().to_string());
+    // Best-effort: the picker just shows less context if this fails.
+    if let Ok(raw) = serde_json::to_string_pretty(&history) {
+        let _ = std::fs::write(path, raw);
+    }
+}
+
+async fn cached_research_brief(
+    path: &Path,
+    refresh: bool,
+    model: &dyn ToneModel,
+    prompt: &str,
+) -> anyhow::Result<Option<String>> {
+    let Some(key) = research_cache::style_key(prompt) else {
+        println!("research_cache=skip (no band/era in prompt)");
+        return Ok(None);
+    };
+
+    let ttl = research_cache::cache_ttl();
+    let mut cache = ResearchCache::load(path);
+    cache.prune(ttl);
+    if refresh {
+        cache.invalidate(&key);
+    }
+    if let Some(brief) = cache.get(&key, ttl) {
+        println!("research_cache=hit key={key}");
+        return Ok(Some(brief.to_string()));
+    }
+
+    let brief = research_brief_with(model, prompt).await?;
+    cache.insert(key.clone(), brief.clone());
+    cache.save().map_err(|e| {
+        anyhow::anyhow!("failed to write --research-cache {}: {e}", path.display())
+    })?;
+    println!("research_cache=miss key={key} (saved)");
+    Ok(Some(brief))
+}
+
+/// Upgrade request for `url`, carrying the pairing key when this machine has one.
+fn connect_request(url: &str) -> anyhow::Result<Request> {
+    let mut req = url.into_client_request()?;
+    if let Some(key) = brain_core::sidecar::pairing_key().map_err(|e| anyhow::anyhow!(e))? {
+        req.headers_mut().insert(PAIRING_KEY_HEADER, key.parse()?);
+    }
+    Ok(req)
+}
+
+fn open_ws(url: &str) -> anyhow::Result<DllSocket> {
+    let (stream, mut req) = match url.strip_prefix(PIPE_URL_PREFIX) {
+        Some(name) => {
+            let name = name.to_ns_name::<GenericNamespaced>()?;
+            let stream = DllStream::Pipe(PipeStream::new(LocalSocketStream::connect(name)?));
+            // The upgrade request still needs a URL; nothing reads the host over a pipe.
+            (stream, connect_request("ws://localhost/")?)
+        }
+        None => {
+            let req = connect_request(url)?;
+            let uri = req.uri();
+            let host = uri.host().unwrap_or_default();
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let default_port = match uri.scheme_str() {
+                Some("wss") => 443,
+                _ => 80,
+            };
+            let port = uri.port_u16().unwrap_or(default_port);
+            (DllStream::Tcp(TcpStream::connect((host, port))?), req)
+        }
+    };
+    let offered = brain_core::sidecar::encoding().map_err(|e| anyhow::anyhow!(e))?;
+    req.headers_mut()
+        .insert("Sec-WebSocket-Protocol", offered.subprotocol().parse()?);
+    let (ws, resp) = ws_handshake(url, req, stream)?;
+    // A DLL from before binary encoding existed doesn't answer the offer; it only speaks JSON.
+    let encoding = resp
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .and_then(|v| v.to_str().ok())
+        .and_then(Encoding::negotiate)
+        .unwrap_or_default();
+    Ok(DllSocket { ws, encoding })
+}
+
+/// The websocket to the DLL plus the encoding negotiated for it. Incoming frames are decoded by
+/// their type (text is JSON, binary is MessagePack), so either side may fall back to JSON.
+struct DllSocket {
+    ws: WebSocket<MaybeTlsStream<DllStream>>,
+    encoding: Encoding,
+}
+
+impl DllSocket {
+    fn send(&mut self, cmd: &ClientCommand) -> anyhow::Result<()> {
+        let msg = match self.encoding.encode(cmd).map_err(|e| anyhow::anyhow!(e))? {
+            Frame::Text(text) => Message::Text(text),
+            Frame::Binary(bytes) => Message::Binary(bytes),
+        };
+        self.ws.send(msg)?;
+        Ok(())
+    }
+
+    /// Next server message; `None` for control frames.
+    fn recv(&mut self) -> anyhow::Result<Option<ServerMessage>> {
+        let server = match self.ws.read()? {
+            Message::Text(text) => Encoding::Json.decode(text.as_bytes()),
+            Message::Binary(bytes) => Encoding::Msgpack.decode(&bytes),
+            _ => return Ok(None),
+        };
+        server.map(Some).map_err(|e| anyhow::anyhow!(e))
+    }
+}
+
+type WsHandshake = anyhow::Result<(WebSocket<MaybeTlsStream<DllStream>>, Response)>;
+
+fn handshake_error<R: HandshakeRole>(e: HandshakeError<R>) -> anyhow::Error {
+    match e {
+        HandshakeError::Failure(tungstenite::Error::Http(resp)) if resp.status() == 401 => {
+            anyhow::anyhow!(
+                "the DLL turned down the pairing key; set GOJIRA_PAIRING_KEY to the contents \
+                 of its pairing.key"
+            )
+        }
+        HandshakeError::Failure(e) => e.into(),
+        HandshakeError::Interrupted(_) => anyhow::anyhow!("websocket handshake interrupted"),
+    }
+}
+
+/// `wss://` trusts only the DLL's pinned self-signed certificate, never the system roots.
+#[cfg(feature = "tls")]
+fn ws_handshake(url: &str, req: Request, stream: DllStream) -> WsHandshake {
+    let connector = if url.starts_with("wss://") {
+        let config = brain_core::sidecar::load_config()
+            .and_then(|c| brain_core::sidecar::tls_client_config(&c))
+            .map_err(|e| anyhow::anyhow!(e))?;
+        Some(Connector::Rustls(config))
+    } else {
+        None
+    };
+    tungstenite::client_tls_with_config(req, stream, None, connector).map_err(handshake_error)
+}
+
+#[cfg(not(feature = "tls"))]
+fn ws_handshake(url: &str, req: Request, stream: DllStream) -> WsHandshake {
+    if url.starts_with("wss://") {
+        return Err(anyhow::anyhow!(
+            "wss:// needs brain_cli built with the `tls` feature"
+        ));
+    }
+    tungstenite::client(req, MaybeTlsStream::Plain(stream)).map_err(handshake_error)
+}
+
+/// How long `--discover` listens for answers: a few mDNS announce rounds.
+#[cfg(feature = "mdns")]
+const DISCOVER_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[cfg(feature = "mdns")]
+fn discover() -> anyhow::Result<()> {
+    let found =
+        brain_core::sidecar::discover_lan(DISCOVER_TIMEOUT).map_err(|e| anyhow::anyhow!(e))?;
+    if found.is_empty() {
+        println!("no DLL answered on the LAN");
+    }
+    for sidecar in found {
+        println!("{}\t{}", sidecar.name, sidecar.ws_url);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "mdns"))]
+fn discover() -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "--discover needs brain_cli built with the `mdns` feature"
+    ))
+}
+
+/// The DLL connection under the websocket: TCP, or the local pipe when the sidecar config selects
+/// that transport (`pipe:<name>` URLs).
+enum DllStream {
+    Tcp(TcpStream),
+    Pipe(PipeStream),
+}
+
+impl DllStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            DllStream::Tcp(s) => s.set_read_timeout(timeout),
+            DllStream::Pipe(s) => {
+                s.timeout = timeout;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A local pipe to the DLL. Pipes have no read timeout of their own, so the receive half is read
+/// on a thread and `read` waits for its chunks for at most `timeout`, like a TCP read would.
+struct PipeStream {
+    send: SendHalf,
+    chunks: mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    /// What is left of the last chunk `read` took.
+    pending: Vec<u8>,
+    timeout: Option<Duration>,
+}
+
+impl PipeStream {
+    fn new(stream: LocalSocketStream) -> Self {
+        let (recv, send) = stream.split();
+        let (tx, chunks) = mpsc::channel();
+        // Blocked in a read, the thread outlives the stream until the DLL closes its end or the
+        // CLI exits.
+        std::thread::spawn(move || pump_pipe(recv, tx));
+        Self {
+            send,
+            chunks,
+            pending: Vec::new(),
+            timeout: None,
+        }
+    }
+}
+
+/// Hands everything read from `recv` to `tx`; an empty chunk is the end of the pipe.
+fn pump_pipe(mut recv: RecvHalf, tx: mpsc::Sender<std::io::Result<Vec<u8>>>) {
+    let mut buf = vec![0u8; 16 * 1024];
+    loop {
+        let chunk = recv.read(&mut buf).map(|n| buf[..n].to_vec());
+        let last = !matches!(&chunk, Ok(c) if !c.is_empty());
+        if tx.send(chunk).is_err() || last {
+            return;
+        }
+    }
+}
+
+impl Read for PipeStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() {
+            let chunk = match self.timeout {
+                Some(timeout) => self.chunks.recv_timeout(timeout).map_err(|e| match e {
+                    mpsc::RecvTimeoutError::Timeout => std::io::ErrorKind::TimedOut,
+                    mpsc::RecvTimeoutError::Disconnected => std::io::ErrorKind::BrokenPipe,
+                }),
+                None => self
+                    .chunks
+                    .recv()
+                    .map_err(|_| std::io::ErrorKind::BrokenPipe),
+            };
+            self.pending = chunk??;
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.send.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send.flush()
+    }
+}
+
+impl Read for DllStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            DllStream::Tcp(s) => s.read(buf),
+            DllStream::Pipe(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for DllStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            DllStream::Tcp(s) => s.write(buf),
+            DllStream::Pipe(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            DllStream::Tcp(s) => s.flush(),
+            DllStream::Pipe(s) => s.flush(),
+        }
+    }
+}
+
 fn wait_handshake(
-    ws: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    ws: &mut DllSocket,
 ) -> anyhow::Result<(
     String,
     Vec<brain_core::protocol::GojiraInstance>,
-    std::collections::HashMap<String, String>,
-    HashMap<i32, Vec<brain_core::protocol::ParamEnumOption>>,
-    HashMap<i32, brain_core::protocol::ParamFormatTriplet>,
-    HashMap<i32, Vec<brain_core::protocol::ParamFormatSample>>,
+    Vec<ParamCheck>,
+    ParamMeta,
 )> {
     loop {
-        let msg = ws.read()?;
-        let Message::Text(text) = msg else { continue };
-        let server: ServerMessage = serde_json::from_str(&text)?;
+        let Some(server) = ws.recv()? else { continue };
         if let ServerMessage::Handshake {
             session_token,
             instances,
             validation_report,
-            param_enums,
-            param_formats,
-            param_format_samples,
-            ..
+            param_meta_streaming,
+            meta: handshake_meta,
+            project_tempo,
+            server_addr,
+            tls_fingerprint,
+            capabilities,
         } = server
         {
-            return Ok((
-                session_token,
-                instances,
-                validation_report,
+            let HandshakeMeta {
                 param_enums,
+                param_enum_totals,
                 param_formats,
                 param_format_samples,
-            ));
+                eq_band_hz,
+                param_kinds,
+                param_schema,
+                param_remap,
+                proposed_remap,
+            } = *handshake_meta;
+            if let Some(addr) = server_addr {
+                println!("server_addr={addr}");
+            }
+            if let Some(fp) = tls_fingerprint {
+                println!("tls_fingerprint={fp}");
+            }
+            if let Ok(names) = serde_json::to_string(&capabilities) {
+                println!("capabilities={names}");
+            }
+            let mut moved: Vec<(i32, i32)> = param_remap.into_iter().collect();
+            moved.sort_unstable();
+            for (from, to) in moved {
+                println!("param moved: {from} -> {to}");
+            }
+            let mut proposed: Vec<(i32, i32)> = proposed_remap.into_iter().collect();
+            proposed.sort_unstable();
+            for (from, to) in proposed {
+                println!("param remap proposed: {from} -> {to}");
+            }
+            print_enum_totals(param_enum_totals);
+            // Match the UI behavior: hand the plugin meta to brain_core so the model can pick enum
+            // labels, and so the resolver can convert human units using formatted triplets/samples.
+            let mut meta = ParamMeta {
+                enums: param_enums,
+                formats: param_formats,
+                samples: param_format_samples,
+                eq_band_hz,
+                kinds: param_kinds,
+                schema: param_schema,
+                tempo: project_tempo,
+            };
+            if param_meta_streaming {
+                wait_param_meta(ws, &mut meta)?;
+            }
+            return Ok((session_token, instances, validation_report, meta));
         }
     }
 }
 
-fn append_plugin_param_meta_to_prompt(
-    prompt: &str,
-    enums: &HashMap<i32, Vec<brain_core::protocol::ParamEnumOption>>,
-    formats: &HashMap<i32, brain_core::protocol::ParamFormatTriplet>,
-    samples: &HashMap<i32, Vec<brain_core::protocol::ParamFormatSample>>,
-) -> String {
-    if enums.is_empty() && formats.is_empty() && samples.is_empty() {
-        return prompt.to_string();
-    }
-
-    let mut meta = String::new();
-    meta.push_str("\n\nPLUGIN PARAM META (from the current REAPER instance):\n");
-
-    let include_enum = [84, 92, 99, 113, 5];
-    let mut enum_obj: HashMap<i32, Vec<(f32, String)>> = HashMap::new();
-    for idx in include_enum {
-        if let Some(opts) = enums.get(&idx) {
-            let mapped: Vec<(f32, String)> = opts
-                .iter()
-                .map(|o| (o.value, o.label.clone()))
-                .collect();
-            enum_obj.insert(idx, mapped);
-        }
-    }
-
-    let mut include_fmt: Vec<i32> = Vec::new();
-    include_fmt.extend([0, 1, 2]); // input/output gain + gate
-    include_fmt.extend(30..=51); // amp knobs
-    include_fmt.extend(54..=82); // graphic EQ bands
-    include_fmt.extend([87, 88, 89, 94, 95, 96]); // cab mic position/distance/level
-    include_fmt.extend([105, 106, 108]); // delay
-    include_fmt.extend([114, 115, 116, 117]); // reverb
-    include_fmt.sort_unstable();
-    include_fmt.dedup();
-
-    let mut fmt_obj: HashMap<i32, (String, String, String)> = HashMap::new();
-    for idx in include_fmt {
-        if let Some(t) = formats.get(&idx) {
-            fmt_obj.insert(idx, (t.min.clone(), t.mid.clone(), t.max.clone()));
-        }
-    }
-
-    if !enum_obj.is_empty() {
-        if let Ok(j) = serde_json::to_string(&enum_obj) {
-            meta.push_str("ENUM_OPTIONS_JSON=");
-            meta.push_str(&j);
-            meta.push('\n');
-        }
-    }
-    if !fmt_obj.is_empty() {
-        if let Ok(j) = serde_json::to_string(&fmt_obj) {
-            meta.push_str("FORMATTED_VALUE_TRIPLETS_JSON=");
-            meta.push_str(&j);
-            meta.push('\n');
-        }
-    }
-
-    if !samples.is_empty() {
-        let mut sample_obj: HashMap<i32, Vec<(f32, String)>> = HashMap::new();
-        let mut include: Vec<i32> = Vec::new();
-        include.extend([0, 1]); // input/output gain
-        include.push(2); // Gate
-        include.extend(54..=82); // Graphic EQ bands
-        include.extend([29, 30, 31, 32, 33, 34, 35]); // Clean amp
-        include.extend(36..=43); // Rust amp
-        include.extend(44..=51); // Hot amp
-        include.extend([101, 105, 106, 108, 112, 113, 114, 115, 116, 117]); // Time FX
-        include.extend([83, 84, 85, 87, 88, 89, 92, 94, 95, 96, 99]); // Cab selectors (+ mic pos/dist/levels)
-        include.sort_unstable();
-        include.dedup();
-
-        for idx in include {
-            if let Some(v) = samples.get(&idx) {
-                let mapped: Vec<(f32, String)> = v
-                    .iter()
-                    .map(|s| (s.norm, s.formatted.clone()))
-                    .collect();
-                if !mapped.is_empty() {
-                    sample_obj.insert(idx, mapped);
-                }
-            }
-        }
-
-        if !sample_obj.is_empty() {
-            if let Ok(j) = serde_json::to_string(&sample_obj) {
-                meta.push_str("PARAM_FORMAT_SAMPLES_JSON=");
-                meta.push_str(&j);
-                meta.push('\n');
+/// Merges the `ParamMeta` chunks that follow a handshake into `meta`, up to the last one.
+fn wait_param_meta(ws: &mut DllSocket, meta: &mut ParamMeta) -> anyhow::Result<()> {
+    loop {
+        let Some(server) = ws.recv()? else { continue };
+        if let ServerMessage::ParamMeta {
+            param_enums,
+            param_enum_totals,
+            param_formats,
+            param_format_samples,
+            param_kinds,
+            done,
+            ..
+        } = server
+        {
+            meta.enums.extend(param_enums);
+            meta.formats.extend(param_formats);
+            meta.samples.extend(param_format_samples);
+            meta.kinds.extend(param_kinds);
+            print_enum_totals(param_enum_totals);
+            if done {
+                return Ok(());
             }
         }
     }
+}
 
-    meta.push_str("Use these option labels when choosing Cab Type (84) and Mic IR (92/99). Set the parameter value close to the provided float for the desired label.\n");
-    meta.push_str("For continuous cab mic controls (Position/Distance), the formatted triplets can hint at units/direction; use them to pick sensible normalized values.\n");
-    meta.push_str("You may specify some values in human units (like dB) if PARAM_FORMAT_SAMPLES_JSON is present; the backend will translate them to 0..1.\n");
-
-    const MAX_EXTRA_CHARS: usize = 25_000;
-    if meta.len() > MAX_EXTRA_CHARS {
-        meta.truncate(MAX_EXTRA_CHARS);
-        meta.push_str("\n...(meta truncated)\n");
+/// Enums the DLL cut to their first page; the rest stay on its side until searched for.
+fn print_enum_totals(totals: HashMap<i32, usize>) {
+    let mut totals: Vec<(i32, usize)> = totals.into_iter().collect();
+    totals.sort_unstable();
+    for (idx, total) in totals {
+        println!("enum {idx}: first page of {total} options");
     }
-
-    format!("{prompt}{meta}")
 }
 
 fn wait_ack(
-    ws: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    ws: &mut DllSocket,
 ) -> anyhow::Result<Vec<AppliedParam>> {
     loop {
-        let msg = ws.read()?;
-        let Message::Text(text) = msg else { continue };
-        let server: ServerMessage = serde_json::from_str(&text)?;
+        let Some(server) = ws.recv()? else { continue };
         match server {
             ServerMessage::Ack {
                 command_id,
                 applied_params,
-                ..
+                skipped_modules,
+                failed_params,
             } => {
                 println!("ack: {command_id}");
+                if !skipped_modules.is_empty() {
+                    let kept: Vec<String> = skipped_modules
+                        .iter()
+                        .map(|m| match m.active {
+                            Some(true) => format!("{} (on)", m.name),
+                            Some(false) => format!("{} (off)", m.name),
+                            None => m.name.clone(),
+                        })
+                        .collect();
+                    println!("left as-is: {}", kept.join(", "));
+                }
+                for f in &failed_params {
+                    println!("not written: param {} = {:.4} ({})", f.index, f.value, f.error);
+                }
                 return Ok(applied_params);
             }
-            ServerMessage::Error { msg, code } => {
+            ServerMessage::Error {
+                msg,
+                code,
+                details,
+                diagnostic,
+                ..
+            } => {
+                let mut text = format!("server error {code:?}: {msg}");
+                if let Some(d) = details {
+                    text.push_str(&format!("\n  details: {d}"));
+                }
+                if let Some(r) = diagnostic.and_then(|d| d.remediation) {
+                    text.push_str(&format!("\n  remedy: {r:?}"));
+                }
+                return Err(anyhow::anyhow!(text));
+            }
+            ServerMessage::Warning { code, msg, context } => match context {
+                Some(c) => println!("warning {code:?}: {msg} ({c})"),
+                None => println!("warning {code:?}: {msg}"),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn run_preset_command(
+    ws: &mut DllSocket,
+    session_token: &str,
+    target: &str,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let command_id = format!("cli-preset-{}", chrono_nanos());
+    let cmd = if let Some(name) = args.save_preset.clone() {
+        ClientCommand::SavePreset {
+            session_token: session_token.to_string(),
+            command_id,
+            target_fx_guid: target.to_string(),
+            name,
+        }
+    } else if let Some(name) = args.load_preset.clone() {
+        ClientCommand::LoadPreset {
+            session_token: session_token.to_string(),
+            command_id,
+            target_fx_guid: target.to_string(),
+            name,
+        }
+    } else {
+        ClientCommand::ListPresets {
+            session_token: session_token.to_string(),
+            command_id,
+            target_fx_guid: target.to_string(),
+        }
+    };
+    ws.send(&cmd)?;
+
+    if let Some(name) = args.save_preset.as_deref() {
+        wait_ack(ws)?;
+        println!("saved preset {name:?}");
+    } else if let Some(name) = args.load_preset.as_deref() {
+        let written = wait_ack(ws)?.len();
+        println!("loaded preset {name:?} ({written} param(s) written)");
+    } else {
+        let (saved, current_native) = wait_preset_list(ws)?;
+        println!("saved presets:");
+        if saved.is_empty() {
+            println!("  (none)");
+        }
+        for name in &saved {
+            println!("  {name}");
+        }
+        if let Some(native) = current_native {
+            println!("plugin preset: {native}");
+        }
+    }
+    Ok(())
+}
+
+fn wait_preset_list(ws: &mut DllSocket) -> anyhow::Result<(Vec<String>, Option<String>)> {
+    loop {
+        let Some(server) = ws.recv()? else { continue };
+        match server {
+            ServerMessage::PresetList {
+                saved,
+                current_native,
+                ..
+            } => return Ok((saved, current_native)),
+            ServerMessage::Error { msg, code, .. } => {
+                return Err(anyhow::anyhow!("server error {code:?}: {msg}"));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn wait_preview(ws: &mut DllSocket) -> anyhow::Result<Vec<PreviewRow>> {
+    loop {
+        let Some(server) = ws.recv()? else { continue };
+        match server {
+            ServerMessage::Preview { rows, .. } => return Ok(rows),
+            ServerMessage::Error { msg, code, .. } => {
+                return Err(anyhow::anyhow!("server error {code:?}: {msg}"));
+            }
+            ServerMessage::Warning { code, msg, context } => match context {
+                Some(c) => println!("warning {code:?}: {msg} ({c})"),
+                None => println!("warning {code:?}: {msg}"),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn print_preview(rows: &[PreviewRow]) {
+    println!("preview:");
+    for r in rows {
+        let before = if r.before_formatted.is_empty() {
+            r.before
+                .map(|v| format!("{v:.6}"))
+                .unwrap_or_else(|| "?".to_string())
+        } else {
+            r.before_formatted.clone()
+        };
+        let after = if r.after_formatted.is_empty() {
+            format!("{:.6}", r.after)
+        } else {
+            r.after_formatted.clone()
+        };
+        let changed = !r.before.is_some_and(|v| (v - r.after).abs() <= 1e-6);
+        println!(
+            "  {} idx={} {}: {before} -> {after}",
+            if changed { "*" } else { " " },
+            r.index,
+            r.name
+        );
+    }
+}
+
+fn wait_pong(ws: &mut DllSocket) -> anyhow::Result<u64> {
+    loop {
+        let Some(server) = ws.recv()? else { continue };
+        match server {
+            ServerMessage::Pong { uptime_ms, .. } => return Ok(uptime_ms),
+            ServerMessage::Error { msg, code, .. } => {
+                return Err(anyhow::anyhow!("server error {code:?}: {msg}"));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn wait_metrics(ws: &mut DllSocket) -> anyhow::Result<RuntimeMetrics> {
+    loop {
+        let Some(server) = ws.recv()? else { continue };
+        match server {
+            ServerMessage::Metrics { metrics } => return Ok(metrics),
+            ServerMessage::Error { msg, code, .. } => {
+                return Err(anyhow::anyhow!("server error {code:?}: {msg}"));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn wait_diagnostics(ws: &mut DllSocket) -> anyhow::Result<(Vec<LogEvent>, TimingStats)> {
+    loop {
+        let Some(server) = ws.recv()? else { continue };
+        match server {
+            ServerMessage::Diagnostics { events, timings } => return Ok((events, timings)),
+            ServerMessage::Error { msg, code, .. } => {
                 return Err(anyhow::anyhow!("server error {code:?}: {msg}"));
             }
             _ => {}
@@ -357,6 +1240,76 @@ fn wait_ack(
     }
 }
 
+fn print_validation_report(checks: &[ParamCheck]) {
+    let ok = checks
+        .iter()
+        .filter(|c| c.status == ParamCheckStatus::Ok)
+        .count();
+    println!("validator: {ok}/{} params as charted", checks.len());
+    for c in checks.iter().filter(|c| c.status != ParamCheckStatus::Ok) {
+        println!(
+            "  {} {} {}: {} (charted {:?}, found {:?})",
+            format!("{:?}", c.status).to_lowercase(),
+            c.module,
+            format!("{:?}", c.role).to_lowercase(),
+            c.expected_index,
+            c.expected_name.as_deref().unwrap_or("-"),
+            c.found_name.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+fn print_diagnostics(events: &[LogEvent], t: &TimingStats) {
+    println!("timings:");
+    println!(
+        "  tick: n={} avg={:.2}ms max={:.2}ms",
+        t.ticks, t.avg_tick_ms, t.max_tick_ms
+    );
+    println!(
+        "  scan: n={} avg={:.2}ms max={:.2}ms",
+        t.scans, t.avg_scan_ms, t.max_scan_ms
+    );
+    println!(
+        "  apply: n={} avg={:.2}ms max={:.2}ms",
+        t.applies, t.avg_apply_ms, t.max_apply_ms
+    );
+    println!("events:");
+    if events.is_empty() {
+        println!("  (none)");
+    }
+    for e in events {
+        println!(
+            "  {:>9.3}s {:<5} {}: {}",
+            e.at_ms as f64 / 1000.0,
+            format!("{:?}", e.level).to_lowercase(),
+            e.target,
+            e.msg
+        );
+    }
+}
+
+fn print_metrics(m: &RuntimeMetrics) {
+    println!("metrics:");
+    println!("  uptime: {:.0}s", m.uptime_secs);
+    println!(
+        "  commands: received={} applied={} rejected={} coalesced={}",
+        m.commands_received, m.commands_applied, m.commands_rejected, m.commands_coalesced
+    );
+    println!(
+        "  apply: avg={:.2}ms max={:.2}ms",
+        m.avg_apply_ms, m.max_apply_ms
+    );
+    println!("  ticks/s: {:.1}", m.ticks_per_sec);
+    println!(
+        "  probes: {} last={:.1}ms max={:.1}ms",
+        m.probes, m.last_probe_ms, m.max_probe_ms
+    );
+    println!(
+        "  queues: inbound_hw={} outbound_hw={} outbound_dropped={}",
+        m.inbound_high_water, m.outbound_high_water, m.outbound_dropped
+    );
+}
+
 fn print_applied_deltas(requested: &[ParamChange], applied: &[AppliedParam]) {
     let mut applied_by_index: HashMap<i32, &AppliedParam> = HashMap::new();
     for a in applied {
@@ -397,6 +1350,11 @@ fn print_applied_deltas(requested: &[ParamChange], applied: &[AppliedParam]) {
     }
 }
 
+fn parse_module_id(s: &str) -> Result<ModuleId, String> {
+    serde_json::from_value(serde_json::Value::String(s.trim().to_string()))
+        .map_err(|_| format!("unknown module {s:?}"))
+}
+
 fn chrono_nanos() -> u128 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
@@ -405,144 +1363,39 @@ fn chrono_nanos() -> u128 {
         .as_nanos()
 }
 
-fn label_for_index(index: i32) -> &'static str {
-    match index {
-        // Global
-        0 => "Input Gain",
-        1 => "Output Gain",
-        2 => "Gate Amount",
-
-        // Pitch / WOW
-        3 => "Pitch Section Active",
-        4 => "WOW Active",
-        5 => "WOW Type",
-        6 => "WOW Position",
-        7 => "WOW Dry/Wet",
-
-        // Octaver
-        8 => "OCT Active",
-        9 => "OCT Oct 1 Level",
-        10 => "OCT Oct 2 Level",
-        11 => "OCT Direct Level",
-
-        param_map::selectors::AMP_TYPE_INDEX => "Amp Type",
-
-        // Amp section + per-amp controls
-        28 => "Amp Section Active",
-        30 => "CLN Amp Gain",
-        31 => "CLN Amp Bright",
-        32 => "CLN Amp Bass",
-        33 => "CLN Amp Mid",
-        34 => "CLN Amp Treble",
-        35 => "CLN Amp Level",
-        36 => "RUST Amp Gain",
-        37 => "RUST Amp Low",
-        38 => "RUST Amp Mid",
-        39 => "RUST Amp High",
-        40 => "RUST Amp Master",
-        41 => "RUST Amp Presence",
-        42 => "RUST Amp Depth",
-        43 => "RUST Amp Level",
-        44 => "HOT Amp Gain",
-        45 => "HOT Amp Low",
-        46 => "HOT Amp Mid",
-        47 => "HOT Amp High",
-        48 => "HOT Amp Master",
-        49 => "HOT Amp Presence",
-        50 => "HOT Amp Depth",
-        51 => "HOT Amp Level",
-
-        // Graphic EQ
-        52 => "EQ Section Active",
-        53 => "CLN EQ Active",
-        54 => "CLN EQ Band 1",
-        55 => "CLN EQ Band 2",
-        56 => "CLN EQ Band 3",
-        57 => "CLN EQ Band 4",
-        58 => "CLN EQ Band 5",
-        59 => "CLN EQ Band 6",
-        60 => "CLN EQ Band 7",
-        61 => "CLN EQ Band 8",
-        62 => "CLN EQ Band 9",
-        63 => "RUST EQ Active",
-        64 => "RUST EQ Band 1",
-        65 => "RUST EQ Band 2",
-        66 => "RUST EQ Band 3",
-        67 => "RUST EQ Band 4",
-        68 => "RUST EQ Band 5",
-        69 => "RUST EQ Band 6",
-        70 => "RUST EQ Band 7",
-        71 => "RUST EQ Band 8",
-        72 => "RUST EQ Band 9",
-        73 => "HOT EQ Active",
-        74 => "HOT EQ Band 1",
-        75 => "HOT EQ Band 2",
-        76 => "HOT EQ Band 3",
-        77 => "HOT EQ Band 4",
-        78 => "HOT EQ Band 5",
-        79 => "HOT EQ Band 6",
-        80 => "HOT EQ Band 7",
-        81 => "HOT EQ Band 8",
-        82 => "HOT EQ Band 9",
-
-        param_map::pedals::overdrive::ACTIVE => "Overdrive Active",
-        param_map::pedals::overdrive::DRIVE => "Overdrive Drive",
-        param_map::pedals::overdrive::TONE => "Overdrive Tone",
-        param_map::pedals::overdrive::LEVEL => "Overdrive Level",
-
-        // Distortion
-        17 => "DRT Active",
-        18 => "DRT Dist",
-        19 => "DRT Filter",
-        20 => "DRT Vol",
-
-        // Phaser
-        21 => "PHSR Active",
-        22 => "PHSR Rate",
-
-        // Chorus
-        23 => "CHR Active",
-        24 => "CHR Rate",
-        25 => "CHR Depth",
-        26 => "CHR Feedback",
-        27 => "CHR Mix",
-
-        param_map::pedals::delay::ACTIVE => "Delay Active",
-        param_map::pedals::delay::MIX => "Delay Dry/Wet",
-        param_map::pedals::delay::FEEDBACK => "Delay Feedback",
-        param_map::pedals::delay::TIME => "Delay Tempo",
-
-        param_map::pedals::reverb::ACTIVE => "Reverb Active",
-        param_map::pedals::reverb::MODE => "Reverb Mode",
-        param_map::pedals::reverb::MIX => "Reverb Dry/Wet",
-        param_map::pedals::reverb::TIME => "Reverb Time",
-        param_map::pedals::reverb::LOW_CUT => "Reverb Low Cut",
-        param_map::pedals::reverb::HIGH_CUT => "Reverb High Cut",
-
-        // Cab
-        83 => "Cab Section Active",
-        84 => "Cab Type",
-        85 => "Cab/Amp Linked",
-        86 => "Cab 1 Active",
-        87 => "Cab 1 Position",
-        88 => "Cab 1 Distance",
-        89 => "Cab 1 Level",
-        90 => "Cab 1 Pan",
-        91 => "Cab 1 Phase",
-        92 => "Cab 1 Mic IR",
-        93 => "Cab 2 Active",
-        94 => "Cab 2 Position",
-        95 => "Cab 2 Distance",
-        96 => "Cab 2 Level",
-        97 => "Cab 2 Pan",
-        98 => "Cab 2 Phase",
-        99 => "Cab 2 Mic IR",
-        100 => "FX Section Active",
-        _ => "Param",
-    }
-}
-
-fn print_qc(raw: &[ParamChange], raw_sanitized: &[ParamChange], final_params: &[ParamChange]) {
+/// The param's name as the DLL's schema reports it.
+fn label_for_index(index: i32, schema: &HashMap<i32, ParamSchemaEntry>) -> &str {
+    schema.get(&index).map_or("Param", |p| p.name.as_str())
+}
+
+fn print_alternatives(alternatives: &[ParamAlternatives], schema: &HashMap<i32, ParamSchemaEntry>) {
+    if alternatives.is_empty() {
+        return;
+    }
+    println!("alternatives:");
+    for alt in alternatives {
+        let options: Vec<String> = alt
+            .options
+            .iter()
+            .map(|o| format!("{} ({:.3})", o.label, o.value))
+            .collect();
+        println!(
+            "  {} {}: chosen {} -> {}",
+            alt.index,
+            label_for_index(alt.index, schema),
+            alt.chosen.as_deref().unwrap_or("?"),
+            options.join(", ")
+        );
+    }
+    println!();
+}
+
+fn print_qc(
+    raw: &[ParamChange],
+    raw_sanitized: &[ParamChange],
+    final_params: &[ParamChange],
+    schema: &HashMap<i32, ParamSchemaEntry>,
+) {
     let mut warnings: Vec<String> = Vec::new();
 
     if raw.len() != raw_sanitized.len() {
@@ -588,11 +1441,11 @@ fn print_qc(raw: &[ParamChange], raw_sanitized: &[ParamChange], final_params: &[
         .collect();
 
     println!("  model (sanitized):");
-    print_grouped(raw_sanitized);
+    print_grouped(raw_sanitized, schema);
 
     if !added_by_cleaner.is_empty() {
         println!("  added_by_replace_active:");
-        print_grouped(&added_by_cleaner);
+        print_grouped(&added_by_cleaner, schema);
     }
 
     // Detect "changed by sanitizer" values (clamp/non-finite shouldn't happen, but keep it explicit).
@@ -607,7 +1460,7 @@ fn print_qc(raw: &[ParamChange], raw_sanitized: &[ParamChange], final_params: &[
     }
     if !changed_by_sanitize.is_empty() {
         println!("  changed_by_sanitize:");
-        print_grouped(&changed_by_sanitize);
+        print_grouped(&changed_by_sanitize, schema);
     }
 
     // Sanity: ensure no index value mismatches (shouldn't happen).
@@ -635,7 +1488,7 @@ fn print_qc(raw: &[ParamChange], raw_sanitized: &[ParamChange], final_params: &[
 
 fn module_consistency_warnings(params: &[ParamChange]) -> Vec<String> {
     let mut w = Vec::new();
-    let set: std::collections::BTreeMap<i32, f32> = to_map(params);
+    let set: std::collections::BTreeMap<i32, f64> = to_map(params);
 
     // If any non-toggle params are present, ensure the module toggle is explicitly present too.
     // We don't auto-fix here; we warn so the prompt/system can be improved.
@@ -715,7 +1568,7 @@ fn module_consistency_warnings(params: &[ParamChange]) -> Vec<String> {
     w
 }
 
-fn to_map(params: &[ParamChange]) -> BTreeMap<i32, f32> {
+fn to_map(params: &[ParamChange]) -> BTreeMap<i32, f64> {
     let mut out = BTreeMap::new();
     for p in params {
         out.insert(p.index, p.value);
@@ -723,23 +1576,20 @@ fn to_map(params: &[ParamChange]) -> BTreeMap<i32, f32> {
     out
 }
 
-fn group_key(index: i32) -> &'static str {
-    match index {
-        0..=2 => "global",
-        3..=27 => "pedals_pre",
-        28..=82 => "amp_eq",
-        83..=100 => "cab",
-        101..=111 => "delay",
-        112..=118 => "reverb",
-        119..=4096 => "midi_or_other",
-        _ => "other",
-    }
+/// The param's section as the DLL's schema reports it; the schema stops at the last tone param.
+fn group_key(index: i32, schema: &HashMap<i32, ParamSchemaEntry>) -> &str {
+    schema
+        .get(&index)
+        .map_or("midi_or_other", |p| p.group.as_str())
 }
 
-fn print_grouped(params: &[ParamChange]) {
-    let mut groups: BTreeMap<&'static str, Vec<&ParamChange>> = BTreeMap::new();
+fn print_grouped(params: &[ParamChange], schema: &HashMap<i32, ParamSchemaEntry>) {
+    let mut groups: BTreeMap<&str, Vec<&ParamChange>> = BTreeMap::new();
     for p in params {
-        groups.entry(group_key(p.index)).or_default().push(p);
+        groups
+            .entry(group_key(p.index, schema))
+            .or_default()
+            .push(p);
     }
 
     for (g, mut items) in groups {
@@ -749,7 +1599,7 @@ fn print_grouped(params: &[ParamChange]) {
             println!(
                 "      {:>4} {:<18} = {:.3}",
                 p.index,
-                label_for_index(p.index),
+                label_for_index(p.index, schema),
                 p.value
             );
         }
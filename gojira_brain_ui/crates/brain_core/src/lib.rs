@@ -1,4 +1,6 @@
 pub mod modules;
 
-pub use modules::{cleaner, gemini, param_map, protocol, system_prompt};
-
+pub use modules::{
+    anthropic, cleaner, few_shot, gemini, openai, param_map, param_meta, protocol,
+    research_cache, sidecar, system_prompt, tone_model,
+};
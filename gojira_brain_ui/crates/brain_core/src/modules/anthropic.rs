@@ -0,0 +1,164 @@
+//! Tone generation over Anthropic's Messages API.
+
+use crate::modules::gemini::{
+    lend_progress, lend_text, research_prompt, response_text, tone_from_response, GeminiError,
+    ProgressSink, TextFormat, TextSink, ToneRequest, ToneResponse,
+};
+use crate::modules::system_prompt::SYSTEM_PROMPT;
+use crate::modules::tone_model::{http_timeout, ModelFuture, ToneModel};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+const API_VERSION: &str = "2023-06-01";
+/// Room for the reasoning and a full tone; the API requires a cap.
+const MAX_TOKENS: u32 = 8192;
+
+const ANTHROPIC_TEXT: TextFormat = TextFormat {
+    body: extract_content_text,
+    event: extract_delta_text,
+};
+
+pub struct AnthropicModel {
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl ToneModel for AnthropicModel {
+    fn research<'a>(
+        &'a self,
+        user_prompt: &'a str,
+        mut on_text: TextSink<'a>,
+    ) -> ModelFuture<'a, String> {
+        Box::pin(async move {
+            let payload = json!({
+                "model": self.model,
+                "max_tokens": MAX_TOKENS,
+                "messages": [{ "role": "user", "content": research_prompt(user_prompt) }],
+                "stream": on_text.is_some(),
+            });
+            let resp = self.send(&payload).await?;
+            response_text(resp, ANTHROPIC_TEXT, lend_text(&mut on_text)).await
+        })
+    }
+
+    fn tone<'a>(
+        &'a self,
+        req: ToneRequest,
+        mut progress: ProgressSink<'a>,
+    ) -> ModelFuture<'a, ToneResponse> {
+        Box::pin(async move {
+            // No JSON mode here; the system prompt's output contract is what keeps it to JSON.
+            let payload = json!({
+                "model": self.model,
+                "max_tokens": MAX_TOKENS,
+                "system": SYSTEM_PROMPT,
+                "messages": [{ "role": "user", "content": req.model_prompt() }],
+                "stream": progress.is_some(),
+            });
+            let resp = self.send(&payload).await?;
+            tone_from_response(
+                resp,
+                ANTHROPIC_TEXT,
+                &req.meta,
+                lend_progress(&mut progress),
+            )
+            .await
+        })
+    }
+}
+
+impl AnthropicModel {
+    /// Posts `payload` and returns the successful response, retrying rate limits, overload and
+    /// server errors.
+    async fn send(&self, payload: &Value) -> Result<reqwest::Response, GeminiError> {
+        let api_key = self
+            .api_key
+            .as_deref()
+            .ok_or_else(|| GeminiError::Auth("missing ANTHROPIC_API_KEY".to_string()))?;
+        let client = reqwest::Client::builder().timeout(http_timeout()).build()?;
+
+        let mut backoff = Duration::from_millis(500);
+        for attempt in 1..=3 {
+            let resp = client
+                .post(MESSAGES_URL)
+                .header("x-api-key", api_key)
+                .header("anthropic-version", API_VERSION)
+                .json(payload)
+                .send()
+                .await?;
+            if resp.status().is_success() {
+                return Ok(resp);
+            }
+
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            // 529 is Anthropic's "overloaded".
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                || status.is_server_error()
+                || status.as_u16() == 529;
+            if !retryable || attempt == 3 {
+                return Err(GeminiError::BadStatus { status, body });
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_secs(5));
+        }
+
+        Err(GeminiError::Parse("exhausted retries".to_string()))
+    }
+}
+
+fn extract_content_text(body: &str) -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct Message {
+        content: Vec<Block>,
+    }
+    #[derive(Deserialize)]
+    struct Block {
+        text: Option<String>,
+    }
+
+    let message: Message = serde_json::from_str(body).map_err(|e| format!("{e}: {body}"))?;
+    let text: String = message.content.into_iter().filter_map(|b| b.text).collect();
+    if text.is_empty() {
+        return Err(format!("missing content text: {body}"));
+    }
+    Ok(text)
+}
+
+/// The text of a `content_block_delta` event; the other events carry none.
+fn extract_delta_text(data: &str) -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct Event {
+        delta: Option<Delta>,
+    }
+    #[derive(Deserialize)]
+    struct Delta {
+        text: Option<String>,
+    }
+
+    let event: Event = serde_json::from_str(data).map_err(|e| format!("{e}: {data}"))?;
+    event
+        .delta
+        .and_then(|d| d.text)
+        .ok_or_else(|| format!("missing delta.text: {data}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_whole_and_streamed_messages() {
+        let body =
+            r#"{"id":"m","type":"message","content":[{"type":"text","text":"{\"params\":[]}"}]}"#;
+        assert_eq!(extract_content_text(body).unwrap(), r#"{"params":[]}"#);
+
+        let delta = r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Tight"}}"#;
+        let stop = r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"}}"#;
+        assert_eq!(extract_delta_text(delta).unwrap(), "Tight");
+        assert!(extract_delta_text(stop).is_err());
+    }
+}
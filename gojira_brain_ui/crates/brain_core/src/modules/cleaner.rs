@@ -1,86 +1,8 @@
 use crate::modules::param_map;
-use crate::modules::protocol::{MergeMode, ParamChange};
+use crate::modules::protocol::{MergeMode, ParamChange, ParamOp};
+use gojira_param_catalog::MODULES;
 use std::collections::HashSet;
 
-#[derive(Clone, Copy)]
-struct ModuleDef {
-    bypass: &'static [i32],
-    params: &'static [i32],
-}
-
-const MODULES: &[ModuleDef] = &[
-    ModuleDef {
-        bypass: &[
-            param_map::pedals::wow_pitch::PEDAL_SWITCH,
-            param_map::pedals::wow_pitch::ACTIVE,
-        ],
-        params: &[
-            param_map::pedals::wow_pitch::PEDAL_SWITCH,
-            param_map::pedals::wow_pitch::ACTIVE,
-            param_map::pedals::wow_pitch::PITCH_VAL,
-        ],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::octaver::ACTIVE],
-        params: &[
-            param_map::pedals::octaver::ACTIVE,
-            param_map::pedals::octaver::OCT1,
-            param_map::pedals::octaver::OCT2,
-            param_map::pedals::octaver::DIRECT,
-        ],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::overdrive::ACTIVE],
-        params: &[
-            param_map::pedals::overdrive::ACTIVE,
-            param_map::pedals::overdrive::DRIVE,
-            param_map::pedals::overdrive::TONE,
-            param_map::pedals::overdrive::LEVEL,
-        ],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::distortion::ACTIVE],
-        params: &[
-            param_map::pedals::distortion::ACTIVE,
-            param_map::pedals::distortion::DIST,
-            param_map::pedals::distortion::FILTER,
-            param_map::pedals::distortion::VOL,
-        ],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::phaser::ACTIVE],
-        params: &[param_map::pedals::phaser::ACTIVE, param_map::pedals::phaser::RATE],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::chorus::ACTIVE],
-        params: &[
-            param_map::pedals::chorus::ACTIVE,
-            param_map::pedals::chorus::RATE,
-            param_map::pedals::chorus::DEPTH,
-            param_map::pedals::chorus::MIX,
-        ],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::delay::ACTIVE],
-        params: &[
-            param_map::pedals::delay::ACTIVE,
-            param_map::pedals::delay::MIX,
-            param_map::pedals::delay::FEEDBACK,
-            param_map::pedals::delay::TIME,
-        ],
-    },
-    ModuleDef {
-        bypass: &[param_map::pedals::reverb::ACTIVE],
-        params: &[
-            param_map::pedals::reverb::ACTIVE,
-            param_map::pedals::reverb::MIX,
-            param_map::pedals::reverb::TIME,
-            param_map::pedals::reverb::LOW_CUT,
-            param_map::pedals::reverb::HIGH_CUT,
-        ],
-    },
-];
-
 pub fn sanitize_params(params: Vec<ParamChange>) -> Result<Vec<ParamChange>, String> {
     const MAX_PARAM_INDEX: i32 = 4096;
     let mut seen = std::collections::HashSet::new();
@@ -98,13 +20,36 @@ pub fn sanitize_params(params: Vec<ParamChange>) -> Result<Vec<ParamChange>, Str
         out.push(ParamChange {
             index: p.index,
             value: p.value.clamp(0.0, 1.0),
+            op: ParamOp::Set,
         });
     }
     out.reverse();
     Ok(out)
 }
 
+/// Drops changes to indices the user has pinned.
+pub fn strip_locked_params(params: Vec<ParamChange>, locked: &HashSet<i32>) -> Vec<ParamChange> {
+    if locked.is_empty() {
+        return params;
+    }
+    params
+        .into_iter()
+        .filter(|p| !locked.contains(&p.index))
+        .collect()
+}
+
 pub fn apply_replace_active_cleaner(mode: MergeMode, params: Vec<ParamChange>) -> Vec<ParamChange> {
+    apply_replace_active_cleaner_locked(mode, params, &HashSet::new())
+}
+
+/// Like `apply_replace_active_cleaner`, but locked indices are stripped first and never
+/// auto-added (section toggles, bypasses), so a module with a pinned param keeps its state.
+pub fn apply_replace_active_cleaner_locked(
+    mode: MergeMode,
+    params: Vec<ParamChange>,
+    locked: &HashSet<i32>,
+) -> Vec<ParamChange> {
+    let params = strip_locked_params(params, locked);
     if !matches!(mode, MergeMode::ReplaceActive) {
         return params;
     }
@@ -123,9 +68,13 @@ pub fn apply_replace_active_cleaner(mode: MergeMode, params: Vec<ParamChange>) -
 
     // Dependency inference: if the model adjusts a section's parameters, ensure the section toggle
     // is present too. This doesn't override explicit user/model choices (only adds when missing).
-    fn ensure(out: &mut Vec<ParamChange>, already_set: &mut HashSet<i32>, index: i32, value: f32) {
+    fn ensure(out: &mut Vec<ParamChange>, already_set: &mut HashSet<i32>, index: i32, value: f64) {
         if already_set.insert(index) {
-            out.push(ParamChange { index, value });
+            out.push(ParamChange {
+                index,
+                value,
+                op: ParamOp::Set,
+            });
         }
     }
 
@@ -142,6 +91,9 @@ pub fn apply_replace_active_cleaner(mode: MergeMode, params: Vec<ParamChange>) -
     let has_cab1 = touches_any(&out, 87, 92) || already_set.contains(&86);
     let has_cab2 = touches_any(&out, 94, 99) || already_set.contains(&93);
 
+    // Locked indices count as "already set" so nothing below ever emits them.
+    already_set.extend(locked.iter().copied());
+
     if has_any_eq {
         ensure(&mut out, &mut already_set, 52, 1.0);
     }
@@ -166,7 +118,7 @@ pub fn apply_replace_active_cleaner(mode: MergeMode, params: Vec<ParamChange>) -
     }
 
     for (i, module) in MODULES.iter().enumerate() {
-        if touched_modules.contains(&i) {
+        if touched_modules.contains(&i) || module.params.iter().any(|p| locked.contains(p)) {
             continue;
         }
         for &bypass_idx in module.bypass {
@@ -174,6 +126,7 @@ pub fn apply_replace_active_cleaner(mode: MergeMode, params: Vec<ParamChange>) -
                 out.push(ParamChange {
                     index: bypass_idx,
                     value: 0.0,
+                    op: ParamOp::Set,
                 });
             }
         }
@@ -190,3 +143,47 @@ pub fn apply_replace_active_cleaner(mode: MergeMode, params: Vec<ParamChange>) -
     out
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::param_map::pedals::{chorus, delay, overdrive, reverb};
+
+    fn set(index: i32, value: f64) -> ParamChange {
+        ParamChange {
+            index,
+            value,
+            op: ParamOp::Set,
+        }
+    }
+
+    fn indices(params: &[ParamChange]) -> Vec<i32> {
+        params.iter().map(|p| p.index).collect()
+    }
+
+    #[test]
+    fn locked_indices_are_stripped() {
+        let locked = HashSet::from([reverb::MIX]);
+        let params = vec![set(delay::MIX, 0.3), set(reverb::MIX, 0.5)];
+        assert_eq!(
+            indices(&strip_locked_params(params.clone(), &locked)),
+            [delay::MIX]
+        );
+        let merged = apply_replace_active_cleaner_locked(MergeMode::Merge, params, &locked);
+        assert_eq!(indices(&merged), [delay::MIX]);
+    }
+
+    #[test]
+    fn replace_active_never_bypasses_a_module_with_a_locked_param() {
+        let locked = HashSet::from([reverb::TIME, chorus::ACTIVE]);
+        let params = vec![set(overdrive::DRIVE, 0.6), set(reverb::TIME, 0.8)];
+        let out = apply_replace_active_cleaner_locked(MergeMode::ReplaceActive, params, &locked);
+
+        let written = indices(&out);
+        assert!(written.contains(&overdrive::DRIVE));
+        assert!(!written.iter().any(|i| locked.contains(i)), "{written:?}");
+        assert!(!written.contains(&reverb::ACTIVE), "{written:?}");
+        // Modules with nothing locked are still switched off.
+        let delay_active = out.iter().find(|p| p.index == delay::ACTIVE);
+        assert_eq!(delay_active.map(|p| p.value), Some(0.0));
+    }
+}
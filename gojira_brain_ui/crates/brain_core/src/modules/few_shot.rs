@@ -0,0 +1,174 @@
+/// A vetted prompt -> params exemplar. `response` uses the same JSON shape the model must return,
+/// so it can be pasted into the prompt verbatim.
+#[derive(Debug, Clone, Copy)]
+pub struct ToneExample {
+    pub id: &'static str,
+    pub genre: &'static str,
+    pub era: &'static str,
+    /// Lowercase keywords matched against the user prompt.
+    pub keywords: &'static [&'static str],
+    pub prompt: &'static str,
+    pub response: &'static str,
+}
+
+pub const EXAMPLES: &[ToneExample] = &[
+    ToneExample {
+        id: "modern-gojira-rhythm",
+        genre: "modern metal",
+        era: "2016+",
+        keywords: &[
+            "modern", "stranded", "silvera", "magma", "fortitude", "chug", "djent", "tight",
+            "palm", "drop",
+        ],
+        prompt: "Modern Gojira rhythm like Stranded: tight chugs, huge low end, no mud",
+        response: r#"{"reasoning":"Hot amp for tight high gain; OD as a clean boost to tighten lows; Hot EQ trims low-mid mud; Cab 3 for the big modern low end; gate for staccato stops.","params":[{"index":29,"value":"Lead"},{"index":13,"value":"1"},{"index":14,"value":"0"},{"index":15,"value":"0.6"},{"index":16,"value":"1"},{"index":2,"value":"-45 dB"},{"index":44,"value":"6"},{"index":45,"value":"5.5"},{"index":46,"value":"5"},{"index":47,"value":"6"},{"index":49,"value":"5.5"},{"index":50,"value":"4"},{"index":52,"value":"1"},{"index":73,"value":"1"},{"index":76,"value":"-3 dB"},{"index":79,"value":"+1.5 dB"},{"index":83,"value":"1"},{"index":84,"value":"Cab 3"},{"index":85,"value":"0"},{"index":89,"value":"-12 dB"}]}"#,
+    },
+    ToneExample {
+        id: "early-gojira-raw",
+        genre: "death/groove metal",
+        era: "2001-2008",
+        keywords: &[
+            "raw", "organic", "old-school", "old school", "link", "from mars", "fmts",
+            "way of all flesh", "early", "scrape",
+        ],
+        prompt: "Early Gojira (From Mars to Sirius) rhythm: raw, organic, mid-forward, pick scrapes cut through",
+        response: r#"{"reasoning":"Rust/Crunch amp for a dynamic, less compressed feel; mids up for articulation; Rust EQ adds upper-mid bite; Cab 2 for classic 4x12 punch; light gate keeps scrapes alive.","params":[{"index":29,"value":"Crunch"},{"index":13,"value":"1"},{"index":14,"value":"0.2"},{"index":15,"value":"0.55"},{"index":16,"value":"0.9"},{"index":2,"value":"-55 dB"},{"index":36,"value":"7"},{"index":37,"value":"5"},{"index":38,"value":"6.5"},{"index":39,"value":"5.5"},{"index":41,"value":"5"},{"index":52,"value":"1"},{"index":63,"value":"1"},{"index":68,"value":"+2 dB"},{"index":65,"value":"-1.5 dB"},{"index":83,"value":"1"},{"index":84,"value":"Cab 2"},{"index":85,"value":"0"},{"index":89,"value":"-12 dB"}]}"#,
+    },
+    ToneExample {
+        id: "ambient-clean",
+        genre: "ambient / post-metal clean",
+        era: "any",
+        keywords: &[
+            "clean", "ambient", "atmospheric", "shimmer", "ethereal", "chorus", "post",
+            "intro", "lush",
+        ],
+        prompt: "Lush atmospheric clean for an intro: chorus, long shimmer reverb, soft highs",
+        response: r#"{"reasoning":"Clean amp with headroom; chorus for width; shimmer reverb for the ethereal tail; Clean EQ softens harsh highs; Cab 1 for an open, bright top.","params":[{"index":29,"value":"Clean"},{"index":30,"value":"3"},{"index":32,"value":"5"},{"index":33,"value":"5"},{"index":34,"value":"5.5"},{"index":35,"value":"6"},{"index":23,"value":"1"},{"index":24,"value":"0.3"},{"index":25,"value":"0.5"},{"index":27,"value":"35%"},{"index":112,"value":"1"},{"index":113,"value":"Shimmer"},{"index":114,"value":"30%"},{"index":115,"value":"4500 ms"},{"index":52,"value":"1"},{"index":53,"value":"1"},{"index":61,"value":"-2 dB"},{"index":83,"value":"1"},{"index":84,"value":"Cab 1"},{"index":85,"value":"0"},{"index":89,"value":"-12 dB"}]}"#,
+    },
+    ToneExample {
+        id: "eighties-thrash",
+        genre: "thrash",
+        era: "1980s",
+        keywords: &[
+            "thrash", "80s", "80's", "eighties", "bay area", "scooped", "metallica",
+            "slayer",
+        ],
+        prompt: "80s thrash rhythm: aggressive, slightly scooped mids, tight but not modern-compressed",
+        response: r#"{"reasoning":"Rust amp pushed hard for a period-correct grind; OD boost for tightness; mild mid scoop on the Rust EQ; Cab 2 for the 4x12 sound.","params":[{"index":29,"value":"Crunch"},{"index":13,"value":"1"},{"index":14,"value":"0.1"},{"index":15,"value":"0.65"},{"index":16,"value":"1"},{"index":2,"value":"-50 dB"},{"index":36,"value":"8"},{"index":37,"value":"6"},{"index":38,"value":"3.5"},{"index":39,"value":"6.5"},{"index":41,"value":"6"},{"index":52,"value":"1"},{"index":63,"value":"1"},{"index":67,"value":"-3 dB"},{"index":70,"value":"+1.5 dB"},{"index":83,"value":"1"},{"index":84,"value":"Cab 2"},{"index":85,"value":"0"},{"index":89,"value":"-12 dB"}]}"#,
+    },
+    ToneExample {
+        id: "soaring-lead",
+        genre: "metal lead",
+        era: "any",
+        keywords: &[
+            "lead", "solo", "sustain", "soaring", "singing", "delay", "melodic",
+        ],
+        prompt: "Soaring lead for a melodic solo: smooth sustain, mid push, delay trails",
+        response: r#"{"reasoning":"Hot amp for sustain; mids pushed so the lead sits above the mix; lighter gate to keep tails; delay and a little reverb for space.","params":[{"index":29,"value":"Lead"},{"index":13,"value":"1"},{"index":14,"value":"0.3"},{"index":15,"value":"0.5"},{"index":16,"value":"0.9"},{"index":2,"value":"-65 dB"},{"index":44,"value":"7"},{"index":45,"value":"5"},{"index":46,"value":"7"},{"index":47,"value":"5.5"},{"index":49,"value":"5"},{"index":52,"value":"1"},{"index":73,"value":"1"},{"index":78,"value":"+2.5 dB"},{"index":101,"value":"1"},{"index":105,"value":"25%"},{"index":106,"value":"0.35"},{"index":108,"value":"120 bpm"},{"index":112,"value":"1"},{"index":113,"value":"Reverb"},{"index":114,"value":"15%"},{"index":83,"value":"1"},{"index":84,"value":"Cab 3"},{"index":85,"value":"0"},{"index":89,"value":"-12 dB"}]}"#,
+    },
+    ToneExample {
+        id: "edge-of-breakup",
+        genre: "blues / rock",
+        era: "any",
+        keywords: &[
+            "blues", "breakup", "edge", "crunch", "rock", "vintage", "dynamic", "warm",
+        ],
+        prompt: "Warm edge-of-breakup tone that cleans up when I pick softly",
+        response: r#"{"reasoning":"Clean amp with gain pushed to the breakup point keeps dynamics; no gate so decays stay natural; a little low-mid warmth on the Clean EQ; Cab 2 for a rounder midrange.","params":[{"index":29,"value":"Clean"},{"index":30,"value":"7"},{"index":32,"value":"5.5"},{"index":33,"value":"6"},{"index":34,"value":"5"},{"index":35,"value":"5.5"},{"index":2,"value":"-96 dB"},{"index":52,"value":"1"},{"index":53,"value":"1"},{"index":57,"value":"+1.5 dB"},{"index":83,"value":"1"},{"index":84,"value":"Cab 2"},{"index":85,"value":"0"},{"index":89,"value":"-12 dB"}]}"#,
+    },
+];
+
+/// Picks up to `max` examples whose keywords appear in the prompt as whole words, best match
+/// first. Returns nothing when no keyword matches, so unrelated prompts stay zero-shot.
+pub fn select_examples(prompt: &str, max: usize) -> Vec<&'static ToneExample> {
+    let lower = prompt.to_ascii_lowercase();
+    let mut scored: Vec<(usize, usize, &'static ToneExample)> = EXAMPLES
+        .iter()
+        .enumerate()
+        .map(|(order, ex)| {
+            let score = ex
+                .keywords
+                .iter()
+                .filter(|k| contains_word(&lower, k))
+                .count();
+            (score, order, ex)
+        })
+        .filter(|(score, _, _)| *score > 0)
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().take(max).map(|(_, _, ex)| ex).collect()
+}
+
+/// Whether `keyword` occurs in `text` with no letter or digit on either side, so "early" doesn't
+/// match "nearly" while "old-school" and "80's" still match as written.
+fn contains_word(text: &str, keyword: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    text.match_indices(keyword).any(|(at, _)| {
+        !is_word(text[..at].chars().next_back())
+            && !is_word(text[at + keyword.len()..].chars().next())
+    })
+}
+
+/// `TONE_FEW_SHOT_MAX=0` turns the examples off, for comparing a prompt against zero-shot; how
+/// much they help has not been measured.
+fn max_examples() -> usize {
+    std::env::var("TONE_FEW_SHOT_MAX")
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .unwrap_or(2)
+}
+
+/// Model-facing block with the selected examples (empty when none match or `TONE_FEW_SHOT_MAX=0`).
+pub fn few_shot_block(prompt: &str) -> String {
+    let picked = select_examples(prompt, max_examples());
+    if picked.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from(
+        "\n\nREFERENCE EXAMPLES (vetted presets; copy the module/amp/EQ-group choices and structure, adapt the values to the request):\n",
+    );
+    for ex in picked {
+        out.push_str(&format!(
+            "- [{} / {}] USER: {}\n  JSON: {}\n",
+            ex.genre, ex.era, ex.prompt, ex.response
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::param_meta::ParamMeta;
+    use crate::modules::value_resolver::{resolve_ai_params, AiToneResponse};
+
+    #[test]
+    fn every_example_resolves_without_meta() {
+        for ex in EXAMPLES {
+            let parsed: AiToneResponse = serde_json::from_str(ex.response)
+                .unwrap_or_else(|e| panic!("{}: invalid json: {e}", ex.id));
+            resolve_ai_params(&ParamMeta::default(), parsed.params)
+                .unwrap_or_else(|e| panic!("{}: does not resolve: {e:?}", ex.id));
+        }
+    }
+
+    #[test]
+    fn selection_prefers_best_keyword_match() {
+        let picked = select_examples("tight modern chug tone like Stranded", 2);
+        assert_eq!(picked[0].id, "modern-gojira-rhythm");
+        assert!(select_examples("polka accordion", 2).is_empty());
+    }
+
+    #[test]
+    fn keywords_match_whole_words_only() {
+        let ids: Vec<_> = select_examples("nearly clean", 2)
+            .iter()
+            .map(|ex| ex.id)
+            .collect();
+        assert_eq!(ids, ["ambient-clean"]);
+        let picked = select_examples("old-school 80's thrash", 2);
+        assert_eq!(picked[0].id, "eighties-thrash");
+        assert_eq!(picked[1].id, "early-gojira-raw");
+    }
+}
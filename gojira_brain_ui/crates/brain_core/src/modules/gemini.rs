@@ -1,9 +1,14 @@
 use crate::modules::cleaner::{apply_replace_active_cleaner, sanitize_params};
+use crate::modules::few_shot::few_shot_block;
 use crate::modules::param_map;
+use crate::modules::param_meta::ParamMeta;
 use crate::modules::protocol::MergeMode;
-use crate::modules::protocol::ParamChange;
+use crate::modules::protocol::{ParamChange, ParamOp};
 use crate::modules::system_prompt::SYSTEM_PROMPT;
-use crate::modules::value_resolver::{resolve_ai_params, AiToneResponse};
+use crate::modules::tone_model::{ModelFuture, ToneModel, ToneModelConfig};
+use crate::modules::value_resolver::{
+    rank_alternatives, resolve_ai_params, AiParamChange, AiToneResponse, ParamAlternatives,
+};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -27,23 +32,77 @@ Output format (plain text, concise, 10-18 bullets total):
 #[derive(Debug, Clone, Serialize)]
 pub struct ToneRequest {
     pub user_prompt: String,
+    /// Live plugin metadata from the REAPER handshake (empty when running without a connection).
+    pub meta: ParamMeta,
+    /// Pre-made stage-1 research brief. When set, stage 1 is skipped and this text is used as-is.
+    pub research_brief: Option<String>,
+}
+
+impl ToneRequest {
+    /// The user turn every provider sends: the prompt, matching examples and the live metadata.
+    pub(crate) fn model_prompt(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.user_prompt,
+            few_shot_block(&self.user_prompt),
+            self.meta.prompt_block()
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ToneResponse {
     pub reasoning: String,
     pub params: Vec<ParamChange>,
+    /// Ranked swaps for the cab/mic selectors that were set.
+    pub alternatives: Vec<ParamAlternatives>,
 }
 
+/// A piece of a tone still being generated, for callers that show the model at work. Text
+/// arrives as deltas to append; `Params` always carries every param finished so far.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ToneProgress {
+    /// More of the stage-1 research brief.
+    Research { text: String },
+    /// More of the model's reasoning for the tone.
+    Reasoning { text: String },
+    /// The params the model has written out so far, resolved like the final answer's.
+    Params { params: Vec<ParamChange> },
+}
+
+/// Where a streamed generation sends its progress.
+pub type ProgressSink<'a> = Option<&'a mut (dyn FnMut(ToneProgress) + Send)>;
+/// Where a streamed text generation sends each delta.
+pub type TextSink<'a> = Option<&'a mut (dyn FnMut(&str) + Send)>;
+
+/// Lends `sink` to one call and keeps it for the next; `as_deref_mut` would borrow it for as long
+/// as the closure it points to lives.
+pub(crate) fn lend_progress<'s>(sink: &'s mut ProgressSink<'_>) -> ProgressSink<'s> {
+    match sink {
+        Some(f) => Some(&mut **f),
+        None => None,
+    }
+}
+
+/// `lend_progress` for text sinks.
+pub(crate) fn lend_text<'s>(sink: &'s mut TextSink<'_>) -> TextSink<'s> {
+    match sink {
+        Some(f) => Some(&mut **f),
+        None => None,
+    }
+}
+
+/// What a tone generation can fail with, on any `ToneModel` (Gemini was the first).
 #[derive(Debug, Error)]
 pub enum GeminiError {
     #[error("http error: {0}")]
     Http(#[from] reqwest::Error),
-    #[error("gemini request failed: status={status} body={body}")]
+    #[error("model request failed: status={status} body={body}")]
     BadStatus { status: StatusCode, body: String },
-    #[error("gemini auth error: {0}")]
+    #[error("model auth error: {0}")]
     Auth(String),
-    #[error("gemini response parse failed: {0}")]
+    #[error("model response parse failed: {0}")]
     Parse(String),
 }
 
@@ -96,6 +155,12 @@ pub fn decide_backend(_model: &str, api_key_present: bool) -> GeminiBackend {
     }
 }
 
+/// Whether `generate_tone_auto` runs a stage-1 research pass (`TONE_PIPELINE`), so callers only
+/// prefetch or cache briefs when they would be used.
+pub fn two_stage_enabled() -> bool {
+    decide_pipeline() == TonePipeline::TwoStage
+}
+
 fn decide_pipeline() -> TonePipeline {
     let env = std::env::var("TONE_PIPELINE")
         .ok()
@@ -119,7 +184,7 @@ fn oauth_scope_insufficient(body: &str) -> bool {
         || (b.contains("permission_denied") && b.contains("scope"))
 }
 
-fn research_model_for(main_model: &str) -> String {
+pub(crate) fn research_model_for(main_model: &str) -> String {
     if let Ok(m) = std::env::var("TONE_RESEARCH_MODEL") {
         let m = m.trim().to_string();
         if !m.is_empty() {
@@ -136,22 +201,17 @@ fn research_model_for(main_model: &str) -> String {
     }
 }
 
-fn truncate_chars(s: &str, max_chars: usize) -> String {
+/// The first `max_chars` chars of `s`, followed by `marker` when anything was cut.
+pub fn truncate_chars(s: &str, max_chars: usize, marker: &str) -> String {
     if s.chars().count() <= max_chars {
         return s.to_string();
     }
-    let mut out = String::new();
-    for (i, ch) in s.chars().enumerate() {
-        if i >= max_chars {
-            break;
-        }
-        out.push(ch);
-    }
-    out.push_str("\n…(truncated)\n");
+    let mut out: String = s.chars().take(max_chars).collect();
+    out.push_str(marker);
     out
 }
 
-fn http_timeout_for_model(model: &str) -> Duration {
+pub(crate) fn http_timeout_for_model(model: &str) -> Duration {
     let env = std::env::var("GEMINI_HTTP_TIMEOUT_SECS")
         .ok()
         .and_then(|s| s.trim().parse::<u64>().ok());
@@ -166,52 +226,23 @@ fn http_timeout_for_model(model: &str) -> Duration {
     Duration::from_secs(secs)
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct EnumOption {
-    value: f32,
-    label: String,
-}
-
-fn extract_prompt_json_line<'a>(prompt: &'a str, key: &str) -> Option<&'a str> {
-    for line in prompt.lines() {
-        let t = line.trim();
-        if let Some(v) = t.strip_prefix(key) {
-            let v = v.trim();
-            if !v.is_empty() {
-                return Some(v);
-            }
-        }
-    }
-    None
-}
-
-fn extract_enum_options(prompt: &str) -> Option<std::collections::HashMap<i32, Vec<EnumOption>>> {
-    let raw = extract_prompt_json_line(prompt, "ENUM_OPTIONS_JSON=")?;
-    let parsed: std::collections::HashMap<String, Vec<EnumOption>> =
-        serde_json::from_str(raw).ok()?;
-
-    let mut out: std::collections::HashMap<i32, Vec<EnumOption>> = std::collections::HashMap::new();
-    for (k, v) in parsed {
-        if let Ok(idx) = k.parse::<i32>() {
-            out.insert(idx, v);
-        }
-    }
-    Some(out)
-}
-
-fn upsert_param(params: &mut Vec<ParamChange>, index: i32, value: f32) {
+fn upsert_param(params: &mut Vec<ParamChange>, index: i32, value: f64) {
     if let Some(p) = params.iter_mut().find(|p| p.index == index) {
         p.value = value;
         return;
     }
-    params.push(ParamChange { index, value });
+    params.push(ParamChange {
+        index,
+        value,
+        op: ParamOp::Set,
+    });
 }
 
-fn get_param(params: &[ParamChange], index: i32) -> Option<f32> {
+fn get_param(params: &[ParamChange], index: i32) -> Option<f64> {
     params.iter().find(|p| p.index == index).map(|p| p.value)
 }
 
-fn apply_prompt_autofixes(prompt: &str, params: &mut Vec<ParamChange>) {
+fn apply_prompt_autofixes(prompt: &str, meta: &ParamMeta, params: &mut Vec<ParamChange>) {
     let plow = prompt.to_ascii_lowercase();
 
     // If delay/reverb are used, ensure both the module Active toggle and a sensible Mix exist.
@@ -249,16 +280,14 @@ fn apply_prompt_autofixes(prompt: &str, params: &mut Vec<ParamChange>) {
     if plow.contains("shimmer") {
         let reverb_on = get_param(params, param_map::pedals::reverb::ACTIVE).unwrap_or(0.0) >= 0.5;
         if reverb_on {
-            // Try to use prompt-provided enum meta, else fall back to observed stable values.
-            if let Some(enums) = extract_enum_options(prompt) {
-                if let Some(opts) = enums.get(&param_map::pedals::reverb::MODE) {
-                    if let Some(sh) = opts
-                        .iter()
-                        .find(|o| o.label.trim().eq_ignore_ascii_case("shimmer"))
-                    {
-                        upsert_param(params, param_map::pedals::reverb::MODE, sh.value);
-                        return;
-                    }
+            // Try to use handshake enum meta, else fall back to observed stable values.
+            if let Some(opts) = meta.enums.get(&param_map::pedals::reverb::MODE) {
+                if let Some(sh) = opts
+                    .iter()
+                    .find(|o| o.label.trim().eq_ignore_ascii_case("shimmer"))
+                {
+                    upsert_param(params, param_map::pedals::reverb::MODE, sh.value);
+                    return;
                 }
             }
             // Default observed Shimmer value in current plugin builds.
@@ -270,7 +299,7 @@ fn apply_prompt_autofixes(prompt: &str, params: &mut Vec<ParamChange>) {
 fn derive_plan(params: &[ParamChange]) -> String {
     use std::collections::BTreeMap;
 
-    let mut m: BTreeMap<i32, f32> = BTreeMap::new();
+    let mut m: BTreeMap<i32, f64> = BTreeMap::new();
     for p in params {
         m.insert(p.index, p.value);
     }
@@ -282,7 +311,7 @@ fn derive_plan(params: &[ParamChange]) -> String {
         Clean,
         Rust,
         Hot,
-        Other(f32),
+        Other(f64),
         Unset,
     }
     let amp_sel = match amp_v {
@@ -432,7 +461,7 @@ fn derive_plan(params: &[ParamChange]) -> String {
             if let Some(v) = m.get(&eq_active).copied() {
                 let mut s = format!("- {eq_name}: Active {eq_active}={v:.0}");
                 let band_start = *band_range.start();
-                let mut deltas: Vec<(i32, f32)> = band_range
+                let mut deltas: Vec<(i32, f64)> = band_range
                     .clone()
                     .filter_map(|i| m.get(&i).copied().map(|v| (i, v - 0.5)))
                     .collect();
@@ -506,9 +535,56 @@ pub async fn generate_tone_auto(
     req: ToneRequest,
     api_key: Option<&str>,
 ) -> Result<ToneResponse, GeminiError> {
-    if decide_pipeline() == TonePipeline::TwoStage {
-        let research_model = research_model_for(model);
-        let research = generate_research_auto(&research_model, &req.user_prompt, api_key).await;
+    let tone_model = ToneModelConfig::from_env(model, api_key).build();
+    generate_tone_with(tone_model.as_ref(), req, None).await
+}
+
+/// `generate_tone_auto` over Gemini's streaming endpoint: the research brief, the reasoning and
+/// the params go to `on_progress` while the model is still writing them, instead of after the
+/// 10-60 s the whole answer takes. The result is the same as the unstreamed one.
+pub async fn generate_tone_streamed(
+    model: &str,
+    req: ToneRequest,
+    api_key: Option<&str>,
+    on_progress: &mut (dyn FnMut(ToneProgress) + Send),
+) -> Result<ToneResponse, GeminiError> {
+    let tone_model = ToneModelConfig::from_env(model, api_key).build();
+    generate_tone_with(tone_model.as_ref(), req, Some(on_progress)).await
+}
+
+/// The tone pipeline (research brief, tone, prompt autofixes and plan) on any provider's model;
+/// `generate_tone_auto` picks the model from the environment, callers with their own provider
+/// setting build one with `ToneModelConfig`.
+pub async fn generate_tone_with(
+    model: &dyn ToneModel,
+    req: ToneRequest,
+    mut progress: ProgressSink<'_>,
+) -> Result<ToneResponse, GeminiError> {
+    let supplied_brief = req
+        .research_brief
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty());
+
+    if supplied_brief.is_some() || decide_pipeline() == TonePipeline::TwoStage {
+        let research = match supplied_brief {
+            Some(brief) => Ok(brief.to_string()),
+            None => {
+                let streaming = progress.is_some();
+                let mut on_text = |text: &str| {
+                    if let Some(p) = progress.as_deref_mut() {
+                        p(ToneProgress::Research {
+                            text: text.to_string(),
+                        });
+                    }
+                };
+                let on_text = streaming.then_some(&mut on_text as &mut (dyn FnMut(&str) + Send));
+                model
+                    .research(&req.user_prompt, on_text)
+                    .await
+                    .map(|text| text.trim().to_string())
+            }
+        };
 
         let (combined_prompt, research_for_reasoning) = match research {
             Ok(text) => {
@@ -517,7 +593,7 @@ pub async fn generate_tone_auto(
                     .and_then(|s| s.trim().parse::<usize>().ok())
                     .unwrap_or(1500);
                 let trimmed = text.trim();
-                let brief = truncate_chars(trimmed, max_chars);
+                let brief = truncate_chars(trimmed, max_chars, "\n…(truncated)\n");
                 (
                     format!(
                         "{}\n\n---\nTONE RESEARCH BRIEF:\n{}\n---\nNow translate this into the Archetype Gojira parameters using the indices and rules in the system prompt.\nIn your reasoning, include a short \"Plan\" section (3-7 bullets) that explicitly maps the brief into concrete module choices (amp + EQ + cab + time FX), and reference key indices you set.",
@@ -532,11 +608,14 @@ pub async fn generate_tone_auto(
             }
         };
 
-        let mut out =
-            generate_tone_single_stage(model, ToneRequest { user_prompt: combined_prompt }, api_key)
-                .await?;
+        let stage2 = ToneRequest {
+            user_prompt: combined_prompt,
+            meta: req.meta.clone(),
+            research_brief: None,
+        };
+        let mut out = model.tone(stage2, lend_progress(&mut progress)).await?;
 
-        apply_prompt_autofixes(&req.user_prompt, &mut out.params);
+        apply_prompt_autofixes(&req.user_prompt, &req.meta, &mut out.params);
 
         // Build a plan off the same post-processing the UI/CLI will apply.
         let sanitized = sanitize_params(out.params.clone()).map_err(GeminiError::Parse)?;
@@ -555,38 +634,108 @@ pub async fn generate_tone_auto(
         return Ok(out);
     }
 
-    let mut out = generate_tone_single_stage(model, req.clone(), api_key).await?;
-    apply_prompt_autofixes(&req.user_prompt, &mut out.params);
+    let mut out = model
+        .tone(req.clone(), lend_progress(&mut progress))
+        .await?;
+    apply_prompt_autofixes(&req.user_prompt, &req.meta, &mut out.params);
     Ok(out)
 }
 
+/// Runs only the stage-1 research pass and returns the untruncated brief, so callers can persist
+/// it and feed it back through `ToneRequest::research_brief` for later generations.
+pub async fn generate_research_brief(
+    model: &str,
+    user_prompt: &str,
+    api_key: Option<&str>,
+) -> Result<String, GeminiError> {
+    let tone_model = ToneModelConfig::from_env(model, api_key).build();
+    research_brief_with(tone_model.as_ref(), user_prompt).await
+}
+
+/// `generate_research_brief` on a model built by the caller.
+pub async fn research_brief_with(
+    model: &dyn ToneModel,
+    user_prompt: &str,
+) -> Result<String, GeminiError> {
+    let text = model.research(user_prompt, None).await?;
+    Ok(text.trim().to_string())
+}
+
+/// The stage-1 prompt for `user_prompt`, the same for every provider.
+pub(crate) fn research_prompt(user_prompt: &str) -> String {
+    format!("{RESEARCH_PROMPT}\n\nUSER:\n{user_prompt}")
+}
+
+/// Gemini on whichever backend `decide_backend` picks: an AI Studio key, Google OAuth or
+/// Vertex AI.
+pub struct GeminiModel {
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl ToneModel for GeminiModel {
+    fn research<'a>(
+        &'a self,
+        user_prompt: &'a str,
+        on_text: TextSink<'a>,
+    ) -> ModelFuture<'a, String> {
+        Box::pin(async move {
+            let research_model = research_model_for(&self.model);
+            generate_research_auto(
+                &research_model,
+                user_prompt,
+                self.api_key.as_deref(),
+                on_text,
+            )
+            .await
+        })
+    }
+
+    fn tone<'a>(
+        &'a self,
+        req: ToneRequest,
+        progress: ProgressSink<'a>,
+    ) -> ModelFuture<'a, ToneResponse> {
+        Box::pin(generate_tone_single_stage(
+            &self.model,
+            req,
+            self.api_key.as_deref(),
+            progress,
+        ))
+    }
+}
+
 async fn generate_tone_single_stage(
     model: &str,
     req: ToneRequest,
     api_key: Option<&str>,
+    mut progress: ProgressSink<'_>,
 ) -> Result<ToneResponse, GeminiError> {
     match decide_backend(model, api_key.is_some()) {
         GeminiBackend::AiStudioApiKey => {
             let api_key =
                 api_key.ok_or_else(|| GeminiError::Auth("missing GEMINI_API_KEY".to_string()))?;
-            match generate_tone_aistudio(api_key, model, req.clone()).await {
+            match generate_tone_aistudio(api_key, model, req.clone(), lend_progress(&mut progress))
+                .await
+            {
                 Ok(ok) => Ok(ok),
                 Err(GeminiError::Auth(msg))
                     if msg.to_ascii_lowercase().contains("oauth2 is required") =>
                 {
-                    generate_tone_google_oauth(model, req).await
+                    generate_tone_google_oauth(model, req, progress).await
                 }
                 Err(GeminiError::BadStatus { status, body })
                     if status == StatusCode::UNAUTHORIZED
                         && body.to_ascii_lowercase().contains("api keys are not supported") =>
                 {
-                    generate_tone_google_oauth(model, req).await
+                    generate_tone_google_oauth(model, req, progress).await
                 }
                 Err(e) => Err(e),
             }
         }
         GeminiBackend::GoogleAiOauth => {
-            match generate_tone_google_oauth(model, req.clone()).await {
+            match generate_tone_google_oauth(model, req.clone(), lend_progress(&mut progress)).await
+            {
                 Ok(ok) => Ok(ok),
                 Err(GeminiError::BadStatus { status, body })
                     if status == StatusCode::FORBIDDEN && oauth_scope_insufficient(&body) =>
@@ -594,12 +743,12 @@ async fn generate_tone_single_stage(
                     // If the OAuth token doesn't have Generative Language API scopes, prefer Vertex
                     // (cloud-platform scoped tokens usually work). Project can be discovered from
                     // gcloud config as a fallback.
-                    generate_tone_vertex(model, req).await
+                    generate_tone_vertex(model, req, progress).await
                 }
                 Err(e) => Err(e),
             }
         }
-        GeminiBackend::VertexAi => generate_tone_vertex(model, req).await,
+        GeminiBackend::VertexAi => generate_tone_vertex(model, req, progress).await,
     }
 }
 
@@ -607,32 +756,37 @@ async fn generate_research_auto(
     model: &str,
     user_prompt: &str,
     api_key: Option<&str>,
+    mut on_text: TextSink<'_>,
 ) -> Result<String, GeminiError> {
-    let full_prompt = format!("{RESEARCH_PROMPT}\n\nUSER:\n{user_prompt}");
+    let full_prompt = research_prompt(user_prompt);
     match decide_backend(model, api_key.is_some()) {
         GeminiBackend::AiStudioApiKey => {
             let api_key =
                 api_key.ok_or_else(|| GeminiError::Auth("missing GEMINI_API_KEY".to_string()))?;
-            match generate_text_aistudio(api_key, model, &full_prompt).await {
+            match generate_text_aistudio(api_key, model, &full_prompt, lend_text(&mut on_text))
+                .await
+            {
                 Ok(ok) => Ok(ok),
                 Err(GeminiError::Auth(msg))
                     if msg.to_ascii_lowercase().contains("oauth2 is required") =>
                 {
-                    generate_text_google_oauth(model, &full_prompt).await
+                    generate_text_google_oauth(model, &full_prompt, on_text).await
                 }
                 Err(e) => Err(e),
             }
         }
-        GeminiBackend::GoogleAiOauth => match generate_text_google_oauth(model, &full_prompt).await {
-            Ok(ok) => Ok(ok),
-            Err(GeminiError::BadStatus { status, body })
-                if status == StatusCode::FORBIDDEN && oauth_scope_insufficient(&body) =>
-            {
-                generate_text_vertex(model, &full_prompt).await
+        GeminiBackend::GoogleAiOauth => {
+            match generate_text_google_oauth(model, &full_prompt, lend_text(&mut on_text)).await {
+                Ok(ok) => Ok(ok),
+                Err(GeminiError::BadStatus { status, body })
+                    if status == StatusCode::FORBIDDEN && oauth_scope_insufficient(&body) =>
+                {
+                    generate_text_vertex(model, &full_prompt, on_text).await
+                }
+                Err(e) => Err(e),
             }
-            Err(e) => Err(e),
-        },
-        GeminiBackend::VertexAi => generate_text_vertex(model, &full_prompt).await,
+        }
+        GeminiBackend::VertexAi => generate_text_vertex(model, &full_prompt, on_text).await,
     }
 }
 
@@ -640,17 +794,18 @@ pub async fn generate_tone_aistudio(
     api_key: &str,
     model: &str,
     req: ToneRequest,
+    mut progress: ProgressSink<'_>,
 ) -> Result<ToneResponse, GeminiError> {
     let client = reqwest::Client::builder()
         .timeout(http_timeout_for_model(model))
         .build()?;
 
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-        model, api_key
+    let url = generate_url(
+        &format!("https://generativelanguage.googleapis.com/v1beta/models/{model}"),
+        progress.is_some(),
     );
 
-    let full_prompt = format!("{SYSTEM_PROMPT}\n\nUSER:\n{}", req.user_prompt);
+    let full_prompt = format!("{SYSTEM_PROMPT}\n\nUSER:\n{}", req.model_prompt());
 
     let payload_with_schema = json!({
         "contents": [
@@ -672,6 +827,17 @@ pub async fn generate_tone_aistudio(
                             },
                             "required": ["index", "value"]
                         }
+                    },
+                    "alternatives": {
+                        "type": "ARRAY",
+                        "items": {
+                            "type": "OBJECT",
+                            "properties": {
+                                "index": { "type": "INTEGER" },
+                                "labels": { "type": "ARRAY", "items": { "type": "STRING" } }
+                            },
+                            "required": ["index", "labels"]
+                        }
                     }
                 },
                 "required": ["reasoning", "params"]
@@ -692,6 +858,7 @@ pub async fn generate_tone_aistudio(
     for attempt in 1..=3 {
         let resp = client
             .post(&url)
+            .query(&[("key", api_key)])
             .json(if attempt == 1 {
                 &payload_with_schema
             } else {
@@ -700,8 +867,8 @@ pub async fn generate_tone_aistudio(
             .send()
             .await?;
         if resp.status().is_success() {
-            let body = resp.text().await?;
-            return parse_tone_response(&body, &req.user_prompt).map_err(GeminiError::Parse);
+            return tone_from_response(resp, GEMINI_TEXT, &req.meta, lend_progress(&mut progress))
+                .await;
         }
 
         let status = resp.status();
@@ -741,14 +908,15 @@ async fn generate_text_aistudio(
     api_key: &str,
     model: &str,
     full_prompt: &str,
+    mut on_text: TextSink<'_>,
 ) -> Result<String, GeminiError> {
     let client = reqwest::Client::builder()
         .timeout(http_timeout_for_model(model))
         .build()?;
 
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-        model, api_key
+    let url = generate_url(
+        &format!("https://generativelanguage.googleapis.com/v1beta/models/{model}"),
+        on_text.is_some(),
     );
 
     let payload = json!({
@@ -759,10 +927,14 @@ async fn generate_text_aistudio(
 
     let mut backoff = Duration::from_millis(500);
     for attempt in 1..=3 {
-        let resp = client.post(&url).json(&payload).send().await?;
+        let resp = client
+            .post(&url)
+            .query(&[("key", api_key)])
+            .json(&payload)
+            .send()
+            .await?;
         if resp.status().is_success() {
-            let body = resp.text().await?;
-            return extract_candidate_text(&body).map_err(GeminiError::Parse);
+            return response_text(resp, GEMINI_TEXT, lend_text(&mut on_text)).await;
         }
 
         let status = resp.status();
@@ -792,6 +964,7 @@ async fn generate_text_aistudio(
 async fn generate_tone_google_oauth(
     model: &str,
     req: ToneRequest,
+    mut progress: ProgressSink<'_>,
 ) -> Result<ToneResponse, GeminiError> {
     let access_token = std::env::var("GEMINI_ACCESS_TOKEN")
         .or_else(|_| std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN"))
@@ -807,12 +980,12 @@ async fn generate_tone_google_oauth(
         .timeout(http_timeout_for_model(model))
         .build()?;
 
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
-        model
+    let url = generate_url(
+        &format!("https://generativelanguage.googleapis.com/v1beta/models/{model}"),
+        progress.is_some(),
     );
 
-    let full_prompt = format!("{SYSTEM_PROMPT}\n\nUSER:\n{}", req.user_prompt);
+    let full_prompt = format!("{SYSTEM_PROMPT}\n\nUSER:\n{}", req.model_prompt());
 
     let payload_with_schema = json!({
         "contents": [
@@ -834,6 +1007,17 @@ async fn generate_tone_google_oauth(
                             },
                             "required": ["index", "value"]
                         }
+                    },
+                    "alternatives": {
+                        "type": "ARRAY",
+                        "items": {
+                            "type": "OBJECT",
+                            "properties": {
+                                "index": { "type": "INTEGER" },
+                                "labels": { "type": "ARRAY", "items": { "type": "STRING" } }
+                            },
+                            "required": ["index", "labels"]
+                        }
                     }
                 },
                 "required": ["reasoning", "params"]
@@ -860,8 +1044,8 @@ async fn generate_tone_google_oauth(
             .send()
             .await?;
         if resp.status().is_success() {
-            let body = resp.text().await?;
-            return parse_tone_response(&body, &req.user_prompt).map_err(GeminiError::Parse);
+            return tone_from_response(resp, GEMINI_TEXT, &req.meta, lend_progress(&mut progress))
+                .await;
         }
 
         let status = resp.status();
@@ -886,7 +1070,11 @@ async fn generate_tone_google_oauth(
     Err(GeminiError::Parse("exhausted retries".to_string()))
 }
 
-async fn generate_text_google_oauth(model: &str, full_prompt: &str) -> Result<String, GeminiError> {
+async fn generate_text_google_oauth(
+    model: &str,
+    full_prompt: &str,
+    mut on_text: TextSink<'_>,
+) -> Result<String, GeminiError> {
     let access_token = std::env::var("GEMINI_ACCESS_TOKEN")
         .or_else(|_| std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN"))
         .unwrap_or_else(|_| String::new());
@@ -901,9 +1089,9 @@ async fn generate_text_google_oauth(model: &str, full_prompt: &str) -> Result<St
         .timeout(http_timeout_for_model(model))
         .build()?;
 
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
-        model
+    let url = generate_url(
+        &format!("https://generativelanguage.googleapis.com/v1beta/models/{model}"),
+        on_text.is_some(),
     );
 
     let payload = json!({
@@ -921,8 +1109,7 @@ async fn generate_text_google_oauth(model: &str, full_prompt: &str) -> Result<St
             .send()
             .await?;
         if resp.status().is_success() {
-            let body = resp.text().await?;
-            return extract_candidate_text(&body).map_err(GeminiError::Parse);
+            return response_text(resp, GEMINI_TEXT, lend_text(&mut on_text)).await;
         }
 
         let status = resp.status();
@@ -938,7 +1125,11 @@ async fn generate_text_google_oauth(model: &str, full_prompt: &str) -> Result<St
     Err(GeminiError::Parse("exhausted retries".to_string()))
 }
 
-async fn generate_tone_vertex(model: &str, req: ToneRequest) -> Result<ToneResponse, GeminiError> {
+async fn generate_tone_vertex(
+    model: &str,
+    req: ToneRequest,
+    mut progress: ProgressSink<'_>,
+) -> Result<ToneResponse, GeminiError> {
     let project = std::env::var("VERTEX_PROJECT")
         .or_else(|_| std::env::var("GOOGLE_CLOUD_PROJECT"))
         .or_else(|_| std::env::var("GCLOUD_PROJECT"))
@@ -1010,7 +1201,7 @@ async fn generate_tone_vertex(model: &str, req: ToneRequest) -> Result<ToneRespo
         .timeout(http_timeout_for_model(model))
         .build()?;
 
-    let full_prompt = format!("{SYSTEM_PROMPT}\n\nUSER:\n{}", req.user_prompt);
+    let full_prompt = format!("{SYSTEM_PROMPT}\n\nUSER:\n{}", req.model_prompt());
 
     let payload_with_schema = json!({
         "contents": [
@@ -1032,6 +1223,17 @@ async fn generate_tone_vertex(model: &str, req: ToneRequest) -> Result<ToneRespo
                             },
                             "required": ["index", "value"]
                         }
+                    },
+                    "alternatives": {
+                        "type": "ARRAY",
+                        "items": {
+                            "type": "OBJECT",
+                            "properties": {
+                                "index": { "type": "INTEGER" },
+                                "labels": { "type": "ARRAY", "items": { "type": "STRING" } }
+                            },
+                            "required": ["index", "labels"]
+                        }
                     }
                 },
                 "required": ["reasoning", "params"]
@@ -1045,15 +1247,19 @@ async fn generate_tone_vertex(model: &str, req: ToneRequest) -> Result<ToneRespo
         ]
     });
 
+    let streaming = progress.is_some();
     let models_to_try = vertex_model_candidates(model);
     let mut last_err: Option<GeminiError> = None;
 
     for candidate_model in models_to_try {
-        let url = format!(
-            "https://{loc}-aiplatform.googleapis.com/v1/projects/{proj}/locations/{loc}/publishers/google/models/{model}:generateContent",
-            loc = location,
-            proj = project,
-            model = candidate_model
+        let url = generate_url(
+            &format!(
+                "https://{loc}-aiplatform.googleapis.com/v1/projects/{proj}/locations/{loc}/publishers/google/models/{model}",
+                loc = location,
+                proj = project,
+                model = candidate_model
+            ),
+            streaming,
         );
 
         let mut backoff = Duration::from_millis(500);
@@ -1070,8 +1276,13 @@ async fn generate_tone_vertex(model: &str, req: ToneRequest) -> Result<ToneRespo
                 .await?;
 
             if resp.status().is_success() {
-                let body = resp.text().await?;
-                return parse_tone_response(&body, &req.user_prompt).map_err(GeminiError::Parse);
+                return tone_from_response(
+                    resp,
+                    GEMINI_TEXT,
+                    &req.meta,
+                    lend_progress(&mut progress),
+                )
+                .await;
             }
 
             let status = resp.status();
@@ -1111,7 +1322,11 @@ async fn generate_tone_vertex(model: &str, req: ToneRequest) -> Result<ToneRespo
     }))
 }
 
-async fn generate_text_vertex(model: &str, full_prompt: &str) -> Result<String, GeminiError> {
+async fn generate_text_vertex(
+    model: &str,
+    full_prompt: &str,
+    mut on_text: TextSink<'_>,
+) -> Result<String, GeminiError> {
     let project = std::env::var("VERTEX_PROJECT")
         .or_else(|_| std::env::var("GOOGLE_CLOUD_PROJECT"))
         .or_else(|_| std::env::var("GCLOUD_PROJECT"))
@@ -1145,15 +1360,19 @@ async fn generate_text_vertex(model: &str, full_prompt: &str) -> Result<String,
         ]
     });
 
+    let streaming = on_text.is_some();
     let models_to_try = vertex_model_candidates(model);
     let mut last_err: Option<GeminiError> = None;
 
     for candidate_model in models_to_try {
-        let url = format!(
-            "https://{loc}-aiplatform.googleapis.com/v1/projects/{proj}/locations/{loc}/publishers/google/models/{model}:generateContent",
-            loc = location,
-            proj = project,
-            model = candidate_model
+        let url = generate_url(
+            &format!(
+                "https://{loc}-aiplatform.googleapis.com/v1/projects/{proj}/locations/{loc}/publishers/google/models/{model}",
+                loc = location,
+                proj = project,
+                model = candidate_model
+            ),
+            streaming,
         );
 
         let mut backoff = Duration::from_millis(500);
@@ -1166,8 +1385,7 @@ async fn generate_text_vertex(model: &str, full_prompt: &str) -> Result<String,
                 .await?;
 
             if resp.status().is_success() {
-                let body = resp.text().await?;
-                return extract_candidate_text(&body).map_err(GeminiError::Parse);
+                return response_text(resp, GEMINI_TEXT, lend_text(&mut on_text)).await;
             }
 
             let status = resp.status();
@@ -1262,11 +1480,238 @@ fn vertex_model_candidates(model: &str) -> Vec<String> {
     ]
 }
 
-fn parse_tone_response(body: &str, original_prompt: &str) -> Result<ToneResponse, String> {
-    let text = extract_candidate_text(body)?;
+/// The endpoint for `model_url`: `streamGenerateContent` as server-sent events when the caller
+/// wants deltas, plain `generateContent` otherwise.
+fn generate_url(model_url: &str, streaming: bool) -> String {
+    if streaming {
+        format!("{model_url}:streamGenerateContent?alt=sse")
+    } else {
+        format!("{model_url}:generateContent")
+    }
+}
+
+/// How a provider's answer text is read out of a response: from the whole body of an unstreamed
+/// one, or from the data of one server-sent event of a streamed one.
+#[derive(Clone, Copy)]
+pub(crate) struct TextFormat {
+    pub(crate) body: fn(&str) -> Result<String, String>,
+    pub(crate) event: fn(&str) -> Result<String, String>,
+}
+
+const GEMINI_TEXT: TextFormat = TextFormat {
+    body: extract_candidate_text,
+    event: extract_candidate_text,
+};
+
+/// The answer text of a successful response. With a sink the response is an SSE stream whose
+/// `data:` events each carry the next piece of text; each piece goes to the sink as it arrives.
+pub(crate) async fn response_text(
+    mut resp: reqwest::Response,
+    format: TextFormat,
+    on_text: TextSink<'_>,
+) -> Result<String, GeminiError> {
+    let Some(on_text) = on_text else {
+        let body = resp.text().await?;
+        return (format.body)(&body).map_err(GeminiError::Parse);
+    };
+
+    let mut text = String::new();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut deliver = |line: &[u8], text: &mut String| {
+        let line = String::from_utf8_lossy(line);
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return;
+        };
+        // Keep-alives and events without text (e.g. the final usage report) carry nothing.
+        if let Ok(delta) = (format.event)(data.trim()) {
+            on_text(&delta);
+            text.push_str(&delta);
+        }
+    };
+    while let Some(chunk) = resp.chunk().await? {
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            deliver(&line, &mut text);
+        }
+    }
+    deliver(&pending, &mut text);
+
+    if text.is_empty() {
+        return Err(GeminiError::Parse(
+            "stream ended without any answer text".to_string(),
+        ));
+    }
+    Ok(text)
+}
+
+/// The tone in a successful response, reporting reasoning and params to `progress` as the
+/// stream writes them when there is a sink.
+pub(crate) async fn tone_from_response(
+    resp: reqwest::Response,
+    format: TextFormat,
+    meta: &ParamMeta,
+    progress: ProgressSink<'_>,
+) -> Result<ToneResponse, GeminiError> {
+    let Some(progress) = progress else {
+        let body = resp.text().await?;
+        let text = (format.body)(&body).map_err(GeminiError::Parse)?;
+        return parse_tone_text(&text, &body, meta).map_err(GeminiError::Parse);
+    };
+
+    let mut stream = ToneStream::new(meta);
+    let on_text: &mut (dyn FnMut(&str) + Send) = &mut |delta: &str| {
+        for event in stream.push(delta) {
+            progress(event);
+        }
+    };
+    let text = response_text(resp, format, Some(on_text)).await?;
+    parse_tone_text(&text, &text, meta).map_err(GeminiError::Parse)
+}
+
+/// Follows the tone JSON while it is being written, turning what has become readable since the
+/// last delta into progress: the new part of `reasoning`, and the `params` list whenever another
+/// entry closes.
+struct ToneStream<'a> {
+    meta: &'a ParamMeta,
+    text: String,
+    reasoning_sent: usize,
+    params_sent: usize,
+}
+
+impl<'a> ToneStream<'a> {
+    fn new(meta: &'a ParamMeta) -> Self {
+        Self {
+            meta,
+            text: String::new(),
+            reasoning_sent: 0,
+            params_sent: 0,
+        }
+    }
+
+    fn push(&mut self, delta: &str) -> Vec<ToneProgress> {
+        self.text.push_str(delta);
+        let mut events = Vec::new();
 
+        if let Some(reasoning) = partial_string_field(&self.text, "reasoning") {
+            if let Some(new) = reasoning
+                .get(self.reasoning_sent..)
+                .filter(|n| !n.is_empty())
+            {
+                events.push(ToneProgress::Reasoning {
+                    text: new.to_string(),
+                });
+                self.reasoning_sent = reasoning.len();
+            }
+        }
+
+        let entries = closed_array_objects(&self.text, "params");
+        if entries.len() > self.params_sent {
+            let ai_params: Vec<AiParamChange> = entries
+                .iter()
+                .filter_map(|raw| serde_json::from_str(raw).ok())
+                .collect();
+            // An entry the resolver refuses fails the final answer too; until then show nothing
+            // rather than a list that is about to be rejected.
+            if let Ok(params) = resolve_ai_params(self.meta, ai_params) {
+                events.push(ToneProgress::Params { params });
+            }
+            self.params_sent = entries.len();
+        }
+
+        events
+    }
+}
+
+/// The decoded value of the string field `key` in partly written JSON, up to the last character
+/// that is complete; `None` until its opening quote has arrived.
+fn partial_string_field(text: &str, key: &str) -> Option<String> {
+    let after_key = &text[text.find(&format!("\"{key}\""))? + key.len() + 2..];
+    let after_colon = after_key.trim_start().strip_prefix(':')?.trim_start();
+    let mut chars = after_colon.strip_prefix('"')?.chars();
+
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => {
+                let decoded = match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        match u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .filter(|_| hex.len() == 4)
+                        {
+                            Some(code) => char::from_u32(code).unwrap_or('\u{fffd}'),
+                            None => break,
+                        }
+                    }
+                    Some(other) => other,
+                    None => break,
+                };
+                out.push(decoded);
+            }
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// The objects of the array field `key` in partly written JSON whose closing brace has arrived.
+fn closed_array_objects<'t>(text: &'t str, key: &str) -> Vec<&'t str> {
+    let Some(key_at) = text.find(&format!("\"{key}\"")) else {
+        return Vec::new();
+    };
+    let rest = &text[key_at + key.len() + 2..];
+    let Some(open) = rest.find('[') else {
+        return Vec::new();
+    };
+    let base = key_at + key.len() + 2 + open + 1;
+
+    let mut objects = Vec::new();
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    let mut start = 0;
+    for (i, c) in text[base..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                if depth == 0 {
+                    start = base + i;
+                }
+                depth += 1;
+            }
+            '}' | ']' if depth == 0 => break,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 && c == '}' {
+                    objects.push(&text[start..=base + i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    objects
+}
+
+/// The tone in the model's answer `text`; `fallback` is tried as the JSON itself when `text`
+/// doesn't hold it.
+fn parse_tone_text(text: &str, fallback: &str, meta: &ParamMeta) -> Result<ToneResponse, String> {
     // If Gemini respects structured output, `text` should be valid JSON.
-    let extracted = extract_json_like(&text).unwrap_or(text.as_str());
+    let extracted = extract_json_like(text).unwrap_or(text);
 
     if let Ok(path) = std::env::var("DUMP_AI_JSON_PATH") {
         let path = path.trim();
@@ -1283,15 +1728,17 @@ fn parse_tone_response(body: &str, original_prompt: &str) -> Result<ToneResponse
     }
 
     let parsed = serde_json::from_str::<AiToneResponse>(extracted)
-        .or_else(|_| serde_json::from_str::<AiToneResponse>(body))
+        .or_else(|_| serde_json::from_str::<AiToneResponse>(fallback))
         .map_err(|e| format!("{e}: {extracted}"))?;
 
-    let resolved = resolve_ai_params(original_prompt, parsed.params)
+    let resolved = resolve_ai_params(meta, parsed.params)
         .map_err(|e| e.to_string())?;
+    let alternatives = rank_alternatives(meta, &resolved, &parsed.alternatives);
 
     Ok(ToneResponse {
         reasoning: parsed.reasoning,
         params: resolved,
+        alternatives,
     })
 }
 
@@ -1348,3 +1795,65 @@ fn extract_json_like(text: &str) -> Option<&str> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_reasoning_decodes_what_has_arrived() {
+        let text = r#"{"reasoning": "Tight \"djent\" gate\nthen"#;
+        assert_eq!(
+            partial_string_field(text, "reasoning").as_deref(),
+            Some("Tight \"djent\" gate\nthen")
+        );
+        // A cut escape waits for the rest of it.
+        assert_eq!(
+            partial_string_field(r#"{"reasoning": "a\u00"#, "reasoning").as_deref(),
+            Some("a")
+        );
+        assert_eq!(partial_string_field(r#"{"reasoning""#, "reasoning"), None);
+    }
+
+    #[test]
+    fn only_closed_param_entries_are_read() {
+        let text = r#"{"reasoning": "x", "params": [{"index": 2, "value": "{-30 dB}"}, {"index": 108, "val"#;
+        assert_eq!(
+            closed_array_objects(text, "params"),
+            vec![r#"{"index": 2, "value": "{-30 dB}"}"#]
+        );
+        assert!(closed_array_objects(r#"{"params": ["#, "params").is_empty());
+    }
+
+    #[test]
+    fn tone_stream_reports_reasoning_deltas_and_growing_params() {
+        let meta = ParamMeta::default();
+        let mut stream = ToneStream::new(&meta);
+        let mut events = Vec::new();
+        for delta in [
+            r#"{"reasoning": "Scoop "#,
+            r#"the mids.", "params": [{"index": 2, "#,
+            r#""value": "-30 dB"}, {"index": 108, "value": "120 bpm"}"#,
+            "]}",
+        ] {
+            events.extend(stream.push(delta));
+        }
+
+        let reasoning: String = events
+            .iter()
+            .filter_map(|e| match e {
+                ToneProgress::Reasoning { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reasoning, "Scoop the mids.");
+        let params: Vec<usize> = events
+            .iter()
+            .filter_map(|e| match e {
+                ToneProgress::Params { params } => Some(params.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(params, vec![2]);
+    }
+}
@@ -1,7 +1,13 @@
+pub mod anthropic;
 pub mod cleaner;
+pub mod few_shot;
 pub mod gemini;
+pub mod openai;
 pub mod param_map;
+pub mod param_meta;
 pub mod protocol;
+pub mod research_cache;
+pub mod sidecar;
 pub mod system_prompt;
+pub mod tone_model;
 pub mod value_resolver;
-
@@ -0,0 +1,180 @@
+//! Tone generation over OpenAI's chat completions API, which OpenAI, most hosted inference
+//! services and the local servers (llama.cpp, Ollama, vLLM) all speak.
+
+use crate::modules::gemini::{
+    lend_progress, lend_text, research_prompt, response_text, tone_from_response, GeminiError,
+    ProgressSink, TextFormat, TextSink, ToneRequest, ToneResponse,
+};
+use crate::modules::system_prompt::SYSTEM_PROMPT;
+use crate::modules::tone_model::{http_timeout, ModelFuture, ToneModel};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const OPENAI_TEXT: TextFormat = TextFormat {
+    body: extract_message_text,
+    event: extract_delta_text,
+};
+
+/// A model behind an OpenAI-style `/chat/completions` endpoint at `base_url`.
+pub struct OpenAiModel {
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    /// Hosted services refuse keyless requests; local servers usually take them.
+    pub key_required: bool,
+}
+
+impl ToneModel for OpenAiModel {
+    fn research<'a>(
+        &'a self,
+        user_prompt: &'a str,
+        mut on_text: TextSink<'a>,
+    ) -> ModelFuture<'a, String> {
+        Box::pin(async move {
+            let messages = json!([{ "role": "user", "content": research_prompt(user_prompt) }]);
+            let resp = self.send(messages, false, on_text.is_some()).await?;
+            response_text(resp, OPENAI_TEXT, lend_text(&mut on_text)).await
+        })
+    }
+
+    fn tone<'a>(
+        &'a self,
+        req: ToneRequest,
+        mut progress: ProgressSink<'a>,
+    ) -> ModelFuture<'a, ToneResponse> {
+        Box::pin(async move {
+            let messages = json!([
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": req.model_prompt() }
+            ]);
+            let resp = self.send(messages, true, progress.is_some()).await?;
+            tone_from_response(resp, OPENAI_TEXT, &req.meta, lend_progress(&mut progress)).await
+        })
+    }
+}
+
+impl OpenAiModel {
+    /// Posts `messages` and returns the successful response, retrying rate limits and server
+    /// errors. `json_mode` asks for a JSON object, dropped again if the server doesn't know it.
+    async fn send(
+        &self,
+        messages: Value,
+        json_mode: bool,
+        stream: bool,
+    ) -> Result<reqwest::Response, GeminiError> {
+        if self.key_required && self.api_key.is_none() {
+            return Err(GeminiError::Auth(format!(
+                "missing API key for {}",
+                self.base_url
+            )));
+        }
+        let client = reqwest::Client::builder().timeout(http_timeout()).build()?;
+        let url = format!("{}/chat/completions", self.base_url);
+
+        let mut payload = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": stream,
+        });
+        if json_mode {
+            payload["response_format"] = json!({ "type": "json_object" });
+        }
+
+        let mut backoff = Duration::from_millis(500);
+        for attempt in 1..=3 {
+            let mut request = client.post(&url).json(&payload);
+            if let Some(key) = self.api_key.as_deref() {
+                request = request.bearer_auth(key);
+            }
+            let resp = request.send().await?;
+            if resp.status().is_success() {
+                return Ok(resp);
+            }
+
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+
+            // Servers without JSON mode reject the field; the system prompt asks for JSON anyway.
+            if payload.get("response_format").is_some()
+                && matches!(
+                    status,
+                    StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY
+                )
+                && body.to_ascii_lowercase().contains("response_format")
+            {
+                if let Some(p) = payload.as_object_mut() {
+                    p.remove("response_format");
+                }
+                continue;
+            }
+
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !retryable || attempt == 3 {
+                return Err(GeminiError::BadStatus { status, body });
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_secs(5));
+        }
+
+        Err(GeminiError::Parse("exhausted retries".to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct Completion {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Option<Message>,
+    delta: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: Option<String>,
+}
+
+fn extract_message_text(body: &str) -> Result<String, String> {
+    let completion: Completion = serde_json::from_str(body).map_err(|e| format!("{e}: {body}"))?;
+    completion
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message)
+        .and_then(|m| m.content)
+        .ok_or_else(|| format!("missing choices.message.content: {body}"))
+}
+
+/// The text of one streamed chunk; the role-only first chunk and `[DONE]` have none.
+fn extract_delta_text(data: &str) -> Result<String, String> {
+    let completion: Completion = serde_json::from_str(data).map_err(|e| format!("{e}: {data}"))?;
+    completion
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.delta)
+        .and_then(|m| m.content)
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| format!("missing choices.delta.content: {data}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_whole_and_streamed_completions() {
+        let body = r#"{"id":"c","choices":[{"index":0,"message":{"role":"assistant","content":"{\"reasoning\":\"r\"}"}}]}"#;
+        assert_eq!(extract_message_text(body).unwrap(), r#"{"reasoning":"r"}"#);
+
+        let first = r#"{"choices":[{"index":0,"delta":{"role":"assistant","content":""}}]}"#;
+        let next = r#"{"choices":[{"index":0,"delta":{"content":"{\"reas"}}]}"#;
+        assert!(extract_delta_text(first).is_err());
+        assert_eq!(extract_delta_text(next).unwrap(), r#"{"reas"#);
+        assert!(extract_delta_text("[DONE]").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::modules::protocol::{
+    ParamEnumOption, ParamFormatSample, ParamFormatTriplet, ParamKind, ParamSchemaEntry,
+    ProjectTempo,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Plugin parameter metadata probed by the DLL and delivered in the handshake.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParamMeta {
+    pub enums: HashMap<i32, Vec<ParamEnumOption>>,
+    pub formats: HashMap<i32, ParamFormatTriplet>,
+    pub samples: HashMap<i32, Vec<ParamFormatSample>>,
+    /// Graphic-EQ band index -> center frequency (Hz).
+    pub eq_band_hz: HashMap<i32, f32>,
+    /// Toggle / stepped / continuous per index, as classified by the DLL.
+    #[serde(default)]
+    pub kinds: HashMap<i32, ParamKind>,
+    /// Name, kind and section per actual index, for labelling params.
+    #[serde(default)]
+    pub schema: HashMap<i32, ParamSchemaEntry>,
+    /// Current REAPER project tempo, used to sync the delay.
+    pub tempo: Option<ProjectTempo>,
+}
+
+impl ParamMeta {
+    pub fn is_empty(&self) -> bool {
+        self.enums.is_empty()
+            && self.formats.is_empty()
+            && self.samples.is_empty()
+            && self.eq_band_hz.is_empty()
+            && self.kinds.is_empty()
+            && self.tempo.is_none()
+    }
+
+    /// Compact, model-facing rendering of the metadata that gets appended to the user prompt.
+    /// The resolver never reads this back; it uses the structured fields directly.
+    pub fn prompt_block(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        // Keep this compact; model gets the full list but in a machine-friendly shape.
+        let mut meta = String::new();
+        meta.push_str("\n\nPLUGIN PARAM META (from the current REAPER instance):\n");
+
+        // Only include the relevant cab/IR + a couple of mode selectors by default.
+        let include_enum = [84, 92, 99, 113, 5];
+        let mut enum_obj: HashMap<i32, Vec<(f64, String)>> = HashMap::new();
+        for idx in include_enum {
+            if let Some(opts) = self.enums.get(&idx) {
+                let mapped: Vec<(f64, String)> = opts
+                    .iter()
+                    .map(|o| (o.value, o.label.clone()))
+                    .collect();
+                enum_obj.insert(idx, mapped);
+            }
+        }
+
+        let mut include_fmt: Vec<i32> = Vec::new();
+        include_fmt.extend([0, 1, 2]); // input/output gain + gate
+        include_fmt.extend(30..=51); // amp knobs
+        include_fmt.extend(54..=82); // graphic EQ bands
+        include_fmt.extend([87, 88, 89, 94, 95, 96]); // cab mic position/distance/level
+        include_fmt.extend([105, 106, 108]); // delay
+        include_fmt.extend([114, 115, 116, 117]); // reverb
+        include_fmt.sort_unstable();
+        include_fmt.dedup();
+
+        let mut fmt_obj: HashMap<i32, (String, String, String)> = HashMap::new();
+        for idx in include_fmt {
+            if let Some(t) = self.formats.get(&idx) {
+                fmt_obj.insert(idx, (t.min.clone(), t.mid.clone(), t.max.clone()));
+            }
+        }
+
+        // JSON keeps token count lower than prose for large IR lists.
+        if !enum_obj.is_empty() {
+            if let Ok(j) = serde_json::to_string(&enum_obj) {
+                meta.push_str("ENUM_OPTIONS_JSON=");
+                meta.push_str(&j);
+                meta.push('\n');
+            }
+        }
+        if !fmt_obj.is_empty() {
+            if let Ok(j) = serde_json::to_string(&fmt_obj) {
+                meta.push_str("FORMATTED_VALUE_TRIPLETS_JSON=");
+                meta.push_str(&j);
+                meta.push('\n');
+            }
+        }
+
+        // Formatted samples (norm->formatted) tell the model which human units are available.
+        // Keep this limited to the most tone-relevant parameters to avoid prompt bloat.
+        if !self.samples.is_empty() {
+            let mut sample_obj: HashMap<i32, Vec<(f64, String)>> = HashMap::new();
+            let mut include: Vec<i32> = Vec::new();
+            include.extend([0, 1]); // input/output gain
+            include.push(2); // Gate
+            include.extend(54..=82); // Graphic EQ bands
+            include.extend([29, 30, 31, 32, 33, 34, 35]); // Clean amp
+            include.extend(36..=43); // Rust amp
+            include.extend(44..=51); // Hot amp
+            include.extend([101, 105, 106, 108, 112, 113, 114, 115, 116, 117]); // Time FX
+            include.extend([83, 84, 85, 87, 88, 89, 92, 94, 95, 96, 99]); // Cab selectors (+ mic pos/dist/levels)
+            include.sort_unstable();
+            include.dedup();
+
+            for idx in include {
+                if let Some(v) = self.samples.get(&idx) {
+                    let mapped: Vec<(f64, String)> = v
+                        .iter()
+                        .map(|s| (s.norm, s.formatted.clone()))
+                        .collect();
+                    if !mapped.is_empty() {
+                        sample_obj.insert(idx, mapped);
+                    }
+                }
+            }
+
+            if !sample_obj.is_empty() {
+                if let Ok(j) = serde_json::to_string(&sample_obj) {
+                    meta.push_str("PARAM_FORMAT_SAMPLES_JSON=");
+                    meta.push_str(&j);
+                    meta.push('\n');
+                }
+            }
+        }
+
+        if !self.eq_band_hz.is_empty() {
+            let band_obj: BTreeMap<i32, f32> =
+                self.eq_band_hz.iter().map(|(k, v)| (*k, *v)).collect();
+            if let Ok(j) = serde_json::to_string(&band_obj) {
+                meta.push_str("EQ_BAND_HZ_JSON=");
+                meta.push_str(&j);
+                meta.push('\n');
+            }
+            meta.push_str("EQ band values may target a frequency instead of a band, e.g. \"-3 dB @ 400 Hz\" on any band of the active EQ; the backend picks the nearest band.\n");
+        }
+
+        if let Some(t) = self.tempo {
+            meta.push_str(&format!(
+                "PROJECT_TEMPO: {:.2} bpm, {} beats per measure. For Delay Tempo (108) write \"project tempo\" to sync to it (or a bpm only when the user asks for a different tempo).\n",
+                t.bpm, t.beats_per_measure
+            ));
+        }
+
+        meta.push_str("Use these option labels when choosing Cab Type (84) and Mic IR (92/99). Set the parameter value close to the provided float for the desired label.\n");
+        meta.push_str("For continuous cab mic controls (Position/Distance), the formatted triplets can hint at units/direction; use them to pick sensible normalized values.\n");
+        meta.push_str("You may specify some values in human units (like dB) if PARAM_FORMAT_SAMPLES_JSON is present; the backend will translate them to 0..1.\n");
+
+        // Hard cap to prevent runaway prompts if IR lists are enormous.
+        const MAX_EXTRA_CHARS: usize = 25_000;
+        if meta.len() > MAX_EXTRA_CHARS {
+            let mut cut = MAX_EXTRA_CHARS;
+            while !meta.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            meta.truncate(cut);
+            meta.push_str("\n...(meta truncated)\n");
+        }
+
+        meta
+    }
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BANDS: &[&str] = &[
+    "gojira",
+    "meshuggah",
+    "metallica",
+    "slayer",
+    "megadeth",
+    "pantera",
+    "sepultura",
+    "mastodon",
+    "periphery",
+    "tesseract",
+    "animals as leaders",
+    "lamb of god",
+    "machine head",
+    "in flames",
+    "at the gates",
+    "opeth",
+    "tool",
+    "deftones",
+    "korn",
+    "slipknot",
+    "trivium",
+    "architects",
+    "killswitch engage",
+    "black sabbath",
+    "iron maiden",
+    "van halen",
+    "dream theater",
+    "rage against the machine",
+    "ac/dc",
+    "converge",
+    "cult of luna",
+    "isis",
+    "neurosis",
+];
+
+/// (prompt word, canonical style). Several words can map to the same style.
+const STYLES: &[(&str, &str)] = &[
+    ("rhythm", "rhythm"),
+    ("chug", "rhythm"),
+    ("riff", "rhythm"),
+    ("lead", "lead"),
+    ("solo", "lead"),
+    ("clean", "clean"),
+    ("crunch", "crunch"),
+    ("ambient", "ambient"),
+    ("djent", "djent"),
+    ("thrash", "thrash"),
+    ("doom", "doom"),
+    ("sludge", "sludge"),
+    ("death", "death"),
+    ("prog", "prog"),
+    ("progressive", "prog"),
+    ("nu", "nu"),
+    ("stoner", "stoner"),
+    ("blues", "blues"),
+    ("shoegaze", "shoegaze"),
+];
+
+const ERA_WORDS: &[(&str, &str)] = &[
+    ("sixties", "60s"),
+    ("seventies", "70s"),
+    ("eighties", "80s"),
+    ("nineties", "90s"),
+];
+
+/// Cache key for the style a prompt asks for: the band(s), era and style words it mentions,
+/// normalized and sorted. Everything else (instrument, tuning, "another ... but ...") is ignored,
+/// so "another Meshuggah-style rhythm tone but for 8-string" keys like "Meshuggah rhythm tone".
+/// Returns `None` when neither a band nor an era is named; style words alone are too generic to
+/// share research.
+pub fn style_key(prompt: &str) -> Option<String> {
+    let lower = prompt.to_ascii_lowercase().replace('\'', "");
+    let words: String = lower
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '/' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    let padded = format!(
+        " {} ",
+        words.split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+
+    let bands: BTreeSet<&str> = BANDS
+        .iter()
+        .copied()
+        .filter(|b| padded.contains(&format!(" {b} ")))
+        .collect();
+
+    let mut eras: BTreeSet<String> = BTreeSet::new();
+    for w in padded.split_whitespace() {
+        if let Some(era) = era_of(w) {
+            eras.insert(era);
+        }
+    }
+    for (word, era) in ERA_WORDS {
+        if padded.contains(&format!(" {word} ")) {
+            eras.insert((*era).to_string());
+        }
+    }
+
+    if bands.is_empty() && eras.is_empty() {
+        return None;
+    }
+
+    let styles: BTreeSet<&str> = STYLES
+        .iter()
+        .filter(|(word, _)| padded.contains(&format!(" {word} ")))
+        .map(|(_, style)| *style)
+        .collect();
+
+    Some(format!(
+        "band={};era={};style={}",
+        bands.into_iter().collect::<Vec<_>>().join(","),
+        eras.into_iter().collect::<Vec<_>>().join(","),
+        styles.into_iter().collect::<Vec<_>>().join(",")
+    ))
+}
+
+/// "80s" / "1980s" / "1987" -> "80s".
+fn era_of(word: &str) -> Option<String> {
+    let digits = word.strip_suffix('s').unwrap_or(word);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match (digits.len(), word.ends_with('s')) {
+        (2, true) if digits.ends_with('0') => Some(format!("{digits}s")),
+        (4, _) => {
+            let year: u32 = digits.parse().ok()?;
+            if !(1950..2100).contains(&year) {
+                return None;
+            }
+            Some(format!("{:02}s", (year % 100) / 10 * 10))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedBrief {
+    pub brief: String,
+    /// Unix seconds.
+    pub created_at: u64,
+}
+
+/// Stage-1 research briefs by `style_key`, optionally persisted as JSON. Unlike
+/// `ToneRequest::research_brief` (an explicit brief for one request), entries are looked up
+/// implicitly and expire after a TTL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResearchCache {
+    entries: HashMap<String, CachedBrief>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ResearchCache {
+    /// Loads the cache file, starting empty if it is missing or unreadable. `save` writes back
+    /// to the same path.
+    pub fn load(path: &Path) -> Self {
+        let mut cache: ResearchCache = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        cache.path = Some(path.to_path_buf());
+        cache
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let raw = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, raw)
+    }
+
+    pub fn get(&self, key: &str, ttl: Duration) -> Option<&str> {
+        let entry = self.entries.get(key)?;
+        if now_secs().saturating_sub(entry.created_at) >= ttl.as_secs() {
+            return None;
+        }
+        Some(entry.brief.as_str())
+    }
+
+    pub fn insert(&mut self, key: String, brief: String) {
+        self.entries.insert(
+            key,
+            CachedBrief {
+                brief,
+                created_at: now_secs(),
+            },
+        );
+    }
+
+    pub fn invalidate(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops expired entries so the file doesn't grow without bound.
+    pub fn prune(&mut self, ttl: Duration) {
+        let now = now_secs();
+        self.entries
+            .retain(|_, e| now.saturating_sub(e.created_at) < ttl.as_secs());
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// `TONE_RESEARCH_CACHE_TTL_SECS` (default: 7 days).
+pub fn cache_ttl() -> Duration {
+    let secs = std::env::var("TONE_RESEARCH_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(7 * 24 * 60 * 60);
+    Duration::from_secs(secs)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_key_ignores_instrument_details() {
+        let a = style_key("Meshuggah rhythm tone").unwrap();
+        let b = style_key("another Meshuggah-style rhythm tone but for 8-string").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(
+            style_key("1987 thrash rhythm").unwrap(),
+            style_key("80's thrash rhythm tone").unwrap()
+        );
+        assert!(style_key("warm clean tone with some chorus").is_none());
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let mut cache = ResearchCache::default();
+        cache.insert("band=gojira;era=;style=".to_string(), "brief".to_string());
+        assert_eq!(
+            cache.get("band=gojira;era=;style=", Duration::from_secs(60)),
+            Some("brief")
+        );
+        assert_eq!(cache.get("band=gojira;era=;style=", Duration::ZERO), None);
+    }
+}
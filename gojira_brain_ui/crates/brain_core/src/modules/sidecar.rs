@@ -0,0 +1,246 @@
+use crate::protocol::{Discovery, Encoding, SidecarConfig};
+use serde::Serialize;
+
+/// The sidecar config the DLL was started with, or defaults when there is no file. A file that
+/// can't be read or parsed is an error, so a typo doesn't silently point clients at the default.
+pub fn load_config() -> Result<SidecarConfig, String> {
+    let Some(path) = SidecarConfig::path() else {
+        return Ok(SidecarConfig::default());
+    };
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SidecarConfig::default()),
+        Err(e) => return Err(format!("sidecar config {}: {e}", path.display())),
+    };
+    serde_json::from_str(&raw).map_err(|e| format!("sidecar config {}: {e}", path.display()))
+}
+
+/// The port the DLL on this machine last said it listens on, which differs from the configured
+/// one when it had to fall back. `None` when it hasn't written one (or removed it on shutdown).
+pub fn discovery() -> Result<Option<Discovery>, String> {
+    let Some(path) = SidecarConfig::discovery_path() else {
+        return Ok(None);
+    };
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("discovery file {}: {e}", path.display())),
+    };
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| format!("discovery file {}: {e}", path.display()))
+}
+
+/// `ws://` URL of the DLL: `GOJIRA_WS_ADDR`, else the sidecar config file with the port from the
+/// discovery file, else the default.
+pub fn ws_url() -> Result<String, String> {
+    let mut config = load_config()?;
+    if let Some(found) = discovery()? {
+        config.port = Some(found.port);
+    }
+    Ok(config.ws_url())
+}
+
+/// Encoding to ask the DLL for in the websocket subprotocol; JSON unless the config says otherwise.
+pub fn encoding() -> Result<Encoding, String> {
+    Ok(load_config()?.encoding.unwrap_or_default())
+}
+
+/// Pairing key to present when connecting: `GOJIRA_PAIRING_KEY`, else the key file the DLL wrote.
+/// `None` when neither exists yet (the DLL has never run on this machine).
+pub fn pairing_key() -> Result<Option<String>, String> {
+    if let Some(key) = crate::protocol::env_pairing_key() {
+        return Ok(Some(key));
+    }
+    let Some(path) = SidecarConfig::pairing_key_path() else {
+        return Ok(None);
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(raw) => Ok(Some(raw.trim().to_string()).filter(|k| !k.is_empty())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("pairing key {}: {e}", path.display())),
+    }
+}
+
+/// A DLL found advertising itself on the LAN.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LanSidecar {
+    /// The machine's name, as the DLL announced it.
+    pub name: String,
+    /// `ws://` (or `wss://`) URL to connect to.
+    pub ws_url: String,
+}
+
+/// DLLs that answered an mDNS browse within `timeout`, sorted by name. Connecting still takes
+/// that machine's pairing key, and for `wss://` its certificate fingerprint in the config.
+#[cfg(feature = "mdns")]
+pub fn discover_lan(timeout: std::time::Duration) -> Result<Vec<LanSidecar>, String> {
+    use crate::protocol::{MDNS_SERVICE_TYPE, MDNS_TLS_PROPERTY};
+    use mdns_sd::{ServiceDaemon, ServiceEvent};
+
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mdns daemon: {e}"))?;
+    let events = daemon
+        .browse(MDNS_SERVICE_TYPE)
+        .map_err(|e| format!("mdns browse: {e}"))?;
+    let deadline = std::time::Instant::now() + timeout;
+    let mut found: Vec<LanSidecar> = Vec::new();
+    while let Ok(event) = events.recv_deadline(deadline) {
+        let ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        // IPv4 only: a link-local IPv6 address needs a scope id a URL can't carry.
+        let Some(ip) = info.get_addresses_v4().into_iter().min().copied() else {
+            continue;
+        };
+        let scheme = match info.get_property_val_str(MDNS_TLS_PROPERTY) {
+            Some("1") => "wss",
+            _ => "ws",
+        };
+        let ws_url = format!("{scheme}://{ip}:{}", info.get_port());
+        if found.iter().any(|s| s.ws_url == ws_url) {
+            continue;
+        }
+        let name = info
+            .get_fullname()
+            .trim_end_matches(MDNS_SERVICE_TYPE)
+            .trim_end_matches('.')
+            .to_string();
+        found.push(LanSidecar { name, ws_url });
+    }
+    let _ = daemon.stop_browse(MDNS_SERVICE_TYPE);
+    let _ = daemon.shutdown();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(found)
+}
+
+/// rustls client config that trusts only the DLL's self-signed certificate: the fingerprint
+/// pinned in the sidecar config, else the certificate file the DLL wrote on this machine.
+#[cfg(feature = "tls")]
+pub fn tls_client_config(
+    config: &SidecarConfig,
+) -> Result<std::sync::Arc<rustls::ClientConfig>, String> {
+    tls::client_config(config)
+}
+
+#[cfg(feature = "tls")]
+mod tls {
+    use crate::protocol::SidecarConfig;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::CryptoProvider;
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+    use sha2::{Digest, Sha256};
+    use std::sync::Arc;
+
+    pub fn client_config(config: &SidecarConfig) -> Result<Arc<ClientConfig>, String> {
+        let pinned = match config.tls_fingerprint.as_deref() {
+            Some(fp) => normalize(fp),
+            None => local_fingerprint()?,
+        };
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = PinnedCert {
+            fingerprint: pinned,
+            provider: Arc::clone(&provider),
+        };
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("tls config: {e}"))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        Ok(Arc::new(config))
+    }
+
+    /// Fingerprint of the certificate the DLL generated, when it runs on this machine.
+    fn local_fingerprint() -> Result<String, String> {
+        let path = SidecarConfig::tls_cert_path()
+            .ok_or_else(|| "no config directory to find the DLL's certificate in".to_string())?;
+        let cert = CertificateDer::from_pem_file(&path).map_err(|e| {
+            format!(
+                "tls cert {}: {e}; set tls_fingerprint in the sidecar config to the one the DLL logs",
+                path.display()
+            )
+        })?;
+        Ok(fingerprint(&cert))
+    }
+
+    fn fingerprint(cert: &[u8]) -> String {
+        Sha256::digest(cert)
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect()
+    }
+
+    /// Hex digits only, upper-cased, so "ab:12" and "AB12" pin the same certificate.
+    fn normalize(fingerprint: &str) -> String {
+        fingerprint
+            .chars()
+            .filter(|c| c.is_ascii_hexdigit())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+
+    /// Accepts exactly one certificate, whatever name it was issued for: the DLL is reached by
+    /// LAN address, which a self-signed certificate can't anticipate.
+    #[derive(Debug)]
+    struct PinnedCert {
+        fingerprint: String,
+        provider: Arc<CryptoProvider>,
+    }
+
+    impl ServerCertVerifier for PinnedCert {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            let presented = fingerprint(end_entity);
+            if presented == self.fingerprint {
+                Ok(ServerCertVerified::assertion())
+            } else {
+                Err(rustls::Error::General(format!(
+                    "DLL certificate fingerprint {presented} is not the pinned {}",
+                    self.fingerprint
+                )))
+            }
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.provider
+                .signature_verification_algorithms
+                .supported_schemes()
+        }
+    }
+}
@@ -48,7 +48,7 @@ GRAPHIC EQ (backend converts dB -> 0..1):
 - Rust EQ: Active (63), Bands 1..9 (64..72)
 - Hot EQ: Active (73), Bands 1..9 (74..82)
 When Amp Type is Clean, prefer changing ONLY Clean amp + Clean EQ. When Rust, ONLY Rust amp + Rust EQ. When Hot, ONLY Hot amp + Hot EQ.
-The band->Hz mapping is unknown here, so describe EQ moves in terms of band numbers plus musical regions (low end / low-mids / high-mids / presence), e.g. "RUST EQ Band 3 (low-mids)". If you need to describe a frequency area, use these regions rather than exact Hz/kHz values.
+If EQ_BAND_HZ_JSON is present, it gives each band's center frequency: pick bands from it, and you may write a band value as "-3 dB @ 400 Hz" to let the backend choose the nearest band of that EQ. Without it, describe EQ moves in terms of band numbers plus musical regions (low end / low-mids / high-mids / presence), e.g. "RUST EQ Band 3 (low-mids)", rather than exact Hz/kHz values.
 For band values (54..82), prefer writing dB strings like "+3.2 dB" or "-2 dB" (0 dB = flat).
 
 VALUE ENTRY (IMPORTANT):
@@ -80,7 +80,7 @@ TIME FX:
 - Reverb: Active=112, Mode=113, Dry/Wet=114, Time=115, LowCut=116, HighCut=117  
 If you turn Delay/Reverb on, include a sensible Dry/Wet (105/114). If you touch non-toggle params, also set the module Active toggle.
 If the prompt asks for "shimmer", set Reverb Mode (113) accordingly (prefer selecting the value by label from ENUM_OPTIONS_JSON when provided).
-For Tempo (108), you may use bpm strings like "120 bpm" (or a plain number in the 40..240 range if FORMATTED_VALUE_TRIPLETS_JSON is present).
+For Tempo (108), if PROJECT_TEMPO is present write "project tempo" so the delay follows the session; otherwise use bpm strings like "120 bpm" (or a plain number in the 40..240 range if FORMATTED_VALUE_TRIPLETS_JSON is present).
 For Reverb Time (115), you may use time strings like "1200 ms" / "1.2 s" (or a plain number if FORMATTED_VALUE_TRIPLETS_JSON is present).
 
 DEFAULT MODERN RHYTHM GUIDELINES (when applicable):
@@ -103,7 +103,8 @@ RUNTIME META (optional):
 - The user prompt may include a block like "PLUGIN PARAM META" with JSON for enumerated options (e.g., Cab Type (84), Mic IR (92/99)). If present, prefer selecting those by label and set the parameter value close to the provided float for that label.
 
 OUTPUT FORMAT (JSON):
-- Return ONLY valid JSON: { "reasoning": "...", "params": [ { "index": int, "value": string }, ... ] }
+- Return ONLY valid JSON: { "reasoning": "...", "params": [ { "index": int, "value": string }, ... ], "alternatives": [ { "index": int, "labels": [string, ...] }, ... ] }
+- "alternatives" is optional: for each of Cab Type (84) and Mic IR (92/99) you set, you may list 2-3 other labels (best first) that would also suit the request. Use labels from ENUM_OPTIONS_JSON when present.
 - The backend will convert human-friendly strings into 0..1 normalized values.
   Examples:
   - Amp Type (29): "Clean" | "Crunch" | "Lead" (numeric allowed: "0", "0.5", "1")
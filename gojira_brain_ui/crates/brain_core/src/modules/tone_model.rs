@@ -0,0 +1,219 @@
+//! The LLMs a tone can be generated with. The pipeline in `gemini` (research brief, tone,
+//! autofixes, plan) runs on any `ToneModel`; which one is picked by a `ToneModelConfig`.
+
+use crate::modules::anthropic::AnthropicModel;
+use crate::modules::gemini::{
+    GeminiError, GeminiModel, ProgressSink, TextSink, ToneRequest, ToneResponse,
+};
+use crate::modules::openai::OpenAiModel;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// What a `ToneModel` call returns.
+pub type ModelFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, GeminiError>> + Send + 'a>>;
+
+/// One provider's model, as the tone pipeline uses it.
+pub trait ToneModel: Send + Sync {
+    /// Stage 1: the free-text research brief for `user_prompt`, each piece to `on_text` as it is
+    /// written when there is a sink.
+    fn research<'a>(
+        &'a self,
+        user_prompt: &'a str,
+        on_text: TextSink<'a>,
+    ) -> ModelFuture<'a, String>;
+
+    /// Stage 2: the tone JSON for `req`, parsed and resolved against `req.meta`, with reasoning
+    /// and params reported to `progress` as they are written when there is a sink.
+    fn tone<'a>(
+        &'a self,
+        req: ToneRequest,
+        progress: ProgressSink<'a>,
+    ) -> ModelFuture<'a, ToneResponse>;
+}
+
+/// Request timeout for the Anthropic and OpenAI-style providers. It covers the whole response,
+/// so it has to fit an 8192-token stream from a slow local server; `TONE_HTTP_TIMEOUT_SECS`
+/// changes it.
+pub(crate) fn http_timeout() -> Duration {
+    let secs = std::env::var("TONE_HTTP_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(600)
+        .clamp(15, 3600);
+    Duration::from_secs(secs)
+}
+
+/// Hosted services that speak OpenAI's chat completions API, by the provider id the UI stores.
+const OPENAI_HOSTS: &[(&str, &str)] = &[
+    ("openai", "https://api.openai.com/v1"),
+    ("openrouter", "https://openrouter.ai/api/v1"),
+    ("groq", "https://api.groq.com/openai/v1"),
+    ("together", "https://api.together.xyz/v1"),
+    ("fireworks", "https://api.fireworks.ai/inference/v1"),
+    ("perplexity", "https://api.perplexity.ai"),
+    ("mistral", "https://api.mistral.ai/v1"),
+    ("deepseek", "https://api.deepseek.com/v1"),
+    ("cohere", "https://api.cohere.ai/compatibility/v1"),
+    ("huggingface", "https://router.huggingface.co/v1"),
+];
+
+/// Self-hosted servers speaking the same API, at their default local address.
+const LOCAL_HOSTS: &[(&str, &str)] = &[
+    ("ollama", "http://localhost:11434/v1"),
+    ("llamacpp", "http://localhost:8080/v1"),
+    ("vllm", "http://localhost:8000/v1"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToneProvider {
+    /// Google Gemini on AI Studio, Google OAuth or Vertex AI (see `decide_backend`).
+    Gemini,
+    /// Anthropic's Messages API.
+    Anthropic,
+    /// OpenAI, or a hosted service speaking its API; `name` is the provider id.
+    OpenAi { name: String, base_url: String },
+    /// Any server speaking OpenAI's chat completions API at `base_url` (llama.cpp, Ollama, vLLM).
+    /// The API key is optional.
+    OpenAiCompatible { base_url: String },
+}
+
+impl ToneProvider {
+    /// The provider for `id` (the UI's provider ids, or `TONE_PROVIDER`). `base_url` points an
+    /// OpenAI-style provider somewhere else, and is required for `custom`.
+    pub fn from_id(id: &str, base_url: Option<&str>) -> Result<Self, String> {
+        let id = id.trim().to_ascii_lowercase();
+        let base_url = base_url
+            .map(|u| u.trim().trim_end_matches('/'))
+            .filter(|u| !u.is_empty())
+            .map(str::to_string);
+
+        match id.as_str() {
+            "" | "gemini" | "google" | "google_ai" | "google-ai" => return Ok(Self::Gemini),
+            "anthropic" | "claude" => return Ok(Self::Anthropic),
+            "custom" | "openai_compatible" | "openai-compatible" | "local" => {
+                return base_url
+                    .map(|base_url| Self::OpenAiCompatible { base_url })
+                    .ok_or_else(|| format!("provider {id} needs a base URL"));
+            }
+            _ => {}
+        }
+        if let Some((name, url)) = OPENAI_HOSTS.iter().find(|(name, _)| *name == id) {
+            return Ok(Self::OpenAi {
+                name: name.to_string(),
+                base_url: base_url.unwrap_or_else(|| url.to_string()),
+            });
+        }
+        if let Some((_, url)) = LOCAL_HOSTS.iter().find(|(name, _)| *name == id) {
+            return Ok(Self::OpenAiCompatible {
+                base_url: base_url.unwrap_or_else(|| url.to_string()),
+            });
+        }
+        Err(format!(
+            "provider {id} is not supported for tone generation"
+        ))
+    }
+
+    /// The environment variable holding this provider's API key when none is given.
+    pub fn api_key_env(&self) -> String {
+        match self {
+            Self::Gemini => "GEMINI_API_KEY".to_string(),
+            Self::Anthropic => "ANTHROPIC_API_KEY".to_string(),
+            Self::OpenAi { name, .. } => format!("{}_API_KEY", name.to_ascii_uppercase()),
+            Self::OpenAiCompatible { .. } => "TONE_API_KEY".to_string(),
+        }
+    }
+}
+
+/// Which model to generate tones with, and how to reach it.
+#[derive(Debug, Clone)]
+pub struct ToneModelConfig {
+    pub provider: ToneProvider,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl ToneModelConfig {
+    /// The provider from `TONE_PROVIDER` (default Gemini) and `TONE_BASE_URL`. Without
+    /// `api_key`, the key comes from the provider's own variable (`ToneProvider::api_key_env`).
+    pub fn from_env(model: &str, api_key: Option<&str>) -> Self {
+        let id = std::env::var("TONE_PROVIDER").unwrap_or_default();
+        let base_url = std::env::var("TONE_BASE_URL").ok();
+        let provider = ToneProvider::from_id(&id, base_url.as_deref()).unwrap_or_else(|e| {
+            eprintln!("warning: {e} (TONE_PROVIDER), falling back to gemini");
+            ToneProvider::Gemini
+        });
+        let api_key = api_key
+            .map(str::to_string)
+            .or_else(|| std::env::var(provider.api_key_env()).ok())
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty());
+        Self {
+            provider,
+            model: model.trim().to_string(),
+            api_key,
+        }
+    }
+
+    pub fn build(self) -> Box<dyn ToneModel> {
+        let Self {
+            provider,
+            model,
+            api_key,
+        } = self;
+        match provider {
+            ToneProvider::Gemini => Box::new(GeminiModel { model, api_key }),
+            ToneProvider::Anthropic => Box::new(AnthropicModel { model, api_key }),
+            ToneProvider::OpenAi { base_url, .. } => Box::new(OpenAiModel {
+                base_url,
+                model,
+                api_key,
+                key_required: true,
+            }),
+            ToneProvider::OpenAiCompatible { base_url } => Box::new(OpenAiModel {
+                base_url,
+                model,
+                api_key,
+                key_required: false,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_ids_resolve_to_their_api() {
+        assert_eq!(
+            ToneProvider::from_id("Gemini", None),
+            Ok(ToneProvider::Gemini)
+        );
+        assert_eq!(
+            ToneProvider::from_id("claude", None),
+            Ok(ToneProvider::Anthropic)
+        );
+        assert_eq!(
+            ToneProvider::from_id("groq", None),
+            Ok(ToneProvider::OpenAi {
+                name: "groq".to_string(),
+                base_url: "https://api.groq.com/openai/v1".to_string(),
+            })
+        );
+        assert_eq!(
+            ToneProvider::from_id("ollama", Some("http://10.0.0.5:11434/v1/")),
+            Ok(ToneProvider::OpenAiCompatible {
+                base_url: "http://10.0.0.5:11434/v1".to_string(),
+            })
+        );
+        assert!(ToneProvider::from_id("custom", None).is_err());
+        assert!(ToneProvider::from_id("azure_openai", None).is_err());
+        assert_eq!(
+            ToneProvider::from_id("openrouter", None)
+                .unwrap()
+                .api_key_env(),
+            "OPENROUTER_API_KEY"
+        );
+    }
+}
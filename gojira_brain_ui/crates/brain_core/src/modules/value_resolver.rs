@@ -1,11 +1,36 @@
 use crate::modules::param_map;
-use crate::modules::protocol::ParamChange;
-use serde::Deserialize;
+use crate::modules::param_meta::ParamMeta;
+use crate::modules::protocol::{ParamChange, ParamKind, ParamOp};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AiToneResponse {
     pub reasoning: String,
     pub params: Vec<AiParamChange>,
+    #[serde(default)]
+    pub alternatives: Vec<AiAlternative>,
+}
+
+/// Model-ranked fallback labels for an enum selector (best first).
+#[derive(Debug, Clone, Deserialize)]
+pub struct AiAlternative {
+    pub index: i32,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlternativeOption {
+    pub label: String,
+    pub value: f64,
+}
+
+/// Ranked swaps for one selector, so a client can offer "try the next mic" without a new request.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParamAlternatives {
+    pub index: i32,
+    pub chosen: Option<String>,
+    pub options: Vec<AlternativeOption>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -25,40 +50,26 @@ impl std::fmt::Display for ResolveError {
 
 impl std::error::Error for ResolveError {}
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone)]
 struct EnumOption {
-    value: f32,
+    value: f64,
     label: String,
 }
 
-fn extract_prompt_json_line<'a>(prompt: &'a str, key: &str) -> Option<&'a str> {
-    for line in prompt.lines() {
-        let t = line.trim();
-        if let Some(v) = t.strip_prefix(key) {
-            let v = v.trim();
-            if !v.is_empty() {
-                return Some(v);
-            }
-        }
-    }
-    None
-}
-
-fn parse_enum_options(
-    prompt: &str,
-) -> Option<std::collections::HashMap<i32, Vec<EnumOption>>> {
-    let raw = extract_prompt_json_line(prompt, "ENUM_OPTIONS_JSON=")?;
-    let parsed: std::collections::HashMap<String, Vec<EnumOption>> =
-        serde_json::from_str(raw).ok()?;
-
-    let mut out: std::collections::HashMap<i32, Vec<EnumOption>> =
-        std::collections::HashMap::new();
-    for (k, v) in parsed {
-        if let Ok(idx) = k.parse::<i32>() {
-            out.insert(idx, v);
-        }
-    }
-    Some(out)
+fn enum_options_from_meta(meta: &ParamMeta) -> std::collections::HashMap<i32, Vec<EnumOption>> {
+    meta.enums
+        .iter()
+        .map(|(idx, opts)| {
+            let opts = opts
+                .iter()
+                .map(|o| EnumOption {
+                    value: o.value,
+                    label: o.label.clone(),
+                })
+                .collect();
+            (*idx, opts)
+        })
+        .collect()
 }
 
 fn default_enum_options() -> std::collections::HashMap<i32, Vec<EnumOption>> {
@@ -66,8 +77,8 @@ fn default_enum_options() -> std::collections::HashMap<i32, Vec<EnumOption>> {
     let mut out: HashMap<i32, Vec<EnumOption>> = HashMap::new();
 
     // These values have been observed in the current Gojira build via TrackFX_FormatParamValue
-    // sampling (and are stable across many plugin builds). If ENUM_OPTIONS_JSON is present, it
-    // takes precedence.
+    // sampling (and are stable across many plugin builds). Enums from the handshake meta take
+    // precedence.
     out.insert(
         param_map::cab::TYPE_SELECTOR,
         vec![
@@ -158,7 +169,7 @@ fn default_formatted_value_triplets() -> std::collections::HashMap<i32, (String,
     let mut out: HashMap<i32, (String, String, String)> = HashMap::new();
 
     // These have been observed via TrackFX_FormatParamValue on a recent Archetype Gojira build.
-    // They are used as a fallback when FORMATTED_VALUE_TRIPLETS_JSON isn't present.
+    // They are used as a fallback when the handshake meta has no formatted triplets.
     out.insert(0, ("-24.0".to_string(), "0.0".to_string(), "24.0".to_string())); // Input Gain
     out.insert(1, ("-24.0".to_string(), "0.0".to_string(), "24.0".to_string())); // Output Gain
     out.insert(2, ("-96.0".to_string(), "-48.0".to_string(), "0.0".to_string())); // Gate Amount
@@ -194,21 +205,21 @@ fn default_formatted_value_triplets() -> std::collections::HashMap<i32, (String,
     out
 }
 
-fn parse_format_samples(
-    prompt: &str,
-) -> Option<std::collections::HashMap<i32, Vec<(f32, String)>>> {
-    let raw = extract_prompt_json_line(prompt, "PARAM_FORMAT_SAMPLES_JSON=")?;
-    let parsed: std::collections::HashMap<String, Vec<(f32, String)>> =
-        serde_json::from_str(raw).ok()?;
-
-    let mut out: std::collections::HashMap<i32, Vec<(f32, String)>> =
-        std::collections::HashMap::new();
-    for (k, v) in parsed {
-        if let Ok(idx) = k.parse::<i32>() {
-            out.insert(idx, v);
-        }
+fn format_samples_from_meta(
+    meta: &ParamMeta,
+) -> Option<std::collections::HashMap<i32, Vec<(f64, String)>>> {
+    if meta.samples.is_empty() {
+        return None;
     }
-    Some(out)
+    Some(
+        meta.samples
+            .iter()
+            .map(|(idx, v)| {
+                let pairs = v.iter().map(|s| (s.norm, s.formatted.clone())).collect();
+                (*idx, pairs)
+            })
+            .collect(),
+    )
 }
 
 fn normalize_ws(s: &str) -> String {
@@ -219,15 +230,15 @@ fn eq_ignore_case(a: &str, b: &str) -> bool {
     a.trim().eq_ignore_ascii_case(b.trim())
 }
 
-fn parse_numeric_value(value: &serde_json::Value) -> Option<f32> {
+fn parse_numeric_value(value: &serde_json::Value) -> Option<f64> {
     match value {
-        serde_json::Value::Number(n) => n.as_f64().map(|v| v as f32),
-        serde_json::Value::String(s) => s.trim().parse::<f32>().ok(),
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse::<f64>().ok(),
         _ => None,
     }
 }
 
-fn parse_bool_like(s: &str) -> Option<f32> {
+fn parse_bool_like(s: &str) -> Option<f64> {
     match s.trim().to_ascii_lowercase().as_str() {
         "on" | "true" | "yes" | "enabled" => Some(1.0),
         "off" | "false" | "no" | "disabled" => Some(0.0),
@@ -235,20 +246,20 @@ fn parse_bool_like(s: &str) -> Option<f32> {
     }
 }
 
-fn parse_percent(s: &str) -> Option<f32> {
+fn parse_percent(s: &str) -> Option<f64> {
     let t = s.trim().trim_end_matches('%').trim();
-    let v = t.parse::<f32>().ok()?;
+    let v = t.parse::<f64>().ok()?;
     Some((v / 100.0).clamp(0.0, 1.0))
 }
 
-fn parse_db(s: &str) -> Option<f32> {
+fn parse_db(s: &str) -> Option<f64> {
     // Accept "+3.2 dB", "-10db", "3db"
     let t = s.trim().to_ascii_lowercase().replace(',', ".");
     let t = t.replace("db", "").trim().to_string();
-    t.parse::<f32>().ok()
+    t.parse::<f64>().ok()
 }
 
-fn parse_db_from_formatted(s: &str) -> Option<f32> {
+fn parse_db_from_formatted(s: &str) -> Option<f64> {
     // Accept "-6.0 dB", "-6dB", "+3,2 dB"
     let t = s.trim().to_ascii_lowercase().replace(',', ".");
     if !t.contains("db") {
@@ -260,14 +271,14 @@ fn parse_db_from_formatted(s: &str) -> Option<f32> {
         .map(|c| if c.is_ascii_digit() || c == '.' || c == '-' || c == '+' || c == ' ' { c } else { ' ' })
         .collect();
     for tok in cleaned.split_whitespace() {
-        if let Ok(v) = tok.parse::<f32>() {
+        if let Ok(v) = tok.parse::<f64>() {
             return Some(v);
         }
     }
     None
 }
 
-fn invert_piecewise(points: &[(f32, f32)], target: f32) -> Option<f32> {
+fn invert_piecewise(points: &[(f64, f64)], target: f64) -> Option<f64> {
     // points: (physical, norm). We assume physical is monotonic after sorting.
     if points.is_empty() {
         return None;
@@ -299,24 +310,24 @@ fn invert_piecewise(points: &[(f32, f32)], target: f32) -> Option<f32> {
     None
 }
 
-fn parse_ms_or_s(s: &str) -> Option<(f32, &'static str)> {
+fn parse_ms_or_s(s: &str) -> Option<(f64, &'static str)> {
     // Returns numeric value and canonical unit "ms" or "s"
     let t = s.trim().to_ascii_lowercase().replace(' ', "");
     if let Some(v) = t.strip_suffix("ms") {
-        return v.parse::<f32>().ok().map(|n| (n, "ms"));
+        return v.parse::<f64>().ok().map(|n| (n, "ms"));
     }
     if let Some(v) = t.strip_suffix('s') {
-        return v.parse::<f32>().ok().map(|n| (n, "s"));
+        return v.parse::<f64>().ok().map(|n| (n, "s"));
     }
     None
 }
 
-fn parse_ms_value(s: &str) -> Option<f32> {
+fn parse_ms_value(s: &str) -> Option<f64> {
     let (n, unit) = parse_ms_or_s(s)?;
     Some(if unit == "s" { n * 1000.0 } else { n })
 }
 
-fn parse_ms_from_formatted(s: &str) -> Option<f32> {
+fn parse_ms_from_formatted(s: &str) -> Option<f64> {
     let t = s.trim().to_ascii_lowercase().replace(',', ".");
     if !(t.contains("ms") || t.ends_with('s')) {
         return None;
@@ -327,7 +338,7 @@ fn parse_ms_from_formatted(s: &str) -> Option<f32> {
         .map(|c| if c.is_ascii_digit() || c == '.' || c == '-' || c == '+' || c == ' ' { c } else { ' ' })
         .collect();
     let first = cleaned.split_whitespace().next()?;
-    let v = first.parse::<f32>().ok()?;
+    let v = first.parse::<f64>().ok()?;
     if t.contains("ms") {
         Some(v)
     } else if t.ends_with('s') {
@@ -337,19 +348,19 @@ fn parse_ms_from_formatted(s: &str) -> Option<f32> {
     }
 }
 
-fn parse_hz_value(s: &str) -> Option<f32> {
+fn parse_hz_value(s: &str) -> Option<f64> {
     let t = s.trim().to_ascii_lowercase().replace(',', ".");
     let t = t.replace(' ', "");
     if let Some(v) = t.strip_suffix("khz") {
-        return v.parse::<f32>().ok().map(|n| n * 1000.0);
+        return v.parse::<f64>().ok().map(|n| n * 1000.0);
     }
     if let Some(v) = t.strip_suffix("hz") {
-        return v.parse::<f32>().ok();
+        return v.parse::<f64>().ok();
     }
     None
 }
 
-fn parse_bpm_value(s: &str) -> Option<f32> {
+fn parse_bpm_value(s: &str) -> Option<f64> {
     let t = s.trim().to_ascii_lowercase().replace(',', ".");
     if !t.contains("bpm") {
         return None;
@@ -366,10 +377,10 @@ fn parse_bpm_value(s: &str) -> Option<f32> {
         })
         .collect();
     let first = cleaned.split_whitespace().next()?;
-    first.parse::<f32>().ok()
+    first.parse::<f64>().ok()
 }
 
-fn resolve_amp_type(value: &serde_json::Value) -> Option<f32> {
+fn resolve_amp_type(value: &serde_json::Value) -> Option<f64> {
     let s = value.as_str()?.trim();
     let s = normalize_ws(s);
     match s.to_ascii_lowercase().as_str() {
@@ -384,11 +395,11 @@ fn resolve_from_enum_label(
     enums: &std::collections::HashMap<i32, Vec<EnumOption>>,
     index: i32,
     value: &serde_json::Value,
-) -> Option<f32> {
+) -> Option<f64> {
     let s = value.as_str()?.trim();
     let s = normalize_ws(s);
     let opts = enums.get(&index)?;
-    let find = |label: &str| -> Option<f32> {
+    let find = |label: &str| -> Option<f64> {
         opts.iter()
             .find(|o| o.label.trim().eq_ignore_ascii_case(label))
             .map(|o| o.value)
@@ -464,11 +475,64 @@ fn resolve_from_enum_label(
     None
 }
 
-fn resolve_eq_band_db(index: i32, s: &str) -> Option<f32> {
+const EQ_BAND_GROUPS: [std::ops::RangeInclusive<i32>; 3] = [54..=62, 64..=72, 74..=82];
+
+/// "-3 dB @ 400 Hz" on any band of an EQ group retargets to the band nearest 400 Hz (using the
+/// handshake band table) and strips the frequency part. Returns None when the value isn't of that
+/// shape; keeps the given index when no band table is available.
+fn retarget_eq_band(
+    meta: &ParamMeta,
+    index: i32,
+    value: &serde_json::Value,
+) -> Option<(i32, serde_json::Value)> {
+    let s = value.as_str()?;
+    let (level, freq) = s.split_once('@')?;
+    let group = EQ_BAND_GROUPS.iter().find(|g| g.contains(&index))?;
+    let level = serde_json::Value::String(level.trim().to_string());
+
+    let Some(hz) = parse_hz_value(freq).filter(|hz| *hz > 0.0) else {
+        return Some((index, level));
+    };
+    let nearest = meta
+        .eq_band_hz
+        .iter()
+        .filter(|(idx, band_hz)| group.contains(*idx) && **band_hz > 0.0)
+        .min_by(|a, b| {
+            let da = (f64::from(*a.1).ln() - hz.ln()).abs();
+            let db = (f64::from(*b.1).ln() - hz.ln()).abs();
+            da.total_cmp(&db)
+        })
+        .map(|(idx, _)| *idx)
+        .unwrap_or(index);
+    Some((nearest, level))
+}
+
+/// "project tempo" / "sync" on Delay Tempo becomes the session bpm from the handshake.
+fn sync_delay_tempo(meta: &ParamMeta, index: i32, value: serde_json::Value) -> serde_json::Value {
+    if index != param_map::pedals::delay::TIME {
+        return value;
+    }
+    let Some(tempo) = meta.tempo else {
+        return value;
+    };
+    let wants_sync = value.as_str().is_some_and(|s| {
+        let s = s.trim().to_ascii_lowercase();
+        ["project", "session", "song", "host", "sync"]
+            .iter()
+            .any(|k| s.contains(k))
+    });
+    if wants_sync {
+        serde_json::Value::String(format!("{:.2} bpm", tempo.bpm))
+    } else {
+        value
+    }
+}
+
+fn resolve_eq_band_db(index: i32, s: &str) -> Option<f64> {
     // Heuristic fallback: Graphic EQ bands are typically -12..+12 dB, with 0 dB at 0.5.
     // Map desired dB into normalized 0..1.
     let db = parse_db(s)?;
-    let (min_db, max_db) = (-12.0_f32, 12.0_f32);
+    let (min_db, max_db) = (-12.0_f64, 12.0_f64);
     // Only apply to known band indices.
     let is_band = (54..=82).contains(&index);
     if !is_band {
@@ -477,24 +541,16 @@ fn resolve_eq_band_db(index: i32, s: &str) -> Option<f32> {
     Some(((db - min_db) / (max_db - min_db)).clamp(0.0, 1.0))
 }
 
-fn parse_formatted_value_triplets(
-    prompt: &str,
-) -> Option<std::collections::HashMap<i32, (String, String, String)>> {
-    let raw = extract_prompt_json_line(prompt, "FORMATTED_VALUE_TRIPLETS_JSON=")?;
-    let parsed: std::collections::HashMap<String, (String, String, String)> =
-        serde_json::from_str(raw).ok()?;
-
-    let mut out: std::collections::HashMap<i32, (String, String, String)> =
-        std::collections::HashMap::new();
-    for (k, v) in parsed {
-        if let Ok(idx) = k.parse::<i32>() {
-            out.insert(idx, v);
-        }
-    }
-    Some(out)
+fn formatted_value_triplets_from_meta(
+    meta: &ParamMeta,
+) -> std::collections::HashMap<i32, (String, String, String)> {
+    meta.formats
+        .iter()
+        .map(|(idx, t)| (*idx, (t.min.clone(), t.mid.clone(), t.max.clone())))
+        .collect()
 }
 
-fn parse_first_float(s: &str) -> Option<f32> {
+fn parse_first_float(s: &str) -> Option<f64> {
     let t = s.trim().to_ascii_lowercase().replace(',', ".");
     let cleaned: String = t
         .chars()
@@ -507,7 +563,7 @@ fn parse_first_float(s: &str) -> Option<f32> {
         })
         .collect();
     for tok in cleaned.split_whitespace() {
-        if let Ok(v) = tok.parse::<f32>() {
+        if let Ok(v) = tok.parse::<f64>() {
             return Some(v);
         }
     }
@@ -517,8 +573,8 @@ fn parse_first_float(s: &str) -> Option<f32> {
 fn invert_from_triplet_physical(
     triplets: &std::collections::HashMap<i32, (String, String, String)>,
     index: i32,
-    physical: f32,
-) -> Option<f32> {
+    physical: f64,
+) -> Option<f64> {
     let (min_s, _mid_s, max_s) = triplets.get(&index)?.clone();
     let min = parse_first_float(&min_s)?;
     let max = parse_first_float(&max_s)?;
@@ -535,10 +591,10 @@ fn invert_from_triplet_physical(
 }
 
 fn invert_from_samples_physical(
-    samples: &std::collections::HashMap<i32, Vec<(f32, String)>>,
+    samples: &std::collections::HashMap<i32, Vec<(f64, String)>>,
     index: i32,
-    physical: f32,
-) -> Option<f32> {
+    physical: f64,
+) -> Option<f64> {
     let raw = samples.get(&index)?;
     if raw.is_empty() {
         return None;
@@ -557,7 +613,7 @@ fn invert_from_samples_physical(
         }
     }
 
-    let mut pts: Vec<(f32, f32)> = Vec::new(); // (physical, norm)
+    let mut pts: Vec<(f64, f64)> = Vec::new(); // (physical, norm)
     for (norm, formatted) in raw {
         let p = if has_db {
             parse_db_from_formatted(formatted)
@@ -573,13 +629,13 @@ fn invert_from_samples_physical(
 }
 
 fn resolve_value_for_index(
-    prompt: &str,
+    has_live_enums: bool,
     enums: Option<&std::collections::HashMap<i32, Vec<EnumOption>>>,
-    samples: Option<&std::collections::HashMap<i32, Vec<(f32, String)>>>,
+    samples: Option<&std::collections::HashMap<i32, Vec<(f64, String)>>>,
     triplets: Option<&std::collections::HashMap<i32, (String, String, String)>>,
     index: i32,
     value: &serde_json::Value,
-) -> Result<f32, ResolveError> {
+) -> Result<f64, ResolveError> {
     // Numbers still work when they are truly normalized 0..1.
     if let Some(v) = parse_numeric_value(value) {
         if (0.0..=1.0).contains(&v) {
@@ -606,7 +662,7 @@ fn resolve_value_for_index(
         }
 
         return Err(ResolveError(format!(
-            "numeric value {v} for idx {index} is not a normalized 0..1 value, and no calibration mapping was available (try \"%\", \"dB\", \"ms\", \"bpm\", or enable PARAM_FORMAT_SAMPLES_JSON)"
+            "numeric value {v} for idx {index} is not a normalized 0..1 value, and no calibration mapping was available (try \"%\", \"dB\", \"ms\", \"bpm\", or enable param format samples in the handshake)"
         )));
     }
 
@@ -653,7 +709,7 @@ fn resolve_value_for_index(
         }
         if let Some(db) = parse_db(s_trim) {
             if let Some(samples) = samples.and_then(|m| m.get(&index)) {
-                let mut pts: Vec<(f32, f32)> = Vec::new(); // (db, norm)
+                let mut pts: Vec<(f64, f64)> = Vec::new(); // (db, norm)
                 for (norm, formatted) in samples {
                     if let Some(v) = parse_db_from_formatted(formatted) {
                         pts.push((v, *norm));
@@ -677,7 +733,7 @@ fn resolve_value_for_index(
     // Time units (ms/s) - without calibration we can't map reliably, so accept normalized fallback.
     if let Some(ms) = parse_ms_value(s_trim) {
         if let Some(samples) = samples.and_then(|m| m.get(&index)) {
-            let mut pts: Vec<(f32, f32)> = Vec::new(); // (ms, norm)
+            let mut pts: Vec<(f64, f64)> = Vec::new(); // (ms, norm)
             for (norm, formatted) in samples {
                 if let Some(v) = parse_ms_from_formatted(formatted) {
                     pts.push((v, *norm));
@@ -693,7 +749,7 @@ fn resolve_value_for_index(
             }
         }
         return Err(ResolveError(format!(
-            "time unit provided for idx {index} but no matching PARAM_FORMAT_SAMPLES_JSON mapping was found"
+            "time unit provided for idx {index} but no matching param format samples were found"
         )));
     }
 
@@ -731,9 +787,8 @@ fn resolve_value_for_index(
         )));
     }
 
-    // If prompt included enums, suggest it in error.
-    let has_enums = extract_prompt_json_line(prompt, "ENUM_OPTIONS_JSON=").is_some();
-    if has_enums {
+    // If the handshake provided enums, suggest them in the error.
+    if has_live_enums {
         Err(ResolveError(format!(
             "could not resolve string value for idx {index}: {s_trim:?} (try a known enum label or a 0..1 number)"
         )))
@@ -744,49 +799,156 @@ fn resolve_value_for_index(
     }
 }
 
+/// Rejects values the plugin would silently round: anything but 0/1 on a toggle, and plain numbers
+/// that fall between the steps of a stepped param (labels and probed option values are fine).
+fn check_param_kind(
+    meta: &ParamMeta,
+    index: i32,
+    raw: &serde_json::Value,
+    v: f64,
+) -> Result<(), ResolveError> {
+    match meta.kinds.get(&index) {
+        Some(ParamKind::Toggle) if v > 0.01 && v < 0.99 => Err(ResolveError(format!(
+            "fractional value {v} for toggle idx {index} (use 0 or 1)"
+        ))),
+        Some(ParamKind::Stepped { steps }) if *steps >= 2 && parse_numeric_value(raw).is_some() => {
+            let pos = v * (*steps - 1) as f64;
+            let on_step = (pos - pos.round()).abs() <= 0.1;
+            let is_option = meta
+                .enums
+                .get(&index)
+                .is_some_and(|opts| opts.iter().any(|o| (o.value - v).abs() <= 0.01));
+            if on_step || is_option {
+                Ok(())
+            } else {
+                Err(ResolveError(format!(
+                    "value {v} for idx {index} falls between steps ({steps} positions); use an option label or one of the step values"
+                )))
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
 pub fn resolve_ai_params(
-    original_prompt: &str,
+    meta: &ParamMeta,
     ai_params: Vec<AiParamChange>,
 ) -> Result<Vec<ParamChange>, ResolveError> {
     let enums = {
         let mut e = default_enum_options();
-        if let Some(from_prompt) = parse_enum_options(original_prompt) {
-            for (k, v) in from_prompt {
-                e.insert(k, v);
-            }
-        }
+        e.extend(enum_options_from_meta(meta));
         Some(e)
     };
-    let samples = parse_format_samples(original_prompt);
+    let samples = format_samples_from_meta(meta);
 
     let triplets = {
         let mut t = default_formatted_value_triplets();
-        if let Some(from_prompt) = parse_formatted_value_triplets(original_prompt) {
-            for (k, v) in from_prompt {
-                t.insert(k, v);
-            }
-        }
+        t.extend(formatted_value_triplets_from_meta(meta));
         Some(t)
     };
 
     let mut out: Vec<ParamChange> = Vec::with_capacity(ai_params.len());
     for p in ai_params {
+        let (index, value) =
+            retarget_eq_band(meta, p.index, &p.value).unwrap_or((p.index, p.value));
+        let value = sync_delay_tempo(meta, index, value);
         let v = resolve_value_for_index(
-            original_prompt,
+            !meta.enums.is_empty(),
             enums.as_ref(),
             samples.as_ref(),
             triplets.as_ref(),
-            p.index,
-            &p.value,
+            index,
+            &value,
         )?;
+        check_param_kind(meta, index, &value, v)?;
         out.push(ParamChange {
-            index: p.index,
+            index,
             value: v,
+            op: ParamOp::Set,
         });
     }
     Ok(out)
 }
 
+const ALTERNATIVE_INDICES: [i32; 3] = [
+    param_map::cab::TYPE_SELECTOR,
+    param_map::cab::mic1::IR_SEL,
+    param_map::cab::mic2::IR_SEL,
+];
+const MAX_ALTERNATIVES: usize = 3;
+
+/// Up to three other options for each cab/mic selector set in `params`: the model's own ranked
+/// picks first (when they exist in the enum list), then the options next to the chosen one.
+pub fn rank_alternatives(
+    meta: &ParamMeta,
+    params: &[ParamChange],
+    suggested: &[AiAlternative],
+) -> Vec<ParamAlternatives> {
+    let mut enums = default_enum_options();
+    enums.extend(enum_options_from_meta(meta));
+
+    let mut out = Vec::new();
+    for index in ALTERNATIVE_INDICES {
+        let Some(chosen) = params.iter().find(|p| p.index == index) else {
+            continue;
+        };
+        let Some(opts) = enums.get(&index).filter(|o| o.len() > 1) else {
+            continue;
+        };
+        let nearest = |v: f64| {
+            opts.iter()
+                .enumerate()
+                .min_by(|a, b| {
+                    (a.1.value - v)
+                        .abs()
+                        .partial_cmp(&(b.1.value - v).abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(i, _)| i)
+        };
+        let Some(chosen_pos) = nearest(chosen.value) else {
+            continue;
+        };
+
+        let mut picked: Vec<usize> = Vec::new();
+        let from_model = suggested
+            .iter()
+            .filter(|a| a.index == index)
+            .flat_map(|a| a.labels.iter())
+            .filter_map(|label| {
+                resolve_from_enum_label(&enums, index, &serde_json::Value::String(label.clone()))
+            })
+            .filter_map(nearest);
+        let neighbors = (1..opts.len()).flat_map(|d| {
+            [chosen_pos.checked_add(d), chosen_pos.checked_sub(d)]
+                .into_iter()
+                .flatten()
+                .filter(|i| *i < opts.len())
+        });
+        for pos in from_model.chain(neighbors) {
+            if picked.len() >= MAX_ALTERNATIVES {
+                break;
+            }
+            if pos != chosen_pos && !picked.contains(&pos) {
+                picked.push(pos);
+            }
+        }
+
+        out.push(ParamAlternatives {
+            index,
+            chosen: Some(opts[chosen_pos].label.clone()),
+            options: picked
+                .into_iter()
+                .map(|i| AlternativeOption {
+                    label: opts[i].label.clone(),
+                    value: opts[i].value,
+                })
+                .collect(),
+        });
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -797,7 +959,7 @@ mod tests {
             index: 2,
             value: serde_json::Value::String("-30 dB".to_string()),
         }];
-        let out = resolve_ai_params("hi", params).unwrap();
+        let out = resolve_ai_params(&ParamMeta::default(), params).unwrap();
         let v = out[0].value;
         // (-30 - -96) / (0 - -96) = 66/96 = 0.6875
         assert!((v - 0.6875).abs() < 1e-4, "got {v}");
@@ -809,7 +971,7 @@ mod tests {
             index: 108,
             value: serde_json::Value::String("120 bpm".to_string()),
         }];
-        let out = resolve_ai_params("hi", params).unwrap();
+        let out = resolve_ai_params(&ParamMeta::default(), params).unwrap();
         let v = out[0].value;
         // (120-40)/(240-40)=0.4
         assert!((v - 0.4).abs() < 1e-4, "got {v}");
@@ -821,7 +983,7 @@ mod tests {
             index: 54,
             value: serde_json::Value::Number(650.into()),
         }];
-        let err = resolve_ai_params("hi", params).unwrap_err();
+        let err = resolve_ai_params(&ParamMeta::default(), params).unwrap_err();
         assert!(
             err.0.contains("not a normalized 0..1"),
             "unexpected err: {err}"
@@ -835,7 +997,7 @@ mod tests {
             index: 90,
             value: serde_json::Value::Number(n),
         }];
-        let out = resolve_ai_params("hi", params).unwrap();
+        let out = resolve_ai_params(&ParamMeta::default(), params).unwrap();
         let v = out[0].value;
         assert!((v - 0.25).abs() < 1e-6, "got {v}");
     }
@@ -846,9 +1008,135 @@ mod tests {
             index: 116,
             value: serde_json::Value::String("150 Hz".to_string()),
         }];
-        let out = resolve_ai_params("hi", params).unwrap();
+        let out = resolve_ai_params(&ParamMeta::default(), params).unwrap();
         let v = out[0].value;
         // (150-50)/(700-50)=100/650
         assert!((v - (100.0 / 650.0)).abs() < 1e-4, "got {v}");
     }
+
+    #[test]
+    fn enum_labels_prefer_handshake_meta() {
+        let mut meta = ParamMeta::default();
+        meta.enums.insert(
+            param_map::pedals::reverb::MODE,
+            vec![crate::modules::protocol::ParamEnumOption {
+                value: 0.7,
+                label: "Shimmer".to_string(),
+            }],
+        );
+        let params = vec![AiParamChange {
+            index: param_map::pedals::reverb::MODE,
+            value: serde_json::Value::String("Shimmer".to_string()),
+        }];
+        let out = resolve_ai_params(&meta, params).unwrap();
+        let v = out[0].value;
+        assert!((v - 0.7).abs() < 1e-6, "got {v}");
+    }
+
+    #[test]
+    fn quantized_enum_values_keep_full_precision() {
+        let mut meta = ParamMeta::default();
+        meta.enums.insert(
+            92,
+            vec![
+                crate::modules::protocol::ParamEnumOption {
+                    value: 0.125976562,
+                    label: "Dynamic 57".to_string(),
+                },
+                crate::modules::protocol::ParamEnumOption {
+                    value: 0.127929687,
+                    label: "Dynamic 421".to_string(),
+                },
+            ],
+        );
+        let params = vec![AiParamChange {
+            index: 92,
+            value: serde_json::Value::String("Dynamic 57".to_string()),
+        }];
+        let out = resolve_ai_params(&meta, params).unwrap();
+        assert_eq!(out[0].value, 0.125976562);
+
+        // Older clients send the f32-rounded number; it still parses.
+        let p: ParamChange = serde_json::from_str(r#"{"index":92,"value":0.12597656}"#).unwrap();
+        assert!((p.value - 0.125976562).abs() < 1e-7);
+    }
+
+    #[test]
+    fn eq_frequency_target_picks_nearest_band() {
+        let mut meta = ParamMeta::default();
+        for (i, hz) in [100.0, 200.0, 400.0, 800.0, 1600.0].into_iter().enumerate() {
+            meta.eq_band_hz.insert(64 + i as i32, hz);
+        }
+        let params = vec![AiParamChange {
+            index: 64,
+            value: serde_json::Value::String("-3 dB @ 450 Hz".to_string()),
+        }];
+        let out = resolve_ai_params(&meta, params).unwrap();
+        assert_eq!(out[0].index, 66);
+        // (-3 - -12) / 24 = 0.375
+        assert!((out[0].value - 0.375).abs() < 1e-4, "got {}", out[0].value);
+    }
+
+    #[test]
+    fn delay_tempo_syncs_to_project() {
+        let meta = ParamMeta {
+            tempo: Some(crate::modules::protocol::ProjectTempo {
+                bpm: 90.0,
+                beats_per_measure: 4,
+            }),
+            ..ParamMeta::default()
+        };
+        let params = vec![AiParamChange {
+            index: 108,
+            value: serde_json::Value::String("project tempo".to_string()),
+        }];
+        let out = resolve_ai_params(&meta, params).unwrap();
+        // 40..240 bpm triplet: 90 bpm -> 0.25
+        assert!((out[0].value - 0.25).abs() < 1e-3, "got {}", out[0].value);
+    }
+
+    #[test]
+    fn alternatives_prefer_model_picks_then_neighbors() {
+        let mut meta = ParamMeta::default();
+        meta.enums.insert(
+            92,
+            ["Dyn 57", "Dyn 421", "Cond 414", "Ribbon 121", "Cond 87"]
+                .iter()
+                .enumerate()
+                .map(|(i, l)| crate::modules::protocol::ParamEnumOption {
+                    value: i as f64 * 0.25,
+                    label: l.to_string(),
+                })
+                .collect(),
+        );
+        let params = vec![ParamChange {
+            index: 92,
+            value: 0.25,
+            op: ParamOp::Set,
+        }];
+        let suggested = vec![AiAlternative {
+            index: 92,
+            labels: vec!["Ribbon 121".to_string()],
+        }];
+        let alts = rank_alternatives(&meta, &params, &suggested);
+        assert_eq!(alts.len(), 1);
+        assert_eq!(alts[0].chosen.as_deref(), Some("Dyn 421"));
+        let labels: Vec<&str> = alts[0].options.iter().map(|o| o.label.as_str()).collect();
+        assert_eq!(labels, ["Ribbon 121", "Cond 414", "Dyn 57"]);
+    }
+
+    #[test]
+    fn param_kinds_reject_values_between_steps() {
+        let mut meta = ParamMeta::default();
+        meta.kinds.insert(13, ParamKind::Toggle);
+        meta.kinds.insert(29, ParamKind::Stepped { steps: 3 });
+        let p = |index: i32, v: &str| AiParamChange {
+            index,
+            value: serde_json::Value::String(v.to_string()),
+        };
+
+        assert!(resolve_ai_params(&meta, vec![p(13, "1"), p(29, "0.5"), p(29, "Lead")]).is_ok());
+        assert!(resolve_ai_params(&meta, vec![p(13, "0.6")]).is_err());
+        assert!(resolve_ai_params(&meta, vec![p(29, "0.3")]).is_err());
+    }
 }
@@ -1,14 +1,22 @@
-use brain_core::cleaner::{apply_replace_active_cleaner, sanitize_params};
-use brain_core::gemini::{generate_tone_auto as gemini_generate_tone, ToneRequest};
+use brain_core::cleaner::{apply_replace_active_cleaner_locked, sanitize_params};
+use brain_core::gemini::{
+    generate_tone_with, research_brief_with, two_stage_enabled, ToneProgress, ToneRequest,
+};
+use brain_core::param_meta::ParamMeta;
+use brain_core::modules::value_resolver::ParamAlternatives;
+use brain_core::research_cache;
+use brain_core::tone_model::{ToneModel, ToneModelConfig, ToneProvider};
 use brain_core::protocol::{
-    ClientCommand, MergeMode, ParamChange, ParamEnumOption, ParamFormatSample, ParamFormatTriplet,
+    ClientCommand, MergeMode, ModuleId, ParamChange, ParamEnumOption, ParamFormatSample,
+    ParamFormatTriplet, ParamOp, ToneTarget,
 };
 use serde::Serialize;
-use std::collections::HashMap;
-use tauri::{AppHandle, State};
+use std::collections::{HashMap, HashSet};
+use tauri::{AppHandle, Emitter, State};
 
-use crate::tauri_utils::app_state::{AppState, UiCommand};
-use crate::tauri_utils::diff::{diff_params, DiffItem};
+use crate::tauri_utils::app_state::{AppState, UiCommand, UNDO_DEPTH};
+use crate::tauri_utils::diff::{diff_params, locked_diff_items, DiffItem};
+use crate::tauri_utils::instances::{self, InstanceDashboardEntry};
 use crate::tauri_utils::vault;
 use serde::Deserialize;
 
@@ -16,10 +24,20 @@ use serde::Deserialize;
 pub struct HandshakePayload {
     pub session_token: String,
     pub instances: Vec<brain_core::protocol::GojiraInstance>,
-    pub validation_report: HashMap<String, String>,
+    pub validation_report: Vec<brain_core::protocol::ParamCheck>,
     pub param_enums: HashMap<i32, Vec<ParamEnumOption>>,
+    pub param_enum_totals: HashMap<i32, usize>,
     pub param_formats: HashMap<i32, ParamFormatTriplet>,
     pub param_format_samples: HashMap<i32, Vec<ParamFormatSample>>,
+    pub eq_band_hz: HashMap<i32, f32>,
+    pub param_kinds: HashMap<i32, brain_core::protocol::ParamKind>,
+    pub param_schema: HashMap<i32, brain_core::protocol::ParamSchemaEntry>,
+    pub param_remap: HashMap<i32, i32>,
+    pub proposed_remap: HashMap<i32, i32>,
+    pub project_tempo: Option<brain_core::protocol::ProjectTempo>,
+    pub server_addr: Option<String>,
+    pub tls_fingerprint: Option<String>,
+    pub capabilities: Vec<brain_core::protocol::Capability>,
 }
 
 #[derive(Serialize)]
@@ -27,11 +45,12 @@ pub struct PreviewResult {
     pub reasoning: String,
     pub params: Vec<ParamChange>,
     pub diff: Vec<DiffItem>,
+    pub alternatives: Vec<ParamAlternatives>,
 }
 
 fn merge_params(base: &[ParamChange], delta: &[ParamChange]) -> Vec<ParamChange> {
     use std::collections::BTreeMap;
-    let mut map: BTreeMap<i32, f32> = BTreeMap::new();
+    let mut map: BTreeMap<i32, f64> = BTreeMap::new();
     for p in base {
         map.insert(p.index, p.value);
     }
@@ -39,7 +58,11 @@ fn merge_params(base: &[ParamChange], delta: &[ParamChange]) -> Vec<ParamChange>
         map.insert(p.index, p.value);
     }
     map.into_iter()
-        .map(|(index, value)| ParamChange { index, value })
+        .map(|(index, value)| ParamChange {
+            index,
+            value,
+            op: ParamOp::Set,
+        })
         .collect()
 }
 
@@ -68,6 +91,24 @@ pub async fn disconnect_ws(state: State<'_, AppState>) -> Result<(), String> {
         .map_err(|_| "ws actor unavailable".to_string())
 }
 
+/// DLLs advertising themselves on the LAN, for picking a REAPER on another machine. Browsing
+/// blocks for a few seconds, so it runs off the async runtime.
+#[cfg(feature = "mdns")]
+#[tauri::command]
+pub async fn discover_sidecars() -> Result<Vec<brain_core::sidecar::LanSidecar>, String> {
+    tokio::task::spawn_blocking(|| {
+        brain_core::sidecar::discover_lan(std::time::Duration::from_secs(3))
+    })
+    .await
+    .map_err(|e| format!("discovery task: {e}"))?
+}
+
+#[cfg(not(feature = "mdns"))]
+#[tauri::command]
+pub async fn discover_sidecars() -> Result<Vec<brain_core::sidecar::LanSidecar>, String> {
+    Err("built without the `mdns` feature".to_string())
+}
+
 #[tauri::command]
 pub fn set_vault_passphrase(state: State<'_, AppState>, passphrase: String) -> Result<(), String> {
     let mut guard = state.vault.lock().map_err(|_| "vault lock poisoned")?;
@@ -184,6 +225,167 @@ pub fn reset_index_remap(state: State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+pub fn get_param_locks(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+) -> Result<Vec<i32>, String> {
+    let mut out: Vec<i32> = locks_for(&state, &target_fx_guid)?.into_iter().collect();
+    out.sort_unstable();
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn set_param_locks(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    indices: Vec<i32>,
+) -> Result<(), String> {
+    state.with_instance(&target_fx_guid, |inst| {
+        inst.locks = indices.into_iter().collect();
+    })
+}
+
+/// Clears the locks of one FX, or of every FX when no GUID is given.
+#[tauri::command]
+pub fn clear_param_locks(
+    state: State<'_, AppState>,
+    target_fx_guid: Option<String>,
+) -> Result<(), String> {
+    let mut instances = state
+        .instances
+        .lock()
+        .map_err(|_| "instances lock poisoned".to_string())?;
+    match target_fx_guid {
+        Some(guid) => {
+            if let Some(inst) = instances.get_mut(&guid) {
+                inst.locks.clear();
+            }
+        }
+        None => instances.values_mut().for_each(|inst| inst.locks.clear()),
+    }
+    Ok(())
+}
+
+fn locks_for(state: &AppState, target_fx_guid: &str) -> Result<HashSet<i32>, String> {
+    Ok(state
+        .instances
+        .lock()
+        .map_err(|_| "instances lock poisoned".to_string())?
+        .get(target_fx_guid)
+        .map(|inst| inst.locks.clone())
+        .unwrap_or_default())
+}
+
+/// Diff baseline: the cached preset, plus live read-back values for `touched` params we never sent.
+fn baseline_for(
+    state: &AppState,
+    target_fx_guid: &str,
+    touched: &[ParamChange],
+) -> Result<Vec<ParamChange>, String> {
+    let instances = state
+        .instances
+        .lock()
+        .map_err(|_| "instances lock poisoned".to_string())?;
+    let Some(inst) = instances.get(target_fx_guid) else {
+        return Ok(Vec::new());
+    };
+    let mut out = inst.param_cache.clone();
+    for p in touched {
+        if out.iter().any(|c| c.index == p.index) {
+            continue;
+        }
+        if let Some(live) = inst.live_params.iter().find(|l| l.index == p.index) {
+            out.push(live.clone());
+        }
+    }
+    Ok(out)
+}
+
+/// Overview of every Gojira instance seen this session (status, cache, locks, undo, last prompt).
+#[tauri::command]
+pub fn get_instance_dashboard(
+    state: State<'_, AppState>,
+) -> Result<Vec<InstanceDashboardEntry>, String> {
+    let all = state
+        .instances
+        .lock()
+        .map_err(|_| "instances lock poisoned".to_string())?;
+    Ok(instances::dashboard(&all))
+}
+
+/// Drops the cached research for the prompt's style, or the whole cache when no prompt is given.
+/// Returns how many entries were removed.
+#[tauri::command]
+pub fn clear_research_cache(
+    state: State<'_, AppState>,
+    prompt: Option<String>,
+) -> Result<usize, String> {
+    let mut cache = state
+        .research_cache
+        .lock()
+        .map_err(|_| "research cache lock poisoned".to_string())?;
+    let removed = match prompt {
+        Some(p) => match research_cache::style_key(&p) {
+            Some(key) => usize::from(cache.invalidate(&key)),
+            None => 0,
+        },
+        None => {
+            let n = cache.len();
+            cache.clear();
+            n
+        }
+    };
+    cache.save().map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+/// Stage-1 brief for prompts whose style (band/era) was researched recently; on a miss it runs
+/// stage 1 here and caches it. `None` leaves stage 1 to the pipeline (single-stage mode, prompts
+/// without a style key, or a failed research call, which the pipeline retries and then skips).
+async fn cached_research_brief(
+    state: &AppState,
+    model: &dyn ToneModel,
+    prompt: &str,
+) -> Result<Option<String>, String> {
+    if !two_stage_enabled() {
+        return Ok(None);
+    }
+    let Some(key) = research_cache::style_key(prompt) else {
+        return Ok(None);
+    };
+    let ttl = research_cache::cache_ttl();
+
+    let cached = state
+        .research_cache
+        .lock()
+        .map_err(|_| "research cache lock poisoned".to_string())?
+        .get(&key, ttl)
+        .map(str::to_string);
+    if cached.is_some() {
+        return Ok(cached);
+    }
+
+    let brief = match research_brief_with(model, prompt).await {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("warning: research for cache key {key} failed: {e}");
+            return Ok(None);
+        }
+    };
+
+    let mut cache = state
+        .research_cache
+        .lock()
+        .map_err(|_| "research cache lock poisoned".to_string())?;
+    cache.prune(ttl);
+    cache.insert(key, brief.clone());
+    if let Err(e) = cache.save() {
+        eprintln!("warning: failed to persist research cache: {e}");
+    }
+    Ok(Some(brief))
+}
+
 #[tauri::command]
 pub async fn generate_tone(
     app: AppHandle,
@@ -195,30 +397,30 @@ pub async fn generate_tone(
     base_params: Option<Vec<ParamChange>>,
     provider: Option<String>,
     model: Option<String>,
+    base_url: Option<String>,
 ) -> Result<PreviewResult, String> {
     let provider = provider
         .unwrap_or_else(|| "gemini".to_string())
         .trim()
         .to_ascii_lowercase();
-    if !matches!(
-        provider.as_str(),
-        "" | "gemini" | "google" | "google_ai" | "google-ai"
-    ) {
-        return Err(format!(
-            "provider {provider} is not wired yet; use Gemini for tone generation"
-        ));
-    }
+    let tone_provider = ToneProvider::from_id(&provider, base_url.as_deref())?;
+    let is_gemini = tone_provider == ToneProvider::Gemini;
 
-    let model = model
-        .and_then(|m| {
-            let trimmed = m.trim();
-            if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed.to_string())
-            }
-        })
-        .unwrap_or_else(|| std::env::var("GEMINI_MODEL").unwrap_or_else(|_| "gemini-2.5-pro".to_string()));
+    let model = model.and_then(|m| {
+        let trimmed = m.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    });
+    let model = match model {
+        Some(model) => model,
+        None if is_gemini => {
+            std::env::var("GEMINI_MODEL").unwrap_or_else(|_| "gemini-2.5-pro".to_string())
+        }
+        None => return Err(format!("set a model for provider {provider}")),
+    };
 
     let backend_env = std::env::var("GEMINI_BACKEND")
         .ok()
@@ -234,8 +436,10 @@ pub async fn generate_tone(
             | Some("google_oauth")
             | Some("googleai-oauth")
     ) || (backend_env.is_none() && vertex_model);
+    // Local OpenAI-style servers usually run without a key.
+    let key_optional = matches!(tone_provider, ToneProvider::OpenAiCompatible { .. });
 
-    let api_key = if skip_api_key {
+    let api_key = if is_gemini && skip_api_key {
         None
     } else {
         let pass = state
@@ -243,18 +447,44 @@ pub async fn generate_tone(
             .lock()
             .map_err(|_| "vault lock poisoned")?
             .passphrase
-            .clone()
-            .ok_or_else(|| "vault passphrase not set".to_string())?;
-        Some(
-            vault::load_api_key(&app, &pass, &provider)
+            .clone();
+        match pass {
+            None if key_optional => None,
+            None => return Err("vault passphrase not set".to_string()),
+            Some(pass) => match vault::load_api_key(&app, &pass, &provider)
                 .map_err(|e| e.to_string())?
-                .ok_or_else(|| "api key not set".to_string())?,
-        )
+            {
+                None if !key_optional => return Err("api key not set".to_string()),
+                key => key,
+            },
+        }
     };
 
-    let prompt = augment_prompt_with_param_meta(&state, &prompt);
+    let tone_model = ToneModelConfig {
+        provider: tone_provider,
+        model,
+        api_key,
+    }
+    .build();
+
+    let research_brief = cached_research_brief(&state, tone_model.as_ref(), &prompt).await?;
 
-    let tone = gemini_generate_tone(&model, ToneRequest { user_prompt: prompt }, api_key.as_deref())
+    state.with_instance(&target_fx_guid, |inst| {
+        inst.last_prompt = Some(prompt.clone());
+    })?;
+
+    let req = ToneRequest {
+        user_prompt: prompt,
+        meta: param_meta_from_state(&state),
+        research_brief,
+    };
+
+    // The research brief and reasoning go to the UI as the model writes them, so the chat shows
+    // it working instead of a spinner for the whole generation.
+    let mut on_progress = |event: ToneProgress| {
+        let _ = app.emit("brain://progress", event);
+    };
+    let tone = generate_tone_with(tone_model.as_ref(), req, Some(&mut on_progress))
         .await
         .map_err(|e| e.to_string())?;
 
@@ -264,23 +494,18 @@ pub async fn generate_tone(
         .map_err(|_| "index remap lock poisoned".to_string())?
         .clone();
 
+    let locked = locks_for(&state, &target_fx_guid)?;
+
     let mut params = sanitize_params(tone.params).map_err(|e| e.to_string())?;
-    if matches!(mode, MergeMode::ReplaceActive) {
-        params = apply_replace_active_cleaner(MergeMode::ReplaceActive, params);
-    }
+    let proposed = params.clone();
+    params = apply_replace_active_cleaner_locked(mode, params, &locked);
     params = apply_index_remap(params, &index_remap);
     params = sanitize_params(params).map_err(|e| e.to_string())?;
 
     let old = if let Some(v) = base_params {
         v
     } else {
-        state
-            .param_cache
-            .lock()
-            .map_err(|_| "cache lock poisoned".to_string())?
-            .get(&target_fx_guid)
-            .cloned()
-            .unwrap_or_default()
+        baseline_for(&state, &target_fx_guid, &params)?
     };
 
     let merged = if matches!(mode, MergeMode::Merge) {
@@ -288,7 +513,13 @@ pub async fn generate_tone(
     } else {
         params.clone()
     };
-    let d = diff_params(&old, &merged, &index_remap);
+    let schema = state
+        .param_schema
+        .lock()
+        .map_err(|_| "param schema lock poisoned".to_string())?
+        .clone();
+    let mut d = diff_params(&old, &merged, &schema);
+    d.extend(locked_diff_items(&old, &proposed, &locked, &index_remap, &schema));
 
     if !preview_only {
         apply_tone_inner(
@@ -297,6 +528,8 @@ pub async fn generate_tone(
             mode,
             params.clone(),
             format!("gen-{}", chrono_nanos()),
+            None,
+            None,
         )
         .await?;
     }
@@ -305,145 +538,630 @@ pub async fn generate_tone(
         reasoning: tone.reasoning,
         params,
         diff: d,
+        alternatives: tone.alternatives,
     })
 }
 
-fn augment_prompt_with_param_meta(state: &AppState, prompt: &str) -> String {
-    let enums = state
-        .param_enums
-        .lock()
-        .ok()
-        .map(|g| g.clone())
-        .unwrap_or_default();
-    let formats = state
-        .param_formats
-        .lock()
-        .ok()
-        .map(|g| g.clone())
-        .unwrap_or_default();
-    let samples = state
-        .param_format_samples
+fn param_meta_from_state(state: &AppState) -> ParamMeta {
+    ParamMeta {
+        enums: state
+            .param_enums
+            .lock()
+            .ok()
+            .map(|g| g.clone())
+            .unwrap_or_default(),
+        formats: state
+            .param_formats
+            .lock()
+            .ok()
+            .map(|g| g.clone())
+            .unwrap_or_default(),
+        samples: state
+            .param_format_samples
+            .lock()
+            .ok()
+            .map(|g| g.clone())
+            .unwrap_or_default(),
+        eq_band_hz: state
+            .param_eq_band_hz
+            .lock()
+            .ok()
+            .map(|g| g.clone())
+            .unwrap_or_default(),
+        kinds: state
+            .param_kinds
+            .lock()
+            .ok()
+            .map(|g| g.clone())
+            .unwrap_or_default(),
+        schema: state
+            .param_schema
+            .lock()
+            .ok()
+            .map(|g| g.clone())
+            .unwrap_or_default(),
+        tempo: state.project_tempo.lock().ok().and_then(|g| *g),
+    }
+}
+
+/// `ramp_ms` glides continuous params instead of jumping; the ack then arrives when it ends.
+/// `modules` writes only those parts of the tone, e.g. just the cab.
+#[tauri::command]
+pub async fn apply_tone(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    mode: MergeMode,
+    params: Vec<ParamChange>,
+    ramp_ms: Option<u32>,
+    modules: Option<Vec<ModuleId>>,
+) -> Result<String, String> {
+    let command_id = format!("cmd-{}", chrono_nanos());
+    apply_tone_inner(
+        &state,
+        &target_fx_guid,
+        mode,
+        params,
+        command_id.clone(),
+        ramp_ms,
+        modules,
+    )
+    .await?;
+    Ok(command_id)
+}
+
+/// Applies one tone to several FX at once (e.g. both guitar tracks of a double-tracked part) as a
+/// single REAPER undo step; the DLL answers with one `reaper://multi_ack`.
+#[tauri::command]
+pub async fn apply_tone_multi(
+    state: State<'_, AppState>,
+    target_fx_guids: Vec<String>,
+    mode: MergeMode,
+    params: Vec<ParamChange>,
+) -> Result<String, String> {
+    let targets = target_fx_guids
+        .iter()
+        .map(|guid| prepare_target(&state, guid, mode, params.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let command_id = format!("multi-{}", chrono_nanos());
+    let cmd = ClientCommand::SetToneMulti {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        targets,
+        allow_background_projects: false,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Restores the preset this FX had before its last apply. Snapshots are already cleaned and
+/// remapped, so they are sent as-is instead of going through `apply_tone_inner`.
+#[tauri::command]
+pub async fn undo_tone(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+) -> Result<String, String> {
+    let (params, locked) = state
+        .with_instance(&target_fx_guid, |inst| {
+            let prev = inst.undo.pop()?;
+            inst.param_cache = prev.clone();
+            inst.last_applied_at = Some(unix_secs());
+            Some((prev, inst.locks.clone()))
+        })?
+        .ok_or_else(|| "nothing to undo for this instance".to_string())?;
+
+    let index_remap = state
+        .index_remap
         .lock()
-        .ok()
-        .map(|g| g.clone())
-        .unwrap_or_default();
+        .map_err(|_| "index remap lock poisoned".to_string())?
+        .clone();
 
-    if enums.is_empty() && formats.is_empty() && samples.is_empty() {
-        return prompt.to_string();
-    }
+    let command_id = format!("undo-{}", chrono_nanos());
+    let cmd = ClientCommand::SetTone {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        mode: MergeMode::ReplaceActive,
+        params,
+        locked_params: locked
+            .iter()
+            .map(|i| index_remap.get(i).copied().unwrap_or(*i))
+            .collect(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
 
-    // Keep this compact; model gets the full list but in a machine-friendly shape.
-    let mut meta = String::new();
-    meta.push_str("\n\nPLUGIN PARAM META (from the current REAPER instance):\n");
-
-    // Only include the relevant cab/IR + a couple of mode selectors by default.
-    let include_enum = [84, 92, 99, 113, 5];
-    let mut enum_obj: HashMap<i32, Vec<(f32, String)>> = HashMap::new();
-    for idx in include_enum {
-        if let Some(opts) = enums.get(&idx) {
-            let mapped: Vec<(f32, String)> = opts
-                .iter()
-                .map(|o| (o.value, o.label.clone()))
-                .collect();
-            enum_obj.insert(idx, mapped);
-        }
+/// Reverts the last applied tone through REAPER's undo history (the DLL refuses when the top undo
+/// point is not one of ours), then re-reads the FX so the cached preset matches what REAPER holds.
+#[tauri::command]
+pub async fn undo_last_tone(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+) -> Result<String, String> {
+    let command_id = format!("reaper-undo-{}", chrono_nanos());
+    let undo = ClientCommand::UndoLastTone {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+    };
+    let read = ClientCommand::GetCurrentTone {
+        session_token: String::new(),
+        command_id: format!("read-{}", chrono_nanos()),
+        target_fx_guid,
+    };
+    for cmd in [undo, read] {
+        state
+            .tx
+            .send(UiCommand::SendToDll(cmd))
+            .await
+            .map_err(|_| "ws actor unavailable".to_string())?;
     }
+    Ok(command_id)
+}
 
-    let mut include_fmt: Vec<i32> = Vec::new();
-    include_fmt.extend([0, 1, 2]); // input/output gain + gate
-    include_fmt.extend(30..=51); // amp knobs
-    include_fmt.extend(54..=82); // graphic EQ bands
-    include_fmt.extend([87, 88, 89, 94, 95, 96]); // cab mic position/distance/level
-    include_fmt.extend([105, 106, 108]); // delay
-    include_fmt.extend([114, 115, 116, 117]); // reverb
-    include_fmt.sort_unstable();
-    include_fmt.dedup();
-
-    let mut fmt_obj: HashMap<i32, (String, String, String)> = HashMap::new();
-    for idx in include_fmt {
-        if let Some(t) = formats.get(&idx) {
-            fmt_obj.insert(idx, (t.min.clone(), t.mid.clone(), t.max.clone()));
-        }
+/// Puts the FX back to the values it had before the apply whose command id is `snapshot_id`, then
+/// re-reads it so the cached preset follows.
+#[tauri::command]
+pub async fn restore_snapshot(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    snapshot_id: String,
+) -> Result<String, String> {
+    let command_id = format!("reaper-restore-{}", chrono_nanos());
+    let restore = ClientCommand::RestoreSnapshot {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        snapshot_id,
+    };
+    let read = ClientCommand::GetCurrentTone {
+        session_token: String::new(),
+        command_id: format!("read-{}", chrono_nanos()),
+        target_fx_guid,
+    };
+    for cmd in [restore, read] {
+        state
+            .tx
+            .send(UiCommand::SendToDll(cmd))
+            .await
+            .map_err(|_| "ws actor unavailable".to_string())?;
     }
+    Ok(command_id)
+}
 
-    // JSON keeps token count lower than prose for large IR lists.
-    if !enum_obj.is_empty() {
-        if let Ok(j) = serde_json::to_string(&enum_obj) {
-            meta.push_str("ENUM_OPTIONS_JSON=");
-            meta.push_str(&j);
-            meta.push('\n');
-        }
-    }
-    if !fmt_obj.is_empty() {
-        if let Ok(j) = serde_json::to_string(&fmt_obj) {
-            meta.push_str("FORMATTED_VALUE_TRIPLETS_JSON=");
-            meta.push_str(&j);
-            meta.push('\n');
-        }
-    }
+/// Drives REAPER's own wet knob / delta solo for the FX (e.g. wet 0 to hear what the plugin adds).
+#[tauri::command]
+pub async fn set_fx_mix(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    wet: Option<f32>,
+    delta_solo: Option<bool>,
+) -> Result<String, String> {
+    let command_id = format!("mix-{}", chrono_nanos());
+    let cmd = ClientCommand::SetFxMix {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        wet,
+        delta_solo,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
 
-    // Optionally include formatted samples (norm->formatted) so the Rust resolver can convert
-    // human units (like dB) into normalized 0..1 values without the model having to guess.
-    // Keep this limited to the most tone-relevant parameters to avoid prompt bloat.
-    if !samples.is_empty() {
-        let mut sample_obj: HashMap<i32, Vec<(f32, String)>> = HashMap::new();
-        let mut include: Vec<i32> = Vec::new();
-        include.extend([0, 1]); // input/output gain
-        include.push(2); // Gate
-        include.extend(54..=82); // Graphic EQ bands
-        include.extend([29, 30, 31, 32, 33, 34, 35]); // Clean amp
-        include.extend(36..=43); // Rust amp
-        include.extend(44..=51); // Hot amp
-        include.extend([101, 105, 106, 108, 112, 113, 114, 115, 116, 117]); // Time FX
-        include.extend([83, 84, 85, 87, 88, 89, 92, 94, 95, 96, 99]); // Cab selectors (+ mic pos/dist/levels)
-
-        include.sort_unstable();
-        include.dedup();
-
-        for idx in include {
-            if let Some(v) = samples.get(&idx) {
-                let mapped: Vec<(f32, String)> = v
-                    .iter()
-                    .map(|s| (s.norm, s.formatted.clone()))
-                    .collect();
-                if !mapped.is_empty() {
-                    sample_obj.insert(idx, mapped);
-                }
-            }
-        }
+/// Switches the FX on/off and/or sets REAPER's wet knob for it (A/B, parallel blend); the
+/// readback arrives as `reaper://fx_state`.
+#[tauri::command]
+pub async fn set_fx_state(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    enabled: Option<bool>,
+    wet: Option<f32>,
+) -> Result<String, String> {
+    let command_id = format!("fx-state-{}", chrono_nanos());
+    let cmd = ClientCommand::SetFxState {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        enabled,
+        wet,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
 
-        if !sample_obj.is_empty() {
-            if let Ok(j) = serde_json::to_string(&sample_obj) {
-                meta.push_str("PARAM_FORMAT_SAMPLES_JSON=");
-                meta.push_str(&j);
-                meta.push('\n');
-            }
+/// Volume/pan/record monitoring of the track hosting the FX; the readback arrives as
+/// `reaper://track_controls`.
+#[tauri::command]
+pub async fn set_track_controls(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    volume_db: Option<f32>,
+    pan: Option<f32>,
+    rec_monitor: Option<bool>,
+) -> Result<String, String> {
+    let command_id = format!("track-{}", chrono_nanos());
+    let cmd = ClientCommand::SetTrackControls {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        volume_db,
+        pan,
+        rec_monitor,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Bounces a few seconds of the mix (from the loop start by default) to a temp WAV; the path
+/// arrives as `reaper://preview_rendered`.
+#[tauri::command]
+pub async fn render_preview(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    start: Option<f64>,
+    length: Option<f64>,
+) -> Result<String, String> {
+    let command_id = format!("render-{}", chrono_nanos());
+    let cmd = ClientCommand::RenderPreview {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        start,
+        length,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Starts (`playing = true`) or stops REAPER's transport; the state arrives as
+/// `reaper://transport`.
+#[tauri::command]
+pub async fn set_playing(state: State<'_, AppState>, playing: bool) -> Result<String, String> {
+    let command_id = format!("transport-{}", chrono_nanos());
+    let cmd = if playing {
+        ClientCommand::Play {
+            session_token: String::new(),
+            command_id: command_id.clone(),
         }
-    }
+    } else {
+        ClientCommand::Stop {
+            session_token: String::new(),
+            command_id: command_id.clone(),
+        }
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
 
-    meta.push_str("Use these option labels when choosing Cab Type (84) and Mic IR (92/99). Set the parameter value close to the provided float for the desired label.\n");
-    meta.push_str("For continuous cab mic controls (Position/Distance), the formatted triplets can hint at units/direction; use them to pick sensible normalized values.\n");
-    meta.push_str("You may specify some values in human units (like dB) if PARAM_FORMAT_SAMPLES_JSON is present; the backend will translate them to 0..1.\n");
+/// Loops `start..end` seconds with repeat on, ready for `set_playing`.
+#[tauri::command]
+pub async fn set_loop(state: State<'_, AppState>, start: f64, end: f64) -> Result<String, String> {
+    let command_id = format!("loop-{}", chrono_nanos());
+    let cmd = ClientCommand::SetLoop {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        start,
+        end,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
 
-    // Hard cap to prevent runaway prompts if IR lists are enormous.
-    const MAX_EXTRA_CHARS: usize = 25_000;
-    if meta.len() > MAX_EXTRA_CHARS {
-        meta.truncate(MAX_EXTRA_CHARS);
-        meta.push_str("\n...(meta truncated)\n");
-    }
+/// Adds the plugin to a track (REAPER's selected one by default). The new instance arrives with
+/// the next `reaper://handshake`; failures as `reaper://error`.
+#[tauri::command]
+pub async fn insert_fx(
+    state: State<'_, AppState>,
+    track_guid: Option<String>,
+    fx_name: Option<String>,
+) -> Result<String, String> {
+    let command_id = format!("insert-{}", chrono_nanos());
+    let cmd = ClientCommand::InsertFx {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        track_guid,
+        fx_name,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
 
-    format!("{prompt}{meta}")
+/// Asks the DLL for the live values of the FX; the reply arrives as `reaper://current_tone` and
+/// also refreshes the backend's cached preset for that FX.
+#[tauri::command]
+pub async fn get_current_tone(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+) -> Result<String, String> {
+    let command_id = format!("read-{}", chrono_nanos());
+    let cmd = ClientCommand::GetCurrentTone {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
 }
 
+/// Dry run of `apply_tone`: the DLL resolves the tone against the live FX and answers with
+/// `reaper://preview` (current and new value of each param), writing nothing.
 #[tauri::command]
-pub async fn apply_tone(
+pub async fn preview_tone(
     state: State<'_, AppState>,
     target_fx_guid: String,
     mode: MergeMode,
     params: Vec<ParamChange>,
 ) -> Result<String, String> {
-    let command_id = format!("cmd-{}", chrono_nanos());
-    apply_tone_inner(&state, &target_fx_guid, mode, params, command_id.clone()).await?;
+    let index_remap = state
+        .index_remap
+        .lock()
+        .map_err(|_| "index remap lock poisoned".to_string())?
+        .clone();
+    let locked = locks_for(&state, &target_fx_guid)?;
+    let mut params = sanitize_params(params).map_err(|e| e.to_string())?;
+    params = apply_index_remap(params, &index_remap);
+    params = sanitize_params(params).map_err(|e| e.to_string())?;
+
+    let command_id = format!("preview-{}", chrono_nanos());
+    let cmd = ClientCommand::PreviewTone {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        mode,
+        params,
+        locked_params: locked
+            .iter()
+            .map(|i| index_remap.get(i).copied().unwrap_or(*i))
+            .collect(),
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Has the DLL check the tone against the live FX (unknown indices, off-option enum values,
+/// toggles left off) and answer with `reaper://tone_validation`, writing nothing.
+#[tauri::command]
+pub async fn validate_tone(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    params: Vec<ParamChange>,
+) -> Result<String, String> {
+    let index_remap = state
+        .index_remap
+        .lock()
+        .map_err(|_| "index remap lock poisoned".to_string())?
+        .clone();
+    let params = apply_index_remap(params, &index_remap);
+
+    let command_id = format!("validate-{}", chrono_nanos());
+    let cmd = ClientCommand::ValidateTone {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        params,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Stores the FX's current values as a named preset in the sidecar's presets folder.
+#[tauri::command]
+pub async fn save_preset(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    name: String,
+) -> Result<String, String> {
+    let command_id = format!("preset-save-{}", chrono_nanos());
+    let cmd = ClientCommand::SavePreset {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+        name,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Loads a saved preset (or, failing that, one of the plugin's own), then re-reads the FX so the
+/// cached preset matches what REAPER holds.
+#[tauri::command]
+pub async fn load_preset(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    name: String,
+) -> Result<String, String> {
+    let command_id = format!("preset-load-{}", chrono_nanos());
+    let load = ClientCommand::LoadPreset {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid: target_fx_guid.clone(),
+        name,
+    };
+    let read = ClientCommand::GetCurrentTone {
+        session_token: String::new(),
+        command_id: format!("read-{}", chrono_nanos()),
+        target_fx_guid,
+    };
+    for cmd in [load, read] {
+        state
+            .tx
+            .send(UiCommand::SendToDll(cmd))
+            .await
+            .map_err(|_| "ws actor unavailable".to_string())?;
+    }
+    Ok(command_id)
+}
+
+/// Asks the DLL for the saved preset names; the reply arrives as `reaper://preset_list`.
+#[tauri::command]
+pub async fn list_presets(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+) -> Result<String, String> {
+    let command_id = format!("preset-list-{}", chrono_nanos());
+    let cmd = ClientCommand::ListPresets {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        target_fx_guid,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Pages through the options of the enum at `index` whose labels contain `query`, e.g. an IR list
+/// the handshake only sent the start of; the reply arrives as `reaper://param_enum_page`.
+#[tauri::command]
+pub async fn search_enum_labels(
+    state: State<'_, AppState>,
+    index: i32,
+    query: String,
+    offset: Option<usize>,
+) -> Result<String, String> {
+    let command_id = format!("enum-search-{}", chrono_nanos());
+    let cmd = ClientCommand::SearchEnumLabels {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        index,
+        query,
+        offset: offset.unwrap_or(0),
+        limit: None,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+    Ok(command_id)
+}
+
+/// Watches `indices` on the FX (empty stops watching it); moves arrive as `reaper://param_changed`
+/// and keep the backend's cached preset in step with the plugin GUI.
+#[tauri::command]
+pub async fn subscribe_params(
+    state: State<'_, AppState>,
+    target_fx_guid: String,
+    indices: Vec<i32>,
+) -> Result<(), String> {
+    state
+        .tx
+        .send(UiCommand::SendToDll(ClientCommand::Subscribe {
+            session_token: String::new(),
+            target_fx_guid,
+            indices,
+        }))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())
+}
+
+/// Asks the DLL for its runtime counters; the reply arrives as `reaper://metrics`.
+#[tauri::command]
+pub async fn request_metrics(state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .tx
+        .send(UiCommand::SendToDll(ClientCommand::GetMetrics {
+            session_token: String::new(),
+        }))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())
+}
+
+/// Asks the DLL for its recent log events and timings; the reply arrives as
+/// `reaper://diagnostics`.
+#[tauri::command]
+pub async fn request_diagnostics(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<(), String> {
+    state
+        .tx
+        .send(UiCommand::SendToDll(ClientCommand::GetDiagnostics {
+            session_token: String::new(),
+            limit,
+        }))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())
+}
+
+/// Switches tones by instance: enables `enable_fx_guid` and disables `disable_fx_guid`.
+#[tauri::command]
+pub async fn switch_fx_pair(
+    state: State<'_, AppState>,
+    enable_fx_guid: String,
+    disable_fx_guid: String,
+) -> Result<String, String> {
+    let command_id = format!("switch-{}", chrono_nanos());
+    let cmd = ClientCommand::SwitchFxPair {
+        session_token: String::new(),
+        command_id: command_id.clone(),
+        enable_fx_guid,
+        disable_fx_guid,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
     Ok(command_id)
 }
 
@@ -453,47 +1171,78 @@ async fn apply_tone_inner(
     mode: MergeMode,
     params: Vec<ParamChange>,
     command_id: String,
+    ramp_ms: Option<u32>,
+    modules: Option<Vec<ModuleId>>,
 ) -> Result<(), String> {
+    let target = prepare_target(state, target_fx_guid, mode, params)?;
+    let cmd = ClientCommand::SetTone {
+        session_token: String::new(),
+        command_id,
+        target_fx_guid: target.target_fx_guid,
+        mode: target.mode,
+        params: target.params,
+        locked_params: target.locked_params,
+        ramp_ms,
+        atomic: false,
+        apply_at: None,
+        modules,
+        allow_background_projects: false,
+    };
+    state
+        .tx
+        .send(UiCommand::SendToDll(cmd))
+        .await
+        .map_err(|_| "ws actor unavailable".to_string())?;
+
+    Ok(())
+}
+
+/// Cleans, remaps and caches `params` for one FX, the way every apply sends them.
+fn prepare_target(
+    state: &AppState,
+    target_fx_guid: &str,
+    mode: MergeMode,
+    params: Vec<ParamChange>,
+) -> Result<ToneTarget, String> {
     let index_remap = state
         .index_remap
         .lock()
         .map_err(|_| "index remap lock poisoned".to_string())?
         .clone();
 
-    let mut params = sanitize_params(params).map_err(|e| e.to_string())?;       
-    params = apply_replace_active_cleaner(mode, params);
+    let locked = locks_for(state, target_fx_guid)?;
+
+    let mut params = sanitize_params(params).map_err(|e| e.to_string())?;
+    params = apply_replace_active_cleaner_locked(mode, params, &locked);
     params = apply_index_remap(params, &index_remap);
     params = sanitize_params(params).map_err(|e| e.to_string())?;
 
     // Cache the full effective preset so later diffs are stable (merge should accumulate).
-    {
-        let mut cache = state
-            .param_cache
-            .lock()
-            .map_err(|_| "cache lock poisoned".to_string())?;
-        let prev = cache.get(target_fx_guid).cloned().unwrap_or_default();
-        let next = if matches!(mode, MergeMode::Merge) {
+    state.with_instance(target_fx_guid, |inst| {
+        let prev = std::mem::take(&mut inst.param_cache);
+        inst.param_cache = if matches!(mode, MergeMode::Merge) {
             merge_params(&prev, &params)
         } else {
             params.clone()
         };
-        cache.insert(target_fx_guid.to_string(), next);
-    }
+        if !prev.is_empty() {
+            inst.undo.push(prev);
+            if inst.undo.len() > UNDO_DEPTH {
+                inst.undo.remove(0);
+            }
+        }
+        inst.last_applied_at = Some(unix_secs());
+    })?;
 
-    let cmd = ClientCommand::SetTone {
-        session_token: String::new(),
-        command_id,
+    Ok(ToneTarget {
         target_fx_guid: target_fx_guid.to_string(),
         mode,
-        params: params.clone(),
-    };
-    state
-        .tx
-        .send(UiCommand::SendToDll(cmd))
-        .await
-        .map_err(|_| "ws actor unavailable".to_string())?;
-
-    Ok(())
+        params,
+        locked_params: locked
+            .iter()
+            .map(|i| index_remap.get(i).copied().unwrap_or(*i))
+            .collect(),
+    })
 }
 
 fn apply_index_remap(params: Vec<ParamChange>, index_remap: &HashMap<i32, i32>) -> Vec<ParamChange> {
@@ -511,6 +1260,10 @@ fn apply_index_remap(params: Vec<ParamChange>, index_remap: &HashMap<i32, i32>)
         .collect()
 }
 
+fn unix_secs() -> u64 {
+    (chrono_nanos() / 1_000_000_000) as u64
+}
+
 fn chrono_nanos() -> u128 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
@@ -6,6 +6,7 @@ mod tauri_utils;
 #[cfg(windows)]
 fn main() {
     use crate::tauri_utils::app_state::{AppState, VaultState};
+    use brain_core::research_cache::ResearchCache;
     use std::collections::HashMap;
     use std::sync::Mutex;
     use tauri::Manager;
@@ -25,15 +26,26 @@ fn main() {
             app.handle()
                 .plugin(tauri_plugin_stronghold::Builder::with_argon2(&salt_path).build())?;
 
+            let research_cache_path = app
+                .path()
+                .app_local_data_dir()
+                .expect("could not resolve app local data path")
+                .join("research_cache.json");
+
             let (tx, rx) = mpsc::channel(32);
             app.manage(AppState {
                 tx,
-                param_cache: Mutex::new(HashMap::new()),
+                instances: Mutex::new(HashMap::new()),
                 param_enums: Mutex::new(HashMap::new()),
                 param_formats: Mutex::new(HashMap::new()),
                 param_format_samples: Mutex::new(HashMap::new()),
+                param_eq_band_hz: Mutex::new(HashMap::new()),
+                param_kinds: Mutex::new(HashMap::new()),
+                param_schema: Mutex::new(HashMap::new()),
+                project_tempo: Mutex::new(None),
                 vault: Mutex::new(VaultState::default()),
                 index_remap: Mutex::new(HashMap::new()),
+                research_cache: Mutex::new(ResearchCache::load(&research_cache_path)),
             });
 
             let handle = app.handle().clone();
@@ -46,6 +58,7 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             commands::connect_ws,
             commands::disconnect_ws,
+            commands::discover_sidecars,
             commands::set_vault_passphrase,
             commands::has_api_key,
             commands::list_api_key_presence,
@@ -54,8 +67,35 @@ fn main() {
             commands::get_index_remap,
             commands::set_index_remap,
             commands::reset_index_remap,
+            commands::get_param_locks,
+            commands::set_param_locks,
+            commands::clear_param_locks,
+            commands::clear_research_cache,
+            commands::get_instance_dashboard,
             commands::generate_tone,
-            commands::apply_tone
+            commands::apply_tone,
+            commands::apply_tone_multi,
+            commands::undo_tone,
+            commands::undo_last_tone,
+            commands::restore_snapshot,
+            commands::set_fx_mix,
+            commands::set_fx_state,
+            commands::set_track_controls,
+            commands::insert_fx,
+            commands::set_playing,
+            commands::set_loop,
+            commands::render_preview,
+            commands::switch_fx_pair,
+            commands::request_metrics,
+            commands::request_diagnostics,
+            commands::get_current_tone,
+            commands::preview_tone,
+            commands::validate_tone,
+            commands::subscribe_params,
+            commands::save_preset,
+            commands::load_preset,
+            commands::list_presets,
+            commands::search_enum_labels
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
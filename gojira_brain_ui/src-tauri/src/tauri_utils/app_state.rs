@@ -1,19 +1,104 @@
 use brain_core::protocol::{
-    ClientCommand, ParamChange, ParamEnumOption, ParamFormatSample, ParamFormatTriplet,
+    ClientCommand, GojiraInstance, ParamChange, ParamEnumOption, ParamFormatSample,
+    ParamFormatTriplet, ParamKind, ParamSchemaEntry, ProjectTempo,
 };
-use std::collections::HashMap;
+use brain_core::research_cache::ResearchCache;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 use tokio::sync::mpsc;
 
 pub struct AppState {
     pub tx: mpsc::Sender<UiCommand>,
-    pub param_cache: Mutex<HashMap<String, Vec<ParamChange>>>,
+    /// Per-FX state (cache, locks, undo, last prompt, status) keyed by FX GUID.
+    pub instances: Mutex<HashMap<String, InstanceState>>,
     pub param_enums: Mutex<HashMap<i32, Vec<ParamEnumOption>>>,
     pub param_formats: Mutex<HashMap<i32, ParamFormatTriplet>>,
     pub param_format_samples: Mutex<HashMap<i32, Vec<ParamFormatSample>>>,
+    /// Graphic-EQ band index -> center frequency (Hz), as reported by the handshake.
+    pub param_eq_band_hz: Mutex<HashMap<i32, f32>>,
+    pub param_kinds: Mutex<HashMap<i32, ParamKind>>,
+    /// Name, kind and section per actual index, as reported by the handshake.
+    pub param_schema: Mutex<HashMap<i32, ParamSchemaEntry>>,
+    pub project_tempo: Mutex<Option<ProjectTempo>>,
     pub vault: Mutex<VaultState>,
     /// Index translation (canonical -> actual) for plugin version drift.
     pub index_remap: Mutex<HashMap<i32, i32>>,
+    /// Stage-1 research briefs by style key, persisted in the app data dir.
+    pub research_cache: Mutex<ResearchCache>,
+}
+
+impl AppState {
+    /// Runs `f` on the state of `fx_guid`, creating an empty entry for a GUID seen the first time.
+    pub fn with_instance<R>(
+        &self,
+        fx_guid: &str,
+        f: impl FnOnce(&mut InstanceState) -> R,
+    ) -> Result<R, String> {
+        let mut instances = self
+            .instances
+            .lock()
+            .map_err(|_| "instances lock poisoned".to_string())?;
+        Ok(f(instances.entry(fx_guid.to_string()).or_default()))
+    }
+
+    /// Marks the instances listed by a handshake online and every other known one missing.
+    /// Entries are kept either way so cache/locks survive a project switch and back.
+    pub fn sync_instances(&self, live: &[GojiraInstance]) {
+        let Ok(mut instances) = self.instances.lock() else {
+            return;
+        };
+        for inst in instances.values_mut() {
+            inst.status = InstanceStatus::Missing;
+        }
+        for info in live {
+            let inst = instances.entry(info.fx_guid.clone()).or_default();
+            inst.info = Some(info.clone());
+            inst.status = InstanceStatus::Online;
+        }
+    }
+
+    pub fn mark_instances_disconnected(&self) {
+        if let Ok(mut instances) = self.instances.lock() {
+            for inst in instances.values_mut() {
+                inst.status = InstanceStatus::Disconnected;
+            }
+        }
+    }
+}
+
+/// How many previous presets `InstanceState::undo` keeps per FX.
+pub const UNDO_DEPTH: usize = 20;
+
+#[derive(Default, Clone, Debug)]
+pub struct InstanceState {
+    /// Track/FX names and position from the latest handshake that listed this FX.
+    pub info: Option<GojiraInstance>,
+    pub status: InstanceStatus,
+    /// Full effective preset last sent to this FX (after cleaning and index remap).
+    pub param_cache: Vec<ParamChange>,
+    /// Pinned (canonical) param indices; AI changes never touch these.
+    pub locks: HashSet<i32>,
+    /// Every tone param as last read back from the plugin (`get_current_tone`, then kept up to
+    /// date by `param_changed` pushes).
+    pub live_params: Vec<ParamChange>,
+    /// Earlier `param_cache` snapshots, most recent last.
+    pub undo: Vec<Vec<ParamChange>>,
+    pub last_prompt: Option<String>,
+    /// Unix seconds of the last SetTone sent to this FX.
+    pub last_applied_at: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceStatus {
+    /// Listed by the latest handshake.
+    Online,
+    /// Known from earlier, but not listed by the latest handshake (deleted or other project).
+    Missing,
+    /// No live connection to the DLL.
+    #[default]
+    Disconnected,
 }
 
 #[derive(Default)]
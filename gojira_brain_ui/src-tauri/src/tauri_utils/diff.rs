@@ -1,29 +1,28 @@
-use brain_core::param_map;
-use brain_core::protocol::ParamChange;
-use std::collections::HashMap;
+use brain_core::protocol::{ParamChange, ParamSchemaEntry};
+use std::collections::{HashMap, HashSet};
 
 #[derive(serde::Serialize, Debug, Clone)]
 pub struct DiffItem {
     pub label: String,
     pub index: i32,
-    pub old_value: Option<f32>,
-    pub new_value: Option<f32>,
+    pub old_value: Option<f64>,
+    pub new_value: Option<f64>,
+    /// The index is pinned: the change was proposed but will not be sent.
+    pub locked: bool,
 }
 
 pub fn diff_params(
     old_params: &[ParamChange],
     new_params: &[ParamChange],
-    index_remap: &HashMap<i32, i32>,
+    schema: &HashMap<i32, ParamSchemaEntry>,
 ) -> Vec<DiffItem> {
-    let old: HashMap<i32, f32> = old_params.iter().map(|p| (p.index, p.value)).collect();
-    let new: HashMap<i32, f32> = new_params.iter().map(|p| (p.index, p.value)).collect();
+    let old: HashMap<i32, f64> = old_params.iter().map(|p| (p.index, p.value)).collect();
+    let new: HashMap<i32, f64> = new_params.iter().map(|p| (p.index, p.value)).collect();
 
     let mut keys: Vec<i32> = old.keys().chain(new.keys()).copied().collect();
     keys.sort_unstable();
     keys.dedup();
 
-    let reverse = reverse_index_remap(index_remap);
-
     keys.into_iter()
         .filter_map(|idx| {
             let o = old.get(&idx).copied();
@@ -32,48 +31,43 @@ pub fn diff_params(
                 return None;
             }
             Some(DiffItem {
-                label: label_for_index(idx, &reverse).to_string(),
+                label: label_for_index(idx, schema).to_string(),
                 index: idx,
                 old_value: o,
                 new_value: n,
+                locked: false,
             })
         })
         .collect()
 }
 
-fn label_for_index(index: i32, reverse_index_remap: &HashMap<i32, i32>) -> &'static str {
-    let canonical = reverse_index_remap.get(&index).copied().unwrap_or(index);
-    match canonical {
-        param_map::global::INPUT_GAIN => "Global: Input Gain",
-        param_map::global::OUTPUT_GAIN => "Global: Output Gain",
-        param_map::global::NOISE_GATE => "Global: Noise Gate",
-        param_map::selectors::AMP_TYPE_INDEX => "Amp: Type Select",
-        param_map::pedals::overdrive::ACTIVE => "Overdrive: Active",
-        param_map::pedals::overdrive::DRIVE => "Overdrive: Drive",
-        param_map::pedals::overdrive::TONE => "Overdrive: Tone",
-        param_map::pedals::overdrive::LEVEL => "Overdrive: Level",
-        param_map::pedals::delay::ACTIVE => "Delay: Active",
-        param_map::pedals::delay::MIX => "Delay: Mix",
-        param_map::pedals::delay::TIME => "Delay: Time",
-        param_map::pedals::reverb::ACTIVE => "Reverb: Active",
-        param_map::pedals::reverb::MODE => "Reverb: Mode",
-        param_map::pedals::reverb::MIX => "Reverb: Mix",
-        param_map::pedals::reverb::TIME => "Reverb: Time",
-        param_map::pedals::reverb::LOW_CUT => "Reverb: Low Cut",
-        param_map::pedals::reverb::HIGH_CUT => "Reverb: High Cut",
-        param_map::cab::ACTIVE => "Cab: Active",
-        param_map::cab::TYPE_SELECTOR => "Cab: Type",
-        param_map::cab::AMP_LINKED => "Cab: Amp Linked",
-        param_map::cab::mic1::IR_SEL => "Cab 1: Mic IR",
-        param_map::cab::mic2::IR_SEL => "Cab 2: Mic IR",
-        _ => "Param",
-    }
+/// Diff rows for proposed changes that were dropped because their index is locked.
+pub fn locked_diff_items(
+    old_params: &[ParamChange],
+    proposed: &[ParamChange],
+    locked: &HashSet<i32>,
+    index_remap: &HashMap<i32, i32>,
+    schema: &HashMap<i32, ParamSchemaEntry>,
+) -> Vec<DiffItem> {
+    let old: HashMap<i32, f64> = old_params.iter().map(|p| (p.index, p.value)).collect();
+
+    proposed
+        .iter()
+        .filter(|p| locked.contains(&p.index))
+        .map(|p| {
+            let actual = index_remap.get(&p.index).copied().unwrap_or(p.index);
+            DiffItem {
+                label: label_for_index(actual, schema).to_string(),
+                index: actual,
+                old_value: old.get(&actual).copied(),
+                new_value: Some(p.value),
+                locked: true,
+            }
+        })
+        .collect()
 }
 
-fn reverse_index_remap(index_remap: &HashMap<i32, i32>) -> HashMap<i32, i32> {
-    let mut out = HashMap::new();
-    for (canonical, actual) in index_remap {
-        out.insert(*actual, *canonical);
-    }
-    out
+/// The schema is keyed by the index the plugin reports, so a remapped param keeps its own name.
+fn label_for_index(index: i32, schema: &HashMap<i32, ParamSchemaEntry>) -> &str {
+    schema.get(&index).map_or("Param", |p| p.name.as_str())
 }
@@ -0,0 +1,137 @@
+use brain_core::protocol::GojiraInstance;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::tauri_utils::app_state::{InstanceState, InstanceStatus};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MovedInstance {
+    pub fx_guid: String,
+    pub from_track_guid: String,
+    pub from_fx_index: i32,
+    pub to_track_guid: String,
+    pub to_fx_index: i32,
+}
+
+/// Payload of `reaper://instances_updated`: the fresh list plus what changed relative to the
+/// previous one. Targets are bound by FX GUID, so a moved instance keeps its cache/locks.
+#[derive(Serialize, Clone, Debug)]
+pub struct InstancesUpdatedPayload {
+    pub instances: Vec<GojiraInstance>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub moved: Vec<MovedInstance>,
+}
+
+pub fn reconcile(prev: &[GojiraInstance], next: &[GojiraInstance]) -> InstancesUpdatedPayload {
+    let prev_by_guid: HashMap<&str, &GojiraInstance> =
+        prev.iter().map(|i| (i.fx_guid.as_str(), i)).collect();
+    let next_by_guid: HashMap<&str, &GojiraInstance> =
+        next.iter().map(|i| (i.fx_guid.as_str(), i)).collect();
+
+    let mut added = Vec::new();
+    let mut moved = Vec::new();
+    for inst in next {
+        match prev_by_guid.get(inst.fx_guid.as_str()) {
+            None => added.push(inst.fx_guid.clone()),
+            Some(old) => {
+                if old.track_guid != inst.track_guid
+                    || old.last_known_fx_index != inst.last_known_fx_index
+                {
+                    moved.push(MovedInstance {
+                        fx_guid: inst.fx_guid.clone(),
+                        from_track_guid: old.track_guid.clone(),
+                        from_fx_index: old.last_known_fx_index,
+                        to_track_guid: inst.track_guid.clone(),
+                        to_fx_index: inst.last_known_fx_index,
+                    });
+                }
+            }
+        }
+    }
+    let removed = prev
+        .iter()
+        .filter(|i| !next_by_guid.contains_key(i.fx_guid.as_str()))
+        .map(|i| i.fx_guid.clone())
+        .collect();
+
+    InstancesUpdatedPayload {
+        instances: next.to_vec(),
+        added,
+        removed,
+        moved,
+    }
+}
+
+/// The instance list after a `project_changed` delta: removed ones dropped, renamed ones replaced
+/// in place, added ones appended.
+pub fn apply_delta(
+    prev: &[GojiraInstance],
+    added: &[GojiraInstance],
+    removed: &[GojiraInstance],
+    renamed: &[GojiraInstance],
+) -> Vec<GojiraInstance> {
+    let mut next: Vec<GojiraInstance> = prev
+        .iter()
+        .filter(|i| !removed.iter().any(|r| r.fx_guid == i.fx_guid))
+        .map(|i| {
+            renamed
+                .iter()
+                .find(|r| r.fx_guid == i.fx_guid)
+                .unwrap_or(i)
+                .clone()
+        })
+        .collect();
+    for inst in added {
+        if !next.iter().any(|i| i.fx_guid == inst.fx_guid) {
+            next.push(inst.clone());
+        }
+    }
+    next
+}
+
+/// One row of `get_instance_dashboard`.
+#[derive(Serialize, Clone, Debug)]
+pub struct InstanceDashboardEntry {
+    pub fx_guid: String,
+    pub track_name: Option<String>,
+    pub fx_name: Option<String>,
+    pub fx_index: Option<i32>,
+    pub status: InstanceStatus,
+    pub param_count: usize,
+    pub locked_params: Vec<i32>,
+    pub undo_depth: usize,
+    pub last_prompt: Option<String>,
+    pub last_applied_at: Option<u64>,
+}
+
+/// Every known instance, online ones first, then by track name and FX slot.
+pub fn dashboard(instances: &HashMap<String, InstanceState>) -> Vec<InstanceDashboardEntry> {
+    let mut rows: Vec<InstanceDashboardEntry> = instances
+        .iter()
+        .map(|(fx_guid, inst)| {
+            let mut locked_params: Vec<i32> = inst.locks.iter().copied().collect();
+            locked_params.sort_unstable();
+            InstanceDashboardEntry {
+                fx_guid: fx_guid.clone(),
+                track_name: inst.info.as_ref().map(|i| i.track_name.clone()),
+                fx_name: inst.info.as_ref().map(|i| i.fx_name.clone()),
+                fx_index: inst.info.as_ref().map(|i| i.last_known_fx_index),
+                status: inst.status,
+                param_count: inst.param_cache.len(),
+                locked_params,
+                undo_depth: inst.undo.len(),
+                last_prompt: inst.last_prompt.clone(),
+                last_applied_at: inst.last_applied_at,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        (a.status != InstanceStatus::Online)
+            .cmp(&(b.status != InstanceStatus::Online))
+            .then_with(|| a.track_name.cmp(&b.track_name))
+            .then_with(|| a.fx_index.cmp(&b.fx_index))
+            .then_with(|| a.fx_guid.cmp(&b.fx_guid))
+    });
+    rows
+}
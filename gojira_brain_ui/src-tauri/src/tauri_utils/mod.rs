@@ -1,5 +1,6 @@
 pub mod app_state;
 pub mod diff;
+pub mod instances;
 pub mod vault;
 pub mod ws_actor;
 
@@ -1,33 +1,211 @@
-use brain_core::protocol::{ClientCommand, ServerMessage};
+use brain_core::protocol::{
+    ClientCommand, Encoding, Frame, GojiraInstance, HandshakeMeta, ParamChange, ParamOp,
+    ServerMessage, PAIRING_KEY_HEADER, PIPE_URL_PREFIX,
+};
 use futures_util::{SinkExt, StreamExt};
+use interprocess::local_socket::tokio::prelude::*;
+use interprocess::local_socket::GenericNamespaced;
 use serde::Serialize;
 use std::collections::VecDeque;
-use std::time::Duration;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
 
 use crate::commands::HandshakePayload;
 use crate::tauri_utils::app_state::UiCommand;
+use crate::tauri_utils::instances;
 use tauri::Manager;
 
-const WS_URL: &str = "ws://127.0.0.1:9001";
+/// How often the actor pings the DLL's main loop once handshaken.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// No pong for this long: the socket is up but REAPER's main thread is stuck (modal dialog,
+/// render, crash in progress). Reported as `unresponsive` until a pong arrives again.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(15);
 
 #[derive(Serialize, Clone)]
 struct StatusEvent {
     status: &'static str,
     retry_in: Option<u64>,
+    /// Where the actor is connecting, so a port mismatch with the DLL is visible in the UI.
+    url: String,
+}
+
+/// Re-read before every attempt so editing the sidecar config only needs a reconnect.
+fn ws_url() -> String {
+    brain_core::sidecar::ws_url().unwrap_or_else(|e| {
+        eprintln!("warning: {e}; using the default address");
+        brain_core::protocol::SidecarConfig::default().ws_url()
+    })
+}
+
+/// Upgrade request carrying the pairing key. Read per attempt like the URL, so a key the DLL
+/// writes on its first start is picked up on the next retry.
+fn connect_request(url: &str) -> Result<Request, tokio_tungstenite::tungstenite::Error> {
+    let mut req = url.into_client_request()?;
+    match brain_core::sidecar::pairing_key() {
+        Ok(Some(key)) => match HeaderValue::from_str(&key) {
+            Ok(value) => {
+                req.headers_mut().insert(PAIRING_KEY_HEADER, value);
+            }
+            Err(_) => eprintln!("warning: pairing key is not a valid header value"),
+        },
+        Ok(None) => {}
+        Err(e) => eprintln!("warning: {e}"),
+    }
+    let encoding = brain_core::sidecar::encoding().unwrap_or_else(|e| {
+        eprintln!("warning: {e}");
+        Encoding::default()
+    });
+    req.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_static(encoding.subprotocol()),
+    );
+    Ok(req)
+}
+
+/// Pinned-certificate TLS for a `wss://` DLL; `None` leaves plain `ws://` to tokio-tungstenite.
+#[cfg(feature = "tls")]
+fn tls_connector(url: &str) -> Result<Option<Connector>, String> {
+    if !url.starts_with("wss://") {
+        return Ok(None);
+    }
+    let config = brain_core::sidecar::load_config()?;
+    let client_config = brain_core::sidecar::tls_client_config(&config)?;
+    Ok(Some(Connector::Rustls(client_config)))
+}
+
+#[cfg(not(feature = "tls"))]
+fn tls_connector(_url: &str) -> Result<Option<Connector>, String> {
+    Ok(None)
+}
+
+/// The DLL connection under the websocket: TCP, or the local pipe when the sidecar config selects
+/// that transport (`pipe:<name>` URLs).
+enum DllStream {
+    Tcp(TcpStream),
+    Pipe(LocalSocketStream),
+}
+
+impl AsyncRead for DllStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            DllStream::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+            DllStream::Pipe(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for DllStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            DllStream::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+            DllStream::Pipe(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            DllStream::Tcp(s) => Pin::new(s).poll_flush(cx),
+            DllStream::Pipe(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            DllStream::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+            DllStream::Pipe(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+type DllSocket = WebSocketStream<MaybeTlsStream<DllStream>>;
+
+/// Dials the DLL: the local pipe for `pipe:<name>`, else TCP, with TLS for `wss://`. Also returns
+/// the encoding the DLL answered the subprotocol offer with; an older DLL answers none and gets
+/// JSON.
+async fn connect(
+    url: &str,
+) -> Result<(DllSocket, Encoding), tokio_tungstenite::tungstenite::Error> {
+    let connector = tls_connector(url).unwrap_or_else(|e| {
+        eprintln!("warning: {e}");
+        None
+    });
+    let (stream, req) = match url.strip_prefix(PIPE_URL_PREFIX) {
+        Some(name) => {
+            let name = name.to_ns_name::<GenericNamespaced>()?;
+            let stream = DllStream::Pipe(LocalSocketStream::connect(name).await?);
+            // The upgrade request still needs a URL; nothing reads the host over a pipe.
+            (stream, connect_request("ws://localhost/")?)
+        }
+        None => {
+            let req = connect_request(url)?;
+            let uri = req.uri();
+            let host = uri.host().unwrap_or_default();
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let default_port = match uri.scheme_str() {
+                Some("wss") => 443,
+                _ => 80,
+            };
+            let port = uri.port_u16().unwrap_or(default_port);
+            (DllStream::Tcp(TcpStream::connect((host, port)).await?), req)
+        }
+    };
+    let (socket, resp) =
+        tokio_tungstenite::client_async_tls_with_config(req, stream, None, connector).await?;
+    let encoding = resp
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .and_then(|v| v.to_str().ok())
+        .and_then(Encoding::negotiate)
+        .unwrap_or_default();
+    Ok((socket, encoding))
+}
+
+/// Write half of the DLL socket, encoding commands the way the connection negotiated.
+struct DllSink {
+    sink: futures_util::stream::SplitSink<DllSocket, Message>,
+    encoding: Encoding,
+}
+
+/// Server messages come as text (JSON) or binary (MessagePack) whatever was negotiated.
+fn decode_server(msg: Message) -> Option<ServerMessage> {
+    match msg {
+        Message::Text(text) => Encoding::Json.decode(text.as_bytes()).ok(),
+        Message::Binary(bytes) => Encoding::Msgpack.decode(&bytes).ok(),
+        _ => None,
+    }
 }
 
 pub async fn run(mut rx: mpsc::Receiver<UiCommand>, app: AppHandle) {
     let mut desired_connected = true;
     let mut backoff = Backoff::default();
     let mut backlog: VecDeque<UiCommand> = VecDeque::new();
+    let mut url = ws_url();
+    // Token of the connection that just dropped, to pick its session up again on the next one.
+    let mut resume_token: Option<String> = None;
 
-    emit_status(&app, "connecting", None);
+    emit_status(&app, "connecting", None, &url);
 
     loop {
         if !desired_connected {
-            emit_status(&app, "disconnected", None);
+            emit_status(&app, "disconnected", None, &url);
             match recv_or_backlog(&mut rx, &mut backlog).await {
                 Some(UiCommand::Connect) => desired_connected = true,
                 Some(UiCommand::Disconnect) => {}
@@ -37,24 +215,41 @@ pub async fn run(mut rx: mpsc::Receiver<UiCommand>, app: AppHandle) {
             continue;
         }
 
-        emit_status(&app, "connecting", None);
-        let socket = match tokio_tungstenite::connect_async(WS_URL).await {
-            Ok((socket, _)) => {
+        url = ws_url();
+        emit_status(&app, "connecting", None, &url);
+        let (socket, encoding) = match connect(&url).await {
+            Ok(connected) => {
                 backoff.reset();
-                emit_status(&app, "connected", None);
-                socket
+                emit_status(&app, "connected", None, &url);
+                connected
             }
-            Err(_) => {
+            Err(e) => {
+                if let tokio_tungstenite::tungstenite::Error::Http(resp) = &e {
+                    if resp.status() == 401 {
+                        eprintln!("warning: the DLL turned down our pairing key");
+                    }
+                }
                 let retry = backoff.next_delay();
-                emit_status(&app, "disconnected", Some(retry.as_secs()));
+                emit_status(&app, "disconnected", Some(retry.as_secs()), &url);
                 tokio::time::sleep(retry).await;
                 continue;
             }
         };
 
-        let (mut write, mut read) = socket.split();
+        let (sink, mut read) = socket.split();
+        let mut write = DllSink { sink, encoding };
         let mut session_token: Option<String> = None;
         let mut pending_set_tone: Option<ClientCommand> = None;
+        let mut known_instances: Vec<GojiraInstance> = Vec::new();
+        let mut refresh_pending = false;
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut ping_nonce: u64 = 0;
+        let mut last_pong = Instant::now();
+        let mut unresponsive = false;
+        if let Some(previous_token) = resume_token.take() {
+            // Sent before anything else, it spares us the handshake if the DLL still has our session.
+            let _ = send_raw(&mut write, &ClientCommand::Resume { previous_token }).await;
+        }
 
         'conn: loop {
             tokio::select! {
@@ -84,14 +279,30 @@ pub async fn run(mut rx: mpsc::Receiver<UiCommand>, app: AppHandle) {
                         }
                     }
                 }
+                _ = heartbeat.tick() => {
+                    if session_token.is_none() {
+                        continue;
+                    }
+                    if !unresponsive && last_pong.elapsed() >= HEARTBEAT_TIMEOUT {
+                        unresponsive = true;
+                        emit_status(&app, "unresponsive", None, &url);
+                    }
+                    ping_nonce += 1;
+                    let ping = ClientCommand::Ping { session_token: String::new(), nonce: ping_nonce };
+                    if send_to_dll(&mut write, &session_token, ping).await.is_err() {
+                        break 'conn;
+                    }
+                }
                 incoming = read.next() => {
                     match incoming {
                         Some(Ok(msg)) => {
-                            let Ok(text) = msg.into_text() else { continue };
-                            let Ok(server_msg) = serde_json::from_str::<ServerMessage>(&text) else { continue };
+                            let Some(server_msg) = decode_server(msg) else { continue };
                             match server_msg {
-                                ServerMessage::Handshake { session_token: t, instances, validation_report, param_enums, param_formats, param_format_samples } => {
+                                ServerMessage::Handshake { session_token: t, instances, validation_report, param_meta_streaming: _, meta, project_tempo, server_addr, tls_fingerprint, capabilities } => {
+                                    let HandshakeMeta { param_enums, param_enum_totals, param_formats, param_format_samples, eq_band_hz, param_kinds, param_schema, param_remap, proposed_remap } = *meta;
                                     session_token = Some(t.clone());
+                                    // The probe behind a handshake can take a while; count from here.
+                                    last_pong = Instant::now();
 
                                     // Keep a copy in backend state so we can inject it into AI prompts.
                                     if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
@@ -104,43 +315,269 @@ pub async fn run(mut rx: mpsc::Receiver<UiCommand>, app: AppHandle) {
                                         if let Ok(mut g) = state.param_format_samples.lock() {
                                             *g = param_format_samples.clone();
                                         }
+                                        if let Ok(mut g) = state.param_eq_band_hz.lock() {
+                                            *g = eq_band_hz.clone();
+                                        }
+                                        if let Ok(mut g) = state.param_kinds.lock() {
+                                            *g = param_kinds.clone();
+                                        }
+                                        if let Ok(mut g) = state.param_schema.lock() {
+                                            *g = param_schema.clone();
+                                        }
+                                        // Params the DLL found moved by name win over hand-edited entries for them.
+                                        if let Ok(mut g) = state.index_remap.lock() {
+                                            g.extend(param_remap.iter().map(|(k, v)| (*k, *v)));
+                                        }
+                                        if let Ok(mut g) = state.project_tempo.lock() {
+                                            *g = project_tempo;
+                                        }
                                     }
 
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        state.sync_instances(&instances);
+                                    }
+
+                                    if refresh_pending {
+                                        refresh_pending = false;
+                                        let update = instances::reconcile(&known_instances, &instances);
+                                        let _ = app.emit("reaper://instances_updated", update);
+                                    }
+                                    known_instances = instances.clone();
+
                                     let _ = app.emit("reaper://handshake", HandshakePayload {
                                         session_token: t.clone(),
                                         instances,
                                         validation_report,
                                         param_enums,
+                                        param_enum_totals,
                                         param_formats,
                                         param_format_samples,
+                                        eq_band_hz,
+                                        param_kinds,
+                                        param_schema,
+                                        param_remap,
+                                        proposed_remap,
+                                        project_tempo,
+                                        server_addr,
+                                        tls_fingerprint,
+                                        capabilities,
                                     });
                                     let _ = send_raw(&mut write, &ClientCommand::HandshakeAck { session_token: t }).await;
                                     if let Some(pending) = pending_set_tone.take() {
                                         let _ = send_to_dll(&mut write, &session_token, pending).await;
                                     }
                                 }
-                                ServerMessage::ProjectChanged => {
-                                    let _ = app.emit("reaper://project_changed", ());
+                                ServerMessage::ProjectChanged { project_tempo, project_name: _, changed_instances, added, removed, renamed } => {
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        if let Ok(mut g) = state.project_tempo.lock() {
+                                            *g = project_tempo;
+                                        }
+                                    }
+                                    let _ = app.emit("reaper://project_changed", project_tempo);
 
-                                    // UX: immediately refresh instance list so the UI updates when the user switches projects,
-                                    // inserts/removes FX, etc. The DLL side already debounces ProjectChanged.
                                     if session_token.is_some() {
-                                        let _ = send_to_dll(
-                                            &mut write,
-                                            &session_token,
-                                            ClientCommand::RefreshInstances {
-                                                session_token: String::new(),
-                                            },
-                                        )
-                                        .await;
+                                        if known_instances.is_empty() && !added.is_empty() {
+                                            // Nothing was probed yet, so the first instance needs the handshake's param meta.
+                                            refresh_pending = true;
+                                            let _ = send_to_dll(
+                                                &mut write,
+                                                &session_token,
+                                                ClientCommand::RefreshInstances {
+                                                    session_token: String::new(),
+                                                    instances_only: false,
+                                                },
+                                            )
+                                            .await;
+                                        } else if !added.is_empty() || !removed.is_empty() || !renamed.is_empty() {
+                                            let next = instances::apply_delta(&known_instances, &added, &removed, &renamed);
+                                            if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                                state.sync_instances(&next);
+                                            }
+                                            let update = instances::reconcile(&known_instances, &next);
+                                            let _ = app.emit("reaper://instances_updated", update);
+                                            known_instances = next;
+                                        }
+                                        // Re-read tones the DLL saw move, so live params and diffs follow them.
+                                        for target_fx_guid in changed_instances {
+                                            let _ = send_to_dll(
+                                                &mut write,
+                                                &session_token,
+                                                ClientCommand::GetCurrentTone {
+                                                    session_token: String::new(),
+                                                    command_id: format!("sync-{target_fx_guid}"),
+                                                    target_fx_guid,
+                                                },
+                                            )
+                                            .await;
+                                        }
+                                    }
+                                }
+                                ServerMessage::Resumed { session_token: t, instances } => {
+                                    // Same session as before the drop, so the last handshake's param meta still holds.
+                                    session_token = Some(t);
+                                    last_pong = Instant::now();
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        state.sync_instances(&instances);
+                                    }
+                                    let update = instances::reconcile(&known_instances, &instances);
+                                    let _ = app.emit("reaper://instances_updated", update);
+                                    known_instances = instances;
+                                    if let Some(pending) = pending_set_tone.take() {
+                                        let _ = send_to_dll(&mut write, &session_token, pending).await;
+                                    }
+                                }
+                                ServerMessage::Instances { instances } => {
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        state.sync_instances(&instances);
                                     }
+                                    let update = instances::reconcile(&known_instances, &instances);
+                                    let _ = app.emit("reaper://instances_updated", update);
+                                    known_instances = instances;
+                                }
+                                ServerMessage::InstanceMoved { ref instance, .. } => {
+                                    // Repoint the instance now rather than wait for the project's rescan.
+                                    let next = instances::apply_delta(&known_instances, &[], &[], std::slice::from_ref(instance));
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        state.sync_instances(&next);
+                                    }
+                                    let update = instances::reconcile(&known_instances, &next);
+                                    let _ = app.emit("reaper://instances_updated", update);
+                                    known_instances = next;
+                                    let _ = app.emit("reaper://instance_moved", server_msg);
+                                }
+                                ServerMessage::ParamMeta { ref param_enums, ref param_formats, ref param_format_samples, ref param_kinds, .. } => {
+                                    // Streamed after the handshake, which cleared these; each chunk adds its index range.
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        if let Ok(mut g) = state.param_enums.lock() {
+                                            g.extend(param_enums.clone());
+                                        }
+                                        if let Ok(mut g) = state.param_formats.lock() {
+                                            g.extend(param_formats.clone());
+                                        }
+                                        if let Ok(mut g) = state.param_format_samples.lock() {
+                                            g.extend(param_format_samples.clone());
+                                        }
+                                        if let Ok(mut g) = state.param_kinds.lock() {
+                                            g.extend(param_kinds.clone());
+                                        }
+                                    }
+                                    let _ = app.emit("reaper://param_meta", server_msg);
+                                }
+                                ServerMessage::ParamEnumPage { index, ref options, .. } => {
+                                    // Pulled pages join the first one, so later prompts can name what the user looked up.
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        if let Ok(mut g) = state.param_enums.lock() {
+                                            let known = g.entry(index).or_default();
+                                            for opt in options {
+                                                if !known.iter().any(|k| k.label == opt.label) {
+                                                    known.push(opt.clone());
+                                                }
+                                            }
+                                        }
+                                    }
+                                    let _ = app.emit("reaper://param_enum_page", server_msg);
+                                }
+                                ServerMessage::TokenRotated { session_token: t, .. } => {
+                                    // Later commands (and a pending SetTone) pick up the new token.
+                                    session_token = Some(t);
+                                }
+                                ServerMessage::Pong { .. } => {
+                                    last_pong = Instant::now();
+                                    if unresponsive {
+                                        unresponsive = false;
+                                        emit_status(&app, "connected", None, &url);
+                                    }
+                                }
+                                ServerMessage::Metrics { metrics } => {
+                                    let _ = app.emit("reaper://metrics", metrics);
+                                }
+                                ServerMessage::Diagnostics { .. } => {
+                                    let _ = app.emit("reaper://diagnostics", server_msg);
+                                }
+                                ServerMessage::CurrentTone { ref target_fx_guid, ref params, ref last_applied, .. } => {
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        let _ = state.with_instance(target_fx_guid, |inst| {
+                                            inst.live_params = params
+                                                .iter()
+                                                .map(|p| ParamChange { index: p.index, value: p.value, op: ParamOp::Set })
+                                                .collect();
+                                            // Nothing sent yet this session: pick up what the project says was applied last.
+                                            if inst.param_cache.is_empty() {
+                                                inst.param_cache = last_applied.clone();
+                                            }
+                                            // GUI edits win over what we last sent, so later diffs and merges start from the plugin.
+                                            for cached in inst.param_cache.iter_mut() {
+                                                if let Some(p) = params.iter().find(|p| p.index == cached.index) {
+                                                    cached.value = p.value;
+                                                }
+                                            }
+                                        });
+                                    }
+                                    let _ = app.emit("reaper://current_tone", server_msg);
+                                }
+                                ServerMessage::ParamChanged { ref target_fx_guid, ref params } => {
+                                    if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+                                        let _ = state.with_instance(target_fx_guid, |inst| {
+                                            for p in params {
+                                                match inst.live_params.iter_mut().find(|l| l.index == p.index) {
+                                                    Some(live) => live.value = p.value,
+                                                    None => inst.live_params.push(ParamChange { index: p.index, value: p.value, op: ParamOp::Set }),
+                                                }
+                                                if let Some(cached) = inst.param_cache.iter_mut().find(|c| c.index == p.index) {
+                                                    cached.value = p.value;
+                                                }
+                                            }
+                                        });
+                                    }
+                                    let _ = app.emit("reaper://param_changed", server_msg);
+                                }
+                                ServerMessage::PresetList { .. } => {
+                                    let _ = app.emit("reaper://preset_list", server_msg);
+                                }
+                                ServerMessage::ToneSlots { .. } => {
+                                    let _ = app.emit("reaper://tone_slots", server_msg);
+                                }
+                                ServerMessage::Replay { .. } => {
+                                    let _ = app.emit("reaper://replay", server_msg);
                                 }
                                 ServerMessage::Ack { .. } => {
                                     let _ = app.emit("reaper://ack", server_msg);
                                 }
+                                ServerMessage::MultiAck { .. } => {
+                                    let _ = app.emit("reaper://multi_ack", server_msg);
+                                }
+                                ServerMessage::Preview { .. } => {
+                                    let _ = app.emit("reaper://preview", server_msg);
+                                }
+                                ServerMessage::ToneValidation { .. } => {
+                                    let _ = app.emit("reaper://tone_validation", server_msg);
+                                }
+                                ServerMessage::PreviewRendered { .. } => {
+                                    let _ = app.emit("reaper://preview_rendered", server_msg);
+                                }
+                                ServerMessage::Transport { .. } => {
+                                    let _ = app.emit("reaper://transport", server_msg);
+                                }
+                                ServerMessage::FxState { .. } => {
+                                    let _ = app.emit("reaper://fx_state", server_msg);
+                                }
+                                ServerMessage::TrackControls { .. } => {
+                                    let _ = app.emit("reaper://track_controls", server_msg);
+                                }
                                 ServerMessage::Error { .. } => {
                                     let _ = app.emit("reaper://error", server_msg);
                                 }
+                                ServerMessage::Warning { .. } => {
+                                    let _ = app.emit("reaper://warning", server_msg);
+                                }
+                                ServerMessage::Shutdown { restart_expected, .. } => {
+                                    // REAPER quitting won't be back within seconds; a socket that drops
+                                    // without this notice was a crash and gets the short retries.
+                                    if !restart_expected {
+                                        backoff.settle();
+                                    }
+                                    break 'conn;
+                                }
                             }
                         }
                         _ => break 'conn,
@@ -148,6 +585,11 @@ pub async fn run(mut rx: mpsc::Receiver<UiCommand>, app: AppHandle) {
                 }
             }
         }
+
+        resume_token = session_token;
+        if let Some(state) = app.try_state::<crate::tauri_utils::app_state::AppState>() {
+            state.mark_instances_disconnected();
+        }
     }
 }
 
@@ -183,12 +625,7 @@ fn coalesce_last_set_tone(
 }
 
 async fn send_to_dll(
-    write: &mut futures_util::stream::SplitSink<
-        tokio_tungstenite::WebSocketStream<
-            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-        >,
-        tokio_tungstenite::tungstenite::Message,
-    >,
+    write: &mut DllSink,
     session_token: &Option<String>,
     cmd: ClientCommand,
 ) -> Result<(), ()> {
@@ -205,8 +642,41 @@ fn inject_token(cmd: ClientCommand, token: &str) -> ClientCommand {
         ClientCommand::HandshakeAck { .. } => ClientCommand::HandshakeAck {
             session_token: token.to_string(),
         },
-        ClientCommand::RefreshInstances { .. } => ClientCommand::RefreshInstances {
+        ClientCommand::RefreshInstances { instances_only, .. } => ClientCommand::RefreshInstances {
+            session_token: token.to_string(),
+            instances_only,
+        },
+        ClientCommand::RefreshParamMeta { indices, .. } => ClientCommand::RefreshParamMeta {
+            session_token: token.to_string(),
+            indices,
+        },
+        ClientCommand::RotateToken { .. } => ClientCommand::RotateToken {
+            session_token: token.to_string(),
+        },
+        // Carries the token it resumes, not the current one.
+        ClientCommand::Resume { previous_token } => ClientCommand::Resume { previous_token },
+        ClientCommand::GetMetrics { .. } => ClientCommand::GetMetrics {
+            session_token: token.to_string(),
+        },
+        ClientCommand::GetDiagnostics { limit, .. } => ClientCommand::GetDiagnostics {
             session_token: token.to_string(),
+            limit,
+        },
+        ClientCommand::Ping {
+            session_token: _,
+            nonce,
+        } => ClientCommand::Ping {
+            session_token: token.to_string(),
+            nonce,
+        },
+        ClientCommand::GetCurrentTone {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+        } => ClientCommand::GetCurrentTone {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
         },
         ClientCommand::SetTone {
             session_token: _,
@@ -214,30 +684,282 @@ fn inject_token(cmd: ClientCommand, token: &str) -> ClientCommand {
             target_fx_guid,
             mode,
             params,
+            locked_params,
+            ramp_ms,
+            atomic,
+            apply_at,
+            modules,
+            allow_background_projects,
         } => ClientCommand::SetTone {
             session_token: token.to_string(),
             command_id,
             target_fx_guid,
             mode,
             params,
+            locked_params,
+            ramp_ms,
+            atomic,
+            apply_at,
+            modules,
+            allow_background_projects,
+        },
+        ClientCommand::PreviewTone {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        } => ClientCommand::PreviewTone {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        },
+        ClientCommand::ValidateTone {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            params,
+        } => ClientCommand::ValidateTone {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            params,
+        },
+        ClientCommand::SetToneMulti {
+            session_token: _,
+            command_id,
+            targets,
+            allow_background_projects,
+        } => ClientCommand::SetToneMulti {
+            session_token: token.to_string(),
+            command_id,
+            targets,
+            allow_background_projects,
+        },
+        ClientCommand::UndoLastTone {
+            session_token: _,
+            command_id,
+        } => ClientCommand::UndoLastTone {
+            session_token: token.to_string(),
+            command_id,
+        },
+        ClientCommand::SetFxMix {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            wet,
+            delta_solo,
+        } => ClientCommand::SetFxMix {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            wet,
+            delta_solo,
+        },
+        ClientCommand::SetFxState {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            enabled,
+            wet,
+        } => ClientCommand::SetFxState {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            enabled,
+            wet,
+        },
+        ClientCommand::SetTrackControls {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            volume_db,
+            pan,
+            rec_monitor,
+        } => ClientCommand::SetTrackControls {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            volume_db,
+            pan,
+            rec_monitor,
+        },
+        ClientCommand::RenderPreview {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            start,
+            length,
+        } => ClientCommand::RenderPreview {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            start,
+            length,
+        },
+        ClientCommand::Play {
+            session_token: _,
+            command_id,
+        } => ClientCommand::Play {
+            session_token: token.to_string(),
+            command_id,
+        },
+        ClientCommand::Stop {
+            session_token: _,
+            command_id,
+        } => ClientCommand::Stop {
+            session_token: token.to_string(),
+            command_id,
+        },
+        ClientCommand::SetLoop {
+            session_token: _,
+            command_id,
+            start,
+            end,
+        } => ClientCommand::SetLoop {
+            session_token: token.to_string(),
+            command_id,
+            start,
+            end,
+        },
+        ClientCommand::InsertFx {
+            session_token: _,
+            command_id,
+            track_guid,
+            fx_name,
+        } => ClientCommand::InsertFx {
+            session_token: token.to_string(),
+            command_id,
+            track_guid,
+            fx_name,
+        },
+        ClientCommand::SwitchFxPair {
+            session_token: _,
+            command_id,
+            enable_fx_guid,
+            disable_fx_guid,
+        } => ClientCommand::SwitchFxPair {
+            session_token: token.to_string(),
+            command_id,
+            enable_fx_guid,
+            disable_fx_guid,
+        },
+        ClientCommand::RestoreSnapshot {
+            session_token: _,
+            command_id,
+            snapshot_id,
+        } => ClientCommand::RestoreSnapshot {
+            session_token: token.to_string(),
+            command_id,
+            snapshot_id,
+        },
+        ClientCommand::Subscribe {
+            session_token: _,
+            target_fx_guid,
+            indices,
+        } => ClientCommand::Subscribe {
+            session_token: token.to_string(),
+            target_fx_guid,
+            indices,
+        },
+        ClientCommand::SavePreset {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            name,
+        } => ClientCommand::SavePreset {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            name,
+        },
+        ClientCommand::LoadPreset {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+            name,
+        } => ClientCommand::LoadPreset {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+            name,
+        },
+        ClientCommand::ListPresets {
+            session_token: _,
+            command_id,
+            target_fx_guid,
+        } => ClientCommand::ListPresets {
+            session_token: token.to_string(),
+            command_id,
+            target_fx_guid,
+        },
+        ClientCommand::StoreToneSlot {
+            session_token: _,
+            command_id,
+            slot,
+            name,
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        } => ClientCommand::StoreToneSlot {
+            session_token: token.to_string(),
+            command_id,
+            slot,
+            name,
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        },
+        ClientCommand::ActivateToneSlot {
+            session_token: _,
+            command_id,
+            slot,
+            ramp_ms,
+            apply_at,
+        } => ClientCommand::ActivateToneSlot {
+            session_token: token.to_string(),
+            command_id,
+            slot,
+            ramp_ms,
+            apply_at,
+        },
+        ClientCommand::ListToneSlots {
+            session_token: _,
+            command_id,
+        } => ClientCommand::ListToneSlots {
+            session_token: token.to_string(),
+            command_id,
+        },
+        ClientCommand::SearchEnumLabels {
+            session_token: _,
+            command_id,
+            index,
+            query,
+            offset,
+            limit,
+        } => ClientCommand::SearchEnumLabels {
+            session_token: token.to_string(),
+            command_id,
+            index,
+            query,
+            offset,
+            limit,
         },
     }
 }
 
-async fn send_raw(
-    write: &mut futures_util::stream::SplitSink<
-        tokio_tungstenite::WebSocketStream<
-            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-        >,
-        tokio_tungstenite::tungstenite::Message,
-    >,
-    cmd: &ClientCommand,
-) -> Result<(), ()> {
-    let payload = serde_json::to_string(cmd).map_err(|_| ())?;
-    write
-        .send(tokio_tungstenite::tungstenite::Message::Text(payload.into()))
-        .await
-        .map_err(|_| ())
+async fn send_raw(write: &mut DllSink, cmd: &ClientCommand) -> Result<(), ()> {
+    let msg = match write.encoding.encode(cmd).map_err(|_| ())? {
+        Frame::Text(text) => Message::Text(text.into()),
+        Frame::Binary(bytes) => Message::Binary(bytes.into()),
+    };
+    write.sink.send(msg).await.map_err(|_| ())
 }
 
 async fn recv_or_backlog(
@@ -250,10 +972,19 @@ async fn recv_or_backlog(
     rx.recv().await
 }
 
-fn emit_status(app: &AppHandle, status: &'static str, retry_in: Option<u64>) {
-    let _ = app.emit("reaper://status", StatusEvent { status, retry_in });
+fn emit_status(app: &AppHandle, status: &'static str, retry_in: Option<u64>, url: &str) {
+    let _ = app.emit(
+        "reaper://status",
+        StatusEvent {
+            status,
+            retry_in,
+            url: url.to_string(),
+        },
+    );
 }
 
+const BACKOFF_SECS: [u64; 4] = [1, 2, 5, 10];
+
 #[derive(Default)]
 struct Backoff {
     idx: usize,
@@ -264,10 +995,14 @@ impl Backoff {
         self.idx = 0;
     }
 
+    /// Skips straight to the longest delay.
+    fn settle(&mut self) {
+        self.idx = BACKOFF_SECS.len();
+    }
+
     fn next_delay(&mut self) -> Duration {
-        let delays = [1, 2, 5, 10];
-        let secs = delays.get(self.idx).copied().unwrap_or(10);
-        self.idx = (self.idx + 1).min(delays.len());
+        let secs = BACKOFF_SECS.get(self.idx).copied().unwrap_or(10);
+        self.idx = (self.idx + 1).min(BACKOFF_SECS.len());
         Duration::from_secs(secs)
     }
 }
@@ -13,7 +13,32 @@ import {
   clampSessionData,
   summarizeTitle,
 } from "./chatArchive";
-import type { AckMessage, GojiraInstance, HandshakePayload, PreviewResult, StatusEvent } from "./types";
+import type {
+  AckMessage,
+  Capability,
+  CurrentToneMessage,
+  ErrorMessage,
+  GojiraInstance,
+  HandshakePayload,
+  InstancesUpdatedPayload,
+  MultiAckMessage,
+  ParamChangedMessage,
+  ParamCheck,
+  ParamEnumPageMessage,
+  ParamMetaMessage,
+  PresetListMessage,
+  FxStateMessage,
+  TransportMessage,
+  PreviewRenderedMessage,
+  PreviewResult,
+  ReaperPreviewMessage,
+  ToneValidationMessage,
+  Remediation,
+  RuntimeMetrics,
+  StatusEvent,
+  ToneProgress,
+  ToneThinking,
+} from "./types";
 import { buildPromptFromChat, initialWorkspace, mergeParamLists, nowId, type ChatMessage, type HistoryEntry, type PickupPosition, type SavedSnapshot, type WorkspaceState } from "./workspace";
 import { summarizeAppliedDelta } from "./workspace";
 import { getChatStore, type ChatStore } from "./platform/chatStore";
@@ -27,18 +52,39 @@ const store: PrefsStore = {
   save: async () => (await getPrefsStore()).save(),
 };
 
+// Tone params are 0..=118 (the DLL's LAST_TONE_PARAM).
+const TONE_PARAM_COUNT = 119;
+const GLIDE_MS = 300;
+
+const REMEDIATION_HINTS: Record<Remediation, string> = {
+  refresh_instances: "Refresh instances and pick the plugin again.",
+  fix_params: "Check the offending parameter and apply again.",
+  complete_handshake: "Wait for the REAPER connection to finish, then retry.",
+  reauthenticate: "Reconnect to REAPER to get a fresh session.",
+  retry: "REAPER was busy; try again in a moment.",
+  allow_background_projects: "The plugin is in another project tab; switch to it or allow background projects.",
+  unknown: "This REAPER suggests a fix the app doesn't know yet.",
+};
+
 export default function App() {
   const tauri = isTauriRuntime();
   const [status, setStatus] = useState<StatusEvent>({ status: "connecting" });  
   const [instances, setInstances] = useState<GojiraInstance[]>([]);
   const [selectedFxGuid, setSelectedFxGuid] = useState<string>("");
-  const [validationReport, setValidationReport] = useState<Record<string, string>>({});
+  const [validationReport, setValidationReport] = useState<ParamCheck[]>([]);
+  // What the connected DLL build supports; features it doesn't list stay off.
+  const [capabilities, setCapabilities] = useState<Capability[]>([]);
+  const [metrics, setMetrics] = useState<RuntimeMetrics | null>(null);
   const [paramEnums, setParamEnums] = useState<Record<string, Array<{ value: number; label: string }>>>({});
+  const [paramEnumTotals, setParamEnumTotals] = useState<Record<string, number>>({});
   const [paramFormats, setParamFormats] = useState<Record<string, { min: string; mid: string; max: string }>>({});
   const [paramFormatSamples, setParamFormatSamples] = useState<
     Record<string, Array<{ norm: number; formatted: string }>>
   >({});
   const [indexRemap, setIndexRemap] = useState<Record<number, number>>({});     
+  const [proposedRemap, setProposedRemap] = useState<Record<number, number>>({});
+  const [paramLocks, setParamLocks] = useState<Record<string, number[]>>({});
+  const [paramLocksLoaded, setParamLocksLoaded] = useState(false);
 
   const [pickupNeck, setPickupNeck] = useState("");
   const [pickupMiddle, setPickupMiddle] = useState("");
@@ -48,6 +94,7 @@ export default function App() {
   const [vaultPassphrase, setVaultPassphrase] = useState("");
   const [apiProvider, setApiProvider] = useState<ProviderId>("gemini");
   const [apiModel, setApiModel] = useState("");
+  const [apiBaseUrl, setApiBaseUrl] = useState("");
   const [apiKeyDrafts, setApiKeyDrafts] = useState<Record<ProviderId, string>>(
     {} as Record<ProviderId, string>,
   );
@@ -57,7 +104,9 @@ export default function App() {
   const [credentialsLoaded, setCredentialsLoaded] = useState(false);
 
   const [busy, setBusy] = useState(false);
+  const [thinking, setThinking] = useState<ToneThinking | null>(null);
   const [previewOnly, setPreviewOnly] = useState(false);
+  const [glide, setGlide] = useState(false);
   const [refineEnabled, setRefineEnabled] = useState(true);
   const [tab, setTab] = useState<"preview" | "qc" | "mapping">("preview");
   const [composer, setComposer] = useState("Make me a dry modern djent rhythm tone.");
@@ -74,6 +123,12 @@ export default function App() {
   });
   const [cursor, setCursor] = useState(0);
   const [snapshots, setSnapshots] = useState<SavedSnapshot[]>([]);
+  const [presetList, setPresetList] = useState<PresetListMessage | null>(null);
+  const [fxState, setFxState] = useState<FxStateMessage | null>(null);
+  const [transport, setTransport] = useState<TransportMessage | null>(null);
+  const [renderedPreview, setRenderedPreview] = useState<PreviewRenderedMessage | null>(null);
+  const [reaperPreview, setReaperPreview] = useState<ReaperPreviewMessage | null>(null);
+  const [toneValidation, setToneValidation] = useState<ToneValidationMessage | null>(null);
 
   const workspace = history[cursor]?.state ?? initialWorkspace();
   const canUndo = cursor > 0;
@@ -89,10 +144,22 @@ export default function App() {
     workspaceRef.current = workspace;
   }, [workspace]);
 
+  const instancesRef = useRef(instances);
+  useEffect(() => {
+    instancesRef.current = instances;
+  }, [instances]);
+
+  const selectedFxGuidRef = useRef(selectedFxGuid);
+  useEffect(() => {
+    selectedFxGuidRef.current = selectedFxGuid;
+  }, [selectedFxGuid]);
+
   const providerIds = useMemo<ProviderId[]>(() => API_PROVIDERS.map((p) => p.id), []);
 
   const pendingApplyIdRef = useRef<string | null>(null);
   const [pendingApplyCommandId, setPendingApplyCommandId] = useState<string | null>(null);
+  // The DLL snapshots the FX before every apply, under the apply's command id.
+  const [lastSnapshot, setLastSnapshot] = useState<{ id: string; fxGuid: string } | null>(null);
 
   function newSessionId() {
     return nowId("chat");
@@ -344,10 +411,21 @@ export default function App() {
       unlistenFns.push(
         await listen<HandshakePayload>("reaper://handshake", async (e) => {
           setInstances(e.payload.instances);
-          setValidationReport(e.payload.validation_report ?? {});
+          setValidationReport(e.payload.validation_report ?? []);
+          setCapabilities(e.payload.capabilities ?? []);
           setParamEnums(e.payload.param_enums ?? {});
+          setParamEnumTotals(e.payload.param_enum_totals ?? {});
           setParamFormats(e.payload.param_formats ?? {});
           setParamFormatSamples(e.payload.param_format_samples ?? {});
+          setProposedRemap(e.payload.proposed_remap ?? {});
+          const moved = Object.entries(e.payload.param_remap ?? {});
+          if (moved.length) {
+            setIndexRemap((prev) => {
+              const next = { ...prev };
+              for (const [from, to] of moved) next[Number(from)] = to;
+              return next;
+            });
+          }
 
           const last = (await store.get<string>("last_target_fx_guid")) ?? "";
           const next =
@@ -360,6 +438,42 @@ export default function App() {
         }),
       );
 
+      // Enum options and formats trail the handshake, one index range at a time.
+      unlistenFns.push(
+        await listen<ParamMetaMessage>("reaper://param_meta", (e) => {
+          setParamEnums((prev) => ({ ...prev, ...(e.payload.param_enums ?? {}) }));
+          setParamEnumTotals((prev) => ({ ...prev, ...(e.payload.param_enum_totals ?? {}) }));
+          setParamFormats((prev) => ({ ...prev, ...(e.payload.param_formats ?? {}) }));
+          setParamFormatSamples((prev) => ({ ...prev, ...(e.payload.param_format_samples ?? {}) }));
+        }),
+      );
+
+      // Long enums (IR lists) only come with their first page; searched pages join it.
+      unlistenFns.push(
+        await listen<ParamEnumPageMessage>("reaper://param_enum_page", (e) => {
+          const key = String(e.payload.index);
+          setParamEnums((prev) => {
+            const known = prev[key] ?? [];
+            const added = e.payload.options.filter((o) => !known.some((k) => k.label === o.label));
+            return added.length ? { ...prev, [key]: [...known, ...added] } : prev;
+          });
+        }),
+      );
+
+      unlistenFns.push(await listen<RuntimeMetrics>("reaper://metrics", (e) => setMetrics(e.payload)));
+
+      unlistenFns.push(
+        await listen<ToneProgress>("brain://progress", (e) => {
+          const p = e.payload;
+          setThinking((prev) => {
+            const t = prev ?? { research: "", reasoning: "", paramCount: 0 };
+            if (p.kind === "research") return { ...t, research: t.research + p.text };
+            if (p.kind === "reasoning") return { ...t, reasoning: t.reasoning + p.text };
+            return { ...t, paramCount: p.params.length };
+          });
+        }),
+      );
+
       unlistenFns.push(
         await listen("reaper://project_changed", () => {
           const w = workspaceRef.current;
@@ -369,28 +483,189 @@ export default function App() {
         }),
       );
 
+      unlistenFns.push(
+        await listen<InstancesUpdatedPayload>("reaper://instances_updated", async (e) => {
+          setInstances(e.payload.instances);
+          const target = (await store.get<string>("last_target_fx_guid")) ?? "";
+          if (!target) return;
+          const moved = e.payload.moved.find((x) => x.fx_guid === target);
+          let text = "";
+          if (e.payload.removed.includes(target)) {
+            text = "The selected Gojira instance is gone after the project change; switched to another target.";
+          } else if (moved && moved.from_track_guid !== moved.to_track_guid) {
+            text = "The selected Gojira instance moved to another track; still targeting it by FX GUID.";
+          }
+          if (!text) return;
+          const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: text };
+          const w = workspaceRef.current;
+          commit({ ...w, chat: [...w.chat, m] }, { label: "instances updated", anchorMessageId: m.id });
+        }),
+      );
+
       unlistenFns.push(
         await listen<AckMessage>("reaper://ack", (e) => {
           const msg = e.payload;
           if (pendingApplyIdRef.current && msg.command_id === pendingApplyIdRef.current) {
             clearPendingApply();
+            setLastSnapshot({ id: msg.command_id, fxGuid: selectedFxGuidRef.current });
           }
           const w = workspaceRef.current;
+          if (msg.command_id.startsWith("reaper-restore-")) {
+            const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: "Restored the plugin to how it was before the last apply." };
+            commit({ ...w, chat: [...w.chat, m] }, { label: "reaper restore", anchorMessageId: m.id });
+            return;
+          }
+          if (msg.command_id.startsWith("reaper-undo-")) {
+            const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: "Reverted the last applied tone in REAPER." };
+            commit({ ...w, chat: [...w.chat, m] }, { label: "reaper undo", anchorMessageId: m.id });
+            return;
+          }
+          if (msg.command_id.startsWith("preset-save-") || msg.command_id.startsWith("preset-load-")) {
+            const saved = msg.command_id.startsWith("preset-save-");
+            const content = saved ? "Saved the current tone as a preset." : "Loaded the preset in REAPER.";
+            const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content };
+            commit({ ...w, chat: [...w.chat, m] }, { label: "preset", anchorMessageId: m.id });
+            if (saved && selectedFxGuidRef.current) {
+              void invoke("list_presets", { targetFxGuid: selectedFxGuidRef.current }).catch(() => {});
+            }
+            return;
+          }
           commit({ ...w, lastAck: msg }, { label: "ack" });
           setTab("qc");
         }),
       );
 
       unlistenFns.push(
-        await listen<any>("reaper://error", (e) => {
-          const msg = e.payload as { type?: string; msg?: string; code?: string };
-          const text = msg?.msg ? `REAPER error: ${msg.code ?? "error"} — ${msg.msg}` : "REAPER error";
-          const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: text };
+        await listen<CurrentToneMessage>("reaper://current_tone", (e) => {
+          // Pull GUI edits made in the plugin into the refine base so diffs start from what is live.
+          if (e.payload.target_fx_guid !== selectedFxGuidRef.current) return;
+          const w = workspaceRef.current;
+          const live = new Map(e.payload.params.map((p) => [p.index, p.value]));
+          if (!w.workingParams?.length) {
+            // Fresh workspace: start from what was last applied to this FX, even in an earlier session.
+            const last = e.payload.last_applied ?? [];
+            if (last.length) {
+              const seeded = last.map((p) => ({ index: p.index, value: live.get(p.index) ?? p.value }));
+              commit({ ...w, workingParams: seeded }, { label: "last applied tone" });
+            }
+            return;
+          }
+          let changed = false;
+          const next = w.workingParams.map((p) => {
+            const v = live.get(p.index);
+            if (v === undefined || Math.abs(v - p.value) < 1e-6) return p;
+            changed = true;
+            return { index: p.index, value: v };
+          });
+          if (changed) commit({ ...w, workingParams: next }, { label: "live tone read" });
+        }),
+      );
+
+      unlistenFns.push(
+        await listen<MultiAckMessage>("reaper://multi_ack", (e) => {
+          const msg = e.payload;
+          if (pendingApplyIdRef.current && msg.command_id === pendingApplyIdRef.current) {
+            clearPendingApply();
+            setLastSnapshot({ id: msg.command_id, fxGuid: selectedFxGuidRef.current });
+          }
+          const failed = msg.results.filter((r) => r.error);
+          const lines = [`Applied to ${msg.results.length - failed.length} of ${msg.results.length} instance(s).`];
+          for (const r of failed) {
+            const inst = instancesRef.current.find((i) => i.fx_guid === r.target_fx_guid);
+            lines.push(`- ${inst?.track_name || r.target_fx_guid}: ${r.error}`);
+          }
+          const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: lines.join("\n") };
+          const w = workspaceRef.current;
+          commit({ ...w, chat: [...w.chat, m] }, { label: "multi apply", anchorMessageId: m.id });
+        }),
+      );
+
+      unlistenFns.push(
+        await listen<ParamChangedMessage>("reaper://param_changed", (e) => {
+          // Knobs turned in the plugin GUI while the app is open.
+          if (e.payload.target_fx_guid !== selectedFxGuidRef.current) return;
+          const w = workspaceRef.current;
+          if (!w.workingParams?.length) return;
+          const moved = new Map(e.payload.params.map((p) => [p.index, p.value]));
+          let changed = false;
+          const next = w.workingParams.map((p) => {
+            const v = moved.get(p.index);
+            if (v === undefined || Math.abs(v - p.value) < 1e-6) return p;
+            changed = true;
+            return { index: p.index, value: v };
+          });
+          if (changed) commit({ ...w, workingParams: next }, { label: "plugin gui edit" });
+        }),
+      );
+
+      unlistenFns.push(
+        await listen<ReaperPreviewMessage>("reaper://preview", (e) => {
+          if (e.payload.target_fx_guid !== selectedFxGuidRef.current) return;
+          setReaperPreview(e.payload);
+        }),
+      );
+
+      unlistenFns.push(
+        await listen<ToneValidationMessage>("reaper://tone_validation", (e) => {
+          if (e.payload.target_fx_guid !== selectedFxGuidRef.current) return;
+          setToneValidation(e.payload);
+        }),
+      );
+
+      unlistenFns.push(
+        await listen<PresetListMessage>("reaper://preset_list", (e) => {
+          if (e.payload.target_fx_guid !== selectedFxGuidRef.current) return;
+          setPresetList(e.payload);
+        }),
+      );
+
+      unlistenFns.push(await listen<TransportMessage>("reaper://transport", (e) => setTransport(e.payload)));
+      unlistenFns.push(
+        await listen<PreviewRenderedMessage>("reaper://preview_rendered", (e) => setRenderedPreview(e.payload)),
+      );
+
+      unlistenFns.push(
+        await listen<FxStateMessage>("reaper://fx_state", (e) => {
+          if (e.payload.target_fx_guid !== selectedFxGuidRef.current) return;
+          setFxState(e.payload);
+        }),
+      );
+
+      unlistenFns.push(
+        await listen<ErrorMessage>("reaper://error", (e) => {
+          const msg = e.payload;
+          if (pendingApplyIdRef.current && msg.command_id === pendingApplyIdRef.current) {
+            clearPendingApply();
+          }
+          const base = msg?.msg ? `REAPER error: ${msg.code ?? "error"} — ${msg.msg}` : "REAPER error";
+          const diag = msg?.diagnostic;
+          const lines = [base];
+          if (diag?.param_index != null) {
+            const value = diag.value != null ? ` (value ${diag.value})` : "";
+            lines.push(`Parameter ${diag.param_index}${value}.`);
+          }
+          if (diag?.resolver) {
+            lines.push(diag.resolver === "cache_miss" ? "The plugin is no longer in the project." : "The plugin moved while REAPER was being scanned.");
+          }
+          if (diag?.remediation) lines.push(REMEDIATION_HINTS[diag.remediation]);
+          if (msg?.details) lines.push("", msg.details);
+          const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: lines.join("\n") };
           const w = workspaceRef.current;
           commit({ ...w, chat: [...w.chat, m] }, { label: "reaper error", anchorMessageId: m.id });
         }),
       );
 
+      unlistenFns.push(
+        await listen<any>("reaper://warning", (e) => {
+          const msg = e.payload as { type?: string; msg?: string; code?: string; context?: string | null };
+          const base = `REAPER warning: ${msg?.code ?? "warning"} — ${msg?.msg ?? ""}`;
+          const text = msg?.context ? `${base} (${msg.context})` : base;
+          const m: ChatMessage = { id: nowId("m"), role: "assistant", ts: Date.now(), content: text };
+          const w = workspaceRef.current;
+          commit({ ...w, chat: [...w.chat, m] }, { label: "reaper warning", anchorMessageId: m.id });
+        }),
+      );
+
         await invoke("connect_ws");
       }
 
@@ -423,12 +698,17 @@ export default function App() {
         });
       }
 
+      const savedLocks = (await store.get<Record<string, number[]>>("param_locks_v1")) ?? {};
+      setParamLocks(savedLocks);
+      setParamLocksLoaded(true);
+
       const storedProvider = (await store.get<string>("llm_provider_v1")) ?? "gemini";
       const provider = API_PROVIDERS.find((p) => p.id === storedProvider)?.id ?? "gemini";
       setApiProvider(provider as ProviderId);
 
       const storedModel = (await store.get<string>("llm_model_v1")) ?? "";
       setApiModel(storedModel);
+      setApiBaseUrl((await store.get<string>("llm_base_url_v1")) ?? "");
       setCredentialsLoaded(true);
     })();
 
@@ -506,6 +786,35 @@ export default function App() {
     })();
   }, [indexRemap]);
 
+  useEffect(() => {
+    if (!paramLocksLoaded) return;
+    void (async () => {
+      await store.set("param_locks_v1", paramLocks);
+      await store.save();
+      if (isTauriRuntime()) {
+        await invoke("clear_param_locks", { targetFxGuid: null });
+        for (const [fxGuid, indices] of Object.entries(paramLocks)) {
+          await invoke("set_param_locks", { targetFxGuid: fxGuid, indices });
+        }
+      }
+    })();
+  }, [paramLocks, paramLocksLoaded]);
+
+  // Locks are stored as canonical indices; the diff shows actual (remapped) ones.
+  const toggleParamLock = (actualIndex: number) => {
+    if (!selectedFxGuid) return;
+    const fromEntry = Object.entries(indexRemap).find(([, to]) => to === actualIndex);
+    const index = fromEntry ? Number(fromEntry[0]) : actualIndex;
+    setParamLocks((prev) => {
+      const cur = prev[selectedFxGuid] ?? [];
+      const next = cur.includes(index) ? cur.filter((i) => i !== index) : [...cur, index].sort((a, b) => a - b);
+      const out = { ...prev };
+      if (next.length) out[selectedFxGuid] = next;
+      else delete out[selectedFxGuid];
+      return out;
+    });
+  };
+
   useEffect(() => {
     if (!selectedFxGuid) return;
     void (async () => {
@@ -514,6 +823,36 @@ export default function App() {
     })();
   }, [selectedFxGuid]);
 
+  useEffect(() => {
+    // Re-read the live tone on target switch and whenever the user comes back from the plugin GUI.
+    if (!tauri || !selectedFxGuid) return;
+    const read = () => void invoke("get_current_tone", { targetFxGuid: selectedFxGuid }).catch(() => {});
+    read();
+    window.addEventListener("focus", read);
+    return () => window.removeEventListener("focus", read);
+  }, [tauri, selectedFxGuid]);
+
+  useEffect(() => {
+    // Follow GUI edits on the selected target for as long as it stays selected.
+    if (!tauri || !selectedFxGuid || !capabilities.includes("param_subscription")) return;
+    const indices = Array.from({ length: TONE_PARAM_COUNT }, (_, i) => i);
+    void invoke("subscribe_params", { targetFxGuid: selectedFxGuid, indices }).catch(() => {});
+    return () => void invoke("subscribe_params", { targetFxGuid: selectedFxGuid, indices: [] }).catch(() => {});
+  }, [tauri, selectedFxGuid, capabilities]);
+
+  useEffect(() => {
+    setPresetList(null);
+    setFxState(null);
+    if (!tauri || !selectedFxGuid) return;
+    if (capabilities.includes("presets")) {
+      void invoke("list_presets", { targetFxGuid: selectedFxGuid }).catch(() => {});
+    }
+    if (capabilities.includes("fx_state")) {
+      // Empty write: just reads the current enable/wet back.
+      void invoke("set_fx_state", { targetFxGuid: selectedFxGuid, enabled: null, wet: null }).catch(() => {});
+    }
+  }, [tauri, selectedFxGuid, capabilities]);
+
   useEffect(() => {
     scheduleAutosave();
   }, [chatStoreReady, activeChatId, history, cursor, snapshots, composer]);
@@ -534,6 +873,14 @@ export default function App() {
     })();
   }, [apiModel, credentialsLoaded]);
 
+  useEffect(() => {
+    if (!credentialsLoaded) return;
+    void (async () => {
+      await store.set("llm_base_url_v1", apiBaseUrl);
+      await store.save();
+    })();
+  }, [apiBaseUrl, credentialsLoaded]);
+
   const pickupSaveTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
   useEffect(() => {
     if (pickupSaveTimer.current) clearTimeout(pickupSaveTimer.current);
@@ -625,6 +972,7 @@ export default function App() {
     const effectivePreviewOnly = previewOnly || noTargetSelected;
 
     setBusy(true);
+    setThinking(null);
     try {
       const prompt = buildPromptFromChat({
         messages: chatAfterUser,
@@ -649,6 +997,7 @@ export default function App() {
         baseParams: refineActive ? base.workingParams : null,
         provider: apiProvider,
         model: apiModel.trim() || null,
+        baseUrl: apiProvider === "custom" ? apiBaseUrl.trim() || null : null,
       });
 
       const assistantMsg: ChatMessage = {
@@ -696,6 +1045,7 @@ export default function App() {
       commit({ ...base, chat: [...chatAfterUser, assistantMsg] }, { label: "ai error", anchorMessageId: assistantMsg.id });
     } finally {
       setBusy(false);
+      setThinking(null);
     }
   }
 
@@ -709,6 +1059,123 @@ export default function App() {
         targetFxGuid: effectiveFxGuid,
         mode: w.lastGenMode,
         params: w.preview.params,
+        rampMs: glide ? GLIDE_MS : null,
+      });
+      pendingApplyIdRef.current = commandId;
+      setPendingApplyCommandId(commandId);
+      setTab("qc");
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function checkInReaper() {
+    const w = workspaceRef.current;
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!w.preview || !effectiveFxGuid) return;
+    await invoke<string>("validate_tone", {
+      targetFxGuid: effectiveFxGuid,
+      params: w.preview.params,
+    });
+    await invoke<string>("preview_tone", {
+      targetFxGuid: effectiveFxGuid,
+      mode: w.lastGenMode,
+      params: w.preview.params,
+    });
+  }
+
+  async function applyToAll() {
+    const w = workspaceRef.current;
+    const guids = instances.map((i) => i.fx_guid);
+    if (!w.preview || guids.length < 2) return;
+    setBusy(true);
+    try {
+      const commandId = await invoke<string>("apply_tone_multi", {
+        targetFxGuids: guids,
+        mode: w.lastGenMode,
+        params: w.preview.params,
+      });
+      pendingApplyIdRef.current = commandId;
+      setPendingApplyCommandId(commandId);
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function revertInReaper() {
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!effectiveFxGuid) return;
+    setBusy(true);
+    try {
+      await invoke<string>("undo_last_tone", { targetFxGuid: effectiveFxGuid });
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function restoreInReaper() {
+    if (!lastSnapshot) return;
+    const targetFxGuid = lastSnapshot.fxGuid || (selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "");
+    setBusy(true);
+    try {
+      await invoke<string>("restore_snapshot", { targetFxGuid, snapshotId: lastSnapshot.id });
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function savePreset(name: string) {
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!effectiveFxGuid || !name.trim()) return;
+    await invoke<string>("save_preset", { targetFxGuid: effectiveFxGuid, name: name.trim() });
+  }
+
+  async function loopAndPlay(start: number, end: number) {
+    await invoke<string>("set_loop", { start, end });
+    await invoke<string>("set_playing", { playing: true });
+  }
+
+  async function renderPreview() {
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!effectiveFxGuid) return;
+    await invoke<string>("render_preview", { targetFxGuid: effectiveFxGuid, start: null, length: null });
+  }
+
+  async function insertGojira() {
+    // The new instance shows up with the handshake that follows.
+    await invoke<string>("insert_fx", { trackGuid: null, fxName: null });
+  }
+
+  async function applyFxState(change: { enabled?: boolean; wet?: number }) {
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!effectiveFxGuid) return;
+    await invoke<string>("set_fx_state", {
+      targetFxGuid: effectiveFxGuid,
+      enabled: change.enabled ?? null,
+      wet: change.wet ?? null,
+    });
+  }
+
+  async function loadPreset(name: string) {
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!effectiveFxGuid) return;
+    setBusy(true);
+    try {
+      await invoke<string>("load_preset", { targetFxGuid: effectiveFxGuid, name });
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function tryAlternative(index: number, value: number) {
+    const effectiveFxGuid = selectedInstance?.fx_guid ?? instances[0]?.fx_guid ?? "";
+    if (!effectiveFxGuid) return;
+    setBusy(true);
+    try {
+      const commandId = await invoke<string>("apply_tone", {
+        targetFxGuid: effectiveFxGuid,
+        mode: "merge",
+        params: [{ index, value }],
       });
       pendingApplyIdRef.current = commandId;
       setPendingApplyCommandId(commandId);
@@ -741,6 +1208,8 @@ export default function App() {
           onRedo={redo}
           previewOnly={previewOnly}
           setPreviewOnly={setPreviewOnly}
+          glide={glide}
+          setGlide={setGlide}
           refineEnabled={refineEnabled}
           setRefineEnabled={setRefineEnabled}
           refineDisabled={refineDisabled}
@@ -751,6 +1220,8 @@ export default function App() {
           setApiProvider={setApiProvider}
           apiModel={apiModel}
           setApiModel={setApiModel}
+          apiBaseUrl={apiBaseUrl}
+          setApiBaseUrl={setApiBaseUrl}
           apiKeyDrafts={apiKeyDrafts}
           setApiKeyDraft={setApiKeyDraft}
           apiKeyPresence={apiKeyPresence}
@@ -768,6 +1239,20 @@ export default function App() {
           snapshots={snapshots}
           onRestoreSnapshot={restoreSnapshot}
           onApplySnapshot={applySnapshot}
+          presets={presetList?.saved ?? []}
+          currentNativePreset={presetList?.current_native ?? null}
+          onSavePreset={(name) => void savePreset(name)}
+          onLoadPreset={(name) => void loadPreset(name)}
+          fxEnabled={fxState?.enabled ?? null}
+          fxWet={fxState?.wet ?? null}
+          onSetFxState={(change) => void applyFxState(change)}
+          onInsertGojira={() => void insertGojira()}
+          transport={transport}
+          onLoopAndPlay={(start, end) => void loopAndPlay(start, end)}
+          onSetPlaying={(playing) => void invoke("set_playing", { playing }).catch(() => {})}
+          renderedPreviewPath={renderedPreview?.path ?? null}
+          onRenderPreview={() => void renderPreview()}
+          capabilities={capabilities}
         />
 
         <ChatPanel
@@ -775,12 +1260,18 @@ export default function App() {
           composer={composer}
           setComposer={setComposer}
           busy={busy}
+          thinking={thinking}
           refineActive={refineActive}
           canSend={tauri}
           canApply={!!(selectedInstance?.fx_guid ?? instances[0]?.fx_guid) && !!workspace.preview}
+          canApplyAll={instances.length > 1}
+          onApplyAll={() => void applyToAll()}
           pendingApply={!!pendingApplyCommandId}
           onSend={send}
           onApply={apply}
+          onRevert={() => void revertInReaper()}
+          canRestore={!!lastSnapshot}
+          onRestore={() => void restoreInReaper()}
           tab={tab}
           setTab={setTab}
           onRevertToMessage={jumpToMessage}
@@ -796,11 +1287,25 @@ export default function App() {
           appliedSorted={appliedSorted}
           ackStats={ackStats}
           validationReport={validationReport}
+          metrics={metrics}
+          onRefreshMetrics={() => void invoke("request_metrics").catch(() => {})}
           indexRemap={indexRemap}
           setIndexRemap={setIndexRemap}
+          proposedRemap={proposedRemap}
           paramEnums={paramEnums}
+          paramEnumTotals={paramEnumTotals}
+          onSearchEnumLabels={(index, query) =>
+            void invoke("search_enum_labels", { index, query, offset: null }).catch(() => {})
+          }
           paramFormats={paramFormats}
           paramFormatSamples={paramFormatSamples}
+          lockedIndices={(paramLocks[selectedFxGuid] ?? []).map((i) => indexRemap[i] ?? i)}
+          onToggleLock={toggleParamLock}
+          canApply={!busy && !!(selectedInstance?.fx_guid ?? instances[0]?.fx_guid)}
+          onTryAlternative={(index, value) => void tryAlternative(index, value)}
+          reaperPreview={reaperPreview}
+          toneValidation={toneValidation}
+          onCheckInReaper={() => void checkInReaper().catch(() => {})}
         />
       </div>
     </div>
@@ -43,7 +43,7 @@ export const API_PROVIDERS: ApiProviderOption[] = [
   {
     id: "custom",
     label: "Custom / self-hosted",
-    placeholder: "Bearer token",
-    hint: "Use with a custom endpoint",
+    placeholder: "Bearer token (optional)",
+    hint: "Any OpenAI-compatible server: llama.cpp, Ollama, vLLM",
   },
 ];
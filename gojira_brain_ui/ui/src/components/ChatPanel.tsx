@@ -1,4 +1,5 @@
 import React, { useEffect, useRef } from "react";
+import type { ToneThinking } from "../types";
 import type { ChatMessage } from "../workspace";
 import { formatTime } from "../workspace";
 
@@ -7,6 +8,7 @@ export default function ChatPanel(props: {
   composer: string;
   setComposer: (v: string) => void;
   busy: boolean;
+  thinking: ToneThinking | null;
   refineActive: boolean;
   canSend: boolean;
   canApply: boolean;
@@ -14,6 +16,11 @@ export default function ChatPanel(props: {
 
   onSend: () => void;
   onApply: () => void;
+  canApplyAll: boolean;
+  onApplyAll: () => void;
+  onRevert: () => void;
+  canRestore: boolean;
+  onRestore: () => void;
 
   tab: "preview" | "qc" | "mapping";
   setTab: (t: "preview" | "qc" | "mapping") => void;
@@ -26,7 +33,9 @@ export default function ChatPanel(props: {
     const el = listRef.current;
     if (!el) return;
     el.scrollTo({ top: el.scrollHeight });
-  }, [props.chat.length]);
+  }, [props.chat.length, props.thinking]);
+
+  const thinking = props.busy ? props.thinking : null;
 
   return (
     <main className="panel chat">
@@ -69,6 +78,18 @@ export default function ChatPanel(props: {
               </div>
             </div>
           ))}
+          {thinking ? (
+            <div className="bubble bubbleAssistant">
+              {thinking.research ? <div className="muted">{thinking.research}</div> : null}
+              {thinking.reasoning}
+              <div className="bubbleMeta">
+                <span>AI</span>
+                <span>
+                  {thinking.paramCount ? `${thinking.paramCount} params so far…` : "Thinking…"}
+                </span>
+              </div>
+            </div>
+          ) : null}
         </div>
 
         <div className="composer">
@@ -123,6 +144,35 @@ export default function ChatPanel(props: {
               >
                 {props.pendingApply ? "Applying…" : "Apply"}
               </button>
+              {props.canApplyAll ? (
+                <button
+                  className="btn"
+                  disabled={props.busy || !props.canApply}
+                  onClick={props.onApplyAll}
+                  type="button"
+                  title="Apply to every Gojira instance in the project (one REAPER undo step)"
+                >
+                  Apply to all
+                </button>
+              ) : null}
+              <button
+                className="btn"
+                disabled={props.busy || !props.canSend}
+                onClick={props.onRevert}
+                type="button"
+                title="Undo the last tone applied to REAPER (one REAPER undo step)"
+              >
+                Revert
+              </button>
+              <button
+                className="btn"
+                disabled={props.busy || !props.canRestore}
+                onClick={props.onRestore}
+                type="button"
+                title="Put the plugin back the way it was before the last apply (ignores REAPER's undo history)"
+              >
+                Restore
+              </button>
             </div>
           </div>
         </div>
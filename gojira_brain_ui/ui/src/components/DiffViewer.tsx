@@ -110,6 +110,8 @@ export default function DiffViewer(props: {
   items: DiffItem[];
   formats?: ParamFormats;
   samples?: ParamFormatSamples;
+  lockedIndices?: number[];
+  onToggleLock?: (index: number) => void;
 }) {
   if (!props.items.length) {
     return <div className="muted">No changes.</div>;
@@ -124,10 +126,24 @@ export default function DiffViewer(props: {
             : it.new_value !== null && it.new_value > it.old_value;
         const hOld = fmtHuman(it.index, it.old_value, props.formats, props.samples);
         const hNew = fmtHuman(it.index, it.new_value, props.formats, props.samples);
+        const locked = !!it.locked || !!props.lockedIndices?.includes(it.index);
         return (
-          <div key={`${it.index}`} className="diffRow">
+          <div key={`${it.index}`} className={`diffRow ${locked ? "locked" : ""}`}>
             <div className="diffLabel">
+              {props.onToggleLock ? (
+                <button
+                  type="button"
+                  className="lockToggle"
+                  title={locked ? "Unlock: AI may change this param" : "Lock: keep this param as dialed"}
+                  onClick={() => props.onToggleLock?.(it.index)}
+                >
+                  {locked ? "\u{1F512}" : "\u{1F513}"}
+                </button>
+              ) : locked ? (
+                <span title="Locked">{"\u{1F512}"}</span>
+              ) : null}
               <span className="badge">#{it.index}</span> {it.label}
+              {it.locked ? <span className="muted"> (locked, not sent)</span> : null}
             </div>
             <div className={`diffValue ${isUp ? "up" : "down"}`}>
               {fmtNorm(it.old_value)} -&gt; {fmtNorm(it.new_value)}
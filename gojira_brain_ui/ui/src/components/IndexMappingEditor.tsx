@@ -1,4 +1,5 @@
 import React, { useState } from "react";
+import type { ParamCheck } from "../types";
 
 export type IndexRemapEntry = { from: number; to: number; label: string };
 
@@ -17,10 +18,14 @@ const knownRoles: Array<IndexRemapEntry> = [
 export default function IndexMappingEditor({
   remap,
   onChange,
+  proposed = {},
+  validationReport = [],
 }: {
   remap: Record<number, number>;
   onChange: (next: Record<number, number>) => void;
-  validationReport?: Record<string, string>;
+  /** Validator guesses for flagged toggles/mix controls, from the handshake. */
+  proposed?: Record<number, number>;
+  validationReport?: ParamCheck[];
 }) {
   const [customFrom, setCustomFrom] = useState("");
   const [customTo, setCustomTo] = useState("");
@@ -39,6 +44,22 @@ export default function IndexMappingEditor({
     onChange({});
   }
 
+  // Proposals the mapping doesn't already follow.
+  const pending = Object.entries(proposed)
+    .map(([from, to]) => [Number(from), to] as const)
+    .filter(([from, to]) => (remap[from] ?? from) !== to);
+
+  function describe(from: number) {
+    const check = validationReport.find((c) => c.expected_index === from);
+    return check ? `${check.module} ${check.role}` : "";
+  }
+
+  function adoptProposed() {
+    const next = { ...remap };
+    for (const [from, to] of pending) next[from] = to;
+    onChange(next);
+  }
+
   function addCustom() {
     const f = Number(customFrom);
     const t = Number(customTo);
@@ -57,6 +78,18 @@ export default function IndexMappingEditor({
         </button>
       </div>
 
+      {pending.length > 0 && (
+        <div className="diffRow">
+          <div className="diffLabel">
+            Validator proposes:{" "}
+            {pending.map(([from, to]) => `#${from} → #${to} ${describe(from)}`.trim()).join(", ")}
+          </div>
+          <button className="btn" type="button" onClick={adoptProposed}>
+            Adopt
+          </button>
+        </div>
+      )}
+
       <div className="diffList">
         {knownRoles.map((r) => {
           const current = remap[r.from] ?? r.from;
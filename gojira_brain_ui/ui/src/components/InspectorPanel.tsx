@@ -1,7 +1,16 @@
 import React from "react";
 import DiffViewer from "./DiffViewer";
 import IndexMappingEditor from "./IndexMappingEditor";
-import type { AckMessage, AppliedParam, ParamChange, PreviewResult } from "../types";
+import type {
+  AckMessage,
+  AppliedParam,
+  ParamChange,
+  ParamCheck,
+  PreviewResult,
+  ReaperPreviewMessage,
+  RuntimeMetrics,
+  ToneValidationMessage,
+} from "../types";
 
 export default function InspectorPanel(props: {
   tab: "preview" | "qc" | "mapping";
@@ -13,13 +22,30 @@ export default function InspectorPanel(props: {
   appliedSorted: AppliedParam[];
   ackStats: { count: number; mismatched: number };
 
-  validationReport: Record<string, string>;
+  validationReport: ParamCheck[];
+  metrics: RuntimeMetrics | null;
+  onRefreshMetrics: () => void;
   indexRemap: Record<number, number>;
   setIndexRemap: (m: Record<number, number>) => void;
+  proposedRemap: Record<number, number>;
   paramEnums: Record<string, Array<{ value: number; label: string }>>;
+  paramEnumTotals: Record<string, number>;
+  onSearchEnumLabels: (index: number, query: string) => void;
   paramFormats: Record<string, { min: string; mid: string; max: string }>;
   paramFormatSamples: Record<string, Array<{ norm: number; formatted: string }>>;
+  lockedIndices: number[];
+  onToggleLock: (index: number) => void;
+  canApply: boolean;
+  onTryAlternative: (index: number, value: number) => void;
+  reaperPreview: ReaperPreviewMessage | null;
+  toneValidation: ToneValidationMessage | null;
+  onCheckInReaper: () => void;
 }) {
+  const alternatives = (props.preview?.alternatives ?? []).filter((a) => a.options.length > 0);
+  const [irQuery, setIrQuery] = React.useState("");
+  const enumCount = (k: string) =>
+    `${props.paramEnums[k]?.length ?? 0}${props.paramEnumTotals[k] ? ` of ${props.paramEnumTotals[k]}` : ""}`;
+  const pagedIrs = ["92", "99"].filter((k) => props.paramEnumTotals[k]);
   return (
     <aside className="panel inspector">
       <div className="panelHeader">
@@ -50,13 +76,88 @@ export default function InspectorPanel(props: {
         <div style={{ display: props.tab === "preview" ? "block" : "none", padding: "12px 14px" }}>
           <h3>Engineer’s Notes</h3>
           <div className="notes">{props.preview?.reasoning || "Generate a tone to see reasoning."}</div>
+          {alternatives.length > 0 && (
+            <>
+              <h3>Alternatives</h3>
+              {alternatives.map((a) => (
+                <div key={a.index} className="altRow">
+                  <span className="muted">
+                    #{a.index} {a.chosen ?? ""} →
+                  </span>
+                  {a.options.map((o) => (
+                    <button
+                      key={o.label}
+                      className="btn altBtn"
+                      type="button"
+                      disabled={!props.canApply}
+                      title={`Apply ${o.label} (${o.value.toFixed(3)}) to #${a.index}`}
+                      onClick={() => props.onTryAlternative(a.index, o.value)}
+                    >
+                      {o.label}
+                    </button>
+                  ))}
+                </div>
+              ))}
+            </>
+          )}
           <h3>Diff</h3>
-          <DiffViewer items={props.preview?.diff ?? []} formats={props.paramFormats} samples={props.paramFormatSamples} />
+          <DiffViewer
+            items={props.preview?.diff ?? []}
+            formats={props.paramFormats}
+            samples={props.paramFormatSamples}
+            lockedIndices={props.lockedIndices}
+            onToggleLock={props.onToggleLock}
+          />
           <div className="muted" style={{ marginTop: 10 }}>
             {props.lastGenMode === "merge"
               ? "Preview shows changes vs current preset; Apply sends only deltas."
               : "Preview is a full preset; Apply replaces active chain."}
           </div>
+          <h3>In REAPER</h3>
+          <button className="btn" type="button" disabled={!props.canApply || !props.preview} onClick={props.onCheckInReaper}>
+            Check against plugin
+          </button>
+          {props.toneValidation &&
+            (props.toneValidation.warnings.length ? (
+              <ul style={{ marginTop: 8 }}>
+                {props.toneValidation.warnings.map((w, i) => (
+                  <li key={`tv:${i}`}>
+                    <span className="badge">{w.issue.replace(/_/g, " ")}</span> {w.msg}
+                  </li>
+                ))}
+              </ul>
+            ) : (
+              <div className="muted" style={{ marginTop: 8 }}>
+                No problems found against the plugin.
+              </div>
+            ))}
+          {props.reaperPreview && (
+            <div style={{ maxHeight: 320, overflow: "auto", marginTop: 8 }}>
+              <table className="table">
+                <thead>
+                  <tr>
+                    <th style={{ width: 64 }}>Idx</th>
+                    <th>Param</th>
+                    <th>Now</th>
+                    <th>After</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {props.reaperPreview.rows.map((r) => {
+                    const same = r.before != null && Math.abs(r.before - r.after) < 1e-6;
+                    return (
+                      <tr key={`rp:${r.index}`} className={same ? "muted" : undefined}>
+                        <td>#{r.index}</td>
+                        <td>{r.name}</td>
+                        <td>{r.before_formatted || (r.before != null ? r.before.toFixed(3) : "?")}</td>
+                        <td>{r.after_formatted || r.after.toFixed(3)}</td>
+                      </tr>
+                    );
+                  })}
+                </tbody>
+              </table>
+            </div>
+          )}
         </div>
 
         <div style={{ display: props.tab === "qc" ? "block" : "none", padding: "12px 14px" }}>
@@ -67,6 +168,20 @@ export default function InspectorPanel(props: {
               <div className="muted" style={{ marginBottom: 10 }}>
                 Ack: <span className="badge">{props.lastAck.command_id}</span>
               </div>
+              {props.lastAck.skipped_modules?.length ? (
+                <div className="muted" style={{ marginBottom: 10 }}>
+                  Left as-is:{" "}
+                  {props.lastAck.skipped_modules
+                    .map((m) => (m.active == null ? m.name : `${m.name} (${m.active ? "on" : "off"})`))
+                    .join(", ")}
+                </div>
+              ) : null}
+              {props.lastAck.failed_params?.length ? (
+                <div className="muted" style={{ marginBottom: 10 }}>
+                  Not written:{" "}
+                  {props.lastAck.failed_params.map((f) => `#${f.index} (${f.error})`).join(", ")}
+                </div>
+              ) : null}
               <div style={{ maxHeight: 520, overflow: "auto" }}>
                 <table className="table">
                   <thead>
@@ -106,22 +221,60 @@ export default function InspectorPanel(props: {
           <h3>Index Mapping</h3>
           <div className="muted" style={{ marginBottom: 10 }}>
             Validator report:{" "}
-            {Object.keys(props.validationReport).length
-              ? Object.entries(props.validationReport)
-                  .map(([k, v]) => `${k}: ${v}`)
-                  .join(" | ")
+            {props.validationReport.length
+              ? [
+                  `${props.validationReport.filter((c) => c.status === "ok").length}/${props.validationReport.length} as charted`,
+                  ...props.validationReport
+                    .filter((c) => c.status !== "ok")
+                    .map((c) => `${c.module} ${c.role} ${c.expected_index}: ${c.status} (${c.found_name ?? "no param"})`),
+                ].join(" | ")
               : "(disabled)"}
           </div>
-          <IndexMappingEditor remap={props.indexRemap} onChange={props.setIndexRemap} validationReport={props.validationReport} />
+          <div className="muted" style={{ marginBottom: 10 }}>
+            Runtime metrics:{" "}
+            {props.metrics
+              ? [
+                  `cmds ${props.metrics.commands_received} (ok ${props.metrics.commands_applied}, err ${props.metrics.commands_rejected}, coalesced ${props.metrics.commands_coalesced})`,
+                  `apply avg ${props.metrics.avg_apply_ms.toFixed(2)} ms / max ${props.metrics.max_apply_ms.toFixed(2)} ms`,
+                  `${props.metrics.ticks_per_sec.toFixed(1)} ticks/s`,
+                  `probe last ${props.metrics.last_probe_ms.toFixed(0)} ms / max ${props.metrics.max_probe_ms.toFixed(0)} ms`,
+                  `queues in ${props.metrics.inbound_high_water} / out ${props.metrics.outbound_high_water} (dropped ${props.metrics.outbound_dropped})`,
+                ].join(" | ")
+              : "(not fetched)"}{" "}
+            <button className="btn" type="button" onClick={props.onRefreshMetrics}>
+              Refresh
+            </button>
+          </div>
+          <IndexMappingEditor
+            remap={props.indexRemap}
+            onChange={props.setIndexRemap}
+            proposed={props.proposedRemap}
+            validationReport={props.validationReport}
+          />
 
           <details style={{ marginTop: 12 }}>
             <summary className="muted" style={{ cursor: "pointer" }}>
               Cab / IR options (from REAPER)
             </summary>
             <div className="muted" style={{ marginTop: 8 }}>
-              Cab Type (84): {props.paramEnums["84"]?.length ?? 0} | Mic IR Cab1 (92):{" "}
-              {props.paramEnums["92"]?.length ?? 0} | Mic IR Cab2 (99): {props.paramEnums["99"]?.length ?? 0}
+              Cab Type (84): {enumCount("84")} | Mic IR Cab1 (92): {enumCount("92")} | Mic IR Cab2 (99):{" "}
+              {enumCount("99")}
             </div>
+            {pagedIrs.length ? (
+              <div className="row" style={{ marginTop: 8 }}>
+                <input
+                  value={irQuery}
+                  onChange={(e) => setIrQuery(e.target.value)}
+                  placeholder="Find IRs not listed yet"
+                />
+                <button
+                  type="button"
+                  onClick={() => pagedIrs.forEach((k) => props.onSearchEnumLabels(Number(k), irQuery))}
+                >
+                  Search
+                </button>
+              </div>
+            ) : null}
             <div style={{ marginTop: 8 }}>
               {props.paramEnums["84"]?.length ? (
                 <div style={{ marginBottom: 10 }}>
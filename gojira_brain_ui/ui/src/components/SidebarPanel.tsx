@@ -1,12 +1,12 @@
 import React from "react";
 import type { ApiProviderOption, ProviderId } from "../apiProviders";
-import type { GojiraInstance } from "../types";
+import type { Capability, GojiraInstance, TransportMessage } from "../types";
 import type { PickupPosition, SavedSnapshot } from "../workspace";
 import { formatTime } from "../workspace";
 import type { ChatSessionMeta } from "../chatArchive";
 
 export default function SidebarPanel(props: {
-  status: "connecting" | "connected" | "disconnected";
+  status: "connecting" | "connected" | "unresponsive" | "disconnected";
   instances: GojiraInstance[];
   selectedFxGuid: string;
   setSelectedFxGuid: (v: string) => void;
@@ -27,6 +27,8 @@ export default function SidebarPanel(props: {
 
   previewOnly: boolean;
   setPreviewOnly: (v: boolean) => void;
+  glide: boolean;
+  setGlide: (v: boolean) => void;
 
   refineEnabled: boolean;
   setRefineEnabled: (v: boolean) => void;
@@ -37,6 +39,8 @@ export default function SidebarPanel(props: {
   setApiProvider: (v: ProviderId) => void;
   apiModel: string;
   setApiModel: (v: string) => void;
+  apiBaseUrl: string;
+  setApiBaseUrl: (v: string) => void;
   vaultPassphrase: string;
   setVaultPassphrase: (v: string) => void;
   apiKeyDrafts: Record<ProviderId, string>;
@@ -58,7 +62,31 @@ export default function SidebarPanel(props: {
   snapshots: SavedSnapshot[];
   onRestoreSnapshot: (s: SavedSnapshot) => void;
   onApplySnapshot: (s: SavedSnapshot) => void;
+
+  presets: string[];
+  currentNativePreset: string | null;
+  onSavePreset: (name: string) => void;
+  onLoadPreset: (name: string) => void;
+
+  fxEnabled: boolean | null;
+  fxWet: number | null;
+  onSetFxState: (change: { enabled?: boolean; wet?: number }) => void;
+
+  onInsertGojira: () => void;
+
+  transport: TransportMessage | null;
+  onLoopAndPlay: (start: number, end: number) => void;
+  onSetPlaying: (playing: boolean) => void;
+  renderedPreviewPath: string | null;
+  onRenderPreview: () => void;
+
+  capabilities: Capability[];
 }) {
+  const [presetName, setPresetName] = React.useState("");
+  const [loopStart, setLoopStart] = React.useState("0");
+  const [loopEnd, setLoopEnd] = React.useState("8");
+  const loopValid = Number(loopStart) >= 0 && Number(loopEnd) > Number(loopStart);
+
   return (
     <aside className="panel sidebar">
       <div className="panelHeader">
@@ -89,7 +117,20 @@ export default function SidebarPanel(props: {
             <div>FX: {props.selectedInstance.fx_name || "Archetype Gojira"}</div>
           </div>
         ) : (
-          <div className="muted">Open a REAPER project with Archetype Gojira loaded.</div>
+          <div className="muted">
+            Open a REAPER project with Archetype Gojira loaded, or add it to the selected track.
+            <div style={{ marginTop: 8 }}>
+              <button
+                className="btn"
+                type="button"
+                disabled={props.status !== "connected"}
+                title="Add Archetype Gojira to the track selected in REAPER."
+                onClick={props.onInsertGojira}
+              >
+                Add to selected track
+              </button>
+            </div>
+          </div>
         )}
 
         <div className="divider" />
@@ -168,6 +209,12 @@ export default function SidebarPanel(props: {
             Preview only
           </label>
         </div>
+        <div className="row" style={{ marginBottom: 0 }}>
+          <label className="checkbox" title="Ramp knobs to the new tone instead of jumping (no zipper noise while playing)">
+            <input checked={props.glide} onChange={(e) => props.setGlide(e.target.checked)} type="checkbox" />
+            Glide on apply
+          </label>
+        </div>
 
         <div className="row" style={{ marginTop: 10 }}>
           <label>Mode</label>
@@ -229,9 +276,20 @@ export default function SidebarPanel(props: {
               />
             </div>
 
+            {props.apiProvider === "custom" ? (
+              <div className="row">
+                <label>Base URL</label>
+                <input
+                  value={props.apiBaseUrl}
+                  onChange={(e) => props.setApiBaseUrl(e.target.value)}
+                  placeholder="http://localhost:11434/v1"
+                />
+              </div>
+            ) : null}
+
             <div className="muted">
-              Keys live in the Stronghold vault. Gemini remains the active tone engine for now; other providers are staged here so you can save
-              credentials and model names ahead of time.
+              Keys live in the Stronghold vault. Tones are generated with the active provider; Gemini picks its model by default, the others
+              need one set here. Azure OpenAI isn't supported yet.
             </div>
 
             <div className="divider" />
@@ -322,6 +380,141 @@ export default function SidebarPanel(props: {
           </div>
         </details>
 
+        <details style={{ marginTop: 10 }}>
+          <summary className="muted" style={{ cursor: "pointer" }}>
+            Presets
+          </summary>
+          <div style={{ marginTop: 10 }}>
+            <div className="row">
+              <label>Save as</label>
+              <input
+                value={presetName}
+                onChange={(e) => setPresetName(e.target.value)}
+                placeholder="e.g. Djent rhythm"
+              />
+              <button
+                className="btn"
+                type="button"
+                disabled={!props.selectedFxGuid || !presetName.trim() || !props.capabilities.includes("presets")}
+                title="Save the target's current values as a preset."
+                onClick={() => {
+                  props.onSavePreset(presetName);
+                  setPresetName("");
+                }}
+              >
+                Save
+              </button>
+            </div>
+            {props.currentNativePreset ? (
+              <div className="muted">Plugin preset: {props.currentNativePreset}</div>
+            ) : null}
+            {props.presets.length ? (
+              <div className="diffList" style={{ maxHeight: 240, overflow: "auto", marginTop: 8 }}>
+                {props.presets.map((name) => (
+                  <div key={name} className="diffRow" style={{ alignItems: "center" }}>
+                    <div className="diffLabel">{name}</div>
+                    <button
+                      className="btn"
+                      type="button"
+                      disabled={!props.selectedFxGuid}
+                      onClick={() => props.onLoadPreset(name)}
+                    >
+                      Load
+                    </button>
+                  </div>
+                ))}
+              </div>
+            ) : (
+              <div className="muted">No saved presets yet.</div>
+            )}
+          </div>
+        </details>
+
+        <details style={{ marginTop: 10 }}>
+          <summary className="muted" style={{ cursor: "pointer" }}>
+            Audition
+          </summary>
+          <div style={{ marginTop: 10 }}>
+            <div className="row">
+              <label>Loop (s)</label>
+              <input value={loopStart} onChange={(e) => setLoopStart(e.target.value)} inputMode="decimal" />
+              <input value={loopEnd} onChange={(e) => setLoopEnd(e.target.value)} inputMode="decimal" />
+            </div>
+            <div className="row" style={{ marginBottom: 0 }}>
+              <button
+                className="btn"
+                type="button"
+                disabled={props.status !== "connected" || !loopValid || !props.capabilities.includes("transport")}
+                title="Loop the riff in REAPER and play it from the start."
+                onClick={() => props.onLoopAndPlay(Number(loopStart), Number(loopEnd))}
+              >
+                Loop &amp; play
+              </button>
+              <button
+                className="btn"
+                type="button"
+                disabled={props.status !== "connected" || !props.capabilities.includes("transport")}
+                onClick={() => props.onSetPlaying(!props.transport?.playing)}
+              >
+                {props.transport?.playing ? "Stop" : "Play"}
+              </button>
+              <button
+                className="btn"
+                type="button"
+                disabled={!props.selectedFxGuid || !props.capabilities.includes("render_preview")}
+                title={
+                  props.capabilities.includes("render_preview")
+                    ? "Bounce 5 s of the mix from the loop start to a WAV."
+                    : "This REAPER's Gojira DLL can't render clips; update it."
+                }
+                onClick={props.onRenderPreview}
+              >
+                Render clip
+              </button>
+            </div>
+            {props.renderedPreviewPath ? <div className="muted">Rendered: {props.renderedPreviewPath}</div> : null}
+            {props.transport?.loop_start != null && props.transport?.loop_end != null ? (
+              <div className="muted">
+                Looping {props.transport.loop_start.toFixed(2)}–{props.transport.loop_end.toFixed(2)} s
+                {props.transport.repeat ? "" : " (repeat off)"}
+              </div>
+            ) : null}
+          </div>
+        </details>
+
+        <details style={{ marginTop: 10 }}>
+          <summary className="muted" style={{ cursor: "pointer" }}>
+            A/B
+          </summary>
+          <div style={{ marginTop: 10 }}>
+            <div className="row" style={{ marginBottom: 0 }}>
+              <label className="checkbox" title="Switch the plugin on/off in REAPER to compare against the dry signal">
+                <input
+                  checked={props.fxEnabled ?? true}
+                  disabled={!props.selectedFxGuid || props.fxEnabled === null}
+                  onChange={(e) => props.onSetFxState({ enabled: e.target.checked })}
+                  type="checkbox"
+                />
+                Plugin on
+              </label>
+            </div>
+            <div className="row">
+              <label>Wet</label>
+              <input
+                type="range"
+                min={0}
+                max={1}
+                step={0.01}
+                value={props.fxWet ?? 1}
+                disabled={!props.selectedFxGuid || props.fxWet === null}
+                title="REAPER's wet knob for the plugin; below 100% blends in the dry signal"
+                onChange={(e) => props.onSetFxState({ wet: Number(e.target.value) })}
+              />
+              <span className="muted">{props.fxWet === null ? "—" : `${Math.round(props.fxWet * 100)}%`}</span>
+            </div>
+          </div>
+        </details>
+
         {props.snapshots.length ? (
           <>
             <div className="divider" />
@@ -6,6 +6,7 @@ const dotColor: Record<StatusEvent["status"], string> = {
   disconnected: "#777",
   connecting: "#f0c000",
   connected: "#00b070",
+  unresponsive: "#e05030",
 };
 
 export default function StatusBar({ status }: { status: StatusEvent }) {
@@ -27,7 +28,10 @@ export default function StatusBar({ status }: { status: StatusEvent }) {
               ? "Connected to Reaper"
               : status.status === "connecting"
                 ? "Connecting..."
-                : `Disconnected${retryText}`}
+                : status.status === "unresponsive"
+                  ? "Reaper is not responding"
+                  : `Disconnected${retryText}`}
+            {status.url ? <span className="muted"> · {status.url}</span> : null}
           </span>
           {status.status === "disconnected" ? (
             <span className="muted" style={{ fontSize: 12 }}>
@@ -0,0 +1,1123 @@
+// Generated from gojira_protocol; don't edit by hand. Regenerate with
+// cargo run -p gojira_protocol --features schema --bin gojira_schema -- --ts gojira_brain_ui/ui/src/protocol.gen.ts --py tools/gojira_client.py
+
+/**
+ * One written param as the plugin holds it afterwards; `applied` differing from `requested`
+ * means the plugin quantized or rejected the value.
+ */
+export type AppliedParam = {
+  index: number;
+  requested: number;
+  applied: number;
+  formatted: string;
+};
+
+/**
+ * Something a DLL build handles beyond the handshake, acks and errors, as listed in its
+ * handshake. Clients enable a feature by its flag rather than by version, so a command rolled
+ * out to some DLLs first doesn't break UIs talking to the others.
+ */
+export type Capability =
+  | "atomic_apply"
+  | "multi_target"
+  | "scheduled_apply"
+  | "module_filter"
+  | "background_projects"
+  | "param_subscription"
+  | "enum_search"
+  | "presets"
+  | "tone_slots"
+  | "preview_tone"
+  | "validate_tone"
+  | "render_preview"
+  | "snapshots"
+  | "transport"
+  | "fx_state"
+  | "track_controls"
+  | "insert_fx"
+  | "resume"
+  | "diagnostics"
+  | "msgpack"
+  | "unknown";
+
+export type Confidence = "high" | "low";
+
+export type ErrorCode =
+  | "unauthorized"
+  | "busy"
+  | "target_not_found"
+  | "invalid_value"
+  | "invalid_command"
+  | "not_ready"
+  | "internal_error"
+  | "target_changed"
+  | "unsupported"
+  | "timeout"
+  | "partial_failure"
+  | "unknown";
+
+/** The structured side of an error, so clients can act on it instead of parsing `msg`. */
+export type ErrorDiagnostic = {
+  /** The param the command failed on. */
+  param_index: number | null;
+  /** The value it failed with, as sent. */
+  value: number | null;
+  /** Set on target-not-found errors. */
+  resolver: ResolverState | null;
+  remediation: Remediation | null;
+};
+
+/** A param whose write REAPER refused, with the value that was sent and REAPER's reason. */
+export type FailedParam = {
+  index: number;
+  value: number;
+  error: string;
+};
+
+export type GojiraInstance = {
+  track_guid: string;
+  track_name: string;
+  fx_guid: string;
+  fx_name: string;
+  last_known_fx_index: number;
+  confidence: Confidence;
+  /** Plugin profile the FX name matched ("gojira", "plini", ...); unset from older DLLs. */
+  profile: string | null;
+};
+
+/** One entry of the DLL's event log. */
+export type LogEvent = {
+  /** Milliseconds since the DLL was loaded. */
+  at_ms: number;
+  level: LogLevel;
+  /** Where it came from, e.g. "init", "net" or "main_loop". */
+  target: string;
+  msg: string;
+};
+
+/** Severity of a DLL log event, most severe first. */
+export type LogLevel = "error" | "warn" | "info" | "debug";
+
+export type MergeMode = "merge" | "replace_active";
+
+/** A part of the layout `set_tone` can be limited to: one of the pedals, or a whole section. */
+export type ModuleId =
+  | "wow"
+  | "oct"
+  | "overdrive"
+  | "distortion"
+  | "phaser"
+  | "chorus"
+  | "delay"
+  | "reverb"
+  | "global"
+  | "amp"
+  | "eq"
+  | "cab";
+
+/**
+ * Normalized values are f64 end to end: REAPER takes doubles, and f32 rounding can tip a
+ * quantized selector (e.g. 0.125976562) onto the neighboring option. f32-producing clients still
+ * deserialize fine, since JSON numbers carry no width.
+ */
+export type ParamChange = {
+  index: number;
+  value: number;
+  op?: ParamOp;
+};
+
+/** One line of the handshake's validation report. */
+export type ParamCheck = {
+  /** Module the param belongs to, e.g. "delay". */
+  module: string;
+  role: ParamRole;
+  expected_index: number;
+  /** Name the chart has for the index. */
+  expected_name: string | null;
+  /** Name the plugin reports at the index. */
+  found_name: string | null;
+  status: ParamCheckStatus;
+};
+
+/** How a charted param's index held up against the plugin. */
+export type ParamCheckStatus = "ok" | "suspicious" | "missing";
+
+export type ParamEnumOption = {
+  value: number;
+  label: string;
+};
+
+export type ParamFormatSample = {
+  norm: number;
+  formatted: string;
+};
+
+export type ParamFormatTriplet = {
+  min: string;
+  mid: string;
+  max: string;
+};
+
+/** How a parameter moves, from REAPER's step sizes or, failing that, its formatted values. */
+export type ParamKind = { kind: "toggle" } | { steps: number; kind: "stepped" } | { kind: "continuous" };
+
+/** What a `ParamChange`'s value means. */
+export type ParamOp = "set" | "add";
+
+export type ParamReading = {
+  index: number;
+  value: number;
+  formatted: string;
+};
+
+/** What a charted param does in its module. */
+export type ParamRole = "knob" | "toggle" | "mix";
+
+/** One param of the handshake's `param_schema`. */
+export type ParamSchemaEntry = {
+  name: string;
+  kind: ParamSchemaKind;
+  /** Section of the plugin, e.g. "amp_eq" or "cab"; "other" where the layout isn't charted. */
+  group: string;
+};
+
+/**
+ * What a control is, as far as labelling and editing it goes. Unlike `ParamKind` this is known
+ * at handshake time, from the plugin's step sizes and the charted layout.
+ */
+export type ParamSchemaKind = "toggle" | "continuous" | "enum";
+
+/** One line of a `preview`. `before` is `None` when the param can't be read back. */
+export type PreviewRow = {
+  index: number;
+  name: string;
+  before: number | null;
+  before_formatted: string;
+  after: number;
+  after_formatted: string;
+};
+
+export type ProjectTempo = {
+  bpm: number;
+  beats_per_measure: number;
+};
+
+/** What a client can do about an error. */
+export type Remediation =
+  | "refresh_instances"
+  | "fix_params"
+  | "complete_handshake"
+  | "reauthenticate"
+  | "retry"
+  | "allow_background_projects"
+  | "unknown";
+
+/** A message `replay` passes on, with how long before the replay it was sent. */
+export type ReplayedEvent = {
+  age_ms: number;
+  message: ServerMessage;
+};
+
+/** How far the resolver got with a target it could not use. */
+export type ResolverState = "cache_miss" | "verify_failed" | "background_project" | "unknown";
+
+/** DLL counters since it was loaded, answered to `get_metrics`. Durations are milliseconds. */
+export type RuntimeMetrics = {
+  uptime_secs: number;
+  commands_received: number;
+  /** Commands answered with an ack / with an error by the main loop. */
+  commands_applied: number;
+  commands_rejected: number;
+  /** SetTone glides cut short by a newer command on the same FX. */
+  commands_coalesced: number;
+  avg_apply_ms: number;
+  max_apply_ms: number;
+  /** Timer ticks over the last full second. */
+  ticks_per_sec: number;
+  /** Handshake probes (validation, kinds, EQ bands) and streamed param meta chunks. */
+  probes: number;
+  last_probe_ms: number;
+  max_probe_ms: number;
+  inbound_high_water: number;
+  outbound_high_water: number;
+  /** Server messages lost because the outbound queue was full. */
+  outbound_dropped: number;
+};
+
+/**
+ * A module a `merge` set_tone sent no params for; `active` is its toggle as read from the
+ * plugin, `None` if it couldn't be read.
+ */
+export type SkippedModule = {
+  name: string;
+  active: boolean | null;
+};
+
+export type TargetResult = {
+  target_fx_guid: string;
+  applied_params: AppliedParam[];
+  /** Writes REAPER refused while the rest of the target's params went through. */
+  failed_params: FailedParam[];
+  /** Set when the target was skipped or none of its params could be written. */
+  error: string | null;
+};
+
+/** How long the main loop's recurring work takes, in milliseconds. */
+export type TimingStats = {
+  ticks: number;
+  avg_tick_ms: number;
+  max_tick_ms: number;
+  /** Instance rescans, from handshakes and the project watchdog. */
+  scans: number;
+  avg_scan_ms: number;
+  max_scan_ms: number;
+  applies: number;
+  avg_apply_ms: number;
+  max_apply_ms: number;
+};
+
+/** What `validate_tone` found wrong with a param. */
+export type ToneIssue =
+  | "unknown_param"
+  | "reserved_param"
+  | "duplicate"
+  | "out_of_range"
+  | "off_enum_option"
+  | "fractional_toggle"
+  | "module_off";
+
+/** A stored tone slot as `tone_slots` lists it. */
+export type ToneSlotInfo = {
+  slot: number;
+  name: string;
+  target_fx_guid: string;
+  param_count: number;
+};
+
+/** One FX of a `set_tone_multi`; the fields mean what they do on `set_tone`. */
+export type ToneTarget = {
+  target_fx_guid: string;
+  mode: MergeMode;
+  params: ParamChange[];
+  locked_params?: number[];
+};
+
+/** One problem `validate_tone` found. */
+export type ToneWarning = {
+  issue: ToneIssue;
+  /** The param at fault; for `module_off`, the module's toggle. */
+  index: number;
+  msg: string;
+};
+
+/** Where in playback a scheduled `set_tone` lands. */
+export type TransportAnchor =
+  | { kind: "next_bar" }
+  | { kind: "on_stop" }
+  | { position_secs: number; kind: "at" };
+
+export type WarningCode =
+  | "reserved_index_skipped"
+  | "command_coalesced"
+  | "linked_cab_override"
+  | "readback_unavailable"
+  | "background_project"
+  | "unknown";
+
+export type HandshakeAckCommand = {
+  type: "handshake_ack";
+  session_token: string;
+};
+
+/**
+ * Rescan and hand everyone a new handshake. With `instances_only`, the sender alone gets a
+ * bare `Instances` list instead, for polling without re-sending the param meta.
+ */
+export type RefreshInstancesCommand = {
+  type: "refresh_instances";
+  session_token: string;
+  instances_only?: boolean;
+};
+
+/**
+ * Forget the DLL's cached param meta for the plugin and probe it again. Everyone gets a new
+ * handshake, with the meta following as `ParamMeta` chunks.
+ *
+ * With `indices`, only those params are probed again (e.g. the IR selectors after new IR
+ * files were installed), with no handshake; everyone gets one `ParamMeta` covering them.
+ */
+export type RefreshParamMetaCommand = {
+  type: "refresh_param_meta";
+  session_token: string;
+  indices?: number[];
+};
+
+/** Ask for a fresh session token; answered with `token_rotated`. */
+export type RotateTokenCommand = {
+  type: "rotate_token";
+  session_token: string;
+};
+
+/**
+ * Take over the session of a connection that dropped less than `RESUME_GRACE_SECS` ago, by
+ * the token it had. Subscriptions and the probe results carry over and the connection goes
+ * on under that token; answered with `resumed`, or a handshake if the plugin must be probed
+ * again. Sent first thing, it spares the connection its handshake.
+ */
+export type ResumeCommand = {
+  type: "resume";
+  previous_token: string;
+};
+
+/** Ask for the DLL's runtime counters; answered with `metrics`. */
+export type GetMetricsCommand = {
+  type: "get_metrics";
+  session_token: string;
+};
+
+/** Ask for the DLL's recent log events and timings; answered with `diagnostics`. */
+export type GetDiagnosticsCommand = {
+  type: "get_diagnostics";
+  session_token: string;
+  /** How many events to return; the DLL's default when unset. */
+  limit?: number | null;
+};
+
+/**
+ * Liveness check, answered with `pong` from REAPER's main thread rather than the socket
+ * thread, so a reply means the DLL is actually ticking.
+ */
+export type PingCommand = {
+  type: "ping";
+  session_token: string;
+  nonce: number;
+};
+
+/** Read back every tone parameter of an instance; answered with `current_tone`. */
+export type GetCurrentToneCommand = {
+  type: "get_current_tone";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+};
+
+export type SetToneCommand = {
+  type: "set_tone";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  mode: MergeMode;
+  params: ParamChange[];
+  /** Indices the user pinned: never written, and their modules are never auto-bypassed. */
+  locked_params?: number[];
+  /**
+   * Glide continuous params to their targets over this many milliseconds (capped at
+   * 5000) instead of jumping; toggles and stepped params still switch at once. The ack
+   * comes when the glide ends.
+   */
+  ramp_ms?: number | null;
+  /**
+   * All or nothing: if any write fails, or reads back off target, every param is put
+   * back to its value from before the command and an `error` is sent. Never glides, so
+   * `ramp_ms` is ignored.
+   */
+  atomic?: boolean;
+  /**
+   * Hold the tone back until the transport reaches this point; the ack comes once it is
+   * written. Without one the tone is written right away.
+   */
+  apply_at?: TransportAnchor | null;
+  /**
+   * Write only the params of these modules, picked out after cleaning, e.g. just the cab
+   * of a full tone; the rest of `params` is dropped. Needs a charted plugin layout.
+   */
+  modules?: ModuleId[] | null;
+  /**
+   * Find the target in other open project tabs too, not just the current one. Writing to
+   * a background tab is announced with a `background_project` warning.
+   */
+  allow_background_projects?: boolean;
+};
+
+/**
+ * Dry run of `set_tone`: sanitized, cleaned and resolved against the live FX exactly like
+ * one, but nothing is written; answered with `preview`.
+ */
+export type PreviewToneCommand = {
+  type: "preview_tone";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  mode: MergeMode;
+  params: ParamChange[];
+  locked_params?: number[];
+};
+
+/**
+ * Checks `params` against the live FX without cleaning or writing them: that each index
+ * exists, that enum values land on a probed option, and that toggles agree with the params
+ * they gate. Answered with `tone_validation`.
+ */
+export type ValidateToneCommand = {
+  type: "validate_tone";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  params: ParamChange[];
+};
+
+/**
+ * `set_tone` for several FX at once (e.g. L/R guitar tracks), as one REAPER undo step and
+ * one `multi_ack`. A target that fails does not stop the others.
+ */
+export type SetToneMultiCommand = {
+  type: "set_tone_multi";
+  session_token: string;
+  command_id: string;
+  targets: ToneTarget[];
+  /** As on `set_tone`, for every target. */
+  allow_background_projects?: boolean;
+};
+
+/**
+ * Reverts the most recent tone applied through `set_tone`, as one REAPER undo step.
+ * Refused when something else sits on top of REAPER's undo history.
+ */
+export type UndoLastToneCommand = {
+  type: "undo_last_tone";
+  session_token: string;
+  command_id: string;
+};
+
+/**
+ * Writes back the values the FX had right before a `set_tone` (or every FX of a
+ * `set_tone_multi`); `snapshot_id` is that command's `command_id`. Works regardless of what happened to REAPER's undo history since.
+ */
+export type RestoreSnapshotCommand = {
+  type: "restore_snapshot";
+  session_token: string;
+  command_id: string;
+  snapshot_id: string;
+};
+
+/**
+ * Watch `indices` on the FX and get `param_changed` pushes when they move. Replaces this
+ * client's earlier subscription for the same FX; empty `indices` ends it.
+ */
+export type SubscribeCommand = {
+  type: "subscribe";
+  session_token: string;
+  target_fx_guid: string;
+  indices?: number[];
+};
+
+/**
+ * Stores the FX's current tone under `name` in the sidecar's preset folder (REAPER's API
+ * can load plugin presets but not write them). Overwrites a preset of the same name.
+ */
+export type SavePresetCommand = {
+  type: "save_preset";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  name: string;
+};
+
+/**
+ * Applies a preset saved with `save_preset`, else the plugin's own preset of that name as
+ * shown in REAPER's preset dropdown. One REAPER undo step, like `set_tone`.
+ */
+export type LoadPresetCommand = {
+  type: "load_preset";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  name: string;
+};
+
+/**
+ * Options of the enum at `index` whose labels contain `query` (case-insensitive; empty
+ * matches all), for enums the handshake only carries the first page of. Answered with
+ * `param_enum_page`; options still being probed are not there yet.
+ */
+export type SearchEnumLabelsCommand = {
+  type: "search_enum_labels";
+  session_token: string;
+  command_id: string;
+  index: number;
+  query?: string;
+  offset?: number;
+  /** Defaults to the handshake's page size. */
+  limit?: number | null;
+};
+
+/** Answered with `preset_list`. */
+export type ListPresetsCommand = {
+  type: "list_presets";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+};
+
+/**
+ * Keeps a named tone in `slot` (0..=127) for `activate_tone_slot`, so switching to it later
+ * doesn't take the whole param list again. Replaces what the slot held; no params clears
+ * it. Slots are saved with the project; answered with `ack`.
+ */
+export type StoreToneSlotCommand = {
+  type: "store_tone_slot";
+  session_token: string;
+  command_id: string;
+  slot: number;
+  name?: string;
+  target_fx_guid: string;
+  mode: MergeMode;
+  params: ParamChange[];
+  locked_params?: number[];
+};
+
+/**
+ * Applies a stored slot as the `set_tone` it was stored from, with this command's glide and
+ * timing; answered with `ack`.
+ */
+export type ActivateToneSlotCommand = {
+  type: "activate_tone_slot";
+  session_token: string;
+  command_id: string;
+  slot: number;
+  ramp_ms?: number | null;
+  apply_at?: TransportAnchor | null;
+};
+
+/** Answered with `tone_slots`. */
+export type ListToneSlotsCommand = {
+  type: "list_tone_slots";
+  session_token: string;
+  command_id: string;
+};
+
+/**
+ * REAPER's per-FX wet knob (0..1) and delta solo; the plugin's own params are untouched.
+ * Fields left out are not changed.
+ */
+export type SetFxMixCommand = {
+  type: "set_fx_mix";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  wet?: number | null;
+  delta_solo?: boolean | null;
+};
+
+/**
+ * The FX's enable switch and REAPER's wet knob (0..1), for A/B against the dry signal or a
+ * parallel blend. Fields left out are not changed; answered with `fx_state`.
+ */
+export type SetFxStateCommand = {
+  type: "set_fx_state";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  enabled?: boolean | null;
+  wet?: number | null;
+};
+
+/**
+ * Bounce `length` seconds of the master mix from `start` to a temp WAV, for listening to (or
+ * scoring) a tone without playing the project; answered with `preview_rendered`. `start`
+ * defaults to the loop start, `length` to 5 s; at most 30 s are rendered.
+ */
+export type RenderPreviewCommand = {
+  type: "render_preview";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  start?: number | null;
+  length?: number | null;
+};
+
+/**
+ * Start playback from the edit cursor, which `set_loop` puts at the loop start; answered
+ * with `transport`.
+ */
+export type PlayCommand = {
+  type: "play";
+  session_token: string;
+  command_id: string;
+};
+
+/** Stop playback; answered with `transport`. */
+export type StopCommand = {
+  type: "stop";
+  session_token: string;
+  command_id: string;
+};
+
+/**
+ * Loop `start..end` (project time, seconds) with repeat on and the edit cursor at `start`, so
+ * the next `play` auditions the riff; answered with `transport`.
+ */
+export type SetLoopCommand = {
+  type: "set_loop";
+  session_token: string;
+  command_id: string;
+  start: number;
+  end: number;
+};
+
+/**
+ * Add the plugin to a track, for when no instance was found. Everyone gets a new handshake
+ * listing it; failures come back as `error`.
+ */
+export type InsertFxCommand = {
+  type: "insert_fx";
+  session_token: string;
+  command_id: string;
+  /** Defaults to the track selected in REAPER. */
+  track_guid?: string | null;
+  /** Name as REAPER's FX browser matches it; defaults to the Gojira plugin. */
+  fx_name?: string | null;
+};
+
+/**
+ * Volume, pan and record monitoring of the track hosting the FX, for "sit it lower in the
+ * mix" requests. Fields left out are not changed; answered with `track_controls`.
+ */
+export type SetTrackControlsCommand = {
+  type: "set_track_controls";
+  session_token: string;
+  command_id: string;
+  target_fx_guid: string;
+  /** Clamped to -150..=12 dB. */
+  volume_db?: number | null;
+  /** -1 (hard left) to 1 (hard right). */
+  pan?: number | null;
+  rec_monitor?: boolean | null;
+};
+
+/**
+ * Tone switching by instance: disable one Gojira and enable another on the same track.
+ * Applied in one tick, disable first, so both are never active at once.
+ */
+export type SwitchFxPairCommand = {
+  type: "switch_fx_pair";
+  session_token: string;
+  command_id: string;
+  enable_fx_guid: string;
+  disable_fx_guid: string;
+};
+
+export type ClientCommand =
+  | HandshakeAckCommand
+  | RefreshInstancesCommand
+  | RefreshParamMetaCommand
+  | RotateTokenCommand
+  | ResumeCommand
+  | GetMetricsCommand
+  | GetDiagnosticsCommand
+  | PingCommand
+  | GetCurrentToneCommand
+  | SetToneCommand
+  | PreviewToneCommand
+  | ValidateToneCommand
+  | SetToneMultiCommand
+  | UndoLastToneCommand
+  | RestoreSnapshotCommand
+  | SubscribeCommand
+  | SavePresetCommand
+  | LoadPresetCommand
+  | SearchEnumLabelsCommand
+  | ListPresetsCommand
+  | StoreToneSlotCommand
+  | ActivateToneSlotCommand
+  | ListToneSlotsCommand
+  | SetFxMixCommand
+  | SetFxStateCommand
+  | RenderPreviewCommand
+  | PlayCommand
+  | StopCommand
+  | SetLoopCommand
+  | InsertFxCommand
+  | SetTrackControlsCommand
+  | SwitchFxPairCommand;
+
+/**
+ * First message on every connection: the session token, the instances and what the DLL
+ * knows about their params.
+ */
+export type HandshakeMessage = {
+  type: "handshake";
+  session_token: string;
+  instances: GojiraInstance[];
+  /**
+   * Every charted module toggle and knob as the plugin names it; empty unless the DLL runs
+   * with GOJIRA_SEND_VALIDATION_REPORT.
+   */
+  validation_report: ParamCheck[];
+  /**
+   * Enum options, formats and samples follow as `ParamMeta` chunks; those param maps are
+   * then empty. Older DLLs put everything here.
+   */
+  param_meta_streaming: boolean;
+  param_enums: Record<string, ParamEnumOption[]>;
+  /**
+   * Full option count of the enums above cut to their first page, like long IR lists; the
+   * rest are pulled with `search_enum_labels`.
+   */
+  param_enum_totals: Record<string, number>;
+  param_formats: Record<string, ParamFormatTriplet>;
+  param_format_samples: Record<string, ParamFormatSample[]>;
+  /** Graphic-EQ band index -> center frequency (Hz), when the plugin exposes it. */
+  eq_band_hz: Record<string, number>;
+  param_kinds: Record<string, ParamKind>;
+  /**
+   * Every tone param of the probed instance by its actual index: the name the plugin
+   * reports, its kind and section. Clients label and group params from this rather than
+   * from tables of their own.
+   */
+  param_schema: Record<string, ParamSchemaEntry>;
+  /**
+   * Charted param index -> the index the plugin now reports that param's name at, for
+   * params a plugin update has moved. Meant to seed the client's index remap.
+   */
+  param_remap: Record<string, number>;
+  /**
+   * Canonical -> actual index guesses for toggles and mix controls the validation report
+   * flags, by a name fitting the role near the charted index. Unlike `param_remap`, meant
+   * for the user to confirm rather than to adopt as is.
+   */
+  proposed_remap: Record<string, number>;
+  /** Project tempo at the start of the timeline. */
+  project_tempo: ProjectTempo | null;
+  /** Address the DLL's server is actually bound to, for diagnostics. */
+  server_addr: string | null;
+  /** Certificate fingerprint when the server speaks TLS, to compare with the pinned one. */
+  tls_fingerprint: string | null;
+  /**
+   * What this DLL build supports; empty from DLLs that predate the list, which support
+   * none of it reliably.
+   */
+  capabilities: Capability[];
+};
+
+/**
+ * Param meta for indices `first_index..=last_index`, probed after the handshake went out.
+ * Merged into what the client already has; `done` marks the last chunk.
+ */
+export type ParamMetaMessage = {
+  type: "param_meta";
+  first_index: number;
+  last_index: number;
+  param_enums: Record<string, ParamEnumOption[]>;
+  /**
+   * Full option count of the enums above cut to their first page, like long IR lists; the
+   * rest are pulled with `search_enum_labels`.
+   */
+  param_enum_totals: Record<string, number>;
+  param_formats: Record<string, ParamFormatTriplet>;
+  param_format_samples: Record<string, ParamFormatSample[]>;
+  /** Kinds of the tone params in this range, enums counting as stepped. */
+  param_kinds: Record<string, ParamKind>;
+  done: boolean;
+};
+
+/**
+ * One per project whose instances changed, so a tab switch arrives as the old project's
+ * instances removed and the new one's added.
+ */
+export type ProjectChangedMessage = {
+  type: "project_changed";
+  project_tempo: ProjectTempo | null;
+  /** File name of the project the instance deltas are from; unset while it's unsaved. */
+  project_name: string | null;
+  /**
+   * Instances whose tone params moved since the DLL last read them, or that are gone.
+   * Instances the DLL never read are not tracked, so they are never listed.
+   */
+  changed_instances: string[];
+  /** Instances that appeared since the last scan. */
+  added: GojiraInstance[];
+  /** Instances that are gone, as they were last seen. */
+  removed: GojiraInstance[];
+  /** Instances still there whose track name, FX name or position changed, as they are now. */
+  renamed: GojiraInstance[];
+};
+
+export type AckMessage = {
+  type: "ack";
+  command_id: string;
+  applied_params: AppliedParam[];
+  /** Modules a `merge` set_tone left as they were. */
+  skipped_modules: SkippedModule[];
+  /**
+   * Params REAPER refused to write. The rest were written anyway; a command none of whose
+   * params could be written gets an `error` instead.
+   */
+  failed_params: FailedParam[];
+};
+
+/** Reply to `set_tone_multi`: one result per target, in request order. */
+export type MultiAckMessage = {
+  type: "multi_ack";
+  command_id: string;
+  results: TargetResult[];
+};
+
+export type ErrorMessage = {
+  type: "error";
+  /** Human-readable summary; `diagnostic` carries the same facts for clients to act on. */
+  msg: string;
+  code: ErrorCode;
+  /** Optional diagnostic context (e.g. nearby parameter names on apply failures). */
+  details: string | null;
+  /** The failed command's `command_id`, for commands that carry one. */
+  command_id: string | null;
+  diagnostic: ErrorDiagnostic | null;
+};
+
+/**
+ * Reply to `rotate_token`, or pushed when the server-side token TTL runs out. The previous
+ * token stays valid for a short grace period so in-flight commands are not rejected.
+ */
+export type TokenRotatedMessage = {
+  type: "token_rotated";
+  session_token: string;
+  expires_in_secs: number | null;
+};
+
+/** Reply to `get_metrics`. */
+export type MetricsMessage = {
+  type: "metrics";
+  metrics: RuntimeMetrics;
+};
+
+/**
+ * Reply to `RefreshInstances` with `instances_only`: the rescanned list and nothing else.
+ * The param meta from the last handshake still holds.
+ */
+export type InstancesMessage = {
+  type: "instances";
+  instances: GojiraInstance[];
+};
+
+/** Reply to `resume`: the connection now goes by `session_token`, the one it asked for. */
+export type ResumedMessage = {
+  type: "resumed";
+  session_token: string;
+  instances: GojiraInstance[];
+};
+
+/** Reply to `get_diagnostics`: the most recent log events, oldest first. */
+export type DiagnosticsMessage = {
+  type: "diagnostics";
+  events: LogEvent[];
+  timings: TimingStats;
+};
+
+/**
+ * Pushed when a command finds an instance moved since the last scan, e.g. dragged along its
+ * FX chain or onto another track, so clients can repoint their references before the
+ * project's next rescan. `instance` is as it is now.
+ */
+export type InstanceMovedMessage = {
+  type: "instance_moved";
+  instance: GojiraInstance;
+  from_track_guid: string;
+  from_fx_index: number;
+};
+
+/** Reply to `ping`, echoing its nonce. */
+export type PongMessage = {
+  type: "pong";
+  nonce: number;
+  uptime_ms: number;
+};
+
+/**
+ * Reply to `search_enum_labels`: the matching options from `offset` on. `total` counts every
+ * match, so a page ending short of it means there are more.
+ */
+export type ParamEnumPageMessage = {
+  type: "param_enum_page";
+  command_id: string;
+  index: number;
+  query: string;
+  offset: number;
+  total: number;
+  options: ParamEnumOption[];
+};
+
+/** Reply to `get_current_tone`: what the plugin holds right now, including GUI edits. */
+export type CurrentToneMessage = {
+  type: "current_tone";
+  command_id: string;
+  target_fx_guid: string;
+  params: ParamReading[];
+  /**
+   * Every param a command has written to this FX, with the value it read back. Saved in
+   * the project, so it outlives the session and a REAPER restart.
+   */
+  last_applied: ParamChange[];
+};
+
+/**
+ * Reply to `preview_tone`: each param the set_tone would write, as the plugin holds it now
+ * and as it would read after the write. Nothing was written.
+ */
+export type PreviewMessage = {
+  type: "preview";
+  command_id: string;
+  target_fx_guid: string;
+  rows: PreviewRow[];
+  skipped_modules: SkippedModule[];
+};
+
+/** Reply to `validate_tone`. No `warnings` means the set looks sound; nothing was written. */
+export type ToneValidationMessage = {
+  type: "tone_validation";
+  command_id: string;
+  target_fx_guid: string;
+  warnings: ToneWarning[];
+};
+
+/** Reply to `render_preview`: where the WAV landed, and the range actually rendered. */
+export type PreviewRenderedMessage = {
+  type: "preview_rendered";
+  command_id: string;
+  target_fx_guid: string;
+  path: string;
+  start: number;
+  length: number;
+};
+
+/** Reply to `play`, `stop` and `set_loop`: the transport as REAPER reports it afterwards. */
+export type TransportMessage = {
+  type: "transport";
+  command_id: string;
+  playing: boolean;
+  repeat: boolean;
+  /** Loop points in seconds; unset when the project has none. */
+  loop_start: number | null;
+  loop_end: number | null;
+};
+
+/**
+ * Reply to `set_fx_state`: the FX's enable switch and wet knob as REAPER reads them after
+ * the write. `wet` is unset when the FX doesn't expose the knob.
+ */
+export type FxStateMessage = {
+  type: "fx_state";
+  command_id: string;
+  target_fx_guid: string;
+  enabled: boolean;
+  wet: number | null;
+};
+
+/**
+ * Reply to `set_track_controls`: the host track's controls as REAPER reads them after the
+ * write. A field is unset when REAPER doesn't report it.
+ */
+export type TrackControlsMessage = {
+  type: "track_controls";
+  command_id: string;
+  target_fx_guid: string;
+  track_guid: string;
+  volume_db: number | null;
+  pan: number | null;
+  /** On for REAPER's "auto" monitoring as well. */
+  rec_monitor: boolean | null;
+};
+
+/**
+ * Pushed to clients that sent `subscribe` when subscribed params move, e.g. a knob turned
+ * in the plugin GUI. Only the params that changed since the last push are listed.
+ */
+export type ParamChangedMessage = {
+  type: "param_changed";
+  target_fx_guid: string;
+  params: ParamReading[];
+};
+
+/** Reply to `list_presets`. */
+export type PresetListMessage = {
+  type: "preset_list";
+  command_id: string;
+  target_fx_guid: string;
+  /** Presets stored with `save_preset`, sorted by name. */
+  saved: string[];
+  /** The plugin's own preset the FX reports being on, if any. */
+  current_native: string | null;
+};
+
+/** Reply to `list_tone_slots`. */
+export type ToneSlotsMessage = {
+  type: "tone_slots";
+  command_id: string;
+  /** Sorted by slot. */
+  slots: ToneSlotInfo[];
+  /** Slot activated last, if it is still stored. */
+  active_slot: number | null;
+};
+
+/** Something the user should know about that did not abort the command. */
+export type WarningMessage = {
+  type: "warning";
+  code: WarningCode;
+  msg: string;
+  /** What it applies to, e.g. the command id or the affected indices. */
+  context: string | null;
+};
+
+/**
+ * Sent right after a handshake to the first client in after a spell with none connected:
+ * what it missed meanwhile (acks and errors of MIDI and action-list switches, project
+ * changes), oldest first. Capped, so the oldest may be gone.
+ */
+export type ReplayMessage = {
+  type: "replay";
+  events: ReplayedEvent[];
+};
+
+/**
+ * The last message before the DLL closes every connection. A socket that drops without one
+ * means REAPER crashed or the network went away.
+ */
+export type ShutdownMessage = {
+  type: "shutdown";
+  /** Why the server is going away, e.g. "reaper_exiting". */
+  reason: string;
+  /**
+   * Set when the DLL expects to be back shortly, so clients can keep retrying at their
+   * usual pace instead of settling in for REAPER to be started again.
+   */
+  restart_expected: boolean;
+};
+
+export type ServerMessage =
+  | HandshakeMessage
+  | ParamMetaMessage
+  | ProjectChangedMessage
+  | AckMessage
+  | MultiAckMessage
+  | ErrorMessage
+  | TokenRotatedMessage
+  | MetricsMessage
+  | InstancesMessage
+  | ResumedMessage
+  | DiagnosticsMessage
+  | InstanceMovedMessage
+  | PongMessage
+  | ParamEnumPageMessage
+  | CurrentToneMessage
+  | PreviewMessage
+  | ToneValidationMessage
+  | PreviewRenderedMessage
+  | TransportMessage
+  | FxStateMessage
+  | TrackControlsMessage
+  | ParamChangedMessage
+  | PresetListMessage
+  | ToneSlotsMessage
+  | WarningMessage
+  | ReplayMessage
+  | ShutdownMessage;
@@ -1,31 +1,47 @@
-export type StatusEvent = {
-  status: "connecting" | "connected" | "disconnected";
-  retry_in?: number | null;
-};
+// The wire protocol's types are generated from gojira_protocol (`npm run protocol`); only what the
+// Tauri side makes up itself is declared here.
+import type { GojiraInstance, HandshakeMessage, ParamChange, PreviewMessage } from "./protocol.gen";
 
-export type Confidence = "high" | "low";
+export type * from "./protocol.gen";
 
-export type GojiraInstance = {
-  track_guid: string;
-  track_name: string;
-  fx_guid: string;
-  fx_name: string;
-  last_known_fx_index: number;
-  confidence: Confidence;
+/** What the Tauri side forwards of a handshake: every field, the streaming flag aside. */
+export type HandshakePayload = Required<Omit<HandshakeMessage, "type" | "param_meta_streaming">>;
+
+/** REAPER's answer to `preview_tone`, not to be confused with the brain's `PreviewResult`. */
+export type ReaperPreviewMessage = PreviewMessage;
+
+export type StatusEvent = {
+  status: "connecting" | "connected" | "unresponsive" | "disconnected";
+  retry_in?: number | null;
+  url?: string;
 };
 
-export type HandshakePayload = {
-  session_token: string;
+export type InstancesUpdatedPayload = {
   instances: GojiraInstance[];
-  validation_report: Record<string, string>;
-  param_enums?: Record<string, Array<{ value: number; label: string }>>;
-  param_formats?: Record<string, { min: string; mid: string; max: string }>;
-  param_format_samples?: Record<string, Array<{ norm: number; formatted: string }>>;
+  added: string[];
+  removed: string[];
+  moved: Array<{
+    fx_guid: string;
+    from_track_guid: string;
+    from_fx_index: number;
+    to_track_guid: string;
+    to_fx_index: number;
+  }>;
 };
 
-export type ParamChange = {
-  index: number;
-  value: number;
+export type InstanceStatus = "online" | "missing" | "disconnected";
+
+export type InstanceDashboardEntry = {
+  fx_guid: string;
+  track_name: string | null;
+  fx_name: string | null;
+  fx_index: number | null;
+  status: InstanceStatus;
+  param_count: number;
+  locked_params: number[];
+  undo_depth: number;
+  last_prompt: string | null;
+  last_applied_at: number | null;
 };
 
 export type DiffItem = {
@@ -33,23 +49,31 @@ export type DiffItem = {
   index: number;
   old_value: number | null;
   new_value: number | null;
+  locked?: boolean;
+};
+
+export type ParamAlternatives = {
+  index: number;
+  chosen: string | null;
+  options: Array<{ label: string; value: number }>;
 };
 
 export type PreviewResult = {
   reasoning: string;
   params: ParamChange[];
   diff: DiffItem[];
+  alternatives?: ParamAlternatives[];
 };
 
-export type AppliedParam = {
-  index: number;
-  requested: number;
-  applied: number;
-  formatted?: string;
-};
+// brain://progress while generate_tone runs: text arrives as deltas, params as the full list so far.
+export type ToneProgress =
+  | { kind: "research"; text: string }
+  | { kind: "reasoning"; text: string }
+  | { kind: "params"; params: ParamChange[] };
 
-export type AckMessage = {
-  type: "ack";
-  command_id: string;
-  applied_params: AppliedParam[];
+// What the model has written so far for the tone being generated.
+export type ToneThinking = {
+  research: string;
+  reasoning: string;
+  paramCount: number;
 };
@@ -0,0 +1,349 @@
+//! The Archetype Gojira's charted param layout, shared by the DLL, brain_core and the UI: every
+//! index known by name, the modules the replace_active cleaner works on, and the sections params
+//! are grouped in. Indices are where the charted plugin build reports them; the DLL's handshake
+//! says which ones a plugin update has moved.
+
+use std::ops::RangeInclusive;
+
+/// What a param's value stands for once the plugin formats it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    /// Off below 0.5, on from there.
+    Toggle,
+    /// Named options, e.g. cab types or IR files.
+    Enum,
+    Decibels,
+    Hertz,
+    Milliseconds,
+    Bpm,
+    Percent,
+    /// An amp knob's 0..10 scale.
+    Knob,
+    /// Nothing more is known than the normalized 0..1 value.
+    Normalized,
+}
+
+/// One charted param.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamSpec {
+    pub index: i32,
+    /// As the plugin labels it.
+    pub name: &'static str,
+    pub unit: Unit,
+    /// What 0.0 and 1.0 read as in `unit`, where that has been observed.
+    pub range: Option<(f64, f64)>,
+}
+
+/// Params that belong together in the plugin's signal chain, e.g. the delay pedal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModuleSpec {
+    pub name: &'static str,
+    /// Toggles that switch the module in or out.
+    pub bypass: &'static [i32],
+    pub params: &'static [i32],
+}
+
+/// Named indices for code that reaches for one param in particular.
+pub mod index {
+    pub mod global {
+        pub const INPUT_GAIN: i32 = 0;
+        pub const OUTPUT_GAIN: i32 = 1;
+        pub const NOISE_GATE: i32 = 2;
+    }
+
+    pub mod selectors {
+        pub const AMP_TYPE_INDEX: i32 = 29;
+    }
+
+    pub mod pedals {
+        pub mod wow_pitch {
+            pub const PEDAL_SWITCH: i32 = 3;
+            pub const ACTIVE: i32 = 4;
+            pub const PITCH_VAL: i32 = 6;
+        }
+
+        pub mod octaver {
+            pub const ACTIVE: i32 = 8;
+            pub const OCT1: i32 = 9;
+            pub const OCT2: i32 = 10;
+            pub const DIRECT: i32 = 11;
+        }
+
+        pub mod overdrive {
+            pub const ACTIVE: i32 = 13;
+            pub const DRIVE: i32 = 14;
+            pub const TONE: i32 = 15;
+            pub const LEVEL: i32 = 16;
+        }
+
+        pub mod distortion {
+            pub const ACTIVE: i32 = 17;
+            pub const DIST: i32 = 18;
+            pub const FILTER: i32 = 19;
+            pub const VOL: i32 = 20;
+        }
+
+        pub mod phaser {
+            pub const ACTIVE: i32 = 21;
+            pub const RATE: i32 = 22;
+        }
+
+        pub mod chorus {
+            pub const ACTIVE: i32 = 23;
+            pub const RATE: i32 = 24;
+            pub const DEPTH: i32 = 25;
+            pub const MIX: i32 = 27;
+        }
+
+        pub mod delay {
+            pub const ACTIVE: i32 = 101;
+            pub const FEEDBACK: i32 = 106;
+            pub const MIX: i32 = 105;
+            pub const TIME: i32 = 108;
+        }
+
+        pub mod reverb {
+            pub const ACTIVE: i32 = 112;
+            pub const MODE: i32 = 113;
+            pub const MIX: i32 = 114;
+            pub const TIME: i32 = 115;
+            pub const LOW_CUT: i32 = 116;
+            pub const HIGH_CUT: i32 = 117;
+        }
+    }
+
+    pub mod cab {
+        pub const ACTIVE: i32 = 83;
+        pub const TYPE_SELECTOR: i32 = 84;
+        pub const AMP_LINKED: i32 = 85;
+
+        pub mod mic1 {
+            pub const POS: i32 = 87;
+            pub const DIST: i32 = 88;
+            pub const LEVEL: i32 = 89;
+            pub const IR_SEL: i32 = 92;
+        }
+
+        pub mod mic2 {
+            pub const POS: i32 = 94;
+            pub const DIST: i32 = 95;
+            pub const LEVEL: i32 = 96;
+            pub const IR_SEL: i32 = 99;
+        }
+    }
+}
+
+const fn param(index: i32, name: &'static str, unit: Unit) -> ParamSpec {
+    ParamSpec {
+        index,
+        name,
+        unit,
+        range: None,
+    }
+}
+
+const fn ranged(index: i32, name: &'static str, unit: Unit, min: f64, max: f64) -> ParamSpec {
+    ParamSpec {
+        index,
+        name,
+        unit,
+        range: Some((min, max)),
+    }
+}
+
+/// Every charted param, by index. EQ bands are left out: their names carry the band frequency
+/// rather than a fixed label.
+pub const PARAMS: &[ParamSpec] = &[
+    ranged(0, "Input Gain", Unit::Decibels, -24.0, 24.0),
+    ranged(1, "Output Gain", Unit::Decibels, -24.0, 24.0),
+    ranged(2, "Gate Amount", Unit::Decibels, -96.0, 0.0),
+    param(3, "Pitch Section Active", Unit::Toggle),
+    param(4, "WOW Active", Unit::Toggle),
+    param(5, "WOW Type", Unit::Enum),
+    param(6, "WOW Position", Unit::Normalized),
+    ranged(7, "WOW Dry/Wet", Unit::Percent, 0.0, 100.0),
+    param(8, "OCT Active", Unit::Toggle),
+    param(9, "OCT Oct 1 Level", Unit::Normalized),
+    param(10, "OCT Oct 2 Level", Unit::Normalized),
+    param(11, "OCT Direct Level", Unit::Normalized),
+    param(13, "OD Active", Unit::Toggle),
+    param(14, "OD Drive", Unit::Normalized),
+    param(15, "OD Tone", Unit::Normalized),
+    param(16, "OD Level", Unit::Normalized),
+    param(17, "DRT Active", Unit::Toggle),
+    param(18, "DRT Dist", Unit::Normalized),
+    param(19, "DRT Filter", Unit::Normalized),
+    param(20, "DRT Vol", Unit::Normalized),
+    param(21, "PHSR Active", Unit::Toggle),
+    param(22, "PHSR Rate", Unit::Normalized),
+    param(23, "CHR Active", Unit::Toggle),
+    param(24, "CHR Rate", Unit::Normalized),
+    param(25, "CHR Depth", Unit::Normalized),
+    param(26, "CHR Feedback", Unit::Normalized),
+    ranged(27, "CHR Mix", Unit::Percent, 0.0, 100.0),
+    param(29, "Amp Type", Unit::Enum),
+    ranged(30, "CLN Gain", Unit::Knob, 0.0, 10.0),
+    // Behaves like a switch, whatever the plugin calls it.
+    param(31, "CLN Bright", Unit::Toggle),
+    ranged(32, "CLN Bass", Unit::Knob, 0.0, 10.0),
+    ranged(33, "CLN Mid", Unit::Knob, 0.0, 10.0),
+    ranged(34, "CLN Treble", Unit::Knob, 0.0, 10.0),
+    ranged(35, "CLN Level", Unit::Knob, 0.0, 10.0),
+    ranged(36, "RUST Gain", Unit::Knob, 0.0, 10.0),
+    ranged(37, "RUST Low", Unit::Knob, 0.0, 10.0),
+    ranged(38, "RUST Mid", Unit::Knob, 0.0, 10.0),
+    ranged(39, "RUST High", Unit::Knob, 0.0, 10.0),
+    ranged(40, "RUST Master", Unit::Knob, 0.0, 10.0),
+    ranged(41, "RUST Presence", Unit::Knob, 0.0, 10.0),
+    ranged(42, "RUST Depth", Unit::Knob, 0.0, 10.0),
+    ranged(43, "RUST Level", Unit::Knob, 0.0, 10.0),
+    ranged(44, "HOT Gain", Unit::Knob, 0.0, 10.0),
+    ranged(45, "HOT Low", Unit::Knob, 0.0, 10.0),
+    ranged(46, "HOT Mid", Unit::Knob, 0.0, 10.0),
+    ranged(47, "HOT High", Unit::Knob, 0.0, 10.0),
+    ranged(48, "HOT Master", Unit::Knob, 0.0, 10.0),
+    ranged(49, "HOT Presence", Unit::Knob, 0.0, 10.0),
+    ranged(50, "HOT Depth", Unit::Knob, 0.0, 10.0),
+    ranged(51, "HOT Level", Unit::Knob, 0.0, 10.0),
+    param(52, "EQ Section Active", Unit::Toggle),
+    param(53, "CLN EQ Active", Unit::Toggle),
+    param(63, "RUST EQ Active", Unit::Toggle),
+    param(73, "HOT EQ Active", Unit::Toggle),
+    param(83, "Cab Section Active", Unit::Toggle),
+    param(84, "Cab Type", Unit::Enum),
+    param(85, "Cab/Amp Linked", Unit::Toggle),
+    param(86, "Cab 1 Active", Unit::Toggle),
+    param(87, "Cab 1 Position", Unit::Normalized),
+    param(88, "Cab 1 Distance", Unit::Normalized),
+    ranged(89, "Cab 1 Level", Unit::Decibels, -24.0, 24.0),
+    param(90, "Cab 1 Pan", Unit::Normalized),
+    param(91, "Cab 1 Phase", Unit::Toggle),
+    param(92, "Cab 1 Mic IR", Unit::Enum),
+    param(93, "Cab 2 Active", Unit::Toggle),
+    param(94, "Cab 2 Position", Unit::Normalized),
+    param(95, "Cab 2 Distance", Unit::Normalized),
+    ranged(96, "Cab 2 Level", Unit::Decibels, -24.0, 24.0),
+    param(97, "Cab 2 Pan", Unit::Normalized),
+    param(98, "Cab 2 Phase", Unit::Toggle),
+    param(99, "Cab 2 Mic IR", Unit::Enum),
+    param(100, "FX Section Active", Unit::Toggle),
+    param(101, "DLY Active", Unit::Toggle),
+    ranged(105, "DLY Dry/Wet", Unit::Percent, 0.0, 100.0),
+    param(106, "DLY Feedback", Unit::Normalized),
+    ranged(108, "DLY Tempo", Unit::Bpm, 40.0, 240.0),
+    param(112, "REV Active", Unit::Toggle),
+    param(113, "REV Mode", Unit::Enum),
+    ranged(114, "REV Dry/Wet", Unit::Percent, 0.0, 100.0),
+    ranged(115, "REV Time", Unit::Milliseconds, 250.0, 10000.0),
+    ranged(116, "REV Low Cut", Unit::Hertz, 50.0, 700.0),
+    ranged(117, "REV High Cut", Unit::Hertz, 1000.0, 10000.0),
+    param(118, "Bypass", Unit::Toggle),
+];
+
+use index::pedals::{chorus, delay, distortion, octaver, overdrive, phaser, reverb, wow_pitch};
+
+pub const MODULES: &[ModuleSpec] = &[
+    // Both the pedal switch and the active flag count as bypass controls.
+    ModuleSpec {
+        name: "wow",
+        bypass: &[wow_pitch::PEDAL_SWITCH, wow_pitch::ACTIVE],
+        params: &[
+            wow_pitch::PEDAL_SWITCH,
+            wow_pitch::ACTIVE,
+            wow_pitch::PITCH_VAL,
+        ],
+    },
+    ModuleSpec {
+        name: "oct",
+        bypass: &[octaver::ACTIVE],
+        params: &[
+            octaver::ACTIVE,
+            octaver::OCT1,
+            octaver::OCT2,
+            octaver::DIRECT,
+        ],
+    },
+    ModuleSpec {
+        name: "overdrive",
+        bypass: &[overdrive::ACTIVE],
+        params: &[
+            overdrive::ACTIVE,
+            overdrive::DRIVE,
+            overdrive::TONE,
+            overdrive::LEVEL,
+        ],
+    },
+    ModuleSpec {
+        name: "distortion",
+        bypass: &[distortion::ACTIVE],
+        params: &[
+            distortion::ACTIVE,
+            distortion::DIST,
+            distortion::FILTER,
+            distortion::VOL,
+        ],
+    },
+    ModuleSpec {
+        name: "phaser",
+        bypass: &[phaser::ACTIVE],
+        params: &[phaser::ACTIVE, phaser::RATE],
+    },
+    ModuleSpec {
+        name: "chorus",
+        bypass: &[chorus::ACTIVE],
+        params: &[chorus::ACTIVE, chorus::RATE, chorus::DEPTH, chorus::MIX],
+    },
+    ModuleSpec {
+        name: "delay",
+        bypass: &[delay::ACTIVE],
+        params: &[delay::ACTIVE, delay::MIX, delay::FEEDBACK, delay::TIME],
+    },
+    ModuleSpec {
+        name: "reverb",
+        bypass: &[reverb::ACTIVE],
+        params: &[
+            reverb::ACTIVE,
+            reverb::MIX,
+            reverb::TIME,
+            reverb::LOW_CUT,
+            reverb::HIGH_CUT,
+        ],
+    },
+];
+
+/// Sections of the layout, in signal order; the MIDI CC params after the last one aren't tone.
+pub const GROUPS: &[(&str, RangeInclusive<i32>)] = &[
+    ("global", 0..=2),
+    ("pedals_pre", 3..=27),
+    ("amp_eq", 28..=82),
+    ("cab", 83..=100),
+    ("delay", 101..=111),
+    ("reverb", 112..=118),
+];
+
+/// Stretches of the layout a tone can be limited to as a whole, named like `MODULES` so one list
+/// of names covers both. Delay and reverb take in their uncharted params too.
+pub const SECTIONS: &[(&str, RangeInclusive<i32>)] = &[
+    ("global", 0..=2),
+    ("amp", 28..=51),
+    ("eq", 52..=82),
+    ("cab", 83..=100),
+    ("delay", 101..=111),
+    ("reverb", 112..=117),
+];
+
+pub fn spec(index: i32) -> Option<&'static ParamSpec> {
+    PARAMS.iter().find(|p| p.index == index)
+}
+
+/// The section `index` falls in, if it is a tone param.
+pub fn group(index: i32) -> Option<&'static str> {
+    GROUPS
+        .iter()
+        .find(|(_, range)| range.contains(&index))
+        .map(|(name, _)| *name)
+}
+
+pub fn module(name: &str) -> Option<&'static ModuleSpec> {
+    MODULES.iter().find(|m| m.name == name)
+}
@@ -0,0 +1,68 @@
+//! The chart has to hold together on its own: every index the DLL, brain_core and the UI look up
+//! has to be there exactly once, in the section it belongs to.
+
+use gojira_param_catalog::{group, index, module, spec, Unit, GROUPS, MODULES, PARAMS, SECTIONS};
+
+#[test]
+fn params_are_sorted_and_unique() {
+    assert!(PARAMS.windows(2).all(|w| w[0].index < w[1].index));
+    assert!(PARAMS.iter().all(|p| !p.name.trim().is_empty()));
+}
+
+#[test]
+fn every_param_is_in_a_group() {
+    for p in PARAMS {
+        assert!(
+            group(p.index).is_some(),
+            "{} ({}) has no group",
+            p.name,
+            p.index
+        );
+    }
+    assert!(GROUPS.windows(2).all(|w| w[0].1.end() < w[1].1.start()));
+    assert_eq!(group(119), None);
+    for (name, range) in SECTIONS {
+        assert!(
+            group(*range.start()).is_some() && group(*range.end()).is_some(),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn modules_are_charted_toggles_and_params() {
+    for m in MODULES {
+        for i in m.params {
+            assert!(spec(*i).is_some(), "{}: {} is not charted", m.name, i);
+        }
+        for i in m.bypass {
+            assert!(
+                m.params.contains(i),
+                "{}: bypass {} is not a param",
+                m.name,
+                i
+            );
+            assert_eq!(
+                spec(*i).map(|p| p.unit),
+                Some(Unit::Toggle),
+                "{}: {}",
+                m.name,
+                i
+            );
+        }
+    }
+    assert_eq!(module("delay").map(|m| m.bypass), Some(&[101][..]));
+    assert!(module("flanger").is_none());
+}
+
+#[test]
+fn ranges_run_low_to_high() {
+    for p in PARAMS {
+        if let Some((min, max)) = p.range {
+            assert!(min < max, "{} ({})", p.name, p.index);
+        }
+    }
+    let time = spec(index::pedals::reverb::TIME).unwrap();
+    assert_eq!(time.unit, Unit::Milliseconds);
+    assert_eq!(time.range, Some((250.0, 10000.0)));
+}
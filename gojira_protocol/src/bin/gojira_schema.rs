@@ -0,0 +1,51 @@
+//! Writes the protocol's JSON Schema files into the given folder (`schema` by default), one per
+//! direction, and prints their paths. `--ts <file>` and `--py <file>` write the generated
+//! TypeScript types and Python client instead; add a folder to get the schema files as well.
+
+use std::path::{Path, PathBuf};
+
+fn write(path: &Path, contents: String) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("creating {}: {e}", dir.display()))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("writing {}: {e}", path.display()))?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+fn main() -> Result<(), String> {
+    let mut dir = None;
+    let mut ts = None;
+    let mut py = None;
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let slot = match arg.to_str() {
+            Some("--ts") => &mut ts,
+            Some("--py") => &mut py,
+            _ => {
+                dir = Some(PathBuf::from(arg));
+                continue;
+            }
+        };
+        let path = args
+            .next()
+            .ok_or_else(|| format!("{} needs a file", arg.display()))?;
+        *slot = Some(PathBuf::from(path));
+    }
+
+    if let Some(path) = &ts {
+        write(path, gojira_protocol::codegen::typescript())?;
+    }
+    if let Some(path) = &py {
+        write(path, gojira_protocol::codegen::python())?;
+    }
+    if dir.is_none() && (ts.is_some() || py.is_some()) {
+        return Ok(());
+    }
+    let dir = dir.unwrap_or_else(|| PathBuf::from("schema"));
+    for (name, schema) in gojira_protocol::schemas() {
+        let json = serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())?;
+        write(&dir.join(name), json + "\n")?;
+    }
+    Ok(())
+}
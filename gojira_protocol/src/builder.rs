@@ -0,0 +1,166 @@
+//! Typed construction of `set_tone`, for clients that would rather find a bad value while building
+//! the command than in the DLL's `error`.
+
+use crate::{ClientCommand, MergeMode, ModuleId, ParamChange, ParamOp, TransportAnchor};
+
+/// A param value in 0..=1, as `ParamOp::Set` writes it.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Normalized(f64);
+
+impl Normalized {
+    pub fn new(value: f64) -> Result<Self, String> {
+        if (0.0..=1.0).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(format!("normalized value {value} is outside 0..1"))
+        }
+    }
+
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+/// A step in -1..=1 added to a param's live value, as `ParamOp::Add` applies it.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Delta(f64);
+
+impl Delta {
+    pub fn new(value: f64) -> Result<Self, String> {
+        if (-1.0..=1.0).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(format!("delta {value} is outside -1..1"))
+        }
+    }
+
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl ParamChange {
+    pub fn set(index: i32, value: Normalized) -> Self {
+        Self {
+            index,
+            value: value.get(),
+            op: ParamOp::Set,
+        }
+    }
+
+    pub fn add(index: i32, delta: Delta) -> Self {
+        Self {
+            index,
+            value: delta.get(),
+            op: ParamOp::Add,
+        }
+    }
+}
+
+/// A `set_tone` with the wire's defaults (merge, nothing locked, no glide, written at once)
+/// until told otherwise; `build` runs `ClientCommand::validate` on the result.
+#[derive(Debug, Clone)]
+pub struct SetToneBuilder {
+    session_token: String,
+    command_id: String,
+    target_fx_guid: String,
+    mode: MergeMode,
+    params: Vec<ParamChange>,
+    locked_params: Vec<i32>,
+    ramp_ms: Option<u32>,
+    atomic: bool,
+    apply_at: Option<TransportAnchor>,
+    modules: Option<Vec<ModuleId>>,
+    allow_background_projects: bool,
+}
+
+impl SetToneBuilder {
+    pub fn new(
+        session_token: impl Into<String>,
+        command_id: impl Into<String>,
+        target_fx_guid: impl Into<String>,
+    ) -> Self {
+        Self {
+            session_token: session_token.into(),
+            command_id: command_id.into(),
+            target_fx_guid: target_fx_guid.into(),
+            mode: MergeMode::Merge,
+            params: Vec::new(),
+            locked_params: Vec::new(),
+            ramp_ms: None,
+            atomic: false,
+            apply_at: None,
+            modules: None,
+            allow_background_projects: false,
+        }
+    }
+
+    pub fn mode(mut self, mode: MergeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn set(mut self, index: i32, value: Normalized) -> Self {
+        self.params.push(ParamChange::set(index, value));
+        self
+    }
+
+    pub fn add(mut self, index: i32, delta: Delta) -> Self {
+        self.params.push(ParamChange::add(index, delta));
+        self
+    }
+
+    /// A change that arrived untyped, e.g. from a model's answer; `build` still checks it.
+    pub fn param(mut self, change: ParamChange) -> Self {
+        self.params.push(change);
+        self
+    }
+
+    pub fn lock(mut self, index: i32) -> Self {
+        self.locked_params.push(index);
+        self
+    }
+
+    pub fn ramp_ms(mut self, ramp_ms: u32) -> Self {
+        self.ramp_ms = Some(ramp_ms);
+        self
+    }
+
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    pub fn apply_at(mut self, anchor: TransportAnchor) -> Self {
+        self.apply_at = Some(anchor);
+        self
+    }
+
+    pub fn modules(mut self, modules: impl IntoIterator<Item = ModuleId>) -> Self {
+        self.modules = Some(modules.into_iter().collect());
+        self
+    }
+
+    pub fn allow_background_projects(mut self, allow: bool) -> Self {
+        self.allow_background_projects = allow;
+        self
+    }
+
+    pub fn build(self) -> Result<ClientCommand, String> {
+        let cmd = ClientCommand::SetTone {
+            session_token: self.session_token,
+            command_id: self.command_id,
+            target_fx_guid: self.target_fx_guid,
+            mode: self.mode,
+            params: self.params,
+            locked_params: self.locked_params,
+            ramp_ms: self.ramp_ms,
+            atomic: self.atomic,
+            apply_at: self.apply_at,
+            modules: self.modules,
+            allow_background_projects: self.allow_background_projects,
+        };
+        cmd.validate()?;
+        Ok(cmd)
+    }
+}
@@ -0,0 +1,584 @@
+//! TypeScript types and a thin Python client generated from the protocol's JSON Schema, so the UI
+//! and scripts stop keeping their own copy of every message. `gojira_schema --ts <file>` and
+//! `--py <file>` write them.
+//!
+//! Commands are typed as the DLL accepts them (fields with a default may be left out), messages
+//! as it sends them (every field is there unless the DLL skips it).
+
+use crate::{
+    ClientCommand, ServerMessage, DEFAULT_WS_PORT, PAIRING_KEY_ENV, PAIRING_KEY_HEADER,
+    SUBPROTOCOL_JSON,
+};
+use schemars::generate::SchemaSettings;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+const REGENERATE: &str = "cargo run -p gojira_protocol --features schema --bin gojira_schema -- \
+     --ts gojira_brain_ui/ui/src/protocol.gen.ts --py tools/gojira_client.py";
+
+/// Words Python won't take as a field name in a `class` body.
+const PY_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// One of the two tagged enums, with a type name for each variant: its tag in PascalCase plus
+/// "Command" or "Message".
+struct Union {
+    name: &'static str,
+    variants: Vec<(String, Value)>,
+}
+
+struct Protocol {
+    defs: BTreeMap<String, Value>,
+    unions: [Union; 2],
+}
+
+impl Protocol {
+    fn load() -> Self {
+        let accepted = SchemaSettings::default()
+            .for_deserialize()
+            .into_generator()
+            .into_root_schema_for::<ClientCommand>()
+            .to_value();
+        let sent = SchemaSettings::default()
+            .for_serialize()
+            .into_generator()
+            .into_root_schema_for::<ServerMessage>()
+            .to_value();
+        let mut defs = BTreeMap::new();
+        // Messages last, so a type both directions carry is described as the DLL sends it.
+        let unions = [
+            (accepted, "ClientCommand", "Command"),
+            (sent, "ServerMessage", "Message"),
+        ]
+        .map(|(mut root, name, suffix)| {
+            name_root(&mut root, name);
+            if let Some(Value::Object(d)) = root.get("$defs") {
+                defs.extend(d.clone());
+            }
+            let variants = root["oneOf"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|v| (format!("{}{suffix}", pascal(tag(v))), v.clone()))
+                .collect();
+            Union { name, variants }
+        });
+        Self { defs, unions }
+    }
+}
+
+/// Points `{"$ref": "#"}`, a type holding the whole message (e.g. `replay`'s events), at the
+/// union's name.
+fn name_root(schema: &mut Value, name: &str) {
+    match schema {
+        Value::Object(obj) => {
+            if obj.get("$ref").is_some_and(|r| r == "#") {
+                obj.insert("$ref".to_string(), format!("#/$defs/{name}").into());
+            }
+            obj.values_mut().for_each(|v| name_root(v, name));
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| name_root(v, name)),
+        _ => {}
+    }
+}
+
+fn tag(variant: &Value) -> &str {
+    variant["properties"]["type"]["const"]
+        .as_str()
+        .unwrap_or_default()
+}
+
+fn pascal(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
+}
+
+fn def_name(reference: &str) -> &str {
+    reference.rsplit('/').next().unwrap_or(reference)
+}
+
+fn properties(schema: &Value) -> Option<&Map<String, Value>> {
+    schema.get("properties").and_then(Value::as_object)
+}
+
+/// Properties in schema order, but the `type` tag first.
+fn fields(props: &Map<String, Value>) -> impl Iterator<Item = (&String, &Value)> {
+    let tag = props.iter().filter(|(k, _)| *k == "type");
+    tag.chain(props.iter().filter(|(k, _)| *k != "type"))
+}
+
+fn required(schema: &Value, field: &str) -> bool {
+    schema["required"]
+        .as_array()
+        .is_some_and(|r| r.iter().any(|f| f == field))
+}
+
+fn description(schema: &Value) -> Option<&str> {
+    schema.get("description").and_then(Value::as_str)
+}
+
+/// Every distinct member, in order.
+fn distinct(members: impl Iterator<Item = String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for m in members {
+        if !out.contains(&m) {
+            out.push(m);
+        }
+    }
+    out
+}
+
+/// The value schema of a map: `patternProperties` for int-keyed ones, else
+/// `additionalProperties`.
+fn map_values(obj: &Map<String, Value>) -> Option<&Value> {
+    obj.get("patternProperties")
+        .and_then(Value::as_object)
+        .and_then(|p| p.values().next())
+        .or_else(|| obj.get("additionalProperties").filter(|v| v.is_object()))
+}
+
+/// TypeScript declarations for every type of the protocol, and the `ClientCommand` and
+/// `ServerMessage` unions of one named type per variant.
+pub fn typescript() -> String {
+    let protocol = Protocol::load();
+    let mut out = format!(
+        "// Generated from gojira_protocol; don't edit by hand. Regenerate with\n// {REGENERATE}\n\n"
+    );
+    for (name, schema) in &protocol.defs {
+        ts_decl(&mut out, name, schema);
+    }
+    for union in &protocol.unions {
+        for (name, variant) in &union.variants {
+            ts_decl(&mut out, name, variant);
+        }
+        let _ = write!(out, "export type {} =", union.name);
+        for (name, _) in &union.variants {
+            let _ = write!(out, "\n  | {name}");
+        }
+        out.push_str(";\n\n");
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+fn ts_doc(out: &mut String, indent: &str, schema: &Value) {
+    let Some(text) = description(schema) else {
+        return;
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    if let [line] = lines[..] {
+        let _ = writeln!(out, "{indent}/** {line} */");
+        return;
+    }
+    let _ = writeln!(out, "{indent}/**");
+    for line in lines {
+        let _ = writeln!(out, "{}", format!("{indent} * {line}").trim_end());
+    }
+    let _ = writeln!(out, "{indent} */");
+}
+
+fn ts_decl(out: &mut String, name: &str, schema: &Value) {
+    ts_doc(out, "", schema);
+    if let Some(props) = properties(schema) {
+        let _ = writeln!(out, "export type {name} = {{");
+        for (field, value) in fields(props) {
+            ts_doc(out, "  ", value);
+            let optional = if required(schema, field) { "" } else { "?" };
+            let _ = writeln!(out, "  {field}{optional}: {};", ts_type(value));
+        }
+        out.push_str("};\n\n");
+        return;
+    }
+    let body = ts_type(schema);
+    if body.len() > 80 && body.contains(" | ") {
+        let _ = write!(out, "export type {name} =");
+        for member in body.split(" | ") {
+            let _ = write!(out, "\n  | {member}");
+        }
+        out.push_str(";\n\n");
+    } else {
+        let _ = writeln!(out, "export type {name} = {body};\n");
+    }
+}
+
+fn ts_union(members: impl Iterator<Item = String>) -> String {
+    distinct(members).join(" | ")
+}
+
+fn ts_type(schema: &Value) -> String {
+    let Some(obj) = schema.as_object() else {
+        return "unknown".to_string();
+    };
+    if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+        return def_name(reference).to_string();
+    }
+    if let Some(value) = obj.get("const") {
+        return value.to_string();
+    }
+    if let Some(Value::Array(values)) = obj.get("enum") {
+        return ts_union(values.iter().map(Value::to_string));
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(members)) = obj.get(key) {
+            return ts_union(members.iter().map(ts_type));
+        }
+    }
+    match obj.get("type") {
+        Some(Value::Array(types)) => ts_union(
+            types
+                .iter()
+                .map(|t| ts_type_of(obj, t.as_str().unwrap_or_default())),
+        ),
+        Some(Value::String(ty)) => ts_type_of(obj, ty),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn ts_type_of(obj: &Map<String, Value>, ty: &str) -> String {
+    match ty {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let item = obj.get("items").map_or("unknown".to_string(), ts_type);
+            if item.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                format!("{item}[]")
+            } else {
+                format!("Array<{item}>")
+            }
+        }
+        "object" => {
+            if let Some(props) = obj.get("properties").and_then(Value::as_object) {
+                let schema = Value::Object(obj.clone());
+                let fields: Vec<String> = fields(props)
+                    .map(|(field, value)| {
+                        let optional = if required(&schema, field) { "" } else { "?" };
+                        format!("{field}{optional}: {}", ts_type(value))
+                    })
+                    .collect();
+                return format!("{{ {} }}", fields.join("; "));
+            }
+            let values = map_values(obj).map_or("unknown".to_string(), ts_type);
+            format!("Record<string, {values}>")
+        }
+        _ => "unknown".to_string(),
+    }
+}
+
+/// A Python module with a `TypedDict` or alias for every type of the protocol, and
+/// `GojiraClient`, which connects, answers the handshake and sends commands by name.
+pub fn python() -> String {
+    let protocol = Protocol::load();
+    let mut out = format!(
+        "\"\"\"Client for the Gojira REAPER sidecar, generated from gojira_protocol; don't edit by hand.\n\
+         Regenerate with `{REGENERATE}`.\n\n\
+         Needs Python 3.11+; `GojiraClient` also needs the `websocket-client` package.\n\"\"\"\n\n"
+    );
+    out.push_str(PY_IMPORTS);
+    let _ = write!(
+        out,
+        "\nDEFAULT_WS_PORT = {DEFAULT_WS_PORT}\nSUBPROTOCOL_JSON = \"{SUBPROTOCOL_JSON}\"\n\
+         PAIRING_KEY_HEADER = \"{PAIRING_KEY_HEADER}\"\nPAIRING_KEY_ENV = \"{PAIRING_KEY_ENV}\"\n\n"
+    );
+
+    // Aliases can't refer to a class before it exists; class bodies can, lazily.
+    let mut classes: Vec<(&str, &Value)> = Vec::new();
+    let mut aliases: Vec<(&str, String)> = Vec::new();
+    for (name, schema) in &protocol.defs {
+        if properties(schema).is_some() {
+            classes.push((name, schema));
+        } else {
+            aliases.push((name, py_type(schema)));
+        }
+    }
+    let (plain, referring): (Vec<_>, Vec<_>) = aliases
+        .into_iter()
+        .partition(|(_, ty)| !protocol.defs.keys().any(|d| refers_to(ty, d)));
+    for (name, ty) in plain {
+        let _ = writeln!(out, "\n{name} = {ty}");
+    }
+    for (name, schema) in classes {
+        py_class(&mut out, name, schema);
+    }
+    for union in &protocol.unions {
+        for (name, variant) in &union.variants {
+            py_class(&mut out, name, variant);
+        }
+    }
+    for (name, ty) in referring {
+        let _ = writeln!(out, "\n\n{name} = {ty}");
+    }
+    for union in &protocol.unions {
+        let _ = writeln!(out, "\n\n{} = Union[", union.name);
+        for (name, _) in &union.variants {
+            let _ = writeln!(out, "    {name},");
+        }
+        out.push_str("]\n");
+    }
+
+    let [commands, messages] = &protocol.unions;
+    out.push_str("\nCOMMAND_TYPES = frozenset({\n");
+    for (_, variant) in &commands.variants {
+        let _ = writeln!(out, "    \"{}\",", tag(variant));
+    }
+    out.push_str("})\n# Commands answered by a message carrying their `command_id`.\n");
+    out.push_str("COMMANDS_WITH_ID = frozenset({\n");
+    for (_, variant) in &commands.variants {
+        if required(variant, "command_id") {
+            let _ = writeln!(out, "    \"{}\",", tag(variant));
+        }
+    }
+    out.push_str("})\nMESSAGE_TYPES = frozenset({\n");
+    for (_, variant) in &messages.variants {
+        let _ = writeln!(out, "    \"{}\",", tag(variant));
+    }
+    out.push_str("})\n");
+    out.push_str(PY_CLIENT);
+    out
+}
+
+/// Whether the Python type `ty` names the type `name`.
+fn refers_to(ty: &str, name: &str) -> bool {
+    ty.match_indices(name).any(|(at, _)| {
+        let ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let before = ty[..at].chars().next_back().is_some_and(ident);
+        let after = ty[at + name.len()..].chars().next().is_some_and(ident);
+        !before && !after
+    })
+}
+
+fn py_class(out: &mut String, name: &str, schema: &Value) {
+    let Some(props) = properties(schema) else {
+        return;
+    };
+    let field_type = |field: &str, value: &Value| {
+        let ty = py_type(value);
+        if required(schema, field) {
+            ty
+        } else {
+            format!("NotRequired[{ty}]")
+        }
+    };
+    if props.keys().any(|k| PY_KEYWORDS.contains(&k.as_str())) {
+        let _ = writeln!(out, "\n\n{name} = TypedDict(\"{name}\", {{");
+        for (field, value) in fields(props) {
+            let _ = writeln!(out, "    \"{field}\": {},", field_type(field, value));
+        }
+        out.push_str("})\n");
+        return;
+    }
+    let _ = writeln!(out, "\n\nclass {name}(TypedDict):");
+    if let Some(text) = description(schema) {
+        let _ = writeln!(out, "    \"\"\"{}\"\"\"\n", text.replace('\n', "\n    "));
+    }
+    for (field, value) in fields(props) {
+        if let Some(text) = description(value) {
+            for line in text.lines() {
+                let _ = writeln!(out, "    # {line}");
+            }
+        }
+        let _ = writeln!(out, "    {field}: {}", field_type(field, value));
+    }
+}
+
+fn py_union(members: impl Iterator<Item = String>) -> String {
+    let mut members = distinct(members);
+    if members.len() > 1 && members.iter().all(|m| m.starts_with("Literal[")) {
+        let values: Vec<&str> = members
+            .iter()
+            .map(|m| &m["Literal[".len()..m.len() - 1])
+            .collect();
+        return format!("Literal[{}]", values.join(", "));
+    }
+    let nullable = members.iter().any(|m| m == "None");
+    members.retain(|m| m != "None");
+    let ty = match &members[..] {
+        [] => "None".to_string(),
+        [one] => one.clone(),
+        many => format!("Union[{}]", many.join(", ")),
+    };
+    if nullable && !members.is_empty() {
+        format!("Optional[{ty}]")
+    } else {
+        ty
+    }
+}
+
+fn py_type(schema: &Value) -> String {
+    let Some(obj) = schema.as_object() else {
+        return "Any".to_string();
+    };
+    if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+        return def_name(reference).to_string();
+    }
+    if let Some(value) = obj.get("const") {
+        return format!("Literal[{value}]");
+    }
+    if let Some(Value::Array(values)) = obj.get("enum") {
+        return py_union(values.iter().map(|v| format!("Literal[{v}]")));
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(members)) = obj.get(key) {
+            return py_union(members.iter().map(py_type));
+        }
+    }
+    match obj.get("type") {
+        Some(Value::Array(types)) => py_union(
+            types
+                .iter()
+                .map(|t| py_type_of(obj, t.as_str().unwrap_or_default())),
+        ),
+        Some(Value::String(ty)) => py_type_of(obj, ty),
+        _ => "Any".to_string(),
+    }
+}
+
+fn py_type_of(obj: &Map<String, Value>, ty: &str) -> String {
+    match ty {
+        "string" => "str".to_string(),
+        "integer" => "int".to_string(),
+        "number" => "float".to_string(),
+        "boolean" => "bool".to_string(),
+        "null" => "None".to_string(),
+        "array" => format!(
+            "List[{}]",
+            obj.get("items").map_or("Any".to_string(), py_type)
+        ),
+        "object" if obj.contains_key("properties") => "Dict[str, Any]".to_string(),
+        "object" => format!(
+            "Dict[str, {}]",
+            map_values(obj).map_or("Any".to_string(), py_type)
+        ),
+        _ => "Any".to_string(),
+    }
+}
+
+const PY_IMPORTS: &str = r#"from __future__ import annotations
+
+import json
+import os
+import uuid
+from collections import deque
+from typing import (
+    Any,
+    Callable,
+    Deque,
+    Dict,
+    List,
+    Literal,
+    NotRequired,
+    Optional,
+    TypedDict,
+    Union,
+)
+"#;
+
+const PY_CLIENT: &str = r#"
+
+def pairing_key() -> Optional[str]:
+    """GOJIRA_PAIRING_KEY, else the key the DLL wrote next to its sidecar.json."""
+    key = os.environ.get(PAIRING_KEY_ENV)
+    if key:
+        return key.strip()
+    base = os.environ.get("APPDATA") or os.path.join(os.path.expanduser("~"), ".config")
+    try:
+        with open(os.path.join(base, "Gojira", "pairing.key"), encoding="utf-8") as f:
+            return f.read().strip()
+    except OSError:
+        return None
+
+
+class GojiraClient:
+    """One JSON connection to the DLL. Connecting waits for the handshake and acknowledges it;
+    `handshake` keeps it, instances and param meta included."""
+
+    def __init__(
+        self,
+        url: str = f"ws://127.0.0.1:{DEFAULT_WS_PORT}",
+        key: Optional[str] = None,
+        timeout: float = 10.0,
+    ) -> None:
+        import websocket  # websocket-client
+
+        key = pairing_key() if key is None else key
+        header = [f"{PAIRING_KEY_HEADER}: {key}"] if key else []
+        self._ws = websocket.create_connection(
+            url, header=header, subprotocols=[SUBPROTOCOL_JSON], timeout=timeout
+        )
+        self._pending: Deque[ServerMessage] = deque()
+        self.handshake: HandshakeMessage = self._wait(lambda m: m["type"] == "handshake")
+        self.session_token: str = self.handshake["session_token"]
+        self._send({"type": "handshake_ack", "session_token": self.session_token})
+
+    def send(self, type: str, **fields: Any) -> Optional[str]:
+        """Sends a command with the session token and, where the command takes one, a fresh
+        `command_id`, which is returned."""
+        if type not in COMMAND_TYPES:
+            raise ValueError(f"unknown command type {type!r}")
+        cmd: Dict[str, Any] = {"type": type, "session_token": self.session_token, **fields}
+        if type in COMMANDS_WITH_ID:
+            cmd.setdefault("command_id", uuid.uuid4().hex)
+        self._send(cmd)
+        return cmd.get("command_id")
+
+    def request(self, type: str, **fields: Any) -> ServerMessage:
+        """`send`, then the reply carrying its `command_id`, an `error` included. Messages that
+        arrive meanwhile are kept for `recv`."""
+        command_id = self.send(type, **fields)
+        if command_id is None:
+            raise ValueError(f"{type} is not answered by command id")
+        return self._wait(lambda m: m.get("command_id") == command_id)
+
+    def recv(self) -> ServerMessage:
+        """The next message not yet handed out."""
+        if self._pending:
+            return self._pending.popleft()
+        return self._read()
+
+    def close(self) -> None:
+        self._ws.close()
+
+    def __enter__(self) -> GojiraClient:
+        return self
+
+    def __exit__(self, *exc: Any) -> None:
+        self.close()
+
+    def _send(self, cmd: Dict[str, Any]) -> None:
+        self._ws.send(json.dumps(cmd))
+
+    def _read(self) -> ServerMessage:
+        while True:
+            frame = self._ws.recv()
+            if not isinstance(frame, str):
+                continue
+            msg = json.loads(frame)
+            if msg.get("type") == "token_rotated":
+                self.session_token = msg["session_token"]
+            return msg
+
+    def _wait(self, wanted: Callable[[ServerMessage], bool]) -> ServerMessage:
+        for msg in self._pending:
+            if wanted(msg):
+                self._pending.remove(msg)
+                return msg
+        while True:
+            msg = self._read()
+            if wanted(msg):
+                return msg
+            self._pending.append(msg)
+"#;
@@ -1,35 +1,365 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod builder;
+#[cfg(feature = "schema")]
+pub mod codegen;
+#[cfg(feature = "schema")]
+mod schema;
+mod validate;
+
+pub use builder::{Delta, Normalized, SetToneBuilder};
+#[cfg(feature = "schema")]
+pub use schema::schemas;
 
 mod int_key_map {
     use serde::de::Error as _;
     use serde::{Deserialize, Deserializer};
     use std::collections::HashMap;
 
+    /// JSON object keys are always strings; MessagePack keeps the integers.
+    #[derive(Deserialize, PartialEq, Eq, Hash)]
+    #[serde(untagged)]
+    enum Key {
+        Int(i32),
+        Str(String),
+    }
+
     pub fn deserialize<'de, D, V>(deserializer: D) -> Result<HashMap<i32, V>, D::Error>
     where
         D: Deserializer<'de>,
         V: Deserialize<'de>,
     {
-        let raw: HashMap<String, V> = HashMap::deserialize(deserializer)?;
+        let raw: HashMap<Key, V> = HashMap::deserialize(deserializer)?;
         let mut out: HashMap<i32, V> = HashMap::with_capacity(raw.len());
         for (k, v) in raw {
-            let idx = k
-                .parse::<i32>()
-                .map_err(|e| D::Error::custom(format!("invalid param index key {k:?}: {e}")))?;
+            let idx = match k {
+                Key::Int(idx) => idx,
+                Key::Str(k) => match k.parse::<i32>() {
+                    Ok(idx) => idx,
+                    Err(e) => {
+                        let msg = format!("invalid param index key {k:?}: {e}");
+                        return Err(D::Error::custom(msg));
+                    }
+                },
+            };
             out.insert(idx, v);
         }
         Ok(out)
     }
 }
 
+mod validation_report {
+    use super::ParamCheck;
+    use serde::de::IgnoredAny;
+    use serde::{Deserialize, Deserializer};
+
+    /// DLLs before the typed report sent free-text lines by anchor key; those read as no checks.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Report {
+        Checks(Vec<ParamCheck>),
+        Legacy(IgnoredAny),
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<ParamCheck>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match Report::deserialize(deserializer)? {
+            Report::Checks(checks) => checks,
+            Report::Legacy(_) => Vec::new(),
+        })
+    }
+}
+
+pub const DEFAULT_WS_BIND: &str = "127.0.0.1";
+pub const DEFAULT_WS_PORT: u16 = 9001;
+/// Ports after the configured one the DLL tries when that one is taken.
+pub const DEFAULT_PORT_FALLBACK: u16 = 10;
+/// Full `host:port` override for the DLL's websocket server; wins over the config file.
+pub const WS_ADDR_ENV: &str = "GOJIRA_WS_ADDR";
+/// `tcp` or `pipe`; wins over the config file's `transport`.
+pub const TRANSPORT_ENV: &str = "GOJIRA_TRANSPORT";
+/// Writes per second the DLL applies at most; wins over the config file's `max_apply_rate`.
+pub const MAX_APPLY_RATE_ENV: &str = "GOJIRA_MAX_APPLY_RATE";
+/// Milliseconds one DLL timer tick may run; wins over the config file's `tick_budget_ms`.
+pub const TICK_BUDGET_ENV: &str = "GOJIRA_TICK_BUDGET_MS";
+pub const DEFAULT_PIPE_NAME: &str = "gojira-sidecar";
+/// What `ws_url` returns for the pipe transport, followed by the pipe name.
+pub const PIPE_URL_PREFIX: &str = "pipe:";
+/// Websocket subprotocols naming the wire encoding. Clients offer one or more; the DLL answers
+/// with the first it knows, and uses JSON when nothing is offered.
+pub const SUBPROTOCOL_JSON: &str = "gojira.json";
+pub const SUBPROTOCOL_MSGPACK: &str = "gojira.msgpack";
+/// Header carrying the pairing key on the websocket upgrade request.
+pub const PAIRING_KEY_HEADER: &str = "x-gojira-pairing-key";
+/// Pairing key override, for a client on another machine or a DLL that shouldn't touch the file.
+pub const PAIRING_KEY_ENV: &str = "GOJIRA_PAIRING_KEY";
+/// How long after a connection drops its token can still `resume` the session.
+pub const RESUME_GRACE_SECS: u64 = 30;
+/// mDNS service type the DLL advertises its websocket under when the config turns `mdns` on.
+pub const MDNS_SERVICE_TYPE: &str = "_gojira-sidecar._tcp.local.";
+/// TXT property of the advertisement: "1" when the DLL serves `wss://`.
+pub const MDNS_TLS_PROPERTY: &str = "tls";
+/// Highest param index a command may name; the DLL refuses anything past it.
+pub const MAX_PARAM_INDEX: i32 = 4096;
+/// Tone slots run 0..MAX_TONE_SLOTS, one per MIDI program number.
+pub const MAX_TONE_SLOTS: u32 = 128;
+
+/// Where the DLL listens, shared with the UI and CLI so they find it: `Gojira/sidecar.json` under
+/// `%APPDATA%` (`~/.config` elsewhere), e.g. `{ "port": 9101 }`. Missing fields keep defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SidecarConfig {
+    /// Interface the DLL binds, e.g. "0.0.0.0" to accept other machines.
+    pub bind: Option<String>,
+    pub port: Option<u16>,
+    /// How many ports after `port` the DLL tries in turn when it can't bind it (another REAPER,
+    /// an orphaned process, a port Windows reserved); 0 turns that off. Clients find the one it
+    /// got in the discovery file.
+    pub port_fallback: Option<u16>,
+    /// Serve `wss://` with a self-signed certificate (DLL built with the `tls` feature).
+    pub tls: Option<bool>,
+    /// SHA-256 of the DLL's certificate as shown in its log and handshake, e.g. "AB:12:...".
+    /// Clients pin it; on the DLL's machine they can read it from the certificate file instead.
+    pub tls_fingerprint: Option<String>,
+    /// `pipe` talks over a named pipe (Unix socket elsewhere) for machines where security
+    /// software blocks local TCP ports.
+    pub transport: Option<Transport>,
+    /// Pipe name both ends use with the pipe transport.
+    pub pipe_name: Option<String>,
+    /// Encoding clients ask for; the DLL speaks both.
+    pub encoding: Option<Encoding>,
+    /// Caps how many writes (tones, mixes, undos, preset loads) the DLL applies per second.
+    /// Commands beyond it wait in a bounded queue; unset applies everything on the next tick.
+    pub max_apply_rate: Option<f64>,
+    /// Milliseconds one timer tick may take on REAPER's main thread before the DLL leaves the
+    /// rest of a rescan, param meta probe or tone read for the next tick.
+    pub tick_budget_ms: Option<u64>,
+    /// REAPER MIDI input device (its index in Preferences > MIDI Inputs, enabled there) whose
+    /// program changes switch tone slots, so a foot controller works with no client connected.
+    /// Unset leaves MIDI alone.
+    pub midi_input: Option<i32>,
+    /// MIDI channel 1-16 the DLL listens on; unset takes every channel.
+    pub midi_channel: Option<u8>,
+    /// CC whose value (0-127) also picks the slot, for controllers without program changes.
+    pub midi_slot_cc: Option<u8>,
+    /// UDP address (e.g. "127.0.0.1:9002") of an OSC endpoint taking the same commands as the
+    /// websocket, for OSC controllers and touch surfaces. Unset turns OSC off.
+    pub osc_addr: Option<String>,
+    /// Let `osc_addr` be something other than loopback, e.g. "0.0.0.0:9002" for a tablet. OSC
+    /// has no pairing key, so anyone who can reach the port can write tones, load presets and
+    /// start playback; the DLL refuses such an address unless this is on.
+    pub osc_allow_remote: Option<bool>,
+    /// Advertise the websocket on the LAN as `_gojira-sidecar._tcp` (DLL built with the `mdns`
+    /// feature), so clients on other machines find it. Only useful with a `bind` they can reach.
+    pub mdns: Option<bool>,
+}
+
+/// How one connection frames its messages: JSON in text frames, or MessagePack in binary frames,
+/// which keeps large handshakes (`param_format_samples` for every param) a lot smaller.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Json,
+    Msgpack,
+}
+
+/// An encoded message and the websocket frame type it travels in.
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Encoding {
+    pub fn subprotocol(self) -> &'static str {
+        match self {
+            Encoding::Json => SUBPROTOCOL_JSON,
+            Encoding::Msgpack => SUBPROTOCOL_MSGPACK,
+        }
+    }
+
+    /// First encoding this build knows in a `Sec-WebSocket-Protocol` value.
+    pub fn negotiate(offer: &str) -> Option<Self> {
+        offer.split(',').map(str::trim).find_map(|name| match name {
+            SUBPROTOCOL_JSON => Some(Encoding::Json),
+            SUBPROTOCOL_MSGPACK => Some(Encoding::Msgpack),
+            _ => None,
+        })
+    }
+
+    pub fn encode<T: Serialize>(self, msg: &T) -> Result<Frame, String> {
+        match self {
+            Encoding::Json => serde_json::to_string(msg)
+                .map(Frame::Text)
+                .map_err(|e| e.to_string()),
+            Encoding::Msgpack => rmp_serde::to_vec_named(msg)
+                .map(Frame::Binary)
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Readers pick the decoder by frame type (text is JSON, binary is MessagePack), so either
+    /// end reads both whatever was negotiated.
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, String> {
+        match self {
+            Encoding::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            Encoding::Msgpack => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    #[default]
+    Tcp,
+    Pipe,
+}
+
+impl SidecarConfig {
+    pub fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(base.join("Gojira").join("sidecar.json"))
+    }
+
+    /// Long-lived secret the DLL writes on first start, next to the config file. Clients must
+    /// present it when connecting, so other local processes can't drive the plugin.
+    pub fn pairing_key_path() -> Option<PathBuf> {
+        Some(Self::path()?.with_file_name("pairing.key"))
+    }
+
+    /// Self-signed certificate the DLL generates for `tls`; the key sits beside it.
+    pub fn tls_cert_path() -> Option<PathBuf> {
+        Some(Self::path()?.with_file_name("sidecar-cert.pem"))
+    }
+
+    /// Where the DLL writes a `Discovery` once it is listening on TCP, next to the config file.
+    pub fn discovery_path() -> Option<PathBuf> {
+        Some(Self::path()?.with_file_name("sidecar-port.json"))
+    }
+
+    pub fn tls_enabled(&self) -> bool {
+        self.tls.unwrap_or(false)
+    }
+
+    pub fn mdns_enabled(&self) -> bool {
+        self.mdns.unwrap_or(false)
+    }
+
+    pub fn osc_remote_allowed(&self) -> bool {
+        self.osc_allow_remote.unwrap_or(false)
+    }
+
+    /// `GOJIRA_TRANSPORT` if it names one, else the config file, else TCP.
+    pub fn transport(&self) -> Transport {
+        let env = std::env::var(TRANSPORT_ENV).unwrap_or_default();
+        match env.trim() {
+            "tcp" => Transport::Tcp,
+            "pipe" => Transport::Pipe,
+            _ => self.transport.unwrap_or_default(),
+        }
+    }
+
+    pub fn pipe_name(&self) -> &str {
+        self.pipe_name.as_deref().unwrap_or(DEFAULT_PIPE_NAME)
+    }
+
+    /// `GOJIRA_MAX_APPLY_RATE` if it parses, else the config file; `None` (or anything not
+    /// positive) means unlimited.
+    pub fn max_apply_rate(&self) -> Option<f64> {
+        let env = std::env::var(MAX_APPLY_RATE_ENV)
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok());
+        env.or(self.max_apply_rate)
+            .filter(|r| r.is_finite() && *r > 0.0)
+    }
+
+    /// `GOJIRA_TICK_BUDGET_MS` if it parses, else the config file; `None` keeps the DLL's default.
+    pub fn tick_budget(&self) -> Option<Duration> {
+        let env = std::env::var(TICK_BUDGET_ENV)
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        env.or(self.tick_budget_ms).map(Duration::from_millis)
+    }
+
+    /// Addresses the DLL tries to listen on, in order: the configured port, then the fallback
+    /// range after it. `GOJIRA_WS_ADDR` names exactly one.
+    pub fn listen_addrs(&self) -> Vec<String> {
+        if let Some(addr) = env_ws_addr() {
+            return vec![addr];
+        }
+        let bind = self.bind.as_deref().unwrap_or(DEFAULT_WS_BIND);
+        let port = self.port.unwrap_or(DEFAULT_WS_PORT);
+        let last = port.saturating_add(self.port_fallback.unwrap_or(DEFAULT_PORT_FALLBACK));
+        (port..=last).map(|p| join_host_port(bind, p)).collect()
+    }
+
+    /// URL a client on this machine connects to; wildcard binds map to loopback. With the pipe
+    /// transport it is `pipe:<name>` instead.
+    pub fn ws_url(&self) -> String {
+        if self.transport() == Transport::Pipe {
+            return format!("{PIPE_URL_PREFIX}{}", self.pipe_name());
+        }
+        let addr = env_ws_addr().unwrap_or_else(|| {
+            let bind = match self.bind.as_deref() {
+                None | Some("0.0.0.0") => DEFAULT_WS_BIND,
+                Some("::") => "::1",
+                Some(b) => b,
+            };
+            join_host_port(bind, self.port.unwrap_or(DEFAULT_WS_PORT))
+        });
+        let scheme = if self.tls_enabled() { "wss" } else { "ws" };
+        format!("{scheme}://{addr}")
+    }
+}
+
+/// What the DLL writes to `SidecarConfig::discovery_path` once it is listening, e.g.
+/// `{ "port": 9002 }`, so clients find it on the port it got rather than the configured one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Discovery {
+    pub port: u16,
+}
+
+/// `GOJIRA_PAIRING_KEY` if set and non-empty.
+pub fn env_pairing_key() -> Option<String> {
+    std::env::var(PAIRING_KEY_ENV)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn env_ws_addr() -> Option<String> {
+    std::env::var(WS_ADDR_ENV)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ParamEnumOption {
-    pub value: f32,
+    pub value: f64,
     pub label: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ParamFormatTriplet {
     pub min: String,
     pub mid: String,
@@ -37,12 +367,120 @@ pub struct ParamFormatTriplet {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ParamFormatSample {
-    pub norm: f32,
+    pub norm: f64,
     pub formatted: String,
 }
 
+/// How a parameter moves, from REAPER's step sizes or, failing that, its formatted values.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ParamKind {
+    Toggle,
+    /// `steps` distinct positions, evenly spaced over 0..1.
+    Stepped { steps: u32 },
+    Continuous,
+}
+
+/// One param of the handshake's `param_schema`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ParamSchemaEntry {
+    pub name: String,
+    pub kind: ParamSchemaKind,
+    /// Section of the plugin, e.g. "amp_eq" or "cab"; "other" where the layout isn't charted.
+    #[serde(default)]
+    pub group: String,
+}
+
+/// What a control is, as far as labelling and editing it goes. Unlike `ParamKind` this is known
+/// at handshake time, from the plugin's step sizes and the charted layout.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ParamSchemaKind {
+    Toggle,
+    Continuous,
+    Enum,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProjectTempo {
+    pub bpm: f32,
+    pub beats_per_measure: i32,
+}
+
+/// DLL counters since it was loaded, answered to `get_metrics`. Durations are milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct RuntimeMetrics {
+    pub uptime_secs: f64,
+    pub commands_received: u64,
+    /// Commands answered with an ack / with an error by the main loop.
+    pub commands_applied: u64,
+    pub commands_rejected: u64,
+    /// SetTone glides cut short by a newer command on the same FX.
+    pub commands_coalesced: u64,
+    pub avg_apply_ms: f64,
+    pub max_apply_ms: f64,
+    /// Timer ticks over the last full second.
+    pub ticks_per_sec: f64,
+    /// Handshake probes (validation, kinds, EQ bands) and streamed param meta chunks.
+    pub probes: u64,
+    pub last_probe_ms: f64,
+    pub max_probe_ms: f64,
+    pub inbound_high_water: usize,
+    pub outbound_high_water: usize,
+    /// Server messages lost because the outbound queue was full.
+    pub outbound_dropped: u64,
+}
+
+/// Severity of a DLL log event, most severe first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+/// One entry of the DLL's event log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LogEvent {
+    /// Milliseconds since the DLL was loaded.
+    pub at_ms: u64,
+    pub level: LogLevel,
+    /// Where it came from, e.g. "init", "net" or "main_loop".
+    pub target: String,
+    pub msg: String,
+}
+
+/// How long the main loop's recurring work takes, in milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct TimingStats {
+    pub ticks: u64,
+    pub avg_tick_ms: f64,
+    pub max_tick_ms: f64,
+    /// Instance rescans, from handshakes and the project watchdog.
+    pub scans: u64,
+    pub avg_scan_ms: f64,
+    pub max_scan_ms: f64,
+    pub applies: u64,
+    pub avg_apply_ms: f64,
+    pub max_apply_ms: f64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorCode {
     Unauthorized,
@@ -52,42 +490,744 @@ pub enum ErrorCode {
     InvalidCommand,
     NotReady,
     InternalError,
+    /// The target FX still verifies by guid, but its plugin or param count is not what the
+    /// handshake probed (replaced, updated or renamed since); the indices may have shifted.
+    TargetChanged,
+    /// The command is understood, but this REAPER, plugin or build can't carry it out.
+    Unsupported,
+    /// Something the command waited on did not answer in time.
+    Timeout,
+    /// Part of the command took effect and part did not; `details` says which.
+    PartialFailure,
+    /// A code from a newer DLL than this build knows. Never sent.
+    #[serde(other)]
+    Unknown,
+}
+
+/// How far the resolver got with a target it could not use.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ResolverState {
+    /// Neither the instance cache nor a rescan knows the guid: the FX is gone or was never there.
+    CacheMiss,
+    /// The rescan found the guid, but the FX at that slot did not verify (moved mid-scan, or its
+    /// track is not in the current project).
+    VerifyFailed,
+    /// The FX is in an open project tab other than the current one, which the command did not
+    /// opt into with `allow_background_projects`.
+    BackgroundProject,
+    /// A state from a newer DLL than this build knows. Never sent.
+    #[serde(other)]
+    Unknown,
+}
+
+/// What a client can do about an error.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Remediation {
+    /// Rescan instances and pick the target again.
+    RefreshInstances,
+    /// Fix the offending param index or value and resend.
+    FixParams,
+    /// Finish the handshake before sending commands.
+    CompleteHandshake,
+    /// Pair again or rotate the session token.
+    Reauthenticate,
+    /// Nothing is wrong with the command itself; resend it shortly.
+    Retry,
+    /// Resend with `allow_background_projects`, if writing to the other tab is intended.
+    AllowBackgroundProjects,
+    /// A remedy from a newer DLL than this build knows. Never sent.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The structured side of an error, so clients can act on it instead of parsing `msg`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ErrorDiagnostic {
+    /// The param the command failed on.
+    #[serde(default)]
+    pub param_index: Option<i32>,
+    /// The value it failed with, as sent.
+    #[serde(default)]
+    pub value: Option<f64>,
+    /// Set on target-not-found errors.
+    #[serde(default)]
+    pub resolver: Option<ResolverState>,
+    #[serde(default)]
+    pub remediation: Option<Remediation>,
+}
+
+impl ErrorDiagnostic {
+    pub fn remedy(remediation: Remediation) -> Self {
+        Self {
+            remediation: Some(remediation),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCode {
+    /// No longer sent now that kinds arrive in the meta stream; still parsed from older DLLs,
+    /// but left out of the schema and the generated bindings.
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    ProbeTimeout,
+    ReservedIndexSkipped,
+    CommandCoalesced,
+    LinkedCabOverride,
+    /// REAPER could not report a written value, so the ack leaves it out of `applied_params`.
+    ReadbackUnavailable,
+    /// The target is in a project tab other than the current one; `context` names the project
+    /// (unset while it's unsaved). Sent before the tone is written.
+    BackgroundProject,
+    /// A code from a newer DLL than this build knows. Never sent.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Something a DLL build handles beyond the handshake, acks and errors, as listed in its
+/// handshake. Clients enable a feature by its flag rather than by version, so a command rolled
+/// out to some DLLs first doesn't break UIs talking to the others.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// `atomic` on `set_tone`.
+    AtomicApply,
+    /// `set_tone_multi`.
+    MultiTarget,
+    /// `apply_at` on `set_tone` and `activate_tone_slot`.
+    ScheduledApply,
+    /// `modules` on `set_tone`.
+    ModuleFilter,
+    /// `allow_background_projects` on the tone commands.
+    BackgroundProjects,
+    /// `subscribe` and the `param_changed` it brings.
+    ParamSubscription,
+    /// `search_enum_labels`.
+    EnumSearch,
+    /// `save_preset`, `load_preset` and `list_presets`.
+    Presets,
+    /// `store_tone_slot`, `activate_tone_slot` and `list_tone_slots`.
+    ToneSlots,
+    /// `preview_tone`.
+    PreviewTone,
+    /// `validate_tone`.
+    ValidateTone,
+    /// `render_preview`.
+    RenderPreview,
+    /// `restore_snapshot`.
+    Snapshots,
+    /// `play`, `stop` and `set_loop`.
+    Transport,
+    /// `set_fx_mix`, `set_fx_state` and `switch_fx_pair`.
+    FxState,
+    /// `set_track_controls`.
+    TrackControls,
+    /// `insert_fx`.
+    InsertFx,
+    /// `resume`.
+    Resume,
+    /// `get_diagnostics`.
+    Diagnostics,
+    /// The msgpack subprotocol.
+    Msgpack,
+    /// A capability of a newer DLL than this build knows. Never sent.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The handshake's per-param maps. Boxed so they don't size every `ServerMessage`; on the wire
+/// they are fields of the handshake itself.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HandshakeMeta {
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_enums: HashMap<i32, Vec<ParamEnumOption>>,
+    /// Full option count of the enums above cut to their first page, like long IR lists; the
+    /// rest are pulled with `search_enum_labels`.
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_enum_totals: HashMap<i32, usize>,
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_formats: HashMap<i32, ParamFormatTriplet>,
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_format_samples: HashMap<i32, Vec<ParamFormatSample>>,
+    /// Graphic-EQ band index -> center frequency (Hz), when the plugin exposes it.
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub eq_band_hz: HashMap<i32, f32>,
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_kinds: HashMap<i32, ParamKind>,
+    /// Every tone param of the probed instance by its actual index: the name the plugin
+    /// reports, its kind and section. Clients label and group params from this rather than
+    /// from tables of their own.
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_schema: HashMap<i32, ParamSchemaEntry>,
+    /// Charted param index -> the index the plugin now reports that param's name at, for
+    /// params a plugin update has moved. Meant to seed the client's index remap.
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub param_remap: HashMap<i32, i32>,
+    /// Canonical -> actual index guesses for toggles and mix controls the validation report
+    /// flags, by a name fitting the role near the charted index. Unlike `param_remap`, meant
+    /// for the user to confirm rather than to adopt as is.
+    #[serde(default, deserialize_with = "int_key_map::deserialize")]
+    pub proposed_remap: HashMap<i32, i32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ServerMessage {
+    /// First message on every connection: the session token, the instances and what the DLL
+    /// knows about their params.
     Handshake {
         session_token: String,
         instances: Vec<GojiraInstance>,
-        validation_report: HashMap<String, String>,
+        /// Every charted module toggle and knob as the plugin names it; empty unless the DLL runs
+        /// with GOJIRA_SEND_VALIDATION_REPORT.
+        #[serde(default, deserialize_with = "validation_report::deserialize")]
+        validation_report: Vec<ParamCheck>,
+        /// Enum options, formats and samples follow as `ParamMeta` chunks; those param maps are
+        /// then empty. Older DLLs put everything here.
+        #[serde(default)]
+        param_meta_streaming: bool,
+        #[serde(flatten)]
+        meta: Box<HandshakeMeta>,
+        /// Project tempo at the start of the timeline.
+        #[serde(default)]
+        project_tempo: Option<ProjectTempo>,
+        /// Address the DLL's server is actually bound to, for diagnostics.
+        #[serde(default)]
+        server_addr: Option<String>,
+        /// Certificate fingerprint when the server speaks TLS, to compare with the pinned one.
+        #[serde(default)]
+        tls_fingerprint: Option<String>,
+        /// What this DLL build supports; empty from DLLs that predate the list, which support
+        /// none of it reliably.
+        #[serde(default)]
+        capabilities: Vec<Capability>,
+    },
+    /// Param meta for indices `first_index..=last_index`, probed after the handshake went out.
+    /// Merged into what the client already has; `done` marks the last chunk.
+    ParamMeta {
+        first_index: i32,
+        last_index: i32,
         #[serde(default, deserialize_with = "int_key_map::deserialize")]
         param_enums: HashMap<i32, Vec<ParamEnumOption>>,
+        /// Full option count of the enums above cut to their first page, like long IR lists; the
+        /// rest are pulled with `search_enum_labels`.
+        #[serde(default, deserialize_with = "int_key_map::deserialize")]
+        param_enum_totals: HashMap<i32, usize>,
         #[serde(default, deserialize_with = "int_key_map::deserialize")]
         param_formats: HashMap<i32, ParamFormatTriplet>,
         #[serde(default, deserialize_with = "int_key_map::deserialize")]
-        param_format_samples: HashMap<i32, Vec<ParamFormatSample>>,     
+        param_format_samples: HashMap<i32, Vec<ParamFormatSample>>,
+        /// Kinds of the tone params in this range, enums counting as stepped.
+        #[serde(default, deserialize_with = "int_key_map::deserialize")]
+        param_kinds: HashMap<i32, ParamKind>,
+        #[serde(default)]
+        done: bool,
+    },
+    /// One per project whose instances changed, so a tab switch arrives as the old project's
+    /// instances removed and the new one's added.
+    ProjectChanged {
+        #[serde(default)]
+        project_tempo: Option<ProjectTempo>,
+        /// File name of the project the instance deltas are from; unset while it's unsaved.
+        #[serde(default)]
+        project_name: Option<String>,
+        /// Instances whose tone params moved since the DLL last read them, or that are gone.
+        /// Instances the DLL never read are not tracked, so they are never listed.
+        #[serde(default)]
+        changed_instances: Vec<String>,
+        /// Instances that appeared since the last scan.
+        #[serde(default)]
+        added: Vec<GojiraInstance>,
+        /// Instances that are gone, as they were last seen.
+        #[serde(default)]
+        removed: Vec<GojiraInstance>,
+        /// Instances still there whose track name, FX name or position changed, as they are now.
+        #[serde(default)]
+        renamed: Vec<GojiraInstance>,
     },
-    ProjectChanged,
     Ack {
         command_id: String,
         #[serde(default)]
         applied_params: Vec<AppliedParam>,
+        /// Modules a `merge` set_tone left as they were.
+        #[serde(default)]
+        skipped_modules: Vec<SkippedModule>,
+        /// Params REAPER refused to write. The rest were written anyway; a command none of whose
+        /// params could be written gets an `error` instead.
+        #[serde(default)]
+        failed_params: Vec<FailedParam>,
+    },
+    /// Reply to `set_tone_multi`: one result per target, in request order.
+    MultiAck {
+        command_id: String,
+        results: Vec<TargetResult>,
+    },
+    Error {
+        /// Human-readable summary; `diagnostic` carries the same facts for clients to act on.
+        msg: String,
+        code: ErrorCode,
+        /// Optional diagnostic context (e.g. nearby parameter names on apply failures).
+        #[serde(default)]
+        details: Option<String>,
+        /// The failed command's `command_id`, for commands that carry one.
+        #[serde(default)]
+        command_id: Option<String>,
+        #[serde(default)]
+        diagnostic: Option<ErrorDiagnostic>,
+    },
+    /// Reply to `rotate_token`, or pushed when the server-side token TTL runs out. The previous
+    /// token stays valid for a short grace period so in-flight commands are not rejected.
+    TokenRotated {
+        session_token: String,
+        #[serde(default)]
+        expires_in_secs: Option<u64>,
+    },
+    /// Reply to `get_metrics`.
+    Metrics { metrics: RuntimeMetrics },
+    /// Reply to `RefreshInstances` with `instances_only`: the rescanned list and nothing else.
+    /// The param meta from the last handshake still holds.
+    Instances { instances: Vec<GojiraInstance> },
+    /// Reply to `resume`: the connection now goes by `session_token`, the one it asked for.
+    Resumed {
+        session_token: String,
+        instances: Vec<GojiraInstance>,
+    },
+    /// Reply to `get_diagnostics`: the most recent log events, oldest first.
+    Diagnostics {
+        events: Vec<LogEvent>,
+        timings: TimingStats,
+    },
+    /// Pushed when a command finds an instance moved since the last scan, e.g. dragged along its
+    /// FX chain or onto another track, so clients can repoint their references before the
+    /// project's next rescan. `instance` is as it is now.
+    InstanceMoved {
+        instance: GojiraInstance,
+        from_track_guid: String,
+        from_fx_index: i32,
+    },
+    /// Reply to `ping`, echoing its nonce.
+    Pong { nonce: u64, uptime_ms: u64 },
+    /// Reply to `search_enum_labels`: the matching options from `offset` on. `total` counts every
+    /// match, so a page ending short of it means there are more.
+    ParamEnumPage {
+        command_id: String,
+        index: i32,
+        query: String,
+        offset: usize,
+        total: usize,
+        options: Vec<ParamEnumOption>,
+    },
+    /// Reply to `get_current_tone`: what the plugin holds right now, including GUI edits.
+    CurrentTone {
+        command_id: String,
+        target_fx_guid: String,
+        params: Vec<ParamReading>,
+        /// Every param a command has written to this FX, with the value it read back. Saved in
+        /// the project, so it outlives the session and a REAPER restart.
+        #[serde(default)]
+        last_applied: Vec<ParamChange>,
+    },
+    /// Reply to `preview_tone`: each param the set_tone would write, as the plugin holds it now
+    /// and as it would read after the write. Nothing was written.
+    Preview {
+        command_id: String,
+        target_fx_guid: String,
+        rows: Vec<PreviewRow>,
+        #[serde(default)]
+        skipped_modules: Vec<SkippedModule>,
+    },
+    /// Reply to `validate_tone`. No `warnings` means the set looks sound; nothing was written.
+    ToneValidation {
+        command_id: String,
+        target_fx_guid: String,
+        warnings: Vec<ToneWarning>,
+    },
+    /// Reply to `render_preview`: where the WAV landed, and the range actually rendered.
+    PreviewRendered {
+        command_id: String,
+        target_fx_guid: String,
+        path: String,
+        start: f64,
+        length: f64,
+    },
+    /// Reply to `play`, `stop` and `set_loop`: the transport as REAPER reports it afterwards.
+    Transport {
+        command_id: String,
+        playing: bool,
+        repeat: bool,
+        /// Loop points in seconds; unset when the project has none.
+        #[serde(default)]
+        loop_start: Option<f64>,
+        #[serde(default)]
+        loop_end: Option<f64>,
+    },
+    /// Reply to `set_fx_state`: the FX's enable switch and wet knob as REAPER reads them after
+    /// the write. `wet` is unset when the FX doesn't expose the knob.
+    FxState {
+        command_id: String,
+        target_fx_guid: String,
+        enabled: bool,
+        #[serde(default)]
+        wet: Option<f32>,
+    },
+    /// Reply to `set_track_controls`: the host track's controls as REAPER reads them after the
+    /// write. A field is unset when REAPER doesn't report it.
+    TrackControls {
+        command_id: String,
+        target_fx_guid: String,
+        track_guid: String,
+        #[serde(default)]
+        volume_db: Option<f32>,
+        #[serde(default)]
+        pan: Option<f32>,
+        /// On for REAPER's "auto" monitoring as well.
+        #[serde(default)]
+        rec_monitor: Option<bool>,
+    },
+    /// Pushed to clients that sent `subscribe` when subscribed params move, e.g. a knob turned
+    /// in the plugin GUI. Only the params that changed since the last push are listed.
+    ParamChanged {
+        target_fx_guid: String,
+        params: Vec<ParamReading>,
+    },
+    /// Reply to `list_presets`.
+    PresetList {
+        command_id: String,
+        target_fx_guid: String,
+        /// Presets stored with `save_preset`, sorted by name.
+        saved: Vec<String>,
+        /// The plugin's own preset the FX reports being on, if any.
+        #[serde(default)]
+        current_native: Option<String>,
+    },
+    /// Reply to `list_tone_slots`.
+    ToneSlots {
+        command_id: String,
+        /// Sorted by slot.
+        slots: Vec<ToneSlotInfo>,
+        /// Slot activated last, if it is still stored.
+        #[serde(default)]
+        active_slot: Option<u32>,
+    },
+    /// Something the user should know about that did not abort the command.
+    Warning {
+        code: WarningCode,
+        msg: String,
+        /// What it applies to, e.g. the command id or the affected indices.
+        #[serde(default)]
+        context: Option<String>,
+    },
+    /// Sent right after a handshake to the first client in after a spell with none connected:
+    /// what it missed meanwhile (acks and errors of MIDI and action-list switches, project
+    /// changes), oldest first. Capped, so the oldest may be gone.
+    Replay { events: Vec<ReplayedEvent> },
+    /// The last message before the DLL closes every connection. A socket that drops without one
+    /// means REAPER crashed or the network went away.
+    Shutdown {
+        /// Why the server is going away, e.g. "reaper_exiting".
+        reason: String,
+        /// Set when the DLL expects to be back shortly, so clients can keep retrying at their
+        /// usual pace instead of settling in for REAPER to be started again.
+        #[serde(default)]
+        restart_expected: bool,
     },
-    Error { msg: String, code: ErrorCode },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ClientCommand {
     HandshakeAck { session_token: String },
-    RefreshInstances { session_token: String },
+    /// Rescan and hand everyone a new handshake. With `instances_only`, the sender alone gets a
+    /// bare `Instances` list instead, for polling without re-sending the param meta.
+    RefreshInstances {
+        session_token: String,
+        #[serde(default)]
+        instances_only: bool,
+    },
+    /// Forget the DLL's cached param meta for the plugin and probe it again. Everyone gets a new
+    /// handshake, with the meta following as `ParamMeta` chunks.
+    ///
+    /// With `indices`, only those params are probed again (e.g. the IR selectors after new IR
+    /// files were installed), with no handshake; everyone gets one `ParamMeta` covering them.
+    RefreshParamMeta {
+        session_token: String,
+        #[serde(default)]
+        indices: Vec<i32>,
+    },
+    /// Ask for a fresh session token; answered with `token_rotated`.
+    RotateToken { session_token: String },
+    /// Take over the session of a connection that dropped less than `RESUME_GRACE_SECS` ago, by
+    /// the token it had. Subscriptions and the probe results carry over and the connection goes
+    /// on under that token; answered with `resumed`, or a handshake if the plugin must be probed
+    /// again. Sent first thing, it spares the connection its handshake.
+    Resume { previous_token: String },
+    /// Ask for the DLL's runtime counters; answered with `metrics`.
+    GetMetrics { session_token: String },
+    /// Ask for the DLL's recent log events and timings; answered with `diagnostics`.
+    GetDiagnostics {
+        session_token: String,
+        /// How many events to return; the DLL's default when unset.
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Liveness check, answered with `pong` from REAPER's main thread rather than the socket
+    /// thread, so a reply means the DLL is actually ticking.
+    Ping { session_token: String, nonce: u64 },
+    /// Read back every tone parameter of an instance; answered with `current_tone`.
+    GetCurrentTone {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+    },
     SetTone {
         session_token: String,
         command_id: String,
         target_fx_guid: String,
         mode: MergeMode,
         params: Vec<ParamChange>,
+        /// Indices the user pinned: never written, and their modules are never auto-bypassed.
+        #[serde(default)]
+        locked_params: Vec<i32>,
+        /// Glide continuous params to their targets over this many milliseconds (capped at
+        /// 5000) instead of jumping; toggles and stepped params still switch at once. The ack
+        /// comes when the glide ends.
+        #[serde(default)]
+        ramp_ms: Option<u32>,
+        /// All or nothing: if any write fails, or reads back off target, every param is put
+        /// back to its value from before the command and an `error` is sent. Never glides, so
+        /// `ramp_ms` is ignored.
+        #[serde(default)]
+        atomic: bool,
+        /// Hold the tone back until the transport reaches this point; the ack comes once it is
+        /// written. Without one the tone is written right away.
+        #[serde(default)]
+        apply_at: Option<TransportAnchor>,
+        /// Write only the params of these modules, picked out after cleaning, e.g. just the cab
+        /// of a full tone; the rest of `params` is dropped. Needs a charted plugin layout.
+        #[serde(default)]
+        modules: Option<Vec<ModuleId>>,
+        /// Find the target in other open project tabs too, not just the current one. Writing to
+        /// a background tab is announced with a `background_project` warning.
+        #[serde(default)]
+        allow_background_projects: bool,
+    },
+    /// Dry run of `set_tone`: sanitized, cleaned and resolved against the live FX exactly like
+    /// one, but nothing is written; answered with `preview`.
+    PreviewTone {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        mode: MergeMode,
+        params: Vec<ParamChange>,
+        #[serde(default)]
+        locked_params: Vec<i32>,
+    },
+    /// Checks `params` against the live FX without cleaning or writing them: that each index
+    /// exists, that enum values land on a probed option, and that toggles agree with the params
+    /// they gate. Answered with `tone_validation`.
+    ValidateTone {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        params: Vec<ParamChange>,
+    },
+    /// `set_tone` for several FX at once (e.g. L/R guitar tracks), as one REAPER undo step and
+    /// one `multi_ack`. A target that fails does not stop the others.
+    SetToneMulti {
+        session_token: String,
+        command_id: String,
+        targets: Vec<ToneTarget>,
+        /// As on `set_tone`, for every target.
+        #[serde(default)]
+        allow_background_projects: bool,
+    },
+    /// Reverts the most recent tone applied through `set_tone`, as one REAPER undo step.
+    /// Refused when something else sits on top of REAPER's undo history.
+    UndoLastTone {
+        session_token: String,
+        command_id: String,
+    },
+    /// Writes back the values the FX had right before a `set_tone` (or every FX of a
+    /// `set_tone_multi`); `snapshot_id` is that command's `command_id`. Works regardless of what happened to REAPER's undo history since.
+    RestoreSnapshot {
+        session_token: String,
+        command_id: String,
+        snapshot_id: String,
+    },
+    /// Watch `indices` on the FX and get `param_changed` pushes when they move. Replaces this
+    /// client's earlier subscription for the same FX; empty `indices` ends it.
+    Subscribe {
+        session_token: String,
+        target_fx_guid: String,
+        #[serde(default)]
+        indices: Vec<i32>,
+    },
+    /// Stores the FX's current tone under `name` in the sidecar's preset folder (REAPER's API
+    /// can load plugin presets but not write them). Overwrites a preset of the same name.
+    SavePreset {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        name: String,
+    },
+    /// Applies a preset saved with `save_preset`, else the plugin's own preset of that name as
+    /// shown in REAPER's preset dropdown. One REAPER undo step, like `set_tone`.
+    LoadPreset {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        name: String,
+    },
+    /// Options of the enum at `index` whose labels contain `query` (case-insensitive; empty
+    /// matches all), for enums the handshake only carries the first page of. Answered with
+    /// `param_enum_page`; options still being probed are not there yet.
+    SearchEnumLabels {
+        session_token: String,
+        command_id: String,
+        index: i32,
+        #[serde(default)]
+        query: String,
+        #[serde(default)]
+        offset: usize,
+        /// Defaults to the handshake's page size.
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Answered with `preset_list`.
+    ListPresets {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+    },
+    /// Keeps a named tone in `slot` (0..=127) for `activate_tone_slot`, so switching to it later
+    /// doesn't take the whole param list again. Replaces what the slot held; no params clears
+    /// it. Slots are saved with the project; answered with `ack`.
+    StoreToneSlot {
+        session_token: String,
+        command_id: String,
+        slot: u32,
+        #[serde(default)]
+        name: String,
+        target_fx_guid: String,
+        mode: MergeMode,
+        params: Vec<ParamChange>,
+        #[serde(default)]
+        locked_params: Vec<i32>,
+    },
+    /// Applies a stored slot as the `set_tone` it was stored from, with this command's glide and
+    /// timing; answered with `ack`.
+    ActivateToneSlot {
+        session_token: String,
+        command_id: String,
+        slot: u32,
+        #[serde(default)]
+        ramp_ms: Option<u32>,
+        #[serde(default)]
+        apply_at: Option<TransportAnchor>,
+    },
+    /// Answered with `tone_slots`.
+    ListToneSlots {
+        session_token: String,
+        command_id: String,
+    },
+    /// REAPER's per-FX wet knob (0..1) and delta solo; the plugin's own params are untouched.
+    /// Fields left out are not changed.
+    SetFxMix {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        #[serde(default)]
+        wet: Option<f32>,
+        #[serde(default)]
+        delta_solo: Option<bool>,
+    },
+    /// The FX's enable switch and REAPER's wet knob (0..1), for A/B against the dry signal or a
+    /// parallel blend. Fields left out are not changed; answered with `fx_state`.
+    SetFxState {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        #[serde(default)]
+        enabled: Option<bool>,
+        #[serde(default)]
+        wet: Option<f32>,
+    },
+    /// Bounce `length` seconds of the master mix from `start` to a temp WAV, for listening to (or
+    /// scoring) a tone without playing the project; answered with `preview_rendered`. `start`
+    /// defaults to the loop start, `length` to 5 s; at most 30 s are rendered.
+    RenderPreview {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        #[serde(default)]
+        start: Option<f64>,
+        #[serde(default)]
+        length: Option<f64>,
+    },
+    /// Start playback from the edit cursor, which `set_loop` puts at the loop start; answered
+    /// with `transport`.
+    Play {
+        session_token: String,
+        command_id: String,
+    },
+    /// Stop playback; answered with `transport`.
+    Stop {
+        session_token: String,
+        command_id: String,
+    },
+    /// Loop `start..end` (project time, seconds) with repeat on and the edit cursor at `start`, so
+    /// the next `play` auditions the riff; answered with `transport`.
+    SetLoop {
+        session_token: String,
+        command_id: String,
+        start: f64,
+        end: f64,
+    },
+    /// Add the plugin to a track, for when no instance was found. Everyone gets a new handshake
+    /// listing it; failures come back as `error`.
+    InsertFx {
+        session_token: String,
+        command_id: String,
+        /// Defaults to the track selected in REAPER.
+        #[serde(default)]
+        track_guid: Option<String>,
+        /// Name as REAPER's FX browser matches it; defaults to the Gojira plugin.
+        #[serde(default)]
+        fx_name: Option<String>,
+    },
+    /// Volume, pan and record monitoring of the track hosting the FX, for "sit it lower in the
+    /// mix" requests. Fields left out are not changed; answered with `track_controls`.
+    SetTrackControls {
+        session_token: String,
+        command_id: String,
+        target_fx_guid: String,
+        /// Clamped to -150..=12 dB.
+        #[serde(default)]
+        volume_db: Option<f32>,
+        /// -1 (hard left) to 1 (hard right).
+        #[serde(default)]
+        pan: Option<f32>,
+        #[serde(default)]
+        rec_monitor: Option<bool>,
+    },
+    /// Tone switching by instance: disable one Gojira and enable another on the same track.
+    /// Applied in one tick, disable first, so both are never active at once.
+    SwitchFxPair {
+        session_token: String,
+        command_id: String,
+        enable_fx_guid: String,
+        disable_fx_guid: String,
     },
 }
 
@@ -95,35 +1235,388 @@ impl ClientCommand {
     pub fn session_token(&self) -> &str {
         match self {
             ClientCommand::HandshakeAck { session_token } => session_token,
-            ClientCommand::RefreshInstances { session_token } => session_token,
+            ClientCommand::RefreshInstances { session_token, .. } => session_token,
+            ClientCommand::RefreshParamMeta { session_token, .. } => session_token,
+            ClientCommand::RotateToken { session_token } => session_token,
+            ClientCommand::Resume { previous_token } => previous_token,
+            ClientCommand::GetMetrics { session_token } => session_token,
+            ClientCommand::GetDiagnostics { session_token, .. } => session_token,
+            ClientCommand::Ping { session_token, .. } => session_token,
+            ClientCommand::GetCurrentTone { session_token, .. } => session_token,
             ClientCommand::SetTone { session_token, .. } => session_token,
+            ClientCommand::PreviewTone { session_token, .. } => session_token,
+            ClientCommand::ValidateTone { session_token, .. } => session_token,
+            ClientCommand::SetToneMulti { session_token, .. } => session_token,
+            ClientCommand::UndoLastTone { session_token, .. } => session_token,
+            ClientCommand::RestoreSnapshot { session_token, .. } => session_token,
+            ClientCommand::Subscribe { session_token, .. } => session_token,
+            ClientCommand::SavePreset { session_token, .. } => session_token,
+            ClientCommand::LoadPreset { session_token, .. } => session_token,
+            ClientCommand::ListPresets { session_token, .. } => session_token,
+            ClientCommand::StoreToneSlot { session_token, .. } => session_token,
+            ClientCommand::ActivateToneSlot { session_token, .. } => session_token,
+            ClientCommand::ListToneSlots { session_token, .. } => session_token,
+            ClientCommand::SearchEnumLabels { session_token, .. } => session_token,
+            ClientCommand::SetFxMix { session_token, .. } => session_token,
+            ClientCommand::SetFxState { session_token, .. } => session_token,
+            ClientCommand::SetTrackControls { session_token, .. } => session_token,
+            ClientCommand::InsertFx { session_token, .. } => session_token,
+            ClientCommand::RenderPreview { session_token, .. } => session_token,
+            ClientCommand::Play { session_token, .. } => session_token,
+            ClientCommand::Stop { session_token, .. } => session_token,
+            ClientCommand::SetLoop { session_token, .. } => session_token,
+            ClientCommand::SwitchFxPair { session_token, .. } => session_token,
+        }
+    }
+
+    /// The command's wire `type`, for logs and status readouts.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientCommand::HandshakeAck { .. } => "handshake_ack",
+            ClientCommand::RefreshInstances { .. } => "refresh_instances",
+            ClientCommand::RefreshParamMeta { .. } => "refresh_param_meta",
+            ClientCommand::RotateToken { .. } => "rotate_token",
+            ClientCommand::Resume { .. } => "resume",
+            ClientCommand::GetMetrics { .. } => "get_metrics",
+            ClientCommand::GetDiagnostics { .. } => "get_diagnostics",
+            ClientCommand::Ping { .. } => "ping",
+            ClientCommand::GetCurrentTone { .. } => "get_current_tone",
+            ClientCommand::SetTone { .. } => "set_tone",
+            ClientCommand::PreviewTone { .. } => "preview_tone",
+            ClientCommand::ValidateTone { .. } => "validate_tone",
+            ClientCommand::SetToneMulti { .. } => "set_tone_multi",
+            ClientCommand::UndoLastTone { .. } => "undo_last_tone",
+            ClientCommand::RestoreSnapshot { .. } => "restore_snapshot",
+            ClientCommand::Subscribe { .. } => "subscribe",
+            ClientCommand::SavePreset { .. } => "save_preset",
+            ClientCommand::LoadPreset { .. } => "load_preset",
+            ClientCommand::ListPresets { .. } => "list_presets",
+            ClientCommand::StoreToneSlot { .. } => "store_tone_slot",
+            ClientCommand::ActivateToneSlot { .. } => "activate_tone_slot",
+            ClientCommand::ListToneSlots { .. } => "list_tone_slots",
+            ClientCommand::SearchEnumLabels { .. } => "search_enum_labels",
+            ClientCommand::SetFxMix { .. } => "set_fx_mix",
+            ClientCommand::SetFxState { .. } => "set_fx_state",
+            ClientCommand::SetTrackControls { .. } => "set_track_controls",
+            ClientCommand::InsertFx { .. } => "insert_fx",
+            ClientCommand::RenderPreview { .. } => "render_preview",
+            ClientCommand::Play { .. } => "play",
+            ClientCommand::Stop { .. } => "stop",
+            ClientCommand::SetLoop { .. } => "set_loop",
+            ClientCommand::SwitchFxPair { .. } => "switch_fx_pair",
+        }
+    }
+
+    /// `None` for commands without one (handshake ack, refreshes, ping, subscribe and the like).
+    pub fn command_id(&self) -> Option<&str> {
+        match self {
+            ClientCommand::HandshakeAck { .. }
+            | ClientCommand::RefreshInstances { .. }
+            | ClientCommand::RefreshParamMeta { .. }
+            | ClientCommand::RotateToken { .. }
+            | ClientCommand::Resume { .. }
+            | ClientCommand::GetMetrics { .. }
+            | ClientCommand::GetDiagnostics { .. }
+            | ClientCommand::Ping { .. }
+            | ClientCommand::Subscribe { .. } => None,
+            ClientCommand::GetCurrentTone { command_id, .. }
+            | ClientCommand::SetTone { command_id, .. }
+            | ClientCommand::PreviewTone { command_id, .. }
+            | ClientCommand::ValidateTone { command_id, .. }
+            | ClientCommand::SetToneMulti { command_id, .. }
+            | ClientCommand::UndoLastTone { command_id, .. }
+            | ClientCommand::RestoreSnapshot { command_id, .. }
+            | ClientCommand::SavePreset { command_id, .. }
+            | ClientCommand::LoadPreset { command_id, .. }
+            | ClientCommand::ListPresets { command_id, .. }
+            | ClientCommand::StoreToneSlot { command_id, .. }
+            | ClientCommand::ActivateToneSlot { command_id, .. }
+            | ClientCommand::ListToneSlots { command_id, .. }
+            | ClientCommand::SearchEnumLabels { command_id, .. }
+            | ClientCommand::SetFxMix { command_id, .. }
+            | ClientCommand::SetFxState { command_id, .. }
+            | ClientCommand::SetTrackControls { command_id, .. }
+            | ClientCommand::InsertFx { command_id, .. }
+            | ClientCommand::RenderPreview { command_id, .. }
+            | ClientCommand::Play { command_id, .. }
+            | ClientCommand::Stop { command_id, .. }
+            | ClientCommand::SetLoop { command_id, .. }
+            | ClientCommand::SwitchFxPair { command_id, .. } => Some(command_id),
         }
     }
 }
 
+/// Where in playback a scheduled `set_tone` lands.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum TransportAnchor {
+    /// The start of the next bar, at the project's tempo and meter. Written at once if the
+    /// transport is stopped, or as soon as it stops or loops back first.
+    NextBar,
+    /// When the transport stops or pauses; written at once if it isn't playing.
+    OnStop,
+    /// Once playback is at or past this project time, however long that takes.
+    At { position_secs: f64 },
+}
+
+/// A stored tone slot as `tone_slots` lists it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToneSlotInfo {
+    pub slot: u32,
+    #[serde(default)]
+    pub name: String,
+    pub target_fx_guid: String,
+    pub param_count: usize,
+}
+
+/// A message `replay` passes on, with how long before the replay it was sent.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ReplayedEvent {
+    pub age_ms: u64,
+    pub message: ServerMessage,
+}
+
+/// One FX of a `set_tone_multi`; the fields mean what they do on `set_tone`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToneTarget {
+    pub target_fx_guid: String,
+    pub mode: MergeMode,
+    pub params: Vec<ParamChange>,
+    #[serde(default)]
+    pub locked_params: Vec<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TargetResult {
+    pub target_fx_guid: String,
+    #[serde(default)]
+    pub applied_params: Vec<AppliedParam>,
+    /// Writes REAPER refused while the rest of the target's params went through.
+    #[serde(default)]
+    pub failed_params: Vec<FailedParam>,
+    /// Set when the target was skipped or none of its params could be written.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum MergeMode {
     Merge,
     ReplaceActive,
 }
 
+/// A part of the layout `set_tone` can be limited to: one of the pedals, or a whole section.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleId {
+    /// Input and output gain, and the gate.
+    Global,
+    Wow,
+    Oct,
+    Overdrive,
+    Distortion,
+    Phaser,
+    Chorus,
+    /// Amp selector and knobs.
+    Amp,
+    /// The amps' graphic EQs.
+    Eq,
+    /// Cab type and both mics.
+    Cab,
+    Delay,
+    Reverb,
+}
+
+impl ModuleId {
+    /// As the param catalog names it.
+    pub fn name(self) -> &'static str {
+        match self {
+            ModuleId::Global => "global",
+            ModuleId::Wow => "wow",
+            ModuleId::Oct => "oct",
+            ModuleId::Overdrive => "overdrive",
+            ModuleId::Distortion => "distortion",
+            ModuleId::Phaser => "phaser",
+            ModuleId::Chorus => "chorus",
+            ModuleId::Amp => "amp",
+            ModuleId::Eq => "eq",
+            ModuleId::Cab => "cab",
+            ModuleId::Delay => "delay",
+            ModuleId::Reverb => "reverb",
+        }
+    }
+}
+
+/// Normalized values are f64 end to end: REAPER takes doubles, and f32 rounding can tip a
+/// quantized selector (e.g. 0.125976562) onto the neighboring option. f32-producing clients still
+/// deserialize fine, since JSON numbers carry no width.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ParamChange {
     pub index: i32,
-    pub value: f32,
+    pub value: f64,
+    #[serde(default, skip_serializing_if = "ParamOp::is_set")]
+    pub op: ParamOp,
+}
+
+/// What a `ParamChange`'s value means.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ParamOp {
+    /// The new normalized value.
+    #[default]
+    Set,
+    /// Added to the param's live value in the DLL, then clamped to 0..1; e.g. 0.1 for "a bit
+    /// more gain" without reading the tone first.
+    Add,
 }
 
+impl ParamOp {
+    pub fn is_set(&self) -> bool {
+        matches!(self, ParamOp::Set)
+    }
+}
+
+/// A module a `merge` set_tone sent no params for; `active` is its toggle as read from the
+/// plugin, `None` if it couldn't be read.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SkippedModule {
+    pub name: String,
+    #[serde(default)]
+    pub active: Option<bool>,
+}
+
+/// One written param as the plugin holds it afterwards; `applied` differing from `requested`
+/// means the plugin quantized or rejected the value.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AppliedParam {
     pub index: i32,
-    pub requested: f32,
-    pub applied: f32,
+    pub requested: f64,
+    pub applied: f64,
+    #[serde(default)]
+    pub formatted: String,
+}
+
+/// A param whose write REAPER refused, with the value that was sent and REAPER's reason.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FailedParam {
+    pub index: i32,
+    pub value: f64,
+    pub error: String,
+}
+
+/// One line of a `preview`. `before` is `None` when the param can't be read back.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PreviewRow {
+    pub index: i32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub before: Option<f64>,
+    #[serde(default)]
+    pub before_formatted: String,
+    pub after: f64,
+    #[serde(default)]
+    pub after_formatted: String,
+}
+
+/// What `validate_tone` found wrong with a param.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ToneIssue {
+    /// The FX has no param at this index.
+    UnknownParam,
+    /// One of REAPER's bypass/wet/delta controls, which `set_tone` skips.
+    ReservedParam,
+    /// Listed more than once; the last value wins.
+    Duplicate,
+    /// Outside 0..1, so it would be clamped.
+    OutOfRange,
+    /// Reads as a label none of the probed enum options carry.
+    OffEnumOption,
+    /// A toggle set to something between off and on.
+    FractionalToggle,
+    /// Params of a module are set while its on/off toggle stays off.
+    ModuleOff,
+}
+
+/// One problem `validate_tone` found.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToneWarning {
+    pub issue: ToneIssue,
+    /// The param at fault; for `module_off`, the module's toggle.
+    pub index: i32,
+    pub msg: String,
+}
+
+/// What a charted param does in its module.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ParamRole {
+    /// Switches the module in or out.
+    Toggle,
+    /// The module's dry/wet control.
+    Mix,
+    Knob,
+}
+
+/// How a charted param's index held up against the plugin.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ParamCheckStatus {
+    /// Named as charted, or like its role where the chart has no name.
+    Ok,
+    /// Something sits at the index, but its name doesn't fit.
+    Suspicious,
+    /// The plugin has no param at the index.
+    Missing,
+}
+
+/// One line of the handshake's validation report.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ParamCheck {
+    /// Module the param belongs to, e.g. "delay".
+    pub module: String,
+    pub role: ParamRole,
+    pub expected_index: i32,
+    /// Name the chart has for the index.
+    #[serde(default)]
+    pub expected_name: Option<String>,
+    /// Name the plugin reports at the index.
+    #[serde(default)]
+    pub found_name: Option<String>,
+    pub status: ParamCheckStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ParamReading {
+    pub index: i32,
+    pub value: f64,
     #[serde(default)]
     pub formatted: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum Confidence {
     High,
@@ -131,6 +1624,7 @@ pub enum Confidence {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GojiraInstance {
     pub track_guid: String,
     pub track_name: String,
@@ -138,5 +1632,8 @@ pub struct GojiraInstance {
     pub fx_name: String,
     pub last_known_fx_index: i32,
     pub confidence: Confidence,
+    /// Plugin profile the FX name matched ("gojira", "plini", ...); unset from older DLLs.
+    #[serde(default)]
+    pub profile: Option<String>,
 }
 
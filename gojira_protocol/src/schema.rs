@@ -0,0 +1,14 @@
+//! JSON Schema for what goes over the wire, behind the `schema` cargo feature, so clients not
+//! written in Rust can check their payloads against the real protocol. The `gojira_schema` binary
+//! writes these out.
+
+use crate::{ClientCommand, ServerMessage};
+use schemars::{schema_for, Schema};
+
+/// Every schema of the protocol, by the file name the generator writes it to.
+pub fn schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("client_command.schema.json", schema_for!(ClientCommand)),
+        ("server_message.schema.json", schema_for!(ServerMessage)),
+    ]
+}
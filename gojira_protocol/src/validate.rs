@@ -0,0 +1,250 @@
+//! What a command can be checked for without a DLL to send it to. The DLL checks all of it again;
+//! this is for clients that want the mistake reported where it was made.
+
+use crate::{
+    ClientCommand, Delta, Normalized, ParamChange, ParamOp, ToneTarget, TransportAnchor,
+    MAX_PARAM_INDEX, MAX_TONE_SLOTS,
+};
+
+impl ClientCommand {
+    /// Catches what is wrong with the command on its face: empty tokens, ids and guids, param
+    /// indices past `MAX_PARAM_INDEX`, values that are NaN, infinite or outside their op's range,
+    /// slots past `MAX_TONE_SLOTS`. Passing says nothing about whether the target exists.
+    pub fn validate(&self) -> Result<(), String> {
+        non_empty("session_token", self.session_token())?;
+        if let Some(command_id) = self.command_id() {
+            non_empty("command_id", command_id)?;
+        }
+        match self {
+            ClientCommand::HandshakeAck { .. }
+            | ClientCommand::RefreshInstances { .. }
+            | ClientCommand::RotateToken { .. }
+            | ClientCommand::Resume { .. }
+            | ClientCommand::GetMetrics { .. }
+            | ClientCommand::GetDiagnostics { .. }
+            | ClientCommand::Ping { .. }
+            | ClientCommand::UndoLastTone { .. }
+            | ClientCommand::ListToneSlots { .. }
+            | ClientCommand::Play { .. }
+            | ClientCommand::Stop { .. } => Ok(()),
+            ClientCommand::RefreshParamMeta { indices, .. } => param_indices(indices),
+            ClientCommand::GetCurrentTone { target_fx_guid, .. }
+            | ClientCommand::ListPresets { target_fx_guid, .. } => guid(target_fx_guid),
+            ClientCommand::SetTone {
+                target_fx_guid,
+                params,
+                locked_params,
+                apply_at,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                param_changes(params)?;
+                param_indices(locked_params)?;
+                anchor(apply_at.as_ref())
+            }
+            ClientCommand::PreviewTone {
+                target_fx_guid,
+                params,
+                locked_params,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                param_changes(params)?;
+                param_indices(locked_params)
+            }
+            ClientCommand::ValidateTone {
+                target_fx_guid,
+                params,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                param_changes(params)
+            }
+            ClientCommand::SetToneMulti { targets, .. } => {
+                if targets.is_empty() {
+                    return Err("set_tone_multi has no targets".to_string());
+                }
+                targets.iter().try_for_each(tone_target)
+            }
+            ClientCommand::RestoreSnapshot { snapshot_id, .. } => {
+                non_empty("snapshot_id", snapshot_id)
+            }
+            ClientCommand::Subscribe {
+                target_fx_guid,
+                indices,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                param_indices(indices)
+            }
+            ClientCommand::SavePreset {
+                target_fx_guid,
+                name,
+                ..
+            }
+            | ClientCommand::LoadPreset {
+                target_fx_guid,
+                name,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                non_empty("name", name)
+            }
+            ClientCommand::SearchEnumLabels { index, .. } => param_index(*index),
+            ClientCommand::StoreToneSlot {
+                slot,
+                target_fx_guid,
+                params,
+                locked_params,
+                ..
+            } => {
+                tone_slot(*slot)?;
+                guid(target_fx_guid)?;
+                param_changes(params)?;
+                param_indices(locked_params)
+            }
+            ClientCommand::ActivateToneSlot { slot, apply_at, .. } => {
+                tone_slot(*slot)?;
+                anchor(apply_at.as_ref())
+            }
+            ClientCommand::SetFxMix {
+                target_fx_guid,
+                wet,
+                ..
+            }
+            | ClientCommand::SetFxState {
+                target_fx_guid,
+                wet,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                finite("wet", wet.map(f64::from))
+            }
+            ClientCommand::RenderPreview {
+                target_fx_guid,
+                start,
+                length,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                finite("start", *start)?;
+                finite("length", *length)?;
+                match (start, length) {
+                    (Some(start), _) if *start < 0.0 => Err(format!("start {start} is negative")),
+                    (_, Some(length)) if *length <= 0.0 => {
+                        Err(format!("length {length} is not positive"))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            ClientCommand::SetLoop { start, end, .. } => {
+                finite("start", Some(*start))?;
+                finite("end", Some(*end))?;
+                if *start < 0.0 || start >= end {
+                    return Err(format!("invalid loop {start}..{end}"));
+                }
+                Ok(())
+            }
+            ClientCommand::InsertFx {
+                track_guid,
+                fx_name,
+                ..
+            } => {
+                if let Some(track_guid) = track_guid {
+                    non_empty("track_guid", track_guid)?;
+                }
+                if let Some(fx_name) = fx_name {
+                    non_empty("fx_name", fx_name)?;
+                }
+                Ok(())
+            }
+            ClientCommand::SetTrackControls {
+                target_fx_guid,
+                volume_db,
+                pan,
+                ..
+            } => {
+                guid(target_fx_guid)?;
+                finite("volume_db", volume_db.map(f64::from))?;
+                finite("pan", pan.map(f64::from))
+            }
+            ClientCommand::SwitchFxPair {
+                enable_fx_guid,
+                disable_fx_guid,
+                ..
+            } => {
+                guid(enable_fx_guid)?;
+                guid(disable_fx_guid)?;
+                if enable_fx_guid == disable_fx_guid {
+                    return Err("enable and disable targets are the same fx".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn non_empty(field: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err(format!("{field} is empty"));
+    }
+    Ok(())
+}
+
+fn guid(value: &str) -> Result<(), String> {
+    non_empty("fx guid", value)
+}
+
+fn finite(field: &str, value: Option<f64>) -> Result<(), String> {
+    match value {
+        Some(v) if !v.is_finite() => Err(format!("non-finite {field}")),
+        _ => Ok(()),
+    }
+}
+
+fn param_index(index: i32) -> Result<(), String> {
+    if !(0..=MAX_PARAM_INDEX).contains(&index) {
+        return Err(format!("invalid param index: {index}"));
+    }
+    Ok(())
+}
+
+fn param_indices(indices: &[i32]) -> Result<(), String> {
+    indices.iter().try_for_each(|&i| param_index(i))
+}
+
+fn param_changes(params: &[ParamChange]) -> Result<(), String> {
+    for p in params {
+        param_index(p.index)?;
+        let checked = match p.op {
+            ParamOp::Set => Normalized::new(p.value).map(drop),
+            ParamOp::Add => Delta::new(p.value).map(drop),
+        };
+        checked.map_err(|e| format!("param {}: {e}", p.index))?;
+    }
+    Ok(())
+}
+
+fn tone_target(target: &ToneTarget) -> Result<(), String> {
+    guid(&target.target_fx_guid)?;
+    param_changes(&target.params)?;
+    param_indices(&target.locked_params)
+}
+
+fn tone_slot(slot: u32) -> Result<(), String> {
+    if slot >= MAX_TONE_SLOTS {
+        return Err(format!("slot {slot} is out of range (0..{MAX_TONE_SLOTS})"));
+    }
+    Ok(())
+}
+
+fn anchor(anchor: Option<&TransportAnchor>) -> Result<(), String> {
+    match anchor {
+        Some(TransportAnchor::At { position_secs })
+            if !position_secs.is_finite() || *position_secs < 0.0 =>
+        {
+            Err(format!("invalid apply_at position {position_secs}"))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,961 @@
+//! Randomly generated commands and server messages round-tripped through both encodings, a
+//! corpus of what older peers send that has to keep parsing, and hostile input that has to come
+//! back as an error rather than a panic. Seeds are fixed, so a failure reproduces.
+
+use gojira_protocol::{
+    AppliedParam, Capability, ClientCommand, Confidence, Encoding, ErrorCode, ErrorDiagnostic,
+    FailedParam, Frame, GojiraInstance, HandshakeMeta, LogEvent, LogLevel, MergeMode, ModuleId,
+    ParamChange, ParamCheck, ParamCheckStatus, ParamEnumOption, ParamFormatSample,
+    ParamFormatTriplet, ParamKind, ParamOp, ParamReading, ParamRole, ParamSchemaEntry,
+    ParamSchemaKind, PreviewRow, ProjectTempo, Remediation, ReplayedEvent, ResolverState,
+    RuntimeMetrics, ServerMessage, SkippedModule, TargetResult, TimingStats, ToneIssue,
+    ToneSlotInfo, ToneTarget, ToneWarning, TransportAnchor, WarningCode,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const CASES: u64 = 2000;
+
+/// xorshift64*: enough randomness for generating messages, and no dependency to pull in.
+struct Gen(u64);
+
+impl Gen {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn bits(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.bits() % n as u64) as usize
+    }
+
+    fn bool(&mut self) -> bool {
+        self.bits() & 1 == 1
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    fn u64(&mut self) -> u64 {
+        match self.below(4) {
+            0 => 0,
+            1 => u64::MAX,
+            _ => self.bits() >> self.below(64),
+        }
+    }
+
+    fn usize(&mut self) -> usize {
+        self.below(10_000)
+    }
+
+    fn i32(&mut self) -> i32 {
+        match self.below(5) {
+            0 => self.pick(&[0, -1, i32::MIN, i32::MAX, 257]),
+            _ => self.below(300) as i32,
+        }
+    }
+
+    /// Finite, with few enough digits that serde_json's default float parsing reads back the
+    /// exact value: JSON has no NaN or infinity, and the last bit of a 17-digit one may differ.
+    fn f64(&mut self) -> f64 {
+        match self.below(5) {
+            0 => self.pick(&[0.0, 1.0, -0.0, 0.125976562, 1e-9, -1e9]),
+            1 => (self.below(4_000_001) as f64 - 2_000_000.0) / 1_000_000.0,
+            _ => self.below(1_000_001) as f64 / 1_000_000.0,
+        }
+    }
+
+    fn f32(&mut self) -> f32 {
+        match self.below(4) {
+            0 => self.pick(&[0.0, -150.0, 12.0, 0.5]),
+            _ => (self.below(20_001) as f32 - 10_000.0) / 8.0,
+        }
+    }
+
+    fn string(&mut self) -> String {
+        const PIECES: &[&str] = &[
+            "a", "Z", "0", " ", "-", "{", "}", "\"", "\\", "\n", "\t", "\u{0}", "\u{1f}", "é",
+            "日本", "🎸", "\u{202e}", "null", "type", "%s", "../", "\u{fffd}",
+        ];
+        (0..self.below(12)).map(|_| self.pick(PIECES)).collect()
+    }
+
+    fn opt<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        self.bool().then(|| f(self))
+    }
+
+    fn vec<T>(&mut self, mut f: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        (0..self.below(5)).map(|_| f(self)).collect()
+    }
+
+    fn int_map<T>(&mut self, mut f: impl FnMut(&mut Self) -> T) -> HashMap<i32, T> {
+        (0..self.below(5)).map(|_| (self.i32(), f(self))).collect()
+    }
+}
+
+fn merge_mode(g: &mut Gen) -> MergeMode {
+    g.pick(&[MergeMode::Merge, MergeMode::ReplaceActive])
+}
+
+fn module_id(g: &mut Gen) -> ModuleId {
+    g.pick(&[
+        ModuleId::Global,
+        ModuleId::Wow,
+        ModuleId::Oct,
+        ModuleId::Overdrive,
+        ModuleId::Distortion,
+        ModuleId::Phaser,
+        ModuleId::Chorus,
+        ModuleId::Amp,
+        ModuleId::Eq,
+        ModuleId::Cab,
+        ModuleId::Delay,
+        ModuleId::Reverb,
+    ])
+}
+
+fn param_change(g: &mut Gen) -> ParamChange {
+    ParamChange {
+        index: g.i32(),
+        value: g.f64(),
+        op: g.pick(&[ParamOp::Set, ParamOp::Add]),
+    }
+}
+
+fn transport_anchor(g: &mut Gen) -> TransportAnchor {
+    match g.below(3) {
+        0 => TransportAnchor::NextBar,
+        1 => TransportAnchor::OnStop,
+        _ => TransportAnchor::At {
+            position_secs: g.f64(),
+        },
+    }
+}
+
+fn tone_target(g: &mut Gen) -> ToneTarget {
+    ToneTarget {
+        target_fx_guid: g.string(),
+        mode: merge_mode(g),
+        params: g.vec(param_change),
+        locked_params: g.vec(Gen::i32),
+    }
+}
+
+fn client_command(g: &mut Gen) -> ClientCommand {
+    let session_token = g.string();
+    let command_id = g.string();
+    let target_fx_guid = g.string();
+    match g.below(32) {
+        0 => ClientCommand::HandshakeAck { session_token },
+        1 => ClientCommand::RefreshInstances {
+            session_token,
+            instances_only: g.bool(),
+        },
+        2 => ClientCommand::RefreshParamMeta {
+            session_token,
+            indices: g.vec(Gen::i32),
+        },
+        3 => ClientCommand::RotateToken { session_token },
+        4 => ClientCommand::GetMetrics { session_token },
+        5 => ClientCommand::GetDiagnostics {
+            session_token,
+            limit: g.opt(Gen::usize),
+        },
+        6 => ClientCommand::Ping {
+            session_token,
+            nonce: g.u64(),
+        },
+        7 => ClientCommand::GetCurrentTone {
+            session_token,
+            command_id,
+            target_fx_guid,
+        },
+        8 => ClientCommand::SetTone {
+            session_token,
+            command_id,
+            target_fx_guid,
+            mode: merge_mode(g),
+            params: g.vec(param_change),
+            locked_params: g.vec(Gen::i32),
+            ramp_ms: g.opt(|g| g.bits() as u32),
+            atomic: g.bool(),
+            apply_at: g.opt(transport_anchor),
+            modules: g.opt(|g| g.vec(module_id)),
+            allow_background_projects: g.bool(),
+        },
+        9 => ClientCommand::PreviewTone {
+            session_token,
+            command_id,
+            target_fx_guid,
+            mode: merge_mode(g),
+            params: g.vec(param_change),
+            locked_params: g.vec(Gen::i32),
+        },
+        10 => ClientCommand::ValidateTone {
+            session_token,
+            command_id,
+            target_fx_guid,
+            params: g.vec(param_change),
+        },
+        11 => ClientCommand::SetToneMulti {
+            session_token,
+            command_id,
+            targets: g.vec(tone_target),
+            allow_background_projects: g.bool(),
+        },
+        12 => ClientCommand::UndoLastTone {
+            session_token,
+            command_id,
+        },
+        13 => ClientCommand::RestoreSnapshot {
+            session_token,
+            command_id,
+            snapshot_id: g.string(),
+        },
+        14 => ClientCommand::Subscribe {
+            session_token,
+            target_fx_guid,
+            indices: g.vec(Gen::i32),
+        },
+        15 => ClientCommand::SavePreset {
+            session_token,
+            command_id,
+            target_fx_guid,
+            name: g.string(),
+        },
+        16 => ClientCommand::LoadPreset {
+            session_token,
+            command_id,
+            target_fx_guid,
+            name: g.string(),
+        },
+        17 => ClientCommand::SearchEnumLabels {
+            session_token,
+            command_id,
+            index: g.i32(),
+            query: g.string(),
+            offset: g.usize(),
+            limit: g.opt(Gen::usize),
+        },
+        18 => ClientCommand::ListPresets {
+            session_token,
+            command_id,
+            target_fx_guid,
+        },
+        19 => ClientCommand::SetFxMix {
+            session_token,
+            command_id,
+            target_fx_guid,
+            wet: g.opt(Gen::f32),
+            delta_solo: g.opt(Gen::bool),
+        },
+        20 => ClientCommand::SetFxState {
+            session_token,
+            command_id,
+            target_fx_guid,
+            enabled: g.opt(Gen::bool),
+            wet: g.opt(Gen::f32),
+        },
+        21 => ClientCommand::RenderPreview {
+            session_token,
+            command_id,
+            target_fx_guid,
+            start: g.opt(Gen::f64),
+            length: g.opt(Gen::f64),
+        },
+        22 => ClientCommand::Play {
+            session_token,
+            command_id,
+        },
+        23 => ClientCommand::Stop {
+            session_token,
+            command_id,
+        },
+        24 => ClientCommand::SetLoop {
+            session_token,
+            command_id,
+            start: g.f64(),
+            end: g.f64(),
+        },
+        25 => ClientCommand::InsertFx {
+            session_token,
+            command_id,
+            track_guid: g.opt(Gen::string),
+            fx_name: g.opt(Gen::string),
+        },
+        26 => ClientCommand::SetTrackControls {
+            session_token,
+            command_id,
+            target_fx_guid,
+            volume_db: g.opt(Gen::f32),
+            pan: g.opt(Gen::f32),
+            rec_monitor: g.opt(Gen::bool),
+        },
+        27 => ClientCommand::StoreToneSlot {
+            session_token,
+            command_id,
+            slot: g.bits() as u32,
+            name: g.string(),
+            target_fx_guid,
+            mode: merge_mode(g),
+            params: g.vec(param_change),
+            locked_params: g.vec(Gen::i32),
+        },
+        28 => ClientCommand::ActivateToneSlot {
+            session_token,
+            command_id,
+            slot: g.bits() as u32,
+            ramp_ms: g.opt(|g| g.bits() as u32),
+            apply_at: g.opt(transport_anchor),
+        },
+        29 => ClientCommand::ListToneSlots {
+            session_token,
+            command_id,
+        },
+        30 => ClientCommand::Resume {
+            previous_token: session_token,
+        },
+        _ => ClientCommand::SwitchFxPair {
+            session_token,
+            command_id,
+            enable_fx_guid: target_fx_guid,
+            disable_fx_guid: g.string(),
+        },
+    }
+}
+
+fn instance(g: &mut Gen) -> GojiraInstance {
+    GojiraInstance {
+        track_guid: g.string(),
+        track_name: g.string(),
+        fx_guid: g.string(),
+        fx_name: g.string(),
+        last_known_fx_index: g.i32(),
+        confidence: if g.bool() {
+            Confidence::High
+        } else {
+            Confidence::Low
+        },
+        profile: g.opt(Gen::string),
+    }
+}
+
+fn enum_option(g: &mut Gen) -> ParamEnumOption {
+    ParamEnumOption {
+        value: g.f64(),
+        label: g.string(),
+    }
+}
+
+fn format_triplet(g: &mut Gen) -> ParamFormatTriplet {
+    ParamFormatTriplet {
+        min: g.string(),
+        mid: g.string(),
+        max: g.string(),
+    }
+}
+
+fn format_sample(g: &mut Gen) -> ParamFormatSample {
+    ParamFormatSample {
+        norm: g.f64(),
+        formatted: g.string(),
+    }
+}
+
+fn schema_entry(g: &mut Gen) -> ParamSchemaEntry {
+    ParamSchemaEntry {
+        name: g.string(),
+        kind: match g.below(3) {
+            0 => ParamSchemaKind::Toggle,
+            1 => ParamSchemaKind::Continuous,
+            _ => ParamSchemaKind::Enum,
+        },
+        group: g.string(),
+    }
+}
+
+fn param_kind(g: &mut Gen) -> ParamKind {
+    match g.below(3) {
+        0 => ParamKind::Toggle,
+        1 => ParamKind::Stepped {
+            steps: g.bits() as u32,
+        },
+        _ => ParamKind::Continuous,
+    }
+}
+
+fn tempo(g: &mut Gen) -> ProjectTempo {
+    ProjectTempo {
+        bpm: g.f32(),
+        beats_per_measure: g.i32(),
+    }
+}
+
+fn param_check(g: &mut Gen) -> ParamCheck {
+    ParamCheck {
+        module: g.string(),
+        role: g.pick(&[ParamRole::Toggle, ParamRole::Mix, ParamRole::Knob]),
+        expected_index: g.i32(),
+        expected_name: g.opt(Gen::string),
+        found_name: g.opt(Gen::string),
+        status: g.pick(&[
+            ParamCheckStatus::Ok,
+            ParamCheckStatus::Suspicious,
+            ParamCheckStatus::Missing,
+        ]),
+    }
+}
+
+fn applied_param(g: &mut Gen) -> AppliedParam {
+    AppliedParam {
+        index: g.i32(),
+        requested: g.f64(),
+        applied: g.f64(),
+        formatted: g.string(),
+    }
+}
+
+fn failed_param(g: &mut Gen) -> FailedParam {
+    FailedParam {
+        index: g.i32(),
+        value: g.f64(),
+        error: g.string(),
+    }
+}
+
+fn skipped_module(g: &mut Gen) -> SkippedModule {
+    SkippedModule {
+        name: g.string(),
+        active: g.opt(Gen::bool),
+    }
+}
+
+fn reading(g: &mut Gen) -> ParamReading {
+    ParamReading {
+        index: g.i32(),
+        value: g.f64(),
+        formatted: g.string(),
+    }
+}
+
+fn diagnostic(g: &mut Gen) -> ErrorDiagnostic {
+    ErrorDiagnostic {
+        param_index: g.opt(Gen::i32),
+        value: g.opt(Gen::f64),
+        resolver: g.opt(|g| {
+            g.pick(&[
+                ResolverState::CacheMiss,
+                ResolverState::VerifyFailed,
+                ResolverState::BackgroundProject,
+            ])
+        }),
+        remediation: g.opt(|g| {
+            g.pick(&[
+                Remediation::RefreshInstances,
+                Remediation::FixParams,
+                Remediation::CompleteHandshake,
+                Remediation::Reauthenticate,
+                Remediation::Retry,
+                Remediation::AllowBackgroundProjects,
+            ])
+        }),
+    }
+}
+
+fn error_code(g: &mut Gen) -> ErrorCode {
+    match g.below(11) {
+        0 => ErrorCode::Unauthorized,
+        1 => ErrorCode::Busy,
+        2 => ErrorCode::TargetNotFound,
+        3 => ErrorCode::InvalidValue,
+        4 => ErrorCode::InvalidCommand,
+        5 => ErrorCode::NotReady,
+        6 => ErrorCode::InternalError,
+        7 => ErrorCode::TargetChanged,
+        8 => ErrorCode::Unsupported,
+        9 => ErrorCode::Timeout,
+        _ => ErrorCode::PartialFailure,
+    }
+}
+
+fn warning_code(g: &mut Gen) -> WarningCode {
+    match g.below(6) {
+        0 => WarningCode::ProbeTimeout,
+        1 => WarningCode::ReservedIndexSkipped,
+        2 => WarningCode::CommandCoalesced,
+        3 => WarningCode::LinkedCabOverride,
+        4 => WarningCode::ReadbackUnavailable,
+        _ => WarningCode::BackgroundProject,
+    }
+}
+
+fn capability(g: &mut Gen) -> Capability {
+    g.pick(&[
+        Capability::AtomicApply,
+        Capability::ParamSubscription,
+        Capability::Presets,
+        Capability::RenderPreview,
+        Capability::ToneSlots,
+        Capability::Msgpack,
+    ])
+}
+
+fn tone_issue(g: &mut Gen) -> ToneIssue {
+    g.pick(&[
+        ToneIssue::UnknownParam,
+        ToneIssue::ReservedParam,
+        ToneIssue::Duplicate,
+        ToneIssue::OutOfRange,
+        ToneIssue::OffEnumOption,
+        ToneIssue::FractionalToggle,
+        ToneIssue::ModuleOff,
+    ])
+}
+
+fn server_message(g: &mut Gen) -> ServerMessage {
+    let command_id = g.string();
+    let target_fx_guid = g.string();
+    match g.below(27) {
+        0 => ServerMessage::Handshake {
+            session_token: g.string(),
+            instances: g.vec(instance),
+            validation_report: g.vec(param_check),
+            param_meta_streaming: g.bool(),
+            meta: Box::new(HandshakeMeta {
+                param_enums: g.int_map(|g| g.vec(enum_option)),
+                param_enum_totals: g.int_map(Gen::usize),
+                param_formats: g.int_map(format_triplet),
+                param_format_samples: g.int_map(|g| g.vec(format_sample)),
+                eq_band_hz: g.int_map(Gen::f32),
+                param_kinds: g.int_map(param_kind),
+                param_schema: g.int_map(schema_entry),
+                param_remap: g.int_map(Gen::i32),
+                proposed_remap: g.int_map(Gen::i32),
+            }),
+            project_tempo: g.opt(tempo),
+            server_addr: g.opt(Gen::string),
+            tls_fingerprint: g.opt(Gen::string),
+            capabilities: g.vec(capability),
+        },
+        1 => ServerMessage::ParamMeta {
+            first_index: g.i32(),
+            last_index: g.i32(),
+            param_enums: g.int_map(|g| g.vec(enum_option)),
+            param_enum_totals: g.int_map(Gen::usize),
+            param_formats: g.int_map(format_triplet),
+            param_format_samples: g.int_map(|g| g.vec(format_sample)),
+            param_kinds: g.int_map(param_kind),
+            done: g.bool(),
+        },
+        2 => ServerMessage::ProjectChanged {
+            project_tempo: g.opt(tempo),
+            project_name: g.opt(Gen::string),
+            changed_instances: g.vec(Gen::string),
+            added: g.vec(instance),
+            removed: g.vec(instance),
+            renamed: g.vec(instance),
+        },
+        3 => ServerMessage::Ack {
+            command_id,
+            applied_params: g.vec(applied_param),
+            skipped_modules: g.vec(skipped_module),
+            failed_params: g.vec(failed_param),
+        },
+        4 => ServerMessage::MultiAck {
+            command_id,
+            results: g.vec(|g| TargetResult {
+                target_fx_guid: g.string(),
+                applied_params: g.vec(applied_param),
+                failed_params: g.vec(failed_param),
+                error: g.opt(Gen::string),
+            }),
+        },
+        5 => ServerMessage::Error {
+            msg: g.string(),
+            code: error_code(g),
+            details: g.opt(Gen::string),
+            command_id: g.opt(Gen::string),
+            diagnostic: g.opt(diagnostic),
+        },
+        6 => ServerMessage::TokenRotated {
+            session_token: g.string(),
+            expires_in_secs: g.opt(Gen::u64),
+        },
+        7 => ServerMessage::Metrics {
+            metrics: RuntimeMetrics {
+                uptime_secs: g.f64(),
+                commands_received: g.u64(),
+                outbound_high_water: g.usize(),
+                max_probe_ms: g.f64(),
+                ..RuntimeMetrics::default()
+            },
+        },
+        8 => ServerMessage::Diagnostics {
+            events: g.vec(|g| LogEvent {
+                at_ms: g.u64(),
+                level: g.pick(&[
+                    LogLevel::Error,
+                    LogLevel::Warn,
+                    LogLevel::Info,
+                    LogLevel::Debug,
+                ]),
+                target: g.string(),
+                msg: g.string(),
+            }),
+            timings: TimingStats {
+                ticks: g.u64(),
+                max_tick_ms: g.f64(),
+                ..TimingStats::default()
+            },
+        },
+        9 => ServerMessage::InstanceMoved {
+            instance: instance(g),
+            from_track_guid: g.string(),
+            from_fx_index: g.i32(),
+        },
+        10 => ServerMessage::Pong {
+            nonce: g.u64(),
+            uptime_ms: g.u64(),
+        },
+        11 => ServerMessage::ParamEnumPage {
+            command_id,
+            index: g.i32(),
+            query: g.string(),
+            offset: g.usize(),
+            total: g.usize(),
+            options: g.vec(enum_option),
+        },
+        12 => ServerMessage::CurrentTone {
+            command_id,
+            target_fx_guid,
+            params: g.vec(reading),
+            last_applied: g.vec(param_change),
+        },
+        13 => ServerMessage::Preview {
+            command_id,
+            target_fx_guid,
+            rows: g.vec(|g| PreviewRow {
+                index: g.i32(),
+                name: g.string(),
+                before: g.opt(Gen::f64),
+                before_formatted: g.string(),
+                after: g.f64(),
+                after_formatted: g.string(),
+            }),
+            skipped_modules: g.vec(skipped_module),
+        },
+        14 => ServerMessage::ToneValidation {
+            command_id,
+            target_fx_guid,
+            warnings: g.vec(|g| ToneWarning {
+                issue: tone_issue(g),
+                index: g.i32(),
+                msg: g.string(),
+            }),
+        },
+        15 => ServerMessage::PreviewRendered {
+            command_id,
+            target_fx_guid,
+            path: g.string(),
+            start: g.f64(),
+            length: g.f64(),
+        },
+        16 => ServerMessage::Transport {
+            command_id,
+            playing: g.bool(),
+            repeat: g.bool(),
+            loop_start: g.opt(Gen::f64),
+            loop_end: g.opt(Gen::f64),
+        },
+        17 => ServerMessage::FxState {
+            command_id,
+            target_fx_guid,
+            enabled: g.bool(),
+            wet: g.opt(Gen::f32),
+        },
+        18 => ServerMessage::TrackControls {
+            command_id,
+            target_fx_guid,
+            track_guid: g.string(),
+            volume_db: g.opt(Gen::f32),
+            pan: g.opt(Gen::f32),
+            rec_monitor: g.opt(Gen::bool),
+        },
+        19 => ServerMessage::ParamChanged {
+            target_fx_guid,
+            params: g.vec(reading),
+        },
+        20 => ServerMessage::PresetList {
+            command_id,
+            target_fx_guid,
+            saved: g.vec(Gen::string),
+            current_native: g.opt(Gen::string),
+        },
+        21 => ServerMessage::Warning {
+            code: warning_code(g),
+            msg: g.string(),
+            context: g.opt(Gen::string),
+        },
+        22 => ServerMessage::ToneSlots {
+            command_id,
+            slots: g.vec(|g| ToneSlotInfo {
+                slot: g.bits() as u32,
+                name: g.string(),
+                target_fx_guid: g.string(),
+                param_count: g.usize(),
+            }),
+            active_slot: g.opt(|g| g.bits() as u32),
+        },
+        23 => ServerMessage::Replay {
+            // Flat messages only; a replay never carries another one.
+            events: g.vec(|g| ReplayedEvent {
+                age_ms: g.bits(),
+                message: ServerMessage::Pong {
+                    nonce: g.bits(),
+                    uptime_ms: g.bits(),
+                },
+            }),
+        },
+        24 => ServerMessage::Instances {
+            instances: g.vec(instance),
+        },
+        25 => ServerMessage::Resumed {
+            session_token: g.string(),
+            instances: g.vec(instance),
+        },
+        _ => ServerMessage::Shutdown {
+            reason: g.string(),
+            restart_expected: g.bool(),
+        },
+    }
+}
+
+fn frame_bytes(frame: &Frame) -> &[u8] {
+    match frame {
+        Frame::Text(text) => text.as_bytes(),
+        Frame::Binary(bytes) => bytes,
+    }
+}
+
+/// Encodes `msg`, decodes it and encodes it again in `encoding`; both encodings have to say the
+/// same thing. Compared as JSON values, since map order and most types' equality aren't defined.
+fn assert_round_trips<T: Serialize + DeserializeOwned + std::fmt::Debug>(
+    msg: &T,
+    encoding: Encoding,
+) {
+    let frame = encoding.encode(msg).expect("generated message encodes");
+    let decoded: T = encoding
+        .decode(frame_bytes(&frame))
+        .unwrap_or_else(|e| panic!("{encoding:?} decode failed ({e}) for {msg:?}"));
+    let before = serde_json::to_value(msg).expect("to value");
+    let after = serde_json::to_value(&decoded).expect("to value");
+    assert_eq!(before, after, "{encoding:?} round trip changed {msg:?}");
+}
+
+#[test]
+fn generated_commands_round_trip() {
+    for seed in 0..CASES {
+        let cmd = client_command(&mut Gen::new(seed));
+        for encoding in [Encoding::Json, Encoding::Msgpack] {
+            assert_round_trips(&cmd, encoding);
+        }
+        let json = serde_json::to_value(&cmd).expect("to value");
+        assert_eq!(json["type"], cmd.kind());
+    }
+}
+
+#[test]
+fn generated_server_messages_round_trip() {
+    for seed in 0..CASES {
+        let msg = server_message(&mut Gen::new(seed));
+        for encoding in [Encoding::Json, Encoding::Msgpack] {
+            assert_round_trips(&msg, encoding);
+        }
+    }
+}
+
+/// What older DLLs and clients put on the wire: fields they didn't have yet, int keys as
+/// strings, free-text validation reports, f32-rounded values. All of it has to keep parsing.
+#[test]
+fn older_peers_still_parse() {
+    let server = [
+        r#"{"type":"handshake","session_token":"t","instances":[]}"#,
+        r#"{"type":"handshake","session_token":"t","instances":[],"validation_report":{"amp":"ok"}}"#,
+        r#"{"type":"handshake","session_token":"t","instances":[{"track_guid":"{T}","track_name":"Gtr","fx_guid":"{F}","fx_name":"Gojira","last_known_fx_index":0,"confidence":"high"}],"param_enums":{"29":[{"value":0.0,"label":"Clean"}]},"param_formats":{"0":{"min":"-24.0 dB","mid":"0.0 dB","max":"24.0 dB"}},"eq_band_hz":{"54":63.0}}"#,
+        r#"{"type":"param_meta","first_index":0,"last_index":63}"#,
+        r#"{"type":"project_changed"}"#,
+        r#"{"type":"ack","command_id":"c1"}"#,
+        r#"{"type":"error","msg":"unauthorized","code":"unauthorized"}"#,
+        r#"{"type":"warning","code":"probe_timeout","msg":"kinds probe took too long"}"#,
+        r#"{"type":"token_rotated","session_token":"t2"}"#,
+        r#"{"type":"metrics","metrics":{"uptime_secs":1.5}}"#,
+        r#"{"type":"shutdown","reason":"reaper_exiting"}"#,
+    ];
+    for text in server {
+        if let Err(e) = Encoding::Json.decode::<ServerMessage>(text.as_bytes()) {
+            panic!("server message no longer parses ({e}): {text}");
+        }
+    }
+
+    let client = [
+        r#"{"type":"handshake_ack","session_token":"t"}"#,
+        r#"{"type":"get_diagnostics","session_token":"t"}"#,
+        r#"{"type":"set_tone","session_token":"t","command_id":"c","target_fx_guid":"{F}","mode":"merge","params":[{"index":29,"value":0.12597656}]}"#,
+        r#"{"type":"subscribe","session_token":"t","target_fx_guid":"{F}"}"#,
+        r#"{"type":"search_enum_labels","session_token":"t","command_id":"c","index":92}"#,
+        r#"{"type":"insert_fx","session_token":"t","command_id":"c"}"#,
+        r#"{"type":"set_fx_state","session_token":"t","command_id":"c","target_fx_guid":"{F}"}"#,
+    ];
+    for text in client {
+        if let Err(e) = Encoding::Json.decode::<ClientCommand>(text.as_bytes()) {
+            panic!("command no longer parses ({e}): {text}");
+        }
+    }
+}
+
+/// Codes a newer DLL adds have to reach an older client as `Unknown`, in either encoding, rather
+/// than failing the whole message.
+#[test]
+fn newer_codes_parse_as_unknown() {
+    let error = serde_json::json!({
+        "type": "error",
+        "msg": "m",
+        "code": "quota_exceeded",
+        "diagnostic": {"resolver": "gone_fishing", "remediation": "upgrade_plugin"},
+    });
+    let warning = serde_json::json!({"type": "warning", "code": "cpu_hot", "msg": "m"});
+    for encoding in [Encoding::Json, Encoding::Msgpack] {
+        let frame = encoding.encode(&error).expect("encodes");
+        match encoding.decode::<ServerMessage>(frame_bytes(&frame)) {
+            Ok(ServerMessage::Error {
+                code: ErrorCode::Unknown,
+                diagnostic: Some(diagnostic),
+                ..
+            }) => {
+                assert_eq!(diagnostic.resolver, Some(ResolverState::Unknown));
+                assert_eq!(diagnostic.remediation, Some(Remediation::Unknown));
+            }
+            other => panic!("{encoding:?}: expected an unknown error code, got {other:?}"),
+        }
+        let frame = encoding.encode(&warning).expect("encodes");
+        match encoding.decode::<ServerMessage>(frame_bytes(&frame)) {
+            Ok(ServerMessage::Warning {
+                code: WarningCode::Unknown,
+                ..
+            }) => {}
+            other => panic!("{encoding:?}: expected an unknown warning code, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn capabilities_of_newer_and_older_dlls_parse() {
+    let newer = r#"{"type":"handshake","session_token":"t","instances":[],"capabilities":["presets","time_travel"]}"#;
+    match serde_json::from_str::<ServerMessage>(newer) {
+        Ok(ServerMessage::Handshake { capabilities, .. }) => {
+            assert_eq!(capabilities, [Capability::Presets, Capability::Unknown]);
+        }
+        other => panic!("expected a handshake, got {other:?}"),
+    }
+    let older = r#"{"type":"handshake","session_token":"t","instances":[]}"#;
+    match serde_json::from_str::<ServerMessage>(older) {
+        Ok(ServerMessage::Handshake { capabilities, .. }) => assert!(capabilities.is_empty()),
+        other => panic!("expected a handshake, got {other:?}"),
+    }
+}
+
+#[test]
+fn legacy_validation_report_reads_as_no_checks() {
+    let legacy = r#"{"type":"handshake","session_token":"t","instances":[],"validation_report":{"drive_on":"ok","gate_on":"missing"}}"#;
+    match serde_json::from_str::<ServerMessage>(legacy) {
+        Ok(ServerMessage::Handshake {
+            validation_report, ..
+        }) => assert!(validation_report.is_empty()),
+        other => panic!("expected a handshake, got {other:?}"),
+    }
+}
+
+const MALFORMED: &[&str] = &[
+    "",
+    " ",
+    "null",
+    "[]",
+    "{}",
+    "\"set_tone\"",
+    "{\"type\":null}",
+    "{\"type\":\"no_such_command\",\"session_token\":\"t\"}",
+    "{\"type\":\"ping\"}",
+    "{\"type\":\"ping\",\"session_token\":\"t\",\"nonce\":-1}",
+    "{\"type\":\"ping\",\"session_token\":\"t\",\"nonce\":18446744073709551616}",
+    "{\"type\":\"set_loop\",\"session_token\":\"t\",\"command_id\":\"c\",\"start\":NaN,\"end\":1}",
+    "{\"type\":\"set_tone\",\"session_token\":\"t\",\"command_id\":\"c\",\"target_fx_guid\":\"f\",\"mode\":\"merge\",\"params\":{}}",
+    "{\"type\":\"set_tone\",\"session_token\":\"t\",\"command_id\":\"c\",\"target_fx_guid\":\"f\",\"mode\":\"replace\",\"params\":[]}",
+    "{\"type\":\"subscribe\",\"session_token\":\"t\",\"target_fx_guid\":\"f\",\"indices\":[1e99]}",
+    "{\"type\":\"handshake_ack\",\"session_token\":\"\\ud800\"}",
+    "{\"type\":\"handshake_ack\",\"session_token\":\"t\"",
+    "{\"type\":\"handshake_ack\",,\"session_token\":\"t\"}",
+    "\u{feff}{\"type\":\"handshake_ack\"}",
+];
+
+fn nested(depth: usize) -> String {
+    format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+/// Valid encodings cut short or with bytes flipped, plus the fixed corpus. Decoding may fail,
+/// but it has to fail with an error.
+#[test]
+fn hostile_input_is_an_error_not_a_panic() {
+    for text in MALFORMED {
+        assert!(
+            Encoding::Json
+                .decode::<ClientCommand>(text.as_bytes())
+                .is_err(),
+            "malformed command parsed: {text}"
+        );
+    }
+    for depth in [64, 4096] {
+        let text = nested(depth);
+        assert!(Encoding::Json
+            .decode::<ClientCommand>(text.as_bytes())
+            .is_err());
+        // Arrays of one element, nested: 0x91 opens one, 0x90 is the empty one inside.
+        let mut bytes = vec![0x91; depth];
+        bytes.push(0x90);
+        assert!(Encoding::Msgpack.decode::<ClientCommand>(&bytes).is_err());
+    }
+
+    for seed in 0..CASES {
+        let mut g = Gen::new(seed);
+        let cmd = client_command(&mut g);
+        let msg = server_message(&mut g);
+        for encoding in [Encoding::Json, Encoding::Msgpack] {
+            let frames = [
+                encoding.encode(&cmd).expect("encodes"),
+                encoding.encode(&msg).expect("encodes"),
+            ];
+            for frame in &frames {
+                let mut bytes = frame_bytes(frame).to_vec();
+                if bytes.is_empty() {
+                    continue;
+                }
+                let cut = g.below(bytes.len());
+                let _ = encoding.decode::<ClientCommand>(&bytes[..cut]);
+                let _ = encoding.decode::<ServerMessage>(&bytes[..cut]);
+                for _ in 0..=g.below(4) {
+                    let at = g.below(bytes.len());
+                    bytes[at] = g.bits() as u8;
+                }
+                let _ = encoding.decode::<ClientCommand>(&bytes);
+                let _ = encoding.decode::<ServerMessage>(&bytes);
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! The generated schema has to cover both directions of the protocol, tags included. The
+//! crate's dev-dependency on itself turns on `schema` for these.
+
+use gojira_protocol::{schemas, ClientCommand, ServerMessage};
+use serde_json::Value;
+
+/// The `type` tags a schema's top-level `oneOf` allows.
+fn tags(schema: &Value) -> Vec<String> {
+    schema["oneOf"]
+        .as_array()
+        .expect("tagged enum schema")
+        .iter()
+        .filter_map(|v| v["properties"]["type"]["const"].as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn schemas_list_every_message_type() {
+    let schemas: Vec<(&str, Value)> = schemas()
+        .into_iter()
+        .map(|(name, s)| (name, serde_json::to_value(s).unwrap()))
+        .collect();
+    let (_, commands) = &schemas[0];
+    let (_, messages) = &schemas[1];
+
+    let commands = tags(commands);
+    let ping = ClientCommand::Ping {
+        session_token: "t".to_string(),
+        nonce: 1,
+    };
+    assert!(commands.iter().any(|t| t == ping.kind()));
+    assert!(commands.iter().any(|t| t == "set_tone_multi"));
+
+    let messages = tags(messages);
+    let pong = serde_json::to_value(ServerMessage::Pong {
+        nonce: 1,
+        uptime_ms: 0,
+    })
+    .unwrap();
+    assert!(messages.iter().any(|t| *t == pong["type"]));
+    assert!(messages.iter().any(|t| t == "handshake"));
+}
+
+/// The committed bindings are what the protocol generates now; regenerate them (see the README)
+/// after changing a message.
+#[test]
+fn generated_bindings_are_current() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let committed = [
+        (
+            "gojira_brain_ui/ui/src/protocol.gen.ts",
+            gojira_protocol::codegen::typescript(),
+        ),
+        ("tools/gojira_client.py", gojira_protocol::codegen::python()),
+    ];
+    for (path, generated) in committed {
+        let on_disk = std::fs::read_to_string(root.join(path)).expect(path);
+        assert!(on_disk == generated, "{path} is out of date");
+    }
+}
@@ -0,0 +1,120 @@
+//! What `validate` and the typed constructors let through has to be what the DLL accepts, and
+//! what they refuse has to be refused before it is sent.
+
+use gojira_protocol::{
+    ClientCommand, Delta, MergeMode, ModuleId, Normalized, ParamChange, ParamOp, SetToneBuilder,
+    ToneTarget, TransportAnchor, MAX_PARAM_INDEX, MAX_TONE_SLOTS,
+};
+
+#[test]
+fn built_set_tone_carries_every_field() {
+    let cmd = SetToneBuilder::new("t", "c1", "{F}")
+        .mode(MergeMode::ReplaceActive)
+        .set(29, Normalized::new(0.125976562).unwrap())
+        .add(30, Delta::new(-0.1).unwrap())
+        .lock(12)
+        .ramp_ms(200)
+        .apply_at(TransportAnchor::NextBar)
+        .modules([ModuleId::Cab])
+        .build()
+        .expect("valid set_tone");
+    let json = serde_json::to_value(&cmd).unwrap();
+    assert_eq!(json["type"], "set_tone");
+    assert_eq!(json["mode"], "replace_active");
+    assert_eq!(json["params"][0]["value"], 0.125976562);
+    assert_eq!(json["params"][1]["op"], "add");
+    assert_eq!(json["locked_params"][0], 12);
+    assert_eq!(json["ramp_ms"], 200);
+    assert_eq!(json["modules"][0], "cab");
+    assert_eq!(json["atomic"], false);
+}
+
+#[test]
+fn unit_newtypes_hold_their_range() {
+    assert!(Normalized::new(0.0).is_ok() && Normalized::new(1.0).is_ok());
+    assert!(Normalized::new(1.5).is_err());
+    assert!(Normalized::new(f64::NAN).is_err());
+    assert!(Delta::new(-1.0).is_ok());
+    assert!(Delta::new(-1.01).is_err());
+    assert!(Delta::new(f64::INFINITY).is_err());
+}
+
+#[test]
+fn obviously_invalid_commands_are_refused() {
+    let change = |index, value, op| ParamChange { index, value, op };
+    let tone = |params| SetToneBuilder::new("t", "c", "{F}").param(params).build();
+    assert!(tone(change(MAX_PARAM_INDEX, 0.5, ParamOp::Set)).is_ok());
+    assert!(tone(change(MAX_PARAM_INDEX + 1, 0.5, ParamOp::Set)).is_err());
+    assert!(tone(change(-1, 0.5, ParamOp::Set)).is_err());
+    assert!(tone(change(29, f64::NAN, ParamOp::Set)).is_err());
+    assert!(tone(change(29, -0.5, ParamOp::Set)).is_err());
+    assert!(tone(change(29, -0.5, ParamOp::Add)).is_ok());
+    assert!(SetToneBuilder::new("t", "c", " ").build().is_err());
+    assert!(SetToneBuilder::new("", "c", "{F}").build().is_err());
+    assert!(SetToneBuilder::new("t", "c", "{F}")
+        .apply_at(TransportAnchor::At {
+            position_secs: f64::INFINITY
+        })
+        .build()
+        .is_err());
+
+    let invalid = [
+        ClientCommand::SetToneMulti {
+            session_token: "t".to_string(),
+            command_id: "c".to_string(),
+            targets: Vec::new(),
+            allow_background_projects: false,
+        },
+        ClientCommand::SetToneMulti {
+            session_token: "t".to_string(),
+            command_id: "c".to_string(),
+            targets: vec![ToneTarget {
+                target_fx_guid: String::new(),
+                mode: MergeMode::Merge,
+                params: Vec::new(),
+                locked_params: Vec::new(),
+            }],
+            allow_background_projects: false,
+        },
+        ClientCommand::ActivateToneSlot {
+            session_token: "t".to_string(),
+            command_id: "c".to_string(),
+            slot: MAX_TONE_SLOTS,
+            ramp_ms: None,
+            apply_at: None,
+        },
+        ClientCommand::SetLoop {
+            session_token: "t".to_string(),
+            command_id: "c".to_string(),
+            start: 4.0,
+            end: 2.0,
+        },
+        ClientCommand::SetFxMix {
+            session_token: "t".to_string(),
+            command_id: "c".to_string(),
+            target_fx_guid: "{F}".to_string(),
+            wet: Some(f32::NAN),
+            delta_solo: None,
+        },
+        ClientCommand::SwitchFxPair {
+            session_token: "t".to_string(),
+            command_id: "c".to_string(),
+            enable_fx_guid: "{F}".to_string(),
+            disable_fx_guid: "{F}".to_string(),
+        },
+        ClientCommand::Subscribe {
+            session_token: "t".to_string(),
+            target_fx_guid: "{F}".to_string(),
+            indices: vec![0, -3],
+        },
+    ];
+    for cmd in invalid {
+        assert!(cmd.validate().is_err(), "passed: {cmd:?}");
+    }
+
+    let ping = ClientCommand::Ping {
+        session_token: "t".to_string(),
+        nonce: 7,
+    };
+    assert!(ping.validate().is_ok());
+}
@@ -0,0 +1,301 @@
+//! Scan, apply and probe hot paths against a synthetic project the size of a large session:
+//! 500 tracks with 6 FX each, a Gojira on every other track. Run with
+//! `cargo bench -p reaper_gojira_dll --features bench`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use gojira_protocol::{ParamChange, ParamOp};
+use reaper_gojira_dll::bench;
+use reaper_gojira_dll::ReaperApi;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::path::Path;
+
+const TRACKS: usize = 500;
+const FX_PER_TRACK: usize = 6;
+/// Where the Gojira sits in the chain of the tracks that have one.
+const GOJIRA_SLOT: i32 = 2;
+const PROJECT: usize = 1;
+
+/// A project that only answers reads. Track handles are the track index plus one, so none is 0.
+struct SyntheticProject {
+    /// Options of the enum `probe_enum` is pointed at, like the IR list.
+    enum_labels: usize,
+}
+
+impl SyntheticProject {
+    fn has_gojira(track: usize) -> bool {
+        track & 1 == 0
+    }
+}
+
+impl ReaperApi for SyntheticProject {
+    fn project_state_change_count(&self) -> i32 {
+        0
+    }
+
+    fn project_state_change_count_in(&self, _project: usize) -> i32 {
+        0
+    }
+
+    fn project_tempo(&self) -> Option<(f32, i32)> {
+        Some((120.0, 4))
+    }
+
+    fn transport_play(&self) {}
+
+    fn transport_stop(&self) {}
+
+    fn transport_playing(&self) -> bool {
+        false
+    }
+
+    fn play_position(&self) -> Option<f64> {
+        None
+    }
+
+    fn loop_range(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    fn set_loop(&self, _start: f64, _end: f64) {}
+
+    fn repeat_enabled(&self) -> bool {
+        false
+    }
+
+    fn render_range(&self, _start: f64, _end: f64, _path: &Path) -> Result<(), String> {
+        Err("no rendering in benches".to_string())
+    }
+
+    fn count_tracks(&self) -> i32 {
+        TRACKS as i32
+    }
+
+    fn get_track(&self, index: i32) -> Option<usize> {
+        self.get_track_in(PROJECT, index)
+    }
+
+    fn enum_project(&self, _index: i32) -> Option<(usize, String)> {
+        None
+    }
+
+    fn current_project(&self) -> Option<(usize, String)> {
+        Some((PROJECT, "bench.rpp".to_string()))
+    }
+
+    fn count_tracks_in(&self, project: usize) -> i32 {
+        if project == PROJECT {
+            TRACKS as i32
+        } else {
+            0
+        }
+    }
+
+    fn get_track_in(&self, project: usize, index: i32) -> Option<usize> {
+        let index = usize::try_from(index).ok()?;
+        (project == PROJECT && index < TRACKS).then_some(index + 1)
+    }
+
+    fn selected_track(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn track_guid(&self, track: usize) -> Option<String> {
+        Some(format!("{{TRACK-{track:08}}}"))
+    }
+
+    fn track_name(&self, track: usize) -> String {
+        format!("Guitar {track}")
+    }
+
+    fn track_get_info(&self, _track: usize, _key: &str) -> Option<f64> {
+        None
+    }
+
+    fn track_set_info(&self, _track: usize, _key: &str, _value: f64) -> bool {
+        false
+    }
+
+    fn track_fx_count(&self, _track: usize) -> i32 {
+        FX_PER_TRACK as i32
+    }
+
+    fn track_fx_add_by_name(&self, _track: usize, _name: &str) -> Option<i32> {
+        None
+    }
+
+    fn track_fx_num_params(&self, _track: usize, _fx_index: i32) -> Option<i32> {
+        Some(135)
+    }
+
+    fn track_fx_guid(&self, track: usize, fx_index: i32) -> Option<String> {
+        Some(format!("{{FX-{track:08}-{fx_index:02}}}"))
+    }
+
+    fn track_fx_name(&self, track: usize, fx_index: i32) -> String {
+        if fx_index == GOJIRA_SLOT && Self::has_gojira(track) {
+            "VST3: Archetype Gojira (Neural DSP)".to_string()
+        } else {
+            format!("VST: ReaEQ {fx_index} (Cockos)")
+        }
+    }
+
+    fn track_fx_ident(&self, _track: usize, _fx_index: i32) -> Option<String> {
+        None
+    }
+
+    fn track_fx_param_name(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        param_index: i32,
+    ) -> Option<String> {
+        Some(
+            bench::charted_name(param_index)
+                .map_or_else(|| format!("Param {param_index}"), str::to_string),
+        )
+    }
+
+    fn track_fx_format_param_value(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        _param_index: i32,
+        value: f64,
+    ) -> Option<String> {
+        let n = self.enum_labels;
+        let option = ((value * n as f64) as usize).min(n - 1);
+        Some(format!("IR {option:03}"))
+    }
+
+    fn track_fx_get_param(&self, _track: usize, _fx_index: i32, _param_index: i32) -> Option<f64> {
+        Some(0.5)
+    }
+
+    fn track_fx_param_step_sizes(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        _param_index: i32,
+    ) -> Option<(f64, bool)> {
+        None
+    }
+
+    fn track_fx_set_param(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        _param_index: i32,
+        _value: f64,
+    ) -> Result<(), String> {
+        Err("read-only".to_string())
+    }
+
+    fn track_fx_param_from_ident(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        _ident: &str,
+    ) -> Option<i32> {
+        None
+    }
+
+    fn track_fx_get_enabled(&self, _track: usize, _fx_index: i32) -> bool {
+        true
+    }
+
+    fn track_fx_set_enabled(&self, _track: usize, _fx_index: i32, _enabled: bool) {}
+
+    fn track_fx_get_preset(&self, _track: usize, _fx_index: i32) -> Option<String> {
+        None
+    }
+
+    fn track_fx_set_preset(&self, _track: usize, _fx_index: i32, _name: &str) -> bool {
+        false
+    }
+
+    fn get_proj_ext_state(&self, _section: &str, _key: &str) -> Option<String> {
+        None
+    }
+
+    fn set_proj_ext_state(&self, _section: &str, _key: &str, _value: &str) {}
+
+    fn undo_begin_block(&self) {}
+
+    fn undo_end_block(&self, _desc: &str) {}
+
+    fn undo_next_desc(&self) -> Option<String> {
+        None
+    }
+
+    fn undo_do(&self) -> bool {
+        false
+    }
+
+    fn show_console_msg(&self, _msg: &str) {}
+}
+
+/// A full tone with every index sent `repeats` times, last values winning.
+fn tone(repeats: usize) -> Vec<ParamChange> {
+    (0..repeats)
+        .flat_map(|r| {
+            (0..=118).map(move |index| ParamChange {
+                index,
+                value: (index as f64 + r as f64) / 128.0,
+                op: ParamOp::Set,
+            })
+        })
+        .collect()
+}
+
+fn scan(c: &mut Criterion) {
+    let api = SyntheticProject { enum_labels: 3 };
+    assert_eq!(bench::scan_project_instances(&api), TRACKS / 2);
+    c.bench_function("scan_project_instances/500_tracks_3000_fx", |b| {
+        b.iter(|| bench::scan_project_instances(black_box(&api)))
+    });
+}
+
+fn sanitize(c: &mut Criterion) {
+    for repeats in [1, 8] {
+        let params = tone(repeats);
+        c.bench_function(&format!("sanitize_params/{}", params.len()), |b| {
+            b.iter_batched(
+                || params.clone(),
+                |p| bench::sanitize_params(black_box(p)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn cleaner(c: &mut Criterion) {
+    // The amp and cab only: every other module gets its toggles switched off.
+    let amp_only: Vec<ParamChange> = tone(1).into_iter().filter(|p| p.index < 54).collect();
+    let none = HashSet::new();
+    let locked: HashSet<i32> = (84..=118).collect();
+    for (name, locked) in [("unlocked", &none), ("locked", &locked)] {
+        c.bench_function(&format!("apply_replace_active_cleaner/{name}"), |b| {
+            b.iter_batched(
+                || amp_only.clone(),
+                |p| bench::apply_replace_active_cleaner(black_box(p), locked),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn probe(c: &mut Criterion) {
+    for (labels, samples) in [(3, 256), (512, 1024)] {
+        let api = SyntheticProject {
+            enum_labels: labels,
+        };
+        let track = 1;
+        c.bench_function(&format!("probe_enum/{labels}_options"), |b| {
+            b.iter(|| bench::probe_enum(&api, track, GOJIRA_SLOT, 92, samples, labels))
+        });
+    }
+}
+
+criterion_group!(benches, scan, sanitize, cleaner, probe);
+criterion_main!(benches);
@@ -0,0 +1,99 @@
+use c_str_macro::c_str;
+use reaper_low::raw::custom_action_register_t;
+use reaper_low::Reaper;
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::sync::OnceLock;
+
+/// Commands the DLL adds to REAPER's action list, so they can go on a shortcut or toolbar button
+/// and work with no client connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReaperAction {
+    RefreshInstances,
+    /// Puts back the tone from before the latest `set_tone`.
+    RevertLastTone,
+    /// Activates the stored tone slot after the active one, wrapping around.
+    NextToneSlot,
+    /// Prints connection state, the last command and error, and the active instance to
+    /// REAPER's console.
+    ShowStatus,
+}
+
+impl ReaperAction {
+    pub const ALL: [ReaperAction; 4] = [
+        ReaperAction::RefreshInstances,
+        ReaperAction::RevertLastTone,
+        ReaperAction::NextToneSlot,
+        ReaperAction::ShowStatus,
+    ];
+
+    /// What REAPER keeps shortcuts and toolbar buttons under; never rename one.
+    fn id(self) -> &'static CStr {
+        match self {
+            ReaperAction::RefreshInstances => c_str!("GOJIRA_SIDECAR_REFRESH_INSTANCES"),
+            ReaperAction::RevertLastTone => c_str!("GOJIRA_SIDECAR_REVERT_LAST_TONE"),
+            ReaperAction::NextToneSlot => c_str!("GOJIRA_SIDECAR_NEXT_TONE_SLOT"),
+            ReaperAction::ShowStatus => c_str!("GOJIRA_SIDECAR_SHOW_STATUS"),
+        }
+    }
+
+    /// As listed in the action list.
+    fn name(self) -> &'static CStr {
+        match self {
+            ReaperAction::RefreshInstances => c_str!("Gojira Sidecar: Refresh instances"),
+            ReaperAction::RevertLastTone => c_str!("Gojira Sidecar: Revert last tone"),
+            ReaperAction::NextToneSlot => c_str!("Gojira Sidecar: Next tone slot"),
+            ReaperAction::ShowStatus => c_str!("Gojira Sidecar: Show status"),
+        }
+    }
+}
+
+/// REAPER's command id for each registered action.
+static COMMANDS: OnceLock<Vec<(i32, ReaperAction)>> = OnceLock::new();
+
+/// Adds every action to the main section of the action list; `hook` then gets called for all
+/// of REAPER's commands and answers for ours.
+pub fn register(reaper: Reaper, hook: extern "C" fn(i32, i32) -> bool) {
+    let mut commands = Vec::new();
+    for action in ReaperAction::ALL {
+        // REAPER keeps the pointer for as long as the action is registered.
+        let reg = Box::leak(Box::new(custom_action_register_t {
+            uniqueSectionId: 0,
+            idStr: action.id().as_ptr(),
+            name: action.name().as_ptr(),
+            extra: std::ptr::null_mut(),
+        }));
+        let command = unsafe {
+            reaper.plugin_register(
+                c_str!("custom_action").as_ptr(),
+                reg as *mut custom_action_register_t as *mut c_void,
+            )
+        };
+        if command > 0 {
+            commands.push((command, action));
+        }
+    }
+    let _ = COMMANDS.set(commands);
+    unsafe {
+        // REAPER's C API uses `void*` for command hook registration.
+        reaper.plugin_register(c_str!("hookcommand").as_ptr(), hook as usize as *mut c_void);
+    }
+}
+
+pub fn unregister(reaper: Reaper, hook: extern "C" fn(i32, i32) -> bool) {
+    unsafe {
+        reaper.plugin_register(
+            c_str!("-hookcommand").as_ptr(),
+            hook as usize as *mut c_void,
+        );
+    }
+}
+
+/// Our action behind REAPER's command id, if it is one of ours.
+pub fn lookup(command: i32) -> Option<ReaperAction> {
+    COMMANDS
+        .get()?
+        .iter()
+        .find(|(id, _)| *id == command)
+        .map(|(_, action)| *action)
+}
@@ -0,0 +1,41 @@
+//! The scan, apply and probe hot paths, for the criterion benches in `benches/` to reach past the
+//! private modules. Only built with the `bench` feature; nothing here is API.
+
+use crate::reaper_api::ReaperApi;
+use crate::{main_loop, profiles, resolver, validator};
+use gojira_protocol::{ParamChange, ParamEnumOption};
+use std::collections::HashSet;
+
+/// Name the Gojira chart has for `index`, so a synthetic plugin can pass the signature check.
+pub fn charted_name(index: i32) -> Option<&'static str> {
+    profiles::GOJIRA.charted(index).map(|p| p.name)
+}
+
+/// Instances found across the watched projects.
+pub fn scan_project_instances(api: &dyn ReaperApi) -> usize {
+    resolver::scan_project_instances(api).0.len()
+}
+
+/// `None` where a command would be refused.
+pub fn sanitize_params(params: Vec<ParamChange>) -> Option<Vec<ParamChange>> {
+    main_loop::sanitize_params(params).ok()
+}
+
+/// The replace_active cleaner over the Gojira's modules.
+pub fn apply_replace_active_cleaner(
+    params: Vec<ParamChange>,
+    locked: &HashSet<i32>,
+) -> Vec<ParamChange> {
+    main_loop::apply_replace_active_cleaner(profiles::GOJIRA.modules, params, locked)
+}
+
+pub fn probe_enum(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    idx: i32,
+    samples: usize,
+    max_options: usize,
+) -> Vec<ParamEnumOption> {
+    validator::probe_enum(api, track, fx_index, idx, samples, max_options, false)
+}
@@ -0,0 +1,641 @@
+mod param_db;
+mod scenario;
+mod script;
+
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use param_db::ParamDb;
+use reaper_gojira_dll::{MainLoop, NetworkThread, ReaperApi};
+use scenario::{Scenario, ScenarioFx};
+use script::{Script, ScriptEvent};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:0";
+/// Track handles are the track's position in the scenario past this.
+const TRACK_BASE: usize = 100;
+
+const USAGE: &str = "\
+usage: mock_sidecar [options]
+  --addr <host:port>          where to listen (default 127.0.0.1:0, or GOJIRA_WS_ADDR)
+  --addr-file <path>          write the address actually bound there
+  --run-for-ms <ms>           shut down after this long (default: run until killed)
+  --scenario <file.json>      tracks and FX to pretend to have (default: one Gojira)
+  --script <file.json>        project changes to play back over time
+  --latency-ms <ms>           hold every outbound message back this long
+  --drop-every <n>            never send every nth outbound message
+  --fail-set-param <i,j,...>  param indices whose writes fail
+  --project-storm-ms <ms>     rename the first track this often, for a stream of project changes";
+
+/// Misbehavior asked for on the command line, so client retry and reconnect paths can be tested
+/// against a DLL that is slow, lossy or noisy in the same way every run.
+#[derive(Default)]
+struct Faults {
+    /// `--latency-ms`: how long every outbound message is held back.
+    latency: Duration,
+    /// `--drop-every`: every Nth outbound message is never sent.
+    drop_every: Option<u64>,
+    /// `--fail-set-param`: comma-separated param indices whose writes fail.
+    fail_params: HashSet<i32>,
+    /// `--project-storm-ms`: the first track is renamed this often, for a stream of project
+    /// changes.
+    project_storm: Option<Duration>,
+}
+
+impl Faults {
+    fn from_args(args: &[String]) -> Self {
+        let millis = |name: &str| {
+            parse_arg_value(args, name)
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(Duration::from_millis)
+        };
+        Self {
+            latency: millis("--latency-ms").unwrap_or_default(),
+            drop_every: parse_arg_value(args, "--drop-every")
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|n| *n > 0),
+            fail_params: parse_arg_value(args, "--fail-set-param")
+                .map(|s| s.split(',').filter_map(|i| i.trim().parse().ok()).collect())
+                .unwrap_or_default(),
+            project_storm: millis("--project-storm-ms").filter(|d| !d.is_zero()),
+        }
+    }
+
+    fn delays_outbound(&self) -> bool {
+        !self.latency.is_zero() || self.drop_every.is_some()
+    }
+}
+
+struct MockReaperApi {
+    layout: ParamDb,
+    scenario: Mutex<Scenario>,
+    /// Values per FX GUID, so instances don't share a tone.
+    params: Mutex<HashMap<(String, i32), f64>>,
+    track_info: Mutex<HashMap<(usize, String), f64>>,
+    fail_params: Mutex<HashSet<i32>>,
+    tempo: Mutex<f32>,
+    storms: Mutex<i32>,
+    /// Project edits made by the script.
+    edits: Mutex<i32>,
+    /// Run time as of the last tick; which FX are in their chains follows it.
+    now: Mutex<Duration>,
+}
+
+impl MockReaperApi {
+    fn new(scenario: Scenario, fail_params: HashSet<i32>) -> Self {
+        Self {
+            layout: ParamDb::gojira(),
+            scenario: Mutex::new(scenario),
+            params: Mutex::new(HashMap::new()),
+            track_info: Mutex::new(HashMap::new()),
+            fail_params: Mutex::new(fail_params),
+            tempo: Mutex::new(120.0),
+            storms: Mutex::new(0),
+            edits: Mutex::new(0),
+            now: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    fn advance(&self, elapsed: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = elapsed;
+        }
+    }
+
+    /// Renames the first track back and forth, which the watchdog reports as a project change.
+    fn storm_step(&self) {
+        if let Ok(mut storms) = self.storms.lock() {
+            *storms += 1;
+        }
+    }
+
+    fn storms(&self) -> i32 {
+        self.storms.lock().map(|s| *s).unwrap_or(0)
+    }
+
+    fn edits(&self) -> i32 {
+        self.edits.lock().map(|e| *e).unwrap_or(0)
+    }
+
+    fn now(&self) -> Duration {
+        self.now.lock().map(|n| *n).unwrap_or_default()
+    }
+
+    fn scenario(&self) -> Option<MutexGuard<'_, Scenario>> {
+        self.scenario.lock().ok()
+    }
+
+    fn track_at(&self, index: i32) -> Option<usize> {
+        let index = usize::try_from(index).ok()?;
+        (index < self.scenario()?.tracks.len()).then_some(TRACK_BASE + index)
+    }
+
+    fn chain(&self, track: usize) -> Vec<ScenarioFx> {
+        let now = self.now();
+        let Some(scenario) = self.scenario() else {
+            return Vec::new();
+        };
+        track
+            .checked_sub(TRACK_BASE)
+            .and_then(|i| scenario.tracks.get(i))
+            .map(|t| t.chain(now).into_iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn fx(&self, track: usize, fx_index: i32) -> Option<ScenarioFx> {
+        let fx_index = usize::try_from(fx_index).ok()?;
+        self.chain(track).into_iter().nth(fx_index)
+    }
+
+    /// Plays one scripted event against the mock project. Everything but param writes counts as
+    /// a project edit, so the watchdog rescans on its next tick.
+    fn apply(&self, event: ScriptEvent) {
+        println!("script: {event:?}");
+        let now = self.now();
+        let edited = match event {
+            ScriptEvent::SetTempo { bpm } => {
+                if let Ok(mut tempo) = self.tempo.lock() {
+                    *tempo = bpm;
+                }
+                true
+            }
+            ScriptEvent::RenameTrack { track, name } => {
+                let Some(mut scenario) = self.scenario() else {
+                    return;
+                };
+                if let Some(t) = scenario.tracks.get_mut(track) {
+                    t.name = name;
+                }
+                true
+            }
+            ScriptEvent::AddFx { track, mut fx } => {
+                let Some(mut scenario) = self.scenario() else {
+                    return;
+                };
+                if let Some(t) = scenario.tracks.get_mut(track) {
+                    fx.added_at_ms = 0;
+                    fx.removed_at_ms = None;
+                    t.fx.push(fx);
+                }
+                true
+            }
+            ScriptEvent::RemoveFx { guid } => {
+                let Some(mut scenario) = self.scenario() else {
+                    return;
+                };
+                for t in scenario.tracks.iter_mut() {
+                    t.take(&guid, now);
+                }
+                true
+            }
+            ScriptEvent::MoveFx { guid, track } => {
+                let Some(mut scenario) = self.scenario() else {
+                    return;
+                };
+                if track < scenario.tracks.len() {
+                    let taken = scenario.tracks.iter_mut().find_map(|t| t.take(&guid, now));
+                    if let Some(mut fx) = taken {
+                        fx.added_at_ms = 0;
+                        fx.removed_at_ms = None;
+                        scenario.tracks[track].fx.push(fx);
+                    }
+                }
+                true
+            }
+            ScriptEvent::SetParam { guid, index, value } => {
+                if let Ok(mut params) = self.params.lock() {
+                    params.insert((guid, index), value.clamp(0.0, 1.0));
+                }
+                false
+            }
+            ScriptEvent::BreakParam { index } => {
+                if let Ok(mut fail) = self.fail_params.lock() {
+                    fail.insert(index);
+                }
+                false
+            }
+            ScriptEvent::FixParam { index } => {
+                if let Ok(mut fail) = self.fail_params.lock() {
+                    fail.remove(&index);
+                }
+                false
+            }
+            ScriptEvent::Quit => false,
+        };
+        if edited {
+            if let Ok(mut edits) = self.edits.lock() {
+                *edits += 1;
+            }
+        }
+    }
+}
+
+impl ReaperApi for MockReaperApi {
+    fn project_state_change_count(&self) -> i32 {
+        let changes = self.scenario().map_or(0, |s| s.changes_by(self.now()));
+        self.storms() + self.edits() + changes
+    }
+
+    fn project_state_change_count_in(&self, _project: usize) -> i32 {
+        self.project_state_change_count()
+    }
+
+    fn project_tempo(&self) -> Option<(f32, i32)> {
+        Some((*self.tempo.lock().ok()?, 4))
+    }
+
+    fn transport_play(&self) {}
+
+    fn transport_stop(&self) {}
+
+    fn transport_playing(&self) -> bool {
+        false
+    }
+
+    fn play_position(&self) -> Option<f64> {
+        None
+    }
+
+    fn loop_range(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    fn set_loop(&self, _start: f64, _end: f64) {}
+
+    fn repeat_enabled(&self) -> bool {
+        false
+    }
+
+    fn render_range(&self, _start: f64, _end: f64, _path: &std::path::Path) -> Result<(), String> {
+        Err("the mock sidecar can't render".to_string())
+    }
+
+    fn count_tracks(&self) -> i32 {
+        self.scenario().map_or(0, |s| s.tracks.len() as i32)
+    }
+
+    fn get_track(&self, index: i32) -> Option<usize> {
+        self.track_at(index)
+    }
+
+    fn enum_project(&self, _index: i32) -> Option<(usize, String)> {
+        None
+    }
+
+    fn current_project(&self) -> Option<(usize, String)> {
+        Some((1, "mock_project.rpp".to_string()))
+    }
+
+    fn count_tracks_in(&self, project: usize) -> i32 {
+        if project == 1 {
+            self.count_tracks()
+        } else {
+            0
+        }
+    }
+
+    fn get_track_in(&self, project: usize, index: i32) -> Option<usize> {
+        if project == 1 {
+            self.track_at(index)
+        } else {
+            None
+        }
+    }
+
+    fn selected_track(&self) -> Option<usize> {
+        self.track_at(0)
+    }
+
+    fn track_guid(&self, track: usize) -> Option<String> {
+        let index = track.checked_sub(TRACK_BASE)?;
+        self.scenario()?.tracks.get(index).map(|t| t.guid.clone())
+    }
+
+    fn track_name(&self, track: usize) -> String {
+        let Some(name) = track.checked_sub(TRACK_BASE).and_then(|i| {
+            let scenario = self.scenario()?;
+            scenario.tracks.get(i).map(|t| t.name.clone())
+        }) else {
+            return String::new();
+        };
+        if track == TRACK_BASE && self.storms() % 2 == 1 {
+            format!("{name} (storm)")
+        } else {
+            name
+        }
+    }
+
+    fn track_get_info(&self, track: usize, key: &str) -> Option<f64> {
+        self.track_guid(track)?;
+        let guard = self.track_info.lock().ok()?;
+        if let Some(value) = guard.get(&(track, key.to_string())) {
+            return Some(*value);
+        }
+        match key {
+            "D_VOL" | "I_RECMON" => Some(1.0),
+            "D_PAN" => Some(0.0),
+            _ => None,
+        }
+    }
+
+    fn track_set_info(&self, track: usize, key: &str, value: f64) -> bool {
+        let Ok(mut guard) = self.track_info.lock() else {
+            return false;
+        };
+        guard.insert((track, key.to_string()), value);
+        true
+    }
+
+    fn track_fx_count(&self, track: usize) -> i32 {
+        self.chain(track).len() as i32
+    }
+
+    fn track_fx_add_by_name(&self, _track: usize, _name: &str) -> Option<i32> {
+        None
+    }
+
+    fn track_fx_num_params(&self, _track: usize, _fx_index: i32) -> Option<i32> {
+        Some(self.layout.num_params())
+    }
+
+    fn track_fx_guid(&self, track: usize, fx_index: i32) -> Option<String> {
+        self.fx(track, fx_index).map(|f| f.guid.clone())
+    }
+
+    fn track_fx_name(&self, track: usize, fx_index: i32) -> String {
+        self.fx(track, fx_index)
+            .map(|f| f.name.clone())
+            .unwrap_or_default()
+    }
+
+    fn track_fx_ident(&self, track: usize, fx_index: i32) -> Option<String> {
+        self.fx(track, fx_index).map(|f| f.ident.clone())
+    }
+
+    fn track_fx_param_name(&self, track: usize, fx_index: i32, param_index: i32) -> Option<String> {
+        if self.fx(track, fx_index)?.charted() {
+            self.layout.name(param_index)
+        } else {
+            Some(format!("Param {}", param_index + 1))
+        }
+    }
+
+    fn track_fx_format_param_value(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        param_index: i32,
+        value: f64,
+    ) -> Option<String> {
+        self.layout.format(param_index, value)
+    }
+
+    fn track_fx_get_param(&self, track: usize, fx_index: i32, param_index: i32) -> Option<f64> {
+        let guid = self.track_fx_guid(track, fx_index)?;
+        let guard = self.params.lock().ok()?;
+        guard.get(&(guid, param_index)).copied()
+    }
+
+    fn track_fx_param_step_sizes(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        param_index: i32,
+    ) -> Option<(f64, bool)> {
+        self.layout.step_sizes(param_index)
+    }
+
+    fn track_fx_set_param(
+        &self,
+        track: usize,
+        fx_index: i32,
+        param_index: i32,
+        value: f64,
+    ) -> Result<(), String> {
+        if self
+            .fail_params
+            .lock()
+            .is_ok_and(|fail| fail.contains(&param_index))
+        {
+            return Err(format!("injected failure writing param {param_index}"));
+        }
+        let Some(guid) = self.track_fx_guid(track, fx_index) else {
+            return Err(format!("no fx at track {track} index {fx_index}"));
+        };
+        let Ok(mut guard) = self.params.lock() else {
+            return Err("mock lock poisoned".to_string());
+        };
+        guard.insert((guid, param_index), value);
+        Ok(())
+    }
+
+    fn track_fx_param_from_ident(&self, _track: usize, _fx_index: i32, ident: &str) -> Option<i32> {
+        // REAPER appends its own controls after the plugin's params.
+        let n = self.layout.num_params();
+        match ident {
+            ":bypass" => Some(n),
+            ":wet" => Some(n + 1),
+            ":delta" => Some(n + 2),
+            _ => None,
+        }
+    }
+
+    fn track_fx_get_enabled(&self, _track: usize, _fx_index: i32) -> bool {
+        true
+    }
+
+    fn track_fx_set_enabled(&self, _track: usize, _fx_index: i32, _enabled: bool) {}
+
+    // No undo history: undo_last_tone against the mock always reports "nothing to undo".
+    fn get_proj_ext_state(&self, _section: &str, _key: &str) -> Option<String> {
+        None
+    }
+
+    fn set_proj_ext_state(&self, _section: &str, _key: &str, _value: &str) {}
+
+    fn undo_begin_block(&self) {}
+
+    fn undo_end_block(&self, _desc: &str) {}
+
+    fn undo_next_desc(&self) -> Option<String> {
+        None
+    }
+
+    fn undo_do(&self) -> bool {
+        false
+    }
+
+    fn show_console_msg(&self, msg: &str) {
+        print!("{msg}");
+    }
+
+    fn track_fx_get_preset(&self, _track: usize, _fx_index: i32) -> Option<String> {
+        None
+    }
+
+    fn track_fx_set_preset(&self, _track: usize, _fx_index: i32, _name: &str) -> bool {
+        false
+    }
+}
+
+fn parse_arg_value(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Forwards the main loop's outbound messages to the network thread `latency` late, leaving out
+/// every `drop_every`th. At most `OUTBOUND_CAP` are held at once; past that the main loop's sends
+/// back up as they would on a slow socket. Ends once the main loop is gone and everything held
+/// back is out.
+fn relay<T: Send + 'static>(
+    from: Receiver<T>,
+    to: Sender<T>,
+    latency: Duration,
+    drop_every: Option<u64>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut held: VecDeque<(Instant, T)> = VecDeque::new();
+        let mut since_drop: u64 = 0;
+        let mut open = true;
+        while open || !held.is_empty() {
+            let wait = held
+                .front()
+                .map(|(due, _)| due.saturating_duration_since(Instant::now()))
+                .unwrap_or(Duration::from_millis(100));
+            if open && held.len() < reaper_gojira_dll::OUTBOUND_CAP {
+                match from.recv_timeout(wait) {
+                    Ok(msg) => {
+                        since_drop += 1;
+                        if Some(since_drop) == drop_every {
+                            since_drop = 0;
+                        } else {
+                            held.push_back((Instant::now() + latency, msg));
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => open = false,
+                }
+            } else {
+                thread::sleep(wait);
+            }
+            while held.front().is_some_and(|(due, _)| *due <= Instant::now()) {
+                let Some((_, msg)) = held.pop_front() else {
+                    break;
+                };
+                if to.send(msg).is_err() {
+                    return;
+                }
+            }
+        }
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{USAGE}");
+        return;
+    }
+
+    let addr = parse_arg_value(&args, "--addr")
+        .or_else(|| std::env::var("GOJIRA_WS_ADDR").ok())
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+
+    let addr_file = parse_arg_value(&args, "--addr-file").map(PathBuf::from);
+    let run_for_ms = parse_arg_value(&args, "--run-for-ms")
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(Duration::from_millis);
+    let faults = Faults::from_args(&args);
+    let scenario = match parse_arg_value(&args, "--scenario") {
+        Some(path) => match Scenario::load(Path::new(&path)) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => Scenario::single(),
+    };
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = match NetworkThread::spawn_with_addr(&addr, in_tx, out_rx) {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(path) = &addr_file {
+        let _ = fs::write(path, net.endpoint().to_string());
+    }
+
+    println!("mock_sidecar listening on ws://{}", net.endpoint());
+
+    let mut script = match parse_arg_value(&args, "--script") {
+        Some(path) => match Script::load(Path::new(&path)) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let api = MockReaperApi::new(scenario, faults.fail_params.clone());
+    let (loop_tx, relay) = if faults.delays_outbound() {
+        let (loop_tx, loop_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+        let handle = relay(loop_rx, out_tx, faults.latency, faults.drop_every);
+        (loop_tx, Some(handle))
+    } else {
+        (out_tx, None)
+    };
+    let mut main_loop = MainLoop::new(in_rx, loop_tx);
+
+    let start = Instant::now();
+    let mut last_storm = start;
+    let mut reason = "run_for_ms elapsed";
+    loop {
+        if let Some(period) = faults.project_storm {
+            if last_storm.elapsed() >= period {
+                last_storm = Instant::now();
+                api.storm_step();
+            }
+        }
+        api.advance(start.elapsed());
+        let due = script
+            .as_mut()
+            .map(|s| s.due(start.elapsed()))
+            .unwrap_or_default();
+        let quit = due.iter().any(|e| matches!(e, ScriptEvent::Quit));
+        for event in due {
+            api.apply(event);
+        }
+        if quit {
+            reason = "script finished";
+            break;
+        }
+        main_loop.tick(&api);
+        thread::sleep(Duration::from_millis(33));
+        if let Some(max) = run_for_ms {
+            if start.elapsed() >= max {
+                break;
+            }
+        }
+    }
+
+    main_loop.announce_shutdown(reason, false);
+    // The relay only lets go once the main loop's sender is gone and the shutdown notice is out.
+    drop(main_loop);
+    if let Some(relay) = relay {
+        let _ = relay.join();
+    }
+    net.shutdown();
+}
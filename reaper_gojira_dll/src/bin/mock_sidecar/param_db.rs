@@ -0,0 +1,105 @@
+//! The Gojira's parameter layout as JSON, for mocks that should answer like the plugin rather
+//! than with `param_N` stubs: the charted names at their indices, how each param formats its
+//! values, and the labels of its selectors. Shared by the mock sidecar and the integration tests.
+
+use serde::Deserialize;
+
+const GOJIRA_PARAMS: &str = include_str!("gojira_params.json");
+
+#[derive(Deserialize)]
+pub struct ParamDb {
+    params: Vec<ParamDef>,
+}
+
+#[derive(Deserialize)]
+struct ParamDef {
+    index: i32,
+    name: String,
+    format: Format,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Format {
+    /// `min..max` evenly over the knob's travel.
+    Linear {
+        min: f64,
+        max: f64,
+        #[serde(default)]
+        unit: String,
+        #[serde(default)]
+        decimals: usize,
+    },
+    /// `min..max` geometrically, like frequency and time knobs.
+    Log {
+        min: f64,
+        max: f64,
+        #[serde(default)]
+        unit: String,
+        #[serde(default)]
+        decimals: usize,
+    },
+    /// Equal segments, one per label, like switches and selectors.
+    Steps { labels: Vec<String> },
+}
+
+impl ParamDb {
+    pub fn gojira() -> Self {
+        serde_json::from_str(GOJIRA_PARAMS).expect("bundled param layout should parse")
+    }
+
+    pub fn num_params(&self) -> i32 {
+        self.params.len() as i32
+    }
+
+    pub fn name(&self, index: i32) -> Option<String> {
+        self.get(index).map(|p| p.name.clone())
+    }
+
+    /// What the plugin shows for `norm`, e.g. "-3.5 dB" or "Hall".
+    pub fn format(&self, index: i32, norm: f64) -> Option<String> {
+        let norm = norm.clamp(0.0, 1.0);
+        let text = match &self.get(index)?.format {
+            Format::Linear {
+                min,
+                max,
+                unit,
+                decimals,
+            } => with_unit(min + norm * (max - min), *decimals, unit),
+            Format::Log {
+                min,
+                max,
+                unit,
+                decimals,
+            } => with_unit(min * (max / min).powf(norm), *decimals, unit),
+            Format::Steps { labels } => {
+                let step = ((norm * labels.len() as f64) as usize).min(labels.len() - 1);
+                labels[step].clone()
+            }
+        };
+        Some(text)
+    }
+
+    /// Step size and toggle flag, as REAPER reports them for the plugin's switches and
+    /// selectors; `None` for continuous params.
+    pub fn step_sizes(&self, index: i32) -> Option<(f64, bool)> {
+        match &self.get(index)?.format {
+            Format::Steps { labels } if labels.len() > 1 => {
+                Some((1.0 / (labels.len() - 1) as f64, labels.len() == 2))
+            }
+            _ => None,
+        }
+    }
+
+    fn get(&self, index: i32) -> Option<&ParamDef> {
+        self.params.iter().find(|p| p.index == index)
+    }
+}
+
+fn with_unit(value: f64, decimals: usize, unit: &str) -> String {
+    if unit.is_empty() {
+        format!("{value:.decimals$}")
+    } else {
+        format!("{value:.decimals$} {unit}")
+    }
+}
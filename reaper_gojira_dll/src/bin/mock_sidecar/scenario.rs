@@ -0,0 +1,122 @@
+//! The tracks and FX the mock sidecar pretends to have, from `--scenario <file.json>`: several
+//! tracks, several instances with their own GUIDs, low-confidence matches and non-Gojira FX
+//! alongside, and instances that come and go mid-run, so resolver edge cases and instance
+//! pickers can be exercised without REAPER.
+
+use gojira_protocol::Confidence;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_FX_NAME: &str = "Neural DSP: Archetype Gojira (Mock)";
+const DEFAULT_FX_IDENT: &str = "Archetype Gojira (Mock).vst3";
+
+#[derive(Deserialize)]
+pub struct Scenario {
+    pub tracks: Vec<ScenarioTrack>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenarioTrack {
+    pub name: String,
+    pub guid: String,
+    #[serde(default)]
+    pub fx: Vec<ScenarioFx>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScenarioFx {
+    pub guid: String,
+    #[serde(default = "default_fx_name")]
+    pub name: String,
+    #[serde(default = "default_fx_ident")]
+    pub ident: String,
+    /// `low` reports param names off the charted layout, which is what drops a match whose
+    /// FX name says Gojira to low confidence.
+    #[serde(default)]
+    pub confidence: Option<Confidence>,
+    /// When the FX shows up on its track; before that it isn't in the chain.
+    #[serde(default)]
+    pub added_at_ms: u64,
+    /// When the FX leaves its track again, if it does.
+    #[serde(default)]
+    pub removed_at_ms: Option<u64>,
+}
+
+fn default_fx_name() -> String {
+    DEFAULT_FX_NAME.to_string()
+}
+
+fn default_fx_ident() -> String {
+    DEFAULT_FX_IDENT.to_string()
+}
+
+impl Scenario {
+    /// One track with one Gojira on it, for when no scenario is given.
+    pub fn single() -> Self {
+        Self {
+            tracks: vec![ScenarioTrack {
+                name: "Mock Track".to_string(),
+                guid: "{MOCK-TRACK-GUID}".to_string(),
+                fx: vec![ScenarioFx {
+                    guid: "{MOCK-FX-GUID}".to_string(),
+                    name: default_fx_name(),
+                    ident: default_fx_ident(),
+                    confidence: None,
+                    added_at_ms: 0,
+                    removed_at_ms: None,
+                }],
+            }],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read scenario {}: {e}", path.display()))?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("failed to parse scenario {}: {e}", path.display()))
+    }
+
+    /// How many FX have been added or removed by `at`; a change here is a project change.
+    pub fn changes_by(&self, at: Duration) -> i32 {
+        let at = at.as_millis() as u64;
+        let fx = self.tracks.iter().flat_map(|t| &t.fx);
+        fx.map(|f| {
+            i32::from(f.added_at_ms > 0 && f.added_at_ms <= at)
+                + i32::from(f.removed_at_ms.is_some_and(|r| r <= at))
+        })
+        .sum()
+    }
+}
+
+impl ScenarioTrack {
+    /// The FX in the chain at `at`, in chain order.
+    pub fn chain(&self, at: Duration) -> Vec<&ScenarioFx> {
+        let at = at.as_millis() as u64;
+        self.fx.iter().filter(|f| f.in_chain(at)).collect()
+    }
+
+    /// Takes the FX with `guid` out of the chain at `at`, returning it as it was. It stays
+    /// listed as removed, so `changes_by` never runs backwards.
+    pub fn take(&mut self, guid: &str, at: Duration) -> Option<ScenarioFx> {
+        let ms = at.as_millis() as u64;
+        let fx = self
+            .fx
+            .iter_mut()
+            .find(|f| f.guid == guid && f.in_chain(ms))?;
+        let taken = fx.clone();
+        fx.removed_at_ms = Some(ms);
+        Some(taken)
+    }
+}
+
+impl ScenarioFx {
+    pub fn charted(&self) -> bool {
+        !matches!(self.confidence, Some(Confidence::Low))
+    }
+
+    fn in_chain(&self, at: u64) -> bool {
+        self.added_at_ms <= at && !self.removed_at_ms.is_some_and(|r| r <= at)
+    }
+}
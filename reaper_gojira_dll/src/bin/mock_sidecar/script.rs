@@ -0,0 +1,89 @@
+//! A timeline for the mock sidecar to play back, from `--script <file.json>`: a list of steps,
+//! each waiting `delay_ms` after the one before and then changing the mock project the way a
+//! user in REAPER would. The DLL sees those changes the way it would see the real ones, so an
+//! end-to-end test of a client's reconciliation can count on the same messages every run.
+//!
+//! ```json
+//! [
+//!   { "delay_ms": 2000, "event": "set_tempo", "bpm": 140 },
+//!   { "delay_ms": 500, "event": "rename_track", "track": 0, "name": "Lead" },
+//!   { "delay_ms": 0, "event": "break_param", "index": 105 },
+//!   { "delay_ms": 3000, "event": "quit" }
+//! ]
+//! ```
+
+use crate::scenario::ScenarioFx;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct Step {
+    #[serde(default)]
+    delay_ms: u64,
+    #[serde(flatten)]
+    event: ScriptEvent,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ScriptEvent {
+    /// Changes the project tempo, which the watchdog reports as a ProjectChanged.
+    SetTempo { bpm: f32 },
+    /// Renames the track at `track` in the scenario; its instances come back renamed.
+    RenameTrack { track: usize, name: String },
+    /// Appends an FX to the end of the track's chain.
+    AddFx { track: usize, fx: ScenarioFx },
+    /// Takes the FX with `guid` out of its chain.
+    RemoveFx { guid: String },
+    /// Moves the FX with `guid` to the end of another track's chain, for the resolver to find.
+    MoveFx { guid: String, track: usize },
+    /// Sets a param from the plugin's side, like a knob turned in its window.
+    SetParam {
+        guid: String,
+        index: i32,
+        value: f64,
+    },
+    /// Makes writes to the param fail from now on.
+    BreakParam { index: i32 },
+    /// Lets writes to the param through again.
+    FixParam { index: i32 },
+    /// Announces a shutdown and exits.
+    Quit,
+}
+
+pub struct Script {
+    /// Events with the run time they are due at.
+    pending: VecDeque<(Duration, ScriptEvent)>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read script {}: {e}", path.display()))?;
+        let steps: Vec<Step> = serde_json::from_str(&text)
+            .map_err(|e| format!("failed to parse script {}: {e}", path.display()))?;
+        let mut at = Duration::ZERO;
+        let pending = steps
+            .into_iter()
+            .map(|step| {
+                at += Duration::from_millis(step.delay_ms);
+                (at, step.event)
+            })
+            .collect();
+        Ok(Self { pending })
+    }
+
+    /// The events due by `at`, in script order.
+    pub fn due(&mut self, at: Duration) -> Vec<ScriptEvent> {
+        let mut events = Vec::new();
+        while self.pending.front().is_some_and(|(due, _)| *due <= at) {
+            if let Some((_, event)) = self.pending.pop_front() {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
@@ -0,0 +1,25 @@
+use std::time::{Duration, Instant};
+
+/// How long one timer tick may run before the main loop leaves the rest of its work for the
+/// next one. REAPER runs the timer on its main thread, so a long tick stalls the UI and can
+/// starve the audio thread of the locks it needs.
+pub const DEFAULT_TICK_BUDGET: Duration = Duration::from_millis(8);
+
+/// Time left in the current tick. Sliced work checks it between steps and resumes on the next
+/// tick, always taking at least one step so a spent budget slows work down but never stalls it.
+#[derive(Clone, Copy, Debug)]
+pub struct TickBudget {
+    deadline: Instant,
+}
+
+impl TickBudget {
+    pub fn start(limit: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + limit,
+        }
+    }
+
+    pub fn exhausted(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
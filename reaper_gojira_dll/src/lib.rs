@@ -1,25 +1,41 @@
+mod actions;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod budget;
 mod main_loop;
+#[cfg(feature = "mdns")]
+mod mdns;
+mod meta_cache;
+mod metrics;
+mod midi;
 mod net;
+mod osc;
+mod presets;
+mod profiles;
 mod protocol;
 mod reaper_api;
 mod resolver;
+mod telemetry;
+#[cfg(feature = "tls")]
+mod tls;
+mod tone_slots;
 mod validator;
 
+pub use crate::actions::ReaperAction;
 pub use crate::main_loop::MainLoop;
+pub use crate::midi::MidiMap;
 pub use crate::net::NetworkThread;
 pub use crate::reaper_api::ReaperApi;
 pub use crate::protocol::{INBOUND_CAP, OUTBOUND_CAP};
 
-use crate::protocol::{OutboundMsg, ServerMessage};
 use crate::reaper_api::ReaperApiImpl;
 use c_str_macro::c_str;
 use crossbeam_channel::bounded;
 use reaper_low::raw::{HINSTANCE, reaper_plugin_info_t};
 use reaper_low::{Reaper, ReaperPluginContext};
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::os::raw::c_void;
+use std::os::raw::{c_int, c_void};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, OnceLock};
 
@@ -28,14 +44,6 @@ static MAIN_LOOP: OnceLock<Mutex<MainLoop>> = OnceLock::new();
 static NET_THREAD: OnceLock<NetworkThread> = OnceLock::new();
 static SHUTDOWN: AtomicBool = AtomicBool::new(false);
 
-fn log_line(msg: &str) {
-    let path = std::env::temp_dir().join("reaper_gojira_dll.log");
-    if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
-        let _ = writeln!(f, "{}", msg);
-        let _ = f.flush();
-    }
-}
-
 fn env_is_true(name: &str) -> bool {
     matches!(
         std::env::var(name).as_deref(),
@@ -62,33 +70,98 @@ extern "C" fn timer_proc() {
     });
 }
 
+/// REAPER calls this for every command it runs; ours go to the main loop.
+extern "C" fn hook_command(command: c_int, _flag: c_int) -> bool {
+    let Some(action) = actions::lookup(command) else {
+        return false;
+    };
+    let _ = reaper_low::firewall(|| {
+        let Some(reaper) = REAPER.get().copied() else {
+            return;
+        };
+        let Some(main_loop) = MAIN_LOOP.get() else {
+            return;
+        };
+        // A command the tick itself runs (render preview) must not wait on the tick's lock.
+        let Ok(mut main_loop) = main_loop.try_lock() else {
+            return;
+        };
+        let api = ReaperApiImpl::new(reaper);
+        main_loop.run_action(&api, action);
+    });
+    true
+}
+
 fn init(context: &ReaperPluginContext) -> Result<(), Box<dyn Error>> {
-    log_line("init: start");
+    telemetry::info("init", "start");
     let reaper = Reaper::load(context);
     let _ = REAPER.set(reaper);
-    log_line("init: reaper loaded");
+    telemetry::info("init", "reaper loaded");
 
     if env_is_true("GOJIRA_DLL_DISABLE_ALL") {
-        log_line("init: GOJIRA_DLL_DISABLE_ALL=1 -> returning early");
+        telemetry::info("init", "GOJIRA_DLL_DISABLE_ALL=1 -> returning early");
         return Ok(());
     }
 
     let (in_tx, in_rx) = bounded(protocol::INBOUND_CAP);
     let (out_tx, out_rx) = bounded(protocol::OUTBOUND_CAP);
-    log_line("init: channels created");
+    telemetry::info("init", "channels created");
 
+    let mut listen_addr = None;
+    let mut tls_fingerprint = None;
     if !env_is_true("GOJIRA_DLL_DISABLE_NET") {
-        log_line("init: spawning net thread");
+        telemetry::info("init", "spawning net thread");
         let net = NetworkThread::spawn(in_tx, out_rx)?;
+        let addr = net.endpoint().to_string();
+        listen_addr = Some(addr.clone());
+        tls_fingerprint = net.tls_fingerprint().map(str::to_string);
+        if let Some(osc) = net.osc_addr() {
+            telemetry::info("init", format!("taking OSC on {osc}"));
+        }
         let _ = NET_THREAD.set(net);
-        log_line("init: net thread ready");
+        telemetry::info("init", format!("net thread ready on {addr}"));
+        if let Some(fp) = tls_fingerprint.as_deref() {
+            telemetry::info("init", format!("serving wss, certificate fingerprint {fp}"));
+        }
     } else {
-        log_line("init: GOJIRA_DLL_DISABLE_NET=1 -> net thread skipped");
+        telemetry::info("init", "GOJIRA_DLL_DISABLE_NET=1 -> net thread skipped");
     }
 
-    let main_loop = MainLoop::new(in_rx, out_tx);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    if let Some(addr) = listen_addr {
+        main_loop.set_server_addr(addr);
+    }
+    if let Some(fp) = tls_fingerprint {
+        main_loop.set_tls_fingerprint(fp);
+    }
+    if let Some(dir) = presets::default_dir() {
+        telemetry::info("init", format!("presets folder {}", dir.display()));
+        main_loop.set_preset_dir(dir);
+    }
+    let resource_dir = unsafe { std::ffi::CStr::from_ptr(reaper.GetResourcePath()) };
+    let meta_cache = meta_cache::default_path(&resource_dir.to_string_lossy());
+    telemetry::info("init", format!("param meta cache {}", meta_cache.display()));
+    main_loop.set_meta_cache(meta_cache);
+    if let Ok(config) = net::load_sidecar_config() {
+        if let Some(rate) = config.max_apply_rate() {
+            telemetry::info("init", format!("applying at most {rate} writes/s"));
+            main_loop.set_max_apply_rate(rate);
+        }
+        if let Some(budget) = config.tick_budget() {
+            telemetry::info("init", format!("tick budget {}ms", budget.as_millis()));
+            main_loop.set_tick_budget(budget);
+        }
+        if let Some(device) = config.midi_input {
+            telemetry::info("init", format!("tone slots follow MIDI input {device}"));
+            let map = MidiMap {
+                channel: config.midi_channel,
+                slot_cc: config.midi_slot_cc,
+            };
+            main_loop.set_midi_input(midi::listen(reaper, device), map);
+        }
+    }
     let _ = MAIN_LOOP.set(Mutex::new(main_loop));
-    log_line("init: main loop set");
+    telemetry::info("init", "main loop set");
 
     if !env_is_true("GOJIRA_DLL_DISABLE_TIMER") {
         unsafe {
@@ -96,21 +169,21 @@ fn init(context: &ReaperPluginContext) -> Result<(), Box<dyn Error>> {
             let timer_ptr = timer_proc as usize as *mut c_void;
             reaper.plugin_register(c_str!("timer").as_ptr(), timer_ptr);
         }
-        log_line("init: timer registered");
+        telemetry::info("init", "timer registered");
     } else {
-        log_line("init: GOJIRA_DLL_DISABLE_TIMER=1 -> timer skipped");
+        telemetry::info("init", "GOJIRA_DLL_DISABLE_TIMER=1 -> timer skipped");
     }
 
-    log_line("init: done");
+    actions::register(reaper, hook_command);
+    telemetry::info("init", "actions registered");
+
+    telemetry::info("init", "done");
     Ok(())
 }
 
 fn shutdown() {
     SHUTDOWN.store(true, Ordering::Relaxed);
-
-    if let Some(net) = NET_THREAD.get() {
-        net.shutdown();
-    }
+    telemetry::info("shutdown", "REAPER is unloading the DLL");
 
     if let Some(reaper) = REAPER.get().copied() {
         unsafe {
@@ -118,19 +191,21 @@ fn shutdown() {
             let timer_ptr = timer_proc as usize as *mut c_void;
             let _ = reaper.plugin_register(c_str!("-timer").as_ptr(), timer_ptr);
         }
+        midi::stop(reaper);
+        actions::unregister(reaper, hook_command);
     }
 
+    // REAPER only unloads extensions on exit, so nobody should expect it back soon.
     if let Some(main_loop) = MAIN_LOOP.get() {
         if let Ok(mut loop_guard) = main_loop.lock() {
-            // Best-effort: send a final "server shutting down" error (will be dropped if no client).
-            loop_guard.try_send(OutboundMsg::Send {
-                msg: ServerMessage::Error {
-                    msg: "server shutting down".to_string(),
-                    code: protocol::ErrorCode::InternalError,
-                },
-            });
+            loop_guard.announce_shutdown("reaper_exiting", false);
         }
     }
+
+    // Stopping the thread flushes the notice above before the sockets close.
+    if let Some(net) = NET_THREAD.get() {
+        net.shutdown();
+    }
 }
 
 #[no_mangle]
@@ -1,34 +1,350 @@
+use crate::actions::ReaperAction;
+use crate::budget::{TickBudget, DEFAULT_TICK_BUDGET};
+use crate::meta_cache::{self, CachedMeta, MetaCache};
+use crate::metrics::Metrics;
+use crate::midi::MidiMap;
+use crate::presets::{self, PresetStore, StoredPreset};
+use crate::profiles;
 use crate::protocol::{
-    AppliedParam, ClientCommand, ErrorCode, InboundMsg, MergeMode, OutboundMsg, ParamChange,
-    ServerMessage,
+    AppliedParam, ClientCommand, ClientId, ErrorCode, ErrorDiagnostic, FailedParam, GojiraInstance,
+    HandshakeMeta, InboundMsg, MergeMode, ModuleId, OutboundMsg, ParamChange, ParamCheck,
+    ParamEnumOption, ParamKind, ParamOp, ParamReading, PreviewRow, ProjectTempo, Remediation,
+    ReplayedEvent, ResolverState, ServerMessage, SkippedModule, TargetResult, ToneTarget,
+    TransportAnchor, WarningCode, CAPABILITIES, MAX_PARAM_INDEX, RESUME_GRACE_SECS,
 };
 use crate::reaper_api::ReaperApi;
-use crate::resolver::{self, FxLookup};
+use crate::resolver::{self, FxLookup, FxMove, ResolveError, Resolved, ScanCursor, ScanMemo};
+use crate::telemetry;
+use crate::tone_slots::{self, ToneSlot, ToneSlots};
 use crate::validator;
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
-use std::collections::{HashMap, HashSet};
+use gojira_param_catalog::ModuleSpec;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 const PROJECT_CHANGED_DEBOUNCE: Duration = Duration::from_millis(500);
-const MAX_PARAM_INDEX: i32 = 4096;
+const PARAM_DUMP_RADIUS: i32 = 6;
+/// Name of the undo point each SetTone creates; `UndoLastTone` only reverts points named this.
+const TONE_UNDO_DESC: &str = "Gojira: apply tone";
+/// Pre-apply snapshots kept for `RestoreSnapshot`; the oldest is dropped first.
+const MAX_SNAPSHOTS: usize = 32;
+/// Subscribed params are polled at most this often, however fast the timer ticks.
+const PARAM_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Smaller moves than this are not reported as a change.
+const PARAM_CHANGE_EPSILON: f64 = 1e-6;
+/// Longest glide a SetTone may ask for.
+const MAX_RAMP_MS: u32 = 5000;
+/// How far an atomic SetTone may read back from a continuous param's requested value before the
+/// whole tone is rolled back.
+const ATOMIC_READBACK_TOLERANCE: f64 = 0.01;
+/// Param indices probed and sent per tick while param meta streams after a handshake.
+const PARAM_META_CHUNK: i32 = 16;
+/// Options per enum the handshake and `ParamMeta` carry; `SearchEnumLabels` pages the rest.
+const ENUM_PAGE_SIZE: usize = 64;
+/// Most options one `SearchEnumLabels` reply lists.
+const MAX_ENUM_PAGE: usize = 512;
+/// Params a sliced GetCurrentTone reads between budget checks.
+const TONE_READ_CHUNK: i32 = 16;
+/// Commands waiting in the apply queue beyond this are turned away with `Busy`.
+const APPLY_QUEUE_CAP: usize = 64;
+/// SetTones waiting on the transport beyond this are turned away with `Busy`.
+const MAX_SCHEDULED_TONES: usize = 16;
+/// SetTrackControls volume range; the floor stands for silence.
+const MIN_TRACK_VOLUME_DB: f32 = -150.0;
+const MAX_TRACK_VOLUME_DB: f32 = 12.0;
+
+/// Project ext-state section the last applied tone of each FX is saved under, keyed by FX guid,
+/// so it survives a REAPER restart along with the project.
+const LAST_TONE_EXT_SECTION: &str = "gojira_last_tone";
+
+const DEFAULT_PREVIEW_SECS: f64 = 5.0;
+const MAX_PREVIEW_SECS: f64 = 30.0;
+
+const INSERT_FX_UNDO_DESC: &str = "Gojira: insert plugin";
+/// Sender of commands started inside REAPER (MIDI, the action list); net thread ids start at 1,
+/// so replies to it go nowhere.
+const LOCAL_CLIENT: ClientId = 0;
+/// What `InsertFx` adds without an `fx_name`; REAPER matches it against the FX browser names.
+const DEFAULT_INSERT_FX_NAME: &str = "Archetype Gojira";
+/// Messages kept for the next client while none is connected; the oldest is dropped first.
+const REPLAY_CAP: usize = 64;
 
 pub struct MainLoop {
     inbound_rx: Receiver<InboundMsg>,
     outbound_tx: Sender<OutboundMsg>,
     cache: GojiraCache,
 
-    active_session_token: Option<String>,
+    /// Session token of every connected client.
+    sessions: HashMap<ClientId, String>,
+    /// What dropped connections left behind, by their token, for a `Resume`.
+    parked: HashMap<String, ParkedSession>,
+    validation_ready: bool,
+    last_validation_report: Vec<ParamCheck>,
+    metrics: Metrics,
+    server_addr: Option<String>,
+    tls_fingerprint: Option<String>,
+    /// Where each connected client came from, for the status readout.
+    peers: HashMap<ClientId, String>,
+    /// The latest command from anyone, and the latest error sent back, for the status readout.
+    last_command: Option<StatusEvent>,
+    last_error: Option<StatusEvent>,
+    presets: Option<PresetStore>,
+    /// Folder rendered previews are written to.
+    render_dir: PathBuf,
+    snapshots: VecDeque<ToneSnapshot>,
+    /// Every param written to each FX (by guid) and the value it read back, as saved in the
+    /// project; reported with `current_tone`.
+    last_applied: HashMap<String, BTreeMap<i32, f64>>,
+    /// Params each client subscribed to, per FX guid.
+    subscriptions: HashMap<ClientId, HashMap<String, HashSet<i32>>>,
+    /// Last polled value of every subscribed param, per FX guid.
+    watched_values: HashMap<String, HashMap<i32, f64>>,
+    last_param_poll: Instant,
+    /// From the last probe; only `Continuous` params glide.
+    param_kinds: HashMap<i32, ParamKind>,
+    /// Enum options from the last probe, as far as they have been streamed.
+    param_enums: HashMap<i32, Vec<ParamEnumOption>>,
+    ramps: Vec<Ramp>,
+    meta_stream: Option<MetaStream>,
+    meta_cache: Option<MetaCache>,
+    /// Cache key of the plugin build the last handshake probed.
+    meta_key: Option<String>,
+    /// What each instance was at the last handshake, by FX guid; one found since is taken as it
+    /// is on its first tone.
+    handshake_layouts: HashMap<String, FxLayout>,
+    /// Tone commands in arrival order, run by `drain_apply_queue`.
+    apply_queue: VecDeque<(ClientId, ClientCommand)>,
+    /// From the max apply rate; `None` runs every queued write on the next tick.
+    min_apply_interval: Option<Duration>,
+    last_apply: Option<Instant>,
+    /// Time one tick may take before scans, param meta and tone reads carry on in the next one.
+    tick_budget: Duration,
+    /// Watchdog rescans still in progress, by project, with the change count they started at.
+    rescans: HashMap<usize, (i32, ScanCursor)>,
+    /// A GetCurrentTone that didn't fit in its tick; the apply queue waits behind it.
+    tone_read: Option<ToneRead>,
+    /// SetTones waiting on the transport, in arrival order.
+    scheduled: Vec<ScheduledTone>,
+    tone_slots: ToneSlots,
+    /// Program changes and CCs from the MIDI input, when one is configured.
+    midi: Option<(Receiver<[u8; 3]>, MidiMap)>,
+    /// What went out while no client was connected, with when; the next one in gets it as
+    /// `Replay`.
+    replay: VecDeque<(Instant, ServerMessage)>,
+}
+
+/// Something the status readout reports with how long ago it happened.
+struct StatusEvent {
+    what: String,
+    at: Instant,
+}
+
+impl StatusEvent {
+    fn now(what: String) -> Self {
+        Self {
+            what,
+            at: Instant::now(),
+        }
+    }
+}
+
+/// A dropped connection's session, kept for `RESUME_GRACE_SECS` in case it comes back.
+struct ParkedSession {
+    at: Instant,
+    subscriptions: Option<HashMap<String, HashSet<i32>>>,
+    /// Whether the probe results held when it dropped; the last one out clears the flag.
     validation_ready: bool,
-    last_validation_report: HashMap<String, String>,
+}
+
+/// A SetTone held back by its `apply_at` until the transport gets there.
+struct ScheduledTone {
+    client: ClientId,
+    due: Due,
+    /// Play position at the last check, to tell when playback jumped back.
+    last_position: Option<f64>,
+    /// The SetTone itself, with `apply_at` taken out.
+    cmd: ClientCommand,
+}
+
+enum Due {
+    /// Reaching this position, the transport stopping, or playback jumping back (a loop wrap
+    /// crosses a bar line too).
+    Bar(f64),
+    /// Playback at or past this position.
+    Position(f64),
+    Stop,
+}
+
+/// A GetCurrentTone read partway through, picked up again on the next tick.
+struct ToneRead {
+    client: ClientId,
+    command_id: String,
+    target_fx_guid: String,
+    change_count: i32,
+    last_index: i32,
+    next_index: i32,
+    params: Vec<ParamReading>,
+}
+
+/// Param meta still to be probed for clients that already have their handshake.
+struct MetaStream {
+    fx_guid: String,
+    plan: validator::MetaPlan,
+    next_index: i32,
+    recipients: Vec<ClientId>,
+    /// Everything sent so far, cached under `key` once the last chunk is out.
+    collected: CachedMeta,
+    key: String,
+}
+
+/// A SetTone whose continuous params are gliding toward their targets, one step per tick.
+struct Ramp {
+    client: ClientId,
+    command_id: String,
+    target_fx_guid: String,
+    started: Instant,
+    duration: Duration,
+    /// Param index, value when the glide started, target value.
+    glides: Vec<(i32, f64, f64)>,
+    /// Every param of the SetTone, written for good (as one undo point) when the glide ends.
+    params: Vec<ParamChange>,
+    skipped_modules: Vec<SkippedModule>,
+}
+
+/// The FX values right before a SetTone wrote to them, keyed by that SetTone's command id.
+struct ToneSnapshot {
+    id: String,
+    /// One entry per FX the SetTone touched: guid and its tone params.
+    targets: Vec<(String, Vec<ParamChange>)>,
+}
+
+/// A SetTone target that resolved and passed validation, ready to write.
+struct PreparedTone {
+    target_fx_guid: String,
+    track: usize,
+    fx_index: i32,
+    params: Vec<ParamChange>,
+    /// Modules a merge left alone, reported in the ack.
+    skipped_modules: Vec<SkippedModule>,
+}
+
+/// The plugin behind an FX guid, as far as tone indices depend on it.
+#[derive(Clone, PartialEq)]
+struct FxLayout {
+    profile: &'static str,
+    num_params: Option<i32>,
+}
+
+impl FxLayout {
+    fn read(api: &dyn ReaperApi, track: usize, fx_index: i32) -> Self {
+        Self {
+            profile: profiles::for_fx(api, track, fx_index).id,
+            num_params: api.track_fx_num_params(track, fx_index),
+        }
+    }
+
+    /// What differs in `now`, for the error details.
+    fn changes(&self, now: &Self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.profile != now.profile {
+            changes.push(format!("profile {} -> {}", self.profile, now.profile));
+        }
+        if self.num_params != now.num_params {
+            let count = |n: Option<i32>| n.map_or("unknown".to_string(), |n| n.to_string());
+            changes.push(format!(
+                "param count {} -> {}",
+                count(self.num_params),
+                count(now.num_params)
+            ));
+        }
+        changes
+    }
+}
+
+/// Why a SetTone target was rejected; sent as-is for a single target.
+struct ToneError {
+    msg: String,
+    code: ErrorCode,
+    details: Option<String>,
+    diagnostic: Option<ErrorDiagnostic>,
+}
+
+impl ToneError {
+    fn into_message(self, command_id: &str) -> ServerMessage {
+        ServerMessage::Error {
+            msg: self.msg,
+            code: self.code,
+            details: self.details,
+            command_id: Some(command_id.to_string()),
+            diagnostic: self.diagnostic,
+        }
+    }
+}
+
+/// Last full read of an instance's tone params; valid while the project change count it was read
+/// at still holds.
+pub struct CachedTone {
+    pub change_count: i32,
+    pub params: Vec<ParamReading>,
+}
+
+pub struct WatchedProject {
+    pub change_count: i32,
+    /// File path as REAPER reports it; empty while unsaved.
+    pub path: String,
+    pub instances: Vec<GojiraInstance>,
+    /// Per-track fingerprints from the last scan, so a rescan skips unchanged tracks.
+    pub memo: ScanMemo,
+}
+
+/// What one project's ProjectChanged reports.
+struct ProjectDelta {
+    project_name: Option<String>,
+    added: Vec<GojiraInstance>,
+    removed: Vec<GojiraInstance>,
+    renamed: Vec<GojiraInstance>,
 }
 
 pub struct GojiraCache {
     pub lookup: FxLookup,
+    /// Tone params per FX guid, read on demand. Dropped whenever the DLL itself writes to an FX,
+    /// since writes outside an undo block (glide steps) don't move the project change count.
+    pub tones: HashMap<String, CachedTone>,
 
-    pub last_project_change_count: i32,
+    /// Instances as of the last scan, current project first; ProjectChanged reports the
+    /// difference to the next one.
+    pub last_instances: Vec<GojiraInstance>,
+
+    /// Every watched project as of its last scan, by project pointer. One that isn't here yet (a
+    /// tab just switched to or opened) gets scanned on the next watchdog pass.
+    pub projects: HashMap<usize, WatchedProject>,
     pub last_broadcast_time: Instant,
-    pub last_track_count: i32,
-    pub last_total_fx_count: i32,
+    pub last_project_tempo: Option<ProjectTempo>,
+    /// Moves `resolve` ran into since the last tick, for `publish_moves`.
+    pub moves: Vec<FxMove>,
+}
+
+impl GojiraCache {
+    pub fn resolve(
+        &mut self,
+        api: &dyn ReaperApi,
+        fx_guid: &str,
+    ) -> Result<(usize, i32), ResolveError> {
+        self.resolve_in(api, fx_guid, false)
+            .map(|r| (r.track, r.fx_index))
+    }
+
+    /// `resolve`, letting the target be in a background project tab with `background`.
+    pub fn resolve_in(
+        &mut self,
+        api: &dyn ReaperApi,
+        fx_guid: &str,
+        background: bool,
+    ) -> Result<Resolved, ResolveError> {
+        resolver::resolve_fx(api, &mut self.lookup, &mut self.moves, fx_guid, background)
+    }
 }
 
 impl MainLoop {
@@ -38,61 +354,304 @@ impl MainLoop {
             outbound_tx,
             cache: GojiraCache {
                 lookup: HashMap::new(),
-                last_project_change_count: 0,
+                tones: HashMap::new(),
+                last_instances: Vec::new(),
+                projects: HashMap::new(),
                 last_broadcast_time: Instant::now().checked_sub(PROJECT_CHANGED_DEBOUNCE).unwrap_or_else(Instant::now),
-                last_track_count: -1,
-                last_total_fx_count: -1,
+                last_project_tempo: None,
+                moves: Vec::new(),
             },
-            active_session_token: None,
+            sessions: HashMap::new(),
+            parked: HashMap::new(),
             validation_ready: false,
-            last_validation_report: HashMap::new(),
+            last_validation_report: Vec::new(),
+            metrics: Metrics::new(),
+            server_addr: None,
+            tls_fingerprint: None,
+            peers: HashMap::new(),
+            last_command: None,
+            last_error: None,
+            presets: None,
+            render_dir: std::env::temp_dir().join("gojira_previews"),
+            snapshots: VecDeque::new(),
+            last_applied: HashMap::new(),
+            subscriptions: HashMap::new(),
+            watched_values: HashMap::new(),
+            last_param_poll: Instant::now(),
+            param_kinds: HashMap::new(),
+            param_enums: HashMap::new(),
+            ramps: Vec::new(),
+            meta_stream: None,
+            meta_cache: None,
+            meta_key: None,
+            handshake_layouts: HashMap::new(),
+            apply_queue: VecDeque::new(),
+            min_apply_interval: None,
+            last_apply: None,
+            tick_budget: DEFAULT_TICK_BUDGET,
+            rescans: HashMap::new(),
+            tone_read: None,
+            scheduled: Vec::new(),
+            tone_slots: ToneSlots::default(),
+            midi: None,
+            replay: VecDeque::new(),
         }
     }
 
+    /// Folder `save_preset` writes to; without one only the plugin's own presets can be loaded.
+    pub fn set_preset_dir(&mut self, dir: PathBuf) {
+        self.presets = Some(PresetStore::new(dir));
+    }
+
+    /// Folder `render_preview` writes its WAVs to; the system temp folder by default.
+    pub fn set_render_dir(&mut self, dir: PathBuf) {
+        self.render_dir = dir;
+    }
+
+    /// File probed param meta is kept in; without one every handshake probes the plugin again.
+    pub fn set_meta_cache(&mut self, path: PathBuf) {
+        self.meta_cache = Some(MetaCache::load(path));
+    }
+
+    /// Applies at most `per_sec` queued writes a second; the rest wait their turn in the queue.
+    pub fn set_max_apply_rate(&mut self, per_sec: f64) {
+        self.min_apply_interval = Duration::try_from_secs_f64(1.0 / per_sec).ok();
+    }
+
+    /// How long one tick may run before sliced work (watchdog rescans, param meta, tone reads,
+    /// the apply queue) is left for the next one.
+    pub fn set_tick_budget(&mut self, budget: Duration) {
+        self.tick_budget = budget;
+    }
+
+    /// Where the net thread is listening; reported in every handshake.
+    pub fn set_server_addr(&mut self, addr: String) {
+        self.server_addr = Some(addr);
+    }
+
+    /// Certificate fingerprint when the net thread serves `wss://`; reported in every handshake.
+    pub fn set_tls_fingerprint(&mut self, fingerprint: String) {
+        self.tls_fingerprint = Some(fingerprint);
+    }
+
+    /// Switches tone slots on program changes (and `map.slot_cc`) read from `rx`.
+    pub fn set_midi_input(&mut self, rx: Receiver<[u8; 3]>, map: MidiMap) {
+        self.midi = Some((rx, map));
+    }
+
     pub fn tick(&mut self, api: &dyn ReaperApi) {
-        let mut connected_token: Option<String> = None;
+        let mut connected: Vec<(ClientId, String)> = Vec::new();
         let mut refresh_instances = false;
-        let mut last_set_tone: Option<ClientCommand> = None;
+        let mut instances_requested: Vec<ClientId> = Vec::new();
+        let mut resumed: Vec<(ClientId, String)> = Vec::new();
+        let mut refresh_param_meta = false;
+        let mut reprobe_indices: Vec<i32> = Vec::new();
+        let mut metrics_requested: Vec<ClientId> = Vec::new();
+        let mut diagnostics_requested: Vec<(ClientId, Option<usize>)> = Vec::new();
+        let tick_started = Instant::now();
+        let budget = TickBudget::start(self.tick_budget);
+
+        self.metrics.tick(self.inbound_rx.len());
 
         loop {
             match self.inbound_rx.try_recv() {
                 Ok(msg) => match msg {
                     InboundMsg::ClientConnected {
-                        session_token, ..
+                        client_id,
+                        peer,
+                        session_token,
                     } => {
-                        connected_token = Some(session_token);
+                        telemetry::info(
+                            "main_loop",
+                            format!("client {client_id} connected from {peer}"),
+                        );
+                        connected.push((client_id, session_token));
+                        self.peers.insert(client_id, peer);
                     }
-                    InboundMsg::ClientDisconnected => {
-                        self.active_session_token = None;
-                        self.validation_ready = false;
-                        self.cache.lookup.clear();
+                    InboundMsg::ClientDisconnected { client_id } => {
+                        telemetry::info("main_loop", format!("client {client_id} disconnected"));
+                        connected.retain(|(id, _)| *id != client_id);
+                        resumed.retain(|(id, _)| *id != client_id);
+                        self.peers.remove(&client_id);
+                        let subscriptions = self.subscriptions.remove(&client_id);
+                        if subscriptions.is_some() {
+                            self.prune_watched_values();
+                        }
+                        if let Some(token) = self.sessions.remove(&client_id) {
+                            self.park_session(token, subscriptions);
+                        }
+                        self.scheduled.retain(|t| t.client != client_id);
+                        self.apply_queue.retain(|(client, _)| *client != client_id);
+                        if self.sessions.is_empty() {
+                            self.validation_ready = false;
+                            self.cache.lookup.clear();
+                        }
+                    }
+                    InboundMsg::TokenRotated {
+                        client_id,
+                        session_token,
+                        expires_in_secs,
+                    } => {
+                        if let Some((_, token)) =
+                            connected.iter_mut().find(|(id, _)| *id == client_id)
+                        {
+                            // The handshake for this connection hasn't gone out yet; it will
+                            // carry the new token.
+                            *token = session_token;
+                        } else if let Some(token) = self.sessions.get_mut(&client_id) {
+                            *token = session_token.clone();
+                            self.send(
+                                client_id,
+                                ServerMessage::TokenRotated {
+                                    session_token,
+                                    expires_in_secs,
+                                },
+                            );
+                        }
+                    }
+                    InboundMsg::Command { client_id, cmd } => {
+                        self.metrics.command_received();
+                        self.note_command(client_id, &cmd);
+                        match cmd {
+                            ClientCommand::RefreshInstances {
+                                instances_only: true,
+                                ..
+                            } => instances_requested.push(client_id),
+                            ClientCommand::RefreshInstances { .. } => refresh_instances = true,
+                            // The net thread already checked the token and switched over to it.
+                            ClientCommand::Resume { previous_token } => {
+                                resumed.push((client_id, previous_token))
+                            }
+                            ClientCommand::RefreshParamMeta { indices, .. } => {
+                                if indices.is_empty() {
+                                    refresh_param_meta = true;
+                                } else {
+                                    reprobe_indices.extend(indices);
+                                }
+                            }
+                            ClientCommand::GetMetrics { .. } => metrics_requested.push(client_id),
+                            ClientCommand::GetDiagnostics { limit, .. } => {
+                                diagnostics_requested.push((client_id, limit))
+                            }
+                            ClientCommand::Ping { nonce, .. } => {
+                                let uptime_ms = self.metrics.uptime().as_millis() as u64;
+                                self.send(client_id, ServerMessage::Pong { nonce, uptime_ms });
+                            }
+                            ClientCommand::Subscribe { .. } => self.subscribe(client_id, cmd),
+                            ClientCommand::SearchEnumLabels { .. } => {
+                                self.search_enum_labels(client_id, cmd)
+                            }
+                            ClientCommand::Play { .. }
+                            | ClientCommand::Stop { .. }
+                            | ClientCommand::SetLoop { .. } => self.transport(api, client_id, cmd),
+                            ClientCommand::InsertFx { .. } => {
+                                // The new instance goes out in everyone's next handshake.
+                                refresh_instances |= self.insert_fx(api, client_id, cmd);
+                            }
+                            ClientCommand::GetCurrentTone { .. }
+                            | ClientCommand::PreviewTone { .. }
+                            | ClientCommand::ValidateTone { .. }
+                            | ClientCommand::RenderPreview { .. }
+                            | ClientCommand::SetTone { .. }
+                            | ClientCommand::SetToneMulti { .. }
+                            | ClientCommand::UndoLastTone { .. }
+                            | ClientCommand::RestoreSnapshot { .. }
+                            | ClientCommand::SavePreset { .. }
+                            | ClientCommand::LoadPreset { .. }
+                            | ClientCommand::ListPresets { .. }
+                            | ClientCommand::StoreToneSlot { .. }
+                            | ClientCommand::ActivateToneSlot { .. }
+                            | ClientCommand::ListToneSlots { .. }
+                            | ClientCommand::SetFxMix { .. }
+                            | ClientCommand::SetFxState { .. }
+                            | ClientCommand::SetTrackControls { .. }
+                            | ClientCommand::SwitchFxPair { .. } => self.enqueue(client_id, cmd),
+                            // Token rotation is handled by the net thread.
+                            ClientCommand::HandshakeAck { .. }
+                            | ClientCommand::RotateToken { .. } => {}
+                        }
                     }
-                    InboundMsg::Command { cmd } => match cmd {
-                        ClientCommand::RefreshInstances { .. } => refresh_instances = true,
-                        ClientCommand::SetTone { .. } => last_set_tone = Some(cmd),
-                        ClientCommand::HandshakeAck { .. } => {}
-                    },
                 },
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => break,
             }
         }
 
-        if let Some(token) = connected_token {
-            self.active_session_token = Some(token.clone());
-            self.validation_ready = false;
-            self.last_validation_report.clear();
-            self.refresh_and_handshake(api, &token);
-        } else if refresh_instances {
-            if let Some(token) = self.active_session_token.clone() {
-                self.refresh_and_handshake(api, &token);
+        let mut newcomers: Vec<ClientId> = connected.iter().map(|(id, _)| *id).collect();
+        self.sessions.extend(connected);
+        let resumed = self.resume_sessions(resumed);
+        // A connection that resumed right away needs no handshake.
+        newcomers.retain(|c| !resumed.contains(c));
+        if refresh_param_meta {
+            if let (Some(cache), Some(key)) = (self.meta_cache.as_mut(), self.meta_key.as_deref()) {
+                // Unwritable, it would only cost the probe that follows anyway.
+                let _ = cache.remove(key);
             }
+            refresh_instances = true;
+        }
+        if refresh_instances {
+            // Everyone gets the updated instance list, each under its own token.
+            let everyone: Vec<ClientId> = self.sessions.keys().copied().collect();
+            self.refresh_and_handshake(api, &everyone);
+        } else if !newcomers.is_empty() {
+            self.refresh_and_handshake(api, &newcomers);
+        }
+        let mut scanned = refresh_instances || !newcomers.is_empty();
+        if !resumed.is_empty() {
+            self.send_instances(api, &resumed, scanned, |session_token, instances| {
+                ServerMessage::Resumed {
+                    session_token,
+                    instances,
+                }
+            });
+            scanned = true;
         }
+        if !instances_requested.is_empty() {
+            self.send_instances(api, &instances_requested, scanned, |_, instances| {
+                ServerMessage::Instances { instances }
+            });
+        }
+        self.replay_to(&[newcomers, resumed].concat());
+        if !refresh_param_meta && !reprobe_indices.is_empty() {
+            self.reprobe_param_meta(api, reprobe_indices);
+        }
+
+        self.stream_param_meta(api, &budget);
+        self.watchdog(api, &budget);
 
-        self.watchdog(api);
+        self.take_midi(api);
+        self.drain_apply_queue(api, &budget);
+        self.fire_scheduled_tones(api);
+        self.advance_ramps(api);
+        self.publish_moves(api);
+
+        if self.last_param_poll.elapsed() >= PARAM_POLL_INTERVAL {
+            self.last_param_poll = Instant::now();
+            self.poll_subscriptions(api);
+        }
+
+        if !metrics_requested.is_empty() {
+            let metrics = self.metrics.snapshot();
+            for client in metrics_requested {
+                self.send(
+                    client,
+                    ServerMessage::Metrics {
+                        metrics: metrics.clone(),
+                    },
+                );
+            }
+        }
 
-        if let Some(cmd) = last_set_tone {
-            self.apply_set_tone(api, cmd);
+        self.metrics.tick_took(tick_started.elapsed());
+        for (client, limit) in diagnostics_requested {
+            let limit = limit.unwrap_or(telemetry::DEFAULT_DIAGNOSTICS_EVENTS);
+            self.send(
+                client,
+                ServerMessage::Diagnostics {
+                    events: telemetry::recent(limit),
+                    timings: self.metrics.timings(),
+                },
+            );
         }
     }
 
@@ -100,77 +659,607 @@ impl MainLoop {
         let _ = self.outbound_tx.try_send(msg);
     }
 
-    fn refresh_and_handshake(&mut self, api: &dyn ReaperApi, session_token: &str) {
-        let (instances, lookup) = resolver::scan_project_instances(api);        
+    /// Tells every client the server is going away. Queue it before stopping the network thread,
+    /// which sends what's left in the queue ahead of the close frames.
+    pub fn announce_shutdown(&mut self, reason: &str, restart_expected: bool) {
+        self.broadcast(ServerMessage::Shutdown {
+            reason: reason.to_string(),
+            restart_expected,
+        });
+    }
+
+    /// Scans every watched project from scratch, replacing the cached lookup and instance list.
+    fn rescan_instances(&mut self, api: &dyn ReaperApi) -> Vec<GojiraInstance> {
+        let mut instances = Vec::new();
+        let mut lookup = HashMap::new();
+        self.cache.projects.clear();
+        self.rescans.clear();
+        for (project, path) in resolver::watched_projects(api) {
+            let scan_started = Instant::now();
+            let scan = resolver::scan_project(api, project, &ScanMemo::default());
+            self.metrics.scan_took(scan_started.elapsed());
+            instances.extend(scan.instances.iter().cloned());
+            lookup.extend(scan.lookup);
+            let entry = WatchedProject {
+                change_count: api.project_state_change_count_in(project),
+                path,
+                instances: scan.instances,
+                memo: scan.memo,
+            };
+            self.cache.projects.insert(project, entry);
+        }
         self.cache.lookup = lookup;
+        self.cache.last_instances = instances.clone();
+        self.reload_last_applied(api);
+        self.tone_slots.reload(api);
+        instances
+    }
+
+    /// Answers `RefreshInstances` with `instances_only`, or `Resume`, scanning unless this tick
+    /// already did. The probe results hold as long as an instance is left, like after a watchdog
+    /// rescan; a client polling its way to the first instance gets the full handshake it would
+    /// need anyway.
+    fn send_instances(
+        &mut self,
+        api: &dyn ReaperApi,
+        recipients: &[ClientId],
+        scanned: bool,
+        reply: impl Fn(String, Vec<GojiraInstance>) -> ServerMessage,
+    ) {
+        if !scanned {
+            self.rescan_instances(api);
+            self.validation_ready &= !self.cache.last_instances.is_empty();
+        }
+        if !self.validation_ready && !self.cache.last_instances.is_empty() {
+            self.refresh_and_handshake(api, recipients);
+            return;
+        }
+        for &client in recipients {
+            let Some(session_token) = self.sessions.get(&client).cloned() else {
+                continue;
+            };
+            let instances = self.cache.last_instances.clone();
+            self.send(client, reply(session_token, instances));
+        }
+    }
+
+    /// Keeps what a dropped connection leaves behind until `RESUME_GRACE_SECS` runs out.
+    fn park_session(
+        &mut self,
+        token: String,
+        subscriptions: Option<HashMap<String, HashSet<i32>>>,
+    ) {
+        let grace = Duration::from_secs(RESUME_GRACE_SECS);
+        self.parked.retain(|_, p| p.at.elapsed() < grace);
+        self.parked.insert(
+            token,
+            ParkedSession {
+                at: Instant::now(),
+                subscriptions,
+                validation_ready: self.validation_ready,
+            },
+        );
+    }
+
+    /// Moves each client onto the token it resumed and hands it that session's subscriptions,
+    /// and the probe results if they held back then. Returns the clients still connected.
+    fn resume_sessions(&mut self, resumed: Vec<(ClientId, String)>) -> Vec<ClientId> {
+        let mut clients = Vec::new();
+        for (client, token) in resumed {
+            if !self.sessions.contains_key(&client) {
+                continue;
+            }
+            if let Some(parked) = self.parked.remove(&token) {
+                if let Some(subscriptions) = parked.subscriptions {
+                    self.subscriptions.insert(client, subscriptions);
+                }
+                self.validation_ready |= parked.validation_ready;
+            }
+            self.sessions.insert(client, token);
+            clients.push(client);
+        }
+        clients
+    }
+
+    /// Rescans and probes once, then sends the handshake to each of `recipients`. Enum options and
+    /// formatted values take hundreds of FormatParamValue calls, so they come from the meta cache or
+    /// follow in `ParamMeta` chunks from `stream_param_meta` rather than hold the handshake up.
+    fn refresh_and_handshake(&mut self, api: &dyn ReaperApi, recipients: &[ClientId]) {
+        let instances = self.rescan_instances(api);
+        self.handshake_layouts.clear();
+        for instance in &instances {
+            if let Ok((track, fx_index)) = self.cache.resolve(api, &instance.fx_guid) {
+                let layout = FxLayout::read(api, track, fx_index);
+                self.handshake_layouts
+                    .insert(instance.fx_guid.clone(), layout);
+            }
+        }
 
         let mut ready = false;
-        let mut validation_report = HashMap::new();
-        let mut param_enums = HashMap::new();
-        let mut param_formats = HashMap::new();
-        let mut param_format_samples = HashMap::new();
+        let mut validation_report = Vec::new();
+        let mut eq_band_hz = HashMap::new();
+        let mut param_remap = HashMap::new();
+        let mut proposed_remap = HashMap::new();
+        let mut param_schema = HashMap::new();
+        let mut meta = CachedMeta::default();
         if let Some(first) = instances.first() {
-            if let Ok((track, fx_index)) =
-                resolver::resolve_fx(api, &mut self.cache.lookup, &first.fx_guid)
-            {
+            if let Ok((track, fx_index)) = self.cache.resolve(api, &first.fx_guid) {
+                let probe_started = Instant::now();
                 ready = true;
-                validation_report = validator::validate_parameter_map(api, track, fx_index);
-                let (enums, formats, samples) = validator::probe_param_meta(api, track, fx_index);
-                param_enums = enums;
-                param_formats = formats;
-                param_format_samples = samples;
+                let profile = profiles::for_fx(api, track, fx_index);
+                validation_report =
+                    validator::validate_parameter_map(api, track, fx_index, profile);
+                eq_band_hz = validator::probe_eq_band_hz(api, track, fx_index, profile);
+                param_remap = validator::discover_param_remap(api, track, fx_index, profile);
+                proposed_remap = validator::propose_remap(
+                    api,
+                    track,
+                    fx_index,
+                    &validation_report,
+                    &param_remap,
+                );
+
+                let plan = validator::MetaPlan::new(api, track, fx_index, profile);
+                let key = meta_cache::key(
+                    &api.track_fx_name(track, fx_index),
+                    api.track_fx_num_params(track, fx_index).unwrap_or(0),
+                    &api.track_fx_ident(track, fx_index).unwrap_or_default(),
+                    &plan.settings(),
+                );
+                self.meta_key = Some(key.clone());
+                let cached = self.meta_cache.as_ref().and_then(|c| c.get(&key)).cloned();
+                if let Some(cached) = cached {
+                    meta = cached;
+                } else {
+                    // Kinds arrive with the rest of the meta stream, so the handshake has none.
+                    // Clients still waiting on an earlier stream get theirs from the start again.
+                    let mut meta_recipients = recipients.to_vec();
+                    if let Some(stream) = self.meta_stream.take() {
+                        meta_recipients.extend(stream.recipients);
+                        meta_recipients.sort_unstable();
+                        meta_recipients.dedup();
+                    }
+                    self.meta_stream = Some(MetaStream {
+                        fx_guid: first.fx_guid.clone(),
+                        plan,
+                        next_index: 0,
+                        recipients: meta_recipients,
+                        collected: CachedMeta::default(),
+                        key,
+                    });
+                }
+                self.param_kinds = meta.param_kinds.clone();
+                self.param_enums = meta.param_enums.clone();
+                param_schema =
+                    profiles::param_schema(api, track, fx_index, profile, &meta.param_kinds);
+                self.metrics.probe_took(probe_started.elapsed());
             }
         }
         self.last_validation_report = validation_report.clone();
+        let project_tempo = project_tempo(api);
+        self.cache.last_project_tempo = project_tempo;
         // We consider the system ready once we successfully resolved a target instance and probed
         // basic metadata. The verbose validation report is optional and may be disabled.
         self.validation_ready = ready;
+        let (param_enums, param_enum_totals) = first_enum_pages(&meta.param_enums);
+        let handshake_meta = HandshakeMeta {
+            param_enums,
+            param_enum_totals,
+            param_formats: meta.param_formats,
+            param_format_samples: meta.param_format_samples,
+            eq_band_hz,
+            param_kinds: meta.param_kinds,
+            param_schema,
+            param_remap,
+            proposed_remap,
+        };
+
+        for &client in recipients {
+            let Some(session_token) = self.sessions.get(&client).cloned() else {
+                continue;
+            };
+            self.send(
+                client,
+                ServerMessage::Handshake {
+                    session_token,
+                    instances: instances.clone(),
+                    validation_report: validation_report.clone(),
+                    param_meta_streaming: self.meta_stream.is_some(),
+                    meta: Box::new(handshake_meta.clone()),
+                    project_tempo,
+                    server_addr: self.server_addr.clone(),
+                    tls_fingerprint: self.tls_fingerprint.clone(),
+                    capabilities: CAPABILITIES.to_vec(),
+                },
+            );
+        }
+    }
+
+    /// Probes just `indices` of the instance the handshake probed, folds the results into the
+    /// meta cache and broadcasts them as one `ParamMeta`. Params the plan doesn't probe come back
+    /// empty.
+    fn reprobe_param_meta(&mut self, api: &dyn ReaperApi, mut indices: Vec<i32>) {
+        indices.retain(|i| (0..=MAX_PARAM_INDEX).contains(i));
+        indices.sort_unstable();
+        indices.dedup();
+        let (Some(&first_index), Some(&last_index)) = (indices.first(), indices.last()) else {
+            return;
+        };
+        let Some(fx_guid) = self.cache.last_instances.first().map(|i| i.fx_guid.clone()) else {
+            telemetry::warn("main_loop", "param meta refresh: no instance to probe");
+            return;
+        };
+        let Ok((track, fx_index)) = self.cache.resolve(api, &fx_guid) else {
+            telemetry::warn("main_loop", "param meta refresh: instance not found");
+            return;
+        };
 
-        self.send(ServerMessage::Handshake {
-            session_token: session_token.to_string(),
-            instances,
-            validation_report,
+        let started = Instant::now();
+        let profile = profiles::for_fx(api, track, fx_index);
+        let plan = validator::MetaPlan::new(api, track, fx_index, profile);
+        let mut meta = CachedMeta::default();
+        for &index in &indices {
+            let probed =
+                validator::probe_param_meta_range(api, track, fx_index, &plan, index..=index);
+            meta.param_enums.extend(probed.param_enums);
+            meta.param_formats.extend(probed.param_formats);
+            meta.param_format_samples
+                .extend(probed.param_format_samples);
+            meta.param_kinds.extend(probed.param_kinds);
+        }
+        self.metrics.probe_took(started.elapsed());
+        telemetry::info(
+            "main_loop",
+            format!("param meta refreshed for {} index(es)", indices.len()),
+        );
+
+        for index in &indices {
+            self.param_enums.remove(index);
+        }
+        self.param_enums.extend(meta.param_enums.clone());
+        self.param_kinds.extend(meta.param_kinds.clone());
+        if let (Some(cache), Some(key)) = (self.meta_cache.as_mut(), self.meta_key.clone()) {
+            if let Some(mut cached) = cache.get(&key).cloned() {
+                for index in &indices {
+                    cached.param_enums.remove(index);
+                    cached.param_formats.remove(index);
+                    cached.param_format_samples.remove(index);
+                }
+                cached.param_enums.extend(meta.param_enums.clone());
+                cached.param_formats.extend(meta.param_formats.clone());
+                cached
+                    .param_format_samples
+                    .extend(meta.param_format_samples.clone());
+                cached.param_kinds.extend(meta.param_kinds.clone());
+                // Unwritable, the next handshake just gets the old options from the cache.
+                let _ = cache.insert(key, cached);
+            }
+        }
+
+        let (param_enums, param_enum_totals) = first_enum_pages(&meta.param_enums);
+        self.broadcast(ServerMessage::ParamMeta {
+            first_index,
+            last_index,
             param_enums,
-            param_formats,
-            param_format_samples,
+            param_enum_totals,
+            param_formats: meta.param_formats,
+            param_format_samples: meta.param_format_samples,
+            param_kinds: meta.param_kinds,
+            // A stream still running for a newcomer has its own last chunk to come.
+            done: self.meta_stream.is_none(),
         });
     }
 
-    fn watchdog(&mut self, api: &dyn ReaperApi) {
-        let state = api.project_state_change_count();
-        if state == self.cache.last_project_change_count {
-            return;
+    /// Streams pending param meta a chunk at a time for as long as the tick's budget lasts.
+    fn stream_param_meta(&mut self, api: &dyn ReaperApi, budget: &TickBudget) {
+        while self.stream_param_meta_chunk(api) && !budget.exhausted() {}
+    }
+
+    /// Probes the next index range of the pending param meta and sends it as one `ParamMeta`;
+    /// false once nothing is left to stream.
+    fn stream_param_meta_chunk(&mut self, api: &dyn ReaperApi) -> bool {
+        let Some(stream) = self.meta_stream.as_mut() else {
+            return false;
+        };
+        stream.recipients.retain(|c| self.sessions.contains_key(c));
+        if stream.recipients.is_empty() {
+            self.meta_stream = None;
+            return false;
         }
-        self.cache.last_project_change_count = state;
+        let Ok((track, fx_index)) = self.cache.resolve(api, &stream.fx_guid) else {
+            // The probed instance is gone; the next handshake starts a new stream.
+            self.meta_stream = None;
+            return false;
+        };
+
+        let first_index = stream.next_index;
+        let last_index = (first_index + PARAM_META_CHUNK - 1).min(stream.plan.last_index);
+        let started = Instant::now();
+        let CachedMeta {
+            param_enums,
+            param_formats,
+            param_format_samples,
+            param_kinds,
+        } = validator::probe_param_meta_range(
+            api,
+            track,
+            fx_index,
+            &stream.plan,
+            first_index..=last_index,
+        );
+        self.metrics.probe_took(started.elapsed());
+        let done = last_index >= stream.plan.last_index;
+        stream.next_index = last_index + 1;
+        let recipients = stream.recipients.clone();
 
-        let track_count = api.count_tracks();
-        let total_fx_count = total_fx_count(api);
-        let instances_affected =
-            track_count != self.cache.last_track_count || total_fx_count != self.cache.last_total_fx_count;
+        self.param_enums.extend(param_enums.clone());
+        self.param_kinds.extend(param_kinds.clone());
 
-        self.cache.last_track_count = track_count;
-        self.cache.last_total_fx_count = total_fx_count;
+        let collected = &mut stream.collected;
+        collected.param_enums.extend(param_enums.clone());
+        collected.param_formats.extend(param_formats.clone());
+        collected
+            .param_format_samples
+            .extend(param_format_samples.clone());
+        collected.param_kinds.extend(param_kinds.clone());
+        if done {
+            if let Some(stream) = self.meta_stream.take() {
+                if let Some(cache) = self.meta_cache.as_mut() {
+                    // Unwritable, the cache only costs the next handshake a probe.
+                    let _ = cache.insert(stream.key, stream.collected);
+                }
+            }
+        }
 
-        if !instances_affected {
-            return;
+        let (param_enums, param_enum_totals) = first_enum_pages(&param_enums);
+        for client in recipients {
+            self.send(
+                client,
+                ServerMessage::ParamMeta {
+                    first_index,
+                    last_index,
+                    param_enums: param_enums.clone(),
+                    param_enum_totals: param_enum_totals.clone(),
+                    param_formats: param_formats.clone(),
+                    param_format_samples: param_format_samples.clone(),
+                    param_kinds: param_kinds.clone(),
+                    done,
+                },
+            );
         }
+        self.meta_stream.is_some()
+    }
 
+    /// Change counts are per project, so each watched project is checked on its own: one whose
+    /// count moved, or that was just switched to, is rescanned; one switched away from or closed
+    /// is dropped. Only those projects' instances and lookup entries are touched, and each one
+    /// whose instances changed gets its own ProjectChanged. A rescan that outlasts the tick's
+    /// budget resumes on the next one, and its project is reported once it lands.
+    fn watchdog(&mut self, api: &dyn ReaperApi, budget: &TickBudget) {
+        let watched = resolver::watched_projects(api);
+        self.rescans
+            .retain(|p, _| watched.iter().any(|(w, _)| w == p));
+        let counts: Vec<i32> = watched
+            .iter()
+            .map(|(p, _)| api.project_state_change_count_in(*p))
+            .collect();
+        let changed: Vec<usize> = watched
+            .iter()
+            .zip(&counts)
+            .filter(|((p, _), count)| {
+                self.cache.projects.get(p).map(|w| w.change_count) != Some(**count)
+            })
+            .map(|((p, _), _)| *p)
+            .collect();
+        let gone: Vec<usize> = self
+            .cache
+            .projects
+            .keys()
+            .filter(|p| !watched.iter().any(|(w, _)| w == *p))
+            .copied()
+            .collect();
+        if changed.is_empty() && gone.is_empty() {
+            return;
+        }
+        // Inside the debounce window the change is left for a later tick rather than dropped;
+        // only rescans already under way carry on.
         let now = Instant::now();
-        if now.duration_since(self.cache.last_broadcast_time) < PROJECT_CHANGED_DEBOUNCE {
+        let debouncing =
+            now.duration_since(self.cache.last_broadcast_time) < PROJECT_CHANGED_DEBOUNCE;
+        if debouncing && self.rescans.is_empty() {
+            return;
+        }
+
+        let tempo = project_tempo(api);
+        let tempo_changed = tempo != self.cache.last_project_tempo;
+        let current = watched.first().map(|(p, _)| *p);
+
+        let mut deltas: Vec<ProjectDelta> = Vec::new();
+        for project in gone.into_iter().filter(|_| !debouncing) {
+            let Some(old) = self.cache.projects.remove(&project) else {
+                continue;
+            };
+            for inst in &old.instances {
+                self.cache.lookup.remove(&inst.fx_guid);
+            }
+            if !old.instances.is_empty() {
+                deltas.push(ProjectDelta {
+                    project_name: project_name(&old.path),
+                    added: Vec::new(),
+                    removed: old.instances,
+                    renamed: Vec::new(),
+                });
+            }
+        }
+        for ((project, path), count) in watched.iter().zip(&counts) {
+            if !changed.contains(project) || (debouncing && !self.rescans.contains_key(project)) {
+                continue;
+            }
+            // The rescan keeps the count it started at; an edit made while it runs moves the
+            // count again and gets a rescan of its own.
+            let (_, cursor) = self.rescans.entry(*project).or_insert_with(|| {
+                let memo = self
+                    .cache
+                    .projects
+                    .get_mut(project)
+                    .map(|w| std::mem::take(&mut w.memo))
+                    .unwrap_or_default();
+                (*count, ScanCursor::new(api, *project, memo))
+            });
+            if !cursor.step(api, budget) {
+                continue;
+            }
+            let Some((count, cursor)) = self.rescans.remove(project) else {
+                continue;
+            };
+            self.metrics.scan_took(cursor.took());
+            let scan = cursor.finish();
+            let before = self
+                .cache
+                .projects
+                .remove(project)
+                .map(|w| w.instances)
+                .unwrap_or_default();
+            for inst in &before {
+                self.cache.lookup.remove(&inst.fx_guid);
+            }
+            self.cache.lookup.extend(scan.lookup);
+            let (added, removed, renamed) = diff_instances(&before, &scan.instances);
+            let affected = !added.is_empty() || !removed.is_empty() || !renamed.is_empty();
+            if affected || (Some(*project) == current && tempo_changed) {
+                deltas.push(ProjectDelta {
+                    project_name: project_name(path),
+                    added,
+                    removed,
+                    renamed,
+                });
+            }
+            let entry = WatchedProject {
+                change_count: count,
+                path: path.clone(),
+                instances: scan.instances,
+                memo: scan.memo,
+            };
+            self.cache.projects.insert(*project, entry);
+        }
+        self.cache.last_instances = watched
+            .iter()
+            .filter_map(|(p, _)| self.cache.projects.get(p))
+            .flat_map(|w| w.instances.iter().cloned())
+            .collect();
+
+        if deltas.is_empty() {
             return;
         }
         self.cache.last_broadcast_time = now;
-        self.cache.lookup.clear();
-        self.validation_ready = false;
-        self.send(ServerMessage::ProjectChanged);
+        self.cache.last_project_tempo = tempo;
+        // The probe results hold for every instance of the plugin, so they stay valid as long as
+        // one is left; clients apply the deltas instead of handshaking again.
+        self.validation_ready &= !self.cache.last_instances.is_empty();
+        self.reload_last_applied(api);
+        self.tone_slots.reload(api);
+        // Tone reads aren't tied to a project; the first message carries them.
+        let mut changed_instances = self.changed_instances(api);
+        for delta in deltas {
+            telemetry::info(
+                "watchdog",
+                format!(
+                    "{}: {} added, {} removed, {} renamed",
+                    delta.project_name.as_deref().unwrap_or("(unsaved project)"),
+                    delta.added.len(),
+                    delta.removed.len(),
+                    delta.renamed.len()
+                ),
+            );
+            self.broadcast(ServerMessage::ProjectChanged {
+                project_tempo: tempo,
+                project_name: delta.project_name,
+                changed_instances: std::mem::take(&mut changed_instances),
+                added: delta.added,
+                removed: delta.removed,
+                renamed: delta.renamed,
+            });
+        }
+    }
+
+    /// Brings the cached instances in line with the moves resolves found this tick and tells
+    /// everyone. The project's next rescan then finds them where they already are, so the move
+    /// isn't reported again as a rename.
+    fn publish_moves(&mut self, api: &dyn ReaperApi) {
+        for mv in std::mem::take(&mut self.cache.moves) {
+            let track_name = resolver::find_track_by_guid(api, &mv.to_track_guid)
+                .map(|track| api.track_name(track));
+            let mut moved: Option<GojiraInstance> = None;
+            let cached = self
+                .cache
+                .projects
+                .values_mut()
+                .flat_map(|w| w.instances.iter_mut())
+                .chain(self.cache.last_instances.iter_mut())
+                .filter(|inst| inst.fx_guid == mv.fx_guid);
+            for inst in cached {
+                inst.track_guid = mv.to_track_guid.clone();
+                inst.last_known_fx_index = mv.to_fx_index;
+                if let Some(name) = &track_name {
+                    inst.track_name = name.clone();
+                }
+                moved = Some(inst.clone());
+            }
+            let Some(instance) = moved else {
+                continue;
+            };
+            telemetry::info(
+                "resolver",
+                format!(
+                    "{} moved from fx {} on {} to fx {} on {}",
+                    mv.fx_guid,
+                    mv.from_fx_index,
+                    mv.from_track_guid,
+                    mv.to_fx_index,
+                    mv.to_track_guid
+                ),
+            );
+            self.broadcast(ServerMessage::InstanceMoved {
+                instance,
+                from_track_guid: mv.from_track_guid,
+                from_fx_index: mv.from_fx_index,
+            });
+        }
     }
 
-    fn apply_set_tone(&mut self, api: &dyn ReaperApi, cmd: ClientCommand) {
+    /// Re-reads every cached instance and returns the guids whose tone params moved, or that
+    /// can't be found anymore.
+    fn changed_instances(&mut self, api: &dyn ReaperApi) -> Vec<String> {
+        let guids: Vec<String> = self.cache.tones.keys().cloned().collect();
+        let mut changed = Vec::new();
+        for guid in guids {
+            let Some(before) = self.cache.tones.remove(&guid) else {
+                continue;
+            };
+            let Ok((track, fx_index)) = self.cache.resolve(api, &guid) else {
+                changed.push(guid);
+                continue;
+            };
+            let after = self.current_tone(api, &guid, track, fx_index);
+            if tone_moved(&before.params, &after) {
+                changed.push(guid);
+            }
+        }
+        changed.sort();
+        changed
+    }
+
+    fn apply_set_tone(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
         let ClientCommand::SetTone {
             command_id,
             target_fx_guid,
             mode,
             params,
+            locked_params,
+            ramp_ms,
+            atomic,
+            modules,
+            allow_background_projects,
             ..
         } = cmd
         else {
@@ -178,161 +1267,3014 @@ impl MainLoop {
         };
 
         if !self.validation_ready {
-            self.send(ServerMessage::Error {
-                msg: "not ready (handshake/validation required)".to_string(),
-                code: ErrorCode::NotReady,
-            });
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
             return;
         }
 
-        let (track, fx_index) = match resolver::resolve_fx(api, &mut self.cache.lookup, &target_fx_guid)
-        {
-            Ok(r) => r,
-            Err(_) => {
-                self.send(ServerMessage::Error {
-                    msg: "target fx guid not found".to_string(),
-                    code: ErrorCode::TargetNotFound,
-                });
+        let target = ToneTarget {
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        };
+        let tone = match self.prepare_tone(
+            api,
+            client,
+            &command_id,
+            target,
+            modules.as_deref(),
+            allow_background_projects,
+        ) {
+            Ok(tone) => tone,
+            Err(e) => {
+                self.send(client, e.into_message(&command_id));
                 return;
             }
         };
 
-        let mut params = match sanitize_params(params) {
-            Ok(p) => p,
-            Err(msg) => {
-                self.send(ServerMessage::Error {
-                    msg,
-                    code: ErrorCode::InvalidValue,
-                });
-                return;
-            }
+        self.take_snapshot(api, &command_id, &[&tone]);
+        // A newer SetTone for the same FX takes over from a glide still in progress.
+        self.cancel_ramps(&tone.target_fx_guid);
+        let duration = Duration::from_millis(u64::from(ramp_ms.unwrap_or(0).min(MAX_RAMP_MS)));
+        // An atomic tone is checked as a whole once written, so it never glides.
+        if !atomic
+            && !duration.is_zero()
+            && self.start_ramp(api, client, &command_id, &tone, duration)
+        {
+            return;
+        }
+        let PreparedTone {
+            target_fx_guid,
+            track,
+            fx_index,
+            params,
+            skipped_modules,
+        } = tone;
+        let written = if atomic {
+            self.write_tone_atomic(api, client, track, fx_index, &params, &command_id)
+                .map(|applied| (applied, Vec::new()))
+        } else {
+            self.write_tone(api, client, track, fx_index, &params, &command_id)
+        };
+        let Some((applied_params, failed_params)) = written else {
+            return;
         };
+        self.remember_applied(api, &target_fx_guid, &applied_params);
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params,
+                skipped_modules,
+                failed_params,
+            },
+        );
+    }
 
-        if matches!(mode, MergeMode::ReplaceActive) {
-            params = apply_replace_active_cleaner(params);
+    /// Starts gliding the tone's continuous params; false if none of them has anywhere to go, in
+    /// which case the tone should just be written. Params that can't glide switch right away.
+    fn start_ramp(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        command_id: &str,
+        tone: &PreparedTone,
+        duration: Duration,
+    ) -> bool {
+        let (track, fx_index) = (tone.track, tone.fx_index);
+        let glides: Vec<(i32, f64, f64)> = tone
+            .params
+            .iter()
+            .filter(|p| self.param_kinds.get(&p.index) == Some(&ParamKind::Continuous))
+            .filter_map(|p| {
+                let from = api.track_fx_get_param(track, fx_index, p.index)?;
+                ((from - p.value).abs() > PARAM_CHANGE_EPSILON).then_some((p.index, from, p.value))
+            })
+            .collect();
+        if glides.is_empty() {
+            return false;
         }
 
-        let mut applied_params: Vec<AppliedParam> = Vec::with_capacity(params.len());
-        for p in &params {
-            if let Err(e) = api.track_fx_set_param(track, fx_index, p.index, p.value) {
-                self.send(ServerMessage::Error {
-                    msg: format!("apply failed at param {}: {e}", p.index),
-                    code: ErrorCode::InternalError,
-                });
-                return;
+        self.cache.tones.clear();
+        // Write failures surface from the final write when the glide ends.
+        for p in &tone.params {
+            if !glides.iter().any(|(index, ..)| *index == p.index) {
+                let _ = api.track_fx_set_param(track, fx_index, p.index, p.value);
             }
-
-            let applied = api
-                .track_fx_get_param(track, fx_index, p.index)
-                .unwrap_or(p.value);
-            let formatted = api
-                .track_fx_format_param_value(track, fx_index, p.index, applied)
-                .unwrap_or_default();
-            applied_params.push(AppliedParam {
-                index: p.index,
-                requested: p.value,
-                applied,
-                formatted,
-            });
         }
-
-        self.send(ServerMessage::Ack {
-            command_id,
-            applied_params,
+        self.ramps.push(Ramp {
+            client,
+            command_id: command_id.to_string(),
+            target_fx_guid: tone.target_fx_guid.clone(),
+            started: Instant::now(),
+            duration,
+            glides,
+            params: tone.params.clone(),
+            skipped_modules: tone.skipped_modules.clone(),
         });
+        true
     }
 
-    fn send(&mut self, msg: ServerMessage) {
-        // Non-blocking best-effort. If outbound is full, ProjectChanged is acceptable to drop.
-        let _ = self
-            .outbound_tx
-            .try_send(OutboundMsg::Send { msg });
-    }
-}
-
-fn total_fx_count(api: &dyn ReaperApi) -> i32 {
-    let mut sum = 0;
-    let track_count = api.count_tracks();
-    for ti in 0..track_count {
-        let Some(track) = api.get_track(ti) else { continue };
-        sum += api.track_fx_count(track);
+    /// Queues a tone command for `drain_apply_queue`, or answers `Busy` if the queue is full.
+    fn enqueue(&mut self, client: ClientId, cmd: ClientCommand) {
+        if self.apply_queue.len() >= APPLY_QUEUE_CAP {
+            let command_id = cmd.command_id().map(str::to_string);
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("apply queue full ({APPLY_QUEUE_CAP} commands waiting)"),
+                    code: ErrorCode::Busy,
+                    details: None,
+                    command_id,
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+                },
+            );
+            return;
+        }
+        self.apply_queue.push_back((client, cmd));
     }
-    sum
-}
 
-fn sanitize_params(params: Vec<ParamChange>) -> Result<Vec<ParamChange>, String> {
-    let mut last_by_index: HashMap<i32, f32> = HashMap::new();
-    for p in &params {
-        if p.index < 0 || p.index > MAX_PARAM_INDEX {
-            return Err(format!("invalid param index: {}", p.index));
+    /// Runs queued commands in arrival order, so an undo, save or read queued behind a write
+    /// sees it. Writes are spaced by the max apply rate, and one that has to wait holds up
+    /// everything behind it.
+    fn drain_apply_queue(&mut self, api: &dyn ReaperApi, budget: &TickBudget) {
+        // A tone read left over from an earlier tick goes first; what's queued behind it waits.
+        if self.tone_read.is_some() && !self.step_tone_read(api, budget) {
+            return;
         }
-        if !p.value.is_finite() {
-            return Err(format!("non-finite value at index {}", p.index));
+        let mut ran = false;
+        while let Some((_, cmd)) = self.apply_queue.front() {
+            if ran && budget.exhausted() {
+                break;
+            }
+            ran = true;
+            let write = is_write(cmd);
+            if write {
+                if let (Some(min), Some(last)) = (self.min_apply_interval, self.last_apply) {
+                    if last.elapsed() < min {
+                        break;
+                    }
+                }
+            }
+            let Some((client, cmd)) = self.apply_queue.pop_front() else {
+                break;
+            };
+            if write {
+                self.last_apply = Some(Instant::now());
+            }
+
+            let started = Instant::now();
+            match cmd {
+                ClientCommand::GetCurrentTone { .. } => {
+                    if !self.read_current_tone(api, client, cmd, budget) {
+                        break;
+                    }
+                    continue;
+                }
+                ClientCommand::PreviewTone { .. } => {
+                    self.preview_tone(api, client, cmd);
+                    continue;
+                }
+                ClientCommand::ValidateTone { .. } => {
+                    self.validate_tone(api, client, cmd);
+                    continue;
+                }
+                ClientCommand::RenderPreview { .. } => {
+                    self.render_preview(api, client, cmd);
+                    continue;
+                }
+                ClientCommand::SetTone {
+                    apply_at: Some(_), ..
+                } => self.schedule_tone(api, client, cmd),
+                ClientCommand::SetTone { .. } => self.apply_set_tone(api, client, cmd),
+                ClientCommand::SetToneMulti { .. } => self.apply_set_tone_multi(api, client, cmd),
+                ClientCommand::UndoLastTone { .. } => self.undo_last_tone(api, client, cmd),
+                ClientCommand::RestoreSnapshot { .. } => self.restore_snapshot(api, client, cmd),
+                ClientCommand::SavePreset { .. } => self.save_preset(api, client, cmd),
+                ClientCommand::LoadPreset { .. } => self.load_preset(api, client, cmd),
+                ClientCommand::ListPresets { .. } => self.list_presets(api, client, cmd),
+                ClientCommand::StoreToneSlot { .. } => self.store_tone_slot(api, client, cmd),
+                ClientCommand::ActivateToneSlot { .. } => self.activate_tone_slot(api, client, cmd),
+                ClientCommand::ListToneSlots { .. } => self.list_tone_slots(client, cmd),
+                ClientCommand::SetFxMix { .. } => self.apply_fx_mix(api, client, cmd),
+                ClientCommand::SetFxState { .. } => self.apply_fx_state(api, client, cmd),
+                ClientCommand::SetTrackControls { .. } => {
+                    self.apply_track_controls(api, client, cmd)
+                }
+                ClientCommand::SwitchFxPair { .. } => self.apply_fx_switch(api, client, cmd),
+                _ => continue,
+            }
+            self.metrics.apply_took(started.elapsed());
         }
-        last_by_index.insert(p.index, p.value.clamp(0.0, 1.0));
     }
 
-    // Preserve the original order of last occurrences ("last-wins").
-    let mut seen = HashSet::new();
-    let mut out = Vec::new();
-    for p in params.into_iter().rev() {
-        if !seen.insert(p.index) {
-            continue;
+    /// Moves every glide one step; a finished one gets its final write and the SetTone's ack.
+    fn advance_ramps(&mut self, api: &dyn ReaperApi) {
+        for ramp in std::mem::take(&mut self.ramps) {
+            let (track, fx_index) = match self.cache.resolve(api, &ramp.target_fx_guid) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send(
+                        ramp.client,
+                        ServerMessage::Error {
+                            msg: "target fx guid not found".to_string(),
+                            code: ErrorCode::TargetNotFound,
+                            details: Some("fx vanished mid-ramp".to_string()),
+                            command_id: Some(ramp.command_id.clone()),
+                            diagnostic: Some(e.diagnostic()),
+                        },
+                    );
+                    continue;
+                }
+            };
+
+            let t = ramp.started.elapsed().as_secs_f64() / ramp.duration.as_secs_f64();
+            if t < 1.0 {
+                self.cache.tones.clear();
+                for &(index, from, to) in &ramp.glides {
+                    let _ = api.track_fx_set_param(track, fx_index, index, from + (to - from) * t);
+                }
+                self.ramps.push(ramp);
+                continue;
+            }
+
+            let Ramp {
+                client,
+                command_id,
+                target_fx_guid,
+                params,
+                skipped_modules,
+                ..
+            } = ramp;
+            let Some((applied_params, failed_params)) =
+                self.write_tone(api, client, track, fx_index, &params, &command_id)
+            else {
+                continue;
+            };
+            self.remember_applied(api, &target_fx_guid, &applied_params);
+            self.send(
+                client,
+                ServerMessage::Ack {
+                    command_id,
+                    applied_params,
+                    skipped_modules,
+                    failed_params,
+                },
+            );
         }
-        let Some(v) = last_by_index.get(&p.index) else { continue };
-        out.push(ParamChange {
-            index: p.index,
-            value: *v,
-        });
     }
-    out.reverse();
-    Ok(out)
+
+    /// Holds a SetTone back until the transport reaches its `apply_at`. One whose moment has
+    /// already come, like `next_bar` or `on_stop` with the transport stopped, is written at once.
+    fn schedule_tone(&mut self, api: &dyn ReaperApi, client: ClientId, mut cmd: ClientCommand) {
+        let ClientCommand::SetTone {
+            command_id,
+            apply_at,
+            ..
+        } = &mut cmd
+        else {
+            return;
+        };
+        let Some(anchor) = apply_at.take() else {
+            return;
+        };
+
+        if self.scheduled.len() >= MAX_SCHEDULED_TONES {
+            let command_id = Some(command_id.clone());
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("{MAX_SCHEDULED_TONES} tones already waiting on the transport"),
+                    code: ErrorCode::Busy,
+                    details: None,
+                    command_id,
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+                },
+            );
+            return;
+        }
+
+        let position = api.play_position();
+        let due = match (anchor, position) {
+            (TransportAnchor::At { position_secs }, _) => Due::Position(position_secs),
+            (TransportAnchor::NextBar, Some(now)) => match next_bar_after(api, now) {
+                Some(bar) => Due::Bar(bar),
+                None => return self.apply_set_tone(api, client, cmd),
+            },
+            (TransportAnchor::OnStop, Some(_)) => Due::Stop,
+            (TransportAnchor::NextBar | TransportAnchor::OnStop, None) => {
+                return self.apply_set_tone(api, client, cmd)
+            }
+        };
+        self.scheduled.push(ScheduledTone {
+            client,
+            due,
+            last_position: position,
+            cmd,
+        });
+    }
+
+    /// Writes every scheduled SetTone whose moment has come, in the order they arrived.
+    fn fire_scheduled_tones(&mut self, api: &dyn ReaperApi) {
+        if self.scheduled.is_empty() {
+            return;
+        }
+        let position = api.play_position();
+        for mut tone in std::mem::take(&mut self.scheduled) {
+            let jumped_back = matches!(
+                (tone.last_position, position),
+                (Some(last), Some(now)) if now < last
+            );
+            let due = match (&tone.due, position) {
+                (Due::Bar(_), None) | (Due::Stop, None) => true,
+                (Due::Bar(bar), Some(now)) => now >= *bar || jumped_back,
+                (Due::Position(at), Some(now)) => now >= *at,
+                (Due::Position(_), None) | (Due::Stop, Some(_)) => false,
+            };
+            if due {
+                self.apply_set_tone(api, tone.client, tone.cmd);
+            } else {
+                tone.last_position = position;
+                self.scheduled.push(tone);
+            }
+        }
+    }
+
+    /// Drops the glides on `target_fx_guid`, telling their clients why the ack won't come.
+    fn cancel_ramps(&mut self, target_fx_guid: &str) {
+        let (cancelled, kept): (Vec<Ramp>, Vec<Ramp>) = std::mem::take(&mut self.ramps)
+            .into_iter()
+            .partition(|r| r.target_fx_guid == target_fx_guid);
+        self.ramps = kept;
+        for ramp in cancelled {
+            self.metrics.command_coalesced();
+            self.warn(
+                ramp.client,
+                WarningCode::CommandCoalesced,
+                "superseded by a newer command before its ramp finished",
+                Some(ramp.command_id),
+            );
+        }
+    }
+
+    /// Every target that resolves and validates is written, all in one REAPER undo point; the
+    /// others are reported failed in the `multi_ack` without stopping the rest.
+    fn apply_set_tone_multi(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SetToneMulti {
+            command_id,
+            targets,
+            allow_background_projects,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        let mut outcomes: Vec<(String, Result<PreparedTone, String>)> = Vec::new();
+        for target in targets {
+            let guid = target.target_fx_guid.clone();
+            let outcome = if outcomes.iter().any(|(g, _)| *g == guid) {
+                Err("target listed more than once".to_string())
+            } else {
+                self.prepare_tone(
+                    api,
+                    client,
+                    &command_id,
+                    target,
+                    None,
+                    allow_background_projects,
+                )
+                .map_err(|e| e.msg)
+            };
+            outcomes.push((guid, outcome));
+        }
+
+        let ready: Vec<&PreparedTone> = outcomes
+            .iter()
+            .filter_map(|(_, outcome)| outcome.as_ref().ok())
+            .collect();
+        for tone in &ready {
+            self.cancel_ramps(&tone.target_fx_guid);
+        }
+        if !ready.is_empty() {
+            self.take_snapshot(api, &command_id, &ready);
+            self.cache.tones.clear();
+            api.undo_begin_block();
+        }
+        let any_ready = !ready.is_empty();
+
+        let mut unread: Vec<i32> = Vec::new();
+        let results: Vec<TargetResult> = outcomes
+            .into_iter()
+            .map(|(target_fx_guid, outcome)| {
+                let written = outcome.and_then(|tone| {
+                    let unread_before = unread.len();
+                    let (applied, failed) =
+                        write_params(api, tone.track, tone.fx_index, &tone.params, &mut unread);
+                    // Params written but not read back count as written.
+                    let none_written = applied.is_empty() && unread.len() == unread_before;
+                    match failed.first() {
+                        Some(first) if none_written => Err(write_failure_msg(first)),
+                        _ => Ok((applied, failed)),
+                    }
+                });
+                match written {
+                    Ok((applied_params, failed_params)) => TargetResult {
+                        target_fx_guid,
+                        applied_params,
+                        failed_params,
+                        error: None,
+                    },
+                    Err(error) => TargetResult {
+                        target_fx_guid,
+                        applied_params: Vec::new(),
+                        failed_params: Vec::new(),
+                        error: Some(error),
+                    },
+                }
+            })
+            .collect();
+        if any_ready {
+            api.undo_end_block(TONE_UNDO_DESC);
+        }
+        for result in &results {
+            self.remember_applied(api, &result.target_fx_guid, &result.applied_params);
+        }
+
+        self.warn_unread(client, &command_id, &unread);
+        self.send(
+            client,
+            ServerMessage::MultiAck {
+                command_id,
+                results,
+            },
+        );
+    }
+
+    /// Resolves and sanitizes one target of a SetTone, warning the client about anything it
+    /// drops. With `modules`, only their params are kept. Nothing is written yet.
+    fn prepare_tone(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        command_id: &str,
+        target: ToneTarget,
+        modules: Option<&[ModuleId]>,
+        background: bool,
+    ) -> Result<PreparedTone, ToneError> {
+        let ToneTarget {
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        } = target;
+
+        let cached = self.cache.lookup.get(&target_fx_guid).cloned();
+        let Resolved {
+            track,
+            fx_index,
+            background_project,
+        } = match self.cache.resolve_in(api, &target_fx_guid, background) {
+            Ok(r) => r,
+            Err(e) => {
+                let msg = match e {
+                    ResolveError::TargetNotFound(ResolverState::BackgroundProject) => {
+                        "target fx is in a background project tab"
+                    }
+                    _ => "target fx guid not found",
+                };
+                let details = target_not_found_details(api, cached, &self.cache.lookup);
+                return Err(ToneError {
+                    msg: msg.to_string(),
+                    code: ErrorCode::TargetNotFound,
+                    details: Some(details),
+                    diagnostic: Some(e.diagnostic()),
+                });
+            }
+        };
+
+        // The guid still verifies after a plugin update or a rename, but the indices the client
+        // was handed may no longer mean the same params.
+        let layout = FxLayout::read(api, track, fx_index);
+        let expected = self
+            .handshake_layouts
+            .entry(target_fx_guid.clone())
+            .or_insert_with(|| layout.clone());
+        let changes = expected.changes(&layout);
+        if !changes.is_empty() {
+            return Err(ToneError {
+                msg: "target fx changed since the handshake; refresh instances".to_string(),
+                code: ErrorCode::TargetChanged,
+                details: Some(format!("{target_fx_guid}: {}", changes.join(", "))),
+                diagnostic: Some(ErrorDiagnostic::remedy(Remediation::RefreshInstances)),
+            });
+        }
+
+        let mut params = sanitize_params(resolve_relative(api, track, fx_index, params)?)?;
+        let profile = profiles::for_fx(api, track, fx_index);
+
+        let locked: HashSet<i32> = locked_params.into_iter().collect();
+        if matches!(mode, MergeMode::ReplaceActive) {
+            params = apply_replace_active_cleaner(profile.modules, params, &locked);
+        }
+        params.retain(|p| !locked.contains(&p.index));
+        // After the cleaner, so a ReplaceActive tone limited to the cab doesn't bypass the rest.
+        if let Some(modules) = modules {
+            if profile.modules.is_empty() {
+                return Err(ToneError {
+                    msg: "modules filter needs a charted plugin layout".to_string(),
+                    code: ErrorCode::Unsupported,
+                    details: Some(profile.id.to_string()),
+                    diagnostic: None,
+                });
+            }
+            params.retain(|p| modules.iter().any(|m| profile.in_module(m.name(), p.index)));
+        }
+        let skipped_modules = match mode {
+            MergeMode::Merge => {
+                let current = self.current_tone(api, &target_fx_guid, track, fx_index);
+                untouched_modules(profile.modules, &current, &params)
+            }
+            MergeMode::ReplaceActive => Vec::new(),
+        };
+
+        // REAPER appends its own bypass/wet/delta controls after the plugin's params; a SetTone
+        // must never reach those (SetFxMix is the way to drive them).
+        let reserved = validator::reserved_params(api, track, fx_index);
+        let skipped: Vec<String> = params
+            .iter()
+            .filter(|p| reserved.contains(&p.index))
+            .map(|p| p.index.to_string())
+            .collect();
+        if !skipped.is_empty() {
+            params.retain(|p| !reserved.contains(&p.index));
+            self.warn(
+                client,
+                WarningCode::ReservedIndexSkipped,
+                "skipped REAPER-reserved fx controls (bypass/wet/delta)",
+                Some(format!("{command_id}: {}", skipped.join(","))),
+            );
+        }
+
+        let cab_overridden = profile.linked_cab.is_some_and(|(cab_type, amp_linked)| {
+            params.iter().any(|p| p.index == cab_type)
+                && !params.iter().any(|p| p.index == amp_linked)
+                && api
+                    .track_fx_get_param(track, fx_index, amp_linked)
+                    .is_some_and(|v| v >= 0.5)
+        });
+        if cab_overridden {
+            self.warn(
+                client,
+                WarningCode::LinkedCabOverride,
+                "cab is linked to the amp; the plugin may override the requested cab type",
+                Some(command_id.to_string()),
+            );
+        }
+        if let Some(path) = background_project {
+            let name = project_name(&path);
+            self.warn(
+                client,
+                WarningCode::BackgroundProject,
+                &format!(
+                    "{command_id}: {target_fx_guid} is in {}, not the current project tab",
+                    name.as_deref().unwrap_or("an unsaved project")
+                ),
+                name,
+            );
+        }
+
+        Ok(PreparedTone {
+            target_fx_guid,
+            track,
+            fx_index,
+            params,
+            skipped_modules,
+        })
+    }
+
+    fn take_snapshot(&mut self, api: &dyn ReaperApi, command_id: &str, tones: &[&PreparedTone]) {
+        let mut targets = Vec::with_capacity(tones.len());
+        for tone in tones {
+            let params = self
+                .current_tone(api, &tone.target_fx_guid, tone.track, tone.fx_index)
+                .into_iter()
+                .map(|r| ParamChange {
+                    index: r.index,
+                    value: r.value,
+                    op: ParamOp::Set,
+                })
+                .collect();
+            targets.push((tone.target_fx_guid.clone(), params));
+        }
+        // A client reusing a command id gets the newer snapshot.
+        self.snapshots.retain(|s| s.id != command_id);
+        if self.snapshots.len() >= MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(ToneSnapshot {
+            id: command_id.to_string(),
+            targets,
+        });
+    }
+
+    fn restore_snapshot(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::RestoreSnapshot {
+            command_id,
+            snapshot_id,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        let Some(snapshot) = self.snapshots.iter().find(|s| s.id == snapshot_id) else {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "unknown snapshot id".to_string(),
+                    code: ErrorCode::InvalidValue,
+                    details: Some(snapshot_id),
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        };
+        let targets = snapshot.targets.clone();
+
+        // Resolve every target before writing anything, so a vanished FX leaves the rest alone.
+        let mut resolved: Vec<(String, usize, i32, Vec<ParamChange>)> =
+            Vec::with_capacity(targets.len());
+        for (target_fx_guid, params) in targets {
+            let Some((track, fx_index)) =
+                self.ready_target(api, client, &command_id, &target_fx_guid)
+            else {
+                return;
+            };
+            self.cancel_ramps(&target_fx_guid);
+            resolved.push((target_fx_guid, track, fx_index, params));
+        }
+
+        let mut applied_params: Vec<AppliedParam> = Vec::new();
+        let mut failed_params: Vec<FailedParam> = Vec::new();
+        let mut first_failure: Option<ToneError> = None;
+        let mut unread: Vec<i32> = Vec::new();
+        self.cache.tones.clear();
+        api.undo_begin_block();
+        for (target_fx_guid, track, fx_index, params) in &resolved {
+            let (applied, failed) = write_params(api, *track, *fx_index, params, &mut unread);
+            if let (None, Some(first)) = (&first_failure, failed.first()) {
+                first_failure = Some(nothing_written(api, *track, *fx_index, first));
+            }
+            self.remember_applied(api, target_fx_guid, &applied);
+            applied_params.extend(applied);
+            failed_params.extend(failed);
+        }
+        api.undo_end_block(TONE_UNDO_DESC);
+
+        let none_written = applied_params.is_empty() && unread.is_empty();
+        if let (true, Some(e)) = (none_written, first_failure) {
+            self.send(client, e.into_message(&command_id));
+            return;
+        }
+        self.warn_unread(client, &command_id, &unread);
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params,
+                skipped_modules: Vec::new(),
+                failed_params,
+            },
+        );
+    }
+
+    /// Folds what a write read back into the FX's last applied tone and saves it in the project.
+    fn remember_applied(
+        &mut self,
+        api: &dyn ReaperApi,
+        target_fx_guid: &str,
+        applied: &[AppliedParam],
+    ) {
+        if applied.is_empty() {
+            return;
+        }
+        let tone = self
+            .last_applied
+            .entry(target_fx_guid.to_string())
+            .or_default();
+        for p in applied {
+            tone.insert(p.index, p.applied);
+        }
+        let params: Vec<ParamChange> = tone
+            .iter()
+            .map(|(&index, &value)| ParamChange {
+                index,
+                value,
+                op: ParamOp::Set,
+            })
+            .collect();
+        if let Ok(json) = serde_json::to_string(&params) {
+            api.set_proj_ext_state(LAST_TONE_EXT_SECTION, target_fx_guid, &json);
+        }
+    }
+
+    /// Reads the last applied tone of every scanned instance back from the project, which may
+    /// have been reopened (or reverted) since they were written.
+    fn reload_last_applied(&mut self, api: &dyn ReaperApi) {
+        self.last_applied = self
+            .cache
+            .last_instances
+            .iter()
+            .filter_map(|inst| {
+                let json = api.get_proj_ext_state(LAST_TONE_EXT_SECTION, &inst.fx_guid)?;
+                let params: Vec<ParamChange> = serde_json::from_str(&json).ok()?;
+                let tone = params.into_iter().map(|p| (p.index, p.value)).collect();
+                Some((inst.fx_guid.clone(), tone))
+            })
+            .collect();
+    }
+
+    /// Writes `params` as one REAPER undo point and reads each back. On a failed write the error
+    /// has been sent and `None` is returned.
+    fn write_tone(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        track: usize,
+        fx_index: i32,
+        params: &[ParamChange],
+        command_id: &str,
+    ) -> Option<(Vec<AppliedParam>, Vec<FailedParam>)> {
+        let mut unread: Vec<i32> = Vec::new();
+        self.cache.tones.clear();
+        api.undo_begin_block();
+        let (applied, failed) = write_params(api, track, fx_index, params, &mut unread);
+        api.undo_end_block(TONE_UNDO_DESC);
+
+        let none_written = applied.is_empty() && unread.is_empty();
+        if let (true, Some(first)) = (none_written, failed.first()) {
+            let e = nothing_written(api, track, fx_index, first);
+            self.send(client, e.into_message(command_id));
+            return None;
+        }
+        self.warn_unread(client, command_id, &unread);
+        Some((applied, failed))
+    }
+
+    /// `write_tone`, except that a failed write or a readback off target puts every param back
+    /// to its value from before, so the plugin ends up with the whole tone or none of it.
+    fn write_tone_atomic(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        track: usize,
+        fx_index: i32,
+        params: &[ParamChange],
+        command_id: &str,
+    ) -> Option<Vec<AppliedParam>> {
+        let before: Vec<(i32, Option<f64>)> = params
+            .iter()
+            .map(|p| (p.index, api.track_fx_get_param(track, fx_index, p.index)))
+            .collect();
+        let mut unread: Vec<i32> = Vec::new();
+        self.cache.tones.clear();
+        api.undo_begin_block();
+        let (applied, failed) = write_params(api, track, fx_index, params, &mut unread);
+        let error = match failed.first() {
+            Some(first) => Some(nothing_written(api, track, fx_index, first)),
+            None => applied
+                .iter()
+                .find(|p| {
+                    let tolerance = readback_tolerance(self.param_kinds.get(&p.index));
+                    (p.applied - p.requested).abs() > tolerance
+                })
+                .map(off_target),
+        };
+        let Some(mut error) = error else {
+            api.undo_end_block(TONE_UNDO_DESC);
+            self.warn_unread(client, command_id, &unread);
+            return Some(applied);
+        };
+
+        for (index, value) in &before {
+            if let Some(value) = value {
+                let _ = api.track_fx_set_param(track, fx_index, *index, *value);
+            }
+        }
+        api.undo_end_block(TONE_UNDO_DESC);
+        error.msg = format!("{}; all {} params rolled back", error.msg, before.len());
+        self.send(client, error.into_message(command_id));
+        None
+    }
+
+    fn apply_fx_mix(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SetFxMix {
+            command_id,
+            target_fx_guid,
+            wet,
+            delta_solo,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        let changes = match fx_mix_changes(wet, delta_solo) {
+            Ok(changes) => changes,
+            Err(e) => {
+                self.send(client, e.into_message(&command_id));
+                return;
+            }
+        };
+
+        let (track, fx_index) = match self.cache.resolve(api, &target_fx_guid) {
+            Ok(r) => r,
+            Err(e) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "target fx guid not found".to_string(),
+                        code: ErrorCode::TargetNotFound,
+                        details: None,
+                        command_id: Some(command_id.clone()),
+                        diagnostic: Some(e.diagnostic()),
+                    },
+                );
+                return;
+            }
+        };
+
+        let Some(applied_params) =
+            self.write_fx_mix(api, client, track, fx_index, &changes, &command_id)
+        else {
+            return;
+        };
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params,
+                skipped_modules: Vec::new(),
+                failed_params: Vec::new(),
+            },
+        );
+    }
+
+    /// Writes the REAPER-side controls from `fx_mix_changes`, for SetFxMix and SetFxState alike.
+    /// Answers the client itself and returns `None` when one can't be written.
+    fn write_fx_mix(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        track: usize,
+        fx_index: i32,
+        changes: &[(&str, f64)],
+        command_id: &str,
+    ) -> Option<Vec<AppliedParam>> {
+        let mut applied_params: Vec<AppliedParam> = Vec::with_capacity(changes.len());
+        let mut unread: Vec<i32> = Vec::new();
+        for &(ident, value) in changes {
+            let Some(index) = api.track_fx_param_from_ident(track, fx_index, ident) else {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: format!("fx does not expose {ident} (REAPER too old?)"),
+                        code: ErrorCode::Unsupported,
+                        details: None,
+                        command_id: Some(command_id.to_string()),
+                        diagnostic: None,
+                    },
+                );
+                return None;
+            };
+            if let Err(e) = api.track_fx_set_param(track, fx_index, index, value) {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: format!("setting {ident} failed: {e}"),
+                        code: ErrorCode::InternalError,
+                        details: None,
+                        command_id: Some(command_id.to_string()),
+                        diagnostic: Some(ErrorDiagnostic {
+                            param_index: Some(index),
+                            value: Some(value),
+                            ..ErrorDiagnostic::remedy(Remediation::Retry)
+                        }),
+                    },
+                );
+                return None;
+            }
+            applied_params.extend(read_back(api, track, fx_index, index, value, &mut unread));
+        }
+
+        self.warn_unread(client, command_id, &unread);
+        Some(applied_params)
+    }
+
+    fn render_preview(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::RenderPreview {
+            command_id,
+            target_fx_guid,
+            start,
+            length,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        if let Err(e) = self.cache.resolve(api, &target_fx_guid) {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "target fx guid not found".to_string(),
+                    code: ErrorCode::TargetNotFound,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(e.diagnostic()),
+                },
+            );
+            return;
+        }
+
+        let start = start.unwrap_or_else(|| api.loop_range().map_or(0.0, |(s, _)| s));
+        let length = length.unwrap_or(DEFAULT_PREVIEW_SECS).min(MAX_PREVIEW_SECS);
+        if !(start.is_finite() && start >= 0.0 && length.is_finite() && length > 0.0) {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("invalid preview range: start {start}, length {length}"),
+                    code: ErrorCode::InvalidValue,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+
+        let path = self.render_dir.join(preview_file_name(&command_id));
+        let rendered = std::fs::create_dir_all(&self.render_dir)
+            .map_err(|e| format!("creating {}: {e}", self.render_dir.display()))
+            .and_then(|()| api.render_range(start, start + length, &path));
+        if let Err(e) = rendered {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("render failed: {e}"),
+                    code: ErrorCode::InternalError,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+                },
+            );
+            return;
+        }
+
+        self.send(
+            client,
+            ServerMessage::PreviewRendered {
+                command_id,
+                target_fx_guid,
+                path: path.to_string_lossy().into_owned(),
+                start,
+                length,
+            },
+        );
+    }
+
+    /// Play/stop/loop for auditioning a tone. Nothing here touches the plugin, so it doesn't wait
+    /// for validation or the apply queue.
+    fn transport(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let command_id = match cmd {
+            ClientCommand::Play { command_id, .. } => {
+                api.transport_play();
+                command_id
+            }
+            ClientCommand::Stop { command_id, .. } => {
+                api.transport_stop();
+                command_id
+            }
+            ClientCommand::SetLoop {
+                command_id,
+                start,
+                end,
+                ..
+            } => {
+                if !(start.is_finite() && end.is_finite() && start >= 0.0 && end > start) {
+                    self.send(
+                        client,
+                        ServerMessage::Error {
+                            msg: format!("invalid loop {start}..{end} (need 0 <= start < end)"),
+                            code: ErrorCode::InvalidValue,
+                            details: None,
+                            command_id: Some(command_id),
+                            diagnostic: None,
+                        },
+                    );
+                    return;
+                }
+                api.set_loop(start, end);
+                command_id
+            }
+            _ => return,
+        };
+
+        let loop_range = api.loop_range();
+        self.send(
+            client,
+            ServerMessage::Transport {
+                command_id,
+                playing: api.transport_playing(),
+                repeat: api.repeat_enabled(),
+                loop_start: loop_range.map(|(s, _)| s),
+                loop_end: loop_range.map(|(_, e)| e),
+            },
+        );
+    }
+
+    /// Adds the plugin to the requested (or selected) track; `true` once it's in the chain.
+    fn insert_fx(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) -> bool {
+        let ClientCommand::InsertFx {
+            command_id,
+            track_guid,
+            fx_name,
+            ..
+        } = cmd
+        else {
+            return false;
+        };
+
+        let track = match track_guid.as_deref() {
+            Some(guid) => resolver::find_track_by_guid(api, guid),
+            None => api.selected_track(),
+        };
+        let Some(track) = track else {
+            let msg = match track_guid {
+                Some(guid) => format!("track {guid} not found"),
+                None => "no track selected".to_string(),
+            };
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg,
+                    code: ErrorCode::TargetNotFound,
+                    details: None,
+                    command_id: Some(command_id),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::RefreshInstances)),
+                },
+            );
+            return false;
+        };
+
+        let fx_name = fx_name.unwrap_or_else(|| DEFAULT_INSERT_FX_NAME.to_string());
+        api.undo_begin_block();
+        let added = api.track_fx_add_by_name(track, &fx_name);
+        api.undo_end_block(INSERT_FX_UNDO_DESC);
+        if added.is_none() {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("no FX matching \"{fx_name}\" (is the plugin installed?)"),
+                    code: ErrorCode::InvalidValue,
+                    details: Some(resolver::fx_chain_dump(api, track)),
+                    command_id: Some(command_id),
+                    diagnostic: None,
+                },
+            );
+            return false;
+        }
+        true
+    }
+
+    fn apply_fx_state(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SetFxState {
+            command_id,
+            target_fx_guid,
+            enabled,
+            wet,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        let changes = match fx_mix_changes(wet, None) {
+            Ok(changes) => changes,
+            Err(e) => {
+                self.send(client, e.into_message(&command_id));
+                return;
+            }
+        };
+
+        let (track, fx_index) = match self.cache.resolve(api, &target_fx_guid) {
+            Ok(r) => r,
+            Err(e) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "target fx guid not found".to_string(),
+                        code: ErrorCode::TargetNotFound,
+                        details: None,
+                        command_id: Some(command_id.clone()),
+                        diagnostic: Some(e.diagnostic()),
+                    },
+                );
+                return;
+            }
+        };
+
+        if self
+            .write_fx_mix(api, client, track, fx_index, &changes, &command_id)
+            .is_none()
+        {
+            return;
+        }
+        if let Some(on) = enabled {
+            api.track_fx_set_enabled(track, fx_index, on);
+        }
+
+        self.send(
+            client,
+            ServerMessage::FxState {
+                command_id,
+                target_fx_guid,
+                enabled: api.track_fx_get_enabled(track, fx_index),
+                wet: api.track_fx_wet(track, fx_index).map(|w| w as f32),
+            },
+        );
+    }
+
+    fn apply_track_controls(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SetTrackControls {
+            command_id,
+            target_fx_guid,
+            volume_db,
+            pan,
+            rec_monitor,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        if volume_db.is_some_and(|v| !v.is_finite()) || pan.is_some_and(|p| !p.is_finite()) {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "non-finite volume or pan".to_string(),
+                    code: ErrorCode::InvalidValue,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::FixParams)),
+                },
+            );
+            return;
+        }
+
+        let (track, _) = match self.cache.resolve(api, &target_fx_guid) {
+            Ok(r) => r,
+            Err(e) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "target fx guid not found".to_string(),
+                        code: ErrorCode::TargetNotFound,
+                        details: None,
+                        command_id: Some(command_id.clone()),
+                        diagnostic: Some(e.diagnostic()),
+                    },
+                );
+                return;
+            }
+        };
+
+        let mut changes: Vec<(&str, f64)> = Vec::new();
+        if let Some(db) = volume_db {
+            let db = db.clamp(MIN_TRACK_VOLUME_DB, MAX_TRACK_VOLUME_DB);
+            let gain = if db <= MIN_TRACK_VOLUME_DB {
+                0.0
+            } else {
+                10f64.powf(f64::from(db) / 20.0)
+            };
+            changes.push(("D_VOL", gain));
+        }
+        if let Some(p) = pan {
+            changes.push(("D_PAN", f64::from(p.clamp(-1.0, 1.0))));
+        }
+        if let Some(on) = rec_monitor {
+            changes.push(("I_RECMON", if on { 1.0 } else { 0.0 }));
+        }
+        for (key, value) in changes {
+            if !api.track_set_info(track, key, value) {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: format!("setting track {key} failed"),
+                        code: ErrorCode::InternalError,
+                        details: None,
+                        command_id: Some(command_id.clone()),
+                        diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+                    },
+                );
+                return;
+            }
+        }
+
+        self.send(
+            client,
+            ServerMessage::TrackControls {
+                command_id,
+                target_fx_guid,
+                track_guid: api.track_guid(track).unwrap_or_default(),
+                volume_db: api.track_get_info(track, "D_VOL").map(|gain| {
+                    if gain > 0.0 {
+                        (20.0 * gain.log10()).max(f64::from(MIN_TRACK_VOLUME_DB)) as f32
+                    } else {
+                        MIN_TRACK_VOLUME_DB
+                    }
+                }),
+                pan: api.track_get_info(track, "D_PAN").map(|p| p as f32),
+                rec_monitor: api.track_get_info(track, "I_RECMON").map(|m| m >= 0.5),
+            },
+        );
+    }
+
+    fn apply_fx_switch(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SwitchFxPair {
+            command_id,
+            enable_fx_guid,
+            disable_fx_guid,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        if enable_fx_guid == disable_fx_guid {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "enable and disable targets are the same fx".to_string(),
+                    code: ErrorCode::InvalidCommand,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+
+        // Only instances the scan found: any other FX on the track could be switched off too.
+        let known = |guid: &str| self.cache.last_instances.iter().any(|i| i.fx_guid == guid);
+        let unknown = [&enable_fx_guid, &disable_fx_guid]
+            .into_iter()
+            .find(|guid| !known(guid.as_str()));
+        if let Some(guid) = unknown {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("not a Gojira instance: {guid}"),
+                    code: ErrorCode::InvalidCommand,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::RefreshInstances)),
+                },
+            );
+            return;
+        }
+
+        let mut resolved = Vec::with_capacity(2);
+        for guid in [&enable_fx_guid, &disable_fx_guid] {
+            match self.cache.resolve(api, guid) {
+                Ok(r) => resolved.push(r),
+                Err(e) => {
+                    self.send(
+                        client,
+                        ServerMessage::Error {
+                            msg: format!("target fx guid not found: {guid}"),
+                            code: ErrorCode::TargetNotFound,
+                            details: None,
+                            command_id: Some(command_id.clone()),
+                            diagnostic: Some(e.diagnostic()),
+                        },
+                    );
+                    return;
+                }
+            }
+        }
+        let (on_track, on_fx) = resolved[0];
+        let (off_track, off_fx) = resolved[1];
+        if on_track != off_track {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "fx pair must live on the same track".to_string(),
+                    code: ErrorCode::InvalidCommand,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+
+        // Disable first: a brief gap is inaudible next to two amps summing.
+        api.track_fx_set_enabled(off_track, off_fx, false);
+        api.track_fx_set_enabled(on_track, on_fx, true);
+        if !api.track_fx_get_enabled(on_track, on_fx) {
+            // Roll back so the track is never left silent.
+            api.track_fx_set_enabled(off_track, off_fx, true);
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "failed to enable fx; previous instance restored".to_string(),
+                    code: ErrorCode::InternalError,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params: Vec::new(),
+                skipped_modules: Vec::new(),
+                failed_params: Vec::new(),
+            },
+        );
+    }
+
+    /// The instance's tone params, from the cache unless the project changed since they were read.
+    fn current_tone(
+        &mut self,
+        api: &dyn ReaperApi,
+        target_fx_guid: &str,
+        track: usize,
+        fx_index: i32,
+    ) -> Vec<ParamReading> {
+        let change_count = api.project_state_change_count();
+        if let Some(cached) = self.cache.tones.get(target_fx_guid) {
+            if cached.change_count == change_count {
+                return cached.params.clone();
+            }
+        }
+        let params = read_tone(api, track, fx_index);
+        self.cache.tones.insert(
+            target_fx_guid.to_string(),
+            CachedTone {
+                change_count,
+                params: params.clone(),
+            },
+        );
+        params
+    }
+
+    /// Answers from the tone cache when it's current, else reads the FX in slices; false while
+    /// that read is still going, to be finished by `step_tone_read` on later ticks.
+    fn read_current_tone(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        cmd: ClientCommand,
+        budget: &TickBudget,
+    ) -> bool {
+        let ClientCommand::GetCurrentTone {
+            command_id,
+            target_fx_guid,
+            ..
+        } = cmd
+        else {
+            return true;
+        };
+
+        let Some((track, fx_index)) = self.ready_target(api, client, &command_id, &target_fx_guid)
+        else {
+            return true;
+        };
+        let change_count = api.project_state_change_count();
+        if let Some(cached) = self.cache.tones.get(&target_fx_guid) {
+            if cached.change_count == change_count {
+                let params = cached.params.clone();
+                self.send_current_tone(client, command_id, target_fx_guid, params);
+                return true;
+            }
+        }
+        let profile = profiles::for_fx(api, track, fx_index);
+        self.tone_read = Some(ToneRead {
+            client,
+            command_id,
+            target_fx_guid,
+            change_count,
+            last_index: profiles::last_tone_param(api, track, fx_index, profile),
+            next_index: 0,
+            params: Vec::new(),
+        });
+        self.step_tone_read(api, budget)
+    }
+
+    /// Reads the pending GetCurrentTone on, a chunk at a time while the budget lasts; true once
+    /// it has been answered.
+    fn step_tone_read(&mut self, api: &dyn ReaperApi, budget: &TickBudget) -> bool {
+        let Some(mut read) = self.tone_read.take() else {
+            return true;
+        };
+        // The FX may have moved since the last tick, so it's resolved again every time.
+        let (track, fx_index) = match self.cache.resolve(api, &read.target_fx_guid) {
+            Ok(r) => r,
+            Err(e) => {
+                self.send(
+                    read.client,
+                    ServerMessage::Error {
+                        msg: "target fx guid not found".to_string(),
+                        code: ErrorCode::TargetNotFound,
+                        details: Some("fx vanished mid-read".to_string()),
+                        command_id: Some(read.command_id),
+                        diagnostic: Some(e.diagnostic()),
+                    },
+                );
+                return true;
+            }
+        };
+        while read.next_index <= read.last_index {
+            let last = (read.next_index + TONE_READ_CHUNK - 1).min(read.last_index);
+            read.params
+                .extend(read_params(api, track, fx_index, read.next_index..=last));
+            read.next_index = last + 1;
+            if read.next_index <= read.last_index && budget.exhausted() {
+                self.tone_read = Some(read);
+                return false;
+            }
+        }
+
+        self.cache.tones.insert(
+            read.target_fx_guid.clone(),
+            CachedTone {
+                change_count: read.change_count,
+                params: read.params.clone(),
+            },
+        );
+        self.send_current_tone(
+            read.client,
+            read.command_id,
+            read.target_fx_guid,
+            read.params,
+        );
+        true
+    }
+
+    fn send_current_tone(
+        &mut self,
+        client: ClientId,
+        command_id: String,
+        target_fx_guid: String,
+        params: Vec<ParamReading>,
+    ) {
+        let last_applied = self
+            .last_applied
+            .get(&target_fx_guid)
+            .map(|tone| {
+                tone.iter()
+                    .map(|(&index, &value)| ParamChange {
+                        index,
+                        value,
+                        op: ParamOp::Set,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        self.send(
+            client,
+            ServerMessage::CurrentTone {
+                command_id,
+                target_fx_guid,
+                params,
+                last_applied,
+            },
+        );
+    }
+
+    fn preview_tone(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::PreviewTone {
+            command_id,
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        let target = ToneTarget {
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        };
+        let PreparedTone {
+            target_fx_guid,
+            track,
+            fx_index,
+            params,
+            skipped_modules,
+        } = match self.prepare_tone(api, client, &command_id, target, None, false) {
+            Ok(tone) => tone,
+            Err(e) => {
+                self.send(client, e.into_message(&command_id));
+                return;
+            }
+        };
+
+        let rows = params
+            .iter()
+            .map(|p| {
+                let before = api.track_fx_get_param(track, fx_index, p.index);
+                PreviewRow {
+                    index: p.index,
+                    name: api
+                        .track_fx_param_name(track, fx_index, p.index)
+                        .unwrap_or_default(),
+                    before,
+                    before_formatted: before
+                        .and_then(|v| api.track_fx_format_param_value(track, fx_index, p.index, v))
+                        .unwrap_or_default(),
+                    after: p.value,
+                    after_formatted: api
+                        .track_fx_format_param_value(track, fx_index, p.index, p.value)
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        self.send(
+            client,
+            ServerMessage::Preview {
+                command_id,
+                target_fx_guid,
+                rows,
+                skipped_modules,
+            },
+        );
+    }
+
+    fn validate_tone(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::ValidateTone {
+            command_id,
+            target_fx_guid,
+            params,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return;
+        }
+
+        let cached = self.cache.lookup.get(&target_fx_guid).cloned();
+        let (track, fx_index) = match self.cache.resolve(api, &target_fx_guid) {
+            Ok(r) => r,
+            Err(e) => {
+                let details = target_not_found_details(api, cached, &self.cache.lookup);
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "target fx guid not found".to_string(),
+                        code: ErrorCode::TargetNotFound,
+                        details: Some(details),
+                        command_id: Some(command_id),
+                        diagnostic: Some(e.diagnostic()),
+                    },
+                );
+                return;
+            }
+        };
+
+        let params = match resolve_relative(api, track, fx_index, params) {
+            Ok(params) => params,
+            Err(e) => {
+                self.send(client, e.into_message(&command_id));
+                return;
+            }
+        };
+        let profile = profiles::for_fx(api, track, fx_index);
+        let warnings = validator::validate_tone(
+            api,
+            track,
+            fx_index,
+            profile,
+            &params,
+            &self.param_kinds,
+            &self.param_enums,
+        );
+        self.send(
+            client,
+            ServerMessage::ToneValidation {
+                command_id,
+                target_fx_guid,
+                warnings,
+            },
+        );
+    }
+
+    fn subscribe(&mut self, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::Subscribe {
+            target_fx_guid,
+            indices,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if let Some(bad) = indices.iter().find(|i| !(0..=MAX_PARAM_INDEX).contains(*i)) {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("invalid param index: {bad}"),
+                    code: ErrorCode::InvalidValue,
+                    details: None,
+                    command_id: None,
+                    diagnostic: Some(ErrorDiagnostic {
+                        param_index: Some(*bad),
+                        ..ErrorDiagnostic::remedy(Remediation::FixParams)
+                    }),
+                },
+            );
+            return;
+        }
+
+        let subs = self.subscriptions.entry(client).or_default();
+        if indices.is_empty() {
+            subs.remove(&target_fx_guid);
+        } else {
+            subs.insert(target_fx_guid, indices.into_iter().collect());
+        }
+        if subs.is_empty() {
+            self.subscriptions.remove(&client);
+        }
+        self.prune_watched_values();
+    }
+
+    /// Answered from the probed options, so it never waits on the apply queue.
+    fn search_enum_labels(&mut self, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SearchEnumLabels {
+            command_id,
+            index,
+            query,
+            offset,
+            limit,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        let needle = query.trim().to_lowercase();
+        let matches: Vec<&ParamEnumOption> = self
+            .param_enums
+            .get(&index)
+            .map(|opts| {
+                opts.iter()
+                    .filter(|o| o.label.to_lowercase().contains(&needle))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let total = matches.len();
+        let limit = limit.unwrap_or(ENUM_PAGE_SIZE).min(MAX_ENUM_PAGE);
+        let options = matches
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        self.send(
+            client,
+            ServerMessage::ParamEnumPage {
+                command_id,
+                index,
+                query,
+                offset,
+                total,
+                options,
+            },
+        );
+    }
+
+    /// Forgets the last values of FXs nobody is subscribed to any more.
+    fn prune_watched_values(&mut self) {
+        let subscribed: HashSet<&String> = self
+            .subscriptions
+            .values()
+            .flat_map(|subs| subs.keys())
+            .collect();
+        self.watched_values
+            .retain(|guid, _| subscribed.contains(guid));
+    }
+
+    /// Reads every subscribed param and pushes the ones that moved since the last poll. The first
+    /// poll after a subscription only records a baseline.
+    fn poll_subscriptions(&mut self, api: &dyn ReaperApi) {
+        if !self.validation_ready || self.subscriptions.is_empty() {
+            return;
+        }
+
+        let mut wanted: HashMap<String, HashSet<i32>> = HashMap::new();
+        for subs in self.subscriptions.values() {
+            for (guid, indices) in subs {
+                wanted.entry(guid.clone()).or_default().extend(indices);
+            }
+        }
+
+        for (guid, indices) in wanted {
+            let Ok((track, fx_index)) = self.cache.resolve(api, &guid) else {
+                continue;
+            };
+            let first_poll = !self.watched_values.contains_key(&guid);
+            let last = self.watched_values.entry(guid.clone()).or_default();
+            let mut changed: HashMap<i32, ParamReading> = HashMap::new();
+            for index in indices {
+                let Some(value) = api.track_fx_get_param(track, fx_index, index) else {
+                    continue;
+                };
+                let moved = last
+                    .insert(index, value)
+                    .is_some_and(|prev| (prev - value).abs() > PARAM_CHANGE_EPSILON);
+                if moved && !first_poll {
+                    let formatted = api
+                        .track_fx_format_param_value(track, fx_index, index, value)
+                        .unwrap_or_default();
+                    changed.insert(
+                        index,
+                        ParamReading {
+                            index,
+                            value,
+                            formatted,
+                        },
+                    );
+                }
+            }
+            if changed.is_empty() {
+                continue;
+            }
+            // Moved outside the DLL, e.g. in the plugin GUI.
+            self.cache.tones.remove(&guid);
+
+            let recipients: Vec<(ClientId, Vec<ParamReading>)> = self
+                .subscriptions
+                .iter()
+                .filter_map(|(client, subs)| {
+                    let mut params: Vec<ParamReading> = subs
+                        .get(&guid)?
+                        .iter()
+                        .filter_map(|i| changed.get(i).cloned())
+                        .collect();
+                    if params.is_empty() {
+                        return None;
+                    }
+                    params.sort_by_key(|p| p.index);
+                    Some((*client, params))
+                })
+                .collect();
+            for (client, params) in recipients {
+                self.send(
+                    client,
+                    ServerMessage::ParamChanged {
+                        target_fx_guid: guid.clone(),
+                        params,
+                    },
+                );
+            }
+        }
+    }
+
+    fn save_preset(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::SavePreset {
+            command_id,
+            target_fx_guid,
+            name,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        let Some(store) = self.presets.clone() else {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "preset storage unavailable (no config folder)".to_string(),
+                    code: ErrorCode::InvalidCommand,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        };
+        let name = match presets::validate_name(&name) {
+            Ok(n) => n.to_string(),
+            Err(msg) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg,
+                        code: ErrorCode::InvalidValue,
+                        details: None,
+                        command_id: Some(command_id.clone()),
+                        diagnostic: None,
+                    },
+                );
+                return;
+            }
+        };
+        let Some((track, fx_index)) = self.ready_target(api, client, &command_id, &target_fx_guid)
+        else {
+            return;
+        };
+
+        let preset = StoredPreset {
+            name,
+            fx_name: api.track_fx_name(track, fx_index),
+            params: self
+                .current_tone(api, &target_fx_guid, track, fx_index)
+                .into_iter()
+                .map(|r| ParamChange {
+                    index: r.index,
+                    value: r.value,
+                    op: ParamOp::Set,
+                })
+                .collect(),
+        };
+        if let Err(e) = store.save(&preset) {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("saving preset {:?} failed", preset.name),
+                    code: ErrorCode::InternalError,
+                    details: Some(e),
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params: Vec::new(),
+                skipped_modules: Vec::new(),
+                failed_params: Vec::new(),
+            },
+        );
+    }
+
+    fn load_preset(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::LoadPreset {
+            command_id,
+            target_fx_guid,
+            name,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        let Some((track, fx_index)) = self.ready_target(api, client, &command_id, &target_fx_guid)
+        else {
+            return;
+        };
+        self.cancel_ramps(&target_fx_guid);
+
+        let saved = match (&self.presets, presets::validate_name(&name)) {
+            (Some(store), Ok(valid)) => store.load(valid),
+            // Not a name we could have saved, but it may still be one of the plugin's own.
+            _ => Ok(None),
+        };
+        let (applied_params, failed_params) = match saved {
+            Ok(Some(preset)) => {
+                let Some((applied, failed)) =
+                    self.write_tone(api, client, track, fx_index, &preset.params, &command_id)
+                else {
+                    return;
+                };
+                self.remember_applied(api, &target_fx_guid, &applied);
+                (applied, failed)
+            }
+            Ok(None) => {
+                self.cache.tones.clear();
+                api.undo_begin_block();
+                let loaded = api.track_fx_set_preset(track, fx_index, &name);
+                api.undo_end_block(TONE_UNDO_DESC);
+                if !loaded {
+                    self.send(
+                        client,
+                        ServerMessage::Error {
+                            msg: format!("no saved or plugin preset named {name:?}"),
+                            code: ErrorCode::InvalidValue,
+                            details: None,
+                            command_id: Some(command_id.clone()),
+                            diagnostic: None,
+                        },
+                    );
+                    return;
+                }
+                // The plugin picked the values, so the whole tone is what was applied.
+                let loaded: Vec<AppliedParam> = self
+                    .current_tone(api, &target_fx_guid, track, fx_index)
+                    .into_iter()
+                    .map(|r| AppliedParam {
+                        index: r.index,
+                        requested: r.value,
+                        applied: r.value,
+                        formatted: r.formatted,
+                    })
+                    .collect();
+                self.last_applied.remove(&target_fx_guid);
+                self.remember_applied(api, &target_fx_guid, &loaded);
+                (Vec::new(), Vec::new())
+            }
+            Err(e) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: format!("reading preset {name:?} failed"),
+                        code: ErrorCode::InternalError,
+                        details: Some(e),
+                        command_id: Some(command_id.clone()),
+                        diagnostic: None,
+                    },
+                );
+                return;
+            }
+        };
+
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params,
+                skipped_modules: Vec::new(),
+                failed_params,
+            },
+        );
+    }
+
+    fn list_presets(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::ListPresets {
+            command_id,
+            target_fx_guid,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        let Some((track, fx_index)) = self.ready_target(api, client, &command_id, &target_fx_guid)
+        else {
+            return;
+        };
+        let saved = match self.presets.as_ref().map(|store| store.list()) {
+            None => Vec::new(),
+            Some(Ok(names)) => names,
+            Some(Err(e)) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "listing saved presets failed".to_string(),
+                        code: ErrorCode::InternalError,
+                        details: Some(e),
+                        command_id: Some(command_id.clone()),
+                        diagnostic: None,
+                    },
+                );
+                return;
+            }
+        };
+
+        self.send(
+            client,
+            ServerMessage::PresetList {
+                command_id,
+                target_fx_guid,
+                saved,
+                current_native: api.track_fx_get_preset(track, fx_index),
+            },
+        );
+    }
+
+    fn store_tone_slot(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::StoreToneSlot {
+            command_id,
+            slot,
+            name,
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        if slot >= tone_slots::MAX_SLOTS {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("slot {slot} is out of range (0..{})", tone_slots::MAX_SLOTS),
+                    code: ErrorCode::InvalidValue,
+                    details: None,
+                    command_id: Some(command_id),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+        let params = match sanitize_params(params) {
+            Ok(params) => params,
+            Err(e) => {
+                self.send(client, e.into_message(&command_id));
+                return;
+            }
+        };
+        let tone = (!params.is_empty()).then(|| ToneSlot {
+            name: name.trim().to_string(),
+            target_fx_guid,
+            mode,
+            params,
+            locked_params,
+        });
+        self.tone_slots.store(api, slot, tone);
+
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params: Vec::new(),
+                skipped_modules: Vec::new(),
+                failed_params: Vec::new(),
+            },
+        );
+    }
+
+    /// Replays the slot's tone as a SetTone under this command's id, so the ack or error it
+    /// gets is the one a SetTone would.
+    fn activate_tone_slot(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::ActivateToneSlot {
+            command_id,
+            slot,
+            ramp_ms,
+            apply_at,
+            ..
+        } = cmd
+        else {
+            return;
+        };
+
+        let Some(tone) = self.tone_slots.get(slot).cloned() else {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: format!("no tone stored in slot {slot}"),
+                    code: ErrorCode::InvalidValue,
+                    details: None,
+                    command_id: Some(command_id),
+                    diagnostic: None,
+                },
+            );
+            return;
+        };
+        self.tone_slots.set_active(slot);
+        let set_tone = ClientCommand::SetTone {
+            session_token: String::new(),
+            command_id,
+            target_fx_guid: tone.target_fx_guid,
+            mode: tone.mode,
+            params: tone.params,
+            locked_params: tone.locked_params,
+            ramp_ms,
+            atomic: false,
+            apply_at,
+            modules: None,
+            allow_background_projects: false,
+        };
+        if apply_at.is_some() {
+            self.schedule_tone(api, client, set_tone);
+        } else {
+            self.apply_set_tone(api, client, set_tone);
+        }
+    }
+
+    /// Runs one of the DLL's entries in REAPER's action list.
+    pub fn run_action(&mut self, api: &dyn ReaperApi, action: ReaperAction) {
+        telemetry::info("main_loop", format!("action {action:?}"));
+        match action {
+            ReaperAction::RefreshInstances => {
+                let everyone: Vec<ClientId> = self.sessions.keys().copied().collect();
+                self.refresh_and_handshake(api, &everyone);
+            }
+            ReaperAction::RevertLastTone => {
+                let Some(snapshot_id) = self.snapshots.back().map(|s| s.id.clone()) else {
+                    telemetry::info("main_loop", "no tone to revert");
+                    return;
+                };
+                self.enqueue_local(
+                    api,
+                    ClientCommand::RestoreSnapshot {
+                        session_token: String::new(),
+                        command_id: format!("action-revert-{snapshot_id}"),
+                        snapshot_id,
+                    },
+                );
+            }
+            ReaperAction::ShowStatus => api.show_console_msg(&self.status_report()),
+            ReaperAction::NextToneSlot => {
+                self.ensure_scanned(api);
+                let Some(slot) = self.tone_slots.next() else {
+                    telemetry::info("main_loop", "no tone slots stored");
+                    return;
+                };
+                let cmd = activate_slot_command(format!("action-slot-{slot}"), slot);
+                self.enqueue_local(api, cmd);
+            }
+        }
+    }
+
+    /// Queues a command that started inside REAPER rather than at a client.
+    fn enqueue_local(&mut self, api: &dyn ReaperApi, cmd: ClientCommand) {
+        self.ensure_scanned(api);
+        self.note_command(LOCAL_CLIENT, &cmd);
+        self.enqueue(LOCAL_CLIENT, cmd);
+    }
+
+    fn note_command(&mut self, client: ClientId, cmd: &ClientCommand) {
+        let from = match self.peers.get(&client) {
+            Some(peer) => peer.as_str(),
+            None if client == LOCAL_CLIENT => "REAPER",
+            None => "?",
+        };
+        let what = match cmd.command_id() {
+            Some(id) => format!("{} {id} from {from}", cmd.kind()),
+            None => format!("{} from {from}", cmd.kind()),
+        };
+        self.last_command = Some(StatusEvent::now(what));
+    }
+
+    /// What "Show status" prints to REAPER's console: whether anyone is connected, what they
+    /// last asked for, what last went wrong and which instance commands land on.
+    pub fn status_report(&self) -> String {
+        let mut out = String::from("Gojira Sidecar\n");
+        let listening = self.server_addr.as_deref().unwrap_or("(network off)");
+        match &self.tls_fingerprint {
+            Some(fp) => out.push_str(&format!("  listening: {listening} (wss, {fp})\n")),
+            None => out.push_str(&format!("  listening: {listening}\n")),
+        }
+        let mut peers: Vec<&str> = self
+            .sessions
+            .keys()
+            .filter_map(|id| self.peers.get(id))
+            .map(String::as_str)
+            .collect();
+        peers.sort_unstable();
+        if peers.is_empty() {
+            out.push_str("  clients: none connected\n");
+        } else {
+            out.push_str(&format!("  clients: {}\n", peers.join(", ")));
+        }
+        let probed = if self.validation_ready {
+            ""
+        } else {
+            ", not probed yet"
+        };
+        match self.cache.last_instances.first() {
+            Some(first) => out.push_str(&format!(
+                "  instance: {} on \"{}\" ({} found{probed})\n",
+                first.fx_name,
+                first.track_name,
+                self.cache.last_instances.len(),
+            )),
+            None => out.push_str("  instance: none found\n"),
+        }
+        if let Some(slot) = self.tone_slots.active() {
+            let name = self.tone_slots.get(slot).map_or("", |t| t.name.as_str());
+            out.push_str(&format!("  tone slot: {slot} {name}\n"));
+        }
+        for (label, event) in [
+            ("last command", &self.last_command),
+            ("last error", &self.last_error),
+        ] {
+            match event {
+                Some(e) => out.push_str(&format!(
+                    "  {label}: {} ({}s ago)\n",
+                    e.what,
+                    e.at.elapsed().as_secs()
+                )),
+                None => out.push_str(&format!("  {label}: none\n")),
+            }
+        }
+        out
+    }
+
+    /// Scans and probes the project unless a handshake already has. Foot controllers and
+    /// shortcuts work with no client connected, so nobody else may have.
+    fn ensure_scanned(&mut self, api: &dyn ReaperApi) {
+        if !self.validation_ready {
+            self.refresh_and_handshake(api, &[]);
+        }
+    }
+
+    /// Queues the slot the latest MIDI switch picked.
+    fn take_midi(&mut self, api: &dyn ReaperApi) {
+        let Some((rx, map)) = &self.midi else {
+            return;
+        };
+        // An expression pedal on the slot CC sends a run of values; only where it stopped counts.
+        let Some(slot) = rx.try_iter().filter_map(|msg| map.slot_for(msg)).last() else {
+            return;
+        };
+        telemetry::info("main_loop", format!("MIDI switch to tone slot {slot}"));
+        let cmd = activate_slot_command(format!("midi-slot-{slot}"), slot);
+        self.enqueue_local(api, cmd);
+    }
+
+    fn list_tone_slots(&mut self, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::ListToneSlots { command_id, .. } = cmd else {
+            return;
+        };
+        self.send(
+            client,
+            ServerMessage::ToneSlots {
+                command_id,
+                slots: self.tone_slots.list(),
+                active_slot: self.tone_slots.active(),
+            },
+        );
+    }
+
+    /// Resolves a command's target FX, answering the client with the error if it can't be used.
+    fn ready_target(
+        &mut self,
+        api: &dyn ReaperApi,
+        client: ClientId,
+        command_id: &str,
+        target_fx_guid: &str,
+    ) -> Option<(usize, i32)> {
+        if !self.validation_ready {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "not ready (handshake/validation required)".to_string(),
+                    code: ErrorCode::NotReady,
+                    details: None,
+                    command_id: Some(command_id.to_string()),
+                    diagnostic: Some(ErrorDiagnostic::remedy(Remediation::CompleteHandshake)),
+                },
+            );
+            return None;
+        }
+
+        match self.cache.resolve(api, target_fx_guid) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "target fx guid not found".to_string(),
+                        code: ErrorCode::TargetNotFound,
+                        details: None,
+                        command_id: Some(command_id.to_string()),
+                        diagnostic: Some(e.diagnostic()),
+                    },
+                );
+                None
+            }
+        }
+    }
+
+    fn undo_last_tone(&mut self, api: &dyn ReaperApi, client: ClientId, cmd: ClientCommand) {
+        let ClientCommand::UndoLastTone { command_id, .. } = cmd else {
+            return;
+        };
+
+        match api.undo_next_desc() {
+            Some(desc) if desc == TONE_UNDO_DESC => {}
+            Some(desc) => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "last undo point is not a Gojira tone".to_string(),
+                        code: ErrorCode::InvalidCommand,
+                        details: Some(desc),
+                        command_id: Some(command_id.clone()),
+                        diagnostic: None,
+                    },
+                );
+                return;
+            }
+            None => {
+                self.send(
+                    client,
+                    ServerMessage::Error {
+                        msg: "nothing to undo".to_string(),
+                        code: ErrorCode::InvalidCommand,
+                        details: None,
+                        command_id: Some(command_id.clone()),
+                        diagnostic: None,
+                    },
+                );
+                return;
+            }
+        }
+
+        self.cache.tones.clear();
+        if !api.undo_do() {
+            self.send(
+                client,
+                ServerMessage::Error {
+                    msg: "REAPER refused the undo".to_string(),
+                    code: ErrorCode::InternalError,
+                    details: None,
+                    command_id: Some(command_id.clone()),
+                    diagnostic: None,
+                },
+            );
+            return;
+        }
+
+        self.send(
+            client,
+            ServerMessage::Ack {
+                command_id,
+                applied_params: Vec::new(),
+                skipped_modules: Vec::new(),
+                failed_params: Vec::new(),
+            },
+        );
+    }
+
+    fn warn_unread(&mut self, client: ClientId, command_id: &str, unread: &[i32]) {
+        if unread.is_empty() {
+            return;
+        }
+        let indices: Vec<String> = unread.iter().map(|i| i.to_string()).collect();
+        self.warn(
+            client,
+            WarningCode::ReadbackUnavailable,
+            "could not read back some written params; their applied values are unverified",
+            Some(format!("{command_id}: {}", indices.join(","))),
+        );
+    }
+
+    fn warn(&mut self, client: ClientId, code: WarningCode, msg: &str, context: Option<String>) {
+        self.send(
+            client,
+            ServerMessage::Warning {
+                code,
+                msg: msg.to_string(),
+                context,
+            },
+        );
+    }
+
+    fn send(&mut self, client_id: ClientId, msg: ServerMessage) {
+        self.queue(OutboundMsg::Send { client_id, msg });
+    }
+
+    /// Sends what went out while nobody was connected to the clients that just got their
+    /// handshake, then forgets it.
+    fn replay_to(&mut self, clients: &[ClientId]) {
+        if clients.is_empty() || self.replay.is_empty() {
+            return;
+        }
+        let events: Vec<ReplayedEvent> = self
+            .replay
+            .drain(..)
+            .map(|(at, message)| ReplayedEvent {
+                age_ms: at.elapsed().as_millis() as u64,
+                message,
+            })
+            .collect();
+        for &client in clients {
+            self.send(
+                client,
+                ServerMessage::Replay {
+                    events: events.clone(),
+                },
+            );
+        }
+    }
+
+    /// Keeps a message no client will hear for `replay_to`: a broadcast while nobody is
+    /// connected, or the answer to a command started inside REAPER.
+    fn record_for_replay(&mut self, out: &OutboundMsg) {
+        if !self.sessions.is_empty() {
+            return;
+        }
+        let msg = match out {
+            OutboundMsg::Broadcast { msg } => msg,
+            OutboundMsg::Send { client_id, msg } if *client_id == LOCAL_CLIENT => msg,
+            OutboundMsg::Send { .. } => return,
+        };
+        if matches!(msg, ServerMessage::Shutdown { .. }) {
+            return;
+        }
+        if self.replay.len() == REPLAY_CAP {
+            self.replay.pop_front();
+        }
+        self.replay.push_back((Instant::now(), msg.clone()));
+    }
+
+    fn broadcast(&mut self, msg: ServerMessage) {
+        self.queue(OutboundMsg::Broadcast { msg });
+    }
+
+    fn queue(&mut self, out: OutboundMsg) {
+        let (OutboundMsg::Send { msg, .. } | OutboundMsg::Broadcast { msg }) = &out;
+        match msg {
+            ServerMessage::Ack { command_id, .. } => {
+                self.metrics.applied();
+                telemetry::debug("main_loop", format!("{command_id}: applied"));
+            }
+            ServerMessage::Error {
+                msg, command_id, ..
+            } => {
+                self.metrics.rejected();
+                let command = command_id.as_deref().unwrap_or("-");
+                telemetry::warn("main_loop", format!("{command}: {msg}"));
+                self.last_error = Some(StatusEvent::now(format!("{command}: {msg}")));
+            }
+            _ => {}
+        }
+        self.record_for_replay(&out);
+        // Non-blocking best-effort. If outbound is full, ProjectChanged is acceptable to drop.
+        let queued = self
+            .outbound_tx
+            .try_send(out)
+            .ok()
+            .map(|()| self.outbound_tx.len());
+        self.metrics.outbound(queued);
+    }
+}
+
+/// Every tone param the FX reports, up to the last one its profile counts as tone.
+fn read_tone(api: &dyn ReaperApi, track: usize, fx_index: i32) -> Vec<ParamReading> {
+    let profile = profiles::for_fx(api, track, fx_index);
+    let last = profiles::last_tone_param(api, track, fx_index, profile);
+    read_params(api, track, fx_index, 0..=last)
+}
+
+fn read_params(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    indices: RangeInclusive<i32>,
+) -> Vec<ParamReading> {
+    indices
+        .filter_map(|index| {
+            let value = api.track_fx_get_param(track, fx_index, index)?;
+            let formatted = api
+                .track_fx_format_param_value(track, fx_index, index, value)
+                .unwrap_or_default();
+            Some(ParamReading {
+                index,
+                value,
+                formatted,
+            })
+        })
+        .collect()
+}
+
+fn tone_moved(before: &[ParamReading], after: &[ParamReading]) -> bool {
+    before.len() != after.len()
+        || before
+            .iter()
+            .zip(after)
+            .any(|(b, a)| b.index != a.index || (b.value - a.value).abs() > PARAM_CHANGE_EPSILON)
+}
+
+/// Writes `params` in order and reads back each one REAPER took. A rejected write doesn't stop
+/// the rest, so the plugin is never left half way through a tone with nothing said about it;
+/// the rejected ones come back second.
+fn write_params(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    params: &[ParamChange],
+    unread: &mut Vec<i32>,
+) -> (Vec<AppliedParam>, Vec<FailedParam>) {
+    let mut applied_params: Vec<AppliedParam> = Vec::with_capacity(params.len());
+    let mut failed_params: Vec<FailedParam> = Vec::new();
+    for p in params {
+        if let Err(error) = api.track_fx_set_param(track, fx_index, p.index, p.value) {
+            failed_params.push(FailedParam {
+                index: p.index,
+                value: p.value,
+                error,
+            });
+            continue;
+        }
+
+        applied_params.extend(read_back(api, track, fx_index, p.index, p.value, unread));
+    }
+    (applied_params, failed_params)
+}
+
+/// The `:wet` and `:delta` writes a SetFxMix or SetFxState asks for, with `wet` clamped to 0..1.
+fn fx_mix_changes(
+    wet: Option<f32>,
+    delta_solo: Option<bool>,
+) -> Result<Vec<(&'static str, f64)>, ToneError> {
+    if wet.is_some_and(|w| !w.is_finite()) {
+        return Err(ToneError {
+            msg: "non-finite wet value".to_string(),
+            code: ErrorCode::InvalidValue,
+            details: None,
+            diagnostic: Some(ErrorDiagnostic::remedy(Remediation::FixParams)),
+        });
+    }
+    let mut changes = Vec::new();
+    if let Some(w) = wet {
+        changes.push((":wet", f64::from(w.clamp(0.0, 1.0))));
+    }
+    if let Some(d) = delta_solo {
+        changes.push((":delta", if d { 1.0 } else { 0.0 }));
+    }
+    Ok(changes)
+}
+
+fn write_failure_msg(failed: &FailedParam) -> String {
+    format!("apply failed at param {}: {}", failed.index, failed.error)
+}
+
+/// The error for a command none of whose params could be written, with the params around the
+/// first one that failed.
+fn nothing_written(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    first: &FailedParam,
+) -> ToneError {
+    let window = (first.index - PARAM_DUMP_RADIUS).max(0)..=(first.index + PARAM_DUMP_RADIUS);
+    ToneError {
+        msg: write_failure_msg(first),
+        code: ErrorCode::InternalError,
+        details: Some(validator::window_dump(api, track, fx_index, window)),
+        diagnostic: Some(ErrorDiagnostic {
+            param_index: Some(first.index),
+            value: Some(first.value),
+            ..ErrorDiagnostic::remedy(Remediation::Retry)
+        }),
+    }
+}
+
+/// Start of the first bar after `position` (seconds), at the project's tempo and meter.
+fn next_bar_after(api: &dyn ReaperApi, position: f64) -> Option<f64> {
+    let (bpm, beats_per_measure) = api.project_tempo()?;
+    let bar = 60.0 / f64::from(bpm) * f64::from(beats_per_measure);
+    (bar > 0.0).then(|| ((position / bar).floor() + 1.0) * bar)
+}
+
+/// The error for an atomic SetTone whose param landed somewhere other than requested.
+fn off_target(p: &AppliedParam) -> ToneError {
+    ToneError {
+        msg: format!(
+            "param {} read back {:.4}, not the requested {:.4}",
+            p.index, p.applied, p.requested
+        ),
+        code: ErrorCode::InternalError,
+        details: None,
+        diagnostic: Some(ErrorDiagnostic {
+            param_index: Some(p.index),
+            value: Some(p.requested),
+            ..ErrorDiagnostic::remedy(Remediation::Retry)
+        }),
+    }
+}
+
+/// How far a readback may land from the requested value before an atomic SetTone rolls back.
+/// The plugin snaps toggles and stepped params to a position, so those get half a step.
+fn readback_tolerance(kind: Option<&ParamKind>) -> f64 {
+    match kind {
+        Some(ParamKind::Toggle) => 0.5,
+        Some(ParamKind::Stepped { steps }) if *steps > 1 => 0.5 / f64::from(steps - 1),
+        _ => ATOMIC_READBACK_TOLERANCE,
+    }
+}
+
+/// Reads a written param back. If REAPER can't report it, the index goes to `unread` instead,
+/// so the caller flags it rather than passing the requested value off as applied.
+fn read_back(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    index: i32,
+    requested: f64,
+    unread: &mut Vec<i32>,
+) -> Option<AppliedParam> {
+    let Some(applied) = api.track_fx_get_param(track, fx_index, index) else {
+        unread.push(index);
+        return None;
+    };
+    let formatted = api
+        .track_fx_format_param_value(track, fx_index, index, applied)
+        .unwrap_or_default();
+    Some(AppliedParam {
+        index,
+        requested,
+        applied,
+        formatted,
+    })
+}
+
+fn project_tempo(api: &dyn ReaperApi) -> Option<ProjectTempo> {
+    api.project_tempo()
+        .map(|(bpm, beats_per_measure)| ProjectTempo {
+            bpm,
+            beats_per_measure,
+        })
+}
+
+/// Enum options cut to their first page, with the full count of every enum that was cut.
+fn first_enum_pages(
+    enums: &HashMap<i32, Vec<ParamEnumOption>>,
+) -> (HashMap<i32, Vec<ParamEnumOption>>, HashMap<i32, usize>) {
+    let mut pages = HashMap::with_capacity(enums.len());
+    let mut totals = HashMap::new();
+    for (idx, opts) in enums {
+        if opts.len() > ENUM_PAGE_SIZE {
+            totals.insert(*idx, opts.len());
+        }
+        pages.insert(*idx, opts.iter().take(ENUM_PAGE_SIZE).cloned().collect());
+    }
+    (pages, totals)
+}
+
+/// File name of a project without the `.rpp`; `None` while it's unsaved.
+fn project_name(path: &str) -> Option<String> {
+    let stem = std::path::Path::new(path).file_stem()?;
+    Some(stem.to_string_lossy().into_owned())
+}
+
+/// `preview-<command id>.wav`, with anything that isn't safe in a file name replaced.
+fn preview_file_name(command_id: &str) -> String {
+    let id: String = command_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("preview-{id}.wav")
+}
+
+/// Instances in `next` but not `prev`, those gone from `next`, and those in both whose track name,
+/// FX name or position changed (as they are in `next`). Matched by FX guid.
+/// `ActivateToneSlot` as MIDI and the action list send it: at once, without a glide.
+fn activate_slot_command(command_id: String, slot: u32) -> ClientCommand {
+    ClientCommand::ActivateToneSlot {
+        session_token: String::new(),
+        command_id,
+        slot,
+        ramp_ms: None,
+        apply_at: None,
+    }
+}
+
+fn diff_instances(
+    prev: &[GojiraInstance],
+    next: &[GojiraInstance],
+) -> (Vec<GojiraInstance>, Vec<GojiraInstance>, Vec<GojiraInstance>) {
+    let mut added = Vec::new();
+    let mut renamed = Vec::new();
+    for inst in next {
+        match prev.iter().find(|p| p.fx_guid == inst.fx_guid) {
+            None => added.push(inst.clone()),
+            Some(old) => {
+                if old.track_name != inst.track_name
+                    || old.fx_name != inst.fx_name
+                    || old.track_guid != inst.track_guid
+                    || old.last_known_fx_index != inst.last_known_fx_index
+                {
+                    renamed.push(inst.clone());
+                }
+            }
+        }
+    }
+    let removed = prev
+        .iter()
+        .filter(|p| !next.iter().any(|n| n.fx_guid == p.fx_guid))
+        .cloned()
+        .collect();
+    (added, removed, renamed)
+}
+
+/// Context for a failed resolve: what sits at the previously cached location (if any) and which
+/// Gojira instances a fresh scan found, so index/GUID drift is visible from one error report.
+fn target_not_found_details(
+    api: &dyn ReaperApi,
+    cached: Option<(String, i32)>,
+    fresh: &FxLookup,
+) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    match cached {
+        Some((track_guid, fx_index)) => match resolver::find_track_by_guid(api, &track_guid) {
+            Some(track) => parts.push(format!(
+                "cached at fx {fx_index} on track {track_guid}; fx chain: {}",
+                resolver::fx_chain_dump(api, track)
+            )),
+            None => parts.push(format!("cached track {track_guid} no longer exists")),
+        },
+        None => parts.push("no cached location".to_string()),
+    }
+
+    let mut known: Vec<String> = fresh
+        .iter()
+        .map(|(fx_guid, (_, idx))| format!("{fx_guid}@{idx}"))
+        .collect();
+    known.sort();
+    if known.is_empty() {
+        parts.push("scan found no instances".to_string());
+    } else {
+        parts.push(format!("scan found: {}", known.join(", ")));
+    }
+    parts.join("; ")
+}
+
+/// Commands that change the plugin's state, and so count against the max apply rate.
+fn is_write(cmd: &ClientCommand) -> bool {
+    matches!(
+        cmd,
+        ClientCommand::SetTone { .. }
+            | ClientCommand::SetToneMulti { .. }
+            | ClientCommand::UndoLastTone { .. }
+            | ClientCommand::RestoreSnapshot { .. }
+            | ClientCommand::LoadPreset { .. }
+            | ClientCommand::ActivateToneSlot { .. }
+            | ClientCommand::SetFxMix { .. }
+            | ClientCommand::SetFxState { .. }
+            | ClientCommand::SetTrackControls { .. }
+            | ClientCommand::SwitchFxPair { .. }
+    )
+}
+
+/// Turns `add` changes into `set` ones against the FX's live values. They resolve in order, so a
+/// later change to an index builds on an earlier one in the same command.
+fn resolve_relative(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    params: Vec<ParamChange>,
+) -> Result<Vec<ParamChange>, ToneError> {
+    if params.iter().all(|p| p.op.is_set()) {
+        return Ok(params);
+    }
+    let mut resolved: HashMap<i32, f64> = HashMap::new();
+    let mut out = Vec::with_capacity(params.len());
+    for p in params {
+        let value = match p.op {
+            ParamOp::Set => p.value,
+            ParamOp::Add => {
+                let current = resolved
+                    .get(&p.index)
+                    .copied()
+                    .or_else(|| api.track_fx_get_param(track, fx_index, p.index));
+                let Some(current) = current else {
+                    return Err(ToneError {
+                        msg: format!("can't read param {} to add to it", p.index),
+                        code: ErrorCode::InvalidValue,
+                        details: None,
+                        diagnostic: Some(ErrorDiagnostic {
+                            param_index: Some(p.index),
+                            ..ErrorDiagnostic::remedy(Remediation::FixParams)
+                        }),
+                    });
+                };
+                // Non-finite deltas stay so, for sanitize_params to refuse.
+                (current + p.value).clamp(0.0, 1.0)
+            }
+        };
+        resolved.insert(p.index, value.clamp(0.0, 1.0));
+        out.push(ParamChange {
+            index: p.index,
+            value,
+            op: ParamOp::Set,
+        });
+    }
+    Ok(out)
+}
+
+pub(crate) fn sanitize_params(params: Vec<ParamChange>) -> Result<Vec<ParamChange>, ToneError> {
+    let mut last_by_index: HashMap<i32, (f64, ParamOp)> = HashMap::new();
+    for p in &params {
+        let invalid = if p.index < 0 || p.index > MAX_PARAM_INDEX {
+            Some(format!("invalid param index: {}", p.index))
+        } else if !p.value.is_finite() {
+            Some(format!("non-finite value at index {}", p.index))
+        } else {
+            None
+        };
+        if let Some(msg) = invalid {
+            return Err(ToneError {
+                msg,
+                code: ErrorCode::InvalidValue,
+                details: None,
+                diagnostic: Some(ErrorDiagnostic {
+                    param_index: Some(p.index),
+                    // JSON has no NaN or infinity to echo back.
+                    value: p.value.is_finite().then_some(p.value),
+                    ..ErrorDiagnostic::remedy(Remediation::FixParams)
+                }),
+            });
+        }
+        // An unresolved `add` (a stored slot's) keeps its sign.
+        let floor = if p.op.is_set() { 0.0 } else { -1.0 };
+        last_by_index.insert(p.index, (p.value.clamp(floor, 1.0), p.op));
+    }
+
+    // Preserve the original order of last occurrences ("last-wins").
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for p in params.into_iter().rev() {
+        if !seen.insert(p.index) {
+            continue;
+        }
+        let Some(&(value, op)) = last_by_index.get(&p.index) else {
+            continue;
+        };
+        out.push(ParamChange {
+            index: p.index,
+            value,
+            op,
+        });
+    }
+    out.reverse();
+    Ok(out)
+}
+
+/// Modules none of `params` belong to, with their toggles as `current` holds them. A module with
+/// several toggles (wow/pitch) counts as active only when all of them are on.
+fn untouched_modules(
+    modules: &[ModuleSpec],
+    current: &[ParamReading],
+    params: &[ParamChange],
+) -> Vec<SkippedModule> {
+    modules
+        .iter()
+        .filter(|m| !params.iter().any(|p| m.params.contains(&p.index)))
+        .map(|m| {
+            let toggles: Option<Vec<f64>> = m
+                .bypass
+                .iter()
+                .map(|&i| current.iter().find(|r| r.index == i).map(|r| r.value))
+                .collect();
+            SkippedModule {
+                name: m.name.to_string(),
+                active: toggles.map(|t| t.iter().all(|v| *v >= 0.5)),
+            }
+        })
+        .collect()
 }
 
-#[derive(Clone, Copy)]
-struct ModuleDef {
-    bypass: &'static [i32],
-    params: &'static [i32],
-}
-
-const MODULES: &[ModuleDef] = &[
-    // wow/pitch: both pedal_switch (3) and active (4) are treated as bypass controls.
-    ModuleDef {
-        bypass: &[3, 4],
-        params: &[3, 4, 6],
-    },
-    ModuleDef {
-        bypass: &[8],
-        params: &[8, 9, 10, 11],
-    },
-    ModuleDef {
-        bypass: &[13],
-        params: &[13, 14, 15, 16],
-    },
-    ModuleDef {
-        bypass: &[17],
-        params: &[17, 18, 19, 20],
-    },
-    ModuleDef {
-        bypass: &[21],
-        params: &[21, 22],
-    },
-    ModuleDef {
-        bypass: &[23],
-        params: &[23, 24, 25, 27],
-    },
-    ModuleDef {
-        bypass: &[101],
-        params: &[101, 105, 106, 108],
-    },
-    ModuleDef {
-        bypass: &[112],
-        params: &[112, 114, 115, 116, 117],
-    },
-];
-
-fn apply_replace_active_cleaner(params: Vec<ParamChange>) -> Vec<ParamChange> {
-    let touched_modules: HashSet<usize> = MODULES
+pub(crate) fn apply_replace_active_cleaner(
+    modules: &[ModuleSpec],
+    params: Vec<ParamChange>,
+    locked: &HashSet<i32>,
+) -> Vec<ParamChange> {
+    let touched_modules: HashSet<usize> = modules
         .iter()
         .enumerate()
         .filter(|(_, m)| {
@@ -344,8 +4286,8 @@ fn apply_replace_active_cleaner(params: Vec<ParamChange>) -> Vec<ParamChange> {
     let mut already_set: HashSet<i32> = params.iter().map(|p| p.index).collect();
     let mut out = params;
 
-    for (i, module) in MODULES.iter().enumerate() {
-        if touched_modules.contains(&i) {
+    for (i, module) in modules.iter().enumerate() {
+        if touched_modules.contains(&i) || module.params.iter().any(|p| locked.contains(p)) {
             continue;
         }
         for &bypass_idx in module.bypass {
@@ -353,6 +4295,7 @@ fn apply_replace_active_cleaner(params: Vec<ParamChange>) -> Vec<ParamChange> {
                 out.push(ParamChange {
                     index: bypass_idx,
                     value: 0.0,
+                    op: ParamOp::Set,
                 });
             }
         }
@@ -0,0 +1,53 @@
+//! LAN discovery for the websocket server, behind the `mdns` cargo feature. With `"mdns": true`
+//! in the sidecar config the DLL announces itself as `_gojira-sidecar._tcp` under this machine's
+//! name, so a UI or CLI elsewhere on the network finds it without a hard-coded URL.
+
+use crate::protocol::{MDNS_SERVICE_TYPE, MDNS_TLS_PROPERTY};
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Advertisement {
+    /// Announces `port` on every interface the daemon finds, until `stop`.
+    pub fn start(port: u16, tls: bool) -> Result<Self, String> {
+        let daemon = ServiceDaemon::new().map_err(|e| format!("mdns daemon: {e}"))?;
+        let name = instance_name();
+        let host = format!("{name}.local.");
+        let properties = [(MDNS_TLS_PROPERTY, if tls { "1" } else { "0" })];
+        let info = ServiceInfo::new(MDNS_SERVICE_TYPE, &name, &host, "", port, &properties[..])
+            .map_err(|e| format!("mdns service {name}: {e}"))?
+            .enable_addr_auto();
+        let fullname = info.get_fullname().to_string();
+        daemon
+            .register(info)
+            .map_err(|e| format!("mdns register {fullname}: {e}"))?;
+        Ok(Self { daemon, fullname })
+    }
+
+    /// Says goodbye on the network, so browsers drop us now rather than when the record expires.
+    pub fn stop(&self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// This machine's name as a DNS label, which is what people see when picking a REAPER.
+fn instance_name() -> String {
+    let name = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_default();
+    let label: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let label = label.trim_matches('-');
+    if label.is_empty() {
+        "reaper".to_string()
+    } else {
+        label.to_string()
+    }
+}
@@ -0,0 +1,79 @@
+use gojira_protocol::{ParamEnumOption, ParamFormatSample, ParamFormatTriplet, ParamKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Everything the slow probes found for one plugin build, as a handshake would carry it.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CachedMeta {
+    #[serde(default)]
+    pub param_enums: HashMap<i32, Vec<ParamEnumOption>>,
+    #[serde(default)]
+    pub param_formats: HashMap<i32, ParamFormatTriplet>,
+    #[serde(default)]
+    pub param_format_samples: HashMap<i32, Vec<ParamFormatSample>>,
+    /// With the enum corrections applied.
+    #[serde(default)]
+    pub param_kinds: HashMap<i32, ParamKind>,
+}
+
+/// `Gojira/param-meta.json` under REAPER's resource folder.
+pub fn default_path(resource_dir: &str) -> PathBuf {
+    PathBuf::from(resource_dir)
+        .join("Gojira")
+        .join("param-meta.json")
+}
+
+/// Which plugin build a probe belongs to. REAPER has no plugin version string to offer, so the
+/// FX's `fx_ident` (plugin file and id) stands in for it; an update that keeps both needs a
+/// `refresh_param_meta`. The probe settings are part of the key since they change the samples.
+pub fn key(fx_name: &str, param_count: i32, ident: &str, probe_settings: &str) -> String {
+    format!("{fx_name}|{param_count}|{ident}|{probe_settings}")
+}
+
+/// Probed param meta on disk, one entry per key, so a handshake only probes a plugin build once.
+pub struct MetaCache {
+    path: PathBuf,
+    entries: HashMap<String, CachedMeta>,
+}
+
+impl MetaCache {
+    /// A missing or unreadable file just means an empty cache; it is rewritten on the next save.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CachedMeta> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, meta: CachedMeta) -> Result<(), String> {
+        self.entries.insert(key, meta);
+        self.save()
+    }
+
+    pub fn remove(&mut self, key: &str) -> Result<(), String> {
+        if self.entries.remove(key).is_none() {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// Written to a temp file first so a crash never leaves a half-written cache behind.
+    fn save(&self) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("param meta cache {}: {e}", dir.display()))?;
+        }
+        let raw = serde_json::to_string(&self.entries).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, raw)
+            .map_err(|e| format!("param meta cache {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("param meta cache {}: {e}", self.path.display()))
+    }
+}
@@ -0,0 +1,180 @@
+use crate::protocol::{RuntimeMetrics, TimingStats};
+use std::time::{Duration, Instant};
+
+const TICK_WINDOW: Duration = Duration::from_secs(1);
+
+/// Main-loop counters. Everything is updated from the timer thread, so plain fields suffice.
+pub struct Metrics {
+    started: Instant,
+    commands_received: u64,
+    commands_applied: u64,
+    commands_rejected: u64,
+    commands_coalesced: u64,
+    applies: u64,
+    apply_total: Duration,
+    apply_max: Duration,
+    window_start: Instant,
+    window_ticks: u32,
+    ticks_per_sec: f64,
+    ticks: u64,
+    tick_total: Duration,
+    tick_max: Duration,
+    scans: u64,
+    scan_total: Duration,
+    scan_max: Duration,
+    probes: u64,
+    last_probe: Duration,
+    max_probe: Duration,
+    inbound_high_water: usize,
+    outbound_high_water: usize,
+    outbound_dropped: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            commands_received: 0,
+            commands_applied: 0,
+            commands_rejected: 0,
+            commands_coalesced: 0,
+            applies: 0,
+            apply_total: Duration::ZERO,
+            apply_max: Duration::ZERO,
+            window_start: now,
+            window_ticks: 0,
+            ticks_per_sec: 0.0,
+            ticks: 0,
+            tick_total: Duration::ZERO,
+            tick_max: Duration::ZERO,
+            scans: 0,
+            scan_total: Duration::ZERO,
+            scan_max: Duration::ZERO,
+            probes: 0,
+            last_probe: Duration::ZERO,
+            max_probe: Duration::ZERO,
+            inbound_high_water: 0,
+            outbound_high_water: 0,
+            outbound_dropped: 0,
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn tick(&mut self, inbound_len: usize) {
+        self.inbound_high_water = self.inbound_high_water.max(inbound_len);
+        self.window_ticks += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= TICK_WINDOW {
+            self.ticks_per_sec = f64::from(self.window_ticks) / elapsed.as_secs_f64();
+            self.window_start = Instant::now();
+            self.window_ticks = 0;
+        }
+    }
+
+    /// How long a whole tick took, from draining the inbound queue to the last reply.
+    pub fn tick_took(&mut self, took: Duration) {
+        self.ticks += 1;
+        self.tick_total += took;
+        self.tick_max = self.tick_max.max(took);
+    }
+
+    pub fn scan_took(&mut self, took: Duration) {
+        self.scans += 1;
+        self.scan_total += took;
+        self.scan_max = self.scan_max.max(took);
+    }
+
+    pub fn command_received(&mut self) {
+        self.commands_received += 1;
+    }
+
+    pub fn command_coalesced(&mut self) {
+        self.commands_coalesced += 1;
+    }
+
+    pub fn applied(&mut self) {
+        self.commands_applied += 1;
+    }
+
+    pub fn rejected(&mut self) {
+        self.commands_rejected += 1;
+    }
+
+    pub fn apply_took(&mut self, took: Duration) {
+        self.applies += 1;
+        self.apply_total += took;
+        self.apply_max = self.apply_max.max(took);
+    }
+
+    pub fn probe_took(&mut self, took: Duration) {
+        self.probes += 1;
+        self.last_probe = took;
+        self.max_probe = self.max_probe.max(took);
+    }
+
+    /// `queued` is the outbound length after a send, `None` if the message was dropped.
+    pub fn outbound(&mut self, queued: Option<usize>) {
+        match queued {
+            Some(len) => self.outbound_high_water = self.outbound_high_water.max(len),
+            None => self.outbound_dropped += 1,
+        }
+    }
+
+    pub fn timings(&self) -> TimingStats {
+        TimingStats {
+            ticks: self.ticks,
+            avg_tick_ms: avg_ms(self.tick_total, self.ticks),
+            max_tick_ms: ms(self.tick_max),
+            scans: self.scans,
+            avg_scan_ms: avg_ms(self.scan_total, self.scans),
+            max_scan_ms: ms(self.scan_max),
+            applies: self.applies,
+            avg_apply_ms: avg_ms(self.apply_total, self.applies),
+            max_apply_ms: ms(self.apply_max),
+        }
+    }
+
+    pub fn snapshot(&self) -> RuntimeMetrics {
+        let avg_apply_ms = avg_ms(self.apply_total, self.applies);
+        RuntimeMetrics {
+            uptime_secs: self.started.elapsed().as_secs_f64(),
+            commands_received: self.commands_received,
+            commands_applied: self.commands_applied,
+            commands_rejected: self.commands_rejected,
+            commands_coalesced: self.commands_coalesced,
+            avg_apply_ms,
+            max_apply_ms: ms(self.apply_max),
+            ticks_per_sec: self.ticks_per_sec,
+            probes: self.probes,
+            last_probe_ms: ms(self.last_probe),
+            max_probe_ms: ms(self.max_probe),
+            inbound_high_water: self.inbound_high_water,
+            outbound_high_water: self.outbound_high_water,
+            outbound_dropped: self.outbound_dropped,
+        }
+    }
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn avg_ms(total: Duration, count: u64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        ms(total) / count as f64
+    }
+}
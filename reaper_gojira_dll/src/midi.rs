@@ -0,0 +1,116 @@
+use crossbeam_channel::{bounded, Receiver, Sender};
+use reaper_low::raw::audio_hook_register_t;
+use reaper_low::Reaper;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::OnceLock;
+
+/// Messages the audio hook may hold for the main loop; a controller can't send more than a few
+/// between two timer ticks, and the hook drops the rest rather than wait.
+const MIDI_CAP: usize = 64;
+
+const STATUS_CC: u8 = 0xB0;
+const STATUS_PROGRAM_CHANGE: u8 = 0xC0;
+
+/// Which MIDI messages switch tone slots: program change N picks slot N, and so does value N of
+/// `slot_cc` when set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MidiMap {
+    /// 1-16; `None` takes every channel.
+    pub channel: Option<u8>,
+    pub slot_cc: Option<u8>,
+}
+
+impl MidiMap {
+    pub fn slot_for(&self, msg: [u8; 3]) -> Option<u32> {
+        let [status, data1, data2] = msg;
+        if let Some(channel) = self.channel {
+            if u16::from(status & 0x0F) + 1 != u16::from(channel) {
+                return None;
+            }
+        }
+        match status & 0xF0 {
+            STATUS_PROGRAM_CHANGE => Some(u32::from(data1 & 0x7F)),
+            STATUS_CC if self.slot_cc == Some(data1) => Some(u32::from(data2 & 0x7F)),
+            _ => None,
+        }
+    }
+}
+
+/// The device the hook reads and where it hands messages to the main loop.
+struct Input {
+    reaper: Reaper,
+    device: i32,
+    tx: Sender<[u8; 3]>,
+}
+
+static INPUT: OnceLock<Input> = OnceLock::new();
+static HOOK: AtomicPtr<audio_hook_register_t> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Starts reading `device` from REAPER's audio thread. MIDI input is only readable there, so the
+/// hook just forwards program changes and CCs; `MainLoop` maps and applies them on its tick.
+pub fn listen(reaper: Reaper, device: i32) -> Receiver<[u8; 3]> {
+    let (tx, rx) = bounded(MIDI_CAP);
+    if INPUT.set(Input { reaper, device, tx }).is_err() {
+        return rx;
+    }
+    // REAPER keeps the pointer until the hook is removed in `stop`.
+    let reg: &'static mut audio_hook_register_t =
+        Box::leak(Box::new(unsafe { std::mem::zeroed() }));
+    reg.OnAudioBuffer = Some(on_audio_buffer);
+    HOOK.store(reg, Ordering::Release);
+    unsafe {
+        reaper.Audio_RegHardwareHook(true, reg);
+    }
+    rx
+}
+
+/// Removes the hook so REAPER's audio thread stops calling into a DLL being unloaded.
+pub fn stop(reaper: Reaper) {
+    let reg = HOOK.swap(std::ptr::null_mut(), Ordering::AcqRel);
+    if !reg.is_null() {
+        unsafe {
+            reaper.Audio_RegHardwareHook(false, reg);
+        }
+    }
+}
+
+extern "C" fn on_audio_buffer(
+    is_post: bool,
+    _len: c_int,
+    _srate: f64,
+    _reg: *mut audio_hook_register_t,
+) {
+    let _ = reaper_low::firewall(|| {
+        if is_post {
+            return;
+        }
+        let Some(input) = INPUT.get() else {
+            return;
+        };
+        unsafe {
+            // Null while the device is disabled in REAPER's preferences or unplugged.
+            let midi_input = input.reaper.GetMidiInput(input.device);
+            if midi_input.is_null() {
+                return;
+            }
+            let list = reaper_low::midi_Input_GetReadBuf(midi_input);
+            if list.is_null() {
+                return;
+            }
+            let mut bpos: c_int = 0;
+            loop {
+                let event = reaper_low::MIDI_eventlist_EnumItems(list, &mut bpos);
+                let Some(event) = event.as_ref() else {
+                    break;
+                };
+                let [status, data1, data2, _] = event.midi_message;
+                if matches!(status & 0xF0, STATUS_CC | STATUS_PROGRAM_CHANGE) {
+                    // Full means the main loop is stalled; a missed switch beats a blocked
+                    // audio thread.
+                    let _ = input.tx.try_send([status, data1, data2]);
+                }
+            }
+        }
+    });
+}
@@ -1,62 +1,967 @@
-use crate::protocol::{ClientCommand, ErrorCode, InboundMsg, OutboundMsg, ServerMessage};
+use crate::osc::{self, OscArg};
+use crate::protocol::{
+    env_pairing_key, ClientCommand, ClientId, Discovery, Encoding, ErrorCode, ErrorDiagnostic,
+    Frame, InboundMsg, OutboundMsg, Remediation, ServerMessage, SidecarConfig, Transport,
+    OUTBOUND_CAP, PAIRING_KEY_HEADER, RESUME_GRACE_SECS,
+};
+use crate::telemetry;
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use interprocess::local_socket::prelude::*;
+use interprocess::local_socket::{GenericNamespaced, ListenerNonblockingMode, ListenerOptions};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::{HeaderValue, StatusCode};
 use tungstenite::protocol::Message;
+use tungstenite::HandshakeError;
 
-const DEFAULT_WS_ADDR: &str = "127.0.0.1:9001";
-const WS_ADDR_ENV: &str = "GOJIRA_WS_ADDR";
+#[cfg(feature = "mdns")]
+use crate::mdns::Advertisement;
+#[cfg(feature = "tls")]
+use crate::tls::TlsIdentity;
 
-struct ActiveClient {
-    ws: tungstenite::WebSocket<TcpStream>,
+/// Stand-in so the server reads the same with the `tls` feature off; never constructed.
+#[cfg(not(feature = "tls"))]
+enum TlsIdentity {}
+
+#[cfg(not(feature = "tls"))]
+impl TlsIdentity {
+    fn fingerprint(&self) -> &str {
+        match *self {}
+    }
+}
+
+/// Stand-in so the server reads the same with the `mdns` feature off; never constructed.
+#[cfg(not(feature = "mdns"))]
+enum Advertisement {}
+
+#[cfg(not(feature = "mdns"))]
+impl Advertisement {
+    fn start(_port: u16, _tls: bool) -> Result<Self, String> {
+        Err(
+            "sidecar config turns on mdns, but this DLL was built without the `mdns` feature"
+                .to_string(),
+        )
+    }
+
+    fn stop(&self) {
+        match *self {}
+    }
+}
+
+const TOKEN_TTL_ENV: &str = "GOJIRA_TOKEN_TTL_SECS";
+const TOKEN_ROTATION_GRACE: Duration = Duration::from_secs(5);
+/// The Tauri UI and brain_cli side by side, plus headroom for scripts.
+const MAX_CLIENTS: usize = 8;
+/// Client sockets never block, so a pass that read nothing from any client waits this long
+/// before the next one instead of spinning.
+const CLIENT_IDLE_WAIT: Duration = Duration::from_millis(5);
+/// Websocket pings from this thread let clients tell a dead socket from a quiet one; a failed
+/// ping also drops a peer that vanished without closing.
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+/// How long the last queued messages get to reach the clients once the thread is told to stop.
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_millis(250);
+/// How long a new connection gets to finish the websocket upgrade; the loop waits on it.
+const WS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+/// The TLS handshake runs before the client joins the loop, so it gets a real timeout; the other
+/// clients wait at most this long behind a slow one.
+#[cfg(feature = "tls")]
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Largest OSC datagram read; controllers send a few dozen bytes, a JSON command a few KB.
+const OSC_MAX_PACKET: usize = 16 * 1024;
+/// Datagrams read per loop, so a flooding sender can't keep the websocket clients waiting.
+const OSC_READS_PER_LOOP: usize = 32;
+
+/// A client's socket: plain TCP, TLS over it when the sidecar config turns `tls` on, or the
+/// local pipe. Every one carries the same websocket framing.
+enum ServerStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>),
+    Pipe(LocalSocketStream),
+}
+
+impl Read for ServerStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ServerStream::Plain(s) => s.read(buf),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(s) => s.read(buf),
+            ServerStream::Pipe(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for ServerStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ServerStream::Plain(s) => s.write(buf),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(s) => s.write(buf),
+            ServerStream::Pipe(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ServerStream::Plain(s) => s.flush(),
+            #[cfg(feature = "tls")]
+            ServerStream::Tls(s) => s.flush(),
+            ServerStream::Pipe(s) => s.flush(),
+        }
+    }
+}
+
+/// Runs the TLS handshake on a freshly accepted TCP socket when `tls` is set. The pipe never
+/// leaves the machine, so it stays as it is.
+fn wrap_stream(stream: ServerStream, tls: Option<&TlsIdentity>) -> Result<ServerStream, String> {
+    match (stream, tls) {
+        (ServerStream::Plain(tcp), Some(tls)) => wrap_tls(tcp, tls),
+        (stream, _) => Ok(stream),
+    }
+}
+
+/// Upgrades a new connection to a websocket, retrying while the client's request is still in
+/// flight (client sockets don't block). Also settles the encoding
+/// the client asked for.
+fn accept_ws(
+    stream: ServerStream,
+    pairing_key: Option<&str>,
+) -> Result<(tungstenite::WebSocket<ServerStream>, Encoding), String> {
+    let deadline = Instant::now() + WS_HANDSHAKE_TIMEOUT;
+    let encoding = Cell::new(Encoding::Json);
+    let mut attempt = tungstenite::accept_hdr(stream, |req: &Request, resp| {
+        let mut resp = check_pairing_key(pairing_key, req, resp)?;
+        if let Some(chosen) = negotiate_encoding(req) {
+            encoding.set(chosen);
+            resp.headers_mut().insert(
+                "Sec-WebSocket-Protocol",
+                HeaderValue::from_static(chosen.subprotocol()),
+            );
+        }
+        Ok(resp)
+    });
+    loop {
+        match attempt {
+            Ok(ws) => return Ok((ws, encoding.get())),
+            Err(HandshakeError::Interrupted(mid)) if Instant::now() < deadline => {
+                thread::sleep(Duration::from_millis(1));
+                attempt = mid.handshake();
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Where the server listens, as reported in logs and the handshake.
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    Tcp(SocketAddr),
+    Pipe(String),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Tcp(addr) => write!(f, "{addr}"),
+            Endpoint::Pipe(name) => write!(f, "pipe:{name}"),
+        }
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    Pipe(LocalSocketListener),
+}
+
+impl Listener {
+    fn bind_tcp(addr: &str) -> Result<(Self, Endpoint), String> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| format!("ws bind failed on {addr}: {e}"))?;
+        let _ = listener.set_nonblocking(true);
+        let listen_addr = listener
+            .local_addr()
+            .map_err(|e| format!("ws local_addr failed: {e}"))?;
+        Ok((Listener::Tcp(listener), Endpoint::Tcp(listen_addr)))
+    }
+
+    /// First of `addrs` that binds, so a port another REAPER or an orphaned process holds costs
+    /// clients nothing but a look at the discovery file. Fails with the first address's error.
+    fn bind_tcp_any(addrs: &[String]) -> Result<(Self, Endpoint), String> {
+        let mut first_err = None;
+        for addr in addrs {
+            match Self::bind_tcp(addr) {
+                Ok(bound) => {
+                    if let Some(e) = &first_err {
+                        telemetry::warn("net", format!("{e}; listening on {addr} instead"));
+                    }
+                    return Ok(bound);
+                }
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_err.unwrap_or_else(|| "ws bind failed: no address to listen on".to_string()))
+    }
+
+    /// Named pipe on Windows, a Unix socket elsewhere. Accepted streams are non-blocking too,
+    /// since a pipe has no read timeout to keep the loop moving.
+    fn bind_pipe(name: &str) -> Result<(Self, Endpoint), String> {
+        let ns_name = name
+            .to_ns_name::<GenericNamespaced>()
+            .map_err(|e| format!("pipe name {name:?}: {e}"))?;
+        let listener = ListenerOptions::new()
+            .name(ns_name)
+            .nonblocking(ListenerNonblockingMode::Both)
+            .create_sync()
+            .map_err(|e| format!("pipe listen failed on {name}: {e}"))?;
+        Ok((Listener::Pipe(listener), Endpoint::Pipe(name.to_string())))
+    }
+
+    /// Next pending connection, set up for the loop's reads, and a peer name for logs.
+    fn accept(&self) -> std::io::Result<(ServerStream, String)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, socket_addr) = listener.accept()?;
+                let _ = stream.set_nodelay(true);
+                // A write the client isn't reading fails with `WouldBlock`: the frame stays
+                // with tungstenite and the client counts as backed up until the socket drains.
+                let _ = stream.set_nonblocking(true);
+                Ok((ServerStream::Plain(stream), socket_addr.to_string()))
+            }
+            Listener::Pipe(listener) => Ok((ServerStream::Pipe(listener.accept()?), "pipe".into())),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+fn wrap_tls(stream: TcpStream, tls: &TlsIdentity) -> Result<ServerStream, String> {
+    // The handshake blocks (bounded by the timeout); the loop's reads don't.
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(TLS_HANDSHAKE_TIMEOUT));
+    let tls_stream = tls.accept(stream)?;
+    let _ = tls_stream.sock.set_nonblocking(true);
+    Ok(ServerStream::Tls(Box::new(tls_stream)))
+}
+
+#[cfg(not(feature = "tls"))]
+fn wrap_tls(_stream: TcpStream, tls: &TlsIdentity) -> Result<ServerStream, String> {
+    match *tls {}
+}
+
+#[cfg(feature = "tls")]
+fn load_tls_identity() -> Result<TlsIdentity, String> {
+    TlsIdentity::load_or_create()
+}
+
+#[cfg(not(feature = "tls"))]
+fn load_tls_identity() -> Result<TlsIdentity, String> {
+    Err("sidecar config turns on tls, but this DLL was built without the `tls` feature".to_string())
+}
+
+struct Client {
+    id: ClientId,
+    ws: tungstenite::WebSocket<ServerStream>,
     session_token: String,
-    socket_addr: SocketAddr,
+    peer: String,
+    /// Negotiated at upgrade; every message to this client goes out in it.
+    encoding: Encoding,
+    token_issued_at: Instant,
+    /// Token replaced by the last rotation and the instant it stops being accepted.
+    previous_token: Option<(String, Instant)>,
+    /// Messages routed to this client and not yet written, so one slow socket does not hold up
+    /// the others' replies.
+    outbound: OutboundQueue,
+    last_ping: Instant,
+}
+
+/// One client's messages waiting to be written: most urgent first, in arrival order within a
+/// priority.
+#[derive(Default)]
+struct OutboundQueue {
+    entries: VecDeque<Queued>,
+    /// Set while the socket won't take more; telemetry is dropped rather than queued meanwhile.
+    backed_up: bool,
+    /// Telemetry dropped during the current backup, reported once it clears.
+    dropped: usize,
+}
+
+struct Queued {
+    priority: Priority,
+    msg: ServerMessage,
+    /// Whether a newer change may still be merged in. Cleared on a change once a more urgent
+    /// message arrives after it, which the client would otherwise get ahead of that newer change.
+    mergeable: bool,
+}
+
+/// How urgently a message has to reach a client that isn't keeping up, least urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    /// Counters, pongs and diagnostics: the next one supersedes a lost one, so they are dropped
+    /// while the client is backed up.
+    Telemetry,
+    /// Project and param change pushes. A newer one is merged into one still queued.
+    Changes,
+    /// Handshakes and what belongs to the session state they set up.
+    Session,
+    /// Acks, errors and other answers a client is waiting on.
+    Reply,
+}
+
+impl Priority {
+    fn of(msg: &ServerMessage) -> Self {
+        match msg {
+            ServerMessage::Metrics { .. }
+            | ServerMessage::Diagnostics { .. }
+            | ServerMessage::Pong { .. } => Priority::Telemetry,
+            ServerMessage::ProjectChanged { .. } | ServerMessage::ParamChanged { .. } => {
+                Priority::Changes
+            }
+            ServerMessage::Handshake { .. }
+            | ServerMessage::ParamMeta { .. }
+            | ServerMessage::Replay { .. }
+            | ServerMessage::Resumed { .. }
+            | ServerMessage::Instances { .. }
+            | ServerMessage::InstanceMoved { .. } => Priority::Session,
+            _ => Priority::Reply,
+        }
+    }
+}
+
+impl Client {
+    fn accepts(&mut self, token: &str) -> bool {
+        if self.session_token == token {
+            // The client switched over; no reason to keep the old token alive.
+            self.previous_token = None;
+            return true;
+        }
+        matches!(&self.previous_token, Some((prev, until)) if prev == token && Instant::now() < *until)
+    }
+
+    fn enqueue(&mut self, msg: ServerMessage) {
+        if !self.outbound.push(msg) {
+            telemetry::warn(
+                "net",
+                format!(
+                    "ws client {} ({}): outbound queue full, dropping",
+                    self.id, self.peer
+                ),
+            );
+        }
+    }
+
+    /// Writes queued messages until the socket pushes back, which leaves the client backed up
+    /// rather than gone; `Err` only when the connection failed.
+    fn flush(&mut self) -> Result<(), ()> {
+        if self.outbound.backed_up {
+            // The frame tungstenite kept from the blocked write goes before anything new.
+            match self.ws.flush() {
+                Ok(()) => {}
+                Err(e) if is_backpressure(&e) => return Ok(()),
+                Err(_) => return Err(()),
+            }
+        }
+        while let Some(msg) = self.outbound.pop() {
+            match self.ws.send(encode_message(self.encoding, &msg)?) {
+                Ok(()) => {}
+                Err(e) if is_backpressure(&e) => {
+                    self.outbound.backed_up = true;
+                    return Ok(());
+                }
+                Err(_) => return Err(()),
+            }
+        }
+        let dropped = self.outbound.drained();
+        if dropped > 0 {
+            telemetry::warn(
+                "net",
+                format!(
+                    "ws client {} ({}): dropped {} telemetry message(s) while it was backed up",
+                    self.id, self.peer, dropped
+                ),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl OutboundQueue {
+    /// Queues `msg` by its priority, merging a change push into a queued one for the same
+    /// target. `false` when the queue is full and `msg` was refused.
+    fn push(&mut self, msg: ServerMessage) -> bool {
+        let priority = Priority::of(&msg);
+        if matches!(msg, ServerMessage::Shutdown { .. }) {
+            // The connection is closing: answers still go out, pushes no longer matter.
+            self.entries.retain(|q| q.priority == Priority::Reply);
+            self.entries.push_back(Queued {
+                priority,
+                msg,
+                mergeable: false,
+            });
+            return true;
+        }
+        if priority == Priority::Telemetry && self.backed_up {
+            self.dropped += 1;
+            return true;
+        }
+        let mut msg = msg;
+        if priority == Priority::Changes {
+            for queued in self.entries.iter_mut().filter(|q| q.mergeable) {
+                match coalesce(&mut queued.msg, msg) {
+                    Some(unmerged) => msg = unmerged,
+                    None => return true,
+                }
+            }
+        } else if priority > Priority::Changes {
+            for queued in self.entries.iter_mut() {
+                queued.mergeable = false;
+            }
+        }
+        if self.entries.len() >= OUTBOUND_CAP {
+            // Telemetry sits at the back; make room by losing the oldest of it.
+            let telemetry = self
+                .entries
+                .iter()
+                .position(|q| q.priority == Priority::Telemetry);
+            match telemetry {
+                Some(i) if priority > Priority::Telemetry => {
+                    self.entries.remove(i);
+                    self.dropped += 1;
+                }
+                _ => return false,
+            }
+        }
+        let at = self
+            .entries
+            .iter()
+            .position(|q| q.priority < priority)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            at,
+            Queued {
+                priority,
+                msg,
+                mergeable: priority == Priority::Changes,
+            },
+        );
+        true
+    }
+
+    fn pop(&mut self) -> Option<ServerMessage> {
+        self.entries.pop_front().map(|q| q.msg)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Everything queued is out: ends a backup, handing back how much telemetry it cost.
+    fn drained(&mut self) -> usize {
+        let dropped = if self.backed_up { self.dropped } else { 0 };
+        self.backed_up = false;
+        self.dropped = 0;
+        dropped
+    }
+}
+
+/// A write the socket can't take yet: tungstenite keeps the frame and writes it ahead of
+/// the next one, so the client is only slow, not gone.
+fn is_backpressure(e: &tungstenite::Error) -> bool {
+    matches!(e, tungstenite::Error::Io(e)
+        if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut)
+}
+
+/// Merges `msg` into `queued` when both are change pushes about the same project or FX, so a
+/// client that fell behind gets one catch-up message instead of every step. Hands `msg` back
+/// when they don't match.
+fn coalesce(queued: &mut ServerMessage, msg: ServerMessage) -> Option<ServerMessage> {
+    match (queued, msg) {
+        (
+            ServerMessage::ProjectChanged {
+                project_tempo,
+                project_name,
+                changed_instances,
+                added,
+                removed,
+                renamed,
+            },
+            ServerMessage::ProjectChanged {
+                project_tempo: next_tempo,
+                project_name: next_name,
+                changed_instances: next_changed,
+                added: next_added,
+                removed: next_removed,
+                renamed: next_renamed,
+            },
+        ) if *project_name == next_name => {
+            *project_tempo = next_tempo.or(*project_tempo);
+            for guid in next_changed {
+                if !changed_instances.contains(&guid) {
+                    changed_instances.push(guid);
+                }
+            }
+            for inst in next_added {
+                // Removed and back again: to the client it is still there, maybe moved.
+                if let Some(i) = removed.iter().position(|r| r.fx_guid == inst.fx_guid) {
+                    removed.remove(i);
+                    renamed.retain(|r| r.fx_guid != inst.fx_guid);
+                    renamed.push(inst);
+                } else {
+                    added.push(inst);
+                }
+            }
+            for inst in next_removed {
+                // Added and gone again before the client heard of it.
+                if let Some(i) = added.iter().position(|a| a.fx_guid == inst.fx_guid) {
+                    added.remove(i);
+                } else {
+                    renamed.retain(|r| r.fx_guid != inst.fx_guid);
+                    removed.push(inst);
+                }
+            }
+            for inst in next_renamed {
+                if let Some(a) = added.iter_mut().find(|a| a.fx_guid == inst.fx_guid) {
+                    *a = inst;
+                } else {
+                    renamed.retain(|r| r.fx_guid != inst.fx_guid);
+                    renamed.push(inst);
+                }
+            }
+            None
+        }
+        (
+            ServerMessage::ParamChanged {
+                target_fx_guid,
+                params,
+            },
+            ServerMessage::ParamChanged {
+                target_fx_guid: next_guid,
+                params: next_params,
+            },
+        ) if *target_fx_guid == next_guid => {
+            for reading in next_params {
+                match params.iter_mut().find(|p| p.index == reading.index) {
+                    Some(p) => *p = reading,
+                    None => params.push(reading),
+                }
+            }
+            None
+        }
+        (_, msg) => Some(msg),
+    }
+}
+
+/// The OSC endpoint. Everyone sending to it shares one session, opened like a websocket
+/// client's by the first datagram; replies go to whoever sent last.
+struct OscPeer {
+    socket: UdpSocket,
+    /// Assigned on the first datagram.
+    id: Option<ClientId>,
+    session_token: String,
+    reply_to: Option<SocketAddr>,
+    /// Numbers the `osc-N` command ids replies carry, since OSC messages have none.
+    commands: u64,
+}
+
+impl OscPeer {
+    /// Binds `addr`, which must be loopback unless `allow_remote`: OSC has no pairing key.
+    fn bind(addr: &str, allow_remote: bool) -> Result<Self, String> {
+        if !allow_remote {
+            let resolved: Vec<SocketAddr> = addr
+                .to_socket_addrs()
+                .map_err(|e| format!("osc address {addr} is invalid: {e}"))?
+                .collect();
+            if resolved.is_empty() || resolved.iter().any(|a| !a.ip().is_loopback()) {
+                return Err(format!(
+                    "osc_addr {addr} is not loopback; OSC has no pairing key, so set \
+                     osc_allow_remote to take commands from other machines"
+                ));
+            }
+        }
+        let socket =
+            UdpSocket::bind(addr).map_err(|e| format!("osc bind failed on {addr}: {e}"))?;
+        let _ = socket.set_nonblocking(true);
+        Ok(Self {
+            socket,
+            id: None,
+            session_token: new_session_token(),
+            reply_to: None,
+            commands: 0,
+        })
+    }
+
+    fn send(&self, packet: &[u8]) {
+        if let Some(to) = self.reply_to {
+            let _ = self.socket.send_to(packet, to);
+        }
+    }
+
+    fn send_error(&self, command_id: &str, msg: &str) {
+        self.send(&osc::encode(
+            "/gojira/error",
+            &[
+                OscArg::Str(command_id.to_string()),
+                OscArg::Str(msg.to_string()),
+            ],
+        ));
+    }
+}
+
+/// The sidecar config file if there is one, defaults otherwise. A file that exists but can't be
+/// read or parsed is an error rather than a silent fallback to the default port.
+pub(crate) fn load_sidecar_config() -> Result<SidecarConfig, String> {
+    let Some(path) = SidecarConfig::path() else {
+        return Ok(SidecarConfig::default());
+    };
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SidecarConfig::default()),
+        Err(e) => return Err(format!("sidecar config {}: {e}", path.display())),
+    };
+    serde_json::from_str(&raw).map_err(|e| format!("sidecar config {}: {e}", path.display()))
+}
+
+/// `GOJIRA_PAIRING_KEY` if set, else the key file, written with a fresh key on first start.
+fn load_or_create_pairing_key() -> Result<String, String> {
+    if let Some(key) = env_pairing_key() {
+        return Ok(key);
+    }
+    let path = SidecarConfig::pairing_key_path()
+        .ok_or_else(|| "no config directory to keep the pairing key in".to_string())?;
+    match std::fs::read_to_string(&path) {
+        Ok(raw) if !raw.trim().is_empty() => return Ok(raw.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("pairing key {}: {e}", path.display())),
+    }
+
+    let key = random_token(48);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("pairing key {}: {e}", dir.display()))?;
+    }
+    std::fs::write(&path, &key).map_err(|e| format!("pairing key {}: {e}", path.display()))?;
+    Ok(key)
+}
+
+/// Tells clients on this machine which port we got, in case it isn't the configured one.
+fn write_discovery(port: u16) -> Result<(), String> {
+    let path = SidecarConfig::discovery_path()
+        .ok_or_else(|| "no config directory to write the discovery file in".to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("discovery file {}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string(&Discovery { port }).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("discovery file {}: {e}", path.display()))
+}
+
+/// Removes the discovery file, unless another DLL has written its own port there since.
+fn remove_discovery(port: u16) {
+    let Some(path) = SidecarConfig::discovery_path() else {
+        return;
+    };
+    let ours = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Discovery>(&raw).ok())
+        .is_some_and(|d| d.port == port);
+    if ours {
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+/// The encoding named by the client's `Sec-WebSocket-Protocol` offer, if it made one we know.
+fn negotiate_encoding(req: &Request) -> Option<Encoding> {
+    req.headers()
+        .get_all("Sec-WebSocket-Protocol")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find_map(Encoding::negotiate)
+}
+
+/// Turns the upgrade away with 401 unless it carries the pairing key (when one is required).
+fn check_pairing_key(
+    expected: Option<&str>,
+    req: &Request,
+    resp: Response,
+) -> Result<Response, ErrorResponse> {
+    let Some(expected) = expected else {
+        return Ok(resp);
+    };
+    let presented = req
+        .headers()
+        .get(PAIRING_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if presented == Some(expected) {
+        return Ok(resp);
+    }
+    let mut err = ErrorResponse::new(Some("pairing key missing or wrong".to_string()));
+    *err.status_mut() = StatusCode::UNAUTHORIZED;
+    Err(err)
+}
+
+/// Unset or 0 keeps the old behavior: one token for the lifetime of the connection.
+fn token_ttl() -> Option<Duration> {
+    std::env::var(TOKEN_TTL_ENV)
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+fn random_token(len: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+fn new_session_token() -> String {
+    random_token(32)
+}
+
+/// Issues a new token. The main loop delivers it to the client (so it is ordered with any
+/// queued handshake); if it can't be told right now, nothing changes and `false` is returned.
+fn rotate_token(in_tx: &Sender<InboundMsg>, client: &mut Client, ttl: Option<Duration>) -> bool {
+    let session_token = new_session_token();
+    let notified = in_tx
+        .try_send(InboundMsg::TokenRotated {
+            client_id: client.id,
+            session_token: session_token.clone(),
+            expires_in_secs: ttl.map(|t| t.as_secs()),
+        })
+        .is_ok();
+    if !notified {
+        return false;
+    }
+
+    let now = Instant::now();
+    let old = std::mem::replace(&mut client.session_token, session_token);
+    client.previous_token = Some((old, now + TOKEN_ROTATION_GRACE));
+    client.token_issued_at = now;
+    true
 }
 
 pub struct NetworkThread {
     shutdown: Arc<AtomicBool>,
     join_handle: Mutex<Option<JoinHandle<()>>>,
-    listen_addr: SocketAddr,
+    endpoint: Endpoint,
+    tls_fingerprint: Option<String>,
+    osc_addr: Option<SocketAddr>,
+    /// Port written to the discovery file, which goes again on shutdown.
+    discovery: Option<u16>,
+    advertisement: Option<Advertisement>,
 }
 
 impl NetworkThread {
+    /// Binds the address from `GOJIRA_WS_ADDR`, else the sidecar config file, else 127.0.0.1:9001,
+    /// trying the next ports in the config's fallback range when it is taken, and only lets in
+    /// clients that present the pairing key. The port it got goes in the discovery file. Serves
+    /// `wss://` when the config turns `tls` on, or listens on the local pipe instead when it
+    /// selects that transport. Also takes OSC on `osc_addr` when the config sets one, and
+    /// announces itself on the LAN when it turns `mdns` on.
     pub fn spawn(in_tx: Sender<InboundMsg>, out_rx: Receiver<OutboundMsg>) -> Result<Self, String> {
-        let addr = std::env::var(WS_ADDR_ENV).unwrap_or_else(|_| DEFAULT_WS_ADDR.to_string());
-        Self::spawn_with_addr(&addr, in_tx, out_rx)
+        let config = load_sidecar_config()?;
+        let pairing_key = load_or_create_pairing_key()?;
+        let (listener, endpoint) = match config.transport() {
+            Transport::Tcp => Listener::bind_tcp_any(&config.listen_addrs())?,
+            Transport::Pipe => Listener::bind_pipe(config.pipe_name())?,
+        };
+        // Clients still find the configured port without it, which is all a failure costs.
+        let discovery = match &endpoint {
+            Endpoint::Tcp(addr) => match write_discovery(addr.port()) {
+                Ok(()) => Some(addr.port()),
+                Err(e) => {
+                    telemetry::warn("net", e);
+                    None
+                }
+            },
+            Endpoint::Pipe(_) => None,
+        };
+        let tls = if config.tls_enabled() && matches!(listener, Listener::Tcp(_)) {
+            Some(load_tls_identity()?)
+        } else {
+            None
+        };
+        // Neither should a LAN that won't carry the announcement.
+        let advertisement = match &endpoint {
+            Endpoint::Tcp(addr) if config.mdns_enabled() => {
+                match Advertisement::start(addr.port(), tls.is_some()) {
+                    Ok(advertisement) => Some(advertisement),
+                    Err(e) => {
+                        telemetry::warn("net", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        // A taken or refused OSC address shouldn't cost the websocket clients their connection.
+        let osc_remote = config.osc_remote_allowed();
+        let osc = config.osc_addr.as_deref().and_then(|addr| {
+            OscPeer::bind(addr, osc_remote)
+                .map_err(|e| telemetry::warn("net", e))
+                .ok()
+        });
+        let mut net = Self::spawn_listener(
+            listener,
+            endpoint,
+            Some(pairing_key),
+            tls,
+            osc,
+            in_tx,
+            out_rx,
+        );
+        net.discovery = discovery;
+        net.advertisement = advertisement;
+        Ok(net)
     }
 
+    /// No pairing key required; for tests and the mock sidecar.
     pub fn spawn_with_addr(
         addr: &str,
         in_tx: Sender<InboundMsg>,
         out_rx: Receiver<OutboundMsg>,
     ) -> Result<Self, String> {
-        let listener = TcpListener::bind(addr)
-            .map_err(|e| format!("ws bind failed on {addr}: {e}"))?;
-        let _ = listener.set_nonblocking(true);
-        let listen_addr = listener
-            .local_addr()
-            .map_err(|e| format!("ws local_addr failed: {e}"))?;
+        Self::spawn_paired(addr, None, in_tx, out_rx)
+    }
 
+    pub fn spawn_paired(
+        addr: &str,
+        pairing_key: Option<String>,
+        in_tx: Sender<InboundMsg>,
+        out_rx: Receiver<OutboundMsg>,
+    ) -> Result<Self, String> {
+        let (listener, endpoint) = Listener::bind_tcp(addr)?;
+        Ok(Self::spawn_listener(
+            listener,
+            endpoint,
+            pairing_key,
+            None,
+            None,
+            in_tx,
+            out_rx,
+        ))
+    }
+
+    /// Like `spawn_with_addr`, on the first of `addrs` that binds.
+    pub fn spawn_with_addrs(
+        addrs: &[String],
+        in_tx: Sender<InboundMsg>,
+        out_rx: Receiver<OutboundMsg>,
+    ) -> Result<Self, String> {
+        let (listener, endpoint) = Listener::bind_tcp_any(addrs)?;
+        Ok(Self::spawn_listener(
+            listener, endpoint, None, None, None, in_tx, out_rx,
+        ))
+    }
+
+    /// Like `spawn_with_addr`, plus the OSC endpoint on `osc_addr`.
+    pub fn spawn_with_osc(
+        addr: &str,
+        osc_addr: &str,
+        in_tx: Sender<InboundMsg>,
+        out_rx: Receiver<OutboundMsg>,
+    ) -> Result<Self, String> {
+        let (listener, endpoint) = Listener::bind_tcp(addr)?;
+        let osc = OscPeer::bind(osc_addr, false)?;
+        Ok(Self::spawn_listener(
+            listener,
+            endpoint,
+            None,
+            None,
+            Some(osc),
+            in_tx,
+            out_rx,
+        ))
+    }
+
+    /// Serves the local pipe `name` (see `SidecarConfig::pipe_name`) instead of TCP.
+    pub fn spawn_pipe(
+        name: &str,
+        pairing_key: Option<String>,
+        in_tx: Sender<InboundMsg>,
+        out_rx: Receiver<OutboundMsg>,
+    ) -> Result<Self, String> {
+        let (listener, endpoint) = Listener::bind_pipe(name)?;
+        Ok(Self::spawn_listener(
+            listener,
+            endpoint,
+            pairing_key,
+            None,
+            None,
+            in_tx,
+            out_rx,
+        ))
+    }
+
+    fn spawn_listener(
+        listener: Listener,
+        endpoint: Endpoint,
+        pairing_key: Option<String>,
+        tls: Option<TlsIdentity>,
+        osc: Option<OscPeer>,
+        in_tx: Sender<InboundMsg>,
+        out_rx: Receiver<OutboundMsg>,
+    ) -> Self {
+        let tls_fingerprint = tls.as_ref().map(|t| t.fingerprint().to_string());
+        let osc_addr = osc.as_ref().and_then(|o| o.socket.local_addr().ok());
         let shutdown = Arc::new(AtomicBool::new(false));
         let shutdown_for_thread = Arc::clone(&shutdown);
 
-        let join_handle =
-            thread::spawn(move || run_server(listener, in_tx, out_rx, shutdown_for_thread));
+        let join_handle = thread::spawn(move || {
+            run_server(
+                listener,
+                pairing_key,
+                tls,
+                osc,
+                in_tx,
+                out_rx,
+                shutdown_for_thread,
+            )
+        });
 
-        Ok(Self {
+        Self {
             shutdown,
             join_handle: Mutex::new(Some(join_handle)),
-            listen_addr,
-        })
+            endpoint,
+            tls_fingerprint,
+            osc_addr,
+            discovery: None,
+            advertisement: None,
+        }
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
     }
 
-    pub fn listen_addr(&self) -> SocketAddr {
-        self.listen_addr
+    /// Bound TCP address; `None` on the pipe transport.
+    pub fn listen_addr(&self) -> Option<SocketAddr> {
+        match self.endpoint {
+            Endpoint::Tcp(addr) => Some(addr),
+            Endpoint::Pipe(_) => None,
+        }
+    }
+
+    /// SHA-256 of the server certificate when serving `wss://`.
+    pub fn tls_fingerprint(&self) -> Option<&str> {
+        self.tls_fingerprint.as_deref()
+    }
+
+    /// Bound OSC address, when the OSC endpoint is on.
+    pub fn osc_addr(&self) -> Option<SocketAddr> {
+        self.osc_addr
     }
 
     pub fn shutdown(&self) {
@@ -66,6 +971,12 @@ impl NetworkThread {
                 let _ = h.join();
             }
         }
+        if let Some(port) = self.discovery {
+            remove_discovery(port);
+        }
+        if let Some(advertisement) = &self.advertisement {
+            advertisement.stop();
+        }
     }
 }
 
@@ -76,136 +987,308 @@ impl Drop for NetworkThread {
 }
 
 fn run_server(
-    listener: TcpListener,
+    listener: Listener,
+    pairing_key: Option<String>,
+    tls: Option<TlsIdentity>,
+    mut osc: Option<OscPeer>,
     in_tx: Sender<InboundMsg>,
     out_rx: Receiver<OutboundMsg>,
     shutdown: Arc<AtomicBool>,
 ) {
-    let mut active: Option<ActiveClient> = None;
+    let mut clients: Vec<Client> = Vec::new();
+    // Tokens of dropped connections and the instant they can no longer be resumed.
+    let mut retired: Vec<(String, Instant)> = Vec::new();
+    let mut next_client_id: ClientId = 1;
+    let ttl = token_ttl();
 
     while !shutdown.load(Ordering::Relaxed) {
-        // Accept new connections (single-client policy).
+        // Accept new connections.
         loop {
             match listener.accept() {
-                Ok((stream, socket_addr)) => {
-                    let _ = stream.set_nodelay(true);
-                    let _ = stream.set_read_timeout(Some(Duration::from_millis(30)));
-                    let _ = stream.set_write_timeout(Some(Duration::from_millis(200)));
-
-                    let ws = match tungstenite::accept(stream) {
-                        Ok(ws) => ws,
+                Ok((stream, peer)) => {
+                    let stream = match wrap_stream(stream, tls.as_ref()) {
+                        Ok(stream) => stream,
                         Err(e) => {
-                            eprintln!("ws handshake failed: {e}");
+                            telemetry::warn(
+                                "net",
+                                format!("tls handshake from {peer} failed: {e}"),
+                            );
                             continue;
                         }
                     };
 
-                    let session_token: String = thread_rng()
-                        .sample_iter(&Alphanumeric)
-                        .take(32)
-                        .map(char::from)
-                        .collect();
+                    let (mut ws, encoding) = match accept_ws(stream, pairing_key.as_deref()) {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            telemetry::warn("net", format!("ws handshake from {peer} failed: {e}"));
+                            continue;
+                        }
+                    };
 
-                    // Close previous active client.
-                    if let Some(mut prev) = active.take() {
-                        let _ = prev.ws.close(None);
-                        let _ = in_tx.try_send(InboundMsg::ClientDisconnected);
+                    if clients.len() >= MAX_CLIENTS {
+                        let _ = send_server_message(
+                            &mut ws,
+                            encoding,
+                            &ServerMessage::Error {
+                                msg: format!("server busy ({MAX_CLIENTS} clients connected)"),
+                                code: ErrorCode::Busy,
+                                details: None,
+                                command_id: None,
+                                diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+                            },
+                        );
+                        let _ = ws.close(None);
+                        continue;
                     }
 
+                    let client_id = next_client_id;
+                    let session_token = new_session_token();
+
                     // Notify main loop.
                     if in_tx
                         .try_send(InboundMsg::ClientConnected {
-                            socket_addr,
+                            client_id,
+                            peer: peer.clone(),
                             session_token: session_token.clone(),
                         })
                         .is_err()
                     {
                         // Busy: try to tell the client then drop the socket.
-                        let mut ws = ws;
                         let _ = send_server_message(
                             &mut ws,
+                            encoding,
                             &ServerMessage::Error {
                                 msg: "server busy".to_string(),
                                 code: ErrorCode::Busy,
+                                details: None,
+                                command_id: None,
+                                diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
                             },
                         );
                         let _ = ws.close(None);
                         continue;
                     }
 
-                    active = Some(ActiveClient {
+                    next_client_id += 1;
+                    clients.push(Client {
+                        id: client_id,
                         ws,
                         session_token,
-                        socket_addr,
+                        peer,
+                        encoding,
+                        token_issued_at: Instant::now(),
+                        previous_token: None,
+                        outbound: OutboundQueue::default(),
+                        last_ping: Instant::now(),
                     });
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
                 Err(e) => {
-                    eprintln!("ws accept failed: {e}");
+                    telemetry::warn("net", format!("ws accept failed: {e}"));
                     break;
                 }
             }
         }
 
-        // Outbound: drain queued messages.
-        if let Some(client) = active.as_mut() {
-            loop {
-                match out_rx.try_recv() {
-                    Ok(OutboundMsg::Send { msg }) => {
-                        if send_server_message(&mut client.ws, &msg).is_err() {
-                            let _ = client.ws.close(None);
-                            active = None;
-                            let _ = in_tx.try_send(InboundMsg::ClientDisconnected);
-                            break;
-                        }
+        if let Some(osc) = osc.as_mut() {
+            read_osc(&in_tx, osc, &mut next_client_id);
+        }
+
+        for client in clients.iter_mut() {
+            if ttl.is_some_and(|t| client.token_issued_at.elapsed() >= t) {
+                rotate_token(&in_tx, client, ttl);
+            }
+            if client
+                .previous_token
+                .as_ref()
+                .is_some_and(|(_, until)| Instant::now() >= *until)
+            {
+                client.previous_token = None;
+            }
+        }
+        retired.retain(|(_, until)| Instant::now() < *until);
+
+        // Outbound: route queued messages, then write each client's queue.
+        if route_outbound(&mut clients, osc.as_ref(), &out_rx).is_err() {
+            return;
+        }
+
+        let mut gone: Vec<ClientId> = Vec::new();
+        for client in clients.iter_mut() {
+            if client.last_ping.elapsed() >= PING_INTERVAL {
+                client.last_ping = Instant::now();
+                match client.ws.send(Message::Ping(Default::default())) {
+                    Ok(()) => {}
+                    Err(e) if is_backpressure(&e) => client.outbound.backed_up = true,
+                    Err(_) => {
+                        gone.push(client.id);
+                        continue;
                     }
-                    Err(TryRecvError::Empty) => break,
-                    Err(TryRecvError::Disconnected) => return,
                 }
             }
+            if client.flush().is_err() {
+                let _ = client.ws.close(None);
+                gone.push(client.id);
+            }
         }
 
-        // Inbound: read at most one message per loop (timeouts keep the loop moving).
-        if let Some(client) = active.as_mut() {
+        // Inbound: read at most one message per client per loop.
+        let mut idle = true;
+        for client in clients.iter_mut().filter(|c| !gone.contains(&c.id)) {
             match client.ws.read() {
                 Ok(msg) => {
-                    if handle_inbound(&in_tx, client, msg).is_err() {
+                    idle = false;
+                    if handle_inbound(&in_tx, client, &mut retired, msg, ttl).is_err() {
                         let _ = client.ws.close(None);
-                        active = None;
-                        let _ = in_tx.try_send(InboundMsg::ClientDisconnected);
+                        gone.push(client.id);
                     }
                 }
                 Err(tungstenite::Error::Io(e))
                     if e.kind() == std::io::ErrorKind::WouldBlock
                         || e.kind() == std::io::ErrorKind::TimedOut => {}
-                Err(tungstenite::Error::ConnectionClosed) => {
-                    active = None;
-                    let _ = in_tx.try_send(InboundMsg::ClientDisconnected);
-                }
-                Err(_) => {
-                    active = None;
-                    let _ = in_tx.try_send(InboundMsg::ClientDisconnected);
-                }
+                Err(_) => gone.push(client.id),
             }
-        } else {
-            // If no active client, avoid busy-looping.
+        }
+
+        if !gone.is_empty() {
+            let until = Instant::now() + Duration::from_secs(RESUME_GRACE_SECS);
+            for client in clients.iter().filter(|c| gone.contains(&c.id)) {
+                retired.push((client.session_token.clone(), until));
+            }
+            clients.retain(|c| !gone.contains(&c.id));
+            for client_id in gone {
+                let _ = in_tx.try_send(InboundMsg::ClientDisconnected { client_id });
+            }
+        }
+
+        if clients.is_empty() {
+            // If no client is connected, avoid busy-looping.
             thread::sleep(Duration::from_millis(25));
+        } else if idle {
+            thread::sleep(CLIENT_IDLE_WAIT);
         }
     }
 
-    if let Some(mut client) = active {
+    // The main loop queues its `Shutdown` notice before stopping this thread; send it and
+    // anything else still queued ahead of the close frame.
+    let _ = route_outbound(&mut clients, osc.as_ref(), &out_rx);
+    let deadline = Instant::now() + SHUTDOWN_FLUSH_TIMEOUT;
+    for mut client in clients {
+        while client.flush().is_ok() && !client.outbound.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
         let _ = client.ws.close(None);
+        while Instant::now() < deadline {
+            match client.ws.flush() {
+                Err(tungstenite::Error::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                _ => break,
+            }
+        }
+    }
+}
+
+/// Hands every queued message to its client(s); `Err` once the main loop is gone. Replies to
+/// the OSC session go out at once, as OSC; broadcasts are for websocket clients only.
+fn route_outbound(
+    clients: &mut [Client],
+    osc: Option<&OscPeer>,
+    out_rx: &Receiver<OutboundMsg>,
+) -> Result<(), ()> {
+    loop {
+        match out_rx.try_recv() {
+            Ok(OutboundMsg::Send { client_id, msg }) => {
+                if let Some(client) = clients.iter_mut().find(|c| c.id == client_id) {
+                    client.enqueue(msg);
+                } else if let Some(osc) = osc.filter(|o| o.id == Some(client_id)) {
+                    if let Some(packet) = osc::reply(&msg) {
+                        osc.send(&packet);
+                    }
+                }
+            }
+            Ok(OutboundMsg::Broadcast { msg }) => {
+                for client in clients.iter_mut() {
+                    client.enqueue(msg.clone());
+                }
+            }
+            Err(TryRecvError::Empty) => return Ok(()),
+            Err(TryRecvError::Disconnected) => return Err(()),
+        }
+    }
+}
+
+/// Turns waiting OSC datagrams into commands. A bad one is answered on `/gojira/error` and
+/// dropped; there is no connection to close.
+fn read_osc(in_tx: &Sender<InboundMsg>, osc: &mut OscPeer, next_client_id: &mut ClientId) {
+    let mut buf = vec![0u8; OSC_MAX_PACKET];
+    for _ in 0..OSC_READS_PER_LOOP {
+        let (len, from) = match osc.socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return,
+            // Windows reports a reply that found no listener on the next read; not our problem.
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionReset => continue,
+            Err(e) => {
+                telemetry::warn("net", format!("osc receive failed: {e}"));
+                return;
+            }
+        };
+        osc.reply_to = Some(from);
+
+        let client_id = match osc.id {
+            Some(id) => id,
+            None => {
+                let client_id = *next_client_id;
+                let connected = in_tx.try_send(InboundMsg::ClientConnected {
+                    client_id,
+                    peer: format!("osc:{from}"),
+                    session_token: osc.session_token.clone(),
+                });
+                if connected.is_err() {
+                    osc.send_error("", "server busy");
+                    continue;
+                }
+                *next_client_id += 1;
+                osc.id = Some(client_id);
+                client_id
+            }
+        };
+
+        let messages = match osc::decode(&buf[..len]) {
+            Ok(messages) => messages,
+            Err(e) => {
+                osc.send_error("", &e);
+                continue;
+            }
+        };
+        for msg in messages {
+            osc.commands += 1;
+            let command_id = format!("osc-{}", osc.commands);
+            match osc::to_command(&msg, &osc.session_token, command_id.clone()) {
+                Ok(Some(cmd)) => {
+                    let queued = in_tx.try_send(InboundMsg::Command { client_id, cmd });
+                    if queued.is_err() {
+                        osc.send_error(&command_id, "server busy");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => osc.send_error(&command_id, &e),
+            }
+        }
     }
 }
 
 fn handle_inbound(
     in_tx: &Sender<InboundMsg>,
-    client: &mut ActiveClient,
+    client: &mut Client,
+    retired: &mut Vec<(String, Instant)>,
     msg: Message,
+    ttl: Option<Duration>,
 ) -> Result<(), ()> {
-    let text = match msg {
-        Message::Text(s) => s,
-        Message::Binary(_) => return Ok(()),
+    // Decode by frame type rather than the negotiated encoding; a client may mix them.
+    let decoded: Result<ClientCommand, String> = match msg {
+        Message::Text(s) => Encoding::Json.decode(s.as_bytes()),
+        Message::Binary(b) => Encoding::Msgpack.decode(&b),
         Message::Ping(payload) => {
             let _ = client.ws.send(Message::Pong(payload));
             return Ok(());
@@ -215,51 +1298,280 @@ fn handle_inbound(
         Message::Frame(_) => return Ok(()),
     };
 
-    let cmd: ClientCommand = match serde_json::from_str(&text) {
+    let cmd = match decoded {
         Ok(c) => c,
         Err(_) => {
-            let _ = send_server_message(
-                &mut client.ws,
-                &ServerMessage::Error {
-                    msg: "invalid json".to_string(),
-                    code: ErrorCode::InvalidCommand,
-                },
-            );
+            client.enqueue(ServerMessage::Error {
+                msg: "invalid command".to_string(),
+                code: ErrorCode::InvalidCommand,
+                details: None,
+                command_id: None,
+                diagnostic: None,
+            });
             return Ok(());
         }
     };
 
-    if client.session_token != cmd.session_token() {
-        let _ = send_server_message(
-            &mut client.ws,
-            &ServerMessage::Error {
-                msg: "unauthorized".to_string(),
-                code: ErrorCode::Unauthorized,
-            },
-        );
+    // A resume is vouched for by the dropped connection's token rather than this one's.
+    let resumed = match &cmd {
+        ClientCommand::Resume { previous_token } => retired
+            .iter()
+            .position(|(token, _)| token == previous_token),
+        _ => None,
+    };
+    let authorized = match &cmd {
+        ClientCommand::Resume { .. } => resumed.is_some(),
+        _ => client.accepts(cmd.session_token()),
+    };
+    if !authorized {
+        client.enqueue(ServerMessage::Error {
+            msg: "unauthorized".to_string(),
+            code: ErrorCode::Unauthorized,
+            details: None,
+            command_id: cmd.command_id().map(str::to_string),
+            diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Reauthenticate)),
+        });
         return Ok(());
     }
 
-    if in_tx.try_send(InboundMsg::Command { cmd: cmd.clone() }).is_err() {
+    if matches!(cmd, ClientCommand::RotateToken { .. }) {
+        if !rotate_token(in_tx, client, ttl) {
+            client.enqueue(ServerMessage::Error {
+                msg: "server busy".to_string(),
+                code: ErrorCode::Busy,
+                details: None,
+                command_id: None,
+                diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+            });
+        }
+        return Ok(());
+    }
+
+    if in_tx
+        .try_send(InboundMsg::Command {
+            client_id: client.id,
+            cmd: cmd.clone(),
+        })
+        .is_err()
+    {
         if matches!(cmd, ClientCommand::RefreshInstances { .. }) {
             return Ok(());
         }
-        let _ = send_server_message(
-            &mut client.ws,
-            &ServerMessage::Error {
-                msg: "server busy".to_string(),
-                code: ErrorCode::Busy,
-            },
-        );
+        client.enqueue(ServerMessage::Error {
+            msg: "server busy".to_string(),
+            code: ErrorCode::Busy,
+            details: None,
+            command_id: cmd.command_id().map(str::to_string),
+            diagnostic: Some(ErrorDiagnostic::remedy(Remediation::Retry)),
+        });
+        return Ok(());
+    }
+
+    if let Some(i) = resumed {
+        // The main loop switches this client over to the old token along with us.
+        let (token, _) = retired.swap_remove(i);
+        client.session_token = token;
+        client.previous_token = None;
     }
 
     Ok(())
 }
 
+fn encode_message(encoding: Encoding, msg: &ServerMessage) -> Result<Message, ()> {
+    match encoding.encode(msg).map_err(|_| ())? {
+        Frame::Text(text) => Ok(Message::Text(text.into())),
+        Frame::Binary(bytes) => Ok(Message::Binary(bytes.into())),
+    }
+}
+
 fn send_server_message(
-    ws: &mut tungstenite::WebSocket<TcpStream>,
+    ws: &mut tungstenite::WebSocket<ServerStream>,
+    encoding: Encoding,
     msg: &ServerMessage,
 ) -> Result<(), ()> {
-    let payload = serde_json::to_string(msg).map_err(|_| ())?;
-    ws.send(Message::Text(payload.into())).map_err(|_| ())
+    ws.send(encode_message(encoding, msg)?).map_err(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Confidence, GojiraInstance, ParamReading};
+
+    fn pong(nonce: u64) -> ServerMessage {
+        ServerMessage::Pong {
+            nonce,
+            uptime_ms: 0,
+        }
+    }
+
+    fn reply(command_id: &str) -> ServerMessage {
+        ServerMessage::Error {
+            msg: "nope".to_string(),
+            code: ErrorCode::Busy,
+            details: None,
+            command_id: Some(command_id.to_string()),
+            diagnostic: None,
+        }
+    }
+
+    fn param_changed(target: &str, params: &[(i32, f64)]) -> ServerMessage {
+        ServerMessage::ParamChanged {
+            target_fx_guid: target.to_string(),
+            params: params
+                .iter()
+                .map(|&(index, value)| ParamReading {
+                    index,
+                    value,
+                    formatted: String::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn instance(fx_guid: &str) -> GojiraInstance {
+        GojiraInstance {
+            track_guid: "track".to_string(),
+            track_name: "Guitar".to_string(),
+            fx_guid: fx_guid.to_string(),
+            fx_name: "Archetype Gojira".to_string(),
+            last_known_fx_index: 0,
+            confidence: Confidence::High,
+            profile: None,
+        }
+    }
+
+    fn project_changed(added: Vec<GojiraInstance>, removed: Vec<GojiraInstance>) -> ServerMessage {
+        ServerMessage::ProjectChanged {
+            project_tempo: None,
+            project_name: Some("song.rpp".to_string()),
+            changed_instances: Vec::new(),
+            added,
+            removed,
+            renamed: Vec::new(),
+        }
+    }
+
+    fn drain(queue: &mut OutboundQueue) -> Vec<ServerMessage> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn replies_jump_ahead_of_queued_pushes() {
+        let mut queue = OutboundQueue::default();
+        assert!(queue.push(pong(1)));
+        assert!(queue.push(param_changed("fx", &[(1, 0.5)])));
+        assert!(queue.push(reply("c1")));
+        let out = drain(&mut queue);
+        assert!(matches!(&out[0], ServerMessage::Error { command_id: Some(id), .. } if id == "c1"));
+        assert!(matches!(out[1], ServerMessage::ParamChanged { .. }));
+        assert!(matches!(out[2], ServerMessage::Pong { nonce: 1, .. }));
+    }
+
+    #[test]
+    fn param_changes_for_one_target_merge() {
+        let mut queue = OutboundQueue::default();
+        queue.push(param_changed("a", &[(1, 0.1)]));
+        queue.push(param_changed("b", &[(1, 0.9)]));
+        queue.push(param_changed("a", &[(1, 0.5), (2, 0.3)]));
+        let out = drain(&mut queue);
+        assert_eq!(out.len(), 2);
+        let ServerMessage::ParamChanged {
+            target_fx_guid,
+            params,
+        } = &out[0]
+        else {
+            panic!("expected param_changed, got {:?}", out[0]);
+        };
+        assert_eq!(target_fx_guid, "a");
+        let values: Vec<_> = params.iter().map(|p| (p.index, p.value)).collect();
+        assert_eq!(values, [(1, 0.5), (2, 0.3)]);
+    }
+
+    #[test]
+    fn removed_then_added_instance_merges_into_renamed() {
+        let mut queue = OutboundQueue::default();
+        queue.push(project_changed(Vec::new(), vec![instance("fx")]));
+        queue.push(project_changed(vec![instance("fx")], Vec::new()));
+        let out = drain(&mut queue);
+        assert_eq!(out.len(), 1);
+        let ServerMessage::ProjectChanged {
+            added,
+            removed,
+            renamed,
+            ..
+        } = &out[0]
+        else {
+            panic!("expected project_changed, got {:?}", out[0]);
+        };
+        assert!(added.is_empty() && removed.is_empty());
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed[0].fx_guid, "fx");
+    }
+
+    #[test]
+    fn changes_do_not_merge_past_a_later_reply() {
+        let mut queue = OutboundQueue::default();
+        queue.push(param_changed("a", &[(1, 0.1)]));
+        queue.push(reply("c1"));
+        queue.push(param_changed("a", &[(1, 0.5)]));
+        let out = drain(&mut queue);
+        assert_eq!(out.len(), 3);
+        assert!(matches!(out[0], ServerMessage::Error { .. }));
+        let first = match &out[1] {
+            ServerMessage::ParamChanged { params, .. } => params[0].value,
+            other => panic!("expected param_changed, got {other:?}"),
+        };
+        assert_eq!(first, 0.1);
+    }
+
+    #[test]
+    fn telemetry_is_dropped_only_while_backed_up() {
+        let mut queue = OutboundQueue::default();
+        queue.push(pong(1));
+        assert_eq!(queue.entries.len(), 1);
+
+        queue.backed_up = true;
+        assert!(queue.push(pong(2)));
+        assert_eq!(queue.entries.len(), 1);
+        assert_eq!(queue.dropped, 1);
+
+        drain(&mut queue);
+        assert_eq!(queue.drained(), 1);
+        assert!(!queue.backed_up);
+        assert_eq!(queue.dropped, 0);
+
+        queue.push(pong(3));
+        assert_eq!(queue.entries.len(), 1);
+    }
+
+    #[test]
+    fn full_queue_evicts_oldest_telemetry_before_refusing_a_reply() {
+        let mut queue = OutboundQueue::default();
+        for nonce in 0..OUTBOUND_CAP as u64 {
+            assert!(queue.push(pong(nonce)));
+        }
+        assert!(!queue.push(pong(u64::MAX)));
+
+        assert!(queue.push(reply("c1")));
+        assert_eq!(queue.entries.len(), OUTBOUND_CAP);
+        assert!(matches!(queue.entries[0].msg, ServerMessage::Error { .. }));
+        assert!(matches!(
+            queue.entries[1].msg,
+            ServerMessage::Pong { nonce: 1, .. }
+        ));
+
+        let mut full = OutboundQueue::default();
+        for i in 0..OUTBOUND_CAP {
+            assert!(full.push(reply(&format!("c{i}"))));
+        }
+        assert!(!full.push(reply("late")));
+    }
+
+    #[test]
+    fn osc_stays_on_loopback_unless_remote_is_allowed() {
+        assert!(OscPeer::bind("127.0.0.1:0", false).is_ok());
+        let refused = OscPeer::bind("0.0.0.0:0", false).err().expect("refused");
+        assert!(refused.contains("osc_allow_remote"), "{refused}");
+        assert!(OscPeer::bind("0.0.0.0:0", true).is_ok());
+    }
 }
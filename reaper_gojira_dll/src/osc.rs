@@ -0,0 +1,298 @@
+use crate::protocol::{ClientCommand, MergeMode, ParamChange, ParamOp, ServerMessage};
+
+/// Every address the bridge answers lives under this.
+const PREFIX: &str = "/gojira/";
+/// Bundles inside bundles beyond this are refused rather than recursed into.
+const MAX_BUNDLE_DEPTH: usize = 4;
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+const TRUNCATED: &str = "truncated OSC packet";
+
+/// An OSC argument of one of the types controllers actually send.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl OscArg {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            OscArg::Int(v) => Some(f64::from(*v)),
+            OscArg::Float(v) => Some(f64::from(*v)),
+            OscArg::Long(v) => Some(*v as f64),
+            OscArg::Double(v) => Some(*v),
+            OscArg::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            OscArg::Str(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            OscArg::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub addr: String,
+    pub args: Vec<OscArg>,
+}
+
+/// The messages in one datagram; a bundle's come out in order, its time tag ignored.
+pub fn decode(packet: &[u8]) -> Result<Vec<OscMessage>, String> {
+    let mut out = Vec::new();
+    decode_into(packet, 0, &mut out)?;
+    Ok(out)
+}
+
+fn decode_into(packet: &[u8], depth: usize, out: &mut Vec<OscMessage>) -> Result<(), String> {
+    if packet.starts_with(BUNDLE_TAG) {
+        if depth >= MAX_BUNDLE_DEPTH {
+            return Err("OSC bundles nested too deep".to_string());
+        }
+        let mut rest = packet.get(BUNDLE_TAG.len() + 8..).ok_or(TRUNCATED)?;
+        while !rest.is_empty() {
+            let len = usize::try_from(read_i32(&mut rest)?).map_err(|_| TRUNCATED)?;
+            let element = take(&mut rest, len)?;
+            decode_into(element, depth + 1, out)?;
+        }
+        return Ok(());
+    }
+
+    let mut rest = packet;
+    let addr = read_str(&mut rest)?;
+    if !addr.starts_with('/') {
+        return Err(format!("not an OSC address: {addr:?}"));
+    }
+    // OSC 1.0 lets old senders leave the type tags out; such a message has no args.
+    let tags = if rest.is_empty() {
+        String::new()
+    } else {
+        read_str(&mut rest)?
+    };
+    let mut args = Vec::new();
+    for tag in tags.strip_prefix(',').unwrap_or(&tags).chars() {
+        args.push(match tag {
+            'i' => OscArg::Int(read_i32(&mut rest)?),
+            'f' => OscArg::Float(f32::from_bits(read_i32(&mut rest)? as u32)),
+            'h' => OscArg::Long(read_i64(&mut rest)?),
+            'd' => OscArg::Double(f64::from_bits(read_i64(&mut rest)? as u64)),
+            's' | 'S' => OscArg::Str(read_str(&mut rest)?),
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            'N' | 'I' => continue,
+            other => return Err(format!("unsupported OSC type tag {other:?}")),
+        });
+    }
+    out.push(OscMessage { addr, args });
+    Ok(())
+}
+
+fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8], String> {
+    if rest.len() < n {
+        return Err(TRUNCATED.to_string());
+    }
+    let (head, tail) = rest.split_at(n);
+    *rest = tail;
+    Ok(head)
+}
+
+fn read_i32(rest: &mut &[u8]) -> Result<i32, String> {
+    let bytes = <[u8; 4]>::try_from(take(rest, 4)?).map_err(|_| TRUNCATED)?;
+    Ok(i32::from_be_bytes(bytes))
+}
+
+fn read_i64(rest: &mut &[u8]) -> Result<i64, String> {
+    let bytes = <[u8; 8]>::try_from(take(rest, 8)?).map_err(|_| TRUNCATED)?;
+    Ok(i64::from_be_bytes(bytes))
+}
+
+/// A nul-terminated string, padded with nuls to a multiple of 4 bytes.
+fn read_str(rest: &mut &[u8]) -> Result<String, String> {
+    let end = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("unterminated OSC string")?;
+    let s = std::str::from_utf8(&rest[..end])
+        .map_err(|_| "OSC string is not UTF-8")?
+        .to_string();
+    take(rest, (end + 4) & !3)?;
+    Ok(s)
+}
+
+pub fn encode(addr: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_str(&mut out, addr);
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::Long(_) => 'h',
+            OscArg::Double(_) => 'd',
+            OscArg::Str(_) => 's',
+            OscArg::Bool(true) => 'T',
+            OscArg::Bool(false) => 'F',
+        }))
+        .collect();
+    write_str(&mut out, &tags);
+    for arg in args {
+        match arg {
+            OscArg::Int(v) => out.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Float(v) => out.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Long(v) => out.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Double(v) => out.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Str(s) => write_str(&mut out, s),
+            OscArg::Bool(_) => {}
+        }
+    }
+    out
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.resize(out.len() + 4 - s.len() % 4, 0);
+}
+
+/// The command a websocket client would send for `msg`:
+///
+/// - `/gojira/settone ,s(if)*`: FX guid, then index/value pairs, merged into the current tone
+/// - `/gojira/slot ,i` or `/gojira/slot/<n>`: activate a tone slot
+/// - `/gojira/undo`, `/gojira/play`, `/gojira/stop`, `/gojira/refresh`
+///
+/// Nothing else: OSC has no pairing key, so it only gets the verbs a control surface needs.
+///
+/// `Ok(None)` for a button's release (a zero arg), which controllers send after every press.
+pub fn to_command(
+    msg: &OscMessage,
+    session_token: &str,
+    command_id: String,
+) -> Result<Option<ClientCommand>, String> {
+    let path = msg
+        .addr
+        .strip_prefix(PREFIX)
+        .ok_or_else(|| format!("unknown OSC address {}", msg.addr))?;
+    let session_token = session_token.to_string();
+    let segments: Vec<&str> = path.split('/').collect();
+    if !matches!(segments[..], ["settone"] | ["slot"]) && !pressed(&msg.args) {
+        return Ok(None);
+    }
+    let cmd = match segments[..] {
+        ["settone"] => {
+            let target_fx_guid = msg
+                .args
+                .first()
+                .and_then(OscArg::as_str)
+                .ok_or("settone: first arg must be the FX guid")?
+                .to_string();
+            let values = &msg.args[1..];
+            if values.len() % 2 != 0 {
+                return Err("settone: params come as index/value pairs".to_string());
+            }
+            let params = values
+                .chunks(2)
+                .map(|pair| match (pair[0].as_f64(), pair[1].as_f64()) {
+                    (Some(index), Some(value)) if index.fract() == 0.0 => Ok(ParamChange {
+                        index: index as i32,
+                        value,
+                        op: ParamOp::Set,
+                    }),
+                    _ => Err("settone: params come as index/value pairs".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            ClientCommand::SetTone {
+                session_token,
+                command_id,
+                target_fx_guid,
+                mode: MergeMode::Merge,
+                params,
+                locked_params: Vec::new(),
+                ramp_ms: None,
+                atomic: false,
+                apply_at: None,
+                modules: None,
+                allow_background_projects: false,
+            }
+        }
+        ["slot"] => {
+            let slot = msg.args.first().and_then(OscArg::as_f64);
+            activate_slot(session_token, command_id, slot)?
+        }
+        ["slot", n] => activate_slot(session_token, command_id, n.parse::<f64>().ok())?,
+        ["undo"] => ClientCommand::UndoLastTone {
+            session_token,
+            command_id,
+        },
+        ["play"] => ClientCommand::Play {
+            session_token,
+            command_id,
+        },
+        ["stop"] => ClientCommand::Stop {
+            session_token,
+            command_id,
+        },
+        ["refresh"] => ClientCommand::RefreshInstances {
+            session_token,
+            instances_only: false,
+        },
+        _ => return Err(format!("unknown OSC address {}", msg.addr)),
+    };
+    Ok(Some(cmd))
+}
+
+fn activate_slot(
+    session_token: String,
+    command_id: String,
+    slot: Option<f64>,
+) -> Result<ClientCommand, String> {
+    let slot = slot
+        .filter(|s| s.fract() == 0.0 && (0.0..=f64::from(u32::MAX)).contains(s))
+        .ok_or("slot: needs a slot number")?;
+    Ok(ClientCommand::ActivateToneSlot {
+        session_token,
+        command_id,
+        slot: slot as u32,
+        ramp_ms: None,
+        apply_at: None,
+    })
+}
+
+/// No arg, or a non-zero first one: a button press rather than its release.
+fn pressed(args: &[OscArg]) -> bool {
+    args.first()
+        .and_then(OscArg::as_f64)
+        .map_or(true, |v| v != 0.0)
+}
+
+/// What an OSC sender hears back, by command id: acks, errors and the transport state. The rest
+/// (handshakes, param meta, project changes) is only for websocket clients.
+pub fn reply(msg: &ServerMessage) -> Option<Vec<u8>> {
+    match msg {
+        ServerMessage::Ack { command_id, .. } | ServerMessage::MultiAck { command_id, .. } => {
+            Some(encode("/gojira/ack", &[OscArg::Str(command_id.clone())]))
+        }
+        ServerMessage::Error {
+            msg, command_id, ..
+        } => Some(encode(
+            "/gojira/error",
+            &[
+                OscArg::Str(command_id.clone().unwrap_or_default()),
+                OscArg::Str(msg.clone()),
+            ],
+        )),
+        ServerMessage::Transport {
+            command_id,
+            playing,
+            ..
+        } => Some(encode(
+            "/gojira/transport",
+            &[OscArg::Str(command_id.clone()), OscArg::Bool(*playing)],
+        )),
+        _ => None,
+    }
+}
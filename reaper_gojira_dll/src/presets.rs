@@ -0,0 +1,101 @@
+use crate::protocol::{ParamChange, SidecarConfig};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const MAX_NAME_LEN: usize = 64;
+
+/// A tone saved with `save_preset`; one JSON file per preset name.
+#[derive(Serialize, Deserialize)]
+pub struct StoredPreset {
+    pub name: String,
+    /// FX the tone was read from, for whoever browses the folder by hand.
+    #[serde(default)]
+    pub fx_name: String,
+    pub params: Vec<ParamChange>,
+}
+
+/// `presets/` next to the sidecar config file.
+pub fn default_dir() -> Option<PathBuf> {
+    let config = SidecarConfig::path()?;
+    Some(config.parent()?.join("presets"))
+}
+
+/// Names become file names, so only a conservative character set is accepted.
+pub fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("preset name is empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("preset name is over {MAX_NAME_LEN} characters"));
+    }
+    if name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '(' | ')'))
+    {
+        return Err(format!(
+            "preset name {name:?} may only use letters, digits, spaces and - _ . ( )"
+        ));
+    }
+    Ok(name)
+}
+
+#[derive(Clone)]
+pub struct PresetStore {
+    dir: PathBuf,
+}
+
+impl PresetStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.json"))
+    }
+
+    /// Saved preset names, sorted; empty if nothing was saved yet.
+    pub fn list(&self) -> Result<Vec<String>, String> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("preset folder {}: {e}", self.dir.display())),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            })
+            .collect();
+        names.sort_by_key(|n| n.to_lowercase());
+        Ok(names)
+    }
+
+    /// Written to a temp file first so a crash never leaves a half-written preset behind.
+    pub fn save(&self, preset: &StoredPreset) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("preset folder {}: {e}", self.dir.display()))?;
+        let json = serde_json::to_string_pretty(preset).map_err(|e| e.to_string())?;
+        let path = self.file(&preset.name);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(|e| format!("{}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// `None` if no preset of that name was saved.
+    pub fn load(&self, name: &str) -> Result<Option<StoredPreset>, String> {
+        let path = self.file(name);
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        serde_json::from_str(&raw)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
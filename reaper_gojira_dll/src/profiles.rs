@@ -0,0 +1,256 @@
+use crate::protocol::{Confidence, ParamKind, ParamSchemaEntry, ParamSchemaKind};
+use crate::reaper_api::ReaperApi;
+use gojira_param_catalog::{self as catalog, ModuleSpec, ParamSpec, Unit};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// One Archetype plugin's param layout, selected by FX name. Profiles without a charted layout
+/// still resolve, but have no modules for the cleaner and nothing for the validator to check.
+pub struct PluginProfile {
+    /// Reported per instance in the handshake.
+    pub id: &'static str,
+    /// Lowercase alphanumeric fragment of the FX name that selects this profile.
+    pub name_key: &'static str,
+    pub modules: &'static [ModuleSpec],
+    /// Tone params end here; the MIDI CC params after it aren't classified. `None` means all.
+    pub last_tone_param: Option<i32>,
+    /// Cab type selector, and the amp link toggle that can override it.
+    pub linked_cab: Option<(i32, i32)>,
+    /// Module params that are dry/wet controls rather than plain knobs.
+    pub mix_params: &'static [i32],
+    /// Enumerated selectors worth probing: param index, resolution option boundaries are found to
+    /// (one part in this many), most options to keep.
+    pub enum_params: &'static [(i32, usize, usize)],
+    /// Continuous controls whose formatted values reveal units, direction or scale.
+    pub format_params: &'static [RangeInclusive<i32>],
+    /// Params that get formatted samples in the default `tone` sample mode.
+    pub sample_params: &'static [RangeInclusive<i32>],
+    /// Graphic EQ bands, whose center frequencies go out in the handshake.
+    pub eq_bands: &'static [RangeInclusive<i32>],
+    /// Param names as the plugin labels them at each charted index, for finding params a plugin
+    /// update has moved.
+    pub param_names: &'static [ParamSpec],
+    /// Charted params whose names identify the plugin whatever the FX is called, checked
+    /// against `param_names`.
+    pub signature: &'static [i32],
+    /// Sections of the layout, as `param_schema` groups them.
+    pub groups: &'static [(&'static str, RangeInclusive<i32>)],
+    /// Stretches a `set_tone` can be limited to, besides the modules.
+    pub sections: &'static [(&'static str, RangeInclusive<i32>)],
+}
+
+pub const GOJIRA: PluginProfile = PluginProfile {
+    id: "gojira",
+    name_key: "gojira",
+    modules: catalog::MODULES,
+    // Bypass (118) is the last tone param.
+    last_tone_param: Some(118),
+    linked_cab: Some((84, 85)),
+    mix_params: &[105, 114],
+    enum_params: &[
+        (84, 512, 64),   // Cab Type
+        (92, 2048, 512), // Cab 1 Mic IR
+        (99, 2048, 512), // Cab 2 Mic IR
+        (113, 256, 32),  // Reverb Mode
+        (5, 128, 32),    // WOW Type
+    ],
+    format_params: &[
+        0..=2,     // input/output gain + gate
+        30..=51,   // amp knobs
+        54..=82,   // graphic EQ bands
+        87..=89,   // cab 1 mic position/distance/level
+        94..=96,   // cab 2 mic position/distance/level
+        105..=106, // delay mix/feedback
+        108..=108, // delay time
+        114..=117, // reverb
+    ],
+    sample_params: &[
+        0..=2,     // input/output gain + gate
+        29..=51,   // amp selector + knobs
+        54..=85,   // EQ bands, cab selectors
+        87..=89,   // cab 1 mic
+        92..=92,   // cab 1 IR
+        94..=96,   // cab 2 mic
+        99..=99,   // cab 2 IR
+        101..=101, // delay
+        105..=106, // delay mix/feedback
+        108..=108, // delay time
+        112..=117, // reverb
+    ],
+    eq_bands: &[54..=62, 64..=72, 74..=82],
+    param_names: catalog::PARAMS,
+    // Gate, amp and cab selectors, and the master bypass: spread over the whole layout.
+    signature: &[2, 29, 84, 118],
+    groups: catalog::GROUPS,
+    sections: catalog::SECTIONS,
+};
+
+impl PluginProfile {
+    /// The charted param at `index`, if any.
+    pub fn charted(&self, index: i32) -> Option<&'static ParamSpec> {
+        self.param_names.iter().find(|p| p.index == index)
+    }
+
+    /// Whether `index` belongs to the module or section called `name`.
+    pub fn in_module(&self, name: &str, index: i32) -> bool {
+        self.modules
+            .iter()
+            .any(|m| m.name == name && m.params.contains(&index))
+            || self
+                .sections
+                .iter()
+                .any(|(s, range)| *s == name && range.contains(&index))
+    }
+}
+
+/// Archetypes that resolve and take raw tones, but whose layout hasn't been charted yet.
+const fn uncharted(id: &'static str, name_key: &'static str) -> PluginProfile {
+    PluginProfile {
+        id,
+        name_key,
+        modules: &[],
+        last_tone_param: None,
+        linked_cab: None,
+        mix_params: &[],
+        enum_params: &[],
+        format_params: &[],
+        sample_params: &[],
+        eq_bands: &[],
+        param_names: &[],
+        signature: &[],
+        groups: &[],
+        sections: &[],
+    }
+}
+
+pub const PROFILES: &[PluginProfile] = &[
+    GOJIRA,
+    uncharted("plini", "plini"),
+    uncharted("petrucci", "petrucci"),
+    uncharted("nolly", "nolly"),
+    uncharted("tim_henson", "timhenson"),
+    uncharted("cory_wong", "corywong"),
+    uncharted("rabea", "rabea"),
+    uncharted("abasi", "abasi"),
+];
+
+/// The profile whose name key `fx_name` contains; "Archetype" in the name as well makes the match
+/// a confident one.
+pub fn match_fx_name(fx_name: &str) -> Option<(&'static PluginProfile, Confidence)> {
+    let n = normalize(fx_name);
+    let profile = PROFILES.iter().find(|p| n.contains(p.name_key))?;
+    let confidence = if n.contains("archetype") {
+        Confidence::High
+    } else {
+        Confidence::Low
+    };
+    Some((profile, confidence))
+}
+
+/// `by_name` checked against the FX's own param names. All of the profile's signature params
+/// named as charted make the match a confident one, even for an FX renamed or wrapped past
+/// "Archetype"; any of them named otherwise means the layout isn't the charted one, whatever the
+/// FX is called. Uncharted profiles have no signature and keep `by_name`.
+pub fn confidence(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    profile: &PluginProfile,
+    by_name: Confidence,
+) -> Confidence {
+    if profile.signature.is_empty() {
+        return by_name;
+    }
+    let charted = |index: i32| profile.charted(index).map(|p| normalize(p.name));
+    let matches = profile.signature.iter().all(|&index| {
+        let actual = api.track_fx_param_name(track, fx_index, index);
+        charted(index).is_some_and(|c| actual.is_some_and(|a| normalize(&a) == c))
+    });
+    if matches {
+        Confidence::High
+    } else {
+        Confidence::Low
+    }
+}
+
+/// Profile of a resolved FX. Resolving only finds FX a profile matched, so the Gojira fallback
+/// only covers one renamed since the scan.
+pub fn for_fx(api: &dyn ReaperApi, track: usize, fx_index: i32) -> &'static PluginProfile {
+    match_fx_name(&api.track_fx_name(track, fx_index)).map_or(&PROFILES[0], |(p, _)| p)
+}
+
+/// Every index in `ranges`, sorted and without duplicates.
+pub fn indices(ranges: &[RangeInclusive<i32>]) -> Vec<i32> {
+    let mut out: Vec<i32> = ranges.iter().flat_map(|r| r.clone()).collect();
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// Last tone param index the FX reports, capped by the profile.
+pub fn last_tone_param(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    profile: &PluginProfile,
+) -> i32 {
+    let cap = profile.last_tone_param.unwrap_or(i32::MAX);
+    api.track_fx_num_params(track, fx_index)
+        .map(|n| (n - 1).min(cap))
+        .or(profile.last_tone_param)
+        .unwrap_or(-1)
+}
+
+/// Name, kind and section of every tone param, by the index the FX reports it at. Names are the
+/// plugin's own, falling back to the charted ones; kinds come from the probe when `kinds` has
+/// them, else from REAPER's step sizes and the charted units.
+pub fn param_schema(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    profile: &PluginProfile,
+    kinds: &HashMap<i32, ParamKind>,
+) -> HashMap<i32, ParamSchemaEntry> {
+    (0..=last_tone_param(api, track, fx_index, profile))
+        .filter_map(|idx| {
+            let name = api
+                .track_fx_param_name(track, fx_index, idx)
+                .filter(|n| !n.trim().is_empty())
+                .or_else(|| profile.charted(idx).map(|p| p.name.to_string()))?;
+            let kind = match kinds.get(&idx) {
+                Some(ParamKind::Toggle) => ParamSchemaKind::Toggle,
+                Some(ParamKind::Stepped { .. }) => ParamSchemaKind::Enum,
+                Some(ParamKind::Continuous) => ParamSchemaKind::Continuous,
+                None => match api.track_fx_param_step_sizes(track, fx_index, idx) {
+                    Some((_, true)) => ParamSchemaKind::Toggle,
+                    Some(_) => ParamSchemaKind::Enum,
+                    None => match profile.charted(idx).map(|p| p.unit) {
+                        Some(Unit::Toggle) => ParamSchemaKind::Toggle,
+                        Some(Unit::Enum) => ParamSchemaKind::Enum,
+                        _ => ParamSchemaKind::Continuous,
+                    },
+                },
+            };
+            let group = profile
+                .groups
+                .iter()
+                .find(|(_, range)| range.contains(&idx))
+                .map_or("other", |(group, _)| group);
+            Some((
+                idx,
+                ParamSchemaEntry {
+                    name,
+                    kind,
+                    group: group.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
@@ -1,22 +1,72 @@
-use std::net::SocketAddr;
-
 pub const INBOUND_CAP: usize = 256;
 pub const OUTBOUND_CAP: usize = 256;
 
+/// What this build supports, sent in every handshake. A command goes in here together with its
+/// handler, so clients can tell it apart from builds that would answer it with an error.
+pub const CAPABILITIES: &[Capability] = &[
+    Capability::AtomicApply,
+    Capability::MultiTarget,
+    Capability::ScheduledApply,
+    Capability::ModuleFilter,
+    Capability::BackgroundProjects,
+    Capability::ParamSubscription,
+    Capability::EnumSearch,
+    Capability::Presets,
+    Capability::ToneSlots,
+    Capability::PreviewTone,
+    Capability::ValidateTone,
+    Capability::RenderPreview,
+    Capability::Snapshots,
+    Capability::Transport,
+    Capability::FxState,
+    Capability::TrackControls,
+    Capability::InsertFx,
+    Capability::Resume,
+    Capability::Diagnostics,
+    Capability::Msgpack,
+];
+
+/// Identifies one websocket connection for as long as the DLL is loaded; never reused.
+pub type ClientId = u64;
+
 pub enum InboundMsg {
     ClientConnected {
-        socket_addr: SocketAddr,
+        client_id: ClientId,
+        /// Remote address, or "pipe" for the pipe transport.
+        peer: String,
+        session_token: String,
+    },
+    ClientDisconnected {
+        client_id: ClientId,
+    },
+    TokenRotated {
+        client_id: ClientId,
         session_token: String,
+        expires_in_secs: Option<u64>,
+    },
+    Command {
+        client_id: ClientId,
+        cmd: ClientCommand,
     },
-    ClientDisconnected,
-    Command { cmd: ClientCommand },
 }
 
 pub enum OutboundMsg {
-    Send { msg: ServerMessage },
+    /// Reply to one client; dropped if it has disconnected meanwhile.
+    Send {
+        client_id: ClientId,
+        msg: ServerMessage,
+    },
+    /// Sent to every connected client.
+    Broadcast { msg: ServerMessage },
 }
 
 pub use gojira_protocol::{
-    AppliedParam, ClientCommand, Confidence, ErrorCode, GojiraInstance, MergeMode, ParamChange,
-    ServerMessage,
+    env_pairing_key, AppliedParam, Capability, ClientCommand, Confidence, Encoding, ErrorCode,
+    ErrorDiagnostic, FailedParam, Frame, GojiraInstance, HandshakeMeta, LogEvent, LogLevel,
+    MergeMode, ModuleId, ParamChange, ParamCheck, ParamEnumOption, ParamKind, ParamOp,
+    ParamReading, ParamSchemaEntry, ParamSchemaKind, PreviewRow, ProjectTempo, Remediation,
+    ReplayedEvent, ResolverState, RuntimeMetrics, ServerMessage, SidecarConfig, SkippedModule,
+    TargetResult, TimingStats, ToneIssue, ToneSlotInfo, ToneTarget, ToneWarning, Transport,
+    TransportAnchor, WarningCode, MAX_PARAM_INDEX, MAX_TONE_SLOTS, MDNS_SERVICE_TYPE,
+    MDNS_TLS_PROPERTY, PAIRING_KEY_HEADER, RESUME_GRACE_SECS,
 };
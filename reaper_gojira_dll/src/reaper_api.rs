@@ -1,23 +1,69 @@
+use c_str_macro::c_str;
 use reaper_low::raw::MediaTrack;
 use reaper_low::Reaper;
 use std::ffi::CStr;
 use std::os::raw::c_char;
+use std::path::Path;
+
+/// `UNDO_STATE_FX` from reaper_plugin.h: the undo point covers track/master FX state.
+const UNDO_STATE_FX: i32 = 2;
+
+/// "File: Render project, using the most recent render settings, auto-close render dialog".
+const ACTION_RENDER_MOST_RECENT: i32 = 42230;
+/// `RENDER_FORMAT` for 24-bit WAV (base64 of the sink's config blob).
+const RENDER_FORMAT_WAV24: &str = "ZXZhdxgAAQ==";
+/// Project render settings a preview overrides and puts back afterwards.
+const RENDER_STRING_KEYS: &[&str] = &["RENDER_FILE", "RENDER_PATTERN", "RENDER_FORMAT"];
+const RENDER_NUMBER_KEYS: &[&str] = &[
+    "RENDER_SETTINGS",
+    "RENDER_BOUNDSFLAG",
+    "RENDER_STARTPOS",
+    "RENDER_ENDPOS",
+];
 
 pub trait ReaperApi {
     fn project_state_change_count(&self) -> i32;
+    fn project_state_change_count_in(&self, project: usize) -> i32;
+    /// (bpm, beats per measure) of the current project.
+    fn project_tempo(&self) -> Option<(f32, i32)>;
+    /// Starts playback from the edit cursor, like the transport's play button.
+    fn transport_play(&self);
+    fn transport_stop(&self);
+    fn transport_playing(&self) -> bool;
+    /// Project time (seconds) of the audio REAPER is processing; `None` unless playing.
+    fn play_position(&self) -> Option<f64>;
+    /// Loop points (seconds) of the current project; `None` when unset.
+    fn loop_range(&self) -> Option<(f64, f64)>;
+    /// Sets the loop points, turns repeat on and moves the edit cursor to `start`.
+    fn set_loop(&self, start: f64, end: f64);
+    fn repeat_enabled(&self) -> bool;
+    /// Renders the master mix of `start..end` (seconds) to the WAV at `path`, blocking until done.
+    /// The project's own render settings are left as they were.
+    fn render_range(&self, start: f64, end: f64, path: &Path) -> Result<(), String>;
     fn count_tracks(&self) -> i32;
     fn get_track(&self, index: i32) -> Option<usize>;
     fn enum_project(&self, index: i32) -> Option<(usize, String)>;
     fn current_project(&self) -> Option<(usize, String)>;
     fn count_tracks_in(&self, project: usize) -> i32;
     fn get_track_in(&self, project: usize, index: i32) -> Option<usize>;
+    /// First track selected in the current project.
+    fn selected_track(&self) -> Option<usize>;
     fn track_guid(&self, track: usize) -> Option<String>;
     fn track_name(&self, track: usize) -> String;
+    /// A numeric track property by REAPER's key: "D_VOL" (linear gain), "D_PAN" (-1..1),
+    /// "I_RECMON" (0 off, 1 on, 2 auto) and the like.
+    fn track_get_info(&self, track: usize, key: &str) -> Option<f64>;
+    fn track_set_info(&self, track: usize, key: &str, value: f64) -> bool;
 
     fn track_fx_count(&self, track: usize) -> i32;
+    /// Appends a new instance of the FX matching `name` to the track's chain; its index, or
+    /// `None` when REAPER knows no such FX.
+    fn track_fx_add_by_name(&self, track: usize, name: &str) -> Option<i32>;
     fn track_fx_num_params(&self, track: usize, fx_index: i32) -> Option<i32>;
     fn track_fx_guid(&self, track: usize, fx_index: i32) -> Option<String>;
     fn track_fx_name(&self, track: usize, fx_index: i32) -> String;
+    /// REAPER's `fx_ident` for the FX: the plugin file and its id.
+    fn track_fx_ident(&self, track: usize, fx_index: i32) -> Option<String>;
     fn track_fx_param_name(&self, track: usize, fx_index: i32, param_index: i32)
         -> Option<String>;
     fn track_fx_format_param_value(
@@ -25,16 +71,50 @@ pub trait ReaperApi {
         track: usize,
         fx_index: i32,
         param_index: i32,
-        value: f32,
+        value: f64,
     ) -> Option<String>;
-    fn track_fx_get_param(&self, track: usize, fx_index: i32, param_index: i32) -> Option<f32>;
+    fn track_fx_get_param(&self, track: usize, fx_index: i32, param_index: i32) -> Option<f64>;
+    /// (step, is_toggle) for params that report discrete steps; `None` for continuous ones.
+    fn track_fx_param_step_sizes(
+        &self,
+        track: usize,
+        fx_index: i32,
+        param_index: i32,
+    ) -> Option<(f64, bool)>;
     fn track_fx_set_param(
         &self,
         track: usize,
         fx_index: i32,
         param_index: i32,
-        value: f32,
+        value: f64,
     ) -> Result<(), String>;
+    /// Param index for a REAPER-side control such as ":wet", ":bypass" or ":delta".
+    fn track_fx_param_from_ident(&self, track: usize, fx_index: i32, ident: &str) -> Option<i32>;
+    fn track_fx_get_enabled(&self, track: usize, fx_index: i32) -> bool;
+    fn track_fx_set_enabled(&self, track: usize, fx_index: i32, enabled: bool);
+    /// REAPER's wet knob for the FX (0..1); `None` when the FX doesn't expose ":wet".
+    fn track_fx_wet(&self, track: usize, fx_index: i32) -> Option<f64> {
+        let index = self.track_fx_param_from_ident(track, fx_index, ":wet")?;
+        self.track_fx_get_param(track, fx_index, index)
+    }
+
+    /// Name of the plugin preset the FX is on, if REAPER reports one.
+    fn track_fx_get_preset(&self, track: usize, fx_index: i32) -> Option<String>;
+    /// Loads a plugin preset by the name shown in REAPER's preset dropdown.
+    fn track_fx_set_preset(&self, track: usize, fx_index: i32, name: &str) -> bool;
+
+    /// A value the project file keeps for us under `section`/`key`; `None` when unset.
+    fn get_proj_ext_state(&self, section: &str, key: &str) -> Option<String>;
+    /// Saved with the project; marks it dirty.
+    fn set_proj_ext_state(&self, section: &str, key: &str, value: &str);
+    fn undo_begin_block(&self);
+    /// Closes the block opened by `undo_begin_block` as one undo point named `desc`.
+    fn undo_end_block(&self, desc: &str);
+    /// Description of the undo point REAPER would revert next, if any.
+    fn undo_next_desc(&self) -> Option<String>;
+    fn undo_do(&self) -> bool;
+    /// Appends to REAPER's console window, opening it if needed.
+    fn show_console_msg(&self, msg: &str);
 }
 
 #[derive(Clone, Copy)]
@@ -55,6 +135,51 @@ impl ReaperApiImpl {
         project as *mut reaper_low::raw::ReaProject
     }
 
+    fn project_info(&self, key: &str) -> f64 {
+        let key = std::ffi::CString::new(key).unwrap_or_default();
+        unsafe {
+            self.reaper
+                .GetSetProjectInfo(std::ptr::null_mut(), key.as_ptr(), 0.0, false)
+        }
+    }
+
+    fn set_project_info(&self, key: &str, value: f64) {
+        let key = std::ffi::CString::new(key).unwrap_or_default();
+        unsafe {
+            self.reaper
+                .GetSetProjectInfo(std::ptr::null_mut(), key.as_ptr(), value, true);
+        }
+    }
+
+    fn project_info_string(&self, key: &str) -> String {
+        let key = std::ffi::CString::new(key).unwrap_or_default();
+        let mut buf = [0 as c_char; 4096];
+        unsafe {
+            self.reaper.GetSetProjectInfo_String(
+                std::ptr::null_mut(),
+                key.as_ptr(),
+                buf.as_mut_ptr(),
+                false,
+            );
+        }
+        Self::c_buf_to_string(&buf)
+    }
+
+    fn set_project_info_string(&self, key: &str, value: &str) -> bool {
+        let (Ok(key), Ok(value)) = (std::ffi::CString::new(key), std::ffi::CString::new(value))
+        else {
+            return false;
+        };
+        unsafe {
+            self.reaper.GetSetProjectInfo_String(
+                std::ptr::null_mut(),
+                key.as_ptr(),
+                value.as_ptr() as *mut c_char,
+                true,
+            )
+        }
+    }
+
     fn c_buf_to_string(buf: &[c_char]) -> String {
         // `buf` is expected to be NUL-terminated on success.
         unsafe { CStr::from_ptr(buf.as_ptr()) }
@@ -68,6 +193,139 @@ impl ReaperApi for ReaperApiImpl {
         unsafe { self.reaper.GetProjectStateChangeCount(std::ptr::null_mut()) }
     }
 
+    fn project_state_change_count_in(&self, project: usize) -> i32 {
+        unsafe {
+            self.reaper
+                .GetProjectStateChangeCount(Self::to_project_ptr(project))
+        }
+    }
+
+    fn project_tempo(&self) -> Option<(f32, i32)> {
+        let mut bpm: f64 = 0.0;
+        let mut bpi: f64 = 0.0;
+        unsafe {
+            self.reaper.GetProjectTimeSignature2(
+                std::ptr::null_mut(),
+                &mut bpm as *mut f64,
+                &mut bpi as *mut f64,
+            );
+        }
+        if !(bpm.is_finite() && bpm > 0.0) {
+            bpm = unsafe { self.reaper.Master_GetTempo() };
+        }
+        if !(bpm.is_finite() && bpm > 0.0) {
+            return None;
+        }
+        let bpi = if bpi.is_finite() && bpi >= 1.0 { bpi.round() as i32 } else { 4 };
+        Some((bpm as f32, bpi))
+    }
+
+    fn transport_play(&self) {
+        unsafe { self.reaper.OnPlayButton() }
+    }
+
+    fn transport_stop(&self) {
+        unsafe { self.reaper.OnStopButton() }
+    }
+
+    fn transport_playing(&self) -> bool {
+        // Bit 0 is playing; paused and recording set other bits.
+        unsafe { self.reaper.GetPlayState() & 1 != 0 }
+    }
+
+    fn play_position(&self) -> Option<f64> {
+        // The block being processed, not the one being heard, so a write lands ahead of it.
+        self.transport_playing()
+            .then(|| unsafe { self.reaper.GetPlayPosition2() })
+    }
+
+    fn loop_range(&self) -> Option<(f64, f64)> {
+        let mut start: f64 = 0.0;
+        let mut end: f64 = 0.0;
+        unsafe {
+            self.reaper.GetSet_LoopTimeRange2(
+                std::ptr::null_mut(),
+                false,
+                true,
+                &mut start as *mut f64,
+                &mut end as *mut f64,
+                false,
+            );
+        }
+        if end > start {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
+
+    fn set_loop(&self, start: f64, end: f64) {
+        let mut start = start;
+        let mut end = end;
+        unsafe {
+            self.reaper.GetSet_LoopTimeRange2(
+                std::ptr::null_mut(),
+                true,
+                true,
+                &mut start as *mut f64,
+                &mut end as *mut f64,
+                false,
+            );
+            // 1 sets repeat, -1 would only query it.
+            self.reaper.GetSetRepeat(1);
+            self.reaper
+                .SetEditCurPos2(std::ptr::null_mut(), start, false, true);
+        }
+    }
+
+    fn repeat_enabled(&self) -> bool {
+        unsafe { self.reaper.GetSetRepeat(-1) == 1 }
+    }
+
+    fn render_range(&self, start: f64, end: f64, path: &Path) -> Result<(), String> {
+        let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+            return Err(format!("bad render path {}", path.display()));
+        };
+        let saved_strings: Vec<String> = RENDER_STRING_KEYS
+            .iter()
+            .map(|k| self.project_info_string(k))
+            .collect();
+        let saved_numbers: Vec<f64> = RENDER_NUMBER_KEYS
+            .iter()
+            .map(|k| self.project_info(k))
+            .collect();
+
+        let ok = self.set_project_info_string("RENDER_FILE", &dir.to_string_lossy())
+            && self.set_project_info_string("RENDER_PATTERN", &stem.to_string_lossy())
+            && self.set_project_info_string("RENDER_FORMAT", RENDER_FORMAT_WAV24);
+        if ok {
+            // Master mix, custom time bounds.
+            self.set_project_info("RENDER_SETTINGS", 0.0);
+            self.set_project_info("RENDER_BOUNDSFLAG", 0.0);
+            self.set_project_info("RENDER_STARTPOS", start);
+            self.set_project_info("RENDER_ENDPOS", end);
+            unsafe { self.reaper.Main_OnCommand(ACTION_RENDER_MOST_RECENT, 0) };
+        }
+
+        for (k, v) in RENDER_STRING_KEYS.iter().zip(&saved_strings) {
+            self.set_project_info_string(k, v);
+        }
+        for (k, v) in RENDER_NUMBER_KEYS.iter().zip(&saved_numbers) {
+            self.set_project_info(k, *v);
+        }
+
+        if !ok {
+            Err("REAPER rejected the render settings".to_string())
+        } else if path.is_file() {
+            Ok(())
+        } else {
+            Err(format!(
+                "render finished without writing {}",
+                path.display()
+            ))
+        }
+    }
+
     fn count_tracks(&self) -> i32 {
         unsafe { self.reaper.CountTracks(std::ptr::null_mut()) }
     }
@@ -81,6 +339,15 @@ impl ReaperApi for ReaperApiImpl {
         }
     }
 
+    fn selected_track(&self) -> Option<usize> {
+        let track = unsafe { self.reaper.GetSelectedTrack(std::ptr::null_mut(), 0) };
+        if track.is_null() {
+            None
+        } else {
+            Some(track as usize)
+        }
+    }
+
     fn track_guid(&self, track: usize) -> Option<String> {
         let guid = unsafe { self.reaper.GetTrackGUID(Self::to_track_ptr(track)) };
         if guid.is_null() {
@@ -106,10 +373,47 @@ impl ReaperApi for ReaperApiImpl {
         }
     }
 
+    fn track_get_info(&self, track: usize, key: &str) -> Option<f64> {
+        let key = std::ffi::CString::new(key).ok()?;
+        let v = unsafe {
+            self.reaper
+                .GetMediaTrackInfo_Value(Self::to_track_ptr(track), key.as_ptr())
+        };
+        if v.is_finite() {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    fn track_set_info(&self, track: usize, key: &str, value: f64) -> bool {
+        let Ok(key) = std::ffi::CString::new(key) else {
+            return false;
+        };
+        unsafe {
+            self.reaper
+                .SetMediaTrackInfo_Value(Self::to_track_ptr(track), key.as_ptr(), value)
+        }
+    }
+
     fn track_fx_count(&self, track: usize) -> i32 {
         unsafe { self.reaper.TrackFX_GetCount(Self::to_track_ptr(track)) }
     }
 
+    fn track_fx_add_by_name(&self, track: usize, name: &str) -> Option<i32> {
+        let name = std::ffi::CString::new(name).ok()?;
+        // instantiate = -1: always add a new instance rather than return an existing one.
+        let idx = unsafe {
+            self.reaper
+                .TrackFX_AddByName(Self::to_track_ptr(track), name.as_ptr(), false, -1)
+        };
+        if idx < 0 {
+            None
+        } else {
+            Some(idx)
+        }
+    }
+
     fn enum_project(&self, index: i32) -> Option<(usize, String)> {
         let mut buf = [0 as c_char; 1024];
         let proj = unsafe { self.reaper.EnumProjects(index, buf.as_mut_ptr(), buf.len() as i32) };
@@ -182,6 +486,25 @@ impl ReaperApi for ReaperApiImpl {
         }
     }
 
+    fn track_fx_ident(&self, track: usize, fx_index: i32) -> Option<String> {
+        let mut buf = [0 as c_char; 1024];
+        let ok = unsafe {
+            self.reaper.TrackFX_GetNamedConfigParm(
+                Self::to_track_ptr(track),
+                fx_index,
+                c_str!("fx_ident").as_ptr(),
+                buf.as_mut_ptr(),
+                buf.len() as i32,
+            )
+        };
+        let ident = Self::c_buf_to_string(&buf);
+        if ok && !ident.is_empty() {
+            Some(ident)
+        } else {
+            None
+        }
+    }
+
     fn track_fx_param_name(
         &self,
         track: usize,
@@ -210,7 +533,7 @@ impl ReaperApi for ReaperApiImpl {
         track: usize,
         fx_index: i32,
         param_index: i32,
-        value: f32,
+        value: f64,
     ) -> Option<String> {
         let mut buf = [0 as c_char; 256];
         let ok = unsafe {
@@ -218,7 +541,7 @@ impl ReaperApi for ReaperApiImpl {
                 Self::to_track_ptr(track),
                 fx_index,
                 param_index,
-                value as f64,
+                value,
                 buf.as_mut_ptr(),
                 buf.len() as i32,
             )
@@ -230,21 +553,45 @@ impl ReaperApi for ReaperApiImpl {
         }
     }
 
-    fn track_fx_set_param(
+    fn track_fx_param_step_sizes(
         &self,
         track: usize,
         fx_index: i32,
         param_index: i32,
-        value: f32,
-    ) -> Result<(), String> {
+    ) -> Option<(f64, bool)> {
+        let mut step: f64 = 0.0;
+        let mut small_step: f64 = 0.0;
+        let mut large_step: f64 = 0.0;
+        let mut is_toggle = false;
         let ok = unsafe {
-            self.reaper.TrackFX_SetParam(
+            self.reaper.TrackFX_GetParameterStepSizes(
                 Self::to_track_ptr(track),
                 fx_index,
                 param_index,
-                value as f64,
+                &mut step as *mut f64,
+                &mut small_step as *mut f64,
+                &mut large_step as *mut f64,
+                &mut is_toggle as *mut bool,
             )
         };
+        if ok {
+            Some((step, is_toggle))
+        } else {
+            None
+        }
+    }
+
+    fn track_fx_set_param(
+        &self,
+        track: usize,
+        fx_index: i32,
+        param_index: i32,
+        value: f64,
+    ) -> Result<(), String> {
+        let ok = unsafe {
+            self.reaper
+                .TrackFX_SetParam(Self::to_track_ptr(track), fx_index, param_index, value)
+        };
         if ok {
             Ok(())
         } else {
@@ -252,7 +599,133 @@ impl ReaperApi for ReaperApiImpl {
         }
     }
 
-    fn track_fx_get_param(&self, track: usize, fx_index: i32, param_index: i32) -> Option<f32> {
+    fn track_fx_param_from_ident(&self, track: usize, fx_index: i32, ident: &str) -> Option<i32> {
+        let ident = std::ffi::CString::new(ident).ok()?;
+        let idx = unsafe {
+            self.reaper.TrackFX_GetParamFromIdent(
+                Self::to_track_ptr(track),
+                fx_index,
+                ident.as_ptr(),
+            )
+        };
+        if idx < 0 {
+            None
+        } else {
+            Some(idx)
+        }
+    }
+
+    fn track_fx_get_enabled(&self, track: usize, fx_index: i32) -> bool {
+        unsafe { self.reaper.TrackFX_GetEnabled(Self::to_track_ptr(track), fx_index) }
+    }
+
+    fn track_fx_set_enabled(&self, track: usize, fx_index: i32, enabled: bool) {
+        unsafe {
+            self.reaper
+                .TrackFX_SetEnabled(Self::to_track_ptr(track), fx_index, enabled)
+        }
+    }
+
+    fn track_fx_get_preset(&self, track: usize, fx_index: i32) -> Option<String> {
+        let mut buf = [0 as c_char; 512];
+        // The return value only says whether the params still match the preset; the name is
+        // filled in either way.
+        unsafe {
+            self.reaper.TrackFX_GetPreset(
+                Self::to_track_ptr(track),
+                fx_index,
+                buf.as_mut_ptr(),
+                buf.len() as i32,
+            );
+        }
+        let name = Self::c_buf_to_string(&buf);
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    fn track_fx_set_preset(&self, track: usize, fx_index: i32, name: &str) -> bool {
+        let Ok(name) = std::ffi::CString::new(name) else {
+            return false;
+        };
+        unsafe {
+            self.reaper
+                .TrackFX_SetPreset(Self::to_track_ptr(track), fx_index, name.as_ptr())
+        }
+    }
+
+    fn get_proj_ext_state(&self, section: &str, key: &str) -> Option<String> {
+        let section = std::ffi::CString::new(section).ok()?;
+        let key = std::ffi::CString::new(key).ok()?;
+        // A whole tone's JSON; REAPER truncates to the buffer.
+        let mut buf = vec![0 as c_char; 64 * 1024];
+        let len = unsafe {
+            self.reaper.GetProjExtState(
+                std::ptr::null_mut(),
+                section.as_ptr(),
+                key.as_ptr(),
+                buf.as_mut_ptr(),
+                buf.len() as i32,
+            )
+        };
+        if len <= 0 {
+            return None;
+        }
+        Some(Self::c_buf_to_string(&buf))
+    }
+
+    fn set_proj_ext_state(&self, section: &str, key: &str, value: &str) {
+        let (Ok(section), Ok(key), Ok(value)) = (
+            std::ffi::CString::new(section),
+            std::ffi::CString::new(key),
+            std::ffi::CString::new(value),
+        ) else {
+            return;
+        };
+        unsafe {
+            self.reaper.SetProjExtState(
+                std::ptr::null_mut(),
+                section.as_ptr(),
+                key.as_ptr(),
+                value.as_ptr(),
+            );
+        }
+    }
+
+    fn undo_begin_block(&self) {
+        unsafe { self.reaper.Undo_BeginBlock() }
+    }
+
+    fn undo_end_block(&self, desc: &str) {
+        let Ok(desc) = std::ffi::CString::new(desc) else {
+            return;
+        };
+        unsafe { self.reaper.Undo_EndBlock(desc.as_ptr(), UNDO_STATE_FX) }
+    }
+
+    fn undo_next_desc(&self) -> Option<String> {
+        let desc = unsafe { self.reaper.Undo_CanUndo2(std::ptr::null_mut()) };
+        if desc.is_null() {
+            return None;
+        }
+        let desc = unsafe { CStr::from_ptr(desc) };
+        Some(desc.to_string_lossy().to_string())
+    }
+
+    fn undo_do(&self) -> bool {
+        unsafe { self.reaper.Undo_DoUndo2(std::ptr::null_mut()) != 0 }
+    }
+
+    fn show_console_msg(&self, msg: &str) {
+        let Ok(msg) = std::ffi::CString::new(msg) else {
+            return;
+        };
+        unsafe { self.reaper.ShowConsoleMsg(msg.as_ptr()) }
+    }
+
+    fn track_fx_get_param(&self, track: usize, fx_index: i32, param_index: i32) -> Option<f64> {
         let mut min: f64 = 0.0;
         let mut max: f64 = 1.0;
         let v = unsafe {
@@ -265,7 +738,7 @@ impl ReaperApi for ReaperApiImpl {
             )
         };
         if v.is_finite() {
-            Some(v as f32)
+            Some(v)
         } else {
             None
         }
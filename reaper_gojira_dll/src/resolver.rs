@@ -1,9 +1,12 @@
-use crate::protocol::{Confidence, GojiraInstance};
+use crate::budget::TickBudget;
+use crate::profiles;
+use crate::protocol::{ErrorDiagnostic, GojiraInstance, Remediation, ResolverState};
 use crate::reaper_api::ReaperApi;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::time::{Duration, Instant};
 
 pub type FxLookup = HashMap<String, (String, i32)>;
 
@@ -32,161 +35,412 @@ fn scan_all_projects_enabled() -> bool {
     )
 }
 
-pub fn scan_project_instances(api: &dyn ReaperApi) -> (Vec<GojiraInstance>, FxLookup) {
-    let mut instances = Vec::new();
-    let mut lookup: FxLookup = HashMap::new();
+/// One open project's share of a scan.
+#[derive(Default)]
+pub struct ProjectScan {
+    pub instances: Vec<GojiraInstance>,
+    pub lookup: FxLookup,
+    /// What the next scan of the project can reuse.
+    pub memo: ScanMemo,
+}
 
-    let mut projects: Vec<(usize, bool)> = Vec::new(); // (proj_ptr, is_current)
-    let mut seen: HashSet<usize> = HashSet::new();
+/// A track as of its last scan: its name and FX guids in chain order. Reading guids is cheap
+/// next to FX names and profile matching, and any insert, delete, move or swap changes them.
+#[derive(Clone, PartialEq)]
+struct TrackFingerprint {
+    name: String,
+    fx_guids: Vec<String>,
+}
+
+/// Each track's fingerprint and instances from a project's last scan, by track guid. A rescan
+/// only reads FX names on tracks whose fingerprint changed. Renaming an FX in place keeps its
+/// guid, so that shows up on the next full scan (a handshake) rather than a rescan.
+#[derive(Default)]
+pub struct ScanMemo {
+    tracks: HashMap<String, (TrackFingerprint, Vec<GojiraInstance>)>,
+}
 
-    let Some((current, _)) = api.current_project() else {
-        return (instances, lookup);
+/// Projects the DLL works in, current tab first: just that one, unless GOJIRA_SCAN_ALL_PROJECTS
+/// adds every other open project. Each comes with its file path (empty while unsaved).
+pub fn watched_projects(api: &dyn ReaperApi) -> Vec<(usize, String)> {
+    open_projects(api, scan_all_projects_enabled())
+}
+
+/// The current project, then with `all` every other open one.
+fn open_projects(api: &dyn ReaperApi, all: bool) -> Vec<(usize, String)> {
+    let Some(current) = api.current_project() else {
+        return Vec::new();
     };
-    projects.push((current, true));
-    seen.insert(current);
+    let mut seen: HashSet<usize> = HashSet::from([current.0]);
+    let mut projects = vec![current];
 
-    if scan_all_projects_enabled() {
+    if all {
         for i in 0..256 {
-            let Some((p, _)) = api.enum_project(i) else { break };
+            let Some((p, path)) = api.enum_project(i) else { break };
             if seen.insert(p) {
-                projects.push((p, false));
+                projects.push((p, path));
             }
         }
     }
+    projects
+}
 
+pub fn scan_project_instances(api: &dyn ReaperApi) -> (Vec<GojiraInstance>, FxLookup) {
+    let mut instances = Vec::new();
+    let mut lookup: FxLookup = HashMap::new();
+
+    let projects = watched_projects(api);
     trace_line(&format!("scan: projects={} all={}", projects.len(), scan_all_projects_enabled()));
-    for (proj, is_current) in projects {
+    for (proj, _) in projects {
+        let scan = scan_project(api, proj, &ScanMemo::default());
+        instances.extend(scan.instances);
+        lookup.extend(scan.lookup);
+    }
+
+    (instances, lookup)
+}
+
+/// Every instance a profile matches in one project, reusing `previous` for unchanged tracks;
+/// an empty memo scans everything.
+pub fn scan_project(api: &dyn ReaperApi, proj: usize, previous: &ScanMemo) -> ProjectScan {
+    let mut scan = ProjectScan::default();
+    let track_count = api.count_tracks_in(proj);
+    trace_line(&format!("scan: project_ptr={} track_count={}", proj, track_count));
+    for ti in 0..track_count {
+        scan_track(api, proj, ti, previous, &mut scan);
+    }
+    scan
+}
+
+/// `scan_project` in slices: it stops between tracks once the tick's budget is spent and
+/// carries on from there next tick. Tracks added or removed meanwhile move the project's change
+/// count, so the watchdog rescans it after this one lands.
+pub struct ScanCursor {
+    proj: usize,
+    track_count: i32,
+    next_track: i32,
+    took: Duration,
+    previous: ScanMemo,
+    scan: ProjectScan,
+}
+
+impl ScanCursor {
+    pub fn new(api: &dyn ReaperApi, proj: usize, previous: ScanMemo) -> Self {
         let track_count = api.count_tracks_in(proj);
         trace_line(&format!(
-            "scan: project_ptr={} current={} track_count={}",
-            proj, is_current, track_count
+            "scan: project_ptr={} track_count={}",
+            proj, track_count
         ));
-        for ti in 0..track_count {
-            let Some(track) = api.get_track_in(proj, ti) else { continue };
-            let Some(track_guid) = api.track_guid(track) else { continue };
-            let track_name_raw = api.track_name(track);
-            let track_name = track_name_raw;
+        Self {
+            proj,
+            track_count,
+            next_track: 0,
+            took: Duration::ZERO,
+            previous,
+            scan: ProjectScan::default(),
+        }
+    }
+
+    /// Scans at least one more track, then as many as fit in `budget`; true once all are done.
+    pub fn step(&mut self, api: &dyn ReaperApi, budget: &TickBudget) -> bool {
+        let started = Instant::now();
+        while self.next_track < self.track_count {
+            scan_track(
+                api,
+                self.proj,
+                self.next_track,
+                &self.previous,
+                &mut self.scan,
+            );
+            self.next_track += 1;
+            if budget.exhausted() {
+                break;
+            }
+        }
+        self.took += started.elapsed();
+        self.next_track >= self.track_count
+    }
+
+    /// Time spent in `step` so far, over however many ticks it took.
+    pub fn took(&self) -> Duration {
+        self.took
+    }
 
-            let fx_count = api.track_fx_count(track);
+    pub fn finish(self) -> ProjectScan {
+        self.scan
+    }
+}
+
+fn scan_track(
+    api: &dyn ReaperApi,
+    proj: usize,
+    ti: i32,
+    previous: &ScanMemo,
+    scan: &mut ProjectScan,
+) {
+    let Some(track) = api.get_track_in(proj, ti) else { return };
+    let Some(track_guid) = api.track_guid(track) else { return };
+    let track_name = api.track_name(track);
+    let fx_count = api.track_fx_count(track);
+    let fingerprint = TrackFingerprint {
+        name: track_name.clone(),
+        fx_guids: (0..fx_count)
+            .map(|fxi| api.track_fx_guid(track, fxi).unwrap_or_default())
+            .collect(),
+    };
+
+    let instances = match previous.tracks.get(&track_guid) {
+        Some((before, instances)) if *before == fingerprint => {
+            trace_line(&format!("scan: track[{ti}] guid='{track_guid}' unchanged"));
+            instances.clone()
+        }
+        _ => {
             trace_line(&format!(
                 "scan: track[{ti}] name='{}' guid='{}' fx_count={}",
                 track_name, track_guid, fx_count
             ));
-            for fxi in 0..fx_count {
-                let fx_name = api.track_fx_name(track, fxi);
-                trace_line(&format!("scan: track[{ti}] fx[{fxi}] name='{}'", fx_name));
-                let Some(confidence) = gojira_confidence(&fx_name) else {
-                    continue;
-                };
-                let Some(fx_guid) = api.track_fx_guid(track, fxi) else { continue };
-                trace_line(&format!(
-                    "scan: MATCH track[{ti}] fx[{fxi}] guid='{}' confidence={:?}",
-                    fx_guid, confidence
-                ));
-
-                lookup.insert(fx_guid.clone(), (track_guid.clone(), fxi));
-                instances.push(GojiraInstance {
-                    track_guid: track_guid.clone(),
-                    track_name: track_name.clone(),
-                    fx_guid,
-                    fx_name,
-                    last_known_fx_index: fxi,
-                    confidence,
-                });
-            }
+            scan_track_fx(api, track, ti, &track_guid, &fingerprint)
         }
+    };
+    for inst in &instances {
+        scan.lookup.insert(
+            inst.fx_guid.clone(),
+            (track_guid.clone(), inst.last_known_fx_index),
+        );
     }
+    scan.instances.extend(instances.iter().cloned());
+    scan.memo
+        .tracks
+        .insert(track_guid, (fingerprint, instances));
+}
 
-    (instances, lookup)
+/// Reads every FX name on the track and keeps the ones a profile matches.
+fn scan_track_fx(
+    api: &dyn ReaperApi,
+    track: usize,
+    ti: i32,
+    track_guid: &str,
+    fingerprint: &TrackFingerprint,
+) -> Vec<GojiraInstance> {
+    let mut instances = Vec::new();
+    for (fxi, fx_guid) in (0..).zip(&fingerprint.fx_guids) {
+        let fx_name = api.track_fx_name(track, fxi);
+        trace_line(&format!("scan: track[{ti}] fx[{fxi}] name='{}'", fx_name));
+        let Some((profile, by_name)) = profiles::match_fx_name(&fx_name) else {
+            continue;
+        };
+        if fx_guid.is_empty() {
+            continue;
+        }
+        let confidence = profiles::confidence(api, track, fxi, profile, by_name);
+        trace_line(&format!(
+            "scan: MATCH track[{ti}] fx[{fxi}] guid='{}' profile={} confidence={:?}",
+            fx_guid, profile.id, confidence
+        ));
+
+        instances.push(GojiraInstance {
+            track_guid: track_guid.to_string(),
+            track_name: fingerprint.name.clone(),
+            fx_guid: fx_guid.clone(),
+            fx_name,
+            last_known_fx_index: fxi,
+            confidence,
+            profile: Some(profile.id.to_string()),
+        });
+    }
+    instances
 }
 
+/// An instance found away from where the lookup had it: dragged along its chain or onto another
+/// track since the last scan.
+#[derive(Debug, Clone)]
+pub struct FxMove {
+    pub fx_guid: String,
+    pub from_track_guid: String,
+    pub from_fx_index: i32,
+    pub to_track_guid: String,
+    pub to_fx_index: i32,
+}
+
+/// Where `resolve_fx` found an FX.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub track: usize,
+    pub fx_index: i32,
+    /// File path of the project tab it is in (empty while unsaved), when that isn't the current
+    /// one.
+    pub background_project: Option<String>,
+}
+
+/// Finds the FX by guid, from the lookup when it still holds. Each time the lookup had it
+/// somewhere else, the move is added to `moves` and the lookup corrected.
+///
+/// Only the current project is searched unless `background` (or GOJIRA_SCAN_ALL_PROJECTS) lets
+/// it into the other open tabs. A target that is only in one of those is reported as
+/// `BackgroundProject`, so the client can tell it apart from one that is gone.
 pub fn resolve_fx(
+    api: &dyn ReaperApi,
+    cache: &mut FxLookup,
+    moves: &mut Vec<FxMove>,
+    target_fx_guid: &str,
+    background: bool,
+) -> Result<Resolved, ResolveError> {
+    let all = background || scan_all_projects_enabled();
+    let before = cache.get(target_fx_guid).cloned();
+    let resolved = lookup_or_locate(api, cache, target_fx_guid, all);
+    if resolved.is_err() && !all && locate_fx(api, target_fx_guid, true).is_some() {
+        return Err(ResolveError::TargetNotFound(ResolverState::BackgroundProject));
+    }
+    if let (Ok(_), Some((from_track_guid, from_fx_index))) = (&resolved, before) {
+        if let Some((to_track_guid, to_fx_index)) = cache.get(target_fx_guid).cloned() {
+            if (&to_track_guid, to_fx_index) != (&from_track_guid, from_fx_index) {
+                moves.push(FxMove {
+                    fx_guid: target_fx_guid.to_string(),
+                    from_track_guid,
+                    from_fx_index,
+                    to_track_guid,
+                    to_fx_index,
+                });
+            }
+        }
+    }
+    let (track, fx_index, proj) = resolved?;
+    let background_project = match api.current_project() {
+        Some((current, _)) if current == proj => None,
+        _ => Some(
+            open_projects(api, true)
+                .into_iter()
+                .find(|(p, _)| *p == proj)
+                .map(|(_, path)| path)
+                .unwrap_or_default(),
+        ),
+    };
+    Ok(Resolved {
+        track,
+        fx_index,
+        background_project,
+    })
+}
+
+/// The FX's track, slot and project, searching every open project with `all`.
+fn lookup_or_locate(
     api: &dyn ReaperApi,
     cache: &mut FxLookup,
     target_fx_guid: &str,
-) -> Result<(usize, i32), ResolveError> {
+    all: bool,
+) -> Result<(usize, i32, usize), ResolveError> {
     if let Some((track_guid, fx_index)) = cache.get(target_fx_guid).cloned() {
-        if let Some(track) = find_track_by_guid(api, &track_guid) {
+        if let Some((track, proj)) = locate_track(api, &track_guid, all) {
             if verify_fx_guid(api, track, fx_index, target_fx_guid) {
-                return Ok((track, fx_index));
+                return Ok((track, fx_index, proj));
             }
         }
     }
 
+    // Most misses are an instance that moved within its chain or to another track; finding it by
+    // guid skips the FX name reads a full scan would make. A rescan is left for a target that
+    // isn't anywhere, where it gives the error an up-to-date list of what is.
+    if let Some((track_guid, track, fx_index, proj)) = locate_fx(api, target_fx_guid, all) {
+        cache.insert(target_fx_guid.to_string(), (track_guid, fx_index));
+        return Ok((track, fx_index, proj));
+    }
+
     let (_instances, fresh) = scan_project_instances(api);
     *cache = fresh;
 
     if let Some((track_guid, fx_index)) = cache.get(target_fx_guid).cloned() {
-        let Some(track) = find_track_by_guid(api, &track_guid) else {
-            return Err(ResolveError::TargetNotFound);
+        let Some((track, proj)) = locate_track(api, &track_guid, all) else {
+            return Err(ResolveError::TargetNotFound(ResolverState::VerifyFailed));
         };
         if verify_fx_guid(api, track, fx_index, target_fx_guid) {
-            return Ok((track, fx_index));
+            return Ok((track, fx_index, proj));
         }
+        return Err(ResolveError::TargetNotFound(ResolverState::VerifyFailed));
     }
 
-    Err(ResolveError::TargetNotFound)
+    Err(ResolveError::TargetNotFound(ResolverState::CacheMiss))
 }
 
-pub fn find_track_by_guid(api: &dyn ReaperApi, track_guid: &str) -> Option<usize> {
-    // Default: only touch the active/current project (prevents applying to a background tab).
-    let Some((proj, _)) = api.current_project() else {
-        return None;
-    };
-
-    let track_count = api.count_tracks_in(proj);
-    for ti in 0..track_count {
-        let Some(track) = api.get_track_in(proj, ti) else { continue };
-        if api.track_guid(track).as_deref() == Some(track_guid) {
-            return Some(track);
+/// Finds a profile-matched FX by guid in the current project (every open one with `all`),
+/// reading only FX guids until the hit. Comes with its track guid, track and project.
+fn locate_fx(
+    api: &dyn ReaperApi,
+    target_fx_guid: &str,
+    all: bool,
+) -> Option<(String, usize, i32, usize)> {
+    for (proj, _) in open_projects(api, all) {
+        for ti in 0..api.count_tracks_in(proj) {
+            let Some(track) = api.get_track_in(proj, ti) else {
+                continue;
+            };
+            let Some(fx_index) = (0..api.track_fx_count(track))
+                .find(|fxi| verify_fx_guid(api, track, *fxi, target_fx_guid))
+            else {
+                continue;
+            };
+            profiles::match_fx_name(&api.track_fx_name(track, fx_index))?;
+            trace_line(&format!(
+                "locate: guid='{target_fx_guid}' at track[{ti}] fx[{fx_index}]"
+            ));
+            return Some((api.track_guid(track)?, track, fx_index, proj));
         }
     }
+    None
+}
 
-    // Opt-in: allow resolving across all open projects.
-    if scan_all_projects_enabled() {
-        for i in 0..256 {
-            let Some((p, _)) = api.enum_project(i) else { break };
-            if p == proj {
-                continue;
-            }
-            let track_count = api.count_tracks_in(p);
-            for ti in 0..track_count {
-                let Some(track) = api.get_track_in(p, ti) else { continue };
-                if api.track_guid(track).as_deref() == Some(track_guid) {
-                    return Some(track);
-                }
+pub fn find_track_by_guid(api: &dyn ReaperApi, track_guid: &str) -> Option<usize> {
+    // Default: only touch the active/current project (prevents applying to a background tab).
+    locate_track(api, track_guid, scan_all_projects_enabled()).map(|(track, _)| track)
+}
+
+/// The track with this guid and its project, in the current project first and then, with
+/// `all`, the other open ones.
+fn locate_track(api: &dyn ReaperApi, track_guid: &str, all: bool) -> Option<(usize, usize)> {
+    for (proj, _) in open_projects(api, all) {
+        let track_count = api.count_tracks_in(proj);
+        for ti in 0..track_count {
+            let Some(track) = api.get_track_in(proj, ti) else { continue };
+            if api.track_guid(track).as_deref() == Some(track_guid) {
+                return Some((track, proj));
             }
         }
     }
-
     None
 }
 
+/// Compact `idx:name` listing of a track's FX chain (for error diagnostics).
+pub fn fx_chain_dump(api: &dyn ReaperApi, track: usize) -> String {
+    let fx_count = api.track_fx_count(track);
+    if fx_count <= 0 {
+        return "empty".to_string();
+    }
+    (0..fx_count)
+        .map(|fxi| format!("{fxi}:{}", api.track_fx_name(track, fxi)))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
 fn verify_fx_guid(api: &dyn ReaperApi, track: usize, fx_index: i32, target_fx_guid: &str) -> bool {
     api.track_fx_guid(track, fx_index)
         .as_deref()
         .is_some_and(|g| g == target_fx_guid)
 }
 
-fn gojira_confidence(fx_name: &str) -> Option<Confidence> {
-    let n = normalize(fx_name);
-    if n.contains("archetype") && n.contains("gojira") {
-        return Some(Confidence::High);
-    }
-    if n.contains("gojira") {
-        return Some(Confidence::Low);
-    }
-    None
-}
-
-fn normalize(s: &str) -> String {
-    s.chars()
-        .filter(|c| c.is_ascii_alphanumeric())
-        .flat_map(|c| c.to_lowercase())
-        .collect()
-}
-
 #[derive(Debug, Clone, Copy)]
 pub enum ResolveError {
-    TargetNotFound,
+    TargetNotFound(ResolverState),
+}
+
+impl ResolveError {
+    pub fn diagnostic(self) -> ErrorDiagnostic {
+        let ResolveError::TargetNotFound(state) = self;
+        let remediation = match state {
+            ResolverState::BackgroundProject => Remediation::AllowBackgroundProjects,
+            _ => Remediation::RefreshInstances,
+        };
+        ErrorDiagnostic {
+            resolver: Some(state),
+            ..ErrorDiagnostic::remedy(remediation)
+        }
+    }
 }
@@ -0,0 +1,108 @@
+use crate::protocol::{LogEvent, LogLevel};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Events kept for `get_diagnostics`; older ones fall off the front.
+const RING_CAP: usize = 512;
+/// What `get_diagnostics` returns when it doesn't ask for a number.
+pub const DEFAULT_DIAGNOSTICS_EVENTS: usize = 100;
+const LEVEL_ENV: &str = "GOJIRA_LOG_LEVEL";
+const FILE_ENV: &str = "GOJIRA_LOG_FILE";
+
+/// The DLL's event log: the most recent events for `get_diagnostics`, and optionally a file of
+/// JSON lines. REAPER's main thread and the network thread both write to it.
+struct Telemetry {
+    started: Instant,
+    level: LogLevel,
+    ring: VecDeque<LogEvent>,
+    file: Option<File>,
+}
+
+static TELEMETRY: OnceLock<Mutex<Telemetry>> = OnceLock::new();
+
+impl Telemetry {
+    /// `GOJIRA_LOG_LEVEL` is one of error, warn, info (the default) or debug. `GOJIRA_LOG_FILE`
+    /// names a file to append every kept event to, one JSON object per line.
+    fn from_env() -> Self {
+        let level = std::env::var(LEVEL_ENV)
+            .ok()
+            .and_then(|raw| parse_level(&raw))
+            .unwrap_or(LogLevel::Info);
+        let file = std::env::var_os(FILE_ENV)
+            .and_then(|path| OpenOptions::new().create(true).append(true).open(path).ok());
+        Self {
+            started: Instant::now(),
+            level,
+            ring: VecDeque::with_capacity(RING_CAP),
+            file,
+        }
+    }
+}
+
+fn parse_level(raw: &str) -> Option<LogLevel> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "error" => Some(LogLevel::Error),
+        "warn" | "warning" => Some(LogLevel::Warn),
+        "info" => Some(LogLevel::Info),
+        "debug" => Some(LogLevel::Debug),
+        _ => None,
+    }
+}
+
+fn telemetry() -> &'static Mutex<Telemetry> {
+    TELEMETRY.get_or_init(|| Mutex::new(Telemetry::from_env()))
+}
+
+/// Records an event if `level` passes the configured one. Warnings and errors also go to stderr,
+/// which is all the mock sidecar and the tests have to look at.
+pub fn log(level: LogLevel, target: &str, msg: impl Into<String>) {
+    let Ok(mut t) = telemetry().lock() else {
+        return;
+    };
+    if level > t.level {
+        return;
+    }
+    let event = LogEvent {
+        at_ms: t.started.elapsed().as_millis() as u64,
+        level,
+        target: target.to_string(),
+        msg: msg.into(),
+    };
+    if level <= LogLevel::Warn {
+        eprintln!("{target}: {}", event.msg);
+    }
+    if let Some(file) = t.file.as_mut() {
+        if let Ok(line) = serde_json::to_string(&event) {
+            let _ = writeln!(file, "{line}");
+            let _ = file.flush();
+        }
+    }
+    if t.ring.len() >= RING_CAP {
+        t.ring.pop_front();
+    }
+    t.ring.push_back(event);
+}
+
+pub fn warn(target: &str, msg: impl Into<String>) {
+    log(LogLevel::Warn, target, msg);
+}
+
+pub fn info(target: &str, msg: impl Into<String>) {
+    log(LogLevel::Info, target, msg);
+}
+
+pub fn debug(target: &str, msg: impl Into<String>) {
+    log(LogLevel::Debug, target, msg);
+}
+
+/// Up to `limit` of the most recent events, oldest first.
+pub fn recent(limit: usize) -> Vec<LogEvent> {
+    let Ok(t) = telemetry().lock() else {
+        return Vec::new();
+    };
+    let skip = t.ring.len().saturating_sub(limit);
+    t.ring.iter().skip(skip).cloned().collect()
+}
@@ -0,0 +1,92 @@
+//! `wss://` for the websocket server, behind the `tls` cargo feature. On first run the DLL makes
+//! itself a self-signed certificate and keeps it beside the sidecar config; clients pin its
+//! SHA-256 fingerprint rather than trusting a CA.
+
+use crate::protocol::SidecarConfig;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use sha2::{Digest, Sha256};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+
+pub struct TlsIdentity {
+    config: Arc<ServerConfig>,
+    fingerprint: String,
+}
+
+impl TlsIdentity {
+    /// Loads the certificate and key, generating and saving a new pair if either is missing.
+    pub fn load_or_create() -> Result<Self, String> {
+        let cert_path = SidecarConfig::tls_cert_path()
+            .ok_or_else(|| "no config directory to keep the TLS certificate in".to_string())?;
+        let key_path = cert_path.with_file_name("sidecar-key.pem");
+        if !cert_path.exists() || !key_path.exists() {
+            generate(&cert_path, &key_path)?;
+        }
+
+        let cert = CertificateDer::from_pem_file(&cert_path)
+            .map_err(|e| format!("tls cert {}: {e}", cert_path.display()))?;
+        let key = PrivateKeyDer::from_pem_file(&key_path)
+            .map_err(|e| format!("tls key {}: {e}", key_path.display()))?;
+        let fingerprint = fingerprint(&cert);
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("tls config: {e}"))?
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .map_err(|e| format!("tls config: {e}"))?;
+
+        Ok(Self {
+            config: Arc::new(config),
+            fingerprint,
+        })
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Runs the TLS handshake on a fresh connection. Blocks until it finishes or the socket's
+    /// read timeout fires, so the caller sets one that a LAN round trip fits in.
+    pub fn accept(
+        &self,
+        mut tcp: TcpStream,
+    ) -> Result<StreamOwned<ServerConnection, TcpStream>, String> {
+        let mut conn =
+            ServerConnection::new(Arc::clone(&self.config)).map_err(|e| e.to_string())?;
+        while conn.is_handshaking() {
+            conn.complete_io(&mut tcp).map_err(|e| e.to_string())?;
+        }
+        Ok(StreamOwned::new(conn, tcp))
+    }
+}
+
+fn generate(cert_path: &Path, key_path: &Path) -> Result<(), String> {
+    let names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("tls cert generation failed: {e}"))?;
+    if let Some(dir) = cert_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("tls cert {}: {e}", dir.display()))?;
+    }
+    std::fs::write(key_path, key_pair.serialize_pem())
+        .map_err(|e| format!("tls key {}: {e}", key_path.display()))?;
+    std::fs::write(cert_path, cert.pem())
+        .map_err(|e| format!("tls cert {}: {e}", cert_path.display()))
+}
+
+/// SHA-256 of the DER certificate as colon-separated upper-case hex, the way browsers show it.
+pub fn fingerprint(cert: &[u8]) -> String {
+    Sha256::digest(cert)
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
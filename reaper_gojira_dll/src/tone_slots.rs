@@ -0,0 +1,93 @@
+use crate::protocol::{MergeMode, ParamChange, ToneSlotInfo, MAX_TONE_SLOTS};
+use crate::reaper_api::ReaperApi;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Slots run 0..MAX_SLOTS, one per MIDI program number.
+pub const MAX_SLOTS: u32 = MAX_TONE_SLOTS;
+
+/// Project ext-state section and key every slot is saved under, as one JSON object.
+const EXT_SECTION: &str = "gojira_tone_slots";
+const EXT_KEY: &str = "slots";
+
+/// A tone kept by `store_tone_slot`: the `set_tone` that `activate_tone_slot` replays.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ToneSlot {
+    #[serde(default)]
+    pub name: String,
+    pub target_fx_guid: String,
+    pub mode: MergeMode,
+    pub params: Vec<ParamChange>,
+    #[serde(default)]
+    pub locked_params: Vec<i32>,
+}
+
+#[derive(Default)]
+pub struct ToneSlots {
+    slots: BTreeMap<u32, ToneSlot>,
+    active: Option<u32>,
+}
+
+impl ToneSlots {
+    /// Reads the slots back from the project, which may have been reopened or switched since
+    /// they were stored. The active slot is kept while the project still has it.
+    pub fn reload(&mut self, api: &dyn ReaperApi) {
+        self.slots = api
+            .get_proj_ext_state(EXT_SECTION, EXT_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        self.active = self.active.filter(|slot| self.slots.contains_key(slot));
+    }
+
+    /// Puts `tone` in `slot`, or empties it on `None`, and saves every slot with the project.
+    pub fn store(&mut self, api: &dyn ReaperApi, slot: u32, tone: Option<ToneSlot>) {
+        match tone {
+            Some(tone) => {
+                self.slots.insert(slot, tone);
+            }
+            None => {
+                self.slots.remove(&slot);
+                if self.active == Some(slot) {
+                    self.active = None;
+                }
+            }
+        }
+        if let Ok(json) = serde_json::to_string(&self.slots) {
+            api.set_proj_ext_state(EXT_SECTION, EXT_KEY, &json);
+        }
+    }
+
+    pub fn get(&self, slot: u32) -> Option<&ToneSlot> {
+        self.slots.get(&slot)
+    }
+
+    pub fn set_active(&mut self, slot: u32) {
+        self.active = Some(slot);
+    }
+
+    pub fn active(&self) -> Option<u32> {
+        self.active
+    }
+
+    /// The stored slot after the active one, wrapping around; the first with none active.
+    pub fn next(&self) -> Option<u32> {
+        let after = self
+            .active
+            .and_then(|active| self.slots.range(active + 1..).next());
+        after
+            .or_else(|| self.slots.iter().next())
+            .map(|(&slot, _)| slot)
+    }
+
+    pub fn list(&self) -> Vec<ToneSlotInfo> {
+        self.slots
+            .iter()
+            .map(|(&slot, tone)| ToneSlotInfo {
+                slot,
+                name: tone.name.clone(),
+                target_fx_guid: tone.target_fx_guid.clone(),
+                param_count: tone.params.len(),
+            })
+            .collect()
+    }
+}
@@ -1,13 +1,24 @@
+use crate::meta_cache::CachedMeta;
+use crate::profiles::{self, PluginProfile};
 use crate::reaper_api::ReaperApi;
-use gojira_protocol::{ParamEnumOption, ParamFormatSample, ParamFormatTriplet};
-use std::collections::HashMap;
+use gojira_protocol::{
+    ParamChange, ParamCheck, ParamCheckStatus, ParamEnumOption, ParamFormatSample,
+    ParamFormatTriplet, ParamKind, ParamRole, ToneIssue, ToneWarning,
+};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
 
 const DELAY_ACTIVE_ANCHOR: i32 = 101;
 const REVERB_ACTIVE_ANCHOR: i32 = 112;
+/// How far from a flagged param's charted index `propose_remap` looks, either way.
+const PROPOSE_WINDOW: i32 = 8;
 
-// Probes for common "mix" (dry/wet) controls in known plugin builds.
-const DELAY_MIX_PROBE: i32 = 105;
-const REVERB_MIX_PROBE: i32 = 114;
+// Formatted-value fallback: more distinct labels than this over KIND_SAMPLES points means continuous.
+const KIND_SAMPLES: i32 = 32;
+const MAX_STEPPED_LABELS: usize = 16;
+/// Points of `probe_enum`'s first, even pass; it bisects from there.
+const ENUM_COARSE_SAMPLES: usize = 64;
 
 fn validation_report_enabled() -> bool {
     matches!(
@@ -16,178 +27,659 @@ fn validation_report_enabled() -> bool {
     )
 }
 
+/// Checks every module toggle and knob by param name, in module order; empty for a profile
+/// without modules.
 pub fn validate_parameter_map(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
-) -> HashMap<String, String> {
+    profile: &PluginProfile,
+) -> Vec<ParamCheck> {
     if !validation_report_enabled() {
-        return HashMap::new();
+        return Vec::new();
     }
-    let mut report = HashMap::new();
-
-    report.insert(
-        "delay_active_101".to_string(),
-        anchor_report(api, track, fx_index, DELAY_ACTIVE_ANCHOR),
-    );
-    report.insert(
-        "delay_mix_105".to_string(),
-        mix_report(api, track, fx_index, DELAY_MIX_PROBE),
-    );
-    report.insert(
-        "reverb_active_112".to_string(),
-        anchor_report(api, track, fx_index, REVERB_ACTIVE_ANCHOR),
-    );
-    report.insert(
-        "reverb_mix_114".to_string(),
-        mix_report(api, track, fx_index, REVERB_MIX_PROBE),
-    );
-    report
+    let mut out = Vec::new();
+    for module in profile.modules {
+        for &index in module.params {
+            let role = if module.bypass.contains(&index) {
+                ParamRole::Toggle
+            } else if profile.mix_params.contains(&index) {
+                ParamRole::Mix
+            } else {
+                ParamRole::Knob
+            };
+            let expected_name = profile.charted(index).map(|p| p.name.to_string());
+            let found_name = api.track_fx_param_name(track, fx_index, index);
+            let status = check_status(role, expected_name.as_deref(), found_name.as_deref());
+            out.push(ParamCheck {
+                module: module.name.to_string(),
+                role,
+                expected_index: index,
+                expected_name,
+                found_name,
+                status,
+            });
+        }
+    }
+    out
 }
 
-pub fn probe_param_meta(
+/// Canonical -> actual index for toggles and mix controls the report flags, found by searching
+/// the indices around each one for a name that fits its role. A guess to offer the user rather
+/// than apply: params `param_remap` already moved by name are left out, and nothing is proposed
+/// onto an index that checked out as charted or that another proposal took.
+pub fn propose_remap(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
-) -> (
-    HashMap<i32, Vec<ParamEnumOption>>,
-    HashMap<i32, ParamFormatTriplet>,
-    HashMap<i32, Vec<ParamFormatSample>>,
-) {
-    let mut enums: HashMap<i32, Vec<ParamEnumOption>> = HashMap::new();
-    let mut formats: HashMap<i32, ParamFormatTriplet> = HashMap::new();
-    let mut samples: HashMap<i32, Vec<ParamFormatSample>> = HashMap::new();
-
-    // Enumerated selectors we care about for cab/IR + a couple of FX modes.
-    for (idx, samples, max_options) in [
-        (84, 512, 64),  // Cab Type
-        (92, 2048, 512), // Cab 1 Mic IR
-        (99, 2048, 512), // Cab 2 Mic IR
-        (113, 256, 32), // Reverb Mode
-        (5, 128, 32),   // WOW Type
-    ] {
-        if api.track_fx_param_name(track, fx_index, idx).is_none() {
-            continue;
+    report: &[ParamCheck],
+    param_remap: &HashMap<i32, i32>,
+) -> HashMap<i32, i32> {
+    let mut taken: HashSet<i32> = report
+        .iter()
+        .filter(|c| c.status == ParamCheckStatus::Ok)
+        .map(|c| c.expected_index)
+        .chain(param_remap.values().copied())
+        .collect();
+    let mut out = HashMap::new();
+    // Toggles first: a module's mix control is searched for around where its toggle ended up.
+    let flagged = report.iter().filter(|c| {
+        c.status != ParamCheckStatus::Ok && !param_remap.contains_key(&c.expected_index)
+    });
+    let (toggles, mixes): (Vec<&ParamCheck>, Vec<&ParamCheck>) = flagged
+        .filter(|c| c.role != ParamRole::Knob)
+        .partition(|c| c.role == ParamRole::Toggle);
+    for c in toggles {
+        let expected = c.expected_index;
+        let window = expected - PROPOSE_WINDOW..=expected + PROPOSE_WINDOW;
+        if let Some(to) = pick_active_near(api, track, fx_index, expected, window, &taken) {
+            taken.insert(to);
+            out.insert(expected, to);
         }
-        let opts = probe_enum(api, track, fx_index, idx, samples, max_options);
-        if !opts.is_empty() {
-            enums.insert(idx, opts);
+    }
+    for c in mixes {
+        let expected = c.expected_index;
+        let anchor = report
+            .iter()
+            .find(|t| t.module == c.module && t.role == ParamRole::Toggle)
+            .map(|t| t.expected_index)
+            .map(|t| out.get(&t).or(param_remap.get(&t)).copied().unwrap_or(t))
+            .unwrap_or(expected);
+        let window = expected - PROPOSE_WINDOW..=expected + PROPOSE_WINDOW;
+        if let Some((to, _)) = pick_mix_near(api, track, fx_index, anchor, window, &taken) {
+            taken.insert(to);
+            out.insert(expected, to);
         }
     }
+    out.retain(|from, to| from != to);
+    out
+}
 
-    // Continuous controls where formatted values can reveal units/direction/scales.
-    // Keep this reasonably broad so the backend can do robust unit->0..1 conversions
-    // without requiring full sample telemetry.
-    let mut format_indices: Vec<i32> = Vec::new();
-    format_indices.extend([0, 1, 2]); // input/output gain + gate
-    format_indices.extend(30..=51); // amp knobs
-    format_indices.extend(54..=82); // graphic EQ bands
-    format_indices.extend([87, 88, 89, 94, 95, 96]); // cab mic position/distance/level
-    format_indices.extend([105, 106, 108]); // delay
-    format_indices.extend([114, 115, 116, 117]); // reverb
-    format_indices.sort_unstable();
-    format_indices.dedup();
-
-    for idx in format_indices {
-        if api.track_fx_param_name(track, fx_index, idx).is_none() {
-            continue;
+/// A charted name has to match; without one, the name only has to fit the role.
+fn check_status(role: ParamRole, expected: Option<&str>, found: Option<&str>) -> ParamCheckStatus {
+    let Some(found) = found else {
+        return ParamCheckStatus::Missing;
+    };
+    let fits = match expected {
+        Some(expected) => name_score(expected, found) > 0,
+        None => {
+            let n = normalize(found);
+            match role {
+                ParamRole::Toggle => {
+                    n.contains("active") || n.contains("on") || n.contains("enable")
+                }
+                ParamRole::Mix => {
+                    n.contains("mix")
+                        || n.contains("drywet")
+                        || (n.contains("dry") && n.contains("wet"))
+                }
+                ParamRole::Knob => true,
+            }
         }
-        if let Some(t) = probe_format_triplet(api, track, fx_index, idx) {
-            formats.insert(idx, t);
+    };
+    if fits {
+        ParamCheckStatus::Ok
+    } else {
+        ParamCheckStatus::Suspicious
+    }
+}
+
+/// How the meta probe gets a param's label for a value.
+#[derive(Clone, Copy, PartialEq)]
+enum ProbeMode {
+    /// FormatParamValue alone; nothing is written.
+    ReadOnly,
+    /// Sets the param to each value before formatting it, for plugins that only label their
+    /// current value. `GOJIRA_TOUCH_PROBE=1`; skipped while the transport plays, where the writes
+    /// would be heard.
+    Touch,
+}
+
+impl ProbeMode {
+    fn from_env() -> Self {
+        match std::env::var("GOJIRA_TOUCH_PROBE")
+            .as_deref()
+            .map(str::trim)
+        {
+            Ok("1") | Ok("true") | Ok("TRUE") | Ok("yes") | Ok("YES") => ProbeMode::Touch,
+            _ => ProbeMode::ReadOnly,
         }
     }
+}
 
-    // Optional: attach formatted samples (norm->formatted) for unit conversion.
-    // Enabled via env var to avoid bloating handshake by default.
-    let enable_samples = std::env::var("GOJIRA_SEND_PARAM_SAMPLES")
-        .ok()
-        .map(|s| s.trim().eq_ignore_ascii_case("1") || s.trim().eq_ignore_ascii_case("true"))
-        .unwrap_or(true);
+/// Which params the meta probe looks at, worked out once per handshake so the probe itself can
+/// run an index range at a time between timer ticks.
+pub struct MetaPlan {
+    /// Param index, resolution option boundaries are found to, most options to keep.
+    enums: Vec<(i32, usize, usize)>,
+    formats: Vec<i32>,
+    samples: Vec<i32>,
+    sample_norms: Vec<f64>,
+    /// Kinds are classified for `0..=last_kind`, the last tone param.
+    last_kind: i32,
+    mode: ProbeMode,
+    /// Highest index any of the above covers.
+    pub last_index: i32,
+}
+
+impl MetaPlan {
+    pub fn new(api: &dyn ReaperApi, track: usize, fx_index: i32, profile: &PluginProfile) -> Self {
+        let enums = profile.enum_params.to_vec();
+        // Broad enough that the backend can do robust unit->0..1 conversions without requiring
+        // full sample telemetry.
+        let formats = profiles::indices(profile.format_params);
+
+        // Optional: attach formatted samples (norm->formatted) for unit conversion.
+        let enable_samples = std::env::var("GOJIRA_SEND_PARAM_SAMPLES")
+            .ok()
+            .map(|s| s.trim().eq_ignore_ascii_case("1") || s.trim().eq_ignore_ascii_case("true"))
+            .unwrap_or(true);
 
-    if enable_samples {
         let steps = std::env::var("GOJIRA_PARAM_SAMPLE_STEPS")
             .ok()
             .and_then(|s| s.trim().parse::<usize>().ok())
             .unwrap_or(11)
             .clamp(3, 201);
+        let sample_norms: Vec<f64> = (0..steps).map(|i| i as f64 / (steps - 1) as f64).collect();
 
         let mode = std::env::var("GOJIRA_PARAM_SAMPLE_MODE")
             .ok()
             .map(|s| s.trim().to_ascii_lowercase())
             .unwrap_or_else(|| "tone".to_string());
 
-        let indices: Vec<i32> = if mode == "all" {
+        let samples: Vec<i32> = if !enable_samples {
+            Vec::new()
+        } else if mode == "all" {
             match api.track_fx_num_params(track, fx_index) {
                 Some(n) => (0..n).map(|i| i as i32).collect(),
                 None => Vec::new(),
             }
         } else {
             // "tone" mode: keep it reasonably small but useful for conversions.
-            let mut v: Vec<i32> = Vec::new();
-            v.extend([0, 1]); // input/output gain
-            v.push(2); // gate
-            v.push(29); // amp selector
-            v.extend(30..=51); // amp knobs
-            v.extend(54..=82); // EQ bands
-            v.extend([83, 84, 85, 87, 88, 89, 92, 94, 95, 96, 99]); // cab selectors (+ mic pos/dist/levels)
-            v.extend([101, 105, 106, 108]); // delay
-            v.extend([112, 113, 114, 115, 116, 117]); // reverb
-            v.sort_unstable();
-            v.dedup();
-            v
+            profiles::indices(profile.sample_params)
         };
 
-        let mut norms: Vec<f32> = Vec::with_capacity(steps);
-        for i in 0..steps {
-            norms.push(i as f32 / (steps - 1) as f32);
+        let last_kind = profiles::last_tone_param(api, track, fx_index, profile);
+        let last_index = enums
+            .iter()
+            .map(|(idx, _, _)| *idx)
+            .chain(formats.iter().copied())
+            .chain(samples.iter().copied())
+            .chain(std::iter::once(last_kind))
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            enums,
+            formats,
+            samples,
+            sample_norms,
+            last_kind,
+            last_index,
+            mode: ProbeMode::from_env(),
         }
+    }
 
-        for idx in indices {
-            if api.track_fx_param_name(track, fx_index, idx).is_none() {
-                continue;
+    /// Runs one param's probe, letting it write the param when the plan touches and the
+    /// transport is stopped; the value it had is put back afterwards. The bool tells `probe`
+    /// whether it may write.
+    fn restoring<T>(
+        &self,
+        api: &dyn ReaperApi,
+        track: usize,
+        fx_index: i32,
+        idx: i32,
+        probe: impl FnOnce(bool) -> T,
+    ) -> T {
+        if self.mode == ProbeMode::ReadOnly || api.transport_playing() {
+            return probe(false);
+        }
+        let Some(before) = api.track_fx_get_param(track, fx_index, idx) else {
+            return probe(false);
+        };
+        let out = probe(true);
+        // A write that fails here failed in the probe too, leaving the value as it was.
+        let _ = api.track_fx_set_param(track, fx_index, idx, before);
+        out
+    }
+
+    /// What the sample settings and this DLL build make of the probe, for the meta cache key.
+    pub fn settings(&self) -> String {
+        format!(
+            "v{}:samples={}x{}{}",
+            env!("CARGO_PKG_VERSION"),
+            self.samples.len(),
+            self.sample_norms.len(),
+            if self.mode == ProbeMode::Touch {
+                ":touch"
+            } else {
+                ""
             }
-            let mut v: Vec<ParamFormatSample> = Vec::new();
-            for &norm in &norms {
-                let formatted = api
-                    .track_fx_format_param_value(track, fx_index, idx, norm)
-                    .unwrap_or_default()
-                    .trim()
-                    .to_string();
-                if formatted.is_empty() {
-                    continue;
-                }
-                v.push(ParamFormatSample { norm, formatted });
+        )
+    }
+}
+
+/// Probes enum options, formatted triplets, (optionally) formatted samples and kinds for the
+/// params of `plan` that fall in `range`. The handshake goes out without them; the main loop
+/// streams the results a few ranges per tick. REAPER's FX calls belong on its main thread, so the
+/// probe is split up rather than handed to a worker.
+pub fn probe_param_meta_range(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    plan: &MetaPlan,
+    range: RangeInclusive<i32>,
+) -> CachedMeta {
+    let mut enums: HashMap<i32, Vec<ParamEnumOption>> = HashMap::new();
+    let mut formats: HashMap<i32, ParamFormatTriplet> = HashMap::new();
+    let mut samples: HashMap<i32, Vec<ParamFormatSample>> = HashMap::new();
+    let exists = |idx: i32| api.track_fx_param_name(track, fx_index, idx).is_some();
+
+    for &(idx, sample_count, max_options) in &plan.enums {
+        if !range.contains(&idx) || !exists(idx) {
+            continue;
+        }
+        let opts = plan.restoring(api, track, fx_index, idx, |touch| {
+            probe_enum(api, track, fx_index, idx, sample_count, max_options, touch)
+        });
+        if !opts.is_empty() {
+            enums.insert(idx, opts);
+        }
+    }
+
+    for &idx in &plan.formats {
+        if !range.contains(&idx) || !exists(idx) {
+            continue;
+        }
+        let triplet = plan.restoring(api, track, fx_index, idx, |touch| {
+            probe_format_triplet(api, track, fx_index, idx, touch)
+        });
+        if let Some(t) = triplet {
+            formats.insert(idx, t);
+        }
+    }
+
+    for &idx in &plan.samples {
+        if !range.contains(&idx) || !exists(idx) {
+            continue;
+        }
+        let v: Vec<ParamFormatSample> = plan.restoring(api, track, fx_index, idx, |touch| {
+            plan.sample_norms
+                .iter()
+                .map(|&norm| ParamFormatSample {
+                    norm,
+                    formatted: format_at(api, track, fx_index, idx, norm, touch),
+                })
+                .filter(|s| !s.formatted.is_empty())
+                .collect()
+        });
+        if !v.is_empty() {
+            samples.insert(idx, v);
+        }
+    }
+
+    // Probed enums count as stepped whatever their step sizes or labels say.
+    let mut kinds: HashMap<i32, ParamKind> = HashMap::new();
+    for idx in *range.start()..=(*range.end()).min(plan.last_kind) {
+        if enums.contains_key(&idx) || !exists(idx) {
+            continue;
+        }
+        let kind = plan.restoring(api, track, fx_index, idx, |touch| {
+            classify_param(api, track, fx_index, idx, touch)
+        });
+        if let Some(kind) = kind {
+            kinds.insert(idx, kind);
+        }
+    }
+    for (idx, opts) in &enums {
+        kinds.insert(*idx, stepped_or_toggle(opts.len() as u32));
+    }
+
+    CachedMeta {
+        param_enums: enums,
+        param_formats: formats,
+        param_format_samples: samples,
+        param_kinds: kinds,
+    }
+}
+
+/// Toggle / stepped / continuous for one param. Uses REAPER's step sizes when the plugin reports
+/// them, else counts distinct formatted values.
+fn classify_param(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    idx: i32,
+    touch: bool,
+) -> Option<ParamKind> {
+    if let Some((step, is_toggle)) = api.track_fx_param_step_sizes(track, fx_index, idx) {
+        if is_toggle {
+            return Some(ParamKind::Toggle);
+        }
+        if step > 0.0 && step <= 1.0 {
+            let steps = (1.0 / step).round() as u32 + 1;
+            return Some(stepped_or_toggle(steps));
+        }
+    }
+
+    let mut labels: Vec<String> = Vec::new();
+    for s in 0..=KIND_SAMPLES {
+        let v = s as f64 / KIND_SAMPLES as f64;
+        let label = format_at(api, track, fx_index, idx, v, touch);
+        if label.is_empty() {
+            continue;
+        }
+        if !labels.contains(&label) {
+            labels.push(label);
+            if labels.len() > MAX_STEPPED_LABELS {
+                return Some(ParamKind::Continuous);
             }
-            if !v.is_empty() {
-                samples.insert(idx, v);
+        }
+    }
+    // A single label over the whole range says nothing about the control.
+    (labels.len() >= 2).then(|| stepped_or_toggle(labels.len() as u32))
+}
+
+fn stepped_or_toggle(steps: u32) -> ParamKind {
+    if steps <= 2 {
+        ParamKind::Toggle
+    } else {
+        ParamKind::Stepped { steps }
+    }
+}
+
+/// Graphic-EQ band center frequencies, read from band names (e.g. "RUST EQ 400Hz") or, failing
+/// that, from the formatted value at the flat point. Bands without an embedded frequency are omitted.
+pub fn probe_eq_band_hz(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    profile: &PluginProfile,
+) -> HashMap<i32, f32> {
+    let mut out = HashMap::new();
+    for idx in profiles::indices(profile.eq_bands) {
+        let Some(name) = api.track_fx_param_name(track, fx_index, idx) else {
+            continue;
+        };
+        let hz = parse_embedded_hz(&name).or_else(|| {
+            api.track_fx_format_param_value(track, fx_index, idx, 0.5)
+                .and_then(|f| parse_embedded_hz(&f))
+        });
+        if let Some(hz) = hz {
+            out.insert(idx, hz);
+        }
+    }
+    out
+}
+
+/// REAPER's own bypass/wet/delta controls, which it appends after the plugin's params.
+pub fn reserved_params(api: &dyn ReaperApi, track: usize, fx_index: i32) -> HashSet<i32> {
+    [":bypass", ":wet", ":delta"]
+        .iter()
+        .filter_map(|ident| api.track_fx_param_from_ident(track, fx_index, ident))
+        .collect()
+}
+
+/// What's wrong with `params` as a tone for the FX, in param order. `kinds` and `enums` come from
+/// the last probe; params they don't cover skip the toggle and enum checks.
+pub fn validate_tone(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    profile: &PluginProfile,
+    params: &[ParamChange],
+    kinds: &HashMap<i32, ParamKind>,
+    enums: &HashMap<i32, Vec<ParamEnumOption>>,
+) -> Vec<ToneWarning> {
+    let reserved = reserved_params(api, track, fx_index);
+    let mut out = Vec::new();
+    let mut values: HashMap<i32, f64> = HashMap::new();
+
+    for p in params {
+        let warn = |issue, msg: String| ToneWarning {
+            issue,
+            index: p.index,
+            msg,
+        };
+        if values.insert(p.index, p.value).is_some() {
+            let msg = format!(
+                "param {} is listed more than once; the last value wins",
+                p.index
+            );
+            out.push(warn(ToneIssue::Duplicate, msg));
+        }
+        if reserved.contains(&p.index) {
+            let msg = format!("param {} is REAPER's own bypass/wet/delta control", p.index);
+            out.push(warn(ToneIssue::ReservedParam, msg));
+            continue;
+        }
+        let Some(name) = api.track_fx_param_name(track, fx_index, p.index) else {
+            let msg = format!("the plugin has no param {}", p.index);
+            out.push(warn(ToneIssue::UnknownParam, msg));
+            continue;
+        };
+        if !(0.0..=1.0).contains(&p.value) {
+            let msg = format!("{name} ({}) = {} is outside 0..1", p.index, p.value);
+            out.push(warn(ToneIssue::OutOfRange, msg));
+            continue;
+        }
+        if kinds.get(&p.index) == Some(&ParamKind::Toggle) && p.value > 0.0 && p.value < 1.0 {
+            let msg = format!("{name} ({}) is a toggle but set to {:.3}", p.index, p.value);
+            out.push(warn(ToneIssue::FractionalToggle, msg));
+        }
+        if let Some(opts) = enums.get(&p.index).filter(|o| !o.is_empty()) {
+            let label = api
+                .track_fx_format_param_value(track, fx_index, p.index, p.value)
+                .unwrap_or_default();
+            let label = label.trim();
+            if !label.is_empty() && !opts.iter().any(|o| o.label == label) {
+                let msg = format!(
+                    "{name} ({}) = {:.3} reads as '{label}', none of its {} known options",
+                    p.index,
+                    p.value,
+                    opts.len()
+                );
+                out.push(warn(ToneIssue::OffEnumOption, msg));
             }
         }
     }
 
-    (enums, formats, samples)
+    // A module's params do nothing while any of its toggles is off, here or on the FX already.
+    for module in profile.modules {
+        let touched = module
+            .params
+            .iter()
+            .any(|i| !module.bypass.contains(i) && values.contains_key(i));
+        if !touched {
+            continue;
+        }
+        let off = module.bypass.iter().copied().find(|&b| {
+            values
+                .get(&b)
+                .copied()
+                .or_else(|| api.track_fx_get_param(track, fx_index, b))
+                .is_some_and(|v| v < 0.5)
+        });
+        if let Some(toggle) = off {
+            out.push(ToneWarning {
+                issue: ToneIssue::ModuleOff,
+                index: toggle,
+                msg: format!(
+                    "{} params are set but its toggle ({toggle}) stays off",
+                    module.name
+                ),
+            });
+        }
+    }
+    out
 }
 
-fn probe_format_triplet(
+/// Charted index -> the index that now carries its name, for params a plugin update has moved.
+/// A param whose charted index still carries a matching name stays put; otherwise the nearest
+/// unclaimed param with the best-matching name takes over. Ties are ambiguous and left unmapped.
+pub fn discover_param_remap(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    profile: &PluginProfile,
+) -> HashMap<i32, i32> {
+    let mut out = HashMap::new();
+    if profile.param_names.is_empty() {
+        return out;
+    }
+    let count = api
+        .track_fx_num_params(track, fx_index)
+        .or(profile.last_tone_param.map(|last| last + 1))
+        .unwrap_or(0);
+    let names: HashMap<i32, String> = (0..count)
+        .filter_map(|idx| {
+            api.track_fx_param_name(track, fx_index, idx)
+                .map(|name| (idx, name))
+        })
+        .collect();
+    let mut claimed: HashSet<i32> = profile
+        .param_names
+        .iter()
+        .filter(|p| {
+            names
+                .get(&p.index)
+                .is_some_and(|n| name_score(p.name, n) > 0)
+        })
+        .map(|p| p.index)
+        .collect();
+
+    for spec in profile.param_names {
+        let (idx, canonical) = (spec.index, spec.name);
+        if claimed.contains(&idx) {
+            continue;
+        }
+        let mut candidates: Vec<(u8, i32, i32)> = names
+            .iter()
+            .filter(|(i, _)| !claimed.contains(*i))
+            .map(|(&i, name)| (name_score(canonical, name), (i - idx).abs(), i))
+            .filter(|(score, _, _)| *score > 0)
+            .collect();
+        candidates.sort_by_key(|&(score, dist, _)| (Reverse(score), dist));
+        let Some(&(score, dist, to)) = candidates.first() else {
+            continue;
+        };
+        if candidates
+            .get(1)
+            .is_some_and(|&(s, d, _)| s == score && d == dist)
+        {
+            continue;
+        }
+        claimed.insert(to);
+        out.insert(idx, to);
+    }
+    out
+}
+
+/// 2 when the names match outright, 1 when `name` has every word of `canonical` (e.g. a suffix
+/// was added), else 0.
+fn name_score(canonical: &str, name: &str) -> u8 {
+    if normalize(canonical) == normalize(name) {
+        return 2;
+    }
+    let words = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_ascii_lowercase())
+            .collect()
+    };
+    let have = words(name);
+    if words(canonical).iter().all(|w| have.contains(w)) {
+        1
+    } else {
+        0
+    }
+}
+
+fn parse_embedded_hz(s: &str) -> Option<f32> {
+    let lower = s.to_ascii_lowercase();
+    let pos = lower.find("hz")?;
+    let head = lower[..pos].trim_end();
+    let (head, mult) = match head.strip_suffix('k') {
+        Some(h) => (h.trim_end(), 1000.0),
+        None => (head, 1.0),
+    };
+    let start = head
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_ascii_digit() || *c == '.' || *c == ','))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    // A comma before exactly three digits groups thousands ("1,000 Hz"); any other is a decimal
+    // comma ("1,5 kHz").
+    let raw = &head[start..];
+    let mut number = String::with_capacity(raw.len());
+    for (i, c) in raw.char_indices() {
+        if c != ',' {
+            number.push(c);
+            continue;
+        }
+        let group = raw[i + 1..]
+            .split(|d: char| !d.is_ascii_digit())
+            .next()
+            .unwrap_or_default();
+        if group.len() != 3 {
+            number.push('.');
+        }
+    }
+    let v = number.parse::<f32>().ok()?;
+    (v > 0.0).then_some(v * mult)
+}
+
+/// The plugin's label for `norm`, trimmed; empty when it has none. With `touch` the param is set
+/// to `norm` first, for plugins that only label their current value.
+fn format_at(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
     idx: i32,
-) -> Option<ParamFormatTriplet> {
-    let min = api
-        .track_fx_format_param_value(track, fx_index, idx, 0.0)
-        .unwrap_or_default()
-        .trim()
-        .to_string();
-    let mid = api
-        .track_fx_format_param_value(track, fx_index, idx, 0.5)
-        .unwrap_or_default()
-        .trim()
-        .to_string();
-    let max = api
-        .track_fx_format_param_value(track, fx_index, idx, 1.0)
+    norm: f64,
+    touch: bool,
+) -> String {
+    if touch {
+        // Unwritable, the label is whatever the plugin makes of `norm` untouched.
+        let _ = api.track_fx_set_param(track, fx_index, idx, norm);
+    }
+    api.track_fx_format_param_value(track, fx_index, idx, norm)
         .unwrap_or_default()
         .trim()
-        .to_string();
+        .to_string()
+}
+
+fn probe_format_triplet(
+    api: &dyn ReaperApi,
+    track: usize,
+    fx_index: i32,
+    idx: i32,
+    touch: bool,
+) -> Option<ParamFormatTriplet> {
+    let min = format_at(api, track, fx_index, idx, 0.0, touch);
+    let mid = format_at(api, track, fx_index, idx, 0.5, touch);
+    let max = format_at(api, track, fx_index, idx, 1.0, touch);
 
     if min.is_empty() && mid.is_empty() && max.is_empty() {
         None
@@ -196,48 +688,45 @@ fn probe_format_triplet(
     }
 }
 
-fn probe_enum(
+/// Finds where an enum's label changes: an even pass of `ENUM_COARSE_SAMPLES` points, then
+/// bisection wherever neighbouring labels differ until each change is pinned to one part in
+/// `samples`. Far fewer calls than scanning at that resolution, and each boundary is the middle
+/// of its bracket rather than the first sample past it.
+pub(crate) fn probe_enum(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
     idx: i32,
-    samples: i32,
+    samples: usize,
     max_options: usize,
+    touch: bool,
 ) -> Vec<ParamEnumOption> {
     let samples = samples.max(16);
-
-    let mut segments: Vec<(String, f32, f32)> = Vec::new();
-    let mut last_label: Option<String> = None;
-    let mut seg_start: f32 = 0.0;
-
-    for s in 0..=samples {
-        let v = (s as f32) / (samples as f32);
-        let label = api
-            .track_fx_format_param_value(track, fx_index, idx, v)
-            .unwrap_or_default()
-            .trim()
-            .to_string();
-        if label.is_empty() {
-            continue;
-        }
-
-        match &last_label {
-            None => {
-                last_label = Some(label);
-                seg_start = v;
-            }
-            Some(prev) if prev == &label => {}
-            Some(prev) => {
-                segments.push((prev.clone(), seg_start, v));
-                last_label = Some(label);
-                seg_start = v;
-            }
+    let coarse = ENUM_COARSE_SAMPLES.min(samples);
+    let min_span = 1.0 / samples as f64;
+    let label_at = |v: f64| format_at(api, track, fx_index, idx, v, touch);
+
+    // Where each label starts, in order. One past `max_options` is enough to end the last kept.
+    let mut starts: Vec<(f64, String)> = vec![(0.0, label_at(0.0))];
+    let mut prev = starts[0].clone();
+    for s in 1..=coarse {
+        if starts.len() > max_options {
+            break;
         }
+        let v = s as f64 / coarse as f64;
+        let next = (v, label_at(v));
+        split_label_change(&label_at, &prev, &next, min_span, max_options, &mut starts);
+        prev = next;
     }
 
-    if let Some(prev) = last_label {
-        segments.push((prev, seg_start, 1.0));
-    }
+    let segments: Vec<(String, f64, f64)> = starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, label))| {
+            let end = starts.get(i + 1).map(|(v, _)| *v).unwrap_or(1.0);
+            (label.clone(), *start, end)
+        })
+        .collect();
 
     // Convert segments to unique options (midpoint value per label).
     let mut out: Vec<ParamEnumOption> = Vec::new();
@@ -261,37 +750,38 @@ fn probe_enum(
     out
 }
 
-fn anchor_report(api: &dyn ReaperApi, track: usize, fx_index: i32, idx: i32) -> String {
-    let Some(name) = api.track_fx_param_name(track, fx_index, idx) else {
-        return format!("missing param name at {idx}");
-    };
-    let n = normalize(&name);
-    if n.contains("active") || n.contains("on") || n.contains("enable") {
-        format!("present at {idx} ({name})")
-    } else {
-        format!("present but suspicious at {idx} ({name})")
+/// Bisects `lo..hi` while its ends read differently, recording the start of every label found
+/// inside once its bracket is narrower than `min_span`.
+fn split_label_change(
+    label_at: &dyn Fn(f64) -> String,
+    lo: &(f64, String),
+    hi: &(f64, String),
+    min_span: f64,
+    max_options: usize,
+    starts: &mut Vec<(f64, String)>,
+) {
+    if lo.1 == hi.1 || starts.len() > max_options {
+        return;
     }
-}
-
-fn mix_report(api: &dyn ReaperApi, track: usize, fx_index: i32, idx: i32) -> String {
-    let Some(name) = api.track_fx_param_name(track, fx_index, idx) else {
-        return format!("missing param name at {idx}");
-    };
-    let n = normalize(&name);
-    if n.contains("mix") || n.contains("drywet") || (n.contains("dry") && n.contains("wet")) {
-        format!("present at {idx} ({name})")
-    } else {
-        format!("present but suspicious at {idx} ({name})")
+    if hi.0 - lo.0 <= min_span {
+        starts.push(((lo.0 + hi.0) * 0.5, hi.1.clone()));
+        return;
     }
+    let v = (lo.0 + hi.0) * 0.5;
+    let mid = (v, label_at(v));
+    split_label_change(label_at, lo, &mid, min_span, max_options, starts);
+    split_label_change(label_at, &mid, hi, min_span, max_options, starts);
 }
 
 #[allow(dead_code)]
 fn validate_mix(api: &dyn ReaperApi, track: usize, fx_index: i32) -> HashMap<String, String> {
     let mut report = HashMap::new();
-    let delay_anchor = pick_active_near(api, track, fx_index, DELAY_ACTIVE_ANCHOR, 96..=110)
+    let none = HashSet::new();
+    let delay_anchor = pick_active_near(api, track, fx_index, DELAY_ACTIVE_ANCHOR, 96..=110, &none)
         .unwrap_or(DELAY_ACTIVE_ANCHOR);
-    let reverb_anchor = pick_active_near(api, track, fx_index, REVERB_ACTIVE_ANCHOR, 108..=120)
-        .unwrap_or(REVERB_ACTIVE_ANCHOR);
+    let reverb_anchor =
+        pick_active_near(api, track, fx_index, REVERB_ACTIVE_ANCHOR, 108..=120, &none)
+            .unwrap_or(REVERB_ACTIVE_ANCHOR);
 
     report.insert("delay_active_best_guess".to_string(), delay_anchor.to_string());
     report.insert(
@@ -299,8 +789,8 @@ fn validate_mix(api: &dyn ReaperApi, track: usize, fx_index: i32) -> HashMap<Str
         reverb_anchor.to_string(),
     );
 
-    let delay_mix = pick_mix_near(api, track, fx_index, delay_anchor, 100..=115);
-    let reverb_mix = pick_mix_near(api, track, fx_index, reverb_anchor, 110..=125);
+    let delay_mix = pick_mix_near(api, track, fx_index, delay_anchor, 100..=115, &none);
+    let reverb_mix = pick_mix_near(api, track, fx_index, reverb_anchor, 110..=125, &none);
 
     report.insert(
         "delay_mix".to_string(),
@@ -325,16 +815,17 @@ fn validate_mix(api: &dyn ReaperApi, track: usize, fx_index: i32) -> HashMap<Str
     report
 }
 
-#[allow(dead_code)]
+/// The toggle-like param in `range` closest to `expected`, skipping `taken` indices.
 fn pick_active_near(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
     expected: i32,
     range: std::ops::RangeInclusive<i32>,
+    taken: &HashSet<i32>,
 ) -> Option<i32> {
     let mut candidates: Vec<(i32, i32)> = Vec::new();
-    for idx in range {
+    for idx in range.filter(|i| !taken.contains(i)) {
         let Some(name) = api.track_fx_param_name(track, fx_index, idx) else {
             continue;
         };
@@ -347,16 +838,18 @@ fn pick_active_near(
     candidates.first().map(|(idx, _)| *idx)
 }
 
-#[allow(dead_code)]
+/// The dry/wet-like param in `range` closest to `anchor`, skipping `taken` indices; ties go to
+/// the one next to a feedback or time control.
 fn pick_mix_near(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
     anchor: i32,
     range: std::ops::RangeInclusive<i32>,
+    taken: &HashSet<i32>,
 ) -> Option<(i32, String)> {
     let mut candidates: Vec<(i32, String, i32)> = Vec::new();
-    for idx in range {
+    for idx in range.filter(|i| !taken.contains(i)) {
         let Some(name) = api.track_fx_param_name(track, fx_index, idx) else {
             continue;
         };
@@ -409,8 +902,7 @@ fn normalize(s: &str) -> String {
         .collect()
 }
 
-#[allow(dead_code)]
-fn window_dump(
+pub fn window_dump(
     api: &dyn ReaperApi,
     track: usize,
     fx_index: i32,
@@ -430,6 +922,22 @@ fn window_dump(
     if joined.len() <= MAX_CHARS {
         joined
     } else {
-        format!("{}…", &joined[..MAX_CHARS])
+        let mut cut = MAX_CHARS;
+        while !joined.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        format!("{}…", &joined[..cut])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_hz_tells_thousands_from_decimal_commas() {
+        assert_eq!(parse_embedded_hz("1,000 Hz"), Some(1000.0));
+        assert_eq!(parse_embedded_hz("1,5 kHz"), Some(1500.0));
+        assert_eq!(parse_embedded_hz("800 Hz"), Some(800.0));
     }
 }
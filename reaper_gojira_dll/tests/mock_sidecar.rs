@@ -0,0 +1,86 @@
+//! Starts the `mock_sidecar` binary with its fault flags on and checks a client still gets a
+//! handshake through them.
+
+use gojira_protocol::ServerMessage;
+use std::net::TcpStream;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+
+/// Kills the sidecar when the test ends, passed or not.
+struct Sidecar(Child);
+
+impl Drop for Sidecar {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn mock_sidecar_handshakes_through_its_faults() {
+    let addr_file = std::env::temp_dir().join(format!("gojira-mock-addr-{}", std::process::id()));
+    let _ = std::fs::remove_file(&addr_file);
+    let _sidecar = Sidecar(
+        Command::new(env!("CARGO_BIN_EXE_mock_sidecar"))
+            .args(["--addr", "127.0.0.1:0", "--addr-file"])
+            .arg(&addr_file)
+            .args(["--run-for-ms", "10000"])
+            .args(["--latency-ms", "50", "--drop-every", "1000"])
+            .args(["--fail-set-param", "29", "--project-storm-ms", "200"])
+            .spawn()
+            .expect("spawn mock_sidecar"),
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let addr = loop {
+        if let Some(addr) = std::fs::read_to_string(&addr_file)
+            .ok()
+            .filter(|a| !a.trim().is_empty())
+        {
+            break addr.trim().to_string();
+        }
+        assert!(
+            Instant::now() < deadline,
+            "mock_sidecar never wrote its address"
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let stream = TcpStream::connect(&addr).expect("tcp connect");
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let (mut ws, _) = tungstenite::client(format!("ws://{addr}"), stream).expect("ws connect");
+    let text = loop {
+        if let Message::Text(s) = ws.read().expect("ws read") {
+            break s;
+        }
+    };
+    match serde_json::from_str(&text).expect("valid server json") {
+        ServerMessage::Handshake { instances, .. } => assert_eq!(instances.len(), 1),
+        other => panic!("expected handshake first, got: {other:?}"),
+    }
+    let _ = std::fs::remove_file(&addr_file);
+}
+
+#[test]
+fn mock_sidecar_help_lists_every_flag() {
+    let out = Command::new(env!("CARGO_BIN_EXE_mock_sidecar"))
+        .arg("--help")
+        .output()
+        .expect("run mock_sidecar --help");
+    assert!(out.status.success());
+    let usage = String::from_utf8_lossy(&out.stdout);
+    for flag in [
+        "--addr",
+        "--addr-file",
+        "--run-for-ms",
+        "--scenario",
+        "--script",
+        "--latency-ms",
+        "--drop-every",
+        "--fail-set-param",
+        "--project-storm-ms",
+    ] {
+        assert!(usage.contains(flag), "{flag} missing from:\n{usage}");
+    }
+}
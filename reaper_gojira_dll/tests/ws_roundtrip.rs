@@ -1,27 +1,154 @@
+#[path = "../src/bin/mock_sidecar/param_db.rs"]
+mod param_db;
+
 use crossbeam_channel::bounded;
-use gojira_protocol::{ClientCommand, ErrorCode, MergeMode, ParamChange, ServerMessage};
-use reaper_gojira_dll::{MainLoop, NetworkThread, ReaperApi};
-use std::collections::HashMap;
-use std::net::TcpStream;
+use gojira_protocol::{
+    Capability, ClientCommand, Confidence, Encoding, ErrorCode, Frame, MergeMode, ModuleId,
+    ParamChange, ParamKind, ParamOp, ParamSchemaKind, Remediation, ResolverState, ServerMessage,
+    ToneIssue, ToneTarget, TransportAnchor, WarningCode, SUBPROTOCOL_MSGPACK,
+};
+use interprocess::local_socket::prelude::*;
+use interprocess::local_socket::GenericNamespaced;
+use param_db::ParamDb;
+use reaper_gojira_dll::{MainLoop, MidiMap, NetworkThread, ReaperAction, ReaperApi};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{TcpStream, UdpSocket};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use tungstenite::client::IntoClientRequest;
 use tungstenite::Message;
 
+/// Previous value of every param written inside one undo block (`None`: it was never set).
+type UndoPoint = HashMap<i32, Option<f64>>;
+
 struct MockReaperApi {
-    params: Mutex<HashMap<i32, f32>>,
+    params: Mutex<HashMap<i32, f64>>,
+    open_undo: Mutex<Option<UndoPoint>>,
+    undo: Mutex<Vec<(String, UndoPoint)>>,
+    preset: Mutex<Option<String>>,
+    change_count: Mutex<i32>,
+    track_name: Mutex<String>,
+    /// Names that replace the generic `param_N` ones, e.g. to move a param.
+    param_names: Mutex<HashMap<i32, String>>,
+    track_info: Mutex<HashMap<String, f64>>,
+    /// FX switched off, by track and chain slot.
+    bypassed: Mutex<HashSet<(usize, i32)>>,
+    /// FX that stay off when enabled, like a plugin that failed to load.
+    unloadable: Mutex<HashSet<(usize, i32)>>,
+    fx_count: Mutex<i32>,
+    /// Chain slot the mock FX's guid is at; moving it stands in for a drag along the chain.
+    fx_slot: Mutex<i32>,
+    /// Gives track 100 a second instance, `{MOCK-FX-GUID-B}`, in slot 1 (with `fx_count` 2).
+    pair: Mutex<bool>,
+    /// Gives project 1 a second track (300) with an instance of its own, `{MOCK-FX-GUID-3}`.
+    second_track: Mutex<bool>,
+    playing: Mutex<bool>,
+    /// Where playback is, while `playing`.
+    play_position: Mutex<f64>,
+    loop_range: Mutex<Option<(f64, f64)>>,
+    /// Project ext state by (section, key); outlives any one MainLoop, like the project file.
+    ext_state: Mutex<HashMap<(String, String), String>>,
+    /// Project tab in front; only project 1 has a track, unless `second_tab`.
+    current_project: Mutex<usize>,
+    /// Gives project 2 a track (200) with an instance of its own, `{MOCK-FX-GUID-2}`.
+    second_tab: Mutex<bool>,
+    /// FX name reads so far, to tell a full track scan from a reused one.
+    fx_name_reads: Mutex<usize>,
+    /// The plugin's real names and value formats in place of the `param_N` stubs.
+    layout: Option<ParamDb>,
+    /// Params whose writes fail, like a param REAPER refuses to set.
+    rejected: Mutex<HashSet<i32>>,
+    /// Params that take writes but read back as nothing, like a param REAPER can't report.
+    unreadable: Mutex<HashSet<i32>>,
+    /// Params a plugin update added on top of the usual count.
+    added_params: Mutex<i32>,
+    /// Everything written to REAPER's console.
+    console: Mutex<String>,
+    /// Every param write that went through, in order.
+    writes: Mutex<Vec<(i32, f64)>>,
 }
 
 impl MockReaperApi {
     fn new() -> Self {
         Self {
             params: Mutex::new(HashMap::new()),
+            open_undo: Mutex::new(None),
+            undo: Mutex::new(Vec::new()),
+            preset: Mutex::new(None),
+            change_count: Mutex::new(0),
+            track_name: Mutex::new("Mock Track".to_string()),
+            param_names: Mutex::new(HashMap::new()),
+            track_info: Mutex::new(HashMap::from([
+                ("D_VOL".to_string(), 1.0),
+                ("D_PAN".to_string(), 0.0),
+                ("I_RECMON".to_string(), 1.0),
+            ])),
+            bypassed: Mutex::new(HashSet::new()),
+            unloadable: Mutex::new(HashSet::new()),
+            fx_count: Mutex::new(1),
+            fx_slot: Mutex::new(0),
+            pair: Mutex::new(false),
+            second_track: Mutex::new(false),
+            playing: Mutex::new(false),
+            play_position: Mutex::new(0.0),
+            loop_range: Mutex::new(None),
+            ext_state: Mutex::new(HashMap::new()),
+            current_project: Mutex::new(1),
+            second_tab: Mutex::new(false),
+            fx_name_reads: Mutex::new(0),
+            layout: None,
+            rejected: Mutex::new(HashSet::new()),
+            unreadable: Mutex::new(HashSet::new()),
+            added_params: Mutex::new(0),
+            console: Mutex::new(String::new()),
+            writes: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn with_gojira_layout() -> Self {
+        Self {
+            layout: Some(ParamDb::gojira()),
+            ..Self::new()
         }
     }
 }
 
 impl ReaperApi for MockReaperApi {
     fn project_state_change_count(&self) -> i32 {
-        0
+        *self.change_count.lock().unwrap()
+    }
+    fn project_state_change_count_in(&self, _project: usize) -> i32 {
+        // Every tab reports the same count, which is what hid tab switches from the watchdog.
+        *self.change_count.lock().unwrap()
+    }
+    fn project_tempo(&self) -> Option<(f32, i32)> {
+        Some((120.0, 4))
+    }
+    fn transport_play(&self) {
+        *self.playing.lock().unwrap() = true;
+    }
+    fn transport_stop(&self) {
+        *self.playing.lock().unwrap() = false;
+    }
+    fn transport_playing(&self) -> bool {
+        *self.playing.lock().unwrap()
+    }
+    fn play_position(&self) -> Option<f64> {
+        self.transport_playing()
+            .then(|| *self.play_position.lock().unwrap())
+    }
+    fn loop_range(&self) -> Option<(f64, f64)> {
+        *self.loop_range.lock().unwrap()
+    }
+    fn set_loop(&self, start: f64, end: f64) {
+        *self.loop_range.lock().unwrap() = Some((start, end));
+    }
+    fn repeat_enabled(&self) -> bool {
+        self.loop_range.lock().unwrap().is_some()
+    }
+    fn render_range(&self, start: f64, end: f64, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, format!("{start}..{end}")).map_err(|e| e.to_string())
     }
     fn count_tracks(&self) -> i32 {
         1
@@ -29,54 +156,105 @@ impl ReaperApi for MockReaperApi {
     fn get_track(&self, index: i32) -> Option<usize> {
         if index == 0 { Some(100) } else { None }
     }
-    fn enum_project(&self, _index: i32) -> Option<(usize, String)> {
-        None
+    fn enum_project(&self, index: i32) -> Option<(usize, String)> {
+        match index {
+            0 => Some((1, "mock_project.rpp".to_string())),
+            1 => Some((2, "other_project_2.rpp".to_string())),
+            _ => None,
+        }
     }
     fn current_project(&self) -> Option<(usize, String)> {
-        Some((1, "mock_project.rpp".to_string()))
+        match *self.current_project.lock().unwrap() {
+            1 => Some((1, "mock_project.rpp".to_string())),
+            p => Some((p, format!("other_project_{p}.rpp"))),
+        }
     }
     fn count_tracks_in(&self, project: usize) -> i32 {
-        if project == 1 { 1 } else { 0 }
+        match project {
+            1 => 1 + i32::from(*self.second_track.lock().unwrap()),
+            2 if *self.second_tab.lock().unwrap() => 1,
+            _ => 0,
+        }
     }
     fn get_track_in(&self, project: usize, index: i32) -> Option<usize> {
-        if project == 1 && index == 0 {
-            Some(100)
-        } else {
-            None
+        match (project, index) {
+            (1, 0) => Some(100),
+            (1, 1) if *self.second_track.lock().unwrap() => Some(300),
+            (2, 0) if *self.second_tab.lock().unwrap() => Some(200),
+            _ => None,
         }
     }
+    fn selected_track(&self) -> Option<usize> {
+        Some(100)
+    }
     fn track_guid(&self, track: usize) -> Option<String> {
-        if track == 100 {
-            Some("{MOCK-TRACK-GUID}".to_string())
-        } else {
-            None
+        match track {
+            100 => Some("{MOCK-TRACK-GUID}".to_string()),
+            200 => Some("{MOCK-TRACK-GUID-2}".to_string()),
+            300 => Some("{MOCK-TRACK-GUID-3}".to_string()),
+            _ => None,
         }
     }
     fn track_name(&self, _track: usize) -> String {
-        "Mock Track".to_string()
+        self.track_name.lock().unwrap().clone()
+    }
+    fn track_get_info(&self, _track: usize, key: &str) -> Option<f64> {
+        self.track_info.lock().unwrap().get(key).copied()
+    }
+    fn track_set_info(&self, _track: usize, key: &str, value: f64) -> bool {
+        self.track_info
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value);
+        true
     }
     fn track_fx_count(&self, track: usize) -> i32 {
-        if track == 100 { 1 } else { 0 }
+        match track {
+            100 => *self.fx_count.lock().unwrap(),
+            200 | 300 => 1,
+            _ => 0,
+        }
+    }
+    fn track_fx_add_by_name(&self, track: usize, name: &str) -> Option<i32> {
+        if track != 100 || !name.contains("Gojira") {
+            return None;
+        }
+        let mut count = self.fx_count.lock().unwrap();
+        *count += 1;
+        Some(*count - 1)
     }
     fn track_fx_num_params(&self, _track: usize, _fx_index: i32) -> Option<i32> {
-        Some(256)
+        let count = self.layout.as_ref().map_or(256, ParamDb::num_params);
+        Some(count + *self.added_params.lock().unwrap())
     }
     fn track_fx_guid(&self, track: usize, fx_index: i32) -> Option<String> {
-        if track == 100 && fx_index == 0 {
-            Some("{MOCK-FX-GUID}".to_string())
-        } else {
-            None
+        match (track, fx_index) {
+            (100, i) if i == *self.fx_slot.lock().unwrap() => Some("{MOCK-FX-GUID}".to_string()),
+            (100, 1) if *self.pair.lock().unwrap() => Some("{MOCK-FX-GUID-B}".to_string()),
+            (200, 0) => Some("{MOCK-FX-GUID-2}".to_string()),
+            (300, 0) => Some("{MOCK-FX-GUID-3}".to_string()),
+            _ => None,
         }
     }
     fn track_fx_name(&self, _track: usize, _fx_index: i32) -> String {
+        *self.fx_name_reads.lock().unwrap() += 1;
         "Neural DSP: Archetype Gojira (Mock)".to_string()
     }
+    fn track_fx_ident(&self, _track: usize, _fx_index: i32) -> Option<String> {
+        Some("Archetype Gojira (Mock).vst3".to_string())
+    }
     fn track_fx_param_name(
         &self,
         _track: usize,
         _fx_index: i32,
         param_index: i32,
     ) -> Option<String> {
+        if let Some(name) = self.param_names.lock().unwrap().get(&param_index) {
+            return Some(name.clone());
+        }
+        if let Some(layout) = &self.layout {
+            return layout.name(param_index);
+        }
         const KNOWN: &[i32] = &[
             0, 1, 2, 3, 4, 5, 29, 30, 84, 92, 99, 101, 105, 106, 108, 112, 113, 114, 115, 116,
             117,
@@ -91,28 +269,127 @@ impl ReaperApi for MockReaperApi {
         &self,
         _track: usize,
         _fx_index: i32,
-        _param_index: i32,
-        value: f32,
+        param_index: i32,
+        value: f64,
     ) -> Option<String> {
-        Some(format!("{:.3}", value))
+        match &self.layout {
+            Some(layout) => layout.format(param_index, value),
+            None => Some(format!("{:.3}", value)),
+        }
     }
-    fn track_fx_get_param(&self, _track: usize, _fx_index: i32, param_index: i32) -> Option<f32> {
+    fn track_fx_get_param(&self, _track: usize, _fx_index: i32, param_index: i32) -> Option<f64> {
+        if self.unreadable.lock().unwrap().contains(&param_index) {
+            return None;
+        }
         let guard = self.params.lock().ok()?;
         guard.get(&param_index).copied()
     }
+    fn track_fx_param_step_sizes(
+        &self,
+        _track: usize,
+        _fx_index: i32,
+        param_index: i32,
+    ) -> Option<(f64, bool)> {
+        self.layout.as_ref()?.step_sizes(param_index)
+    }
     fn track_fx_set_param(
         &self,
         _track: usize,
         _fx_index: i32,
         param_index: i32,
-        value: f32,
+        value: f64,
     ) -> Result<(), String> {
+        if self.rejected.lock().unwrap().contains(&param_index) {
+            return Err("TrackFX_SetParam returned false".to_string());
+        }
         let Ok(mut guard) = self.params.lock() else {
             return Err("mock lock poisoned".to_string());
         };
-        guard.insert(param_index, value);
+        let prev = guard.insert(param_index, value);
+        self.writes.lock().unwrap().push((param_index, value));
+        if let Some(point) = self.open_undo.lock().unwrap().as_mut() {
+            point.entry(param_index).or_insert(prev);
+        }
         Ok(())
     }
+    fn track_fx_param_from_ident(&self, _track: usize, _fx_index: i32, ident: &str) -> Option<i32> {
+        // REAPER appends its own controls after the plugin's params.
+        match ident {
+            ":bypass" => Some(256),
+            ":wet" => Some(257),
+            ":delta" => Some(258),
+            _ => None,
+        }
+    }
+    fn track_fx_get_enabled(&self, track: usize, fx_index: i32) -> bool {
+        !self.bypassed.lock().unwrap().contains(&(track, fx_index))
+    }
+    fn track_fx_set_enabled(&self, track: usize, fx_index: i32, enabled: bool) {
+        let mut bypassed = self.bypassed.lock().unwrap();
+        if !enabled {
+            bypassed.insert((track, fx_index));
+        } else if !self.unloadable.lock().unwrap().contains(&(track, fx_index)) {
+            bypassed.remove(&(track, fx_index));
+        }
+    }
+    fn get_proj_ext_state(&self, section: &str, key: &str) -> Option<String> {
+        let state = self.ext_state.lock().unwrap();
+        state.get(&(section.to_string(), key.to_string())).cloned()
+    }
+    fn set_proj_ext_state(&self, section: &str, key: &str, value: &str) {
+        self.ext_state
+            .lock()
+            .unwrap()
+            .insert((section.to_string(), key.to_string()), value.to_string());
+    }
+    fn undo_begin_block(&self) {
+        *self.open_undo.lock().unwrap() = Some(HashMap::new());
+    }
+    fn undo_end_block(&self, desc: &str) {
+        if let Some(point) = self.open_undo.lock().unwrap().take() {
+            self.undo.lock().unwrap().push((desc.to_string(), point));
+        }
+    }
+    fn undo_next_desc(&self) -> Option<String> {
+        let undo = self.undo.lock().unwrap();
+        undo.last().map(|(desc, _)| desc.clone())
+    }
+    fn undo_do(&self) -> bool {
+        let Some((_, point)) = self.undo.lock().unwrap().pop() else {
+            return false;
+        };
+        let mut params = self.params.lock().unwrap();
+        for (index, prev) in point {
+            match prev {
+                Some(v) => params.insert(index, v),
+                None => params.remove(&index),
+            };
+        }
+        true
+    }
+    fn show_console_msg(&self, msg: &str) {
+        self.console.lock().unwrap().push_str(msg);
+    }
+    fn track_fx_get_preset(&self, _track: usize, _fx_index: i32) -> Option<String> {
+        self.preset.lock().unwrap().clone()
+    }
+    fn track_fx_set_preset(&self, _track: usize, _fx_index: i32, name: &str) -> bool {
+        // The mock plugin ships a single factory preset.
+        if name != "Factory Clean" {
+            return false;
+        }
+        *self.preset.lock().unwrap() = Some(name.to_string());
+        true
+    }
+}
+
+/// Parses a server frame; `None` for the `ParamMeta` chunks that stream after every handshake,
+/// which only `ws_param_meta_streams_after_the_handshake` looks at.
+fn parse_server(text: &str) -> Option<ServerMessage> {
+    match serde_json::from_str(text).expect("valid server json") {
+        ServerMessage::ParamMeta { .. } => None,
+        msg => Some(msg),
+    }
 }
 
 fn read_server_message(
@@ -122,7 +399,11 @@ fn read_server_message(
     let deadline = Instant::now() + timeout;
     loop {
         match ws.read() {
-            Ok(Message::Text(s)) => return serde_json::from_str(&s).expect("valid server json"),
+            Ok(Message::Text(s)) => {
+                if let Some(msg) = parse_server(&s) {
+                    return msg;
+                }
+            }
             Ok(_) => continue,
             Err(tungstenite::Error::Io(e))
                 if e.kind() == std::io::ErrorKind::WouldBlock
@@ -137,6 +418,63 @@ fn read_server_message(
     }
 }
 
+fn tick_until_message<S: Read + Write>(
+    main_loop: &mut MainLoop,
+    api: &MockReaperApi,
+    ws: &mut tungstenite::WebSocket<S>,
+    timeout: Duration,
+) -> ServerMessage {
+    let deadline = Instant::now() + timeout;
+    loop {
+        main_loop.tick(api);
+        match ws.read() {
+            Ok(Message::Text(s)) => {
+                if let Some(msg) = parse_server(&s) {
+                    return msg;
+                }
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => panic!("ws read failed: {e:?}"),
+        }
+        if Instant::now() >= deadline {
+            panic!("timeout waiting for server message");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Like `tick_until_message`, but the message has to arrive as a MessagePack binary frame.
+fn tick_until_binary<S: Read + Write>(
+    main_loop: &mut MainLoop,
+    api: &MockReaperApi,
+    ws: &mut tungstenite::WebSocket<S>,
+    timeout: Duration,
+) -> ServerMessage {
+    let deadline = Instant::now() + timeout;
+    loop {
+        main_loop.tick(api);
+        match ws.read() {
+            Ok(Message::Binary(b)) => match Encoding::Msgpack.decode(&b).expect("valid msgpack") {
+                ServerMessage::ParamMeta { .. } => {}
+                msg => return msg,
+            },
+            Ok(Message::Text(s)) => panic!("expected a binary frame, got text: {s}"),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => panic!("ws read failed: {e:?}"),
+        }
+        if Instant::now() >= deadline {
+            panic!("timeout waiting for server message");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
 #[test]
 fn ws_handshake_set_tone_and_unauthorized() {
     std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
@@ -146,10 +484,11 @@ fn ws_handshake_set_tone_and_unauthorized() {
     let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
 
     let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
-    let addr = net.listen_addr();
+    let addr = net.listen_addr().expect("tcp listener");
 
     let api = MockReaperApi::new();
     let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_server_addr(addr.to_string());
 
     let stream = TcpStream::connect(addr).expect("tcp connect");
     let _ = stream.set_read_timeout(Some(Duration::from_millis(50)));
@@ -160,7 +499,11 @@ fn ws_handshake_set_tone_and_unauthorized() {
     let handshake = loop {
         main_loop.tick(&api);
         match ws.read() {
-            Ok(Message::Text(s)) => break serde_json::from_str(&s).expect("valid server json"),
+            Ok(Message::Text(s)) => {
+                if let Some(msg) = parse_server(&s) {
+                    break msg;
+                }
+            }
             Ok(_) => {}
             Err(tungstenite::Error::Io(e))
                 if e.kind() == std::io::ErrorKind::WouldBlock
@@ -176,9 +519,32 @@ fn ws_handshake_set_tone_and_unauthorized() {
         ServerMessage::Handshake {
             session_token,
             instances,
+            server_addr,
+            capabilities,
+            meta,
             ..
         } => {
             assert!(!instances.is_empty(), "mock scan should produce an instance");
+            assert!(capabilities.contains(&Capability::AtomicApply));
+            assert!(capabilities.contains(&Capability::ParamSubscription));
+            assert!(!capabilities.contains(&Capability::Unknown));
+            assert_eq!(instances[0].profile.as_deref(), Some("gojira"));
+            assert_eq!(server_addr, Some(addr.to_string()));
+            // Kinds are classified in the meta stream behind the handshake.
+            assert!(meta.param_kinds.is_empty());
+            // The plugin's names where it reports one, the charted ones where it doesn't.
+            let amp_gain = &meta.param_schema[&30];
+            assert_eq!(amp_gain.name, "param_30");
+            assert_eq!(amp_gain.kind, ParamSchemaKind::Continuous);
+            assert_eq!(amp_gain.group, "amp_eq");
+            assert_eq!(meta.param_schema[&84].kind, ParamSchemaKind::Enum);
+            assert_eq!(meta.param_schema[&84].group, "cab");
+            assert_eq!(meta.param_schema[&13].name, "OD Active");
+            assert!(!meta.param_schema.contains_key(&12));
+            // No charted names, so nothing can be found moved.
+            assert!(meta.param_remap.is_empty());
+            // The validation report is off, so nothing is flagged to propose a remap for.
+            assert!(meta.proposed_remap.is_empty());
             (session_token, instances[0].fx_guid.clone())
         }
         other => panic!("expected handshake, got: {other:?}"),
@@ -192,7 +558,14 @@ fn ws_handshake_set_tone_and_unauthorized() {
         params: vec![ParamChange {
             index: 30,
             value: 0.42,
+            op: ParamOp::Set,
         }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
     };
     ws.send(Message::Text(serde_json::to_string(&cmd).unwrap().into()))
         .unwrap();
@@ -201,7 +574,11 @@ fn ws_handshake_set_tone_and_unauthorized() {
     let ack = loop {
         main_loop.tick(&api);
         match ws.read() {
-            Ok(Message::Text(s)) => break serde_json::from_str(&s).expect("valid server json"),
+            Ok(Message::Text(s)) => {
+                if let Some(msg) = parse_server(&s) {
+                    break msg;
+                }
+            }
             Ok(_) => {}
             Err(tungstenite::Error::Io(e))
                 if e.kind() == std::io::ErrorKind::WouldBlock
@@ -217,6 +594,7 @@ fn ws_handshake_set_tone_and_unauthorized() {
         ServerMessage::Ack {
             command_id,
             applied_params,
+            ..
         } => {
             assert_eq!(command_id, "test-1");
             assert_eq!(applied_params.len(), 1);
@@ -226,8 +604,196 @@ fn ws_handshake_set_tone_and_unauthorized() {
         other => panic!("expected ack, got: {other:?}"),
     }
 
+    let missing = ClientCommand::SetTone {
+        session_token: session_token.clone(),
+        command_id: "test-2".to_string(),
+        target_fx_guid: "{NOT-A-REAL-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.5,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    ws.send(Message::Text(serde_json::to_string(&missing).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            code,
+            details,
+            command_id,
+            diagnostic,
+            ..
+        } => {
+            assert!(matches!(code, ErrorCode::TargetNotFound));
+            assert_eq!(command_id.as_deref(), Some("test-2"));
+            let details = details.expect("target-not-found should carry details");
+            assert!(details.contains("{MOCK-FX-GUID}"), "details: {details}");
+            let diagnostic = diagnostic.expect("target-not-found should carry a diagnostic");
+            assert_eq!(diagnostic.resolver, Some(ResolverState::CacheMiss));
+            assert_eq!(diagnostic.remediation, Some(Remediation::RefreshInstances));
+        }
+        other => panic!("expected target_not_found error, got: {other:?}"),
+    }
+
+    let out_of_range = ClientCommand::SetTone {
+        session_token: session_token.clone(),
+        command_id: "test-2b".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 9999,
+            value: 0.5,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    ws.send(Message::Text(
+        serde_json::to_string(&out_of_range).unwrap().into(),
+    ))
+    .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            code, diagnostic, ..
+        } => {
+            assert!(matches!(code, ErrorCode::InvalidValue));
+            let diagnostic = diagnostic.expect("invalid value should carry a diagnostic");
+            assert_eq!(diagnostic.param_index, Some(9999));
+            assert_eq!(diagnostic.value, Some(0.5));
+            assert_eq!(diagnostic.remediation, Some(Remediation::FixParams));
+        }
+        other => panic!("expected invalid_value error, got: {other:?}"),
+    }
+
+    let mix = ClientCommand::SetFxMix {
+        session_token: session_token.clone(),
+        command_id: "test-3".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        wet: Some(0.0),
+        delta_solo: None,
+    };
+    ws.send(Message::Text(serde_json::to_string(&mix).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            ..
+        } => {
+            assert_eq!(command_id, "test-3");
+            assert_eq!(applied_params.len(), 1);
+            assert_eq!(applied_params[0].index, 257);
+            assert_eq!(applied_params[0].applied, 0.0);
+        }
+        other => panic!("expected fx mix ack, got: {other:?}"),
+    }
+
+    let rotate = ClientCommand::RotateToken {
+        session_token: session_token.clone(),
+    };
+    ws.send(Message::Text(serde_json::to_string(&rotate).unwrap().into()))
+        .unwrap();
+    let rotated = match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::TokenRotated { session_token: t, .. } => t,
+        other => panic!("expected token_rotated, got: {other:?}"),
+    };
+    assert_ne!(rotated, session_token);
+
+    let mix = ClientCommand::SetFxMix {
+        session_token: rotated.clone(),
+        command_id: "test-4".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        wet: Some(1.0),
+        delta_solo: None,
+    };
+    ws.send(Message::Text(serde_json::to_string(&mix).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "test-4"),
+        other => panic!("expected ack with rotated token, got: {other:?}"),
+    }
+
+    let req = ClientCommand::GetMetrics {
+        session_token: rotated.clone(),
+    };
+    ws.send(Message::Text(serde_json::to_string(&req).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Metrics { metrics } => {
+            assert!(metrics.commands_applied >= 2);
+            assert!(metrics.commands_rejected >= 1);
+            assert!(metrics.probes >= 1);
+            assert!(metrics.commands_received > metrics.commands_applied);
+        }
+        other => panic!("expected metrics, got: {other:?}"),
+    }
+
+    let req = ClientCommand::GetCurrentTone {
+        session_token: rotated.clone(),
+        command_id: "test-5".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+    };
+    ws.send(Message::Text(serde_json::to_string(&req).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::CurrentTone {
+            command_id, params, ..
+        } => {
+            assert_eq!(command_id, "test-5");
+            let gain = params
+                .iter()
+                .find(|p| p.index == 30)
+                .expect("param 30 read back");
+            assert!((gain.value - 0.42).abs() < 1e-9);
+            assert_eq!(gain.formatted, "0.420");
+            // The REAPER-side wet control sits past the tone params and is not reported.
+            assert!(params.iter().all(|p| p.index <= 118));
+        }
+        other => panic!("expected current_tone, got: {other:?}"),
+    }
+
+    let undo = ClientCommand::UndoLastTone {
+        session_token: rotated.clone(),
+        command_id: "test-6".to_string(),
+    };
+    ws.send(Message::Text(serde_json::to_string(&undo).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "test-6"),
+        other => panic!("expected undo ack, got: {other:?}"),
+    }
+    {
+        let params = api.params.lock().unwrap();
+        assert_eq!(params.get(&30), None, "undo should revert the tone write");
+        // SetFxMix is not part of the tone's undo point.
+        assert_eq!(params.get(&257), Some(&1.0));
+    }
+
+    let undo = ClientCommand::UndoLastTone {
+        session_token: rotated,
+        command_id: "test-7".to_string(),
+    };
+    ws.send(Message::Text(serde_json::to_string(&undo).unwrap().into()))
+        .unwrap();
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::InvalidCommand)),
+        other => panic!("expected nothing-to-undo error, got: {other:?}"),
+    }
+
     let bad = ClientCommand::RefreshInstances {
         session_token: "WRONG".to_string(),
+        instances_only: false,
     };
     ws.send(Message::Text(serde_json::to_string(&bad).unwrap().into()))
         .unwrap();
@@ -240,3 +806,3741 @@ fn ws_handshake_set_tone_and_unauthorized() {
 
     net.shutdown();
 }
+
+fn connect_client(addr: std::net::SocketAddr) -> tungstenite::WebSocket<TcpStream> {
+    let stream = TcpStream::connect(addr).expect("tcp connect");
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(50)));
+    let _ = stream.set_write_timeout(Some(Duration::from_millis(200)));
+    let (ws, _) = tungstenite::client(format!("ws://{addr}"), stream).expect("ws connect");
+    ws
+}
+
+fn send_command<S: Read + Write>(ws: &mut tungstenite::WebSocket<S>, cmd: &ClientCommand) {
+    ws.send(Message::Text(serde_json::to_string(cmd).unwrap().into()))
+        .unwrap();
+}
+
+fn handshake_token(msg: ServerMessage) -> String {
+    match msg {
+        ServerMessage::Handshake { session_token, .. } => session_token,
+        other => panic!("expected handshake, got: {other:?}"),
+    }
+}
+
+#[test]
+fn ws_two_clients_keep_separate_sessions() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ui = connect_client(addr);
+    let ui_token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ui,
+        Duration::from_secs(2),
+    ));
+    let mut cli = connect_client(addr);
+    let cli_token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut cli,
+        Duration::from_secs(2),
+    ));
+    assert_ne!(ui_token, cli_token, "each client gets its own token");
+
+    // A token is only good on the connection it was issued to.
+    let stolen = ClientCommand::RefreshInstances {
+        session_token: ui_token.clone(),
+        instances_only: false,
+    };
+    send_command(&mut cli, &stolen);
+    match read_server_message(&mut cli, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::Unauthorized)),
+        other => panic!("expected unauthorized error, got: {other:?}"),
+    }
+
+    // Replies go to the client that sent the command.
+    let cmd = ClientCommand::SetTone {
+        session_token: cli_token.clone(),
+        command_id: "cli-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.7,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut cli, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut cli, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "cli-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+
+    // A refresh from one client re-sends the handshake to every client, under its own token.
+    let refresh = ClientCommand::RefreshInstances {
+        session_token: ui_token.clone(),
+        instances_only: false,
+    };
+    send_command(&mut ui, &refresh);
+    assert_eq!(
+        handshake_token(tick_until_message(
+            &mut main_loop,
+            &api,
+            &mut ui,
+            Duration::from_secs(2),
+        )),
+        ui_token
+    );
+    assert_eq!(
+        handshake_token(read_server_message(&mut cli, Duration::from_secs(2))),
+        cli_token
+    );
+
+    // The UI going away leaves the CLI's session usable.
+    let _ = ui.close(None);
+    drop(ui);
+    let read = ClientCommand::GetCurrentTone {
+        session_token: cli_token,
+        command_id: "cli-2".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+    };
+    send_command(&mut cli, &read);
+    match tick_until_message(&mut main_loop, &api, &mut cli, Duration::from_secs(2)) {
+        ServerMessage::CurrentTone { command_id, .. } => assert_eq!(command_id, "cli-2"),
+        other => panic!("expected current_tone, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_resume_picks_up_the_dropped_session() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // The last client dropping takes the probe results down with it.
+    let _ = ws.close(None);
+    drop(ws);
+    for _ in 0..20 {
+        main_loop.tick(&api);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // Resumed first thing, the new connection gets no handshake and goes on under the old token.
+    let mut ws = connect_client(addr);
+    send_command(
+        &mut ws,
+        &ClientCommand::Resume {
+            previous_token: token.clone(),
+        },
+    );
+    std::thread::sleep(Duration::from_millis(100));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Resumed {
+            session_token,
+            instances,
+        } => {
+            assert_eq!(session_token, token);
+            assert_eq!(instances.len(), 1);
+        }
+        other => panic!("expected resumed, got: {other:?}"),
+    }
+
+    // Still ready: a tone goes in without another handshake.
+    let cmd = ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: "resumed-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.7,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "resumed-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+
+    // A session is only resumed once.
+    let mut other = connect_client(addr);
+    send_command(
+        &mut other,
+        &ClientCommand::Resume {
+            previous_token: token,
+        },
+    );
+    match read_server_message(&mut other, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::Unauthorized)),
+        other => panic!("expected unauthorized error, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_save_list_and_load_presets() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let dir = std::env::temp_dir().join(format!("gojira-presets-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.25);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_preset_dir(dir.clone());
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    let fx_guid = "{MOCK-FX-GUID}".to_string();
+
+    let save = ClientCommand::SavePreset {
+        session_token: token.clone(),
+        command_id: "preset-1".to_string(),
+        target_fx_guid: fx_guid.clone(),
+        name: "Djent Rhythm".to_string(),
+    };
+    send_command(&mut ws, &save);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "preset-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+
+    let list = ClientCommand::ListPresets {
+        session_token: token.clone(),
+        command_id: "preset-2".to_string(),
+        target_fx_guid: fx_guid.clone(),
+    };
+    send_command(&mut ws, &list);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::PresetList {
+            saved,
+            current_native,
+            ..
+        } => {
+            assert_eq!(saved, vec!["Djent Rhythm".to_string()]);
+            assert_eq!(current_native, None);
+        }
+        other => panic!("expected preset_list, got: {other:?}"),
+    }
+
+    // Loading a saved preset writes its values back.
+    api.params.lock().unwrap().insert(30, 0.9);
+    let load = ClientCommand::LoadPreset {
+        session_token: token.clone(),
+        command_id: "preset-3".to_string(),
+        target_fx_guid: fx_guid.clone(),
+        name: "Djent Rhythm".to_string(),
+    };
+    send_command(&mut ws, &load);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { applied_params, .. } => {
+            assert!(applied_params.iter().any(|p| p.index == 30));
+        }
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.25));
+
+    // Names we never saved fall through to the plugin's own presets.
+    let native = ClientCommand::LoadPreset {
+        session_token: token.clone(),
+        command_id: "preset-4".to_string(),
+        target_fx_guid: fx_guid.clone(),
+        name: "Factory Clean".to_string(),
+    };
+    send_command(&mut ws, &native);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "preset-4"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+
+    let unknown = ClientCommand::LoadPreset {
+        session_token: token.clone(),
+        command_id: "preset-5".to_string(),
+        target_fx_guid: fx_guid.clone(),
+        name: "Nope".to_string(),
+    };
+    send_command(&mut ws, &unknown);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::InvalidValue)),
+        other => panic!("expected invalid_value error, got: {other:?}"),
+    }
+
+    let bad_name = ClientCommand::SavePreset {
+        session_token: token,
+        command_id: "preset-6".to_string(),
+        target_fx_guid: fx_guid,
+        name: "../escape".to_string(),
+    };
+    send_command(&mut ws, &bad_name);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::InvalidValue)),
+        other => panic!("expected invalid_value error, got: {other:?}"),
+    }
+
+    net.shutdown();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn ws_tone_slots_are_stored_listed_and_activated() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let store = |slot: u32, name: &str, value: f64| ClientCommand::StoreToneSlot {
+        session_token: token.clone(),
+        command_id: format!("store-{slot}"),
+        slot,
+        name: name.to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+    };
+    let activate = |command_id: &str, slot: u32| ClientCommand::ActivateToneSlot {
+        session_token: token.clone(),
+        command_id: command_id.to_string(),
+        slot,
+        ramp_ms: None,
+        apply_at: None,
+    };
+
+    for cmd in [store(1, "Rhythm", 0.3), store(2, "Lead", 0.9)] {
+        send_command(&mut ws, &cmd);
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::Ack { .. } => {}
+            other => panic!("expected ack for the stored slot, got: {other:?}"),
+        }
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), None);
+
+    send_command(&mut ws, &activate("go-2", 2));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            ..
+        } => {
+            assert_eq!(command_id, "go-2");
+            assert_eq!(applied_params.len(), 1);
+        }
+        other => panic!("expected ack for the activated slot, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.9));
+
+    let list = ClientCommand::ListToneSlots {
+        session_token: token.clone(),
+        command_id: "slots".to_string(),
+    };
+    send_command(&mut ws, &list);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ToneSlots {
+            slots, active_slot, ..
+        } => {
+            let names: Vec<(u32, &str)> = slots.iter().map(|s| (s.slot, s.name.as_str())).collect();
+            assert_eq!(names, vec![(1, "Rhythm"), (2, "Lead")]);
+            assert_eq!(active_slot, Some(2));
+        }
+        other => panic!("expected tone_slots, got: {other:?}"),
+    }
+
+    send_command(&mut ws, &activate("go-7", 7));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::InvalidValue)),
+        other => panic!("expected an error for an empty slot, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_midi_program_change_activates_a_slot_with_no_client() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (_in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, _out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+    let (midi_tx, midi_rx) = bounded(16);
+
+    // Slots stored by an earlier session, saved with the project.
+    let api = MockReaperApi::new();
+    api.set_proj_ext_state(
+        "gojira_tone_slots",
+        "slots",
+        r#"{"3":{"name":"Lead","target_fx_guid":"{MOCK-FX-GUID}","mode":"merge","params":[{"index":30,"value":0.7}]}}"#,
+    );
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_midi_input(
+        midi_rx,
+        MidiMap {
+            channel: Some(1),
+            slot_cc: Some(80),
+        },
+    );
+
+    // Wrong channel, then a CC the map doesn't use.
+    midi_tx.send([0xC1, 3, 0]).unwrap();
+    midi_tx.send([0xB0, 7, 3]).unwrap();
+    main_loop.tick(&api);
+    assert_eq!(api.params.lock().unwrap().get(&30), None);
+
+    midi_tx.send([0xC0, 3, 0]).unwrap();
+    main_loop.tick(&api);
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.7));
+
+    // Nothing stored in slot 5: the tone stays.
+    midi_tx.send([0xB0, 80, 5]).unwrap();
+    main_loop.tick(&api);
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.7));
+}
+
+#[test]
+fn ws_client_connecting_late_gets_the_replay() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+    let (midi_tx, midi_rx) = bounded(16);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.set_proj_ext_state(
+        "gojira_tone_slots",
+        "slots",
+        r#"{"3":{"name":"Lead","target_fx_guid":"{MOCK-FX-GUID}","mode":"merge","params":[{"index":30,"value":0.7}]}}"#,
+    );
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_midi_input(midi_rx, MidiMap::default());
+
+    // One switch that lands and one to an empty slot, with nobody connected to hear either.
+    midi_tx.send([0xC0, 3, 0]).unwrap();
+    main_loop.tick(&api);
+    midi_tx.send([0xC0, 5, 0]).unwrap();
+    main_loop.tick(&api);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    let events = match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Replay { events } => events,
+        other => panic!("expected a replay after the handshake, got: {other:?}"),
+    };
+    // Oldest first: the ack, then the error.
+    let replies: Vec<(&str, bool)> = events
+        .iter()
+        .filter_map(|e| match &e.message {
+            ServerMessage::Ack { command_id, .. } => Some((command_id.as_str(), true)),
+            ServerMessage::Error { command_id, .. } => Some((command_id.as_deref()?, false)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        replies,
+        [("midi-slot-3", true), ("midi-slot-5", false)],
+        "{events:?}"
+    );
+    assert!(events.windows(2).all(|w| w[0].age_ms >= w[1].age_ms));
+
+    // Replayed once: a ping now gets its pong straight back.
+    send_command(
+        &mut ws,
+        &ClientCommand::Ping {
+            session_token: token,
+            nonce: 9,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Pong { nonce: 9, .. } => {}
+        other => panic!("expected a pong, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_reaper_actions_step_slots_and_revert() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    for (slot, value) in [(1, 0.3), (4, 0.9)] {
+        let cmd = ClientCommand::StoreToneSlot {
+            session_token: token.clone(),
+            command_id: format!("store-{slot}"),
+            slot,
+            name: String::new(),
+            target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+            mode: MergeMode::Merge,
+            params: vec![ParamChange {
+                index: 30,
+                value,
+                op: ParamOp::Set,
+            }],
+            locked_params: Vec::new(),
+        };
+        send_command(&mut ws, &cmd);
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::Ack { .. } => {}
+            other => panic!("expected ack for the stored slot, got: {other:?}"),
+        }
+    }
+
+    // From no active slot to the first, then along and around.
+    for expected in [0.3, 0.9, 0.3] {
+        main_loop.run_action(&api, ReaperAction::NextToneSlot);
+        main_loop.tick(&api);
+        assert_eq!(api.params.lock().unwrap().get(&30), Some(&expected));
+    }
+
+    main_loop.run_action(&api, ReaperAction::RevertLastTone);
+    main_loop.tick(&api);
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.9));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_show_status_action_prints_to_the_console() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    main_loop.run_action(&api, ReaperAction::ShowStatus);
+    let console = std::mem::take(&mut *api.console.lock().unwrap());
+    assert!(console.contains("clients: none connected"), "{console}");
+    assert!(console.contains("last command: none"), "{console}");
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    let cmd = ClientCommand::ActivateToneSlot {
+        session_token: token,
+        command_id: "go-7".to_string(),
+        slot: 7,
+        ramp_ms: None,
+        apply_at: None,
+    };
+    send_command(&mut ws, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { .. } => {}
+        other => panic!("expected an error for an empty slot, got: {other:?}"),
+    }
+
+    main_loop.run_action(&api, ReaperAction::ShowStatus);
+    let console = api.console.lock().unwrap().clone();
+    assert!(console.contains("clients: 127.0.0.1:"), "{console}");
+    assert!(console.contains("instance: "), "{console}");
+    assert!(!console.contains("none found"), "{console}");
+    assert!(
+        console.contains("last command: activate_tone_slot go-7 from 127.0.0.1:"),
+        "{console}"
+    );
+    assert!(
+        console.contains("last error: go-7: no tone stored in slot 7"),
+        "{console}"
+    );
+
+    net.shutdown();
+}
+
+/// Appends an OSC string: nul-terminated, padded to 4 bytes.
+fn osc_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.resize(out.len() + 4 - s.len() % 4, 0);
+}
+
+/// Strings of an OSC reply (address first), skipping any other arg.
+fn osc_strings(packet: &[u8]) -> Vec<String> {
+    packet
+        .split(|&b| b == 0)
+        .filter(|s| !s.is_empty() && !s.starts_with(b","))
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect()
+}
+
+fn tick_until_osc(main_loop: &mut MainLoop, api: &MockReaperApi, osc: &UdpSocket) -> Vec<String> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut buf = [0u8; 1024];
+    while Instant::now() < deadline {
+        main_loop.tick(api);
+        if let Ok(len) = osc.recv(&mut buf) {
+            return osc_strings(&buf[..len]);
+        }
+    }
+    panic!("no OSC reply");
+}
+
+#[test]
+fn ws_osc_messages_drive_the_same_commands() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_osc("127.0.0.1:0", "127.0.0.1:0", in_tx, out_rx)
+        .expect("spawn net");
+    let osc_addr = net.osc_addr().expect("osc endpoint");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let osc = UdpSocket::bind("127.0.0.1:0").expect("bind osc client");
+    osc.connect(osc_addr).expect("connect osc client");
+    osc.set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+
+    // /gojira/settone ,sif "{MOCK-FX-GUID}" 30 0.5
+    let mut settone = Vec::new();
+    osc_str(&mut settone, "/gojira/settone");
+    osc_str(&mut settone, ",sif");
+    osc_str(&mut settone, "{MOCK-FX-GUID}");
+    settone.extend_from_slice(&30i32.to_be_bytes());
+    settone.extend_from_slice(&0.5f32.to_be_bytes());
+    osc.send(&settone).unwrap();
+    assert_eq!(
+        tick_until_osc(&mut main_loop, &api, &osc),
+        vec!["/gojira/ack", "osc-1"]
+    );
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.5));
+
+    // A button's release does nothing; its press finds slot 9 empty.
+    let mut release = Vec::new();
+    osc_str(&mut release, "/gojira/slot/9");
+    osc_str(&mut release, ",f");
+    release.extend_from_slice(&0.0f32.to_be_bytes());
+    osc.send(&release).unwrap();
+    let mut press = Vec::new();
+    osc_str(&mut press, "/gojira/slot/9");
+    osc_str(&mut press, ",");
+    osc.send(&press).unwrap();
+    let reply = tick_until_osc(&mut main_loop, &api, &osc);
+    assert_eq!(reply[..2], ["/gojira/error", "osc-3"]);
+    assert!(reply[2].contains("slot 9"), "{reply:?}");
+
+    let mut unknown = Vec::new();
+    osc_str(&mut unknown, "/gojira/explode");
+    osc.send(&unknown).unwrap();
+    let reply = tick_until_osc(&mut main_loop, &api, &osc);
+    assert_eq!(reply[..2], ["/gojira/error", "osc-4"]);
+
+    // Only the fixed verbs: no way to smuggle an arbitrary command in past the pairing key.
+    let mut passthrough = Vec::new();
+    osc_str(&mut passthrough, "/gojira/command");
+    osc_str(&mut passthrough, ",s");
+    osc_str(
+        &mut passthrough,
+        r#"{"type":"undo_last_tone","command_id":"x"}"#,
+    );
+    osc.send(&passthrough).unwrap();
+    let reply = tick_until_osc(&mut main_loop, &api, &osc);
+    assert_eq!(reply[..2], ["/gojira/error", "osc-5"]);
+    assert!(reply[2].contains("unknown OSC address"), "{reply:?}");
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_restore_snapshot_taken_before_set_tone() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.25);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: "tone-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.8,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "tone-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.8));
+
+    let restore = ClientCommand::RestoreSnapshot {
+        session_token: token.clone(),
+        command_id: "restore-1".to_string(),
+        snapshot_id: "tone-1".to_string(),
+    };
+    send_command(&mut ws, &restore);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "restore-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.25));
+
+    let unknown = ClientCommand::RestoreSnapshot {
+        session_token: token,
+        command_id: "restore-2".to_string(),
+        snapshot_id: "tone-404".to_string(),
+    };
+    send_command(&mut ws, &unknown);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::InvalidValue)),
+        other => panic!("expected invalid_value error, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_rejected_param_is_reported_or_rolls_back_an_atomic_tone() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.rejected.lock().unwrap().insert(31);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let tone = |command_id: &str, indices: &[i32], atomic: bool| ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: command_id.to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: indices
+            .iter()
+            .map(|&index| ParamChange {
+                index,
+                value: 0.6,
+                op: ParamOp::Set,
+            })
+            .collect(),
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+
+    send_command(&mut ws, &tone("partial-1", &[30, 31, 32], false));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            failed_params,
+            ..
+        } => {
+            assert_eq!(command_id, "partial-1");
+            let applied: Vec<i32> = applied_params.iter().map(|p| p.index).collect();
+            assert_eq!(applied, vec![30, 32]);
+            assert_eq!(failed_params.len(), 1);
+            assert_eq!(failed_params[0].index, 31);
+            assert!((failed_params[0].value - 0.6).abs() < 1e-9);
+        }
+        other => panic!("expected partial ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&32), Some(&0.6));
+
+    send_command(&mut ws, &tone("partial-2", &[31], false));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            code, diagnostic, ..
+        } => {
+            assert!(matches!(code, ErrorCode::InternalError));
+            assert_eq!(diagnostic.and_then(|d| d.param_index), Some(31));
+        }
+        other => panic!("expected an error when nothing was written, got: {other:?}"),
+    }
+
+    // Atomic: the refused write takes the ones around it back to where they were.
+    api.params.lock().unwrap().insert(30, 0.2);
+    send_command(&mut ws, &tone("atomic-1", &[30, 31, 32], true));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            msg, diagnostic, ..
+        } => {
+            assert!(msg.contains("rolled back"), "{msg}");
+            assert_eq!(diagnostic.and_then(|d| d.param_index), Some(31));
+        }
+        other => panic!("expected an atomic rollback error, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.2));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_unreadable_param_is_flagged_and_left_out_of_the_ack() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.unreadable.lock().unwrap().insert(32);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    send_command(
+        &mut ws,
+        &ClientCommand::SetTone {
+            session_token: token,
+            command_id: "unread-1".to_string(),
+            target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+            mode: MergeMode::Merge,
+            params: [30, 32]
+                .into_iter()
+                .map(|index| ParamChange {
+                    index,
+                    value: 0.6,
+                    op: ParamOp::Set,
+                })
+                .collect(),
+            locked_params: Vec::new(),
+            ramp_ms: None,
+            atomic: false,
+            apply_at: None,
+            modules: None,
+            allow_background_projects: false,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Warning { code, context, .. } => {
+            assert!(matches!(code, WarningCode::ReadbackUnavailable));
+            assert_eq!(context.as_deref(), Some("unread-1: 32"));
+        }
+        other => panic!("expected a readback warning, got: {other:?}"),
+    }
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            failed_params,
+            ..
+        } => {
+            assert_eq!(command_id, "unread-1");
+            let applied: Vec<i32> = applied_params.iter().map(|p| p.index).collect();
+            assert_eq!(applied, vec![30]);
+            assert!(failed_params.is_empty());
+        }
+        other => panic!("expected an ack, got: {other:?}"),
+    }
+    // Written all the same.
+    assert_eq!(api.params.lock().unwrap().get(&32), Some(&0.6));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_subscription_pushes_gui_param_changes() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.25);
+    api.params.lock().unwrap().insert(31, 0.5);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let subscribe = ClientCommand::Subscribe {
+        session_token: token,
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        indices: vec![30],
+    };
+    send_command(&mut ws, &subscribe);
+    // Let the subscription land and the first poll record its baseline.
+    let settle = Instant::now() + Duration::from_millis(300);
+    while Instant::now() < settle {
+        main_loop.tick(&api);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // A knob turned in the plugin GUI; 31 is not subscribed.
+    api.params.lock().unwrap().insert(30, 0.75);
+    api.params.lock().unwrap().insert(31, 0.9);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ParamChanged {
+            target_fx_guid,
+            params,
+        } => {
+            assert_eq!(target_fx_guid, "{MOCK-FX-GUID}");
+            assert_eq!(params.len(), 1);
+            assert_eq!(params[0].index, 30);
+            assert!((params[0].value - 0.75).abs() < 1e-9);
+        }
+        other => panic!("expected param_changed, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_set_tone_multi_reports_each_target() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let target = |guid: &str, value: f64| ToneTarget {
+        target_fx_guid: guid.to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+    };
+    let cmd = ClientCommand::SetToneMulti {
+        session_token: token,
+        command_id: "multi-1".to_string(),
+        targets: vec![
+            target("{MOCK-FX-GUID}", 0.6),
+            target("{NOT-A-REAL-GUID}", 0.6),
+        ],
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::MultiAck {
+            command_id,
+            results,
+        } => {
+            assert_eq!(command_id, "multi-1");
+            assert_eq!(results.len(), 2);
+            assert_eq!(results[0].target_fx_guid, "{MOCK-FX-GUID}");
+            assert!(results[0].error.is_none());
+            assert_eq!(results[0].applied_params.len(), 1);
+            assert_eq!(results[1].target_fx_guid, "{NOT-A-REAL-GUID}");
+            assert!(results[1].error.is_some());
+            assert!(results[1].applied_params.is_empty());
+        }
+        other => panic!("expected multi_ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.6));
+    // Both targets went out as one undo point.
+    assert_eq!(api.undo.lock().unwrap().len(), 1);
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_set_tone_ramp_glides_before_ack() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.0);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "ramp-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 1.0,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: Some(200),
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+
+    let sent = Instant::now();
+    let mut midway = Vec::new();
+    let ack = loop {
+        main_loop.tick(&api);
+        let v = api.params.lock().unwrap().get(&30).copied().unwrap_or(0.0);
+        if v > 0.0 && v < 1.0 {
+            midway.push(v);
+        }
+        match ws.read() {
+            Ok(Message::Text(s)) => {
+                if let Some(msg) = parse_server(&s) {
+                    break msg;
+                }
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => panic!("ws read failed: {e:?}"),
+        }
+        if sent.elapsed() > Duration::from_secs(2) {
+            panic!("timeout waiting for ack");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    match ack {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "ramp-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert!(sent.elapsed() >= Duration::from_millis(200));
+    assert!(!midway.is_empty(), "param should pass in-between values");
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&1.0));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_apply_queue_is_rate_limited_and_bounded() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_max_apply_rate(1.0);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // q-0 applies at once, the next 64 fill the queue behind it and q-65 is turned away.
+    for n in 0..=65 {
+        let cmd = ClientCommand::SetTone {
+            session_token: token.clone(),
+            command_id: format!("q-{n}"),
+            target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+            mode: MergeMode::Merge,
+            params: vec![ParamChange {
+                index: 30,
+                value: n as f64 / 100.0,
+                op: ParamOp::Set,
+            }],
+            locked_params: Vec::new(),
+            ramp_ms: None,
+            atomic: false,
+            apply_at: None,
+            modules: None,
+            allow_background_projects: false,
+        };
+        send_command(&mut ws, &cmd);
+    }
+
+    let mut acked = Vec::new();
+    let mut busy = Vec::new();
+    while acked.len() < 2 || busy.is_empty() {
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(3)) {
+            ServerMessage::Ack { command_id, .. } => acked.push(command_id),
+            ServerMessage::Error {
+                code: ErrorCode::Busy,
+                command_id,
+                diagnostic,
+                ..
+            } => {
+                let diagnostic = diagnostic.expect("busy should carry a diagnostic");
+                assert_eq!(diagnostic.remediation, Some(Remediation::Retry));
+                busy.push(command_id.expect("busy should name the command"));
+            }
+            other => panic!("expected ack or busy, got: {other:?}"),
+        }
+    }
+    assert_eq!(acked, ["q-0", "q-1"]);
+    assert_eq!(busy, ["q-65"]);
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.01));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_merge_reports_untouched_modules() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(101, 1.0);
+    api.params.lock().unwrap().insert(112, 0.0);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "merge-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![
+            ParamChange {
+                index: 13,
+                value: 1.0,
+                op: ParamOp::Set,
+            },
+            ParamChange {
+                index: 14,
+                value: 0.6,
+                op: ParamOp::Set,
+            },
+        ],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            skipped_modules,
+            ..
+        } => {
+            assert_eq!(command_id, "merge-1");
+            assert_eq!(applied_params.len(), 2);
+            let active = |name: &str| {
+                skipped_modules
+                    .iter()
+                    .find(|m| m.name == name)
+                    .unwrap_or_else(|| panic!("{name} should be reported as skipped"))
+                    .active
+            };
+            assert!(!skipped_modules.iter().any(|m| m.name == "overdrive"));
+            assert_eq!(active("delay"), Some(true));
+            assert_eq!(active("reverb"), Some(false));
+            assert_eq!(active("chorus"), None);
+        }
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    // Untouched toggles are only read, never written.
+    assert_eq!(api.params.lock().unwrap().get(&101), Some(&1.0));
+    assert_eq!(api.params.lock().unwrap().get(&112), Some(&0.0));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_replace_active_keeps_locked_params_and_their_modules() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    for (index, value) in [(23, 1.0), (101, 1.0), (112, 1.0), (114, 0.4)] {
+        api.params.lock().unwrap().insert(index, value);
+    }
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // Reverb mix and the chorus switch are pinned; the tone still asks for the mix.
+    let cmd = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "locked-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::ReplaceActive,
+        params: vec![
+            ParamChange {
+                index: 14,
+                value: 0.6,
+                op: ParamOp::Set,
+            },
+            ParamChange {
+                index: 114,
+                value: 0.9,
+                op: ParamOp::Set,
+            },
+        ],
+        locked_params: vec![114, 23],
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            ..
+        } => {
+            assert_eq!(command_id, "locked-1");
+            let applied: Vec<i32> = applied_params.iter().map(|p| p.index).collect();
+            assert!(applied.contains(&14), "{applied:?}");
+            assert!(!applied.contains(&114), "{applied:?}");
+            assert!(!applied.contains(&23), "{applied:?}");
+        }
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    let params = api.params.lock().unwrap();
+    assert_eq!(params.get(&114), Some(&0.4), "locked param written");
+    // Reverb and chorus each hold a locked param, so neither is switched off; delay is.
+    assert_eq!(params.get(&112), Some(&1.0));
+    assert_eq!(params.get(&23), Some(&1.0));
+    assert_eq!(params.get(&101), Some(&0.0));
+    drop(params);
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_set_tone_limited_to_modules_writes_only_theirs() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(14, 0.2);
+    api.params.lock().unwrap().insert(101, 1.0);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "cab-only".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::ReplaceActive,
+        params: vec![
+            ParamChange {
+                index: 14,
+                value: 0.6,
+                op: ParamOp::Set,
+            },
+            ParamChange {
+                index: 84,
+                value: 0.3,
+                op: ParamOp::Set,
+            },
+            ParamChange {
+                index: 89,
+                value: 0.5,
+                op: ParamOp::Set,
+            },
+        ],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: Some(vec![ModuleId::Cab]),
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            ..
+        } => {
+            assert_eq!(command_id, "cab-only");
+            let mut written: Vec<i32> = applied_params.iter().map(|p| p.index).collect();
+            written.sort();
+            // The cleaner's bypasses for the untouched pedals are dropped with the overdrive.
+            assert_eq!(written, vec![84, 89]);
+        }
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&14), Some(&0.2));
+    assert_eq!(api.params.lock().unwrap().get(&101), Some(&1.0));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_set_tone_adds_to_live_values() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.5);
+    api.params.lock().unwrap().insert(14, 0.2);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let add = |index: i32, value: f64| ParamChange {
+        index,
+        value,
+        op: ParamOp::Add,
+    };
+    let cmd = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "nudge".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        // The second add to 30 builds on the first; 14 bottoms out at 0.
+        params: vec![add(30, 0.1), add(30, 0.1), add(14, -0.5)],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack {
+            command_id,
+            applied_params,
+            ..
+        } => {
+            assert_eq!(command_id, "nudge");
+            let requested = |index: i32| {
+                applied_params
+                    .iter()
+                    .find(|p| p.index == index)
+                    .map(|p| p.requested)
+            };
+            assert!((requested(30).unwrap() - 0.7).abs() < 1e-9);
+            assert_eq!(requested(14), Some(0.0));
+        }
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert!((api.params.lock().unwrap()[&30] - 0.7).abs() < 1e-9);
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_preview_tone_reports_without_writing() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.25);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::PreviewTone {
+        session_token: token,
+        command_id: "preview-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::ReplaceActive,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.75,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Preview {
+            command_id, rows, ..
+        } => {
+            assert_eq!(command_id, "preview-1");
+            let row = rows.iter().find(|r| r.index == 30).expect("row for #30");
+            assert_eq!(row.before, Some(0.25));
+            assert_eq!(row.before_formatted, "0.250");
+            assert_eq!(row.after_formatted, "0.750");
+            // The cleaner's bypasses show up too, as they would be written.
+            assert!(rows.iter().any(|r| r.index == 101 && r.after == 0.0));
+        }
+        other => panic!("expected preview, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.25));
+    assert!(!api.params.lock().unwrap().contains_key(&101));
+    assert!(api.undo.lock().unwrap().is_empty());
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_validate_tone_warns_without_writing() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let params = [
+        (30, 0.2),
+        (30, 1.5),
+        (256, 1.0),
+        (9999, 0.5),
+        (101, 0.0),
+        (105, 0.4),
+    ]
+    .into_iter()
+    .map(|(index, value)| ParamChange {
+        index,
+        value,
+        op: ParamOp::Set,
+    })
+    .collect();
+    let cmd = ClientCommand::ValidateTone {
+        session_token: token,
+        command_id: "validate-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        params,
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ToneValidation {
+            command_id,
+            warnings,
+            ..
+        } => {
+            assert_eq!(command_id, "validate-1");
+            let found: Vec<(ToneIssue, i32)> =
+                warnings.iter().map(|w| (w.issue, w.index)).collect();
+            assert_eq!(
+                found,
+                vec![
+                    (ToneIssue::Duplicate, 30),
+                    (ToneIssue::OutOfRange, 30),
+                    (ToneIssue::ReservedParam, 256),
+                    (ToneIssue::UnknownParam, 9999),
+                    // Delay mix is set while the delay stays off.
+                    (ToneIssue::ModuleOff, 101),
+                ]
+            );
+        }
+        other => panic!("expected tone validation, got: {other:?}"),
+    }
+    assert!(api.params.lock().unwrap().is_empty());
+    assert!(api.undo.lock().unwrap().is_empty());
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_track_controls_act_on_the_host_track() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::SetTrackControls {
+        session_token: token,
+        command_id: "track-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        volume_db: Some(-6.0),
+        pan: Some(-0.5),
+        rec_monitor: Some(false),
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::TrackControls {
+            command_id,
+            track_guid,
+            volume_db,
+            pan,
+            rec_monitor,
+            ..
+        } => {
+            assert_eq!(command_id, "track-1");
+            assert_eq!(track_guid, "{MOCK-TRACK-GUID}");
+            assert!((volume_db.expect("volume read back") + 6.0).abs() < 1e-3);
+            assert_eq!(pan, Some(-0.5));
+            assert_eq!(rec_monitor, Some(false));
+        }
+        other => panic!("expected track controls, got: {other:?}"),
+    }
+    {
+        let info = api.track_info.lock().unwrap();
+        assert!((info["D_VOL"] - 0.501).abs() < 1e-3);
+        assert_eq!(info["I_RECMON"], 0.0);
+    }
+    // The plugin's own params are untouched.
+    assert!(api.params.lock().unwrap().is_empty());
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_fx_state_toggles_the_plugin_and_sets_wet() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::SetFxState {
+        session_token: token,
+        command_id: "fx-state-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        enabled: Some(false),
+        wet: Some(0.5),
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::FxState {
+            command_id,
+            enabled,
+            wet,
+            ..
+        } => {
+            assert_eq!(command_id, "fx-state-1");
+            assert!(!enabled);
+            assert_eq!(wet, Some(0.5));
+        }
+        other => panic!("expected fx state, got: {other:?}"),
+    }
+    assert!(api.bypassed.lock().unwrap().contains(&(100, 0)));
+    assert_eq!(api.params.lock().unwrap().get(&257).copied(), Some(0.5));
+
+    net.shutdown();
+}
+
+fn switch_fx_pair(token: &str, command_id: &str, enable: &str, disable: &str) -> ClientCommand {
+    ClientCommand::SwitchFxPair {
+        session_token: token.to_string(),
+        command_id: command_id.to_string(),
+        enable_fx_guid: enable.to_string(),
+        disable_fx_guid: disable.to_string(),
+    }
+}
+
+/// The error answering `command_id`, as (code, message).
+fn expect_error(msg: ServerMessage, expected_id: &str) -> (ErrorCode, String) {
+    match msg {
+        ServerMessage::Error {
+            msg,
+            code,
+            command_id,
+            ..
+        } => {
+            assert_eq!(command_id.as_deref(), Some(expected_id));
+            (code, msg)
+        }
+        other => panic!("expected an error for {expected_id}, got: {other:?}"),
+    }
+}
+
+#[test]
+fn ws_switch_fx_pair_swaps_two_instances_on_a_track() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    *api.fx_count.lock().unwrap() = 2;
+    *api.pair.lock().unwrap() = true;
+    *api.second_track.lock().unwrap() = true;
+    api.bypassed.lock().unwrap().insert((100, 1));
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // B comes on and A goes off.
+    send_command(
+        &mut ws,
+        &switch_fx_pair(&token, "switch-1", "{MOCK-FX-GUID-B}", "{MOCK-FX-GUID}"),
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "switch-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(*api.bypassed.lock().unwrap(), HashSet::from([(100, 0)]));
+
+    let same = switch_fx_pair(&token, "switch-2", "{MOCK-FX-GUID}", "{MOCK-FX-GUID}");
+    send_command(&mut ws, &same);
+    let (code, msg) = expect_error(
+        tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)),
+        "switch-2",
+    );
+    assert!(matches!(code, ErrorCode::InvalidCommand));
+    assert!(msg.contains("same fx"), "{msg}");
+
+    // An instance on another track is still an instance, but not half of this pair.
+    send_command(
+        &mut ws,
+        &switch_fx_pair(&token, "switch-3", "{MOCK-FX-GUID-3}", "{MOCK-FX-GUID-B}"),
+    );
+    let (code, msg) = expect_error(
+        tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)),
+        "switch-3",
+    );
+    assert!(matches!(code, ErrorCode::InvalidCommand));
+    assert!(msg.contains("same track"), "{msg}");
+
+    // Nothing the scan didn't report as a Gojira instance gets switched.
+    send_command(
+        &mut ws,
+        &switch_fx_pair(&token, "switch-4", "{MOCK-FX-GUID}", "{SOME-OTHER-FX}"),
+    );
+    let (code, msg) = expect_error(
+        tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)),
+        "switch-4",
+    );
+    assert!(matches!(code, ErrorCode::InvalidCommand));
+    assert!(msg.contains("{SOME-OTHER-FX}"), "{msg}");
+
+    // None of the refused switches touched anything.
+    assert_eq!(*api.bypassed.lock().unwrap(), HashSet::from([(100, 0)]));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_switch_fx_pair_restores_the_old_instance_when_the_new_one_stays_off() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    *api.fx_count.lock().unwrap() = 2;
+    *api.pair.lock().unwrap() = true;
+    api.bypassed.lock().unwrap().insert((100, 1));
+    api.unloadable.lock().unwrap().insert((100, 1));
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    send_command(
+        &mut ws,
+        &switch_fx_pair(&token, "switch-1", "{MOCK-FX-GUID-B}", "{MOCK-FX-GUID}"),
+    );
+    let (code, msg) = expect_error(
+        tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)),
+        "switch-1",
+    );
+    assert!(matches!(code, ErrorCode::InternalError));
+    assert!(msg.contains("restored"), "{msg}");
+    // The track is never left silent: A is back on, B still off.
+    assert_eq!(*api.bypassed.lock().unwrap(), HashSet::from([(100, 1)]));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_insert_fx_adds_the_plugin_and_sends_a_new_handshake() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    *api.fx_count.lock().unwrap() = 0;
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let handshake = tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2));
+    let ServerMessage::Handshake {
+        ref session_token,
+        ref instances,
+        ..
+    } = handshake
+    else {
+        panic!("expected handshake, got: {handshake:?}");
+    };
+    assert!(instances.is_empty());
+    let token = session_token.clone();
+
+    let missing = ClientCommand::InsertFx {
+        session_token: token.clone(),
+        command_id: "insert-1".to_string(),
+        track_guid: None,
+        fx_name: Some("Archetype Plini".to_string()),
+    };
+    send_command(&mut ws, &missing);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            code, command_id, ..
+        } => {
+            assert!(matches!(code, ErrorCode::InvalidValue));
+            assert_eq!(command_id.as_deref(), Some("insert-1"));
+        }
+        other => panic!("expected error, got: {other:?}"),
+    }
+
+    let insert = ClientCommand::InsertFx {
+        session_token: token,
+        command_id: "insert-2".to_string(),
+        track_guid: Some("{MOCK-TRACK-GUID}".to_string()),
+        fx_name: None,
+    };
+    send_command(&mut ws, &insert);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Handshake { instances, .. } => {
+            assert_eq!(instances.len(), 1);
+            assert_eq!(instances[0].fx_guid, "{MOCK-FX-GUID}");
+        }
+        other => panic!("expected handshake, got: {other:?}"),
+    }
+    let undo = api.undo.lock().unwrap();
+    assert_eq!(undo.last().map(|(desc, _)| desc.as_str()), Some("Gojira: insert plugin"));
+    drop(undo);
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_transport_loops_and_plays() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let bad = ClientCommand::SetLoop {
+        session_token: token.clone(),
+        command_id: "loop-bad".to_string(),
+        start: 6.0,
+        end: 2.0,
+    };
+    send_command(&mut ws, &bad);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error { code, .. } => assert!(matches!(code, ErrorCode::InvalidValue)),
+        other => panic!("expected error, got: {other:?}"),
+    }
+    assert!(api.loop_range.lock().unwrap().is_none());
+
+    let cmds = [
+        ClientCommand::SetLoop {
+            session_token: token.clone(),
+            command_id: "loop-1".to_string(),
+            start: 2.0,
+            end: 6.0,
+        },
+        ClientCommand::Play {
+            session_token: token.clone(),
+            command_id: "play-1".to_string(),
+        },
+        ClientCommand::Stop {
+            session_token: token,
+            command_id: "stop-1".to_string(),
+        },
+    ];
+    let mut seen = Vec::new();
+    for cmd in &cmds {
+        send_command(&mut ws, cmd);
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::Transport {
+                command_id,
+                playing,
+                repeat,
+                loop_start,
+                loop_end,
+            } => {
+                assert!(repeat);
+                assert_eq!((loop_start, loop_end), (Some(2.0), Some(6.0)));
+                seen.push((command_id, playing));
+            }
+            other => panic!("expected transport, got: {other:?}"),
+        }
+    }
+    assert_eq!(
+        seen,
+        vec![
+            ("loop-1".to_string(), false),
+            ("play-1".to_string(), true),
+            ("stop-1".to_string(), false),
+        ]
+    );
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_scheduled_set_tone_waits_for_the_transport() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let tone = |command_id: &str, value: f64, apply_at: TransportAnchor| ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: command_id.to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: Some(apply_at),
+        modules: None,
+        allow_background_projects: false,
+    };
+    let hold = |main_loop: &mut MainLoop| {
+        for _ in 0..5 {
+            main_loop.tick(&api);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    // 120 bpm in 4/4: bars start every two seconds.
+    *api.playing.lock().unwrap() = true;
+    *api.play_position.lock().unwrap() = 0.5;
+    send_command(&mut ws, &tone("bar-1", 0.7, TransportAnchor::NextBar));
+    hold(&mut main_loop);
+    assert_eq!(api.params.lock().unwrap().get(&30), None);
+
+    *api.play_position.lock().unwrap() = 2.01;
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "bar-1"),
+        other => panic!("expected ack at the bar line, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.7));
+
+    send_command(&mut ws, &tone("stop-1", 0.3, TransportAnchor::OnStop));
+    hold(&mut main_loop);
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.7));
+
+    *api.playing.lock().unwrap() = false;
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "stop-1"),
+        other => panic!("expected ack on stop, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.3));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_render_preview_bounces_the_loop_to_a_wav() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let dir = std::env::temp_dir().join(format!("gojira-previews-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    *api.loop_range.lock().unwrap() = Some((2.0, 6.0));
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_render_dir(dir.clone());
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let cmd = ClientCommand::RenderPreview {
+        session_token: token,
+        command_id: "render/1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        start: None,
+        length: Some(60.0),
+    };
+    send_command(&mut ws, &cmd);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::PreviewRendered {
+            command_id,
+            path,
+            start,
+            length,
+            ..
+        } => {
+            assert_eq!(command_id, "render/1");
+            // Starts at the loop, capped at 30 s, and the id can't escape the folder.
+            assert_eq!((start, length), (2.0, 30.0));
+            assert_eq!(path, dir.join("preview-render_1.wav").to_string_lossy());
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "2..32");
+        }
+        other => panic!("expected preview_rendered, got: {other:?}"),
+    }
+
+    net.shutdown();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn ws_last_applied_tone_survives_a_restart() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let api = MockReaperApi::new();
+
+    // First session: apply a tone, then REAPER (and the DLL) go away.
+    {
+        let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+        let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+        let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+        let addr = net.listen_addr().expect("tcp listener");
+        let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+        let mut ws = connect_client(addr);
+        let token = handshake_token(tick_until_message(
+            &mut main_loop,
+            &api,
+            &mut ws,
+            Duration::from_secs(2),
+        ));
+        let cmd = ClientCommand::SetTone {
+            session_token: token,
+            command_id: "tone-1".to_string(),
+            target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+            mode: MergeMode::Merge,
+            params: vec![ParamChange {
+                index: 30,
+                value: 0.42,
+                op: ParamOp::Set,
+            }],
+            locked_params: Vec::new(),
+            ramp_ms: None,
+            atomic: false,
+            apply_at: None,
+            modules: None,
+            allow_background_projects: false,
+        };
+        send_command(&mut ws, &cmd);
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "tone-1"),
+            other => panic!("expected ack, got: {other:?}"),
+        }
+        net.shutdown();
+    }
+
+    // Second session: a fresh main loop finds the tone in the project.
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    let req = ClientCommand::GetCurrentTone {
+        session_token: token,
+        command_id: "read-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+    };
+    send_command(&mut ws, &req);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::CurrentTone { last_applied, .. } => {
+            let applied: Vec<(i32, f64)> =
+                last_applied.iter().map(|p| (p.index, p.value)).collect();
+            assert_eq!(applied, vec![(30, 0.42)]);
+        }
+        other => panic!("expected current_tone, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_current_tone_is_cached_until_the_project_changes() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    api.params.lock().unwrap().insert(30, 0.25);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let mut read = |command_id: &str| {
+        let req = ClientCommand::GetCurrentTone {
+            session_token: token.clone(),
+            command_id: command_id.to_string(),
+            target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        };
+        send_command(&mut ws, &req);
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::CurrentTone { params, .. } => {
+                params
+                    .iter()
+                    .find(|p| p.index == 30)
+                    .expect("param 30")
+                    .value
+            }
+            other => panic!("expected current_tone, got: {other:?}"),
+        }
+    };
+
+    assert_eq!(read("read-1"), 0.25);
+    // Nothing bumped the change count, so the first read is reused.
+    api.params.lock().unwrap().insert(30, 0.5);
+    assert_eq!(read("read-2"), 0.25);
+    *api.change_count.lock().unwrap() += 1;
+    assert_eq!(read("read-3"), 0.5);
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_tone_read_sliced_across_ticks_holds_the_queue_behind_it() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    {
+        let mut params = api.params.lock().unwrap();
+        for index in 0..40 {
+            params.insert(index, 0.1);
+        }
+        params.insert(30, 0.25);
+    }
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    // Every tick is over budget at once, so each one takes a single slice.
+    main_loop.set_tick_budget(Duration::ZERO);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let read = ClientCommand::GetCurrentTone {
+        session_token: token.clone(),
+        command_id: "read-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+    };
+    let write = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "write-1".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.75,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &read);
+    send_command(&mut ws, &write);
+
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::CurrentTone { params, .. } => {
+            assert!(params.len() >= 40, "the read spans more than one slice");
+            assert!(params.windows(2).all(|w| w[0].index < w[1].index));
+            let p30 = params.iter().find(|p| p.index == 30).expect("param 30");
+            assert_eq!(p30.value, 0.25, "the SetTone waited for the read");
+        }
+        other => panic!("expected current_tone, got: {other:?}"),
+    }
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "write-1"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_project_changed_carries_instance_deltas() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    *api.track_name.lock().unwrap() = "Rhythm L".to_string();
+    *api.change_count.lock().unwrap() += 1;
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ProjectChanged {
+            added,
+            removed,
+            renamed,
+            ..
+        } => {
+            assert!(added.is_empty());
+            assert!(removed.is_empty());
+            assert_eq!(renamed.len(), 1);
+            assert_eq!(renamed[0].fx_guid, "{MOCK-FX-GUID}");
+            assert_eq!(renamed[0].track_name, "Rhythm L");
+        }
+        other => panic!("expected project_changed, got: {other:?}"),
+    }
+
+    // Still ready: no RefreshInstances needed before the next apply.
+    let cmd = ClientCommand::SetTone {
+        session_token: token,
+        command_id: "after-rename".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.5,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "after-rename"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_confidence_follows_the_signature_param_names() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    *api.param_names.lock().unwrap() = HashMap::from([
+        (2, "Gate Amount".to_string()),
+        (29, "Amp Type".to_string()),
+        (84, "Cab Type".to_string()),
+        (118, "Bypass".to_string()),
+    ]);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Handshake {
+            session_token,
+            instances,
+            ..
+        } => {
+            assert_eq!(instances.len(), 1);
+            assert!(matches!(instances[0].confidence, Confidence::High));
+            session_token
+        }
+        other => panic!("expected handshake, got: {other:?}"),
+    };
+
+    // Same FX name, but something else sits where the amp selector should be.
+    api.param_names
+        .lock()
+        .unwrap()
+        .insert(29, "Chain Slot 2".to_string());
+    send_command(
+        &mut ws,
+        &ClientCommand::RefreshInstances {
+            session_token: token,
+            instances_only: false,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Handshake { instances, .. } => {
+            assert_eq!(instances.len(), 1);
+            assert!(matches!(instances[0].confidence, Confidence::Low));
+        }
+        other => panic!("expected handshake, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_instances_only_refresh_skips_the_handshake() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let (token, fx_guid) =
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::Handshake {
+                session_token,
+                instances,
+                ..
+            } => (session_token, instances[0].fx_guid.clone()),
+            other => panic!("expected handshake, got: {other:?}"),
+        };
+
+    send_command(
+        &mut ws,
+        &ClientCommand::RefreshInstances {
+            session_token: token,
+            instances_only: true,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Instances { instances } => {
+            assert_eq!(instances.len(), 1);
+            assert_eq!(instances[0].fx_guid, fx_guid);
+        }
+        other => panic!("expected instances, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_handshake_against_the_real_param_layout() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::with_gojira_layout();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    let mut ws = connect_client(addr);
+
+    let frames = tick_until_meta_done(&mut main_loop, &api, &mut ws);
+    match &frames[0] {
+        ServerMessage::Handshake {
+            instances, meta, ..
+        } => {
+            assert!(matches!(instances[0].confidence, Confidence::High));
+            assert_eq!(meta.eq_band_hz.get(&54), Some(&63.0));
+            assert_eq!(meta.eq_band_hz.get(&58), Some(&1000.0));
+            assert!(
+                meta.param_remap.is_empty(),
+                "every charted name is where it should be"
+            );
+        }
+        other => panic!("expected handshake first, got: {other:?}"),
+    }
+
+    let mut enums = HashMap::new();
+    let mut formats = HashMap::new();
+    let mut kinds = HashMap::new();
+    for frame in &frames[1..] {
+        if let ServerMessage::ParamMeta {
+            param_enums,
+            param_formats,
+            param_kinds,
+            ..
+        } = frame
+        {
+            enums.extend(param_enums.clone());
+            formats.extend(param_formats.clone());
+            kinds.extend(param_kinds.clone());
+        }
+    }
+    let cab_types: Vec<&str> = enums[&84].iter().map(|o| o.label.as_str()).collect();
+    assert_eq!(cab_types, ["CLN 2x12", "RUST 4x12", "HOT 4x12"]);
+    assert_eq!(formats[&0].min, "-24.0 dB");
+    assert_eq!(kinds.get(&4), Some(&ParamKind::Toggle));
+    assert_eq!(kinds.get(&29), Some(&ParamKind::Stepped { steps: 3 }));
+    assert_eq!(kinds.get(&30), Some(&ParamKind::Continuous));
+    assert!(
+        !kinds.contains_key(&119),
+        "MIDI CC params come after the tone params"
+    );
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_rescan_skips_tracks_whose_fx_fingerprint_is_unchanged() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    tick_until_meta_done(&mut main_loop, &api, &mut ws);
+
+    // An edit that leaves the track's name and FX guids alone rescans without reading FX names.
+    let reads = *api.fx_name_reads.lock().unwrap();
+    *api.change_count.lock().unwrap() += 1;
+    for _ in 0..3 {
+        main_loop.tick(&api);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(*api.fx_name_reads.lock().unwrap(), reads);
+
+    *api.track_name.lock().unwrap() = "Rhythm R".to_string();
+    *api.change_count.lock().unwrap() += 1;
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ProjectChanged { renamed, .. } => {
+            assert_eq!(renamed.len(), 1);
+            assert_eq!(renamed[0].track_name, "Rhythm R");
+        }
+        other => panic!("expected project_changed, got: {other:?}"),
+    }
+    assert!(*api.fx_name_reads.lock().unwrap() > reads);
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_instance_moved_is_pushed_when_a_command_finds_the_fx_elsewhere() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // Dragged down a slot; the change count hasn't moved, so no rescan has seen it yet.
+    *api.fx_count.lock().unwrap() = 2;
+    *api.fx_slot.lock().unwrap() = 1;
+    let cmd = ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: "after-drag".to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.5,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+    send_command(&mut ws, &cmd);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "after-drag"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::InstanceMoved {
+            instance,
+            from_track_guid,
+            from_fx_index,
+        } => {
+            assert_eq!(instance.fx_guid, "{MOCK-FX-GUID}");
+            assert_eq!(instance.last_known_fx_index, 1);
+            assert_eq!(from_track_guid, "{MOCK-TRACK-GUID}");
+            assert_eq!(from_fx_index, 0);
+        }
+        other => panic!("expected instance_moved, got: {other:?}"),
+    }
+
+    // The rescan that follows finds it where the move already put it.
+    *api.change_count.lock().unwrap() += 1;
+    for _ in 0..5 {
+        main_loop.tick(&api);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    send_command(
+        &mut ws,
+        &ClientCommand::Ping {
+            session_token: token,
+            nonce: 7,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Pong { nonce, .. } => assert_eq!(nonce, 7),
+        other => panic!("expected pong, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_project_tab_switch_is_reported_per_project() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // Switching to an empty tab takes the instance away, though no change count moved.
+    *api.current_project.lock().unwrap() = 2;
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ProjectChanged {
+            project_name,
+            added,
+            removed,
+            ..
+        } => {
+            assert_eq!(project_name.as_deref(), Some("mock_project"));
+            assert!(added.is_empty());
+            assert_eq!(removed.len(), 1);
+            assert_eq!(removed[0].fx_guid, "{MOCK-FX-GUID}");
+        }
+        other => panic!("expected project_changed, got: {other:?}"),
+    }
+
+    *api.current_project.lock().unwrap() = 1;
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ProjectChanged {
+            project_name,
+            added,
+            removed,
+            ..
+        } => {
+            assert_eq!(project_name.as_deref(), Some("mock_project"));
+            assert_eq!(added.len(), 1);
+            assert!(removed.is_empty());
+        }
+        other => panic!("expected project_changed, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_background_tab_target_needs_the_opt_in() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    *api.second_tab.lock().unwrap() = true;
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // The user switches to the other tab while the client still targets the first one.
+    *api.current_project.lock().unwrap() = 2;
+    let mut added_second = false;
+    for _ in 0..2 {
+        match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+            ServerMessage::ProjectChanged { added, .. } => {
+                added_second |= added.iter().any(|i| i.fx_guid == "{MOCK-FX-GUID-2}");
+            }
+            other => panic!("expected project_changed, got: {other:?}"),
+        }
+    }
+    assert!(added_second);
+
+    let tone = |command_id: &str, allow_background_projects: bool| ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: command_id.to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.7,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects,
+    };
+
+    send_command(&mut ws, &tone("stay-in-front", false));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            code, diagnostic, ..
+        } => {
+            assert!(matches!(code, ErrorCode::TargetNotFound));
+            let diagnostic = diagnostic.expect("diagnostic");
+            assert_eq!(diagnostic.resolver, Some(ResolverState::BackgroundProject));
+            assert_eq!(
+                diagnostic.remediation,
+                Some(Remediation::AllowBackgroundProjects)
+            );
+        }
+        other => panic!("expected target_not_found error, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), None);
+
+    send_command(&mut ws, &tone("reach-back", true));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Warning { code, context, .. } => {
+            assert!(matches!(code, WarningCode::BackgroundProject));
+            assert_eq!(context.as_deref(), Some("mock_project"));
+        }
+        other => panic!("expected background_project warning, got: {other:?}"),
+    }
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "reach-back"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.7));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_target_changed_since_handshake_is_refused() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // A plugin update keeps the FX guid but shifts the param list under the client.
+    *api.added_params.lock().unwrap() = 4;
+    let tone = |command_id: &str| ClientCommand::SetTone {
+        session_token: token.clone(),
+        command_id: command_id.to_string(),
+        target_fx_guid: "{MOCK-FX-GUID}".to_string(),
+        mode: MergeMode::Merge,
+        params: vec![ParamChange {
+            index: 30,
+            value: 0.7,
+            op: ParamOp::Set,
+        }],
+        locked_params: Vec::new(),
+        ramp_ms: None,
+        atomic: false,
+        apply_at: None,
+        modules: None,
+        allow_background_projects: false,
+    };
+
+    send_command(&mut ws, &tone("stale"));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Error {
+            code,
+            details,
+            diagnostic,
+            ..
+        } => {
+            assert!(matches!(code, ErrorCode::TargetChanged));
+            let details = details.expect("details");
+            assert!(details.contains("param count 256 -> 260"), "{details}");
+            assert_eq!(
+                diagnostic.and_then(|d| d.remediation),
+                Some(Remediation::RefreshInstances)
+            );
+        }
+        other => panic!("expected target_changed error, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), None);
+
+    // After a refresh the new layout is the one the client works from.
+    let refresh = ClientCommand::RefreshInstances {
+        session_token: token.clone(),
+        instances_only: false,
+    };
+    send_command(&mut ws, &refresh);
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    send_command(&mut ws, &tone("fresh"));
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Ack { command_id, .. } => assert_eq!(command_id, "fresh"),
+        other => panic!("expected ack, got: {other:?}"),
+    }
+    assert_eq!(api.params.lock().unwrap().get(&30), Some(&0.7));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_ping_is_answered_by_the_main_loop() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let ping = ClientCommand::Ping {
+        session_token: token,
+        nonce: 42,
+    };
+    send_command(&mut ws, &ping);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Pong { nonce, .. } => assert_eq!(nonce, 42),
+        other => panic!("expected pong, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_hostile_frames_are_rejected_and_the_connection_survives() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let texts = [
+        String::new(),
+        "null".to_string(),
+        "{}".to_string(),
+        r#"{"type":"no_such_command","session_token":"t"}"#.to_string(),
+        r#"{"type":"ping","session_token":"t","nonce":-1}"#.to_string(),
+        r#"{"type":"set_tone","session_token":"t","command_id":"c","target_fx_guid":"f","mode":"merge","params":{}}"#.to_string(),
+        r#"{"type":"handshake_ack","session_token":"\ud800"}"#.to_string(),
+        r#"{"type":"handshake_ack""#.to_string(),
+        "[".repeat(10_000),
+    ];
+    let binaries: [Vec<u8>; 4] = [
+        Vec::new(),
+        vec![0xc1],
+        vec![0xdf, 0xff, 0xff, 0xff, 0xff],
+        vec![0x91; 10_000],
+    ];
+    let frames = texts
+        .into_iter()
+        .map(|t| Message::Text(t.into()))
+        .chain(binaries.into_iter().map(|b| Message::Binary(b.into())));
+    for frame in frames {
+        ws.send(frame).unwrap();
+        match read_server_message(&mut ws, Duration::from_secs(2)) {
+            ServerMessage::Error { code, .. } => {
+                assert!(matches!(code, ErrorCode::InvalidCommand))
+            }
+            other => panic!("expected invalid_command, got: {other:?}"),
+        }
+    }
+
+    send_command(
+        &mut ws,
+        &ClientCommand::Ping {
+            session_token: token,
+            nonce: 9,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Pong { nonce, .. } => assert_eq!(nonce, 9),
+        other => panic!("expected pong, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_diagnostics_report_recent_events_and_timings() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    let get = ClientCommand::GetDiagnostics {
+        session_token: token,
+        limit: Some(512),
+    };
+    send_command(&mut ws, &get);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Diagnostics { events, timings } => {
+            // Other tests log to the same ring, so only look for this connection's events.
+            assert!(events
+                .iter()
+                .any(|e| e.target == "main_loop" && e.msg.contains("connected from")));
+            assert!(events.windows(2).all(|w| w[0].at_ms <= w[1].at_ms));
+            assert!(timings.ticks > 0);
+            assert!(timings.scans >= 1, "the handshake rescans");
+            assert!(timings.max_tick_ms >= timings.avg_tick_ms);
+        }
+        other => panic!("expected diagnostics, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_shutdown_notice_is_sent_before_the_socket_closes() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    // Nothing ticks the main loop after this, like REAPER unloading the DLL.
+    main_loop.announce_shutdown("reaper_exiting", false);
+    net.shutdown();
+
+    loop {
+        match read_server_message(&mut ws, Duration::from_secs(2)) {
+            ServerMessage::Shutdown {
+                reason,
+                restart_expected,
+            } => {
+                assert_eq!(reason, "reaper_exiting");
+                assert!(!restart_expected);
+                break;
+            }
+            _ => continue,
+        }
+    }
+}
+
+#[test]
+fn ws_handshake_remaps_params_moved_by_a_plugin_update() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    // Delay mix moved from 105 to 107; active and feedback stayed put.
+    let api = MockReaperApi::new();
+    api.param_names.lock().unwrap().extend([
+        (101, "DLY Active".to_string()),
+        (105, "DLY Mode".to_string()),
+        (106, "DLY Feedback".to_string()),
+        (107, "DLY Dry/Wet".to_string()),
+    ]);
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let mut ws = connect_client(addr);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Handshake { meta, .. } => {
+            assert_eq!(meta.param_remap, HashMap::from([(105, 107)]));
+        }
+        other => panic!("expected handshake, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_falls_back_to_the_next_free_port() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    // Another REAPER got there first.
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let taken_addr = taken.local_addr().unwrap();
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let addrs = [taken_addr.to_string(), "127.0.0.1:0".to_string()];
+    let net = NetworkThread::spawn_with_addrs(&addrs, in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+    assert_ne!(addr, taken_addr);
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    let mut ws = connect_client(addr);
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_pairing_key_is_required_at_connect() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_paired(
+        "127.0.0.1:0",
+        Some("paired-secret".to_string()),
+        in_tx,
+        out_rx,
+    )
+    .expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let connect_with = |key: Option<&str>| {
+        let stream = TcpStream::connect(addr).expect("tcp connect");
+        let _ = stream.set_read_timeout(Some(Duration::from_millis(50)));
+        let _ = stream.set_write_timeout(Some(Duration::from_millis(200)));
+        let mut req = format!("ws://{addr}").into_client_request().unwrap();
+        if let Some(key) = key {
+            req.headers_mut()
+                .insert(gojira_protocol::PAIRING_KEY_HEADER, key.parse().unwrap());
+        }
+        tungstenite::client(req, stream).map(|(ws, _)| ws)
+    };
+
+    for key in [None, Some("guessed")] {
+        match connect_with(key) {
+            Err(tungstenite::HandshakeError::Failure(tungstenite::Error::Http(resp))) => {
+                assert_eq!(resp.status(), 401)
+            }
+            Err(e) => panic!("expected 401 for {key:?}, got: {e}"),
+            Ok(_) => panic!("connected without the right pairing key ({key:?})"),
+        }
+    }
+
+    let mut ws = connect_with(Some("paired-secret")).expect("ws connect");
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_pipe_transport_speaks_the_same_protocol() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let name = format!("gojira-test-{}", std::process::id());
+    let net = NetworkThread::spawn_pipe(&name, None, in_tx, out_rx).expect("spawn net");
+    assert_eq!(net.listen_addr(), None);
+    assert_eq!(net.endpoint().to_string(), format!("pipe:{name}"));
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let ns_name = name.as_str().to_ns_name::<GenericNamespaced>().unwrap();
+    let stream = LocalSocketStream::connect(ns_name).expect("pipe connect");
+    let (mut ws, _) = tungstenite::client("ws://localhost/", stream).expect("ws connect");
+    // Blocking reads would stall the main loop ticks between them.
+    ws.get_ref().set_nonblocking(true).unwrap();
+
+    let token = handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut ws,
+        Duration::from_secs(2),
+    ));
+    let ping = ClientCommand::Ping {
+        session_token: token,
+        nonce: 7,
+    };
+    send_command(&mut ws, &ping);
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Pong { nonce, .. } => assert_eq!(nonce, 7),
+        other => panic!("expected pong, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_msgpack_is_negotiated_per_connection() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+
+    let stream = TcpStream::connect(addr).expect("tcp connect");
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(50)));
+    let _ = stream.set_write_timeout(Some(Duration::from_millis(200)));
+    let mut req = format!("ws://{addr}").into_client_request().unwrap();
+    req.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        SUBPROTOCOL_MSGPACK.parse().unwrap(),
+    );
+    let (mut ws, resp) = tungstenite::client(req, stream).expect("ws connect");
+    assert_eq!(
+        resp.headers().get("Sec-WebSocket-Protocol").unwrap(),
+        SUBPROTOCOL_MSGPACK
+    );
+
+    let handshake = tick_until_binary(&mut main_loop, &api, &mut ws, Duration::from_secs(2));
+    let token = handshake_token(handshake);
+
+    // A JSON client alongside keeps getting text frames.
+    let mut json_client = connect_client(addr);
+    handshake_token(tick_until_message(
+        &mut main_loop,
+        &api,
+        &mut json_client,
+        Duration::from_secs(2),
+    ));
+
+    let ping = ClientCommand::Ping {
+        session_token: token,
+        nonce: 9,
+    };
+    let Frame::Binary(bytes) = Encoding::Msgpack.encode(&ping).unwrap() else {
+        panic!("msgpack encodes to a binary frame");
+    };
+    ws.send(Message::Binary(bytes.into())).unwrap();
+    match tick_until_binary(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::Pong { nonce, .. } => assert_eq!(nonce, 9),
+        other => panic!("expected pong, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+/// Every frame up to and including the last `ParamMeta` chunk.
+fn tick_until_meta_done(
+    main_loop: &mut MainLoop,
+    api: &MockReaperApi,
+    ws: &mut tungstenite::WebSocket<TcpStream>,
+) -> Vec<ServerMessage> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut frames: Vec<ServerMessage> = Vec::new();
+    loop {
+        main_loop.tick(api);
+        match ws.read() {
+            Ok(Message::Text(s)) => {
+                let msg: ServerMessage = serde_json::from_str(&s).expect("valid server json");
+                let done = matches!(msg, ServerMessage::ParamMeta { done: true, .. });
+                frames.push(msg);
+                if done {
+                    return frames;
+                }
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => panic!("ws read failed: {e:?}"),
+        }
+        if Instant::now() >= deadline {
+            panic!("timeout waiting for the last param meta chunk");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn ws_param_meta_streams_after_the_handshake() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    let mut ws = connect_client(addr);
+
+    // The handshake is first and carries no formatted meta; that comes in chunks behind it.
+    let frames = tick_until_meta_done(&mut main_loop, &api, &mut ws);
+
+    match &frames[0] {
+        ServerMessage::Handshake { meta, .. } => {
+            assert!(meta.param_formats.is_empty());
+            assert!(meta.param_enums.is_empty());
+            assert!(meta.param_kinds.is_empty());
+        }
+        other => panic!("expected handshake first, got: {other:?}"),
+    }
+
+    let mut next_index = 0;
+    let mut formats = HashMap::new();
+    let mut enums = HashMap::new();
+    let mut kinds = HashMap::new();
+    for frame in &frames[1..] {
+        let ServerMessage::ParamMeta {
+            first_index,
+            last_index,
+            param_formats,
+            param_enums,
+            param_kinds,
+            ..
+        } = frame
+        else {
+            panic!("expected param meta, got: {frame:?}");
+        };
+        assert_eq!(
+            *first_index, next_index,
+            "chunks cover the indices in order"
+        );
+        next_index = last_index + 1;
+        formats.extend(param_formats.clone());
+        enums.extend(param_enums.clone());
+        kinds.extend(param_kinds.clone());
+    }
+    assert!(frames.len() > 2, "meta should span several chunks");
+    assert!(formats.contains_key(&30), "amp knob formats should arrive");
+    assert!(enums.contains_key(&84), "cab type options should arrive");
+    // The mock formats every value distinctly, so knobs classify as continuous.
+    assert_eq!(kinds.get(&30), Some(&ParamKind::Continuous));
+    // Continuous from its labels alone; its options make it stepped.
+    assert!(matches!(kinds.get(&84), Some(ParamKind::Stepped { .. })));
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_long_enums_are_paged_and_searchable() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    let mut ws = connect_client(addr);
+
+    let frames = tick_until_meta_done(&mut main_loop, &api, &mut ws);
+    let token = handshake_token(frames[0].clone());
+    let mut enums = HashMap::new();
+    let mut totals = HashMap::new();
+    for frame in &frames[1..] {
+        if let ServerMessage::ParamMeta {
+            param_enums,
+            param_enum_totals,
+            ..
+        } = frame
+        {
+            enums.extend(param_enums.clone());
+            totals.extend(param_enum_totals.clone());
+        }
+    }
+    // The mock labels every value apart, so the IR list fills up to its cap of 512.
+    assert_eq!(totals.get(&92), Some(&512));
+    assert_eq!(enums.get(&92).map(Vec::len), Some(64));
+    assert!(!totals.contains_key(&84), "cab types fit in one page");
+
+    send_command(
+        &mut ws,
+        &ClientCommand::SearchEnumLabels {
+            session_token: token.clone(),
+            command_id: "irs".to_string(),
+            index: 92,
+            query: "0.1".to_string(),
+            offset: 0,
+            limit: None,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ParamEnumPage {
+            command_id,
+            total,
+            options,
+            ..
+        } => {
+            assert_eq!(command_id, "irs");
+            // 0.100 to 0.199 is more than a page.
+            assert!(total > options.len());
+            assert_eq!(options.len(), 64);
+            assert!(options.iter().all(|o| o.label.contains("0.1")));
+        }
+        other => panic!("expected param enum page, got: {other:?}"),
+    }
+
+    // An empty query pages through everything.
+    send_command(
+        &mut ws,
+        &ClientCommand::SearchEnumLabels {
+            session_token: token,
+            command_id: "irs-tail".to_string(),
+            index: 92,
+            query: String::new(),
+            offset: 500,
+            limit: None,
+        },
+    );
+    match tick_until_message(&mut main_loop, &api, &mut ws, Duration::from_secs(2)) {
+        ServerMessage::ParamEnumPage { total, options, .. } => {
+            assert_eq!(total, 512);
+            assert_eq!(options.len(), 12);
+        }
+        other => panic!("expected param enum page, got: {other:?}"),
+    }
+
+    net.shutdown();
+}
+
+#[test]
+fn ws_param_meta_is_cached_until_refreshed() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let dir = std::env::temp_dir().join(format!("gojira-meta-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache_path = dir.join("param-meta.json");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    main_loop.set_meta_cache(cache_path.clone());
+
+    // Cold: probed and streamed, then written to disk.
+    let mut first = connect_client(addr);
+    let frames = tick_until_meta_done(&mut main_loop, &api, &mut first);
+    assert!(matches!(
+        frames[0],
+        ServerMessage::Handshake {
+            param_meta_streaming: true,
+            ..
+        }
+    ));
+    assert!(cache_path.exists(), "the finished stream should be cached");
+
+    // Warm, even for a fresh main loop: everything is in the handshake.
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+    let net2 = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr2 = net2.listen_addr().expect("tcp listener");
+    let mut main_loop2 = MainLoop::new(in_rx, out_tx);
+    main_loop2.set_meta_cache(cache_path.clone());
+
+    let mut second = connect_client(addr2);
+    let token = match tick_until_message(&mut main_loop2, &api, &mut second, Duration::from_secs(2))
+    {
+        ServerMessage::Handshake {
+            session_token,
+            param_meta_streaming,
+            meta,
+            ..
+        } => {
+            assert!(!param_meta_streaming);
+            assert!(meta.param_enums.contains_key(&84));
+            assert!(meta.param_formats.contains_key(&30));
+            assert!(matches!(
+                meta.param_kinds.get(&84),
+                Some(ParamKind::Stepped { .. })
+            ));
+            session_token
+        }
+        other => panic!("expected handshake, got: {other:?}"),
+    };
+
+    // An explicit refresh probes again.
+    send_command(
+        &mut second,
+        &ClientCommand::RefreshParamMeta {
+            session_token: token,
+            indices: Vec::new(),
+        },
+    );
+    let frames = tick_until_meta_done(&mut main_loop2, &api, &mut second);
+    let token = match &frames[0] {
+        ServerMessage::Handshake {
+            session_token,
+            param_meta_streaming: true,
+            ..
+        } => session_token.clone(),
+        other => panic!("expected a streaming handshake, got: {other:?}"),
+    };
+
+    // A targeted refresh probes just those params, with no handshake.
+    send_command(
+        &mut second,
+        &ClientCommand::RefreshParamMeta {
+            session_token: token,
+            indices: vec![84, 30],
+        },
+    );
+    let frames = tick_until_meta_done(&mut main_loop2, &api, &mut second);
+    assert_eq!(frames.len(), 1, "{frames:?}");
+    match &frames[0] {
+        ServerMessage::ParamMeta {
+            first_index: 30,
+            last_index: 84,
+            param_enums,
+            param_formats,
+            ..
+        } => {
+            assert!(param_enums.contains_key(&84));
+            assert!(param_formats.contains_key(&30));
+            assert!(!param_formats.contains_key(&31));
+        }
+        other => panic!("expected param meta, got: {other:?}"),
+    }
+
+    net.shutdown();
+    net2.shutdown();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn ws_touch_probe_restores_params_and_keeps_off_a_playing_transport() {
+    std::env::set_var("GOJIRA_SEND_PARAM_SAMPLES", "0");
+    std::env::set_var("GOJIRA_SEND_VALIDATION_REPORT", "0");
+
+    let (in_tx, in_rx) = bounded(reaper_gojira_dll::INBOUND_CAP);
+    let (out_tx, out_rx) = bounded(reaper_gojira_dll::OUTBOUND_CAP);
+
+    let net = NetworkThread::spawn_with_addr("127.0.0.1:0", in_tx, out_rx).expect("spawn net");
+    let addr = net.listen_addr().expect("tcp listener");
+
+    let api = MockReaperApi::new();
+    let before: HashMap<i32, f64> = (0..=117).map(|i| (i, 0.3 + i as f64 / 1000.0)).collect();
+    *api.params.lock().unwrap() = before.clone();
+    *api.playing.lock().unwrap() = true;
+    let mut main_loop = MainLoop::new(in_rx, out_tx);
+    let mut ws = connect_client(addr);
+
+    // The plan takes the mode when it is made; only this test's probes are built while it's set.
+    std::env::set_var("GOJIRA_TOUCH_PROBE", "1");
+    let frames = tick_until_meta_done(&mut main_loop, &api, &mut ws);
+    let token = handshake_token(frames[0].clone());
+    assert!(
+        api.writes.lock().unwrap().is_empty(),
+        "nothing may be written while the transport plays"
+    );
+
+    // Stopped, the probe writes each param it labels and puts it back.
+    *api.playing.lock().unwrap() = false;
+    send_command(
+        &mut ws,
+        &ClientCommand::RefreshParamMeta {
+            session_token: token,
+            indices: Vec::new(),
+        },
+    );
+    tick_until_meta_done(&mut main_loop, &api, &mut ws);
+    std::env::remove_var("GOJIRA_TOUCH_PROBE");
+
+    let writes = api.writes.lock().unwrap().clone();
+    let touched: HashSet<i32> = writes.iter().map(|(idx, _)| *idx).collect();
+    assert!(touched.contains(&84), "{touched:?}");
+    for idx in &touched {
+        let last = writes.iter().rev().find(|(i, _)| i == idx).map(|(_, v)| *v);
+        assert_eq!(last, before.get(idx).copied(), "param {idx} not restored");
+    }
+    assert_eq!(*api.params.lock().unwrap(), before);
+
+    net.shutdown();
+}
@@ -0,0 +1,1210 @@
+"""Client for the Gojira REAPER sidecar, generated from gojira_protocol; don't edit by hand.
+Regenerate with `cargo run -p gojira_protocol --features schema --bin gojira_schema -- --ts gojira_brain_ui/ui/src/protocol.gen.ts --py tools/gojira_client.py`.
+
+Needs Python 3.11+; `GojiraClient` also needs the `websocket-client` package.
+"""
+
+from __future__ import annotations
+
+import json
+import os
+import uuid
+from collections import deque
+from typing import (
+    Any,
+    Callable,
+    Deque,
+    Dict,
+    List,
+    Literal,
+    NotRequired,
+    Optional,
+    TypedDict,
+    Union,
+)
+
+DEFAULT_WS_PORT = 9001
+SUBPROTOCOL_JSON = "gojira.json"
+PAIRING_KEY_HEADER = "x-gojira-pairing-key"
+PAIRING_KEY_ENV = "GOJIRA_PAIRING_KEY"
+
+
+Capability = Literal["atomic_apply", "multi_target", "scheduled_apply", "module_filter", "background_projects", "param_subscription", "enum_search", "presets", "tone_slots", "preview_tone", "validate_tone", "render_preview", "snapshots", "transport", "fx_state", "track_controls", "insert_fx", "resume", "diagnostics", "msgpack", "unknown"]
+
+Confidence = Literal["high", "low"]
+
+ErrorCode = Literal["unauthorized", "busy", "target_not_found", "invalid_value", "invalid_command", "not_ready", "internal_error", "target_changed", "unsupported", "timeout", "partial_failure", "unknown"]
+
+LogLevel = Literal["error", "warn", "info", "debug"]
+
+MergeMode = Literal["merge", "replace_active"]
+
+ModuleId = Literal["wow", "oct", "overdrive", "distortion", "phaser", "chorus", "delay", "reverb", "global", "amp", "eq", "cab"]
+
+ParamCheckStatus = Literal["ok", "suspicious", "missing"]
+
+ParamKind = Dict[str, Any]
+
+ParamOp = Literal["set", "add"]
+
+ParamRole = Literal["knob", "toggle", "mix"]
+
+ParamSchemaKind = Literal["toggle", "continuous", "enum"]
+
+Remediation = Literal["refresh_instances", "fix_params", "complete_handshake", "reauthenticate", "retry", "allow_background_projects", "unknown"]
+
+ResolverState = Literal["cache_miss", "verify_failed", "background_project", "unknown"]
+
+ToneIssue = Literal["unknown_param", "reserved_param", "duplicate", "out_of_range", "off_enum_option", "fractional_toggle", "module_off"]
+
+TransportAnchor = Dict[str, Any]
+
+WarningCode = Literal["reserved_index_skipped", "command_coalesced", "linked_cab_override", "readback_unavailable", "background_project", "unknown"]
+
+
+class AppliedParam(TypedDict):
+    """One written param as the plugin holds it afterwards; `applied` differing from `requested`
+    means the plugin quantized or rejected the value."""
+
+    index: int
+    requested: float
+    applied: float
+    formatted: str
+
+
+class ErrorDiagnostic(TypedDict):
+    """The structured side of an error, so clients can act on it instead of parsing `msg`."""
+
+    # The param the command failed on.
+    param_index: Optional[int]
+    # The value it failed with, as sent.
+    value: Optional[float]
+    # Set on target-not-found errors.
+    resolver: Optional[ResolverState]
+    remediation: Optional[Remediation]
+
+
+class FailedParam(TypedDict):
+    """A param whose write REAPER refused, with the value that was sent and REAPER's reason."""
+
+    index: int
+    value: float
+    error: str
+
+
+class GojiraInstance(TypedDict):
+    track_guid: str
+    track_name: str
+    fx_guid: str
+    fx_name: str
+    last_known_fx_index: int
+    confidence: Confidence
+    # Plugin profile the FX name matched ("gojira", "plini", ...); unset from older DLLs.
+    profile: Optional[str]
+
+
+class LogEvent(TypedDict):
+    """One entry of the DLL's event log."""
+
+    # Milliseconds since the DLL was loaded.
+    at_ms: int
+    level: LogLevel
+    # Where it came from, e.g. "init", "net" or "main_loop".
+    target: str
+    msg: str
+
+
+class ParamChange(TypedDict):
+    """Normalized values are f64 end to end: REAPER takes doubles, and f32 rounding can tip a
+    quantized selector (e.g. 0.125976562) onto the neighboring option. f32-producing clients still
+    deserialize fine, since JSON numbers carry no width."""
+
+    index: int
+    value: float
+    op: NotRequired[ParamOp]
+
+
+class ParamCheck(TypedDict):
+    """One line of the handshake's validation report."""
+
+    # Module the param belongs to, e.g. "delay".
+    module: str
+    role: ParamRole
+    expected_index: int
+    # Name the chart has for the index.
+    expected_name: Optional[str]
+    # Name the plugin reports at the index.
+    found_name: Optional[str]
+    status: ParamCheckStatus
+
+
+class ParamEnumOption(TypedDict):
+    value: float
+    label: str
+
+
+class ParamFormatSample(TypedDict):
+    norm: float
+    formatted: str
+
+
+class ParamFormatTriplet(TypedDict):
+    min: str
+    mid: str
+    max: str
+
+
+class ParamReading(TypedDict):
+    index: int
+    value: float
+    formatted: str
+
+
+class ParamSchemaEntry(TypedDict):
+    """One param of the handshake's `param_schema`."""
+
+    name: str
+    kind: ParamSchemaKind
+    # Section of the plugin, e.g. "amp_eq" or "cab"; "other" where the layout isn't charted.
+    group: str
+
+
+class PreviewRow(TypedDict):
+    """One line of a `preview`. `before` is `None` when the param can't be read back."""
+
+    index: int
+    name: str
+    before: Optional[float]
+    before_formatted: str
+    after: float
+    after_formatted: str
+
+
+class ProjectTempo(TypedDict):
+    bpm: float
+    beats_per_measure: int
+
+
+class ReplayedEvent(TypedDict):
+    """A message `replay` passes on, with how long before the replay it was sent."""
+
+    age_ms: int
+    message: ServerMessage
+
+
+class RuntimeMetrics(TypedDict):
+    """DLL counters since it was loaded, answered to `get_metrics`. Durations are milliseconds."""
+
+    uptime_secs: float
+    commands_received: int
+    # Commands answered with an ack / with an error by the main loop.
+    commands_applied: int
+    commands_rejected: int
+    # SetTone glides cut short by a newer command on the same FX.
+    commands_coalesced: int
+    avg_apply_ms: float
+    max_apply_ms: float
+    # Timer ticks over the last full second.
+    ticks_per_sec: float
+    # Handshake probes (validation, kinds, EQ bands) and streamed param meta chunks.
+    probes: int
+    last_probe_ms: float
+    max_probe_ms: float
+    inbound_high_water: int
+    outbound_high_water: int
+    # Server messages lost because the outbound queue was full.
+    outbound_dropped: int
+
+
+class SkippedModule(TypedDict):
+    """A module a `merge` set_tone sent no params for; `active` is its toggle as read from the
+    plugin, `None` if it couldn't be read."""
+
+    name: str
+    active: Optional[bool]
+
+
+class TargetResult(TypedDict):
+    target_fx_guid: str
+    applied_params: List[AppliedParam]
+    # Writes REAPER refused while the rest of the target's params went through.
+    failed_params: List[FailedParam]
+    # Set when the target was skipped or none of its params could be written.
+    error: Optional[str]
+
+
+class TimingStats(TypedDict):
+    """How long the main loop's recurring work takes, in milliseconds."""
+
+    ticks: int
+    avg_tick_ms: float
+    max_tick_ms: float
+    # Instance rescans, from handshakes and the project watchdog.
+    scans: int
+    avg_scan_ms: float
+    max_scan_ms: float
+    applies: int
+    avg_apply_ms: float
+    max_apply_ms: float
+
+
+class ToneSlotInfo(TypedDict):
+    """A stored tone slot as `tone_slots` lists it."""
+
+    slot: int
+    name: str
+    target_fx_guid: str
+    param_count: int
+
+
+class ToneTarget(TypedDict):
+    """One FX of a `set_tone_multi`; the fields mean what they do on `set_tone`."""
+
+    target_fx_guid: str
+    mode: MergeMode
+    params: List[ParamChange]
+    locked_params: NotRequired[List[int]]
+
+
+class ToneWarning(TypedDict):
+    """One problem `validate_tone` found."""
+
+    issue: ToneIssue
+    # The param at fault; for `module_off`, the module's toggle.
+    index: int
+    msg: str
+
+
+class HandshakeAckCommand(TypedDict):
+    type: Literal["handshake_ack"]
+    session_token: str
+
+
+class RefreshInstancesCommand(TypedDict):
+    """Rescan and hand everyone a new handshake. With `instances_only`, the sender alone gets a
+    bare `Instances` list instead, for polling without re-sending the param meta."""
+
+    type: Literal["refresh_instances"]
+    session_token: str
+    instances_only: NotRequired[bool]
+
+
+class RefreshParamMetaCommand(TypedDict):
+    """Forget the DLL's cached param meta for the plugin and probe it again. Everyone gets a new
+    handshake, with the meta following as `ParamMeta` chunks.
+    
+    With `indices`, only those params are probed again (e.g. the IR selectors after new IR
+    files were installed), with no handshake; everyone gets one `ParamMeta` covering them."""
+
+    type: Literal["refresh_param_meta"]
+    session_token: str
+    indices: NotRequired[List[int]]
+
+
+class RotateTokenCommand(TypedDict):
+    """Ask for a fresh session token; answered with `token_rotated`."""
+
+    type: Literal["rotate_token"]
+    session_token: str
+
+
+class ResumeCommand(TypedDict):
+    """Take over the session of a connection that dropped less than `RESUME_GRACE_SECS` ago, by
+    the token it had. Subscriptions and the probe results carry over and the connection goes
+    on under that token; answered with `resumed`, or a handshake if the plugin must be probed
+    again. Sent first thing, it spares the connection its handshake."""
+
+    type: Literal["resume"]
+    previous_token: str
+
+
+class GetMetricsCommand(TypedDict):
+    """Ask for the DLL's runtime counters; answered with `metrics`."""
+
+    type: Literal["get_metrics"]
+    session_token: str
+
+
+class GetDiagnosticsCommand(TypedDict):
+    """Ask for the DLL's recent log events and timings; answered with `diagnostics`."""
+
+    type: Literal["get_diagnostics"]
+    session_token: str
+    # How many events to return; the DLL's default when unset.
+    limit: NotRequired[Optional[int]]
+
+
+class PingCommand(TypedDict):
+    """Liveness check, answered with `pong` from REAPER's main thread rather than the socket
+    thread, so a reply means the DLL is actually ticking."""
+
+    type: Literal["ping"]
+    session_token: str
+    nonce: int
+
+
+class GetCurrentToneCommand(TypedDict):
+    """Read back every tone parameter of an instance; answered with `current_tone`."""
+
+    type: Literal["get_current_tone"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+
+
+class SetToneCommand(TypedDict):
+    type: Literal["set_tone"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    mode: MergeMode
+    params: List[ParamChange]
+    # Indices the user pinned: never written, and their modules are never auto-bypassed.
+    locked_params: NotRequired[List[int]]
+    # Glide continuous params to their targets over this many milliseconds (capped at
+    # 5000) instead of jumping; toggles and stepped params still switch at once. The ack
+    # comes when the glide ends.
+    ramp_ms: NotRequired[Optional[int]]
+    # All or nothing: if any write fails, or reads back off target, every param is put
+    # back to its value from before the command and an `error` is sent. Never glides, so
+    # `ramp_ms` is ignored.
+    atomic: NotRequired[bool]
+    # Hold the tone back until the transport reaches this point; the ack comes once it is
+    # written. Without one the tone is written right away.
+    apply_at: NotRequired[Optional[TransportAnchor]]
+    # Write only the params of these modules, picked out after cleaning, e.g. just the cab
+    # of a full tone; the rest of `params` is dropped. Needs a charted plugin layout.
+    modules: NotRequired[Optional[List[ModuleId]]]
+    # Find the target in other open project tabs too, not just the current one. Writing to
+    # a background tab is announced with a `background_project` warning.
+    allow_background_projects: NotRequired[bool]
+
+
+class PreviewToneCommand(TypedDict):
+    """Dry run of `set_tone`: sanitized, cleaned and resolved against the live FX exactly like
+    one, but nothing is written; answered with `preview`."""
+
+    type: Literal["preview_tone"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    mode: MergeMode
+    params: List[ParamChange]
+    locked_params: NotRequired[List[int]]
+
+
+class ValidateToneCommand(TypedDict):
+    """Checks `params` against the live FX without cleaning or writing them: that each index
+    exists, that enum values land on a probed option, and that toggles agree with the params
+    they gate. Answered with `tone_validation`."""
+
+    type: Literal["validate_tone"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    params: List[ParamChange]
+
+
+class SetToneMultiCommand(TypedDict):
+    """`set_tone` for several FX at once (e.g. L/R guitar tracks), as one REAPER undo step and
+    one `multi_ack`. A target that fails does not stop the others."""
+
+    type: Literal["set_tone_multi"]
+    session_token: str
+    command_id: str
+    targets: List[ToneTarget]
+    # As on `set_tone`, for every target.
+    allow_background_projects: NotRequired[bool]
+
+
+class UndoLastToneCommand(TypedDict):
+    """Reverts the most recent tone applied through `set_tone`, as one REAPER undo step.
+    Refused when something else sits on top of REAPER's undo history."""
+
+    type: Literal["undo_last_tone"]
+    session_token: str
+    command_id: str
+
+
+class RestoreSnapshotCommand(TypedDict):
+    """Writes back the values the FX had right before a `set_tone` (or every FX of a
+    `set_tone_multi`); `snapshot_id` is that command's `command_id`. Works regardless of what happened to REAPER's undo history since."""
+
+    type: Literal["restore_snapshot"]
+    session_token: str
+    command_id: str
+    snapshot_id: str
+
+
+class SubscribeCommand(TypedDict):
+    """Watch `indices` on the FX and get `param_changed` pushes when they move. Replaces this
+    client's earlier subscription for the same FX; empty `indices` ends it."""
+
+    type: Literal["subscribe"]
+    session_token: str
+    target_fx_guid: str
+    indices: NotRequired[List[int]]
+
+
+class SavePresetCommand(TypedDict):
+    """Stores the FX's current tone under `name` in the sidecar's preset folder (REAPER's API
+    can load plugin presets but not write them). Overwrites a preset of the same name."""
+
+    type: Literal["save_preset"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    name: str
+
+
+class LoadPresetCommand(TypedDict):
+    """Applies a preset saved with `save_preset`, else the plugin's own preset of that name as
+    shown in REAPER's preset dropdown. One REAPER undo step, like `set_tone`."""
+
+    type: Literal["load_preset"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    name: str
+
+
+class SearchEnumLabelsCommand(TypedDict):
+    """Options of the enum at `index` whose labels contain `query` (case-insensitive; empty
+    matches all), for enums the handshake only carries the first page of. Answered with
+    `param_enum_page`; options still being probed are not there yet."""
+
+    type: Literal["search_enum_labels"]
+    session_token: str
+    command_id: str
+    index: int
+    query: NotRequired[str]
+    offset: NotRequired[int]
+    # Defaults to the handshake's page size.
+    limit: NotRequired[Optional[int]]
+
+
+class ListPresetsCommand(TypedDict):
+    """Answered with `preset_list`."""
+
+    type: Literal["list_presets"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+
+
+class StoreToneSlotCommand(TypedDict):
+    """Keeps a named tone in `slot` (0..=127) for `activate_tone_slot`, so switching to it later
+    doesn't take the whole param list again. Replaces what the slot held; no params clears
+    it. Slots are saved with the project; answered with `ack`."""
+
+    type: Literal["store_tone_slot"]
+    session_token: str
+    command_id: str
+    slot: int
+    name: NotRequired[str]
+    target_fx_guid: str
+    mode: MergeMode
+    params: List[ParamChange]
+    locked_params: NotRequired[List[int]]
+
+
+class ActivateToneSlotCommand(TypedDict):
+    """Applies a stored slot as the `set_tone` it was stored from, with this command's glide and
+    timing; answered with `ack`."""
+
+    type: Literal["activate_tone_slot"]
+    session_token: str
+    command_id: str
+    slot: int
+    ramp_ms: NotRequired[Optional[int]]
+    apply_at: NotRequired[Optional[TransportAnchor]]
+
+
+class ListToneSlotsCommand(TypedDict):
+    """Answered with `tone_slots`."""
+
+    type: Literal["list_tone_slots"]
+    session_token: str
+    command_id: str
+
+
+class SetFxMixCommand(TypedDict):
+    """REAPER's per-FX wet knob (0..1) and delta solo; the plugin's own params are untouched.
+    Fields left out are not changed."""
+
+    type: Literal["set_fx_mix"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    wet: NotRequired[Optional[float]]
+    delta_solo: NotRequired[Optional[bool]]
+
+
+class SetFxStateCommand(TypedDict):
+    """The FX's enable switch and REAPER's wet knob (0..1), for A/B against the dry signal or a
+    parallel blend. Fields left out are not changed; answered with `fx_state`."""
+
+    type: Literal["set_fx_state"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    enabled: NotRequired[Optional[bool]]
+    wet: NotRequired[Optional[float]]
+
+
+class RenderPreviewCommand(TypedDict):
+    """Bounce `length` seconds of the master mix from `start` to a temp WAV, for listening to (or
+    scoring) a tone without playing the project; answered with `preview_rendered`. `start`
+    defaults to the loop start, `length` to 5 s; at most 30 s are rendered."""
+
+    type: Literal["render_preview"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    start: NotRequired[Optional[float]]
+    length: NotRequired[Optional[float]]
+
+
+class PlayCommand(TypedDict):
+    """Start playback from the edit cursor, which `set_loop` puts at the loop start; answered
+    with `transport`."""
+
+    type: Literal["play"]
+    session_token: str
+    command_id: str
+
+
+class StopCommand(TypedDict):
+    """Stop playback; answered with `transport`."""
+
+    type: Literal["stop"]
+    session_token: str
+    command_id: str
+
+
+class SetLoopCommand(TypedDict):
+    """Loop `start..end` (project time, seconds) with repeat on and the edit cursor at `start`, so
+    the next `play` auditions the riff; answered with `transport`."""
+
+    type: Literal["set_loop"]
+    session_token: str
+    command_id: str
+    start: float
+    end: float
+
+
+class InsertFxCommand(TypedDict):
+    """Add the plugin to a track, for when no instance was found. Everyone gets a new handshake
+    listing it; failures come back as `error`."""
+
+    type: Literal["insert_fx"]
+    session_token: str
+    command_id: str
+    # Defaults to the track selected in REAPER.
+    track_guid: NotRequired[Optional[str]]
+    # Name as REAPER's FX browser matches it; defaults to the Gojira plugin.
+    fx_name: NotRequired[Optional[str]]
+
+
+class SetTrackControlsCommand(TypedDict):
+    """Volume, pan and record monitoring of the track hosting the FX, for "sit it lower in the
+    mix" requests. Fields left out are not changed; answered with `track_controls`."""
+
+    type: Literal["set_track_controls"]
+    session_token: str
+    command_id: str
+    target_fx_guid: str
+    # Clamped to -150..=12 dB.
+    volume_db: NotRequired[Optional[float]]
+    # -1 (hard left) to 1 (hard right).
+    pan: NotRequired[Optional[float]]
+    rec_monitor: NotRequired[Optional[bool]]
+
+
+class SwitchFxPairCommand(TypedDict):
+    """Tone switching by instance: disable one Gojira and enable another on the same track.
+    Applied in one tick, disable first, so both are never active at once."""
+
+    type: Literal["switch_fx_pair"]
+    session_token: str
+    command_id: str
+    enable_fx_guid: str
+    disable_fx_guid: str
+
+
+class HandshakeMessage(TypedDict):
+    """First message on every connection: the session token, the instances and what the DLL
+    knows about their params."""
+
+    type: Literal["handshake"]
+    session_token: str
+    instances: List[GojiraInstance]
+    # Every charted module toggle and knob as the plugin names it; empty unless the DLL runs
+    # with GOJIRA_SEND_VALIDATION_REPORT.
+    validation_report: List[ParamCheck]
+    # Enum options, formats and samples follow as `ParamMeta` chunks; those param maps are
+    # then empty. Older DLLs put everything here.
+    param_meta_streaming: bool
+    param_enums: Dict[str, List[ParamEnumOption]]
+    # Full option count of the enums above cut to their first page, like long IR lists; the
+    # rest are pulled with `search_enum_labels`.
+    param_enum_totals: Dict[str, int]
+    param_formats: Dict[str, ParamFormatTriplet]
+    param_format_samples: Dict[str, List[ParamFormatSample]]
+    # Graphic-EQ band index -> center frequency (Hz), when the plugin exposes it.
+    eq_band_hz: Dict[str, float]
+    param_kinds: Dict[str, ParamKind]
+    # Every tone param of the probed instance by its actual index: the name the plugin
+    # reports, its kind and section. Clients label and group params from this rather than
+    # from tables of their own.
+    param_schema: Dict[str, ParamSchemaEntry]
+    # Charted param index -> the index the plugin now reports that param's name at, for
+    # params a plugin update has moved. Meant to seed the client's index remap.
+    param_remap: Dict[str, int]
+    # Canonical -> actual index guesses for toggles and mix controls the validation report
+    # flags, by a name fitting the role near the charted index. Unlike `param_remap`, meant
+    # for the user to confirm rather than to adopt as is.
+    proposed_remap: Dict[str, int]
+    # Project tempo at the start of the timeline.
+    project_tempo: Optional[ProjectTempo]
+    # Address the DLL's server is actually bound to, for diagnostics.
+    server_addr: Optional[str]
+    # Certificate fingerprint when the server speaks TLS, to compare with the pinned one.
+    tls_fingerprint: Optional[str]
+    # What this DLL build supports; empty from DLLs that predate the list, which support
+    # none of it reliably.
+    capabilities: List[Capability]
+
+
+class ParamMetaMessage(TypedDict):
+    """Param meta for indices `first_index..=last_index`, probed after the handshake went out.
+    Merged into what the client already has; `done` marks the last chunk."""
+
+    type: Literal["param_meta"]
+    first_index: int
+    last_index: int
+    param_enums: Dict[str, List[ParamEnumOption]]
+    # Full option count of the enums above cut to their first page, like long IR lists; the
+    # rest are pulled with `search_enum_labels`.
+    param_enum_totals: Dict[str, int]
+    param_formats: Dict[str, ParamFormatTriplet]
+    param_format_samples: Dict[str, List[ParamFormatSample]]
+    # Kinds of the tone params in this range, enums counting as stepped.
+    param_kinds: Dict[str, ParamKind]
+    done: bool
+
+
+class ProjectChangedMessage(TypedDict):
+    """One per project whose instances changed, so a tab switch arrives as the old project's
+    instances removed and the new one's added."""
+
+    type: Literal["project_changed"]
+    project_tempo: Optional[ProjectTempo]
+    # File name of the project the instance deltas are from; unset while it's unsaved.
+    project_name: Optional[str]
+    # Instances whose tone params moved since the DLL last read them, or that are gone.
+    # Instances the DLL never read are not tracked, so they are never listed.
+    changed_instances: List[str]
+    # Instances that appeared since the last scan.
+    added: List[GojiraInstance]
+    # Instances that are gone, as they were last seen.
+    removed: List[GojiraInstance]
+    # Instances still there whose track name, FX name or position changed, as they are now.
+    renamed: List[GojiraInstance]
+
+
+class AckMessage(TypedDict):
+    type: Literal["ack"]
+    command_id: str
+    applied_params: List[AppliedParam]
+    # Modules a `merge` set_tone left as they were.
+    skipped_modules: List[SkippedModule]
+    # Params REAPER refused to write. The rest were written anyway; a command none of whose
+    # params could be written gets an `error` instead.
+    failed_params: List[FailedParam]
+
+
+class MultiAckMessage(TypedDict):
+    """Reply to `set_tone_multi`: one result per target, in request order."""
+
+    type: Literal["multi_ack"]
+    command_id: str
+    results: List[TargetResult]
+
+
+class ErrorMessage(TypedDict):
+    type: Literal["error"]
+    # Human-readable summary; `diagnostic` carries the same facts for clients to act on.
+    msg: str
+    code: ErrorCode
+    # Optional diagnostic context (e.g. nearby parameter names on apply failures).
+    details: Optional[str]
+    # The failed command's `command_id`, for commands that carry one.
+    command_id: Optional[str]
+    diagnostic: Optional[ErrorDiagnostic]
+
+
+class TokenRotatedMessage(TypedDict):
+    """Reply to `rotate_token`, or pushed when the server-side token TTL runs out. The previous
+    token stays valid for a short grace period so in-flight commands are not rejected."""
+
+    type: Literal["token_rotated"]
+    session_token: str
+    expires_in_secs: Optional[int]
+
+
+class MetricsMessage(TypedDict):
+    """Reply to `get_metrics`."""
+
+    type: Literal["metrics"]
+    metrics: RuntimeMetrics
+
+
+class InstancesMessage(TypedDict):
+    """Reply to `RefreshInstances` with `instances_only`: the rescanned list and nothing else.
+    The param meta from the last handshake still holds."""
+
+    type: Literal["instances"]
+    instances: List[GojiraInstance]
+
+
+class ResumedMessage(TypedDict):
+    """Reply to `resume`: the connection now goes by `session_token`, the one it asked for."""
+
+    type: Literal["resumed"]
+    session_token: str
+    instances: List[GojiraInstance]
+
+
+class DiagnosticsMessage(TypedDict):
+    """Reply to `get_diagnostics`: the most recent log events, oldest first."""
+
+    type: Literal["diagnostics"]
+    events: List[LogEvent]
+    timings: TimingStats
+
+
+class InstanceMovedMessage(TypedDict):
+    """Pushed when a command finds an instance moved since the last scan, e.g. dragged along its
+    FX chain or onto another track, so clients can repoint their references before the
+    project's next rescan. `instance` is as it is now."""
+
+    type: Literal["instance_moved"]
+    instance: GojiraInstance
+    from_track_guid: str
+    from_fx_index: int
+
+
+class PongMessage(TypedDict):
+    """Reply to `ping`, echoing its nonce."""
+
+    type: Literal["pong"]
+    nonce: int
+    uptime_ms: int
+
+
+class ParamEnumPageMessage(TypedDict):
+    """Reply to `search_enum_labels`: the matching options from `offset` on. `total` counts every
+    match, so a page ending short of it means there are more."""
+
+    type: Literal["param_enum_page"]
+    command_id: str
+    index: int
+    query: str
+    offset: int
+    total: int
+    options: List[ParamEnumOption]
+
+
+class CurrentToneMessage(TypedDict):
+    """Reply to `get_current_tone`: what the plugin holds right now, including GUI edits."""
+
+    type: Literal["current_tone"]
+    command_id: str
+    target_fx_guid: str
+    params: List[ParamReading]
+    # Every param a command has written to this FX, with the value it read back. Saved in
+    # the project, so it outlives the session and a REAPER restart.
+    last_applied: List[ParamChange]
+
+
+class PreviewMessage(TypedDict):
+    """Reply to `preview_tone`: each param the set_tone would write, as the plugin holds it now
+    and as it would read after the write. Nothing was written."""
+
+    type: Literal["preview"]
+    command_id: str
+    target_fx_guid: str
+    rows: List[PreviewRow]
+    skipped_modules: List[SkippedModule]
+
+
+class ToneValidationMessage(TypedDict):
+    """Reply to `validate_tone`. No `warnings` means the set looks sound; nothing was written."""
+
+    type: Literal["tone_validation"]
+    command_id: str
+    target_fx_guid: str
+    warnings: List[ToneWarning]
+
+
+class PreviewRenderedMessage(TypedDict):
+    """Reply to `render_preview`: where the WAV landed, and the range actually rendered."""
+
+    type: Literal["preview_rendered"]
+    command_id: str
+    target_fx_guid: str
+    path: str
+    start: float
+    length: float
+
+
+class TransportMessage(TypedDict):
+    """Reply to `play`, `stop` and `set_loop`: the transport as REAPER reports it afterwards."""
+
+    type: Literal["transport"]
+    command_id: str
+    playing: bool
+    repeat: bool
+    # Loop points in seconds; unset when the project has none.
+    loop_start: Optional[float]
+    loop_end: Optional[float]
+
+
+class FxStateMessage(TypedDict):
+    """Reply to `set_fx_state`: the FX's enable switch and wet knob as REAPER reads them after
+    the write. `wet` is unset when the FX doesn't expose the knob."""
+
+    type: Literal["fx_state"]
+    command_id: str
+    target_fx_guid: str
+    enabled: bool
+    wet: Optional[float]
+
+
+class TrackControlsMessage(TypedDict):
+    """Reply to `set_track_controls`: the host track's controls as REAPER reads them after the
+    write. A field is unset when REAPER doesn't report it."""
+
+    type: Literal["track_controls"]
+    command_id: str
+    target_fx_guid: str
+    track_guid: str
+    volume_db: Optional[float]
+    pan: Optional[float]
+    # On for REAPER's "auto" monitoring as well.
+    rec_monitor: Optional[bool]
+
+
+class ParamChangedMessage(TypedDict):
+    """Pushed to clients that sent `subscribe` when subscribed params move, e.g. a knob turned
+    in the plugin GUI. Only the params that changed since the last push are listed."""
+
+    type: Literal["param_changed"]
+    target_fx_guid: str
+    params: List[ParamReading]
+
+
+class PresetListMessage(TypedDict):
+    """Reply to `list_presets`."""
+
+    type: Literal["preset_list"]
+    command_id: str
+    target_fx_guid: str
+    # Presets stored with `save_preset`, sorted by name.
+    saved: List[str]
+    # The plugin's own preset the FX reports being on, if any.
+    current_native: Optional[str]
+
+
+class ToneSlotsMessage(TypedDict):
+    """Reply to `list_tone_slots`."""
+
+    type: Literal["tone_slots"]
+    command_id: str
+    # Sorted by slot.
+    slots: List[ToneSlotInfo]
+    # Slot activated last, if it is still stored.
+    active_slot: Optional[int]
+
+
+class WarningMessage(TypedDict):
+    """Something the user should know about that did not abort the command."""
+
+    type: Literal["warning"]
+    code: WarningCode
+    msg: str
+    # What it applies to, e.g. the command id or the affected indices.
+    context: Optional[str]
+
+
+class ReplayMessage(TypedDict):
+    """Sent right after a handshake to the first client in after a spell with none connected:
+    what it missed meanwhile (acks and errors of MIDI and action-list switches, project
+    changes), oldest first. Capped, so the oldest may be gone."""
+
+    type: Literal["replay"]
+    events: List[ReplayedEvent]
+
+
+class ShutdownMessage(TypedDict):
+    """The last message before the DLL closes every connection. A socket that drops without one
+    means REAPER crashed or the network went away."""
+
+    type: Literal["shutdown"]
+    # Why the server is going away, e.g. "reaper_exiting".
+    reason: str
+    # Set when the DLL expects to be back shortly, so clients can keep retrying at their
+    # usual pace instead of settling in for REAPER to be started again.
+    restart_expected: bool
+
+
+ClientCommand = Union[
+    HandshakeAckCommand,
+    RefreshInstancesCommand,
+    RefreshParamMetaCommand,
+    RotateTokenCommand,
+    ResumeCommand,
+    GetMetricsCommand,
+    GetDiagnosticsCommand,
+    PingCommand,
+    GetCurrentToneCommand,
+    SetToneCommand,
+    PreviewToneCommand,
+    ValidateToneCommand,
+    SetToneMultiCommand,
+    UndoLastToneCommand,
+    RestoreSnapshotCommand,
+    SubscribeCommand,
+    SavePresetCommand,
+    LoadPresetCommand,
+    SearchEnumLabelsCommand,
+    ListPresetsCommand,
+    StoreToneSlotCommand,
+    ActivateToneSlotCommand,
+    ListToneSlotsCommand,
+    SetFxMixCommand,
+    SetFxStateCommand,
+    RenderPreviewCommand,
+    PlayCommand,
+    StopCommand,
+    SetLoopCommand,
+    InsertFxCommand,
+    SetTrackControlsCommand,
+    SwitchFxPairCommand,
+]
+
+
+ServerMessage = Union[
+    HandshakeMessage,
+    ParamMetaMessage,
+    ProjectChangedMessage,
+    AckMessage,
+    MultiAckMessage,
+    ErrorMessage,
+    TokenRotatedMessage,
+    MetricsMessage,
+    InstancesMessage,
+    ResumedMessage,
+    DiagnosticsMessage,
+    InstanceMovedMessage,
+    PongMessage,
+    ParamEnumPageMessage,
+    CurrentToneMessage,
+    PreviewMessage,
+    ToneValidationMessage,
+    PreviewRenderedMessage,
+    TransportMessage,
+    FxStateMessage,
+    TrackControlsMessage,
+    ParamChangedMessage,
+    PresetListMessage,
+    ToneSlotsMessage,
+    WarningMessage,
+    ReplayMessage,
+    ShutdownMessage,
+]
+
+COMMAND_TYPES = frozenset({
+    "handshake_ack",
+    "refresh_instances",
+    "refresh_param_meta",
+    "rotate_token",
+    "resume",
+    "get_metrics",
+    "get_diagnostics",
+    "ping",
+    "get_current_tone",
+    "set_tone",
+    "preview_tone",
+    "validate_tone",
+    "set_tone_multi",
+    "undo_last_tone",
+    "restore_snapshot",
+    "subscribe",
+    "save_preset",
+    "load_preset",
+    "search_enum_labels",
+    "list_presets",
+    "store_tone_slot",
+    "activate_tone_slot",
+    "list_tone_slots",
+    "set_fx_mix",
+    "set_fx_state",
+    "render_preview",
+    "play",
+    "stop",
+    "set_loop",
+    "insert_fx",
+    "set_track_controls",
+    "switch_fx_pair",
+})
+# Commands answered by a message carrying their `command_id`.
+COMMANDS_WITH_ID = frozenset({
+    "get_current_tone",
+    "set_tone",
+    "preview_tone",
+    "validate_tone",
+    "set_tone_multi",
+    "undo_last_tone",
+    "restore_snapshot",
+    "save_preset",
+    "load_preset",
+    "search_enum_labels",
+    "list_presets",
+    "store_tone_slot",
+    "activate_tone_slot",
+    "list_tone_slots",
+    "set_fx_mix",
+    "set_fx_state",
+    "render_preview",
+    "play",
+    "stop",
+    "set_loop",
+    "insert_fx",
+    "set_track_controls",
+    "switch_fx_pair",
+})
+MESSAGE_TYPES = frozenset({
+    "handshake",
+    "param_meta",
+    "project_changed",
+    "ack",
+    "multi_ack",
+    "error",
+    "token_rotated",
+    "metrics",
+    "instances",
+    "resumed",
+    "diagnostics",
+    "instance_moved",
+    "pong",
+    "param_enum_page",
+    "current_tone",
+    "preview",
+    "tone_validation",
+    "preview_rendered",
+    "transport",
+    "fx_state",
+    "track_controls",
+    "param_changed",
+    "preset_list",
+    "tone_slots",
+    "warning",
+    "replay",
+    "shutdown",
+})
+
+
+def pairing_key() -> Optional[str]:
+    """GOJIRA_PAIRING_KEY, else the key the DLL wrote next to its sidecar.json."""
+    key = os.environ.get(PAIRING_KEY_ENV)
+    if key:
+        return key.strip()
+    base = os.environ.get("APPDATA") or os.path.join(os.path.expanduser("~"), ".config")
+    try:
+        with open(os.path.join(base, "Gojira", "pairing.key"), encoding="utf-8") as f:
+            return f.read().strip()
+    except OSError:
+        return None
+
+
+class GojiraClient:
+    """One JSON connection to the DLL. Connecting waits for the handshake and acknowledges it;
+    `handshake` keeps it, instances and param meta included."""
+
+    def __init__(
+        self,
+        url: str = f"ws://127.0.0.1:{DEFAULT_WS_PORT}",
+        key: Optional[str] = None,
+        timeout: float = 10.0,
+    ) -> None:
+        import websocket  # websocket-client
+
+        key = pairing_key() if key is None else key
+        header = [f"{PAIRING_KEY_HEADER}: {key}"] if key else []
+        self._ws = websocket.create_connection(
+            url, header=header, subprotocols=[SUBPROTOCOL_JSON], timeout=timeout
+        )
+        self._pending: Deque[ServerMessage] = deque()
+        self.handshake: HandshakeMessage = self._wait(lambda m: m["type"] == "handshake")
+        self.session_token: str = self.handshake["session_token"]
+        self._send({"type": "handshake_ack", "session_token": self.session_token})
+
+    def send(self, type: str, **fields: Any) -> Optional[str]:
+        """Sends a command with the session token and, where the command takes one, a fresh
+        `command_id`, which is returned."""
+        if type not in COMMAND_TYPES:
+            raise ValueError(f"unknown command type {type!r}")
+        cmd: Dict[str, Any] = {"type": type, "session_token": self.session_token, **fields}
+        if type in COMMANDS_WITH_ID:
+            cmd.setdefault("command_id", uuid.uuid4().hex)
+        self._send(cmd)
+        return cmd.get("command_id")
+
+    def request(self, type: str, **fields: Any) -> ServerMessage:
+        """`send`, then the reply carrying its `command_id`, an `error` included. Messages that
+        arrive meanwhile are kept for `recv`."""
+        command_id = self.send(type, **fields)
+        if command_id is None:
+            raise ValueError(f"{type} is not answered by command id")
+        return self._wait(lambda m: m.get("command_id") == command_id)
+
+    def recv(self) -> ServerMessage:
+        """The next message not yet handed out."""
+        if self._pending:
+            return self._pending.popleft()
+        return self._read()
+
+    def close(self) -> None:
+        self._ws.close()
+
+    def __enter__(self) -> GojiraClient:
+        return self
+
+    def __exit__(self, *exc: Any) -> None:
+        self.close()
+
+    def _send(self, cmd: Dict[str, Any]) -> None:
+        self._ws.send(json.dumps(cmd))
+
+    def _read(self) -> ServerMessage:
+        while True:
+            frame = self._ws.recv()
+            if not isinstance(frame, str):
+                continue
+            msg = json.loads(frame)
+            if msg.get("type") == "token_rotated":
+                self.session_token = msg["session_token"]
+            return msg
+
+    def _wait(self, wanted: Callable[[ServerMessage], bool]) -> ServerMessage:
+        for msg in self._pending:
+            if wanted(msg):
+                self._pending.remove(msg)
+                return msg
+        while True:
+            msg = self._read()
+            if wanted(msg):
+                return msg
+            self._pending.append(msg)
@@ -43,25 +43,12 @@ function Send-TextMessage {
 
 function Print-Report {
   param($Report)
-  if ($null -eq $Report) {
+  if ($null -eq $Report -or @($Report).Count -eq 0) {
     Write-Host "(none)"
     return
   }
-  foreach ($k in @(
-    "delay_active_101",
-    "delay_mix_105",
-    "delay_active_best_guess",
-    "delay_mix",
-    "reverb_active_112",
-    "reverb_mix_114",
-    "reverb_active_best_guess",
-    "reverb_mix",
-    "delay_window_98_110",
-    "reverb_window_110_122"
-  )) {
-    if ($Report.PSObject.Properties.Name -contains $k) {
-      Write-Host ("- {0}: {1}" -f $k, $Report.$k)
-    }
+  foreach ($c in @($Report)) {
+    Write-Host ("- {0} {1} {2}: {3} (charted '{4}', found '{5}')" -f $c.module, $c.role, $c.expected_index, $c.status, $c.expected_name, $c.found_name)
   }
 }
 